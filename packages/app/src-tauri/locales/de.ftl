@@ -0,0 +1,69 @@
+## Tray menu
+tray-toggle-window = Fenster ein-/ausblenden
+tray-status-running = Server: läuft
+tray-status-external = Server: läuft (extern)
+tray-status-stopped = Server: gestoppt
+tray-open-logs = Protokolle öffnen
+
+## App menu
+menu-file = Datei
+menu-edit = Bearbeiten
+menu-agent = Agent
+menu-help = Hilfe
+menu-settings = Einstellungen…
+menu-new-conversation = Neue Unterhaltung
+menu-open-logs-folder = Protokollordner öffnen
+menu-quick-chat = Schnellchat
+menu-edit-character = Charakter bearbeiten…
+menu-server-logs = Serverprotokolle anzeigen
+menu-check-for-updates = Nach Updates suchen…
+
+## Shared labels
+start-server = Server starten
+stop-server = Server stoppen
+restart-server = Server neu starten
+quit = Beenden
+
+## Server lifecycle
+lifecycle-stopped = ist gestoppt
+lifecycle-starting = wird gestartet
+lifecycle-running = läuft
+lifecycle-stopping = wird gestoppt
+server-lifecycle-busy = Der Eliza-Server { $state }
+server-cli-missing = Die elizaos-CLI wurde nicht gefunden. Installiere sie, um den Server zu starten.
+server-external-running = Auf { $address } läuft bereits ein anderer elizaOS-Server
+server-remote-connected = Die App ist mit dem Remote-Server { $url } verbunden. Trenne die Verbindung, um einen lokalen Server zu starten.
+server-spawn-failed = Der Eliza-Server konnte nicht gestartet werden: { $error }
+
+## Capability grants
+capability-read-secrets = deine gespeicherten API-Schlüssel und Anmeldetokens lesen
+capability-run-programs = Pakete installieren oder ändern, welche Programme ausgeführt werden
+capability-export-data = von dir gewählte Dateien lesen oder deine Unterhaltungen, Sicherungen oder Diagnosedaten speichern
+capability-remote-access = anderen Geräten in deinem Netzwerk erlauben, mit deinem Agenten zu chatten
+capability-remote-servers = sich mit einem elizaOS-Server auf einem anderen Rechner verbinden
+capability-prompt-title = Zugriff erlauben?
+capability-prompt = Eliza Desktop möchte { $capability }. Bis zum Beenden der App erlauben?
+capability-prompt-reason = Angegebener Grund: { $reason }
+capability-allow = Erlauben
+capability-deny = Ablehnen
+capability-required = Eine Berechtigung ist nötig: { $capability }
+capability-denied = Die Berechtigung wurde verweigert
+
+## Content filters
+filter-prompt-title = Inhaltsfilter
+filter-prompt-outgoing = Eine Anfrage an { $provider } entspricht Ihren Inhaltsfiltern ({ $rules }). Trotzdem senden?
+filter-prompt-incoming = Eine Antwort von { $provider } entspricht Ihren Inhaltsfiltern ({ $rules }). Trotzdem anzeigen?
+filter-send-anyway = Trotzdem senden
+filter-show-anyway = Trotzdem anzeigen
+filter-block = Blockieren
+filter-blocked = Von Ihren Inhaltsfiltern blockiert: { $rules }
+
+## LAN access
+lan-off = Der LAN-Zugriff ist aus
+lan-pairing-expired = Die Kopplung ist abgelaufen
+lan-invalid-token = Ungültiges Kopplungstoken
+lan-pair-first = Scanne den Kopplungscode in Eliza Desktop
+lan-no-network = Dieses Gerät ist mit keinem lokalen Netzwerk verbunden
+lan-bind-failed = Port { $port } konnte nicht geöffnet werden: { $error }
+lan-address-failed = Die LAN-Adresse konnte nicht gelesen werden: { $error }
+lan-ttl-invalid = Die Kopplung muss zwischen 1 und { $max } Minuten dauern
@@ -0,0 +1,69 @@
+## Tray menu
+tray-toggle-window = Show/Hide Window
+tray-status-running = Server: running
+tray-status-external = Server: running (external)
+tray-status-stopped = Server: stopped
+tray-open-logs = Open Logs
+
+## App menu
+menu-file = File
+menu-edit = Edit
+menu-agent = Agent
+menu-help = Help
+menu-settings = Settings…
+menu-new-conversation = New Conversation
+menu-open-logs-folder = Open Logs Folder
+menu-quick-chat = Quick Chat
+menu-edit-character = Edit Character…
+menu-server-logs = Show Server Logs
+menu-check-for-updates = Check for Updates…
+
+## Shared labels
+start-server = Start Server
+stop-server = Stop Server
+restart-server = Restart Server
+quit = Quit
+
+## Server lifecycle
+lifecycle-stopped = stopped
+lifecycle-starting = starting
+lifecycle-running = running
+lifecycle-stopping = stopping
+server-lifecycle-busy = The Eliza server is { $state }
+server-cli-missing = The elizaos CLI was not found. Install it to start the server.
+server-external-running = Another elizaOS server is already running on { $address }
+server-remote-connected = The app is connected to the remote server { $url }; disconnect to run one locally
+server-spawn-failed = Failed to start the Eliza server: { $error }
+
+## Capability grants
+capability-read-secrets = read your stored API keys and sign-in tokens
+capability-run-programs = install packages or change which programs it runs
+capability-export-data = read files you choose, or save your conversations, backups or diagnostics to disk
+capability-remote-access = let other devices on your network chat with your agent
+capability-remote-servers = connect to an elizaOS server on another machine
+capability-prompt-title = Allow access?
+capability-prompt = Eliza Desktop wants to { $capability }. Allow this until the app quits?
+capability-prompt-reason = Reason given: { $reason }
+capability-allow = Allow
+capability-deny = Deny
+capability-required = Permission is needed to { $capability }
+capability-denied = Permission was denied
+
+## Content filters
+filter-prompt-title = Content filter
+filter-prompt-outgoing = A request to { $provider } matches your content filters ({ $rules }). Send it anyway?
+filter-prompt-incoming = A reply from { $provider } matches your content filters ({ $rules }). Show it anyway?
+filter-send-anyway = Send anyway
+filter-show-anyway = Show anyway
+filter-block = Block
+filter-blocked = Blocked by your content filters: { $rules }
+
+## LAN access
+lan-off = LAN access is off
+lan-pairing-expired = The pairing has expired
+lan-invalid-token = Invalid pairing token
+lan-pair-first = Scan the pairing code in Eliza Desktop
+lan-no-network = This machine isn't connected to a local network
+lan-bind-failed = Failed to listen on port { $port }: { $error }
+lan-address-failed = Failed to read the LAN address: { $error }
+lan-ttl-invalid = The pairing must last between 1 and { $max } minutes
@@ -0,0 +1,69 @@
+## Tray menu
+tray-toggle-window = Mostrar/ocultar ventana
+tray-status-running = Servidor: en ejecución
+tray-status-external = Servidor: en ejecución (externo)
+tray-status-stopped = Servidor: detenido
+tray-open-logs = Abrir registros
+
+## App menu
+menu-file = Archivo
+menu-edit = Editar
+menu-agent = Agente
+menu-help = Ayuda
+menu-settings = Ajustes…
+menu-new-conversation = Nueva conversación
+menu-open-logs-folder = Abrir carpeta de registros
+menu-quick-chat = Chat rápido
+menu-edit-character = Editar personaje…
+menu-server-logs = Mostrar registros del servidor
+menu-check-for-updates = Buscar actualizaciones…
+
+## Shared labels
+start-server = Iniciar servidor
+stop-server = Detener servidor
+restart-server = Reiniciar servidor
+quit = Salir
+
+## Server lifecycle
+lifecycle-stopped = detenido
+lifecycle-starting = iniciándose
+lifecycle-running = en ejecución
+lifecycle-stopping = deteniéndose
+server-lifecycle-busy = El servidor de Eliza está { $state }
+server-cli-missing = No se encontró la CLI de elizaos. Instálala para iniciar el servidor.
+server-external-running = Ya hay otro servidor de elizaOS en ejecución en { $address }
+server-remote-connected = La aplicación está conectada al servidor remoto { $url }; desconéctate para ejecutar uno local
+server-spawn-failed = No se pudo iniciar el servidor de Eliza: { $error }
+
+## Capability grants
+capability-read-secrets = leer tus claves de API y tokens de inicio de sesión guardados
+capability-run-programs = instalar paquetes o cambiar los programas que ejecuta
+capability-export-data = leer los archivos que elijas, o guardar tus conversaciones, copias de seguridad o diagnósticos en el disco
+capability-remote-access = permitir que otros dispositivos de tu red chateen con tu agente
+capability-remote-servers = conectarse a un servidor elizaOS en otro equipo
+capability-prompt-title = ¿Permitir el acceso?
+capability-prompt = Eliza Desktop quiere { $capability }. ¿Permitirlo hasta que se cierre la app?
+capability-prompt-reason = Motivo indicado: { $reason }
+capability-allow = Permitir
+capability-deny = Denegar
+capability-required = Se necesita permiso para { $capability }
+capability-denied = Se denegó el permiso
+
+## Content filters
+filter-prompt-title = Filtro de contenido
+filter-prompt-outgoing = Una solicitud a { $provider } coincide con tus filtros de contenido ({ $rules }). ¿Enviarla de todos modos?
+filter-prompt-incoming = Una respuesta de { $provider } coincide con tus filtros de contenido ({ $rules }). ¿Mostrarla de todos modos?
+filter-send-anyway = Enviar de todos modos
+filter-show-anyway = Mostrar de todos modos
+filter-block = Bloquear
+filter-blocked = Bloqueado por tus filtros de contenido: { $rules }
+
+## LAN access
+lan-off = El acceso por red local está desactivado
+lan-pairing-expired = El emparejamiento ha caducado
+lan-invalid-token = Token de emparejamiento no válido
+lan-pair-first = Escanea el código de emparejamiento en Eliza Desktop
+lan-no-network = Este equipo no está conectado a ninguna red local
+lan-bind-failed = No se pudo escuchar en el puerto { $port }: { $error }
+lan-address-failed = No se pudo leer la dirección de la red local: { $error }
+lan-ttl-invalid = El emparejamiento debe durar entre 1 y { $max } minutos
@@ -0,0 +1,69 @@
+## Tray menu
+tray-toggle-window = Afficher/masquer la fenêtre
+tray-status-running = Serveur : en cours d’exécution
+tray-status-external = Serveur : en cours d’exécution (externe)
+tray-status-stopped = Serveur : arrêté
+tray-open-logs = Ouvrir les journaux
+
+## App menu
+menu-file = Fichier
+menu-edit = Édition
+menu-agent = Agent
+menu-help = Aide
+menu-settings = Réglages…
+menu-new-conversation = Nouvelle conversation
+menu-open-logs-folder = Ouvrir le dossier des journaux
+menu-quick-chat = Chat rapide
+menu-edit-character = Modifier le personnage…
+menu-server-logs = Afficher les journaux du serveur
+menu-check-for-updates = Rechercher des mises à jour…
+
+## Shared labels
+start-server = Démarrer le serveur
+stop-server = Arrêter le serveur
+restart-server = Redémarrer le serveur
+quit = Quitter
+
+## Server lifecycle
+lifecycle-stopped = arrêté
+lifecycle-starting = en cours de démarrage
+lifecycle-running = en cours d’exécution
+lifecycle-stopping = en cours d’arrêt
+server-lifecycle-busy = Le serveur Eliza est { $state }
+server-cli-missing = La CLI elizaos est introuvable. Installez-la pour démarrer le serveur.
+server-external-running = Un autre serveur elizaOS est déjà en cours d’exécution sur { $address }
+server-remote-connected = L’application est connectée au serveur distant { $url } ; déconnectez-vous pour en lancer un en local
+server-spawn-failed = Impossible de démarrer le serveur Eliza : { $error }
+
+## Capability grants
+capability-read-secrets = lire vos clés d’API et jetons de connexion enregistrés
+capability-run-programs = installer des paquets ou changer les programmes qu’elle exécute
+capability-export-data = lire les fichiers que vous choisissez, ou enregistrer vos conversations, sauvegardes ou diagnostics sur le disque
+capability-remote-access = permettre à d'autres appareils de votre réseau de discuter avec votre agent
+capability-remote-servers = se connecter à un serveur elizaOS sur une autre machine
+capability-prompt-title = Autoriser l’accès ?
+capability-prompt = Eliza Desktop veut { $capability }. L’autoriser jusqu’à la fermeture de l’app ?
+capability-prompt-reason = Raison donnée : { $reason }
+capability-allow = Autoriser
+capability-deny = Refuser
+capability-required = Une autorisation est nécessaire pour { $capability }
+capability-denied = L’autorisation a été refusée
+
+## Content filters
+filter-prompt-title = Filtre de contenu
+filter-prompt-outgoing = Une requête vers { $provider } correspond à vos filtres de contenu ({ $rules }). L’envoyer quand même ?
+filter-prompt-incoming = Une réponse de { $provider } correspond à vos filtres de contenu ({ $rules }). L’afficher quand même ?
+filter-send-anyway = Envoyer quand même
+filter-show-anyway = Afficher quand même
+filter-block = Bloquer
+filter-blocked = Bloqué par vos filtres de contenu : { $rules }
+
+## LAN access
+lan-off = L’accès réseau local est désactivé
+lan-pairing-expired = L’appairage a expiré
+lan-invalid-token = Jeton d’appairage invalide
+lan-pair-first = Scannez le code d’appairage dans Eliza Desktop
+lan-no-network = Cette machine n’est connectée à aucun réseau local
+lan-bind-failed = Impossible d’écouter sur le port { $port } : { $error }
+lan-address-failed = Impossible de lire l’adresse sur le réseau local : { $error }
+lan-ttl-invalid = L’appairage doit durer entre 1 et { $max } minutes
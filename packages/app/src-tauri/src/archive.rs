@@ -0,0 +1,416 @@
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager};
+use zip::write::SimpleFileOptions;
+use zip::{ZipArchive, ZipWriter};
+
+use crate::error::AppError;
+use crate::history::{self, db_error, now_millis, Conversation, ConversationSummary};
+use crate::settings;
+
+const ARCHIVES_DIR: &str = "archives";
+const ARCHIVE_EXTENSION: &str = "zip";
+const MANIFEST_FILE: &str = "manifest.json";
+const FORMAT_VERSION: u32 = 1;
+const CHECK_INTERVAL: Duration = Duration::from_secs(6 * 60 * 60);
+const DAY_MILLIS: u64 = 24 * 60 * 60 * 1000;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetentionSettings {
+    // Archive old conversations automatically
+    pub enabled: bool,
+    // Conversations untouched for this long are archived
+    pub archive_after_days: u32,
+    // Remove archived conversations from the live history; they can be restored from the file
+    pub delete_archived: bool,
+}
+
+impl Default for RetentionSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            archive_after_days: 180,
+            delete_archived: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Manifest {
+    format_version: u32,
+    // Milliseconds since the Unix epoch
+    created_at: u64,
+    conversations: Vec<ConversationSummary>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ArchiveInfo {
+    pub name: String,
+    pub path: PathBuf,
+    pub created_at: u64,
+    pub size: u64,
+    pub conversations: Vec<ConversationSummary>,
+}
+
+// Payload of the `archive-completed` event
+#[derive(Debug, Clone, Serialize)]
+pub struct ArchiveRun {
+    // `None` when nothing was old enough to archive
+    pub archive: Option<ArchiveInfo>,
+    // Conversations removed from the live history, including ones archived by earlier runs
+    pub deleted: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RestoreSummary {
+    pub restored: usize,
+    // Live copies changed since they were archived, so kept as they are
+    pub skipped: usize,
+}
+
+fn archives_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_data_dir()
+        .map(|dir| dir.join(ARCHIVES_DIR))
+        .map_err(|e| format!("Failed to resolve app data dir: {}", e))
+}
+
+fn io_error(path: &Path, e: impl std::fmt::Display) -> String {
+    format!("{}: {}", path.display(), e)
+}
+
+fn entry_name(conversation_id: &str) -> String {
+    format!("conversations/{}.json", conversation_id)
+}
+
+fn read_manifest(zip: &mut ZipArchive<File>) -> Result<Manifest, String> {
+    let entry = zip
+        .by_name(MANIFEST_FILE)
+        .map_err(|_| "The archive has no manifest".to_string())?;
+    let manifest: Manifest =
+        serde_json::from_reader(entry).map_err(|e| format!("Invalid archive manifest: {}", e))?;
+    if manifest.format_version > FORMAT_VERSION {
+        return Err(format!(
+            "The archive was made by a newer version of the app (format {})",
+            manifest.format_version
+        ));
+    }
+    Ok(manifest)
+}
+
+fn open(path: &Path) -> Result<ZipArchive<File>, String> {
+    let file = File::open(path).map_err(|e| io_error(path, e))?;
+    ZipArchive::new(file).map_err(|e| format!("Not a valid archive: {}", e))
+}
+
+// Archives on disk, newest first
+fn archives(app: &AppHandle) -> Result<Vec<ArchiveInfo>, String> {
+    let dir = archives_dir(app)?;
+    let Ok(entries) = fs::read_dir(&dir) else {
+        return Ok(Vec::new());
+    };
+    let mut archives: Vec<ArchiveInfo> = entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().and_then(|ext| ext.to_str()) == Some(ARCHIVE_EXTENSION))
+        .filter_map(|path| {
+            let manifest = open(&path).and_then(|mut zip| read_manifest(&mut zip));
+            let manifest = match manifest {
+                Ok(manifest) => manifest,
+                Err(e) => {
+                    tracing::warn!("Skipping archive {}: {}", path.display(), e);
+                    return None;
+                }
+            };
+            Some(ArchiveInfo {
+                name: path.file_name()?.to_string_lossy().into_owned(),
+                size: fs::metadata(&path)
+                    .map(|metadata| metadata.len())
+                    .unwrap_or(0),
+                path,
+                created_at: manifest.created_at,
+                conversations: manifest.conversations,
+            })
+        })
+        .collect();
+    archives.sort_by_key(|archive| std::cmp::Reverse(archive.created_at));
+    Ok(archives)
+}
+
+fn write_archive(
+    path: &Path,
+    conversations: &[Conversation],
+    created_at: u64,
+) -> Result<(), String> {
+    let tmp = path.with_extension("part");
+    let file = File::create(&tmp).map_err(|e| io_error(&tmp, e))?;
+    let mut zip = ZipWriter::new(BufWriter::new(file));
+    for conversation in conversations {
+        zip.start_file(
+            entry_name(&conversation.summary.id),
+            SimpleFileOptions::default(),
+        )
+        .map_err(|e| io_error(&tmp, e))?;
+        serde_json::to_writer(&mut zip, conversation).map_err(|e| io_error(&tmp, e))?;
+    }
+    let manifest = Manifest {
+        format_version: FORMAT_VERSION,
+        created_at,
+        conversations: conversations
+            .iter()
+            .map(|conversation| conversation.summary.clone())
+            .collect(),
+    };
+    zip.start_file(MANIFEST_FILE, SimpleFileOptions::default())
+        .map_err(|e| io_error(&tmp, e))?;
+    serde_json::to_writer_pretty(&mut zip, &manifest).map_err(|e| io_error(&tmp, e))?;
+    zip.finish()
+        .map_err(|e| io_error(&tmp, e))?
+        .flush()
+        .map_err(|e| io_error(&tmp, e))?;
+    fs::rename(&tmp, path).map_err(|e| io_error(path, e))
+}
+
+// Conversations last updated before `cutoff` that aren't in an archive in their current state
+fn unarchived(conn: &Connection, cutoff: u64) -> Result<Vec<String>, String> {
+    let mut statement = conn
+        .prepare(
+            "SELECT c.id FROM conversations c
+                LEFT JOIN archived_conversations a ON a.conversation_id = c.id
+                WHERE c.updated_at < ?1 AND (a.updated_at IS NULL OR a.updated_at <> c.updated_at)
+                ORDER BY c.updated_at",
+        )
+        .map_err(db_error)?;
+    let ids = statement
+        .query_map([cutoff as i64], |row| row.get(0))
+        .map_err(db_error)?
+        .collect::<rusqlite::Result<Vec<String>>>()
+        .map_err(db_error)?;
+    Ok(ids)
+}
+
+// Live conversations already safe in an archive, except ones the user restored on purpose
+fn archived_live(conn: &Connection, cutoff: u64) -> Result<Vec<String>, String> {
+    let mut statement = conn
+        .prepare(
+            "SELECT c.id FROM conversations c
+                JOIN archived_conversations a ON a.conversation_id = c.id
+                WHERE c.updated_at < ?1 AND a.updated_at = c.updated_at AND a.restored = 0",
+        )
+        .map_err(db_error)?;
+    let ids = statement
+        .query_map([cutoff as i64], |row| row.get(0))
+        .map_err(db_error)?
+        .collect::<rusqlite::Result<Vec<String>>>()
+        .map_err(db_error)?;
+    Ok(ids)
+}
+
+// Write conversations untouched for `days` to a new archive, then optionally drop them from
+// the live history
+fn run(app: &AppHandle, days: u32, delete: bool) -> Result<ArchiveRun, String> {
+    let started = now_millis();
+    let cutoff = started.saturating_sub(u64::from(days) * DAY_MILLIS);
+    let dir = archives_dir(app)?;
+
+    let archive = history::with_db_blocking(|conn| {
+        let ids = unarchived(conn, cutoff)?;
+        if ids.is_empty() {
+            return Ok(None);
+        }
+        let conversations = ids
+            .iter()
+            .filter_map(|id| history::load_conversation(conn, id).transpose())
+            .collect::<Result<Vec<_>, String>>()?;
+        fs::create_dir_all(&dir).map_err(|e| io_error(&dir, e))?;
+        let name = format!("archive-{}.{}", started, ARCHIVE_EXTENSION);
+        let path = dir.join(&name);
+        write_archive(&path, &conversations, started)?;
+
+        // Recorded only once the file is complete, so a failed write archives them again
+        let tx = conn.transaction().map_err(db_error)?;
+        for conversation in &conversations {
+            tx.execute(
+                "INSERT INTO archived_conversations
+                    (conversation_id, archive_name, updated_at, archived_at, restored)
+                    VALUES (?1, ?2, ?3, ?4, 0)
+                ON CONFLICT(conversation_id) DO UPDATE SET
+                    archive_name = excluded.archive_name,
+                    updated_at = excluded.updated_at,
+                    archived_at = excluded.archived_at,
+                    restored = 0",
+                params![
+                    conversation.summary.id,
+                    name,
+                    conversation.summary.updated_at as i64,
+                    started as i64
+                ],
+            )
+            .map_err(db_error)?;
+        }
+        tx.commit().map_err(db_error)?;
+        Ok(Some(ArchiveInfo {
+            name,
+            size: fs::metadata(&path)
+                .map(|metadata| metadata.len())
+                .unwrap_or(0),
+            path,
+            created_at: started,
+            conversations: conversations
+                .into_iter()
+                .map(|conversation| conversation.summary)
+                .collect(),
+        }))
+    })?;
+
+    let deleted = if delete {
+        history::with_db_blocking(|conn| {
+            let ids = archived_live(conn, cutoff)?;
+            let tx = conn.transaction().map_err(db_error)?;
+            for id in &ids {
+                history::remove_conversation(&tx, id)?;
+            }
+            tx.commit().map_err(db_error)?;
+            if !ids.is_empty() {
+                conn.execute_batch("INSERT INTO messages_fts(messages_fts) VALUES ('optimize');")
+                    .map_err(db_error)?;
+            }
+            Ok(ids.len())
+        })?
+    } else {
+        0
+    };
+
+    let count = archive
+        .as_ref()
+        .map_or(0, |archive| archive.conversations.len());
+    if count > 0 || deleted > 0 {
+        tracing::info!(archived = count, deleted, "Archived old conversations");
+    }
+    Ok(ArchiveRun { archive, deleted })
+}
+
+// Archive on the configured schedule for as long as the app runs; called once from the setup hook
+pub fn spawn(app: AppHandle) {
+    thread::spawn(move || loop {
+        let retention = settings::current().retention;
+        if retention.enabled {
+            match run(
+                &app,
+                retention.archive_after_days,
+                retention.delete_archived,
+            ) {
+                Ok(result) => {
+                    if result.archive.is_some() || result.deleted > 0 {
+                        let _ = app.emit("archive-completed", &result);
+                    }
+                }
+                Err(e) => tracing::error!("Scheduled archiving failed: {}", e),
+            }
+        }
+        thread::sleep(CHECK_INTERVAL);
+    });
+}
+
+#[tauri::command]
+pub fn configure_retention(
+    app: AppHandle,
+    retention: RetentionSettings,
+) -> Result<RetentionSettings, AppError> {
+    if retention.archive_after_days == 0 {
+        return Err(AppError::Validation(
+            "Conversations must be at least a day old to be archived".to_string(),
+        ));
+    }
+    Ok(settings::update(&app, |settings| settings.retention = retention)?.retention)
+}
+
+// Archive now instead of waiting for the schedule; arguments default to the retention settings
+#[tauri::command]
+pub async fn archive_now(
+    app: AppHandle,
+    older_than_days: Option<u32>,
+    delete: Option<bool>,
+) -> Result<ArchiveRun, AppError> {
+    let retention = settings::current().retention;
+    let days = older_than_days.unwrap_or(retention.archive_after_days);
+    let delete = delete.unwrap_or(retention.delete_archived);
+    let handle = app.clone();
+    let result = tauri::async_runtime::spawn_blocking(move || run(&handle, days, delete))
+        .await?
+        .map_err(AppError::Database)?;
+    let _ = app.emit("archive-completed", &result);
+    Ok(result)
+}
+
+#[tauri::command]
+pub async fn list_archives(app: AppHandle) -> Result<Vec<ArchiveInfo>, AppError> {
+    tauri::async_runtime::spawn_blocking(move || archives(&app))
+        .await?
+        .map_err(AppError::Io)
+}
+
+// Put conversations from an archive back into the live history, all of them unless
+// `conversation_ids` picks some. Restored conversations aren't deleted again by later runs.
+#[tauri::command]
+pub async fn restore_archive(
+    app: AppHandle,
+    name: String,
+    conversation_ids: Option<Vec<String>>,
+) -> Result<RestoreSummary, AppError> {
+    let archive = list_archives(app)
+        .await?
+        .into_iter()
+        .find(|archive| archive.name == name)
+        .ok_or_else(|| AppError::NotFound(format!("Unknown archive: {}", name)))?;
+    history::with_db(move |conn| {
+        let mut zip = open(&archive.path)?;
+        let mut summary = RestoreSummary {
+            restored: 0,
+            skipped: 0,
+        };
+        for archived in &archive.conversations {
+            if conversation_ids
+                .as_ref()
+                .is_some_and(|ids| !ids.contains(&archived.id))
+            {
+                continue;
+            }
+            let live: Option<i64> = conn
+                .query_row(
+                    "SELECT updated_at FROM conversations WHERE id = ?1",
+                    [&archived.id],
+                    |row| row.get(0),
+                )
+                .optional()
+                .map_err(db_error)?;
+            if live.is_some_and(|updated_at| updated_at as u64 > archived.updated_at) {
+                summary.skipped += 1;
+                continue;
+            }
+            let entry = zip
+                .by_name(&entry_name(&archived.id))
+                .map_err(|_| format!("The archive is missing conversation {}", archived.id))?;
+            let conversation: Conversation = serde_json::from_reader(entry)
+                .map_err(|e| format!("Invalid conversation {}: {}", archived.id, e))?;
+            history::replace_conversation(conn, &conversation)?;
+            conn.execute(
+                "UPDATE archived_conversations SET restored = 1 WHERE conversation_id = ?1",
+                [&archived.id],
+            )
+            .map_err(db_error)?;
+            summary.restored += 1;
+        }
+        tracing::info!(archive = %name, restored = summary.restored, "Restored an archive");
+        Ok(summary)
+    })
+    .await
+    .map_err(AppError::Database)
+}
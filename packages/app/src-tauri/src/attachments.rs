@@ -0,0 +1,728 @@
+use std::fs::{self, File};
+use std::io::{self, Cursor, Read, Write};
+use std::path::{Path, PathBuf};
+
+use image::{DynamicImage, GrayImage, ImageFormat, ImageReader, Limits, RgbImage};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use tauri::http::{header, Request, Response, StatusCode};
+use tauri::{AppHandle, Manager, UriSchemeContext, UriSchemeResponder, Wry};
+use tauri_plugin_dialog::DialogExt;
+
+use crate::error::AppError;
+use crate::history::{self, db_error, now_millis};
+
+// Registered in `run`; the webview loads `attachment://localhost/<hash>` (or
+// `http://attachment.localhost/<hash>` on Windows), with `/preview` for the thumbnail
+pub const SCHEME: &str = "attachment";
+const ATTACHMENTS_DIR: &str = "attachments";
+const OBJECTS_DIR: &str = "objects";
+const PREVIEWS_DIR: &str = "previews";
+// Larger files are refused outright
+const MAX_STORED_BYTES: u64 = 200 * 1024 * 1024;
+// Larger originals aren't served whole through the protocol; their previews still are
+const MAX_SERVED_BYTES: u64 = 32 * 1024 * 1024;
+// Images beyond this are treated as decompression bombs and get no preview
+const MAX_DECODED_SIDE: u32 = 16_384;
+const MAX_DECODE_ALLOC: u64 = 256 * 1024 * 1024;
+const PREVIEW_SIZE: u32 = 320;
+// How much of a PDF's first page the text preview shows
+const PREVIEW_LINES: usize = 28;
+const PREVIEW_LINE_CHARS: usize = 56;
+
+// Width and height in pixels
+type Dimensions = (u32, u32);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Preview {
+    Png,
+    Svg,
+}
+
+impl Preview {
+    fn extension(self) -> &'static str {
+        match self {
+            Preview::Png => "png",
+            Preview::Svg => "svg",
+        }
+    }
+
+    fn mime_type(self) -> &'static str {
+        match self {
+            Preview::Png => "image/png",
+            Preview::Svg => "image/svg+xml",
+        }
+    }
+}
+
+// A stored file, identified by the SHA-256 of its contents so the same file is only kept once
+#[derive(Debug, Clone, Serialize)]
+pub struct Attachment {
+    pub hash: String,
+    pub file_name: String,
+    pub mime_type: String,
+    pub size: u64,
+    // Pixel size, for images
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub url: String,
+    // `None` when no preview could be made, e.g. for a GIF or an encrypted PDF
+    pub preview_url: Option<String>,
+    // Milliseconds since the Unix epoch
+    pub created_at: u64,
+}
+
+fn attachments_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_data_dir()
+        .map(|dir| dir.join(ATTACHMENTS_DIR))
+        .map_err(|e| format!("Failed to resolve app data dir: {}", e))
+}
+
+// Objects are fanned out by the first two hex digits so no directory grows too large
+fn object_path(dir: &Path, hash: &str) -> PathBuf {
+    dir.join(OBJECTS_DIR).join(&hash[..2]).join(hash)
+}
+
+fn preview_path(dir: &Path, hash: &str, preview: Preview) -> PathBuf {
+    dir.join(PREVIEWS_DIR)
+        .join(format!("{}.{}", hash, preview.extension()))
+}
+
+fn is_hash(value: &str) -> bool {
+    value.len() == 64
+        && value
+            .bytes()
+            .all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+}
+
+fn url(hash: &str, preview: bool) -> String {
+    let suffix = if preview { "/preview" } else { "" };
+    if cfg!(windows) {
+        format!("http://{}.localhost/{}{}", SCHEME, hash, suffix)
+    } else {
+        format!("{}://localhost/{}{}", SCHEME, hash, suffix)
+    }
+}
+
+// Sniffed from the first bytes, falling back to the file name
+fn mime_type(head: &[u8], file_name: &str) -> &'static str {
+    const MAGIC: &[(&[u8], &str)] = &[
+        (b"\x89PNG\r\n\x1a\n", "image/png"),
+        (b"\xff\xd8\xff", "image/jpeg"),
+        (b"GIF87a", "image/gif"),
+        (b"GIF89a", "image/gif"),
+        (b"%PDF-", "application/pdf"),
+        (b"PK\x03\x04", "application/zip"),
+    ];
+    if let Some((_, mime)) = MAGIC.iter().find(|(magic, _)| head.starts_with(magic)) {
+        return mime;
+    }
+    if head.len() >= 12 && &head[..4] == b"RIFF" && &head[8..12] == b"WEBP" {
+        return "image/webp";
+    }
+    let extension = Path::new(file_name)
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(str::to_ascii_lowercase)
+        .unwrap_or_default();
+    match extension.as_str() {
+        "txt" | "log" => "text/plain",
+        "md" => "text/markdown",
+        "csv" => "text/csv",
+        "json" => "application/json",
+        "svg" => "image/svg+xml",
+        "mp3" => "audio/mpeg",
+        "wav" => "audio/wav",
+        "mp4" => "video/mp4",
+        "webm" => "video/webm",
+        _ => "application/octet-stream",
+    }
+}
+
+fn decode_image(bytes: &[u8]) -> Result<DynamicImage, String> {
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(MAX_DECODED_SIDE);
+    limits.max_image_height = Some(MAX_DECODED_SIDE);
+    limits.max_alloc = Some(MAX_DECODE_ALLOC);
+    let mut reader = ImageReader::new(Cursor::new(bytes))
+        .with_guessed_format()
+        .map_err(|e| e.to_string())?;
+    reader.limits(limits);
+    reader.decode().map_err(|e| e.to_string())
+}
+
+fn xml_escape(text: &str) -> String {
+    text.chars().filter(|c| !c.is_control() || *c == '\n').fold(
+        String::with_capacity(text.len()),
+        |mut out, c| {
+            match c {
+                '&' => out.push_str("&amp;"),
+                '<' => out.push_str("&lt;"),
+                '>' => out.push_str("&gt;"),
+                _ => out.push(c),
+            }
+            out
+        },
+    )
+}
+
+// Hard-wrapped lines of `text`, enough to fill the preview
+fn preview_lines(text: &str) -> Vec<String> {
+    let mut lines = Vec::new();
+    for paragraph in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+        let mut line = String::new();
+        for word in paragraph.split_whitespace() {
+            if !line.is_empty() && line.chars().count() + word.chars().count() >= PREVIEW_LINE_CHARS
+            {
+                lines.push(std::mem::take(&mut line));
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.extend(word.chars().take(PREVIEW_LINE_CHARS));
+        }
+        lines.push(line);
+        if lines.len() >= PREVIEW_LINES {
+            break;
+        }
+    }
+    lines.truncate(PREVIEW_LINES);
+    lines
+}
+
+// The first page's text laid out on a page of the same shape
+fn text_preview(text: &str, width: f32, height: f32) -> Vec<u8> {
+    let (width, height) = if width > 0.0 && height > 0.0 {
+        (
+            PREVIEW_SIZE as f32 * (width / height).min(1.0),
+            PREVIEW_SIZE as f32 * (height / width).min(1.0),
+        )
+    } else {
+        (PREVIEW_SIZE as f32 * 0.77, PREVIEW_SIZE as f32)
+    };
+    let margin = width * 0.08;
+    let font_size = (width - 2.0 * margin) / PREVIEW_LINE_CHARS as f32 * 1.8;
+    let mut svg = format!(
+        r##"<svg xmlns="http://www.w3.org/2000/svg" width="{w:.0}" height="{h:.0}" viewBox="0 0 {w:.1} {h:.1}"><rect width="100%" height="100%" fill="#fff"/><g font-family="sans-serif" font-size="{font_size:.2}" fill="#333">"##,
+        w = width,
+        h = height,
+    );
+    for (i, line) in preview_lines(text).iter().enumerate() {
+        let y = margin + font_size * 1.4 * (i + 1) as f32;
+        if y > height - margin {
+            break;
+        }
+        svg.push_str(&format!(
+            r#"<text x="{:.1}" y="{:.1}">{}</text>"#,
+            margin,
+            y,
+            xml_escape(line)
+        ));
+    }
+    svg.push_str("</g></svg>");
+    svg.into_bytes()
+}
+
+// The largest picture on a page, which for a scanned document is the page itself
+fn page_image(doc: &lopdf::Document, page: lopdf::ObjectId) -> Option<DynamicImage> {
+    let images = doc.get_page_images(page).ok()?;
+    let image = images
+        .iter()
+        .max_by_key(|image| image.width * image.height)?;
+    let filters = image.filters.clone().unwrap_or_default();
+    let filters: Vec<&str> = filters.iter().map(String::as_str).collect();
+    match filters.as_slice() {
+        ["DCTDecode"] => decode_image(image.content).ok(),
+        [] | ["FlateDecode"] if image.bits_per_component == Some(8) => {
+            let data = doc
+                .get_object(image.id)
+                .ok()?
+                .as_stream()
+                .ok()?
+                .decompressed_content()
+                .unwrap_or_else(|_| image.content.to_vec());
+            let (width, height) = (
+                u32::try_from(image.width).ok()?,
+                u32::try_from(image.height).ok()?,
+            );
+            if width > MAX_DECODED_SIDE || height > MAX_DECODED_SIDE {
+                return None;
+            }
+            match image.color_space.as_deref() {
+                Some("DeviceRGB") => {
+                    RgbImage::from_raw(width, height, data).map(DynamicImage::from)
+                }
+                Some("DeviceGray") => {
+                    GrayImage::from_raw(width, height, data).map(DynamicImage::from)
+                }
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+// A picture of the first page: its text when it has any, otherwise its largest image. Full
+// PDF rendering would need a rasterizer this app doesn't ship.
+fn pdf_preview(bytes: &[u8]) -> Option<(Preview, Vec<u8>)> {
+    let doc = lopdf::Document::load_mem(bytes).ok()?;
+    if doc.is_encrypted() {
+        return None;
+    }
+    let (&number, &page) = doc.get_pages().iter().next()?;
+    let text = doc.extract_text(&[number]).unwrap_or_default();
+    if !text.trim().is_empty() {
+        let media_box = doc
+            .get_dictionary(page)
+            .ok()
+            .and_then(|page| page.get(b"MediaBox").ok())
+            .and_then(|media_box| media_box.as_array().ok())
+            .map(|values| {
+                values
+                    .iter()
+                    .filter_map(|value| value.as_float().ok())
+                    .collect::<Vec<f32>>()
+            })
+            .filter(|values| values.len() == 4);
+        let (width, height) = media_box
+            .map(|values| (values[2] - values[0], values[3] - values[1]))
+            .unwrap_or((612.0, 792.0));
+        return Some((Preview::Svg, text_preview(&text, width, height)));
+    }
+    let image = page_image(&doc, page)?;
+    encode_png(&shrink(&image)).map(|png| (Preview::Png, png))
+}
+
+// Scaled to fit the preview size; small images are left as they are
+fn shrink(image: &DynamicImage) -> DynamicImage {
+    if image.width() <= PREVIEW_SIZE && image.height() <= PREVIEW_SIZE {
+        return image.clone();
+    }
+    image.thumbnail(PREVIEW_SIZE, PREVIEW_SIZE)
+}
+
+fn encode_png(image: &DynamicImage) -> Option<Vec<u8>> {
+    let mut png = Vec::new();
+    image
+        .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+        .inspect_err(|e| tracing::warn!("Failed to encode a preview: {}", e))
+        .ok()?;
+    Some(png)
+}
+
+// Make the preview for a stored object; returns the image's size for images
+fn make_preview(
+    dir: &Path,
+    hash: &str,
+    mime_type: &str,
+) -> Result<(Option<Preview>, Option<Dimensions>), String> {
+    let is_image = matches!(mime_type, "image/png" | "image/jpeg");
+    if !is_image && mime_type != "application/pdf" {
+        return Ok((None, None));
+    }
+    let object = object_path(dir, hash);
+    let bytes =
+        fs::read(&object).map_err(|e| format!("Failed to read {}: {}", object.display(), e))?;
+    let (preview, size) = if is_image {
+        match decode_image(&bytes) {
+            Ok(image) => {
+                let size = (image.width(), image.height());
+                let png = encode_png(&shrink(&image));
+                (png.map(|png| (Preview::Png, png)), Some(size))
+            }
+            Err(e) => {
+                tracing::warn!("No preview for attachment {}: {}", hash, e);
+                (None, None)
+            }
+        }
+    } else {
+        (pdf_preview(&bytes), None)
+    };
+    let Some((preview, data)) = preview else {
+        return Ok((None, size));
+    };
+    let path = preview_path(dir, hash, preview);
+    fs::create_dir_all(dir.join(PREVIEWS_DIR))
+        .and_then(|_| fs::write(&path, data))
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    Ok((Some(preview), size))
+}
+
+fn attachment_from_row(row: &Row) -> rusqlite::Result<Attachment> {
+    let hash: String = row.get("hash")?;
+    let preview: Option<String> = row.get("preview")?;
+    Ok(Attachment {
+        url: url(&hash, false),
+        preview_url: preview.map(|_| url(&hash, true)),
+        file_name: row.get("file_name")?,
+        mime_type: row.get("mime_type")?,
+        size: row.get::<_, i64>("size")? as u64,
+        width: row.get("width")?,
+        height: row.get("height")?,
+        created_at: row.get::<_, i64>("created_at")? as u64,
+        hash,
+    })
+}
+
+fn load(conn: &Connection, hash: &str) -> Result<Option<Attachment>, String> {
+    conn.query_row(
+        "SELECT * FROM attachments WHERE hash = ?1",
+        [hash],
+        attachment_from_row,
+    )
+    .optional()
+    .map_err(db_error)
+}
+
+// Copy `source` into the store while hashing it, then move it under its hash
+fn copy_object(dir: &Path, source: &Path) -> Result<(String, u64, Vec<u8>), String> {
+    let mut input =
+        File::open(source).map_err(|e| format!("Failed to open {}: {}", source.display(), e))?;
+    let objects = dir.join(OBJECTS_DIR);
+    fs::create_dir_all(&objects)
+        .map_err(|e| format!("Failed to create {}: {}", objects.display(), e))?;
+    let partial = objects.join(format!(".{}.partial", history::new_id()));
+    let copied = (|| -> io::Result<(String, u64, Vec<u8>)> {
+        let mut output = File::create(&partial)?;
+        let mut hasher = Sha256::new();
+        let mut head = Vec::new();
+        let mut size = 0u64;
+        let mut buffer = vec![0u8; 64 * 1024];
+        loop {
+            let read = input.read(&mut buffer)?;
+            if read == 0 {
+                break;
+            }
+            size += read as u64;
+            if size > MAX_STORED_BYTES {
+                return Err(io::Error::other(format!(
+                    "Attachments are limited to {} MB",
+                    MAX_STORED_BYTES / 1024 / 1024
+                )));
+            }
+            if head.len() < 16 {
+                head.extend_from_slice(&buffer[..read.min(16 - head.len())]);
+            }
+            hasher.update(&buffer[..read]);
+            output.write_all(&buffer[..read])?;
+        }
+        output.sync_all()?;
+        let hash = hasher
+            .finalize()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect();
+        Ok((hash, size, head))
+    })();
+    let (hash, size, head) = match copied {
+        Ok(copied) => copied,
+        Err(e) => {
+            let _ = fs::remove_file(&partial);
+            return Err(e.to_string());
+        }
+    };
+    let object = object_path(dir, &hash);
+    let moved = if object.exists() {
+        fs::remove_file(&partial)
+    } else {
+        fs::create_dir_all(object.parent().unwrap()).and_then(|_| fs::rename(&partial, &object))
+    };
+    moved.map_err(|e| format!("Failed to store {}: {}", object.display(), e))?;
+    Ok((hash, size, head))
+}
+
+fn store(app: &AppHandle, source: &Path) -> Result<Attachment, String> {
+    let dir = attachments_dir(app)?;
+    let file_name = source
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or("File name is not valid UTF-8")?
+        .to_string();
+    let (hash, size, head) = copy_object(&dir, source)?;
+    if let Some(existing) = history::with_db_blocking(|conn| load(conn, &hash))? {
+        return Ok(existing);
+    }
+    let mime_type = mime_type(&head, &file_name);
+    let (preview, dimensions) = make_preview(&dir, &hash, mime_type)?;
+    history::with_db_blocking(|conn| {
+        conn.execute(
+            "INSERT OR IGNORE INTO attachments
+                (hash, file_name, mime_type, size, width, height, preview, created_at)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                hash,
+                file_name,
+                mime_type,
+                size as i64,
+                dimensions.map(|(width, _)| width),
+                dimensions.map(|(_, height)| height),
+                preview.map(Preview::extension),
+                now_millis() as i64
+            ],
+        )
+        .map_err(db_error)?;
+        load(conn, &hash)?.ok_or_else(|| format!("Unknown attachment: {}", hash))
+    })
+}
+
+fn response(status: StatusCode, body: impl Into<Vec<u8>>) -> Response<Vec<u8>> {
+    Response::builder()
+        .status(status)
+        .header(header::CONTENT_TYPE, "text/plain")
+        .body(body.into())
+        .unwrap()
+}
+
+fn serve(app: &AppHandle, request: &Request<Vec<u8>>) -> Response<Vec<u8>> {
+    let path = request.uri().path().trim_matches('/');
+    let (hash, want_preview) = match path.split_once('/') {
+        Some((hash, "preview")) => (hash, true),
+        Some(_) => return response(StatusCode::NOT_FOUND, "Not found"),
+        None => (path, false),
+    };
+    // Only hashes come through, so the path can't reach outside the store
+    if !is_hash(hash) {
+        return response(StatusCode::NOT_FOUND, "Not found");
+    }
+    let (dir, attachment) = match attachments_dir(app)
+        .and_then(|dir| Ok((dir, history::with_db_blocking(|conn| load(conn, hash))?)))
+    {
+        Ok((dir, Some(attachment))) => (dir, attachment),
+        Ok((_, None)) => return response(StatusCode::NOT_FOUND, "Unknown attachment"),
+        Err(e) => return response(StatusCode::INTERNAL_SERVER_ERROR, e),
+    };
+
+    let (path, mime_type) = if want_preview {
+        let preview = [Preview::Png, Preview::Svg]
+            .into_iter()
+            .find(|preview| preview_path(&dir, hash, *preview).exists());
+        let preview = match preview {
+            Some(preview) => Some(preview),
+            // Previews are a cache; make it again if it was cleared
+            None if attachment.preview_url.is_some() => {
+                make_preview(&dir, hash, &attachment.mime_type)
+                    .ok()
+                    .and_then(|(preview, _)| preview)
+            }
+            None => None,
+        };
+        let Some(preview) = preview else {
+            return response(StatusCode::NOT_FOUND, "This attachment has no preview");
+        };
+        (
+            preview_path(&dir, hash, preview),
+            preview.mime_type().to_string(),
+        )
+    } else {
+        if attachment.size > MAX_SERVED_BYTES {
+            return response(
+                StatusCode::PAYLOAD_TOO_LARGE,
+                "This attachment is too large to show; open it instead",
+            );
+        }
+        (object_path(&dir, hash), attachment.mime_type)
+    };
+    match fs::read(&path) {
+        Ok(body) => Response::builder()
+            .header(header::CONTENT_TYPE, mime_type)
+            .header(header::CONTENT_LENGTH, body.len())
+            // Content-addressed, so a URL's content never changes
+            .header(
+                header::CACHE_CONTROL,
+                "private, max-age=31536000, immutable",
+            )
+            .header(header::X_CONTENT_TYPE_OPTIONS, "nosniff")
+            // Keeps scripts in stored SVGs and HTML from running
+            .header(
+                header::CONTENT_SECURITY_POLICY,
+                "default-src 'none'; style-src 'unsafe-inline'",
+            )
+            .body(body)
+            .unwrap(),
+        Err(e) => response(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+// Handler for the `attachment` protocol; the file is read off the main thread
+pub fn protocol(
+    ctx: UriSchemeContext<'_, Wry>,
+    request: Request<Vec<u8>>,
+    responder: UriSchemeResponder,
+) {
+    let app = ctx.app_handle().clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        responder.respond(serve(&app, &request));
+    });
+}
+
+// Ask for a file, then copy it into the attachment store and make its preview; `None` when
+// the dialog is cancelled. The path comes from the dialog rather than the frontend, so the
+// webview can't read arbitrary files back through the attachment scheme.
+#[tauri::command]
+pub async fn store_attachment(app: AppHandle) -> Result<Option<Attachment>, AppError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let picked = app
+            .dialog()
+            .file()
+            .set_title("Attach a file")
+            .blocking_pick_file();
+        let Some(picked) = picked else {
+            return Ok(None);
+        };
+        let path = picked.into_path().map_err(|e| e.to_string())?;
+        store(&app, &path).map(Some)
+    })
+    .await?
+    .map_err(AppError::Io)
+}
+
+#[tauri::command]
+pub async fn get_attachment(hash: String) -> Result<Attachment, AppError> {
+    let lookup = hash.clone();
+    history::with_db(move |conn| load(conn, &lookup))
+        .await
+        .map_err(AppError::Database)?
+        .ok_or_else(|| AppError::NotFound(format!("Unknown attachment: {}", hash)))
+}
+
+#[tauri::command]
+pub async fn delete_attachment(app: AppHandle, hash: String) -> Result<(), AppError> {
+    if !is_hash(&hash) {
+        return Err(AppError::NotFound(format!("Unknown attachment: {}", hash)));
+    }
+    let dir = attachments_dir(&app).map_err(AppError::Io)?;
+    let deleted = history::with_db(move |conn| {
+        let deleted = conn
+            .execute("DELETE FROM attachments WHERE hash = ?1", [&hash])
+            .map_err(db_error)?;
+        if deleted > 0 {
+            for path in [
+                object_path(&dir, &hash),
+                preview_path(&dir, &hash, Preview::Png),
+                preview_path(&dir, &hash, Preview::Svg),
+            ] {
+                if let Err(e) = fs::remove_file(&path) {
+                    if e.kind() != io::ErrorKind::NotFound {
+                        tracing::warn!("Failed to remove {}: {}", path.display(), e);
+                    }
+                }
+            }
+        }
+        Ok(deleted)
+    })
+    .await
+    .map_err(AppError::Database)?;
+    if deleted == 0 {
+        return Err(AppError::NotFound("Unknown attachment".to_string()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // SHA-256 of "hello"
+    const HELLO: &str = "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824";
+
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        encode_png(&DynamicImage::ImageRgb8(RgbImage::new(width, height))).unwrap()
+    }
+
+    #[test]
+    fn stores_objects_under_their_hash() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("hello.txt");
+        fs::write(&source, b"hello").unwrap();
+
+        let (hash, size, head) = copy_object(dir.path(), &source).unwrap();
+        assert_eq!(hash, HELLO);
+        assert_eq!(size, 5);
+        assert_eq!(head, b"hello");
+        assert!(is_hash(&hash));
+        assert_eq!(fs::read(object_path(dir.path(), &hash)).unwrap(), b"hello");
+    }
+
+    #[test]
+    fn keeps_one_copy_of_the_same_contents() {
+        let dir = tempfile::tempdir().unwrap();
+        let (first, second) = (dir.path().join("a.txt"), dir.path().join("b.txt"));
+        fs::write(&first, b"hello").unwrap();
+        fs::write(&second, b"hello").unwrap();
+
+        let (a, _, _) = copy_object(dir.path(), &first).unwrap();
+        let (b, _, _) = copy_object(dir.path(), &second).unwrap();
+        assert_eq!(a, b);
+        let fanout = dir.path().join(OBJECTS_DIR).join(&a[..2]);
+        assert_eq!(fs::read_dir(fanout).unwrap().count(), 1);
+        // No partial copies are left behind
+        let objects = fs::read_dir(dir.path().join(OBJECTS_DIR)).unwrap();
+        assert_eq!(objects.count(), 1);
+    }
+
+    #[test]
+    fn recognizes_hashes_only() {
+        assert!(is_hash(HELLO));
+        assert!(!is_hash(&HELLO.to_uppercase()));
+        assert!(!is_hash(&HELLO[..63]));
+        assert!(!is_hash("../../../../etc/passwd"));
+    }
+
+    #[test]
+    fn sniffs_before_trusting_the_name() {
+        assert_eq!(mime_type(&png(1, 1), "notes.txt"), "image/png");
+        assert_eq!(mime_type(b"%PDF-1.7", "scan"), "application/pdf");
+        assert_eq!(mime_type(b"RIFF\0\0\0\0WEBPVP8 ", "x"), "image/webp");
+        assert_eq!(mime_type(b"plain", "notes.MD"), "text/markdown");
+        assert_eq!(
+            mime_type(b"plain", "program.exe"),
+            "application/octet-stream"
+        );
+    }
+
+    #[test]
+    fn previews_images_within_the_preview_size() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("wide.png");
+        fs::write(&source, png(1000, 500)).unwrap();
+        let (hash, _, _) = copy_object(dir.path(), &source).unwrap();
+
+        let (preview, size) = make_preview(dir.path(), &hash, "image/png").unwrap();
+        assert_eq!(preview, Some(Preview::Png));
+        assert_eq!(size, Some((1000, 500)));
+        let thumbnail = fs::read(preview_path(dir.path(), &hash, Preview::Png)).unwrap();
+        let thumbnail = decode_image(&thumbnail).unwrap();
+        assert_eq!((thumbnail.width(), thumbnail.height()), (PREVIEW_SIZE, 160));
+    }
+
+    #[test]
+    fn skips_previews_for_other_types_and_broken_images() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("broken.png");
+        fs::write(&source, b"\x89PNG\r\n\x1a\nnot really").unwrap();
+        let (hash, _, _) = copy_object(dir.path(), &source).unwrap();
+
+        assert_eq!(
+            make_preview(dir.path(), &hash, "image/png").unwrap(),
+            (None, None)
+        );
+        assert_eq!(
+            make_preview(dir.path(), &hash, "text/plain").unwrap(),
+            (None, None)
+        );
+    }
+
+    #[test]
+    fn text_previews_are_escaped_and_wrapped() {
+        let svg = String::from_utf8(text_preview("<script>&", 612.0, 792.0)).unwrap();
+        assert!(svg.contains("&lt;script&gt;&amp;"));
+        let long = "word ".repeat(200);
+        let lines = preview_lines(&long);
+        assert!(lines.len() > 1);
+        assert!(lines
+            .iter()
+            .all(|line| line.chars().count() <= PREVIEW_LINE_CHARS));
+    }
+}
@@ -0,0 +1,169 @@
+use std::fmt::Display;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+use crate::error::AppError;
+use crate::history::now_millis;
+use crate::redaction;
+
+// Under the app data dir; entries are only ever appended, one JSON object per line
+const AUDIT_FILE: &str = "audit.jsonl";
+const DEFAULT_LIMIT: usize = 500;
+
+static AUDIT_PATH: OnceCell<PathBuf> = OnceCell::new();
+// Keeps concurrent appends from interleaving
+static WRITE_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditAction {
+    KeychainRead,
+    KeychainWrite,
+    KeychainDelete,
+    OauthCodeExchange,
+    OauthTokenRefresh,
+    BackupExport,
+    ServerStart,
+    ServerStop,
+    ContentFilterOverride,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditOutcome {
+    Success,
+    Failure,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    // Milliseconds since the Unix epoch
+    pub timestamp: u64,
+    pub action: AuditAction,
+    // What the action was applied to: a keychain account, provider, file or server instance
+    pub subject: String,
+    pub outcome: AuditOutcome,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct AuditFilter {
+    // Only these actions; empty means all of them
+    pub actions: Vec<AuditAction>,
+    pub outcome: Option<AuditOutcome>,
+    // Only entries whose subject contains this text
+    pub subject: Option<String>,
+    // Milliseconds since the Unix epoch, inclusive
+    pub since: Option<u64>,
+    pub until: Option<u64>,
+    pub limit: Option<usize>,
+}
+
+impl AuditFilter {
+    fn matches(&self, entry: &AuditEntry) -> bool {
+        (self.actions.is_empty() || self.actions.contains(&entry.action))
+            && self.outcome.is_none_or(|outcome| outcome == entry.outcome)
+            && self
+                .subject
+                .as_deref()
+                .is_none_or(|subject| entry.subject.contains(subject))
+            && self.since.is_none_or(|since| entry.timestamp >= since)
+            && self.until.is_none_or(|until| entry.timestamp <= until)
+    }
+}
+
+// Resolve where the log lives; called from the setup hook before anything is recorded
+pub fn init(app: &AppHandle) -> Result<(), String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to resolve app data dir: {}", e))?;
+    let _ = AUDIT_PATH.set(dir.join(AUDIT_FILE));
+    Ok(())
+}
+
+fn append(entry: &AuditEntry) -> Result<(), String> {
+    let Some(path) = AUDIT_PATH.get() else {
+        return Ok(());
+    };
+    let mut line = serde_json::to_string(entry).map_err(|e| e.to_string())?;
+    line.push('\n');
+    let _lock = WRITE_LOCK.lock().unwrap();
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    }
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| file.write_all(line.as_bytes()))
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+// Append the outcome of a sensitive operation; failing to record never fails the operation
+pub fn record<T, E: Display>(action: AuditAction, subject: &str, result: &Result<T, E>) {
+    let entry = AuditEntry {
+        timestamp: now_millis(),
+        action,
+        subject: subject.to_string(),
+        outcome: match result {
+            Ok(_) => AuditOutcome::Success,
+            Err(_) => AuditOutcome::Failure,
+        },
+        error: result
+            .as_ref()
+            .err()
+            .map(|e| redaction::redact(&e.to_string()).into_owned()),
+    };
+    if let Err(e) = append(&entry) {
+        tracing::warn!("Failed to record {:?} in the audit log: {}", action, e);
+    }
+}
+
+pub fn success(action: AuditAction, subject: &str) {
+    record(action, subject, &Ok::<(), String>(()));
+}
+
+// The raw log with anything secret-looking masked, for diagnostics bundles
+pub fn redacted() -> Option<String> {
+    let contents = fs::read_to_string(AUDIT_PATH.get()?).ok()?;
+    Some(redaction::redact(&contents).into_owned())
+}
+
+// Matching entries, newest first
+#[tauri::command]
+pub async fn get_audit_log(filter: Option<AuditFilter>) -> Result<Vec<AuditEntry>, AppError> {
+    let filter = filter.unwrap_or_default();
+    let Some(path) = AUDIT_PATH.get().cloned() else {
+        return Ok(Vec::new());
+    };
+    tauri::async_runtime::spawn_blocking(move || {
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => {
+                return Err(AppError::Io(format!(
+                    "Failed to read {}: {}",
+                    path.display(),
+                    e
+                )))
+            }
+        };
+        Ok(contents
+            .lines()
+            .rev()
+            .filter_map(|line| serde_json::from_str::<AuditEntry>(line).ok())
+            .filter(|entry| filter.matches(entry))
+            .take(filter.limit.unwrap_or(DEFAULT_LIMIT))
+            .collect())
+    })
+    .await?
+}
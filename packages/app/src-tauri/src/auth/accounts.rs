@@ -0,0 +1,156 @@
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager};
+
+use super::keychain::{self, AuthSession};
+use super::unix_now;
+use super::verification::{self, Action};
+use crate::error::AppError;
+
+// The index only holds metadata; tokens live in the keychain under the account id
+const INDEX_FILE: &str = "accounts.json";
+
+// Serializes read-modify-write cycles on the index file
+static INDEX_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Account {
+    pub id: String,
+    pub provider: String,
+    // Seconds since the Unix epoch
+    pub added_at: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AccountList {
+    pub active: Option<String>,
+    pub accounts: Vec<Account>,
+}
+
+fn index_path(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_data_dir()
+        .map(|dir| dir.join(INDEX_FILE))
+        .map_err(|e| format!("Failed to resolve app data dir: {}", e))
+}
+
+fn load_index(app: &AppHandle) -> Result<AccountList, String> {
+    let path = index_path(app)?;
+    match fs::read_to_string(&path) {
+        Ok(contents) => serde_json::from_str(&contents)
+            .map_err(|e| format!("Invalid account index {}: {}", path.display(), e)),
+        Err(_) => Ok(AccountList::default()),
+    }
+}
+
+fn save_index(app: &AppHandle, index: &AccountList) -> Result<(), String> {
+    let path = index_path(app)?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    }
+    let contents = serde_json::to_string_pretty(index).map_err(|e| e.to_string())?;
+    fs::write(&path, contents).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+fn validate_account_id(account_id: &str) -> Result<(), String> {
+    if account_id.trim().is_empty() || account_id.len() > 128 {
+        return Err("Account id must be between 1 and 128 characters".to_string());
+    }
+    Ok(())
+}
+
+// Store a session and register the account, making it active if none is
+pub async fn save_session(
+    app: &AppHandle,
+    account_id: &str,
+    session: &AuthSession,
+) -> Result<(), String> {
+    validate_account_id(account_id)?;
+    keychain::store_session(account_id, session).await?;
+
+    let _lock = INDEX_LOCK.lock().unwrap();
+    let mut index = load_index(app)?;
+    match index.accounts.iter_mut().find(|a| a.id == account_id) {
+        Some(account) => account.provider = session.provider.clone(),
+        None => index.accounts.push(Account {
+            id: account_id.to_string(),
+            provider: session.provider.clone(),
+            added_at: unix_now(),
+        }),
+    }
+    if index.active.is_none() {
+        index.active = Some(account_id.to_string());
+    }
+    save_index(app, &index)
+}
+
+// Forget an account, activating the next one if it was active
+pub async fn remove(app: &AppHandle, account_id: &str) -> Result<(), String> {
+    keychain::clear_session(account_id).await?;
+
+    let _lock = INDEX_LOCK.lock().unwrap();
+    let mut index = load_index(app)?;
+    index.accounts.retain(|a| a.id != account_id);
+    if index.active.as_deref() == Some(account_id) {
+        index.active = index.accounts.first().map(|a| a.id.clone());
+    }
+    save_index(app, &index)
+}
+
+pub fn list(app: &AppHandle) -> Result<AccountList, String> {
+    let _lock = INDEX_LOCK.lock().unwrap();
+    load_index(app)
+}
+
+pub fn active_id(app: &AppHandle) -> Result<Option<String>, String> {
+    Ok(list(app)?.active)
+}
+
+#[tauri::command]
+pub fn list_accounts(app: AppHandle) -> Result<AccountList, AppError> {
+    list(&app).map_err(AppError::Io)
+}
+
+#[tauri::command]
+pub async fn store_auth_session(
+    app: AppHandle,
+    account_id: String,
+    mut session: AuthSession,
+) -> Result<(), AppError> {
+    session.issued_at.get_or_insert_with(unix_now);
+    save_session(&app, &account_id, &session)
+        .await
+        .map_err(AppError::Keychain)
+}
+
+#[tauri::command]
+#[tracing::instrument(name = "auth_switch", skip(app))]
+pub fn switch_account(app: AppHandle, account_id: String) -> Result<(), AppError> {
+    {
+        let _lock = INDEX_LOCK.lock().unwrap();
+        let mut index = load_index(&app)?;
+        if !index.accounts.iter().any(|a| a.id == account_id) {
+            return Err(AppError::NotFound(format!(
+                "Unknown account: {}",
+                account_id
+            )));
+        }
+        index.active = Some(account_id.clone());
+        save_index(&app, &index)?;
+    }
+    Ok(app.emit("auth-account-switched", &account_id)?)
+}
+
+#[tauri::command]
+#[tracing::instrument(name = "auth_remove", skip(app))]
+pub async fn remove_account(app: AppHandle, account_id: String) -> Result<(), AppError> {
+    verification::verify(&app, Action::ClearSession)
+        .await
+        .map_err(AppError::Auth)?;
+    remove(&app, &account_id).await.map_err(AppError::Keychain)
+}
@@ -0,0 +1,50 @@
+use serde::{Deserialize, Serialize};
+
+use super::secure_store;
+
+const AUTH_SESSION_PREFIX: &str = "auth-session:";
+
+// Tokens obtained from an OAuth provider; only ever stored in the OS keychain or its
+// encrypted fallback
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuthSession {
+    pub provider: String,
+    pub access_token: String,
+    pub refresh_token: Option<String>,
+    pub token_type: String,
+    pub scope: Option<String>,
+    // Seconds since the Unix epoch; unknown for sessions stored by older versions
+    #[serde(default)]
+    pub issued_at: Option<u64>,
+    // Seconds since the Unix epoch
+    pub expires_at: Option<u64>,
+}
+
+fn session_account(account_id: &str) -> String {
+    format!("{}{}", AUTH_SESSION_PREFIX, account_id)
+}
+
+pub async fn store_session(account_id: &str, session: &AuthSession) -> Result<(), String> {
+    let json = serde_json::to_string(session).map_err(|e| e.to_string())?;
+    secure_store::write(&session_account(account_id), &json)
+        .await
+        .map_err(|e| format!("Failed to store auth session: {}", e))
+}
+
+pub async fn load_session(account_id: &str) -> Result<Option<AuthSession>, String> {
+    match secure_store::read(&session_account(account_id))
+        .await
+        .map_err(|e| format!("Failed to read auth session: {}", e))?
+    {
+        Some(json) => serde_json::from_str(&json)
+            .map(Some)
+            .map_err(|e| format!("Stored auth session is corrupt: {}", e)),
+        None => Ok(None),
+    }
+}
+
+pub async fn clear_session(account_id: &str) -> Result<(), String> {
+    secure_store::delete(&session_account(account_id))
+        .await
+        .map_err(|e| format!("Failed to clear auth session: {}", e))
+}
@@ -0,0 +1,115 @@
+pub mod accounts;
+pub mod keychain;
+pub mod oauth;
+mod providers;
+pub mod refresh;
+pub mod secure_store;
+mod vault;
+pub mod verification;
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+use tauri::AppHandle;
+
+use self::verification::Action;
+use crate::error::AppError;
+
+// Seconds since the Unix epoch, the unit used for token expiry
+pub(crate) fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AuthStatus {
+    pub authenticated: bool,
+    pub account_id: Option<String>,
+    pub provider: Option<String>,
+    pub expires_at: Option<u64>,
+}
+
+// Status of the active account
+#[tauri::command]
+pub async fn get_auth_status(app: AppHandle) -> Result<AuthStatus, AppError> {
+    let account_id = accounts::active_id(&app)?;
+    let session = match &account_id {
+        Some(id) => keychain::load_session(id)
+            .await
+            .map_err(AppError::Keychain)?,
+        None => None,
+    };
+    Ok(AuthStatus {
+        authenticated: session.is_some(),
+        account_id,
+        provider: session.as_ref().map(|s| s.provider.clone()),
+        expires_at: session.and_then(|s| s.expires_at),
+    })
+}
+
+// Token metadata for a signed-in account; never includes the tokens themselves
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionInfo {
+    pub account_id: String,
+    pub provider: String,
+    pub issued_at: Option<u64>,
+    pub expires_at: Option<u64>,
+    // Seconds until the access token expires, zero once it has
+    pub expires_in: Option<u64>,
+    pub scopes: Vec<String>,
+    // Whether the session can outlive its access token
+    pub refreshable: bool,
+}
+
+// Session details of `account_id`, or of the active account; `None` when not signed in
+#[tauri::command]
+pub async fn get_session_info(
+    app: AppHandle,
+    account_id: Option<String>,
+) -> Result<Option<SessionInfo>, AppError> {
+    let Some(account_id) = account_id.or(accounts::active_id(&app)?) else {
+        return Ok(None);
+    };
+    let Some(session) = keychain::load_session(&account_id)
+        .await
+        .map_err(AppError::Keychain)?
+    else {
+        return Ok(None);
+    };
+    Ok(Some(SessionInfo {
+        account_id,
+        provider: session.provider,
+        issued_at: session.issued_at,
+        expires_at: session.expires_at,
+        expires_in: session
+            .expires_at
+            .map(|expires_at| expires_at.saturating_sub(unix_now())),
+        // OAuth scopes are space separated
+        scopes: session
+            .scope
+            .as_deref()
+            .unwrap_or_default()
+            .split_whitespace()
+            .map(str::to_string)
+            .collect(),
+        refreshable: session.refresh_token.is_some(),
+    }))
+}
+
+// Sign out of the active account
+#[tauri::command]
+pub async fn logout(app: AppHandle) -> Result<(), AppError> {
+    match accounts::active_id(&app)? {
+        Some(id) => {
+            verification::verify(&app, Action::ClearSession)
+                .await
+                .map_err(AppError::Auth)?;
+            accounts::remove(&app, &id)
+                .await
+                .map_err(AppError::Keychain)
+        }
+        None => Ok(()),
+    }
+}
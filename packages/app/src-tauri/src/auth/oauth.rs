@@ -0,0 +1,239 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use once_cell::sync::Lazy;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tauri::{AppHandle, Emitter, Url};
+use tauri_plugin_opener::OpenerExt;
+
+use super::accounts;
+use super::keychain::AuthSession;
+use super::providers::{self, OAuthProvider};
+use super::unix_now;
+use crate::audit::{self, AuditAction};
+use crate::error::AppError;
+
+// How long a started flow waits for its callback before the state is discarded
+const FLOW_TTL: Duration = Duration::from_secs(10 * 60);
+
+struct PendingFlow {
+    account_id: String,
+    provider_name: String,
+    provider: OAuthProvider,
+    code_verifier: String,
+    started: Instant,
+}
+
+// Flows started via `begin_oauth_flow`, keyed by their `state` parameter
+static PENDING_FLOWS: Lazy<Mutex<HashMap<String, PendingFlow>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+// Payload of the `oauth-completed` event; tokens stay in the keychain
+#[derive(Debug, Clone, Serialize)]
+struct OAuthCompleted {
+    account_id: String,
+    provider: String,
+    scope: Option<String>,
+    expires_at: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(super) struct TokenResponse {
+    access_token: String,
+    #[serde(default)]
+    refresh_token: Option<String>,
+    #[serde(default = "default_token_type")]
+    token_type: String,
+    #[serde(default)]
+    scope: Option<String>,
+    #[serde(default)]
+    expires_in: Option<u64>,
+}
+
+fn default_token_type() -> String {
+    "Bearer".to_string()
+}
+
+impl TokenResponse {
+    pub(super) fn into_session(self, provider: &str) -> AuthSession {
+        AuthSession {
+            provider: provider.to_string(),
+            access_token: self.access_token,
+            refresh_token: self.refresh_token,
+            token_type: self.token_type,
+            scope: self.scope,
+            issued_at: Some(unix_now()),
+            expires_at: self.expires_in.map(|secs| unix_now() + secs),
+        }
+    }
+}
+
+// POST a grant to the provider's token endpoint
+pub(super) async fn request_tokens(
+    provider: &OAuthProvider,
+    params: &[(&str, &str)],
+) -> Result<TokenResponse, String> {
+    let response = crate::network::client()
+        .post(&provider.token_endpoint)
+        .form(params)
+        .send()
+        .await
+        .map_err(|e| format!("Token request failed: {}", e))?;
+
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        return Err(format!("Token endpoint returned {}: {}", status, body));
+    }
+    response
+        .json()
+        .await
+        .map_err(|e| format!("Invalid token response: {}", e))
+}
+
+fn random_token() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+fn code_challenge(verifier: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()))
+}
+
+// Consume the pending flow for `state`; each state is only accepted once
+fn take_pending(state: &str) -> Option<PendingFlow> {
+    let mut pending = PENDING_FLOWS.lock().unwrap();
+    pending.retain(|_, flow| flow.started.elapsed() < FLOW_TTL);
+    pending.remove(state)
+}
+
+fn authorization_url(
+    provider: &OAuthProvider,
+    state: &str,
+    code_verifier: &str,
+) -> Result<Url, String> {
+    let mut url = Url::parse(&provider.authorization_endpoint)
+        .map_err(|e| format!("Invalid authorization endpoint: {}", e))?;
+    url.query_pairs_mut()
+        .append_pair("response_type", "code")
+        .append_pair("client_id", &provider.client_id)
+        .append_pair("redirect_uri", &provider.redirect_uri)
+        .append_pair("scope", &provider.scopes.join(" "))
+        .append_pair("state", state)
+        .append_pair("code_challenge", &code_challenge(code_verifier))
+        .append_pair("code_challenge_method", "S256");
+    Ok(url)
+}
+
+async fn exchange_code(flow: &PendingFlow, code: &str) -> Result<AuthSession, String> {
+    let provider = &flow.provider;
+    let tokens = request_tokens(
+        provider,
+        &[
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", provider.redirect_uri.as_str()),
+            ("client_id", provider.client_id.as_str()),
+            ("code_verifier", flow.code_verifier.as_str()),
+        ],
+    )
+    .await;
+    audit::record(AuditAction::OauthCodeExchange, &flow.provider_name, &tokens);
+    Ok(tokens?.into_session(&flow.provider_name))
+}
+
+// Start an Authorization Code + PKCE flow and open the provider's login page in the browser.
+// The resulting session is stored under `account_id`, which defaults to the provider name.
+#[tauri::command]
+#[tracing::instrument(name = "oauth_begin", skip(app))]
+pub fn begin_oauth_flow(
+    app: AppHandle,
+    provider: String,
+    account_id: Option<String>,
+) -> Result<String, AppError> {
+    let config = providers::load(&app, &provider).map_err(AppError::Auth)?;
+    let state = random_token();
+    let code_verifier = random_token();
+    let url = authorization_url(&config, &state, &code_verifier)?;
+
+    {
+        let mut pending = PENDING_FLOWS.lock().unwrap();
+        pending.retain(|_, flow| flow.started.elapsed() < FLOW_TTL);
+        pending.insert(
+            state.clone(),
+            PendingFlow {
+                account_id: account_id.unwrap_or_else(|| provider.clone()),
+                provider_name: provider,
+                provider: config,
+                code_verifier,
+                started: Instant::now(),
+            },
+        );
+    }
+
+    app.opener()
+        .open_url(url.as_str(), None::<&str>)
+        .map_err(|e| AppError::Io(format!("Failed to open browser: {}", e)))?;
+    Ok(state)
+}
+
+// Validate a redirect URL against the flows we started, then exchange the code for tokens
+#[tauri::command]
+#[tracing::instrument(name = "oauth_callback", skip_all)]
+pub async fn handle_oauth_callback(app: AppHandle, url: String) -> Result<(), AppError> {
+    let url = Url::parse(&url)
+        .map_err(|e| AppError::Validation(format!("Invalid OAuth callback URL: {}", e)))?;
+    let param = |name: &str| {
+        url.query_pairs()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.into_owned())
+    };
+
+    if let Some(error) = param("error") {
+        return Err(AppError::Auth(format!(
+            "OAuth provider returned an error: {}",
+            error
+        )));
+    }
+    let missing = |name: &str| {
+        AppError::Validation(format!("OAuth callback is missing the {} parameter", name))
+    };
+    let state = param("state").ok_or_else(|| missing("state"))?;
+    let code = param("code").ok_or_else(|| missing("code"))?;
+
+    let Some(flow) = take_pending(&state) else {
+        tracing::warn!("Rejected OAuth callback with unknown or expired state");
+        return Err(AppError::Auth("OAuth state mismatch".to_string()));
+    };
+
+    let result = match exchange_code(&flow, &code).await {
+        Ok(session) => accounts::save_session(&app, &flow.account_id, &session)
+            .await
+            .map(|_| session),
+        Err(e) => Err(e),
+    };
+
+    match result {
+        Ok(session) => {
+            tracing::info!(account_id = %flow.account_id, "OAuth flow completed");
+            let completed = OAuthCompleted {
+                account_id: flow.account_id,
+                provider: session.provider,
+                scope: session.scope,
+                expires_at: session.expires_at,
+            };
+            Ok(app.emit("oauth-completed", &completed)?)
+        }
+        Err(e) => {
+            tracing::error!("OAuth token exchange failed: {}", e);
+            let _ = app.emit("oauth-failed", &e);
+            Err(AppError::Auth(e))
+        }
+    }
+}
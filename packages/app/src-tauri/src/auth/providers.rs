@@ -0,0 +1,42 @@
+use std::collections::HashMap;
+use std::fs;
+
+use serde::Deserialize;
+use tauri::{AppHandle, Manager};
+
+const PROVIDERS_FILE: &str = "oauth.json";
+
+// An OAuth 2.0 authorization server the app can sign in with
+#[derive(Debug, Clone, Deserialize)]
+pub struct OAuthProvider {
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+    pub client_id: String,
+    pub redirect_uri: String,
+    #[serde(default)]
+    pub scopes: Vec<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ProvidersFile {
+    #[serde(default)]
+    providers: HashMap<String, OAuthProvider>,
+}
+
+// Look up a provider in `oauth.json` in the app config dir
+pub fn load(app: &AppHandle, name: &str) -> Result<OAuthProvider, String> {
+    let path = app
+        .path()
+        .app_config_dir()
+        .map_err(|e| format!("Failed to resolve app config dir: {}", e))?
+        .join(PROVIDERS_FILE);
+    let contents = fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let file: ProvidersFile = serde_json::from_str(&contents)
+        .map_err(|e| format!("Invalid OAuth provider config {}: {}", path.display(), e))?;
+
+    file.providers
+        .get(name)
+        .cloned()
+        .ok_or_else(|| format!("Unknown OAuth provider: {}", name))
+}
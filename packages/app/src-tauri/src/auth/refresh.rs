@@ -0,0 +1,191 @@
+use std::time::Duration;
+
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+use tokio::sync::Mutex;
+
+use super::accounts;
+use super::keychain::{self, AuthSession};
+use super::oauth::request_tokens;
+use super::{providers, unix_now};
+use crate::audit::{self, AuditAction};
+use crate::capabilities::{self, Capability};
+use crate::error::AppError;
+
+// Refresh this long before the access token actually expires
+const REFRESH_MARGIN_SECS: u64 = 5 * 60;
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+// Shortest wait between checks, so a failing refresh near expiry doesn't spin
+const MIN_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+// Held while a refresh is in flight so the background task and commands don't race
+static REFRESH_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+
+// Payload of the `auth-token-refreshed` event
+#[derive(Debug, Clone, Serialize)]
+struct TokenRefreshed {
+    account_id: String,
+    provider: String,
+    expires_at: Option<u64>,
+}
+
+fn needs_refresh(session: &AuthSession) -> bool {
+    session
+        .expires_at
+        .is_some_and(|expires_at| expires_at <= unix_now() + REFRESH_MARGIN_SECS)
+}
+
+fn is_expired(session: &AuthSession) -> bool {
+    session
+        .expires_at
+        .is_some_and(|expires_at| expires_at <= unix_now())
+}
+
+async fn refresh(app: &AppHandle, session: &AuthSession) -> Result<AuthSession, String> {
+    let refresh_token = session
+        .refresh_token
+        .as_deref()
+        .ok_or("Session has no refresh token")?;
+    let provider = providers::load(app, &session.provider)?;
+    let tokens = request_tokens(
+        &provider,
+        &[
+            ("grant_type", "refresh_token"),
+            ("refresh_token", refresh_token),
+            ("client_id", provider.client_id.as_str()),
+        ],
+    )
+    .await;
+    audit::record(AuditAction::OauthTokenRefresh, &session.provider, &tokens);
+    let tokens = tokens?;
+
+    let mut refreshed = tokens.into_session(&session.provider);
+    // Providers that don't rotate refresh tokens omit them from the response, and the
+    // scope is only sent when it changed
+    if refreshed.refresh_token.is_none() {
+        refreshed.refresh_token = session.refresh_token.clone();
+    }
+    if refreshed.scope.is_none() {
+        refreshed.scope = session.scope.clone();
+    }
+    Ok(refreshed)
+}
+
+async fn expire(app: &AppHandle, account_id: &str) {
+    if let Err(e) = accounts::remove(app, account_id).await {
+        tracing::warn!("{}", e);
+    }
+    let _ = app.emit("auth-session-expired", account_id);
+}
+
+// Return a session that is valid for at least the refresh margin, refreshing if needed.
+// The caller must hold REFRESH_LOCK.
+#[tracing::instrument(name = "auth_refresh", skip(app, session))]
+async fn ensure_fresh(
+    app: &AppHandle,
+    account_id: &str,
+    session: AuthSession,
+) -> Result<AuthSession, String> {
+    if !needs_refresh(&session) {
+        return Ok(session);
+    }
+
+    match refresh(app, &session).await {
+        Ok(refreshed) => {
+            keychain::store_session(account_id, &refreshed).await?;
+            let _ = app.emit(
+                "auth-token-refreshed",
+                TokenRefreshed {
+                    account_id: account_id.to_string(),
+                    provider: refreshed.provider.clone(),
+                    expires_at: refreshed.expires_at,
+                },
+            );
+            Ok(refreshed)
+        }
+        Err(e) if is_expired(&session) => {
+            tracing::warn!("Failed to refresh expired session: {}", e);
+            expire(app, account_id).await;
+            Err("Session expired".to_string())
+        }
+        Err(e) => {
+            // The current token is still usable; try again on the next check
+            tracing::warn!("Failed to refresh access token: {}", e);
+            Ok(session)
+        }
+    }
+}
+
+// When the session next needs attention: its refresh window, or expiry once refreshing
+// has failed
+fn next_check(session: &AuthSession) -> Option<u64> {
+    let expires_at = session.expires_at?;
+    let refresh_at = expires_at.saturating_sub(REFRESH_MARGIN_SECS);
+    Some(if refresh_at > unix_now() {
+        refresh_at
+    } else {
+        expires_at
+    })
+}
+
+// Refresh or expire every stored session, returning the earliest time one needs another
+// look
+async fn refresh_all(app: &AppHandle) -> Result<Option<u64>, String> {
+    let _guard = REFRESH_LOCK.lock().await;
+    let mut next = None;
+    for account in accounts::list(app)?.accounts {
+        if let Some(session) = keychain::load_session(&account.id).await? {
+            let Ok(session) = ensure_fresh(app, &account.id, session).await else {
+                continue;
+            };
+            if let Some(at) = next_check(&session) {
+                next = Some(next.map_or(at, |next: u64| next.min(at)));
+            }
+        }
+    }
+    Ok(next)
+}
+
+// Periodically refresh stored sessions before they expire
+pub fn spawn(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            // Wake up right when a session lapses rather than up to a minute later
+            let delay = match refresh_all(&app).await {
+                Ok(Some(at)) => Duration::from_secs(at.saturating_sub(unix_now()))
+                    .clamp(MIN_CHECK_INTERVAL, CHECK_INTERVAL),
+                Ok(None) => CHECK_INTERVAL,
+                Err(e) => {
+                    tracing::warn!("{}", e);
+                    CHECK_INTERVAL
+                }
+            };
+            tokio::time::sleep(delay).await;
+        }
+    });
+}
+
+// Check every session now rather than on the next tick, e.g. after waking from sleep
+pub async fn refresh_now(app: &AppHandle) {
+    if let Err(e) = refresh_all(app).await {
+        tracing::warn!("{}", e);
+    }
+}
+
+// Access token of the active account, refreshed first if it is about to expire
+#[tauri::command]
+pub async fn get_access_token(app: AppHandle) -> Result<String, AppError> {
+    capabilities::require(Capability::ReadSecrets)?;
+    let _guard = REFRESH_LOCK.lock().await;
+    let signed_out = || AppError::Auth("Not signed in".to_string());
+    let account_id = accounts::active_id(&app)?.ok_or_else(signed_out)?;
+    let session = keychain::load_session(&account_id)
+        .await
+        .map_err(AppError::Keychain)?
+        .ok_or_else(signed_out)?;
+    ensure_fresh(&app, &account_id, session)
+        .await
+        .map(|session| session.access_token)
+        .map_err(AppError::Auth)
+}
@@ -0,0 +1,196 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+
+use super::vault;
+use crate::audit::{self, AuditAction};
+use crate::error::AppError;
+
+// Every secret the app keeps goes through here. Keychain calls can block for a long time,
+// on DBus on Linux or behind an unlock prompt on macOS, so async code uses the functions
+// that run them on the blocking pool; the `_blocking` variants are for code that's already
+// off the async runtime.
+
+const KEYCHAIN_SERVICE: &str = "com.elizaos.app";
+const VAULT_FILE: &str = "secrets.vault";
+// Read by `get_secret_backend` to find out whether the keychain works
+const PROBE_ACCOUNT: &str = "backend-probe";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SecretBackend {
+    Keychain,
+    EncryptedFile,
+}
+
+// Unknown until the first keychain operation succeeds or fails
+static BACKEND: Lazy<Mutex<Option<SecretBackend>>> = Lazy::new(|| Mutex::new(None));
+// Values already read or written this session, `None` for accounts known to be empty, so
+// the keychain is asked (and on macOS may prompt) once per account
+static CACHE: Lazy<Mutex<HashMap<String, Option<String>>>> = Lazy::new(Default::default);
+
+// Resolve where the encrypted fallback lives; called from the setup hook
+pub fn init(app: &AppHandle) -> Result<(), String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to resolve app data dir: {}", e))?;
+    vault::init(dir.join(VAULT_FILE));
+    Ok(())
+}
+
+// Forget a cached value, e.g. one that may have changed outside the app
+pub fn invalidate(account: &str) {
+    CACHE.lock().unwrap().remove(account);
+}
+
+// Forget every cached value, e.g. after the keychain may have been locked or edited
+pub fn invalidate_all() {
+    CACHE.lock().unwrap().clear();
+}
+
+fn remember(account: &str, result: &Result<Option<String>, String>) {
+    let mut cache = CACHE.lock().unwrap();
+    match result {
+        Ok(value) => cache.insert(account.to_string(), value.clone()),
+        // What's stored is unknown after a failure
+        Err(_) => cache.remove(account),
+    };
+}
+
+// The keychain itself is missing or locked, as opposed to a problem with one entry
+fn unavailable(e: &keyring::Error) -> bool {
+    matches!(
+        e,
+        keyring::Error::PlatformFailure(_) | keyring::Error::NoStorageAccess(_)
+    )
+}
+
+// Run `op` against the OS keychain. Returns `None` when the encrypted file should be
+// used instead, which sticks for the rest of the session once the keychain has failed.
+fn keychain<T>(
+    account: &str,
+    op: impl FnOnce(&keyring::Entry) -> keyring::Result<T>,
+) -> Result<Option<T>, String> {
+    let mut backend = BACKEND.lock().unwrap();
+    if *backend == Some(SecretBackend::EncryptedFile) {
+        return Ok(None);
+    }
+    match keyring::Entry::new(KEYCHAIN_SERVICE, account).and_then(|entry| op(&entry)) {
+        Ok(value) => {
+            *backend = Some(SecretBackend::Keychain);
+            Ok(Some(value))
+        }
+        Err(e) if unavailable(&e) => {
+            tracing::warn!(
+                "OS keychain is unavailable, storing secrets in an encrypted file: {}",
+                e
+            );
+            *backend = Some(SecretBackend::EncryptedFile);
+            // Cached values came from the keychain, not the file now in use
+            invalidate_all();
+            Ok(None)
+        }
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+fn read_stored(account: &str) -> Result<Option<String>, String> {
+    let stored = keychain(account, |entry| match entry.get_password() {
+        Ok(value) => Ok(Some(value)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(e),
+    })?;
+    match stored {
+        Some(value) => Ok(value),
+        None => vault::read(account),
+    }
+}
+
+fn write_stored(account: &str, value: &str) -> Result<(), String> {
+    match keychain(account, |entry| entry.set_password(value))? {
+        Some(()) => Ok(()),
+        None => vault::write(account, value),
+    }
+}
+
+fn delete_stored(account: &str) -> Result<(), String> {
+    let deleted = keychain(account, |entry| match entry.delete_credential() {
+        Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+        Err(e) => Err(e),
+    })?;
+    match deleted {
+        Some(()) => Ok(()),
+        None => vault::delete(account),
+    }
+}
+
+// Reads that reach a store are recorded in the audit log; the cache answers the frequent
+// repeats, such as a token sent with every request, without adding entries
+pub fn read_blocking(account: &str) -> Result<Option<String>, String> {
+    if let Some(value) = CACHE.lock().unwrap().get(account).cloned() {
+        return Ok(value);
+    }
+    let result = read_stored(account);
+    remember(account, &result);
+    audit::record(AuditAction::KeychainRead, account, &result);
+    result
+}
+
+pub fn write_blocking(account: &str, value: &str) -> Result<(), String> {
+    let result = write_stored(account, value);
+    remember(account, &result.clone().map(|()| Some(value.to_string())));
+    audit::record(AuditAction::KeychainWrite, account, &result);
+    result
+}
+
+pub fn delete_blocking(account: &str) -> Result<(), String> {
+    let result = delete_stored(account);
+    remember(account, &result.clone().map(|()| None));
+    audit::record(AuditAction::KeychainDelete, account, &result);
+    result
+}
+
+async fn blocking<T: Send + 'static>(
+    op: impl FnOnce() -> Result<T, String> + Send + 'static,
+) -> Result<T, String> {
+    tauri::async_runtime::spawn_blocking(op)
+        .await
+        .map_err(|e| format!("Secret store task failed: {}", e))?
+}
+
+pub async fn read(account: &str) -> Result<Option<String>, String> {
+    let account = account.to_string();
+    blocking(move || read_blocking(&account)).await
+}
+
+pub async fn write(account: &str, value: &str) -> Result<(), String> {
+    let (account, value) = (account.to_string(), value.to_string());
+    blocking(move || write_blocking(&account, &value)).await
+}
+
+pub async fn delete(account: &str) -> Result<(), String> {
+    let account = account.to_string();
+    blocking(move || delete_blocking(&account)).await
+}
+
+// Which store secrets are kept in, probing the keychain if it hasn't been used yet
+pub fn backend_blocking() -> SecretBackend {
+    if let Some(backend) = *BACKEND.lock().unwrap() {
+        return backend;
+    }
+    // Only a real keychain call tells
+    invalidate(PROBE_ACCOUNT);
+    if let Err(e) = read_blocking(PROBE_ACCOUNT) {
+        tracing::warn!("Keychain probe failed: {}", e);
+    }
+    BACKEND.lock().unwrap().unwrap_or(SecretBackend::Keychain)
+}
+
+#[tauri::command]
+pub async fn get_secret_backend() -> Result<SecretBackend, AppError> {
+    Ok(blocking(|| Ok(backend_blocking())).await?)
+}
@@ -0,0 +1,190 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use once_cell::sync::{Lazy, OnceCell};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+// Encrypted file used in place of the OS keychain when there is none, e.g. on Linux
+// without a Secret Service daemon.
+
+const VAULT_VERSION: u32 = 1;
+// Overrides the machine-derived secret the vault key is derived from
+const PASSPHRASE_VAR: &str = "ELIZA_SECRET_STORE_PASSPHRASE";
+const KDF_ROUNDS: u32 = 210_000;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+#[derive(Serialize, Deserialize)]
+struct VaultFile {
+    version: u32,
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+struct Vault {
+    salt: Vec<u8>,
+    key: [u8; 32],
+    entries: HashMap<String, String>,
+}
+
+static PATH: OnceCell<PathBuf> = OnceCell::new();
+// Decrypted on first use and kept for the rest of the session
+static VAULT: Lazy<Mutex<Option<Vault>>> = Lazy::new(|| Mutex::new(None));
+
+pub(super) fn init(path: PathBuf) {
+    let _ = PATH.set(path);
+}
+
+fn path() -> Result<&'static PathBuf, String> {
+    PATH.get()
+        .ok_or_else(|| "The encrypted secret store is not initialised".to_string())
+}
+
+// A user passphrase if one is set, otherwise something stable about this machine and user.
+// The machine-derived secret keeps the file useless when copied elsewhere, but not from
+// other programs running as the same user.
+fn secret() -> Result<String, String> {
+    if let Ok(passphrase) = std::env::var(PASSPHRASE_VAR) {
+        if !passphrase.is_empty() {
+            return Ok(passphrase);
+        }
+    }
+    let machine = ["/etc/machine-id", "/var/lib/dbus/machine-id"]
+        .iter()
+        .find_map(|path| fs::read_to_string(path).ok())
+        .map(|id| id.trim().to_string())
+        .filter(|id| !id.is_empty())
+        .or_else(sysinfo::System::host_name)
+        .ok_or("No machine id is available to protect the secret store")?;
+    let user = std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_default();
+    Ok(format!("{}:{}", machine, user))
+}
+
+fn derive_key(salt: &[u8]) -> Result<[u8; 32], String> {
+    let mut key = [0u8; 32];
+    pbkdf2::pbkdf2_hmac::<Sha256>(secret()?.as_bytes(), salt, KDF_ROUNDS, &mut key);
+    Ok(key)
+}
+
+fn decode(field: &str) -> Result<Vec<u8>, String> {
+    BASE64
+        .decode(field)
+        .map_err(|e| format!("The secret store is corrupt: {}", e))
+}
+
+fn open() -> Result<Vault, String> {
+    let path = path()?;
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            let mut salt = vec![0u8; SALT_LEN];
+            rand::thread_rng().fill_bytes(&mut salt);
+            return Ok(Vault {
+                key: derive_key(&salt)?,
+                salt,
+                entries: HashMap::new(),
+            });
+        }
+        Err(e) => return Err(format!("Failed to read {}: {}", path.display(), e)),
+    };
+
+    let file: VaultFile = serde_json::from_str(&contents)
+        .map_err(|e| format!("The secret store is corrupt: {}", e))?;
+    if file.version != VAULT_VERSION {
+        return Err(format!("Unsupported secret store version {}", file.version));
+    }
+    let salt = decode(&file.salt)?;
+    let key = derive_key(&salt)?;
+    let plaintext = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key))
+        .decrypt(
+            Nonce::from_slice(&decode(&file.nonce)?),
+            decode(&file.ciphertext)?.as_slice(),
+        )
+        .map_err(|_| {
+            format!(
+                "Failed to decrypt {}; if it was created with {}, set it again",
+                path.display(),
+                PASSPHRASE_VAR
+            )
+        })?;
+    let entries = serde_json::from_slice(&plaintext)
+        .map_err(|e| format!("The secret store is corrupt: {}", e))?;
+    Ok(Vault { salt, key, entries })
+}
+
+fn save(vault: &Vault) -> Result<(), String> {
+    let path = path()?;
+    let mut nonce = [0u8; NONCE_LEN];
+    rand::thread_rng().fill_bytes(&mut nonce);
+    let plaintext = serde_json::to_vec(&vault.entries).map_err(|e| e.to_string())?;
+    let ciphertext = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&vault.key))
+        .encrypt(Nonce::from_slice(&nonce), plaintext.as_slice())
+        .map_err(|_| "Failed to encrypt the secret store".to_string())?;
+    let file = VaultFile {
+        version: VAULT_VERSION,
+        salt: BASE64.encode(&vault.salt),
+        nonce: BASE64.encode(nonce),
+        ciphertext: BASE64.encode(ciphertext),
+    };
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    }
+    let contents = serde_json::to_string_pretty(&file).map_err(|e| e.to_string())?;
+    // Write to a sibling file first so a crash can't leave a truncated store behind
+    let tmp = path.with_extension("tmp");
+    fs::write(&tmp, contents).map_err(|e| format!("Failed to write {}: {}", tmp.display(), e))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let _ = fs::set_permissions(&tmp, fs::Permissions::from_mode(0o600));
+    }
+    fs::rename(&tmp, path).map_err(|e| format!("Failed to replace {}: {}", path.display(), e))
+}
+
+fn with_vault<T>(f: impl FnOnce(&mut Vault) -> Result<T, String>) -> Result<T, String> {
+    let mut guard = VAULT.lock().unwrap();
+    if guard.is_none() {
+        *guard = Some(open()?);
+    }
+    f(guard.as_mut().unwrap())
+}
+
+pub(super) fn read(account: &str) -> Result<Option<String>, String> {
+    with_vault(|vault| Ok(vault.entries.get(account).cloned()))
+}
+
+pub(super) fn write(account: &str, value: &str) -> Result<(), String> {
+    with_vault(|vault| {
+        let previous = vault.entries.insert(account.to_string(), value.to_string());
+        save(vault).inspect_err(|_| match previous {
+            Some(previous) => {
+                vault.entries.insert(account.to_string(), previous);
+            }
+            None => {
+                vault.entries.remove(account);
+            }
+        })
+    })
+}
+
+pub(super) fn delete(account: &str) -> Result<(), String> {
+    with_vault(|vault| match vault.entries.remove(account) {
+        Some(previous) => save(vault).inspect_err(|_| {
+            vault.entries.insert(account.to_string(), previous);
+        }),
+        None => Ok(()),
+    })
+}
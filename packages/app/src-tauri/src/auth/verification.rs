@@ -0,0 +1,241 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::error::AppError;
+use crate::settings;
+
+// A successful check covers follow-up actions for this long, e.g. revealing several keys
+const GRACE_PERIOD: Duration = Duration::from_secs(30);
+const MAX_REASON_LEN: usize = 120;
+
+// Which actions ask the user to prove they're at the keyboard first
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VerificationSettings {
+    pub reveal_api_keys: bool,
+    pub export_backups: bool,
+    pub clear_sessions: bool,
+}
+
+impl Default for VerificationSettings {
+    fn default() -> Self {
+        Self {
+            reveal_api_keys: true,
+            export_backups: true,
+            clear_sessions: true,
+        }
+    }
+}
+
+impl VerificationSettings {
+    // Whether `next` turns off a check that's on here
+    pub fn weakened_by(&self, next: &VerificationSettings) -> bool {
+        (self.reveal_api_keys && !next.reveal_api_keys)
+            || (self.export_backups && !next.export_backups)
+            || (self.clear_sessions && !next.clear_sessions)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Action {
+    RevealApiKey,
+    ExportBackup,
+    ClearSession,
+}
+
+impl Action {
+    fn required(self, settings: &VerificationSettings) -> bool {
+        match self {
+            Action::RevealApiKey => settings.reveal_api_keys,
+            Action::ExportBackup => settings.export_backups,
+            Action::ClearSession => settings.clear_sessions,
+        }
+    }
+
+    // Completes "Eliza Desktop is trying to ..." in the system prompt
+    fn reason(self) -> &'static str {
+        match self {
+            Action::RevealApiKey => "reveal an API key",
+            Action::ExportBackup => "export a backup",
+            Action::ClearSession => "sign out of an account",
+        }
+    }
+}
+
+enum Outcome {
+    Verified,
+    Denied,
+    // Nothing to verify with, e.g. no Windows Hello or polkit; the action is allowed
+    Unavailable,
+}
+
+static LAST_VERIFIED: Lazy<Mutex<Option<Instant>>> = Lazy::new(|| Mutex::new(None));
+
+// Touch ID, or the account password when there is no biometry
+#[cfg(target_os = "macos")]
+fn verify_user(_app: &AppHandle, reason: &str) -> Result<Outcome, String> {
+    use block2::RcBlock;
+    use objc2::runtime::Bool;
+    use objc2_foundation::{NSError, NSString};
+    use objc2_local_authentication::{LAContext, LAPolicy};
+
+    let policy = LAPolicy::DeviceOwnerAuthentication;
+    let context = unsafe { LAContext::new() };
+    if unsafe { context.canEvaluatePolicy_error(policy) }.is_err() {
+        return Ok(Outcome::Unavailable);
+    }
+    let (sender, reply) = std::sync::mpsc::channel();
+    let block = RcBlock::new(move |success: Bool, _error: *mut NSError| {
+        let _ = sender.send(success.as_bool());
+    });
+    unsafe {
+        context.evaluatePolicy_localizedReason_reply(policy, &NSString::from_str(reason), &block)
+    };
+    // The reply arrives on a private queue; `context` must outlive it
+    let verified = reply.recv().unwrap_or(false);
+    Ok(if verified {
+        Outcome::Verified
+    } else {
+        Outcome::Denied
+    })
+}
+
+// Windows Hello, parented to the main window so the prompt isn't hidden behind it
+#[cfg(windows)]
+fn verify_user(app: &AppHandle, reason: &str) -> Result<Outcome, String> {
+    use tauri::Manager;
+    use windows::core::{factory, HSTRING};
+    use windows::Security::Credentials::UI::{
+        UserConsentVerificationResult, UserConsentVerifier, UserConsentVerifierAvailability,
+    };
+    use windows::Win32::Foundation::HWND;
+    use windows::Win32::System::WinRT::IUserConsentVerifierInterop;
+    use windows_future::IAsyncOperation;
+
+    let error = |e: windows::core::Error| format!("Windows Hello failed: {}", e);
+    let availability = UserConsentVerifier::CheckAvailabilityAsync()
+        .and_then(|operation| operation.get())
+        .map_err(error)?;
+    if availability != UserConsentVerifierAvailability::Available {
+        return Ok(Outcome::Unavailable);
+    }
+
+    let message = HSTRING::from(format!("Eliza Desktop is trying to {}", reason));
+    let window = app
+        .get_webview_window("main")
+        .and_then(|window| window.hwnd().ok());
+    let operation: IAsyncOperation<UserConsentVerificationResult> = match window {
+        Some(hwnd) => factory::<UserConsentVerifier, IUserConsentVerifierInterop>().and_then(
+            |interop| unsafe { interop.RequestVerificationForWindowAsync(HWND(hwnd.0), &message) },
+        ),
+        None => UserConsentVerifier::RequestVerificationAsync(&message),
+    }
+    .map_err(error)?;
+    Ok(
+        if operation.get().map_err(error)? == UserConsentVerificationResult::Verified {
+            Outcome::Verified
+        } else {
+            Outcome::Denied
+        },
+    )
+}
+
+// polkit asks the session's authentication agent; the action it checks normally needs the
+// user's (or an admin's) password
+#[cfg(target_os = "linux")]
+fn verify_user(_app: &AppHandle, _reason: &str) -> Result<Outcome, String> {
+    let status = std::process::Command::new("pkcheck")
+        .args([
+            "--action-id",
+            "org.freedesktop.policykit.exec",
+            "--process",
+            &std::process::id().to_string(),
+            "--allow-user-interaction",
+        ])
+        .status();
+    match status {
+        Ok(status) if status.success() => Ok(Outcome::Verified),
+        // 4 means polkit itself failed, e.g. no authentication agent is running
+        Ok(status) if status.code() == Some(4) => Ok(Outcome::Unavailable),
+        Ok(_) => Ok(Outcome::Denied),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Outcome::Unavailable),
+        Err(e) => Err(format!("Failed to run pkcheck: {}", e)),
+    }
+}
+
+#[cfg(not(any(target_os = "macos", windows, target_os = "linux")))]
+fn verify_user(_app: &AppHandle, _reason: &str) -> Result<Outcome, String> {
+    Ok(Outcome::Unavailable)
+}
+
+async fn require(app: &AppHandle, reason: String) -> Result<(), String> {
+    if LAST_VERIFIED
+        .lock()
+        .unwrap()
+        .is_some_and(|at| at.elapsed() < GRACE_PERIOD)
+    {
+        return Ok(());
+    }
+    prompt(app, reason).await
+}
+
+// Ask the OS to verify the user now, whatever the grace period
+async fn prompt(app: &AppHandle, reason: String) -> Result<(), String> {
+    let handle = app.clone();
+    let outcome = tauri::async_runtime::spawn_blocking(move || verify_user(&handle, &reason))
+        .await
+        .map_err(|e| e.to_string())??;
+    match outcome {
+        Outcome::Verified => {
+            *LAST_VERIFIED.lock().unwrap() = Some(Instant::now());
+            Ok(())
+        }
+        Outcome::Unavailable => {
+            tracing::warn!("No OS user verification is available; allowing the action");
+            Ok(())
+        }
+        Outcome::Denied => Err("User verification failed or was cancelled".to_string()),
+    }
+}
+
+// Ask the user to verify themselves before `action` if the settings call for it
+pub async fn verify(app: &AppHandle, action: Action) -> Result<(), String> {
+    if !action.required(&settings::current().verification) {
+        return Ok(());
+    }
+    require(app, action.reason().to_string()).await
+}
+
+// Let the frontend guard its own sensitive views; `reason` completes "Eliza Desktop is
+// trying to ..."
+#[tauri::command]
+pub async fn require_user_verification(app: AppHandle, reason: String) -> Result<(), AppError> {
+    let reason = reason.trim();
+    if reason.is_empty() || reason.len() > MAX_REASON_LEN {
+        return Err(AppError::Validation(
+            "A short reason is required".to_string(),
+        ));
+    }
+    require(&app, reason.to_string())
+        .await
+        .map_err(AppError::Auth)
+}
+
+// Choose which actions ask for verification. Turning a check off always asks first, so
+// someone at an unlocked machine can't switch the gate off and then go through it.
+#[tauri::command]
+pub async fn set_verification_settings(
+    app: AppHandle,
+    verification: VerificationSettings,
+) -> Result<VerificationSettings, AppError> {
+    if settings::current().verification.weakened_by(&verification) {
+        prompt(&app, "turn off a verification check".to_string())
+            .await
+            .map_err(AppError::Auth)?;
+    }
+    Ok(settings::update(&app, |settings| settings.verification = verification)?.verification)
+}
@@ -0,0 +1,90 @@
+use serde::Serialize;
+use tauri::plugin::TauriPlugin;
+use tauri::{AppHandle, Runtime};
+use tauri_plugin_autostart::ManagerExt;
+
+use crate::error::AppError;
+use crate::settings;
+
+// Passed to the app when the OS launches it at login
+const AUTOSTART_ARG: &str = "--autostart";
+
+// The plugin writes a LaunchAgent on macOS (its default launcher), a Run registry key
+// on Windows and an autostart desktop entry on Linux
+pub fn plugin<R: Runtime>() -> TauriPlugin<R> {
+    tauri_plugin_autostart::Builder::new()
+        .arg(AUTOSTART_ARG)
+        .build()
+}
+
+// Whether this process was started by the login item rather than by the user
+pub fn launched_at_login() -> bool {
+    std::env::args().any(|arg| arg == AUTOSTART_ARG)
+}
+
+// Bring the stored flag in line with the OS, in case the user removed the login item there
+pub fn init(app: &AppHandle) {
+    match app.autolaunch().is_enabled() {
+        Ok(enabled) if enabled != settings::current().autostart => {
+            if let Err(e) = settings::update(app, |settings| settings.autostart = enabled) {
+                tracing::warn!("{}", e);
+            }
+        }
+        Ok(_) => {}
+        Err(e) => tracing::warn!("Failed to read autostart state: {}", e),
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AutostartStatus {
+    pub enabled: bool,
+    pub start_minimized: bool,
+    pub start_server: bool,
+}
+
+fn status(enabled: bool) -> AutostartStatus {
+    let settings = settings::current();
+    AutostartStatus {
+        enabled,
+        start_minimized: settings.autostart_minimized,
+        start_server: settings.autostart_server,
+    }
+}
+
+// Launch at login, optionally hidden in the tray and with or without the server
+#[tauri::command]
+pub fn enable_autostart(
+    app: AppHandle,
+    start_minimized: Option<bool>,
+    start_server: Option<bool>,
+) -> Result<AutostartStatus, AppError> {
+    app.autolaunch()
+        .enable()
+        .map_err(|e| AppError::Io(format!("Failed to enable autostart: {}", e)))?;
+    settings::update(&app, |settings| {
+        settings.autostart = true;
+        if let Some(minimized) = start_minimized {
+            settings.autostart_minimized = minimized;
+        }
+        if let Some(start_server) = start_server {
+            settings.autostart_server = start_server;
+        }
+    })?;
+    Ok(status(true))
+}
+
+#[tauri::command]
+pub fn disable_autostart(app: AppHandle) -> Result<AutostartStatus, AppError> {
+    app.autolaunch()
+        .disable()
+        .map_err(|e| AppError::Io(format!("Failed to disable autostart: {}", e)))?;
+    settings::update(&app, |settings| settings.autostart = false)?;
+    Ok(status(false))
+}
+
+#[tauri::command]
+pub fn is_autostart_enabled(app: AppHandle) -> Result<bool, AppError> {
+    app.autolaunch()
+        .is_enabled()
+        .map_err(|e| AppError::Io(format!("Failed to read autostart state: {}", e)))
+}
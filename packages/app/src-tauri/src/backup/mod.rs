@@ -0,0 +1,459 @@
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::iter;
+use std::path::{Component, Path, PathBuf};
+
+use age::secrecy::SecretString;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tauri::{AppHandle, Manager};
+use zip::write::SimpleFileOptions;
+use zip::{ZipArchive, ZipWriter};
+
+pub mod schedule;
+
+use crate::audit::{self, AuditAction};
+use crate::auth::unix_now;
+use crate::auth::verification::{self, Action};
+use crate::capabilities::{self, Capability};
+use crate::error::AppError;
+use crate::server::{self, ServerStatus};
+use crate::{characters, knowledge, settings, workspace};
+
+// Bump when the archive layout changes; newer archives are refused
+const FORMAT_VERSION: u32 = 1;
+const MANIFEST_FILE: &str = "manifest.json";
+const SETTINGS_ENTRY: &str = "settings.json";
+// Where elizaOS keeps the agent database inside the workspace
+const AGENT_DATA_DIR: &str = ".eliza";
+// Existing data is moved aside under this suffix rather than deleted on restore
+const PRE_RESTORE_SUFFIX: &str = "before-restore";
+const STAGING_SUFFIX: &str = "restoring";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    pub path: String,
+    pub size: u64,
+    pub sha256: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Manifest {
+    pub format_version: u32,
+    pub app_version: String,
+    // Seconds since the Unix epoch
+    pub created_at: u64,
+    pub entries: Vec<ManifestEntry>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BackupInfo {
+    pub path: PathBuf,
+    pub app_version: String,
+    pub created_at: u64,
+    pub entries: usize,
+    pub size: u64,
+}
+
+// Directories archived under `<name>/`
+fn roots() -> Result<Vec<(&'static str, PathBuf)>, String> {
+    Ok(vec![
+        ("database", workspace::dir()?.join(AGENT_DATA_DIR)),
+        ("characters", characters::characters_dir()?),
+        ("knowledge", knowledge::knowledge_dir()?),
+    ])
+}
+
+fn scratch_file(app: &AppHandle, name: &str) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_cache_dir()
+        .map_err(|e| format!("Failed to resolve app cache dir: {}", e))?;
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    Ok(dir.join(format!("{}-{}", name, std::process::id())))
+}
+
+fn io_error(context: &Path, e: io::Error) -> String {
+    format!("{}: {}", context.display(), e)
+}
+
+// Copy `source` into the archive while hashing it
+fn add_file(
+    zip: &mut ZipWriter<BufWriter<File>>,
+    name: String,
+    source: &Path,
+) -> Result<ManifestEntry, String> {
+    zip.start_file(name.as_str(), SimpleFileOptions::default())
+        .map_err(|e| format!("Failed to add {}: {}", name, e))?;
+    let mut reader = File::open(source).map_err(|e| io_error(source, e))?;
+    let mut hasher = Sha256::new();
+    let mut buffer = [0u8; 64 * 1024];
+    let mut size = 0;
+    loop {
+        let read = reader.read(&mut buffer).map_err(|e| io_error(source, e))?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+        zip.write_all(&buffer[..read])
+            .map_err(|e| format!("Failed to add {}: {}", name, e))?;
+        size += read as u64;
+    }
+    Ok(ManifestEntry {
+        path: name,
+        size,
+        sha256: format!("{:x}", hasher.finalize()),
+    })
+}
+
+fn add_dir(
+    zip: &mut ZipWriter<BufWriter<File>>,
+    root_name: &str,
+    root: &Path,
+    entries: &mut Vec<ManifestEntry>,
+) -> Result<(), String> {
+    let mut pending = vec![root.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let Ok(children) = fs::read_dir(&dir) else {
+            continue;
+        };
+        for path in children.filter_map(Result::ok).map(|entry| entry.path()) {
+            if path.is_dir() {
+                pending.push(path);
+                continue;
+            }
+            let relative = path.strip_prefix(root).map_err(|e| e.to_string())?;
+            let name = format!(
+                "{}/{}",
+                root_name,
+                relative.to_string_lossy().replace('\\', "/")
+            );
+            entries.push(add_file(zip, name, &path)?);
+        }
+    }
+    Ok(())
+}
+
+fn write_archive(app: &AppHandle, archive: &Path) -> Result<Manifest, String> {
+    let file = File::create(archive).map_err(|e| io_error(archive, e))?;
+    let mut zip = ZipWriter::new(BufWriter::new(file));
+    let mut entries = Vec::new();
+
+    for (name, dir) in roots()? {
+        add_dir(&mut zip, name, &dir, &mut entries)?;
+    }
+    let settings_file = settings::settings_path(app)?;
+    if settings_file.is_file() {
+        entries.push(add_file(
+            &mut zip,
+            SETTINGS_ENTRY.to_string(),
+            &settings_file,
+        )?);
+    }
+
+    let manifest = Manifest {
+        format_version: FORMAT_VERSION,
+        app_version: app.package_info().version.to_string(),
+        created_at: unix_now(),
+        entries,
+    };
+    zip.start_file(MANIFEST_FILE, SimpleFileOptions::default())
+        .map_err(|e| format!("Failed to add the manifest: {}", e))?;
+    serde_json::to_writer_pretty(&mut zip, &manifest).map_err(|e| e.to_string())?;
+    zip.finish()
+        .map_err(|e| format!("Failed to finish the archive: {}", e))?
+        .flush()
+        .map_err(|e| io_error(archive, e))?;
+    Ok(manifest)
+}
+
+fn encrypt(source: &Path, destination: &Path, passphrase: SecretString) -> Result<(), String> {
+    let mut input = BufReader::new(File::open(source).map_err(|e| io_error(source, e))?);
+    let output = BufWriter::new(File::create(destination).map_err(|e| io_error(destination, e))?);
+    let mut writer = age::Encryptor::with_user_passphrase(passphrase)
+        .wrap_output(output)
+        .map_err(|e| format!("Failed to encrypt the backup: {}", e))?;
+    io::copy(&mut input, &mut writer).map_err(|e| io_error(destination, e))?;
+    writer
+        .finish()
+        .and_then(|mut output| output.flush())
+        .map_err(|e| io_error(destination, e))
+}
+
+fn decrypt(source: &Path, destination: &Path, passphrase: SecretString) -> Result<(), String> {
+    let input = BufReader::new(File::open(source).map_err(|e| io_error(source, e))?);
+    let decryptor =
+        age::Decryptor::new_buffered(input).map_err(|e| format!("Not a valid backup: {}", e))?;
+    let identity = age::scrypt::Identity::new(passphrase);
+    let mut reader = decryptor
+        .decrypt(iter::once(&identity as &dyn age::Identity))
+        .map_err(|_| "Incorrect passphrase or corrupted backup".to_string())?;
+    let mut output = File::create(destination).map_err(|e| io_error(destination, e))?;
+    // age authenticates every chunk, so a tampered archive fails here
+    io::copy(&mut reader, &mut output).map_err(|e| format!("The backup is corrupted: {}", e))?;
+    Ok(())
+}
+
+// Archive entries must stay inside their root
+fn is_safe_entry(path: &str) -> bool {
+    Path::new(path)
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)))
+}
+
+fn read_manifest(zip: &mut ZipArchive<File>) -> Result<Manifest, String> {
+    let entry = zip
+        .by_name(MANIFEST_FILE)
+        .map_err(|_| "The backup has no manifest".to_string())?;
+    let manifest: Manifest =
+        serde_json::from_reader(entry).map_err(|e| format!("Invalid backup manifest: {}", e))?;
+    if manifest.format_version > FORMAT_VERSION {
+        return Err(format!(
+            "The backup was made by a newer version of the app (format {})",
+            manifest.format_version
+        ));
+    }
+    Ok(manifest)
+}
+
+// Check every file against the manifest before anything on disk is touched
+fn verify(zip: &mut ZipArchive<File>, manifest: &Manifest) -> Result<(), String> {
+    for expected in &manifest.entries {
+        if !is_safe_entry(&expected.path) {
+            return Err(format!("Refusing unsafe path in backup: {}", expected.path));
+        }
+        let mut entry = zip
+            .by_name(&expected.path)
+            .map_err(|_| format!("The backup is missing {}", expected.path))?;
+        let mut hasher = Sha256::new();
+        let size = io::copy(&mut entry, &mut hasher)
+            .map_err(|e| format!("Failed to read {}: {}", expected.path, e))?;
+        if size != expected.size || format!("{:x}", hasher.finalize()) != expected.sha256 {
+            return Err(format!("Checksum mismatch for {}", expected.path));
+        }
+    }
+    Ok(())
+}
+
+fn extract(zip: &mut ZipArchive<File>, name: &str, target: &Path) -> Result<(), String> {
+    if let Some(dir) = target.parent() {
+        fs::create_dir_all(dir).map_err(|e| io_error(dir, e))?;
+    }
+    let mut entry = zip
+        .by_name(name)
+        .map_err(|e| format!("Failed to read {}: {}", name, e))?;
+    let mut output = File::create(target).map_err(|e| io_error(target, e))?;
+    io::copy(&mut entry, &mut output).map_err(|e| io_error(target, e))?;
+    Ok(())
+}
+
+fn suffixed(path: &Path, suffix: &str) -> PathBuf {
+    let mut path = path.as_os_str().to_owned();
+    path.push(format!(".{}", suffix));
+    PathBuf::from(path)
+}
+
+fn remove(path: &Path) -> Result<(), String> {
+    if path.is_dir() {
+        fs::remove_dir_all(path).map_err(|e| io_error(path, e))
+    } else if path.exists() {
+        fs::remove_file(path).map_err(|e| io_error(path, e))
+    } else {
+        Ok(())
+    }
+}
+
+// Returns whether there was anything to move
+fn move_aside(path: &Path) -> Result<bool, String> {
+    if !path.exists() {
+        return Ok(false);
+    }
+    let aside = suffixed(path, PRE_RESTORE_SUFFIX);
+    remove(&aside)?;
+    fs::rename(path, &aside).map_err(|e| io_error(path, e))?;
+    Ok(true)
+}
+
+// A target restored from the backup, extracted next to it before anything is replaced
+struct Staged {
+    target: PathBuf,
+    staging: PathBuf,
+}
+
+fn stage(
+    zip: &mut ZipArchive<File>,
+    target: PathBuf,
+    files: &[(&str, PathBuf)],
+) -> Result<Staged, String> {
+    let staging = suffixed(&target, STAGING_SUFFIX);
+    remove(&staging)?;
+    let staged = Staged { target, staging };
+    for (name, relative) in files {
+        let path = match relative.as_os_str().is_empty() {
+            true => staged.staging.clone(),
+            false => staged.staging.join(relative),
+        };
+        if let Err(e) = extract(zip, name, &path) {
+            let _ = remove(&staged.staging);
+            return Err(e);
+        }
+    }
+    Ok(staged)
+}
+
+// Put back what `swap` replaced, newest first
+fn roll_back(swapped: &[(&Staged, bool)]) {
+    for (staged, moved) in swapped.iter().rev() {
+        let _ = remove(&staged.target);
+        if *moved {
+            let aside = suffixed(&staged.target, PRE_RESTORE_SUFFIX);
+            if let Err(e) = fs::rename(&aside, &staged.target) {
+                tracing::error!(path = %staged.target.display(), "Failed to roll back a restore: {}", e);
+            }
+        }
+    }
+}
+
+fn swap(staged: &[Staged]) -> Result<(), String> {
+    let mut swapped = Vec::with_capacity(staged.len());
+    for next in staged {
+        let result = move_aside(&next.target).and_then(|moved| {
+            swapped.push((next, moved));
+            fs::rename(&next.staging, &next.target).map_err(|e| io_error(&next.target, e))
+        });
+        if let Err(e) = result {
+            roll_back(&swapped);
+            return Err(e);
+        }
+    }
+    Ok(())
+}
+
+fn restore_archive(app: &AppHandle, archive: &Path) -> Result<Manifest, String> {
+    let file = File::open(archive).map_err(|e| io_error(archive, e))?;
+    let mut zip = ZipArchive::new(file).map_err(|e| format!("Not a valid backup: {}", e))?;
+    let manifest = read_manifest(&mut zip)?;
+    verify(&mut zip, &manifest)?;
+
+    let mut targets = Vec::new();
+    for (name, dir) in roots()? {
+        let prefix = format!("{}/", name);
+        let files = manifest
+            .entries
+            .iter()
+            .filter_map(|entry| {
+                let relative = entry.path.strip_prefix(&prefix)?;
+                Some((entry.path.as_str(), PathBuf::from(relative)))
+            })
+            .collect::<Vec<_>>();
+        if !files.is_empty() {
+            targets.push((dir, files));
+        }
+    }
+    let restores_settings = manifest
+        .entries
+        .iter()
+        .any(|entry| entry.path == SETTINGS_ENTRY);
+    if restores_settings {
+        let files = vec![(SETTINGS_ENTRY, PathBuf::new())];
+        targets.push((settings::settings_path(app)?, files));
+    }
+
+    // Extract everything first, so a bad entry leaves the current data untouched
+    let mut staged = Vec::with_capacity(targets.len());
+    let result = targets
+        .into_iter()
+        .try_for_each(|(target, files)| {
+            staged.push(stage(&mut zip, target, &files)?);
+            Ok(())
+        })
+        .and_then(|()| swap(&staged));
+    for next in &staged {
+        let _ = remove(&next.staging);
+    }
+    result?;
+
+    if restores_settings {
+        settings::init(app);
+    }
+    Ok(manifest)
+}
+
+fn info(path: PathBuf, manifest: &Manifest) -> BackupInfo {
+    BackupInfo {
+        size: fs::metadata(&path).map(|m| m.len()).unwrap_or_default(),
+        path,
+        app_version: manifest.app_version.clone(),
+        created_at: manifest.created_at,
+        entries: manifest.entries.len(),
+    }
+}
+
+fn write_backup(app: &AppHandle, path: &Path, passphrase: String) -> Result<BackupInfo, String> {
+    if passphrase.is_empty() {
+        return Err("A passphrase is required".to_string());
+    }
+    let archive = scratch_file(app, "backup.zip")?;
+    let result = write_archive(app, &archive).and_then(|manifest| {
+        encrypt(&archive, path, SecretString::from(passphrase)).map(|_| manifest)
+    });
+    let _ = fs::remove_file(&archive);
+    let manifest = result?;
+    tracing::info!(path = %path.display(), entries = manifest.entries.len(), "Created backup");
+    Ok(info(path.to_path_buf(), &manifest))
+}
+
+// Archive the agent database, characters, knowledge and settings, encrypted with `passphrase`
+pub fn create(app: &AppHandle, path: &Path, passphrase: String) -> Result<BackupInfo, String> {
+    let result = write_backup(app, path, passphrase);
+    audit::record(
+        AuditAction::BackupExport,
+        &path.display().to_string(),
+        &result,
+    );
+    result
+}
+
+#[tauri::command]
+pub async fn create_backup(
+    app: AppHandle,
+    path: PathBuf,
+    passphrase: String,
+) -> Result<BackupInfo, AppError> {
+    capabilities::require(Capability::ExportData)?;
+    verification::verify(&app, Action::ExportBackup)
+        .await
+        .map_err(AppError::Auth)?;
+    tauri::async_runtime::spawn_blocking(move || create(&app, &path, passphrase))
+        .await?
+        .map_err(AppError::Io)
+}
+
+// Replace the current data with a verified backup. The data it replaces is kept next to
+// the originals with a `.before-restore` suffix, and put back if the restore fails. The
+// restored settings choose which workspace and arguments the server runs with, so this
+// needs RunPrograms as well as file access.
+#[tauri::command]
+pub async fn restore_backup(
+    app: AppHandle,
+    path: PathBuf,
+    passphrase: String,
+) -> Result<BackupInfo, AppError> {
+    capabilities::require(Capability::ExportData)?;
+    capabilities::require(Capability::RunPrograms)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        if server::status() != ServerStatus::Stopped {
+            return Err(AppError::ServerLifecycle(
+                "Stop the elizaOS server before restoring a backup".to_string(),
+            ));
+        }
+        let archive = scratch_file(&app, "restore.zip")?;
+        let result = decrypt(&path, &archive, SecretString::from(passphrase))
+            .and_then(|()| restore_archive(&app, &archive));
+        let _ = fs::remove_file(&archive);
+        let manifest = result.map_err(AppError::Io)?;
+        tracing::info!(path = %path.display(), "Restored backup");
+        Ok(info(path, &manifest))
+    })
+    .await?
+}
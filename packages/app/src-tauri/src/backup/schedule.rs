@@ -0,0 +1,198 @@
+use std::fs;
+use std::path::PathBuf;
+use std::thread;
+use std::time::{Duration, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::auth::secure_store;
+use crate::auth::unix_now;
+use crate::error::AppError;
+use crate::server::{self, metrics, ServerStatus};
+use crate::settings;
+
+const BACKUPS_DIR: &str = "backups";
+const BACKUP_EXTENSION: &str = "elizabackup";
+const PASSPHRASE_ENTRY: &str = "backup-passphrase";
+const CHECK_INTERVAL: Duration = Duration::from_secs(5 * 60);
+// A running server using less CPU than this (percent of one core) counts as idle
+const IDLE_CPU_PERCENT: f32 = 10.0;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BackupSchedule {
+    pub enabled: bool,
+    pub interval_hours: u64,
+    // Number of automatic backups kept; older ones are deleted
+    pub retention: usize,
+}
+
+impl Default for BackupSchedule {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_hours: 24,
+            retention: 7,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BackupFile {
+    pub name: String,
+    pub path: PathBuf,
+    // Seconds since the Unix epoch
+    pub created_at: u64,
+    pub size: u64,
+}
+
+fn backups_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_data_dir()
+        .map(|dir| dir.join(BACKUPS_DIR))
+        .map_err(|e| format!("Failed to resolve app data dir: {}", e))
+}
+
+fn load_passphrase() -> Result<Option<String>, String> {
+    secure_store::read_blocking(PASSPHRASE_ENTRY)
+        .map_err(|e| format!("Failed to read the backup passphrase: {}", e))
+}
+
+// Automatic backups, newest first
+fn backups(app: &AppHandle) -> Result<Vec<BackupFile>, String> {
+    let dir = backups_dir(app)?;
+    let Ok(entries) = fs::read_dir(&dir) else {
+        return Ok(Vec::new());
+    };
+    let mut backups = entries
+        .filter_map(Result::ok)
+        .filter(|entry| {
+            entry.path().extension().and_then(|ext| ext.to_str()) == Some(BACKUP_EXTENSION)
+        })
+        .filter_map(|entry| {
+            let metadata = entry.metadata().ok()?;
+            let created_at = metadata
+                .modified()
+                .ok()?
+                .duration_since(UNIX_EPOCH)
+                .ok()?
+                .as_secs();
+            Some(BackupFile {
+                name: entry.file_name().to_string_lossy().into_owned(),
+                path: entry.path(),
+                created_at,
+                size: metadata.len(),
+            })
+        })
+        .collect::<Vec<_>>();
+    backups.sort_by_key(|backup| std::cmp::Reverse(backup.created_at));
+    Ok(backups)
+}
+
+// Stopped, or running without doing much, so copying its database is safe enough
+fn server_is_idle() -> bool {
+    match server::status() {
+        ServerStatus::Stopped => true,
+        ServerStatus::Running => {
+            metrics::latest().is_some_and(|sample| sample.cpu_percent < IDLE_CPU_PERCENT)
+        }
+        ServerStatus::External => false,
+    }
+}
+
+fn is_due(app: &AppHandle, schedule: &BackupSchedule) -> Result<bool, String> {
+    let interval = schedule.interval_hours.max(1) * 3600;
+    Ok(match backups(app)?.first() {
+        Some(latest) => unix_now().saturating_sub(latest.created_at) >= interval,
+        None => true,
+    })
+}
+
+fn run_scheduled(app: &AppHandle, schedule: &BackupSchedule) -> Result<(), String> {
+    let passphrase =
+        load_passphrase()?.ok_or("Set a backup passphrase to enable automatic backups")?;
+    let dir = backups_dir(app)?;
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+
+    let path = dir.join(format!("backup-{}.{}", unix_now(), BACKUP_EXTENSION));
+    let info = super::create(app, &path, passphrase)?;
+    let _ = app.emit("backup-completed", &info);
+
+    for old in backups(app)?.iter().skip(schedule.retention.max(1)) {
+        if let Err(e) = fs::remove_file(&old.path) {
+            tracing::warn!("Failed to delete old backup {}: {}", old.path.display(), e);
+        }
+    }
+    Ok(())
+}
+
+// Check periodically whether a backup is due and the server is idle enough to take one
+pub fn spawn(app: AppHandle) {
+    thread::spawn(move || loop {
+        let schedule = settings::current().backups;
+        if schedule.enabled && server_is_idle() {
+            let result = is_due(&app, &schedule).and_then(|due| {
+                if due {
+                    run_scheduled(&app, &schedule)
+                } else {
+                    Ok(())
+                }
+            });
+            if let Err(e) = result {
+                tracing::error!("Scheduled backup failed: {}", e);
+                let _ = app.emit("backup-failed", &e);
+            }
+        }
+        thread::sleep(CHECK_INTERVAL);
+    });
+}
+
+// Update the schedule; a passphrase, when given, is stored in the keychain for unattended runs
+#[tauri::command]
+pub async fn configure_backups(
+    app: AppHandle,
+    schedule: BackupSchedule,
+    passphrase: Option<String>,
+) -> Result<BackupSchedule, AppError> {
+    if let Some(passphrase) = passphrase {
+        if passphrase.is_empty() {
+            return Err(AppError::Validation("A passphrase is required".to_string()));
+        }
+        secure_store::write(PASSPHRASE_ENTRY, &passphrase)
+            .await
+            .map_err(|e| {
+                AppError::Keychain(format!("Failed to store the backup passphrase: {}", e))
+            })?;
+    }
+    if schedule.enabled
+        && secure_store::read(PASSPHRASE_ENTRY)
+            .await
+            .map_err(|e| {
+                AppError::Keychain(format!("Failed to read the backup passphrase: {}", e))
+            })?
+            .is_none()
+    {
+        return Err(AppError::Validation(
+            "Set a backup passphrase to enable automatic backups".to_string(),
+        ));
+    }
+    Ok(settings::update(&app, |settings| settings.backups = schedule)?.backups)
+}
+
+#[tauri::command]
+pub fn list_backups(app: AppHandle) -> Result<Vec<BackupFile>, AppError> {
+    backups(&app).map_err(AppError::Io)
+}
+
+// Delete an automatic backup by file name
+#[tauri::command]
+pub fn delete_backup(app: AppHandle, name: String) -> Result<(), AppError> {
+    let backup = backups(&app)
+        .map_err(AppError::Io)?
+        .into_iter()
+        .find(|backup| backup.name == name)
+        .ok_or_else(|| AppError::NotFound(format!("Unknown backup: {}", name)))?;
+    fs::remove_file(&backup.path)
+        .map_err(|e| AppError::Io(format!("Failed to delete {}: {}", backup.path.display(), e)))
+}
@@ -0,0 +1,203 @@
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::Serialize;
+use serde_json::json;
+
+use crate::error::AppError;
+use crate::history::{self, db_error, new_id, now_millis, ConversationSummary};
+use crate::server::chat;
+
+// Where a forked conversation came from
+#[derive(Debug, Clone, Serialize)]
+pub struct Branch {
+    pub conversation: ConversationSummary,
+    pub parent_id: String,
+    // Last message copied from the parent; the fork's own messages follow it
+    pub fork_message_id: String,
+    pub created_at: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ForkOrigin {
+    pub parent_id: String,
+    // `None` once the parent has been deleted
+    pub parent: Option<ConversationSummary>,
+    pub fork_message_id: String,
+    pub created_at: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ConversationBranches {
+    // Set when the conversation is itself a fork
+    pub forked_from: Option<ForkOrigin>,
+    pub branches: Vec<Branch>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ForkResult {
+    pub branch: Branch,
+    // Whether the server accepted the new room; it's created on the first message otherwise
+    pub server_notified: bool,
+}
+
+fn branch_from_row(conn: &Connection, row: &Row) -> rusqlite::Result<Branch> {
+    let conversation_id: String = row.get("conversation_id")?;
+    let conversation = history::load_summary(conn, &conversation_id)
+        .ok()
+        .flatten()
+        .ok_or(rusqlite::Error::QueryReturnedNoRows)?;
+    Ok(Branch {
+        conversation,
+        parent_id: row.get("parent_id")?,
+        fork_message_id: row.get("fork_message_id")?,
+        created_at: row.get::<_, i64>("created_at")? as u64,
+    })
+}
+
+fn load_branch(conn: &Connection, conversation_id: &str) -> Result<Option<Branch>, String> {
+    conn.query_row(
+        "SELECT * FROM conversation_branches WHERE conversation_id = ?1",
+        [conversation_id],
+        |row| branch_from_row(conn, row),
+    )
+    .optional()
+    .map_err(db_error)
+}
+
+fn load_origin(conn: &Connection, conversation_id: &str) -> Result<Option<ForkOrigin>, String> {
+    let Some(branch) = load_branch(conn, conversation_id)? else {
+        return Ok(None);
+    };
+    Ok(Some(ForkOrigin {
+        parent: history::load_summary(conn, &branch.parent_id)?,
+        parent_id: branch.parent_id,
+        fork_message_id: branch.fork_message_id,
+        created_at: branch.created_at,
+    }))
+}
+
+fn load_children(conn: &Connection, parent_id: &str) -> Result<Vec<Branch>, String> {
+    let mut statement = conn
+        .prepare(
+            "SELECT * FROM conversation_branches WHERE parent_id = ?1 ORDER BY created_at, rowid",
+        )
+        .map_err(db_error)?;
+    let branches = statement
+        .query_map([parent_id], |row| branch_from_row(conn, row))
+        .map_err(db_error)?
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .map_err(db_error)?;
+    Ok(branches)
+}
+
+// Copy `parent_id` up to and including `message_id` into a new conversation
+fn fork(
+    conn: &mut Connection,
+    parent_id: &str,
+    message_id: &str,
+    title: Option<String>,
+) -> Result<Branch, String> {
+    let parent = history::load_summary(conn, parent_id)?
+        .ok_or_else(|| format!("Unknown conversation: {}", parent_id))?;
+    let mut copied = Vec::new();
+    let mut found = false;
+    history::for_each_message(conn, parent_id, |message| {
+        if !found {
+            found = message.id == message_id;
+            copied.push(message.id);
+        }
+        Ok(())
+    })?;
+    if !found {
+        return Err(format!(
+            "Message {} is not part of conversation {}",
+            message_id, parent_id
+        ));
+    }
+
+    let id = new_id();
+    let now = now_millis();
+    let title = title.filter(|title| !title.trim().is_empty()).or_else(|| {
+        parent
+            .title
+            .as_ref()
+            .map(|title| format!("{} (branch)", title))
+    });
+    let tx = conn.transaction().map_err(db_error)?;
+    tx.execute(
+        "INSERT INTO conversations (id, title, agent_id, created_at, updated_at)
+            VALUES (?1, ?2, ?3, ?4, ?4)",
+        params![id, title, parent.agent_id, now as i64],
+    )
+    .map_err(db_error)?;
+    // Message ids are unique across conversations, so each copy gets a new one
+    for message in &copied {
+        tx.execute(
+            "INSERT INTO messages (id, conversation_id, role, sender, content, created_at, metadata)
+                SELECT ?1, ?2, role, sender, content, created_at, metadata
+                FROM messages WHERE id = ?3",
+            params![new_id(), id, message],
+        )
+        .map_err(db_error)?;
+    }
+    tx.execute(
+        "INSERT INTO conversation_branches (conversation_id, parent_id, fork_message_id, created_at)
+            VALUES (?1, ?2, ?3, ?4)",
+        params![id, parent_id, message_id, now as i64],
+    )
+    .map_err(db_error)?;
+    tx.commit().map_err(db_error)?;
+
+    load_branch(conn, &id)?.ok_or_else(|| format!("Unknown conversation: {}", id))
+}
+
+// Start a new conversation from the history of another one up to `message_id`, to take it
+// somewhere else from there. The parent is left as it is.
+#[tauri::command]
+pub async fn fork_conversation(
+    conversation_id: String,
+    message_id: String,
+    title: Option<String>,
+) -> Result<ForkResult, AppError> {
+    let parent_id = conversation_id.clone();
+    let branch = history::with_db(move |conn| fork(conn, &conversation_id, &message_id, title))
+        .await
+        .map_err(AppError::Database)?;
+    crate::embeddings::wake();
+    tracing::info!(
+        conversation_id = %branch.conversation.id,
+        parent_id = %parent_id,
+        "Forked a conversation"
+    );
+
+    // The server keys conversations by room; an offline server just won't know the lineage
+    let notified = chat::create_room(
+        branch.conversation.agent_id.clone(),
+        &branch.conversation.id,
+        branch.conversation.title.as_deref().unwrap_or("Branch"),
+        json!({
+            "forkedFrom": branch.parent_id,
+            "forkedAtMessage": branch.fork_message_id,
+        }),
+    )
+    .await;
+    if let Err(e) = &notified {
+        tracing::warn!("{}", e);
+    }
+    Ok(ForkResult {
+        branch,
+        server_notified: notified.is_ok(),
+    })
+}
+
+// The forks of a conversation, oldest first, and the conversation it was forked from
+#[tauri::command]
+pub async fn list_branches(conversation_id: String) -> Result<ConversationBranches, AppError> {
+    history::with_db(move |conn| {
+        Ok(ConversationBranches {
+            forked_from: load_origin(conn, &conversation_id)?,
+            branches: load_children(conn, &conversation_id)?,
+        })
+    })
+    .await
+    .map_err(AppError::Database)
+}
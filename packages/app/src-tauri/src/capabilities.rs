@@ -0,0 +1,140 @@
+use std::collections::BTreeSet;
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter};
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+
+use crate::error::AppError;
+use crate::i18n::{t, t_args};
+
+const MAX_REASON_LEN: usize = 120;
+
+// Groups of sensitive commands the frontend has to be granted before it may call them, so a
+// compromised webview can't quietly read keys, run programs or write files
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Capability {
+    // Reading stored API keys, `.env` values or sign-in tokens back out
+    ReadSecrets,
+    // Installing packages, or changing which program the app runs and with what arguments
+    RunPrograms,
+    // Reading or writing files at a path chosen by the frontend, e.g. exporting
+    // conversations or importing documents
+    ExportData,
+    // Letting other devices on the network reach the agent
+    RemoteAccess,
+    // Sending chats and keys to an elizaOS server on another machine
+    RemoteServers,
+}
+
+impl Capability {
+    // Completes "Eliza Desktop wants to ..." in the confirmation dialog
+    fn description(self) -> String {
+        t(match self {
+            Capability::ReadSecrets => "capability-read-secrets",
+            Capability::RunPrograms => "capability-run-programs",
+            Capability::ExportData => "capability-export-data",
+            Capability::RemoteAccess => "capability-remote-access",
+            Capability::RemoteServers => "capability-remote-servers",
+        })
+    }
+}
+
+// Payload of the `capabilities-changed` event
+#[derive(Debug, Clone, Serialize)]
+struct GrantsChanged {
+    granted: Vec<Capability>,
+}
+
+// Grants are only kept in memory, so every launch starts without any
+static GRANTED: Mutex<BTreeSet<Capability>> = Mutex::new(BTreeSet::new());
+
+fn granted() -> Vec<Capability> {
+    GRANTED.lock().unwrap().iter().copied().collect()
+}
+
+fn emit(app: &AppHandle) {
+    let payload = GrantsChanged { granted: granted() };
+    if let Err(e) = app.emit("capabilities-changed", payload) {
+        tracing::warn!("Failed to emit capability change: {}", e);
+    }
+}
+
+// Fail unless the user granted `capability` this session; called first by gated commands
+pub fn require(capability: Capability) -> Result<(), AppError> {
+    if GRANTED.lock().unwrap().contains(&capability) {
+        return Ok(());
+    }
+    Err(AppError::PermissionDenied(t_args(
+        "capability-required",
+        &[("capability", &capability.description())],
+    )))
+}
+
+// Ask the user to allow `capability` for the rest of the session. `reason` is shown in the
+// dialog as the frontend's explanation; returns the capabilities granted so far.
+#[tauri::command]
+pub async fn request_capability(
+    app: AppHandle,
+    capability: Capability,
+    reason: Option<String>,
+) -> Result<Vec<Capability>, AppError> {
+    if require(capability).is_ok() {
+        return Ok(granted());
+    }
+    let reason = reason
+        .map(|reason| reason.trim().to_string())
+        .filter(|reason| !reason.is_empty());
+    if reason
+        .as_ref()
+        .is_some_and(|reason| reason.len() > MAX_REASON_LEN)
+    {
+        return Err(AppError::Validation("The reason is too long".to_string()));
+    }
+
+    let handle = app.clone();
+    let allowed = tauri::async_runtime::spawn_blocking(move || {
+        let description = capability.description();
+        let mut message = t_args("capability-prompt", &[("capability", &description)]);
+        if let Some(reason) = &reason {
+            message.push_str("\n\n");
+            message.push_str(&t_args("capability-prompt-reason", &[("reason", reason)]));
+        }
+        handle
+            .dialog()
+            .message(message)
+            .title(t("capability-prompt-title"))
+            .kind(MessageDialogKind::Warning)
+            .buttons(MessageDialogButtons::OkCancelCustom(
+                t("capability-allow"),
+                t("capability-deny"),
+            ))
+            .blocking_show()
+    })
+    .await?;
+
+    if !allowed {
+        tracing::info!(?capability, "Capability denied");
+        return Err(AppError::PermissionDenied(t("capability-denied")));
+    }
+    tracing::info!(?capability, "Capability granted for this session");
+    GRANTED.lock().unwrap().insert(capability);
+    emit(&app);
+    Ok(granted())
+}
+
+#[tauri::command]
+pub fn list_capability_grants() -> Vec<Capability> {
+    granted()
+}
+
+// Give up a grant early, e.g. once the view that needed it closes
+#[tauri::command]
+pub fn revoke_capability(app: AppHandle, capability: Capability) -> Vec<Capability> {
+    if GRANTED.lock().unwrap().remove(&capability) {
+        tracing::info!(?capability, "Capability revoked");
+        emit(&app);
+    }
+    granted()
+}
@@ -0,0 +1,210 @@
+use std::fs;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::{AppHandle, Emitter, Url};
+
+use super::{character_path, characters_dir, list_characters, validate, CharacterSummary};
+use crate::downloads::fetch_bytes;
+use crate::error::AppError;
+use crate::plugins;
+
+// Index of community characters: `{ "characters": [{ "name", "url", ... }] }`
+const REGISTRY_URL: &str =
+    "https://raw.githubusercontent.com/elizaOS/characters/main/registry.json";
+const MAX_CHARACTER_BYTES: usize = 512 * 1024;
+const MAX_REGISTRY_BYTES: usize = 4 * 1024 * 1024;
+const MAX_DEPTH: usize = 32;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegistryCharacter {
+    pub name: String,
+    pub url: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub author: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    // A character with this name is already in the workspace
+    #[serde(default)]
+    pub installed: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct Registry {
+    characters: Vec<RegistryCharacter>,
+}
+
+// Payload of the `character-imported` event
+#[derive(Debug, Clone, Serialize)]
+pub struct CharacterImported {
+    #[serde(flatten)]
+    pub character: CharacterSummary,
+    pub url: String,
+}
+
+fn parse_url(url: &str) -> Result<Url, AppError> {
+    let url = Url::parse(url.trim())
+        .map_err(|e| AppError::Validation(format!("Invalid URL {}: {}", url, e)))?;
+    if url.scheme() != "https" {
+        return Err(AppError::Validation(
+            "Characters can only be imported over https".to_string(),
+        ));
+    }
+    Ok(url)
+}
+
+fn depth(value: &Value) -> usize {
+    match value {
+        Value::Array(items) => 1 + items.iter().map(depth).max().unwrap_or(0),
+        Value::Object(fields) => 1 + fields.values().map(depth).max().unwrap_or(0),
+        _ => 0,
+    }
+}
+
+// Beyond the schema, refuse anything that would make the server load code from elsewhere:
+// plugins must be registry packages, not paths, URLs or git remotes
+pub(crate) fn check_untrusted(character: &Value) -> Result<(), String> {
+    if depth(character) > MAX_DEPTH {
+        return Err("The character is nested too deeply".to_string());
+    }
+    let plugins = character
+        .get("plugins")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten();
+    for plugin in plugins {
+        let name = plugin.as_str().unwrap_or_default();
+        plugins::validate_name(name)
+            .map_err(|e| format!("Unsupported plugin in the character: {}", e))?;
+    }
+    Ok(())
+}
+
+// Lowercase ASCII words joined by dashes, usable as a file or package name
+pub(crate) fn slug(name: &str) -> String {
+    let stem: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    let mut stem = stem
+        .split('-')
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+    stem.truncate(64);
+    stem.trim_end_matches('-').to_string()
+}
+
+// A file name from the character's name that doesn't clash with an existing character
+fn unique_file(name: &str) -> Result<String, String> {
+    let mut stem = slug(name);
+    if stem.is_empty() {
+        stem = "character".to_string();
+    }
+    let mut file = format!("{}.json", stem);
+    let mut n = 2;
+    while character_path(&file)?.exists() {
+        file = format!("{}-{}.json", stem, n);
+        n += 1;
+    }
+    Ok(file)
+}
+
+// Download a character JSON file and add it to the workspace once it passes validation
+#[tauri::command]
+pub async fn import_character_from_url(
+    app: AppHandle,
+    url: String,
+) -> Result<CharacterSummary, AppError> {
+    let url = parse_url(&url)?;
+    let body = fetch_bytes(url.clone(), MAX_CHARACTER_BYTES).await?;
+    let character: Value = serde_json::from_slice(&body)
+        .map_err(|e| AppError::Validation(format!("{} is not valid JSON: {}", url, e)))?;
+    let issues = validate(&character);
+    if let Some(first) = issues.first() {
+        return Err(AppError::Validation(format!(
+            "Character is invalid ({} issue(s)): {}",
+            issues.len(),
+            first.message
+        )));
+    }
+    check_untrusted(&character).map_err(AppError::Validation)?;
+
+    let name = character
+        .get("name")
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string();
+    let summary = tauri::async_runtime::spawn_blocking(move || {
+        let dir = characters_dir()?;
+        fs::create_dir_all(&dir)
+            .map_err(|e| AppError::Io(format!("Failed to create {}: {}", dir.display(), e)))?;
+        let file = unique_file(&name)?;
+        let path = character_path(&file)?;
+        let contents = serde_json::to_string_pretty(&character).map_err(|e| e.to_string())?;
+        fs::write(&path, contents)
+            .map_err(|e| AppError::Io(format!("Failed to write {}: {}", path.display(), e)))?;
+        Ok::<_, AppError>(CharacterSummary {
+            file,
+            name: Some(name),
+            path,
+        })
+    })
+    .await??;
+
+    tracing::info!(file = %summary.file, %url, "Imported character");
+    let payload = CharacterImported {
+        character: summary.clone(),
+        url: url.to_string(),
+    };
+    if let Err(e) = app.emit("character-imported", payload) {
+        tracing::warn!("Failed to emit character import: {}", e);
+    }
+    Ok(summary)
+}
+
+// Community characters whose name, description, author or tags contain `query`
+#[tauri::command]
+pub async fn browse_character_registry(query: String) -> Result<Vec<RegistryCharacter>, AppError> {
+    let url = Url::parse(REGISTRY_URL).map_err(|e| e.to_string())?;
+    let body = fetch_bytes(url, MAX_REGISTRY_BYTES).await?;
+    let registry: Registry = serde_json::from_slice(&body)
+        .map_err(|e| AppError::Network(format!("Invalid character registry: {}", e)))?;
+
+    let installed: Vec<String> = tauri::async_runtime::spawn_blocking(list_characters)
+        .await?
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|character| character.name)
+        .collect();
+    let query = query.trim().to_lowercase();
+    Ok(registry
+        .characters
+        .into_iter()
+        .filter(|character| parse_url(&character.url).is_ok())
+        .filter(|character| {
+            query.is_empty()
+                || [
+                    Some(&character.name),
+                    character.description.as_ref(),
+                    character.author.as_ref(),
+                ]
+                .into_iter()
+                .flatten()
+                .chain(&character.tags)
+                .any(|text| text.to_lowercase().contains(&query))
+        })
+        .map(|mut character| {
+            character.installed = installed.contains(&character.name);
+            character
+        })
+        .collect())
+}
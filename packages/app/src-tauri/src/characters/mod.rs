@@ -0,0 +1,155 @@
+use std::fs;
+use std::path::PathBuf;
+
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::error::AppError;
+use crate::workspace;
+
+pub mod import;
+
+const CHARACTERS_DIR: &str = "characters";
+
+static CHARACTER_SCHEMA: Lazy<jsonschema::Validator> = Lazy::new(|| {
+    let schema: Value = serde_json::from_str(include_str!("character.schema.json"))
+        .expect("character schema is valid JSON");
+    jsonschema::validator_for(&schema).expect("character schema is a valid JSON schema")
+});
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CharacterSummary {
+    pub file: String,
+    pub name: Option<String>,
+    pub path: PathBuf,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ValidationIssue {
+    // JSON pointer to the offending value, empty for the document root
+    pub path: String,
+    pub message: String,
+}
+
+pub fn characters_dir() -> Result<PathBuf, String> {
+    Ok(workspace::dir()?.join(CHARACTERS_DIR))
+}
+
+// Resolve a bare file name inside the characters dir, refusing anything that could escape it
+fn character_path(file: &str) -> Result<PathBuf, String> {
+    if file.is_empty() || file.contains(['/', '\\']) || file.starts_with('.') {
+        return Err(format!("Invalid character file name: {}", file));
+    }
+    let file = if file.ends_with(".json") {
+        file.to_string()
+    } else {
+        format!("{}.json", file)
+    };
+    Ok(characters_dir()?.join(file))
+}
+
+// Resolve a character given either as a file in the characters dir or as a path
+pub fn resolve(character: &str) -> Result<PathBuf, String> {
+    let path = if character.contains(['/', '\\']) {
+        workspace::dir()?.join(character)
+    } else {
+        character_path(character)?
+    };
+    if !path.is_file() {
+        return Err(format!("Character file not found: {}", path.display()));
+    }
+    Ok(path)
+}
+
+pub fn validate(character: &Value) -> Vec<ValidationIssue> {
+    CHARACTER_SCHEMA
+        .iter_errors(character)
+        .map(|error| ValidationIssue {
+            path: error.instance_path.to_string(),
+            message: error.to_string(),
+        })
+        .collect()
+}
+
+#[tauri::command]
+pub fn list_characters() -> Result<Vec<CharacterSummary>, AppError> {
+    let dir = characters_dir()?;
+    let entries = match fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => {
+            return Err(AppError::Io(format!(
+                "Failed to read {}: {}",
+                dir.display(),
+                e
+            )))
+        }
+    };
+
+    let mut characters: Vec<CharacterSummary> = entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .map(|path| {
+            let name = fs::read_to_string(&path)
+                .ok()
+                .and_then(|contents| serde_json::from_str::<Value>(&contents).ok())
+                .and_then(|value| value.get("name")?.as_str().map(str::to_string));
+            CharacterSummary {
+                file: path
+                    .file_name()
+                    .map(|f| f.to_string_lossy().into_owned())
+                    .unwrap_or_default(),
+                name,
+                path,
+            }
+        })
+        .collect();
+    characters.sort_by(|a, b| a.file.cmp(&b.file));
+    Ok(characters)
+}
+
+#[tauri::command]
+pub fn read_character(file: String) -> Result<Value, AppError> {
+    let path = character_path(&file)?;
+    let contents = fs::read_to_string(&path)
+        .map_err(|e| AppError::Io(format!("Failed to read {}: {}", path.display(), e)))?;
+    serde_json::from_str(&contents)
+        .map_err(|e| AppError::Validation(format!("Invalid JSON in {}: {}", file, e)))
+}
+
+#[tauri::command]
+pub fn validate_character(character: Value) -> Vec<ValidationIssue> {
+    validate(&character)
+}
+
+// Validate and write a character, creating the characters dir if needed
+#[tauri::command]
+pub fn save_character(file: String, character: Value) -> Result<PathBuf, AppError> {
+    let issues = validate(&character);
+    if let Some(first) = issues.first() {
+        return Err(AppError::Validation(format!(
+            "Character is invalid ({} issue(s)): {}",
+            issues.len(),
+            first.message
+        )));
+    }
+
+    let path = character_path(&file)?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .map_err(|e| AppError::Io(format!("Failed to create {}: {}", dir.display(), e)))?;
+    }
+    let contents = serde_json::to_string_pretty(&character).map_err(|e| e.to_string())?;
+    fs::write(&path, contents)
+        .map_err(|e| AppError::Io(format!("Failed to write {}: {}", path.display(), e)))?;
+    Ok(path)
+}
+
+#[tauri::command]
+pub fn delete_character(file: String) -> Result<(), AppError> {
+    let path = character_path(&file)?;
+    fs::remove_file(&path)
+        .map_err(|e| AppError::Io(format!("Failed to delete {}: {}", path.display(), e)))
+}
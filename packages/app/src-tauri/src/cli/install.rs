@@ -0,0 +1,214 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager, Url};
+
+use super::path::find_tool;
+use crate::capabilities::{self, Capability};
+use crate::downloads::{self, Checksum, Download};
+use crate::error::AppError;
+use crate::server::{self, config};
+
+// Matches the @elizaos/cli version the app is developed against
+pub const CLI_VERSION: &str = "1.0.6";
+pub(super) const REGISTRY_URL: &str = "https://registry.npmjs.org/@elizaos/cli";
+const INSTALL_DIR: &str = "cli";
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub(super) enum InstallStage {
+    Resolving,
+    // Byte progress and the checksum check are reported by `download-progress`
+    Downloading,
+    Installing,
+    Completed,
+    Failed,
+}
+
+// Payload of the `cli-install-progress` event
+#[derive(Debug, Clone, Serialize)]
+struct InstallProgress {
+    stage: InstallStage,
+    downloaded: u64,
+    total: Option<u64>,
+    message: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PackageVersion {
+    dist: Dist,
+}
+
+#[derive(Debug, Deserialize)]
+struct Dist {
+    tarball: String,
+    integrity: String,
+}
+
+fn emit(app: &AppHandle, stage: InstallStage, downloaded: u64, total: Option<u64>) {
+    emit_message(app, stage, downloaded, total, None);
+}
+
+pub(super) fn emit_message(
+    app: &AppHandle,
+    stage: InstallStage,
+    downloaded: u64,
+    total: Option<u64>,
+    message: Option<String>,
+) {
+    let progress = InstallProgress {
+        stage,
+        downloaded,
+        total,
+        message,
+    };
+    if let Err(e) = app.emit("cli-install-progress", progress) {
+        tracing::warn!("Failed to emit install progress: {}", e);
+    }
+}
+
+fn install_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_data_dir()
+        .map(|dir| dir.join(INSTALL_DIR))
+        .map_err(|e| format!("Failed to resolve app data dir: {}", e))
+}
+
+pub fn installed_binary(app: &AppHandle) -> Option<PathBuf> {
+    let bin = install_dir(app).ok()?.join("node_modules").join(".bin");
+    Some(if cfg!(windows) {
+        bin.join("elizaos.cmd")
+    } else {
+        bin.join("elizaos")
+    })
+}
+
+// The script the managed CLI's `elizaos` binary runs, from its package.json
+pub fn installed_entry(app: &AppHandle) -> Option<PathBuf> {
+    let package = install_dir(app)
+        .ok()?
+        .join("node_modules")
+        .join("@elizaos")
+        .join("cli");
+    let manifest: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(package.join("package.json")).ok()?).ok()?;
+    let bin = match &manifest["bin"] {
+        serde_json::Value::String(bin) => bin.as_str(),
+        bin => bin[super::CLI_NAME].as_str()?,
+    };
+    let entry = package.join(bin);
+    entry.is_file().then_some(entry)
+}
+
+fn bun_add(bun: &Path, dir: &Path, tarball: &Path) -> Command {
+    let mut command = Command::new(bun);
+    command.arg("add").arg("--cwd").arg(dir).arg(tarball);
+    command
+}
+
+// Install the verified tarball (and its dependencies) into `dir` with npm, or bun if npm is
+// missing. With the app's own runtime selected, that runtime installs it instead.
+fn install_tarball(app: &AppHandle, dir: &Path, tarball: &Path) -> Result<(), String> {
+    let mut command = if server::runtime::enabled() {
+        let bun = server::runtime::installed(app)
+            .ok_or("The app's Bun runtime has to be installed before the CLI")?;
+        bun_add(&bun, dir, tarball)
+    } else if let Some(npm) = find_tool(app, "npm") {
+        let mut command = Command::new(npm);
+        command
+            .arg("install")
+            .arg("--prefix")
+            .arg(dir)
+            .args(["--no-audit", "--no-fund"])
+            .arg(tarball);
+        command
+    } else if let Some(bun) = find_tool(app, "bun") {
+        bun_add(&bun, dir, tarball)
+    } else {
+        return Err("Installing the elizaos CLI requires Node.js (npm) or Bun".to_string());
+    };
+
+    let output = command
+        .output()
+        .map_err(|e| format!("Failed to run package manager: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "Package manager failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(())
+}
+
+async fn run(app: &AppHandle, version: &str) -> Result<PathBuf, String> {
+    emit(app, InstallStage::Resolving, 0, None);
+    let metadata: PackageVersion = crate::network::client()
+        .get(format!("{}/{}", REGISTRY_URL, version))
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| format!("Failed to query the npm registry: {}", e))?
+        .json()
+        .await
+        .map_err(|e| format!("Invalid registry response: {}", e))?;
+
+    emit(app, InstallStage::Downloading, 0, None);
+    let dir = install_dir(app)?;
+    let url = Url::parse(&metadata.dist.tarball)
+        .map_err(|e| format!("Invalid tarball URL {}: {}", metadata.dist.tarball, e))?;
+    // Checked against the registry's Subresource Integrity string before it's used
+    let tarball = downloads::fetch(
+        app,
+        Download {
+            kind: "cli",
+            label: format!("elizaos CLI {}", version),
+            url,
+            path: dir.join(format!("elizaos-cli-{}.tgz", version)),
+            checksum: Some(Checksum::from_integrity(&metadata.dist.integrity)?),
+        },
+    )
+    .await?;
+    let size = fs::metadata(&tarball).map_or(0, |metadata| metadata.len());
+
+    emit(app, InstallStage::Installing, size, None);
+
+    let (handle, install_in, tarball_path) = (app.clone(), dir.clone(), tarball.clone());
+    tauri::async_runtime::spawn_blocking(move || {
+        install_tarball(&handle, &install_in, &tarball_path)
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+    let _ = fs::remove_file(&tarball);
+
+    let binary = installed_binary(app)
+        .filter(|path| path.is_file())
+        .ok_or("The elizaos CLI was installed but its binary could not be found")?;
+    config::update(app, |config| config.cli_path = Some(binary.clone()))?;
+    Ok(binary)
+}
+
+// Download, verify and install `version` into the app data dir, reporting the outcome
+pub(crate) async fn install_version(app: &AppHandle, version: &str) -> Result<PathBuf, String> {
+    match run(app, version).await {
+        Ok(binary) => {
+            emit(app, InstallStage::Completed, 0, None);
+            Ok(binary)
+        }
+        Err(e) => {
+            tracing::error!("Failed to install the elizaos CLI: {}", e);
+            emit_message(app, InstallStage::Failed, 0, None, Some(e.clone()));
+            Err(e)
+        }
+    }
+}
+
+// Download, verify and install the pinned CLI version into the app data dir
+#[tauri::command]
+pub async fn install_cli(app: AppHandle) -> Result<PathBuf, AppError> {
+    capabilities::require(Capability::RunPrograms)?;
+    install_version(&app, CLI_VERSION)
+        .await
+        .map_err(AppError::Io)
+}
@@ -0,0 +1,97 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+
+use crate::capabilities::{self, Capability};
+use crate::error::AppError;
+use crate::server::config;
+
+pub mod install;
+pub mod path;
+pub mod version;
+
+pub const CLI_NAME: &str = "elizaos";
+
+// Payload of the `cli-not-found` event
+#[derive(Debug, Clone, Serialize)]
+struct CliNotFound {
+    message: String,
+    searched: Vec<PathBuf>,
+}
+
+// The CLI to launch: the configured path, then PATH and common install locations,
+// then our own managed install
+pub fn resolve(app: &AppHandle) -> Option<PathBuf> {
+    config::current()
+        .cli_path
+        .filter(|path| path.is_file())
+        .or_else(|| path::search_cli(app).found)
+        .or_else(|| install::installed_binary(app).filter(|path| path.is_file()))
+}
+
+// Output of `elizaos --version`, e.g. "1.0.6"
+pub fn version(app: &AppHandle, cli: &Path) -> Result<String, String> {
+    let output = Command::new(cli)
+        .arg("--version")
+        .env("PATH", path::spawn_path(app, cli))
+        .output()
+        .map_err(|e| format!("Failed to run {}: {}", cli.display(), e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "{} --version exited with {}",
+            cli.display(),
+            output.status
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+// Tell the frontend where we looked so it can offer installation or a manual path
+pub fn report_missing(app: &AppHandle) {
+    let search = path::search_cli(app);
+    let payload = CliNotFound {
+        message: "The elizaos CLI was not found".to_string(),
+        searched: search.searched,
+    };
+    tracing::warn!("{}", payload.message);
+    if let Err(e) = app.emit("cli-not-found", payload) {
+        tracing::warn!("Failed to emit cli-not-found: {}", e);
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CliStatus {
+    pub path: Option<PathBuf>,
+    // Whether the resolved CLI is the copy installed by the app
+    pub managed: bool,
+    pub pinned_version: &'static str,
+}
+
+#[tauri::command]
+pub fn get_cli_status(app: AppHandle) -> CliStatus {
+    let path = resolve(&app);
+    let managed = path.is_some() && path == install::installed_binary(&app);
+    CliStatus {
+        path,
+        managed,
+        pinned_version: install::CLI_VERSION,
+    }
+}
+
+// Override the CLI location, or clear the override with `None`
+#[tauri::command]
+pub fn set_cli_path(app: AppHandle, path: Option<PathBuf>) -> Result<CliStatus, AppError> {
+    capabilities::require(Capability::RunPrograms)?;
+    if let Some(path) = &path {
+        if !path.is_file() {
+            return Err(AppError::Validation(format!(
+                "{} is not a file",
+                path.display()
+            )));
+        }
+    }
+    config::update(&app, |config| config.cli_path = path)?;
+    Ok(get_cli_status(app))
+}
@@ -0,0 +1,130 @@
+use std::env;
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use tauri::{AppHandle, Manager};
+
+use super::CLI_NAME;
+
+#[cfg(windows)]
+fn candidates(dir: &Path, name: &str) -> Vec<PathBuf> {
+    ["exe", "cmd", "bat"]
+        .iter()
+        .map(|ext| dir.join(format!("{}.{}", name, ext)))
+        .collect()
+}
+
+#[cfg(not(windows))]
+fn candidates(dir: &Path, name: &str) -> Vec<PathBuf> {
+    vec![dir.join(name)]
+}
+
+// Places package managers commonly install binaries. GUI apps on macOS don't inherit the
+// shell PATH, so these have to be checked explicitly.
+fn common_dirs(app: &AppHandle) -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+
+    if let Ok(home) = app.path().home_dir() {
+        dirs.push(home.join(".bun").join("bin"));
+        dirs.push(home.join(".npm-global").join("bin"));
+        dirs.push(home.join(".volta").join("bin"));
+        dirs.push(home.join(".local").join("bin"));
+
+        // Every node version installed through nvm, newest first
+        if let Ok(entries) = fs::read_dir(home.join(".nvm").join("versions").join("node")) {
+            let mut versions: Vec<PathBuf> = entries
+                .filter_map(Result::ok)
+                .map(|entry| entry.path().join("bin"))
+                .collect();
+            versions.sort();
+            versions.reverse();
+            dirs.extend(versions);
+        }
+    }
+
+    #[cfg(unix)]
+    dirs.extend(
+        ["/opt/homebrew/bin", "/usr/local/bin", "/usr/bin"]
+            .iter()
+            .map(PathBuf::from),
+    );
+
+    #[cfg(windows)]
+    if let Some(appdata) = env::var_os("APPDATA") {
+        dirs.push(PathBuf::from(appdata).join("npm"));
+    }
+
+    dirs
+}
+
+fn search_dirs(app: &AppHandle) -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = env::var_os("PATH")
+        .map(|path| env::split_paths(&path).collect())
+        .unwrap_or_default();
+    for dir in common_dirs(app) {
+        if !dirs.contains(&dir) {
+            dirs.push(dir);
+        }
+    }
+    dirs
+}
+
+// Find an executable on PATH or in the common install locations
+pub fn find_tool(app: &AppHandle, name: &str) -> Option<PathBuf> {
+    search_dirs(app)
+        .iter()
+        .flat_map(|dir| candidates(dir, name))
+        .find(|candidate| candidate.is_file())
+}
+
+// Where `npm install -g` puts binaries, which may be a custom prefix
+fn npm_global_bin(app: &AppHandle) -> Option<PathBuf> {
+    let npm = find_tool(app, "npm")?;
+    let output = Command::new(npm).args(["prefix", "-g"]).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let prefix = PathBuf::from(String::from_utf8_lossy(&output.stdout).trim());
+    Some(if cfg!(windows) {
+        prefix
+    } else {
+        prefix.join("bin")
+    })
+}
+
+pub struct CliSearch {
+    pub found: Option<PathBuf>,
+    pub searched: Vec<PathBuf>,
+}
+
+pub fn search_cli(app: &AppHandle) -> CliSearch {
+    let mut dirs = search_dirs(app);
+    if let Some(dir) = npm_global_bin(app) {
+        if !dirs.contains(&dir) {
+            dirs.push(dir);
+        }
+    }
+
+    let found = dirs
+        .iter()
+        .flat_map(|dir| candidates(dir, CLI_NAME))
+        .find(|candidate| candidate.is_file());
+    CliSearch {
+        found,
+        searched: dirs,
+    }
+}
+
+// PATH for the spawned server: the CLI's own dir and the common locations come first so
+// its `#!/usr/bin/env node` (or bun) shebang resolves even without a login shell
+pub fn spawn_path(app: &AppHandle, cli: &Path) -> OsString {
+    let mut dirs: Vec<PathBuf> = cli.parent().map(Path::to_path_buf).into_iter().collect();
+    for dir in search_dirs(app) {
+        if !dirs.contains(&dir) {
+            dirs.push(dir);
+        }
+    }
+    env::join_paths(dirs).unwrap_or_else(|_| env::var_os("PATH").unwrap_or_default())
+}
@@ -0,0 +1,178 @@
+use std::io::{BufRead, BufReader, Read};
+use std::process::{Command, Stdio};
+use std::thread;
+
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use super::install::{self, InstallStage, REGISTRY_URL};
+use super::path::find_tool;
+use crate::capabilities::{self, Capability};
+use crate::error::AppError;
+use crate::server::{self, ServerStatus};
+
+#[derive(Debug, Deserialize)]
+struct LatestVersion {
+    version: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CliUpdate {
+    pub installed: Option<String>,
+    pub latest: String,
+    pub update_available: bool,
+}
+
+// Numeric components of a version, ignoring any prefix text and pre-release suffix
+pub(crate) fn parse_version(text: &str) -> Option<Vec<u64>> {
+    text.split_whitespace().find_map(|word| {
+        let word = word.trim_start_matches('v');
+        let core = word.split(['-', '+']).next()?;
+        core.split('.')
+            .map(|part| part.parse().ok())
+            .collect::<Option<Vec<u64>>>()
+            .filter(|parts| !parts.is_empty())
+    })
+}
+
+fn installed_version(app: &AppHandle) -> Result<Option<String>, String> {
+    match super::resolve(app) {
+        Some(cli) => super::version(app, &cli).map(Some),
+        None => Ok(None),
+    }
+}
+
+async fn latest_version() -> Result<String, String> {
+    let latest: LatestVersion = crate::network::client()
+        .get(format!("{}/latest", REGISTRY_URL))
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| format!("Failed to query the npm registry: {}", e))?
+        .json()
+        .await
+        .map_err(|e| format!("Invalid registry response: {}", e))?;
+    Ok(latest.version)
+}
+
+fn stream_lines(app: &AppHandle, source: impl Read) {
+    for line in BufReader::new(source).lines().map_while(Result::ok) {
+        install::emit_message(app, InstallStage::Installing, 0, None, Some(line));
+    }
+}
+
+// Upgrade a CLI installed outside the app with the global package manager, forwarding its output
+fn upgrade_global(app: &AppHandle, version: &str) -> Result<(), String> {
+    let package = format!("@elizaos/cli@{}", version);
+    let mut command = if let Some(npm) = find_tool(app, "npm") {
+        let mut command = Command::new(npm);
+        command.args(["install", "-g", "--no-audit", "--no-fund", &package]);
+        command
+    } else if let Some(bun) = find_tool(app, "bun") {
+        let mut command = Command::new(bun);
+        command.args(["add", "-g", &package]);
+        command
+    } else {
+        return Err("Upgrading the elizaos CLI requires Node.js (npm) or Bun".to_string());
+    };
+
+    install::emit_message(app, InstallStage::Installing, 0, None, None);
+    let mut child = command
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to run package manager: {}", e))?;
+    let stdout = child.stdout.take();
+    let stderr = child.stderr.take();
+    thread::scope(|scope| {
+        if let Some(stdout) = stdout {
+            scope.spawn(|| stream_lines(app, stdout));
+        }
+        if let Some(stderr) = stderr {
+            scope.spawn(|| stream_lines(app, stderr));
+        }
+    });
+
+    let status = child
+        .wait()
+        .map_err(|e| format!("Failed to wait for package manager: {}", e))?;
+    if !status.success() {
+        return Err(format!("Package manager exited with {}", status));
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_cli_version(app: AppHandle) -> Result<Option<String>, AppError> {
+    tauri::async_runtime::spawn_blocking(move || installed_version(&app))
+        .await?
+        .map_err(AppError::Io)
+}
+
+// Compare the installed CLI with the latest release on npm
+#[tauri::command]
+pub async fn check_cli_update(app: AppHandle) -> Result<CliUpdate, AppError> {
+    let installed = tauri::async_runtime::spawn_blocking(move || installed_version(&app))
+        .await?
+        .map_err(AppError::Io)?;
+    let latest = latest_version().await.map_err(AppError::Network)?;
+    let update_available = match (
+        installed.as_deref().and_then(parse_version),
+        parse_version(&latest),
+    ) {
+        (Some(installed), Some(latest)) => latest > installed,
+        _ => installed.is_none(),
+    };
+    Ok(CliUpdate {
+        installed,
+        latest,
+        update_available,
+    })
+}
+
+// Install the latest CLI, streaming progress as `cli-install-progress`. A server we spawned
+// keeps the old binary in use, so the upgrade is refused while it runs unless
+// `restart_server` confirms it may be stopped and started again afterwards.
+#[tauri::command]
+pub async fn upgrade_cli(app: AppHandle, restart_server: Option<bool>) -> Result<String, AppError> {
+    capabilities::require(Capability::RunPrograms)?;
+    let was_running = server::status() == ServerStatus::Running;
+    if was_running && !restart_server.unwrap_or(false) {
+        return Err(AppError::ServerLifecycle(
+            "The elizaOS server is running; restart it to upgrade the CLI".to_string(),
+        ));
+    }
+
+    let latest = latest_version().await.map_err(AppError::Network)?;
+    if was_running {
+        let handle = app.clone();
+        tauri::async_runtime::spawn_blocking(move || server::stop(&handle))
+            .await?
+            .map_err(AppError::ServerLifecycle)?;
+    }
+
+    let current = super::resolve(&app);
+    let result = if current.is_none() || current == install::installed_binary(&app) {
+        install::install_version(&app, &latest).await.map(|_| ())
+    } else {
+        let (handle, version) = (app.clone(), latest.clone());
+        let result =
+            tauri::async_runtime::spawn_blocking(move || upgrade_global(&handle, &version)).await?;
+        match &result {
+            Ok(()) => install::emit_message(&app, InstallStage::Completed, 0, None, None),
+            Err(e) => {
+                tracing::error!("Failed to upgrade the elizaos CLI: {}", e);
+                install::emit_message(&app, InstallStage::Failed, 0, None, Some(e.clone()));
+            }
+        }
+        result
+    };
+
+    if was_running {
+        let handle = app.clone();
+        tauri::async_runtime::spawn_blocking(move || server::start(&handle))
+            .await?
+            .map_err(AppError::ServerLifecycle)?;
+    }
+    result.map(|()| latest).map_err(AppError::Io)
+}
@@ -0,0 +1,292 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter};
+use tauri_plugin_clipboard_manager::ClipboardExt;
+use tauri_plugin_global_shortcut::ShortcutState;
+use tauri_plugin_notification::NotificationExt;
+
+use crate::error::AppError;
+use crate::server::chat;
+use crate::{quick_chat, settings, shortcuts};
+
+// Agent conversation that clipboard prompts are sent to
+const CONVERSATION_ID: &str = "clipboard";
+// Replaced with the copied text in prompt templates
+const TEXT_PLACEHOLDER: &str = "{text}";
+// Longest reply shown in a notification before it is cut off
+const NOTIFICATION_LIMIT: usize = 240;
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromptTemplate {
+    pub name: String,
+    pub prompt: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClipboardOutput {
+    Notification,
+    QuickChat,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ClipboardSettings {
+    pub enabled: bool,
+    // Two copies within this window trigger the action
+    pub double_copy_ms: u64,
+    // Optional hotkey that sends the clipboard right away; empty disables it
+    pub shortcut: String,
+    // Name of the template in `templates` to use
+    pub template: String,
+    pub templates: Vec<PromptTemplate>,
+    pub output: ClipboardOutput,
+}
+
+impl Default for ClipboardSettings {
+    fn default() -> Self {
+        let template = |name: &str, prompt: &str| PromptTemplate {
+            name: name.to_string(),
+            prompt: prompt.to_string(),
+        };
+        Self {
+            enabled: false,
+            double_copy_ms: 600,
+            shortcut: String::new(),
+            template: "Explain".to_string(),
+            templates: vec![
+                template("Explain", "Explain the following:\n\n{text}"),
+                template(
+                    "Summarize",
+                    "Summarize the following in a few sentences:\n\n{text}",
+                ),
+                template(
+                    "Improve",
+                    "Fix the grammar and improve the wording of the following:\n\n{text}",
+                ),
+            ],
+            output: ClipboardOutput::Notification,
+        }
+    }
+}
+
+// Payload of the `clipboard-response` event
+#[derive(Debug, Clone, Serialize)]
+pub struct ClipboardResponse {
+    pub template: String,
+    pub text: String,
+    pub reply: String,
+}
+
+// Bumped on every start/stop so a superseded watcher thread exits
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+// Kept so a quick chat window opened for the reply can fetch it once loaded
+static LAST_RESPONSE: Lazy<Mutex<Option<ClipboardResponse>>> = Lazy::new(|| Mutex::new(None));
+
+// A value that changes on every copy, even when the same text is copied again
+#[cfg(windows)]
+fn change_marker(_app: &AppHandle) -> Option<String> {
+    let sequence =
+        unsafe { windows_sys::Win32::System::DataExchange::GetClipboardSequenceNumber() };
+    Some(sequence.to_string())
+}
+
+#[cfg(target_os = "macos")]
+fn change_marker(_app: &AppHandle) -> Option<String> {
+    use objc2_app_kit::NSPasteboard;
+    let count = NSPasteboard::generalPasteboard().changeCount();
+    Some(count.to_string())
+}
+
+// Without a change counter, only copies of different text can be told apart
+#[cfg(not(any(windows, target_os = "macos")))]
+fn change_marker(app: &AppHandle) -> Option<String> {
+    app.clipboard().read_text().ok()
+}
+
+// Poll for copies and trigger the action when two land within `double_copy_ms`
+fn watch(app: AppHandle, generation: u64) {
+    let mut marker = change_marker(&app);
+    let mut last_copy: Option<Instant> = None;
+    while GENERATION.load(Ordering::SeqCst) == generation {
+        thread::sleep(POLL_INTERVAL);
+        let current = change_marker(&app);
+        if current == marker {
+            continue;
+        }
+        marker = current;
+
+        let window = Duration::from_millis(settings::current().clipboard.double_copy_ms);
+        match last_copy.take() {
+            Some(copied) if copied.elapsed() <= window => trigger(&app),
+            _ => last_copy = Some(Instant::now()),
+        }
+    }
+}
+
+fn render(template: &PromptTemplate, text: &str) -> String {
+    if template.prompt.contains(TEXT_PLACEHOLDER) {
+        template.prompt.replace(TEXT_PLACEHOLDER, text)
+    } else {
+        format!("{}\n\n{}", template.prompt, text)
+    }
+}
+
+fn deliver(app: &AppHandle, output: ClipboardOutput, response: ClipboardResponse) {
+    *LAST_RESPONSE.lock().unwrap() = Some(response.clone());
+    match output {
+        ClipboardOutput::Notification => {
+            let mut body = response.reply.clone();
+            if body.chars().count() > NOTIFICATION_LIMIT {
+                body = body.chars().take(NOTIFICATION_LIMIT).collect::<String>() + "…";
+            }
+            if let Err(e) = app
+                .notification()
+                .builder()
+                .title(&response.template)
+                .body(body)
+                .show()
+            {
+                tracing::warn!("Failed to show notification: {}", e);
+            }
+        }
+        ClipboardOutput::QuickChat => {
+            if let Err(e) = quick_chat::show(app) {
+                tracing::warn!("{}", e);
+            }
+        }
+    }
+    let _ = app.emit("clipboard-response", response);
+}
+
+async fn run(app: &AppHandle) -> Result<(), String> {
+    let text = app
+        .clipboard()
+        .read_text()
+        .map_err(|e| format!("Failed to read the clipboard: {}", e))?;
+    let text = text.trim();
+    if text.is_empty() {
+        return Ok(());
+    }
+
+    let config = settings::current().clipboard;
+    let template = config
+        .templates
+        .iter()
+        .find(|template| template.name == config.template)
+        .or_else(|| config.templates.first())
+        .ok_or("No clipboard prompt templates are configured")?;
+    let response = chat::send_message(None, CONVERSATION_ID, &render(template, text)).await?;
+    let reply = chat::reply_text(&response).ok_or("The agent did not reply")?;
+
+    deliver(
+        app,
+        config.output,
+        ClipboardResponse {
+            template: template.name.clone(),
+            text: text.to_string(),
+            reply,
+        },
+    );
+    Ok(())
+}
+
+// Send the clipboard contents to the agent with the selected template
+fn trigger(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = run(&app).await {
+            tracing::warn!("Clipboard action failed: {}", e);
+            let _ = app.emit("clipboard-action-failed", &e);
+        }
+    });
+}
+
+fn on_shortcut(app: &AppHandle, state: ShortcutState) {
+    if state == ShortcutState::Pressed {
+        trigger(app);
+    }
+}
+
+fn start(app: &AppHandle) {
+    let generation = GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+    let app = app.clone();
+    thread::spawn(move || watch(app, generation));
+}
+
+fn stop() {
+    GENERATION.fetch_add(1, Ordering::SeqCst);
+}
+
+fn active_shortcut() -> String {
+    let config = settings::current().clipboard;
+    if config.enabled {
+        config.shortcut
+    } else {
+        String::new()
+    }
+}
+
+pub fn init(app: &AppHandle) -> Result<(), String> {
+    if !settings::current().clipboard.enabled {
+        return Ok(());
+    }
+    start(app);
+    shortcuts::bind(app, &active_shortcut(), on_shortcut)
+}
+
+// Opt in or out of the clipboard action; nothing is read from the clipboard while disabled
+#[tauri::command]
+pub fn set_clipboard_watcher_enabled(app: AppHandle, enabled: bool) -> Result<(), AppError> {
+    let previous = active_shortcut();
+    if enabled {
+        start(&app);
+        shortcuts::rebind(
+            &app,
+            &previous,
+            &settings::current().clipboard.shortcut,
+            on_shortcut,
+        )?;
+    } else {
+        stop();
+        shortcuts::unbind(&app, &previous)?;
+    }
+    settings::update(&app, |settings| settings.clipboard.enabled = enabled)?;
+    Ok(())
+}
+
+// Replace the templates, trigger and output; `enabled` is left to `set_clipboard_watcher_enabled`
+#[tauri::command]
+pub fn configure_clipboard_action(
+    app: AppHandle,
+    config: ClipboardSettings,
+) -> Result<ClipboardSettings, AppError> {
+    if !config.templates.iter().any(|t| t.name == config.template) {
+        return Err(AppError::NotFound(format!(
+            "Unknown prompt template: {}",
+            config.template
+        )));
+    }
+    let current = settings::current().clipboard;
+    if current.enabled {
+        shortcuts::rebind(&app, &current.shortcut, &config.shortcut, on_shortcut)?;
+    }
+    let updated = settings::update(&app, |settings| {
+        settings.clipboard = ClipboardSettings {
+            enabled: current.enabled,
+            ..config
+        }
+    })?;
+    Ok(updated.clipboard)
+}
+
+#[tauri::command]
+pub fn last_clipboard_response() -> Option<ClipboardResponse> {
+    LAST_RESPONSE.lock().unwrap().clone()
+}
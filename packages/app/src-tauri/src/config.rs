@@ -0,0 +1,387 @@
+use std::fs;
+use std::path::PathBuf;
+
+use serde::Serialize;
+
+use crate::auth::secure_store;
+use crate::capabilities::{self, Capability};
+use crate::error::AppError;
+use crate::workspace;
+
+const ENV_FILE: &str = ".env";
+const ENV_SECRET_PREFIX: &str = "env:";
+
+// Values of this form are stored in the keychain and resolved when the server is spawned
+const SECRET_PLACEHOLDER_PREFIX: &str = "keychain:";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EnvVar {
+    pub key: String,
+    // Omitted for values kept in the keychain
+    pub value: Option<String>,
+    pub secret: bool,
+}
+
+enum Line {
+    Entry { key: String, value: String },
+    // Comments, blank lines and anything we don't understand are kept verbatim
+    Other(String),
+}
+
+pub fn env_path() -> Result<PathBuf, String> {
+    Ok(workspace::dir()?.join(ENV_FILE))
+}
+
+fn validate_key(key: &str) -> Result<(), String> {
+    let mut chars = key.chars();
+    let valid = chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("Invalid environment variable name: {}", key))
+    }
+}
+
+fn parse_value(raw: &str) -> String {
+    let raw = raw.trim();
+    if let Some(inner) = raw.strip_prefix('"').and_then(|r| r.strip_suffix('"')) {
+        let mut value = String::with_capacity(inner.len());
+        let mut chars = inner.chars();
+        while let Some(c) = chars.next() {
+            match (c, chars.clone().next()) {
+                ('\\', Some('n')) => {
+                    value.push('\n');
+                    chars.next();
+                }
+                ('\\', Some(next @ ('"' | '\\'))) => {
+                    value.push(next);
+                    chars.next();
+                }
+                _ => value.push(c),
+            }
+        }
+        value
+    } else if let Some(inner) = raw.strip_prefix('\'').and_then(|r| r.strip_suffix('\'')) {
+        inner.to_string()
+    } else {
+        // Unquoted values may carry a trailing comment
+        match raw.find(" #") {
+            Some(index) => raw[..index].trim_end().to_string(),
+            None => raw.to_string(),
+        }
+    }
+}
+
+fn format_value(value: &str) -> String {
+    let needs_quotes = value.is_empty()
+        || value
+            .chars()
+            .any(|c| c.is_whitespace() || matches!(c, '#' | '"' | '\'' | '\\' | '='));
+    if !needs_quotes {
+        return value.to_string();
+    }
+    let escaped = value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n");
+    format!("\"{}\"", escaped)
+}
+
+fn parse_line(line: &str) -> Line {
+    let trimmed = line.trim_start();
+    if trimmed.starts_with('#') {
+        return Line::Other(line.to_string());
+    }
+    let body = trimmed.strip_prefix("export ").unwrap_or(trimmed);
+    match body.split_once('=') {
+        Some((key, value)) if validate_key(key.trim()).is_ok() => Line::Entry {
+            key: key.trim().to_string(),
+            value: parse_value(value),
+        },
+        _ => Line::Other(line.to_string()),
+    }
+}
+
+fn parse(contents: &str) -> Vec<Line> {
+    contents.lines().map(parse_line).collect()
+}
+
+fn render(lines: &[Line]) -> String {
+    let mut contents = String::new();
+    for line in lines {
+        match line {
+            Line::Entry { key, value } => {
+                contents.push_str(key);
+                contents.push('=');
+                contents.push_str(&format_value(value));
+            }
+            Line::Other(raw) => contents.push_str(raw),
+        }
+        contents.push('\n');
+    }
+    contents
+}
+
+fn read_lines() -> Result<Vec<Line>, String> {
+    let path = env_path()?;
+    match fs::read_to_string(&path) {
+        Ok(contents) => Ok(parse(&contents)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(format!("Failed to read {}: {}", path.display(), e)),
+    }
+}
+
+fn write_lines(lines: &[Line]) -> Result<(), String> {
+    let path = env_path()?;
+    let contents = render(lines);
+
+    // Write to a sibling file first so a crash can't leave a truncated .env behind
+    let tmp = path.with_extension("tmp");
+    fs::write(&tmp, contents).map_err(|e| format!("Failed to write {}: {}", tmp.display(), e))?;
+    fs::rename(&tmp, &path).map_err(|e| format!("Failed to replace {}: {}", path.display(), e))
+}
+
+fn secret_account(key: &str) -> String {
+    format!("{}{}", ENV_SECRET_PREFIX, key)
+}
+
+fn read_secret(name: &str) -> Result<String, String> {
+    secure_store::read_blocking(&secret_account(name))
+        .map_err(|e| format!("Failed to read secret {}: {}", name, e))?
+        .ok_or_else(|| format!("Secret {} is missing from the keychain", name))
+}
+
+fn is_placeholder(value: &str) -> bool {
+    value.starts_with(SECRET_PLACEHOLDER_PREFIX)
+}
+
+// Real values for `.env` entries that point at the keychain, for the spawned server.
+// dotenv doesn't override variables that are already set, so these take precedence.
+pub fn resolve_secret_env() -> Vec<(String, String)> {
+    let lines = match read_lines() {
+        Ok(lines) => lines,
+        Err(e) => {
+            tracing::warn!("{}", e);
+            return Vec::new();
+        }
+    };
+
+    lines
+        .into_iter()
+        .filter_map(|line| match line {
+            Line::Entry { key, value } if is_placeholder(&value) => {
+                let name = &value[SECRET_PLACEHOLDER_PREFIX.len()..];
+                match read_secret(name) {
+                    Ok(secret) => Some((key, secret)),
+                    Err(e) => {
+                        tracing::warn!("{}", e);
+                        None
+                    }
+                }
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+// Value of one `.env` entry, resolving it from the keychain if it is a placeholder
+pub fn lookup(key: &str) -> Result<Option<String>, String> {
+    let value = read_lines()?.into_iter().find_map(|line| match line {
+        Line::Entry { key: k, value } if k == key => Some(value),
+        _ => None,
+    });
+    match value {
+        Some(value) if is_placeholder(&value) => {
+            let name = &value[SECRET_PLACEHOLDER_PREFIX.len()..];
+            read_secret(name).map(Some)
+        }
+        value => Ok(value),
+    }
+}
+
+// Plaintext entries often hold API keys too, so reading them needs ReadSecrets
+#[tauri::command]
+pub fn read_env() -> Result<Vec<EnvVar>, AppError> {
+    capabilities::require(Capability::ReadSecrets)?;
+    Ok(read_lines()
+        .map_err(AppError::Io)?
+        .into_iter()
+        .filter_map(|line| match line {
+            Line::Entry { key, value } => {
+                let secret = is_placeholder(&value);
+                Some(EnvVar {
+                    key,
+                    value: (!secret).then_some(value),
+                    secret,
+                })
+            }
+            Line::Other(_) => None,
+        })
+        .collect())
+}
+
+// Set a variable in place, or append it; `secret` values are stored in the keychain
+#[tauri::command]
+pub fn write_env_var(key: String, value: String, secret: Option<bool>) -> Result<(), AppError> {
+    validate_key(&key).map_err(AppError::Validation)?;
+    let stored = if secret.unwrap_or(false) {
+        secure_store::write_blocking(&secret_account(&key), &value)
+            .map_err(|e| AppError::Keychain(format!("Failed to store secret {}: {}", key, e)))?;
+        format!("{}{}", SECRET_PLACEHOLDER_PREFIX, key)
+    } else {
+        value
+    };
+
+    let mut lines = read_lines().map_err(AppError::Io)?;
+    let mut found = false;
+    for line in lines.iter_mut() {
+        if let Line::Entry {
+            key: existing,
+            value,
+        } = line
+        {
+            if *existing == key {
+                *value = stored.clone();
+                found = true;
+            }
+        }
+    }
+    if !found {
+        lines.push(Line::Entry { key, value: stored });
+    }
+    write_lines(&lines).map_err(AppError::Io)
+}
+
+#[tauri::command]
+pub fn delete_env_var(key: String) -> Result<(), AppError> {
+    validate_key(&key).map_err(AppError::Validation)?;
+    let mut lines = read_lines().map_err(AppError::Io)?;
+    let mut had_secret = false;
+    lines.retain(|line| match line {
+        Line::Entry {
+            key: existing,
+            value,
+        } if *existing == key => {
+            had_secret |= is_placeholder(value);
+            false
+        }
+        _ => true,
+    });
+    write_lines(&lines).map_err(AppError::Io)?;
+
+    if had_secret {
+        secure_store::delete_blocking(&secret_account(&key))
+            .map_err(|e| AppError::Keychain(format!("Failed to delete secret {}: {}", key, e)))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entries(lines: &[Line]) -> Vec<(&str, &str)> {
+        lines
+            .iter()
+            .filter_map(|line| match line {
+                Line::Entry { key, value } => Some((key.as_str(), value.as_str())),
+                Line::Other(_) => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn parses_dotenv_lines() {
+        let contents = "\
+# Provider keys
+OPENAI_API_KEY=sk-test
+export LOG_LEVEL=debug
+  SPACED = value with spaces  # comment
+QUOTED=\"line one\\nsaid \\\"hi\\\" \\\\ done\"
+SINGLE='kept \\n as is'
+EMPTY=
+not a valid line
+1BAD=value
+URL=https://example.com/?a=b#frag
+";
+        let lines = parse(contents);
+        assert_eq!(
+            entries(&lines),
+            vec![
+                ("OPENAI_API_KEY", "sk-test"),
+                ("LOG_LEVEL", "debug"),
+                ("SPACED", "value with spaces"),
+                ("QUOTED", "line one\nsaid \"hi\" \\ done"),
+                ("SINGLE", "kept \\n as is"),
+                ("EMPTY", ""),
+                ("URL", "https://example.com/?a=b#frag"),
+            ]
+        );
+        // Comments and lines that aren't entries survive a rewrite untouched
+        let rendered = render(&lines);
+        assert!(rendered.starts_with("# Provider keys\n"));
+        assert!(rendered.contains("\nnot a valid line\n1BAD=value\n"));
+    }
+
+    #[test]
+    fn values_round_trip() {
+        let values = [
+            "plain",
+            "",
+            " padded ",
+            "with # hash",
+            "a=b",
+            "multi\nline",
+            "quote \" and back\\slash",
+            "it's",
+            "'single'",
+            "\"double\"",
+            "tab\there",
+            "trailing\\",
+            "ünïcødé ✓",
+            "keychain:OPENAI_API_KEY",
+        ];
+        let lines: Vec<Line> = values
+            .iter()
+            .enumerate()
+            .map(|(index, value)| Line::Entry {
+                key: format!("KEY_{}", index),
+                value: value.to_string(),
+            })
+            .collect();
+        let parsed = parse(&render(&lines));
+        let parsed: Vec<&str> = entries(&parsed)
+            .into_iter()
+            .map(|(_, value)| value)
+            .collect();
+        assert_eq!(parsed, values);
+    }
+
+    #[test]
+    fn rendering_is_stable() {
+        let contents = "# comment\nA=1\nB=\"two words\"\n\nexport C=3\n";
+        let once = render(&parse(contents));
+        assert_eq!(render(&parse(&once)), once);
+        assert_eq!(once, "# comment\nA=1\nB=\"two words\"\n\nC=3\n");
+    }
+
+    #[test]
+    fn keys() {
+        for key in ["A", "_A", "OPENAI_API_KEY", "a1"] {
+            assert!(validate_key(key).is_ok(), "{}", key);
+        }
+        for key in ["", "1A", "A-B", "A B", "A.B", "É"] {
+            assert!(validate_key(key).is_err(), "{:?}", key);
+        }
+    }
+
+    #[test]
+    fn placeholders() {
+        assert!(is_placeholder("keychain:OPENAI_API_KEY"));
+        assert!(!is_placeholder("sk-keychain:"));
+        assert_eq!(secret_account("OPENAI_API_KEY"), "env:OPENAI_API_KEY");
+    }
+}
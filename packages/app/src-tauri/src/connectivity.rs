@@ -0,0 +1,236 @@
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use futures_util::future::join_all;
+
+use once_cell::sync::Lazy;
+use rusqlite::params;
+use serde::Serialize;
+use serde_json::Value;
+use tauri::{AppHandle, Emitter};
+use tokio::net::TcpStream;
+
+use crate::error::AppError;
+use crate::history::{self, db_error};
+use crate::server::{chat, poller};
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(5);
+const PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+// Public DNS resolvers; reaching any of them counts as having internet access
+const PROBES: &[&str] = &["1.1.1.1:443", "8.8.8.8:53", "9.9.9.9:53"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConnectivityMode {
+    Online,
+    // The internet is reachable but the local elizaOS server isn't
+    ServerDown,
+    // The local server is up but model providers can't be reached
+    NoInternet,
+    Offline,
+}
+
+// Payload of the `connectivity-changed` event
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Connectivity {
+    pub mode: ConnectivityMode,
+    pub server: bool,
+    pub internet: bool,
+}
+
+impl Connectivity {
+    fn new(server: bool, internet: bool) -> Self {
+        let mode = match (server, internet) {
+            (true, true) => ConnectivityMode::Online,
+            (false, true) => ConnectivityMode::ServerDown,
+            (true, false) => ConnectivityMode::NoInternet,
+            (false, false) => ConnectivityMode::Offline,
+        };
+        Self {
+            mode,
+            server,
+            internet,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct QueuedMessage {
+    pub id: i64,
+    pub conversation_id: String,
+    pub agent_id: Option<String>,
+    pub content: String,
+    pub created_at: u64,
+}
+
+// Payload of the `queued-message-sent` event
+#[derive(Debug, Clone, Serialize)]
+struct QueuedMessageSent {
+    message: QueuedMessage,
+    response: Value,
+}
+
+static CURRENT: Lazy<Mutex<Connectivity>> =
+    Lazy::new(|| Mutex::new(Connectivity::new(false, false)));
+static FLUSHING: AtomicBool = AtomicBool::new(false);
+
+async fn probe(address: &str) -> bool {
+    let Ok(address) = address.parse::<SocketAddr>() else {
+        return false;
+    };
+    matches!(
+        tokio::time::timeout(PROBE_TIMEOUT, TcpStream::connect(address)).await,
+        Ok(Ok(_))
+    )
+}
+
+async fn internet_reachable() -> bool {
+    join_all(PROBES.iter().map(|address| probe(address)))
+        .await
+        .into_iter()
+        .any(|reachable| reachable)
+}
+
+pub fn current() -> Connectivity {
+    *CURRENT.lock().unwrap()
+}
+
+fn load_queue(conn: &rusqlite::Connection) -> Result<Vec<QueuedMessage>, String> {
+    let mut statement = conn
+        .prepare("SELECT * FROM outbox ORDER BY id")
+        .map_err(db_error)?;
+    let queue = statement
+        .query_map([], |row| {
+            Ok(QueuedMessage {
+                id: row.get("id")?,
+                conversation_id: row.get("conversation_id")?,
+                agent_id: row.get("agent_id")?,
+                content: row.get("content")?,
+                created_at: row.get::<_, i64>("created_at")? as u64,
+            })
+        })
+        .map_err(db_error)?
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .map_err(db_error)?;
+    Ok(queue)
+}
+
+async fn remove_queued(id: i64) -> Result<(), String> {
+    history::with_db(move |conn| {
+        conn.execute("DELETE FROM outbox WHERE id = ?1", [id])
+            .map_err(db_error)?;
+        Ok(())
+    })
+    .await
+}
+
+// Deliver queued messages oldest first, stopping at the first failure so order is kept
+async fn flush(app: &AppHandle) -> Result<(), String> {
+    for message in history::with_db(|conn| load_queue(conn)).await? {
+        let response = chat::send_message(
+            message.agent_id.clone(),
+            &message.conversation_id,
+            &message.content,
+        )
+        .await?;
+        remove_queued(message.id).await?;
+        tracing::info!(id = message.id, "Delivered queued chat message");
+        let _ = app.emit(
+            "queued-message-sent",
+            QueuedMessageSent { message, response },
+        );
+    }
+    Ok(())
+}
+
+fn spawn_flush(app: &AppHandle) {
+    if FLUSHING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = flush(&app).await {
+            tracing::warn!("Failed to deliver queued messages: {}", e);
+        }
+        FLUSHING.store(false, Ordering::SeqCst);
+    });
+}
+
+// Watch the local server and internet access, flushing the outbox whenever both are up. The
+// server's health comes from the poller, which also wakes this up as soon as it changes.
+pub fn spawn(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut health = poller::subscribe();
+        loop {
+            let state = Connectivity::new(poller::is_healthy(), internet_reachable().await);
+            let changed = {
+                let mut current = CURRENT.lock().unwrap();
+                std::mem::replace(&mut *current, state) != state
+            };
+            if changed {
+                tracing::info!(mode = ?state.mode, "Connectivity changed");
+                if let Err(e) = app.emit("connectivity-changed", state) {
+                    tracing::warn!("Failed to emit connectivity-changed: {}", e);
+                }
+            }
+            if state.mode == ConnectivityMode::Online {
+                spawn_flush(&app);
+            }
+            tokio::select! {
+                _ = tokio::time::sleep(CHECK_INTERVAL) => {}
+                _ = health.changed() => {}
+            }
+        }
+    });
+}
+
+#[tauri::command]
+pub fn get_connectivity() -> Connectivity {
+    current()
+}
+
+// Add a chat message to the outbox; it is sent right away when online, otherwise once we are
+#[tauri::command]
+pub async fn queue_chat_message(
+    app: AppHandle,
+    conversation_id: String,
+    content: String,
+    agent_id: Option<String>,
+) -> Result<QueuedMessage, AppError> {
+    let created_at = history::now_millis();
+    let message = history::with_db(move |conn| {
+        conn.execute(
+            "INSERT INTO outbox (conversation_id, agent_id, content, created_at)
+                VALUES (?1, ?2, ?3, ?4)",
+            params![conversation_id, agent_id, content, created_at as i64],
+        )
+        .map_err(db_error)?;
+        Ok(QueuedMessage {
+            id: conn.last_insert_rowid(),
+            conversation_id,
+            agent_id,
+            content,
+            created_at,
+        })
+    })
+    .await?;
+
+    if current().mode == ConnectivityMode::Online {
+        spawn_flush(&app);
+    }
+    Ok(message)
+}
+
+#[tauri::command]
+pub async fn list_queued_messages() -> Result<Vec<QueuedMessage>, AppError> {
+    history::with_db(|conn| load_queue(conn))
+        .await
+        .map_err(AppError::Database)
+}
+
+#[tauri::command]
+pub async fn discard_queued_message(id: i64) -> Result<(), AppError> {
+    remove_queued(id).await.map_err(AppError::Database)
+}
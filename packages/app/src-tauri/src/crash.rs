@@ -0,0 +1,198 @@
+use std::backtrace::Backtrace;
+use std::fs;
+use std::panic::{self, PanicHookInfo};
+use std::path::{Path, PathBuf};
+use std::thread;
+
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+use crate::auth::unix_now;
+use crate::error::AppError;
+use crate::settings;
+
+// Under the app data dir; also collected by the diagnostics bundle
+pub const CRASH_DIR: &str = "crashes";
+
+// Reports kept on disk before the oldest are deleted
+const MAX_CRASH_REPORTS: usize = 20;
+
+static CRASH_DIR_PATH: OnceCell<PathBuf> = OnceCell::new();
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CrashReport {
+    pub id: String,
+    // Seconds since the Unix epoch
+    pub timestamp: u64,
+    pub app_version: String,
+    pub os: String,
+    pub arch: String,
+    pub thread: String,
+    pub message: String,
+    pub location: Option<String>,
+    pub backtrace: String,
+    // Set once the report has been sent to the configured endpoint
+    #[serde(default)]
+    pub uploaded: bool,
+}
+
+fn panic_message(info: &PanicHookInfo) -> String {
+    let payload = info.payload();
+    payload
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "Box<dyn Any>".to_string())
+}
+
+fn report_path(dir: &Path, id: &str) -> PathBuf {
+    dir.join(format!("{}.json", id))
+}
+
+fn write(dir: &Path, report: &CrashReport) -> Result<(), String> {
+    fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    let path = report_path(dir, &report.id);
+    let contents = serde_json::to_string_pretty(report).map_err(|e| e.to_string())?;
+    fs::write(&path, contents).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+fn read_all(dir: &Path) -> Vec<CrashReport> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut reports = entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| fs::read_to_string(entry.path()).ok())
+        .filter_map(|contents| serde_json::from_str::<CrashReport>(&contents).ok())
+        .collect::<Vec<_>>();
+    reports.sort_by_key(|report| std::cmp::Reverse(report.timestamp));
+    reports
+}
+
+fn prune(dir: &Path) {
+    for report in read_all(dir).iter().skip(MAX_CRASH_REPORTS) {
+        let _ = fs::remove_file(report_path(dir, &report.id));
+    }
+}
+
+// Record panics from any thread, including the supervisor and keyring threads, then
+// defer to the default hook. Installed before the app is built; reports are only
+// written to disk once `init` has resolved the crash dir.
+pub fn install_hook(app_version: String) {
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        let timestamp = unix_now();
+        let report = CrashReport {
+            id: format!("{}-{}", timestamp, std::process::id()),
+            timestamp,
+            app_version: app_version.clone(),
+            os: std::env::consts::OS.to_string(),
+            arch: std::env::consts::ARCH.to_string(),
+            thread: thread::current().name().unwrap_or("<unnamed>").to_string(),
+            message: panic_message(info),
+            location: info
+                .location()
+                .map(|l| format!("{}:{}:{}", l.file(), l.line(), l.column())),
+            backtrace: Backtrace::force_capture().to_string(),
+            uploaded: false,
+        };
+        tracing::error!(
+            thread = %report.thread,
+            location = report.location.as_deref().unwrap_or_default(),
+            "Panic: {}",
+            report.message
+        );
+        if let Some(dir) = CRASH_DIR_PATH.get() {
+            if let Err(e) = write(dir, &report) {
+                tracing::error!("Failed to write crash report: {}", e);
+            }
+        }
+        default_hook(info);
+    }));
+}
+
+// Send reports that haven't been uploaded yet, if the user opted in
+async fn upload_pending(dir: PathBuf) {
+    let settings = settings::current();
+    let Some(url) = settings
+        .crash_report_url
+        .filter(|_| settings.crash_upload_consent)
+    else {
+        return;
+    };
+
+    let client = crate::network::client();
+    for mut report in read_all(&dir).into_iter().filter(|report| !report.uploaded) {
+        match client.post(&url).json(&report).send().await {
+            Ok(response) if response.status().is_success() => {
+                report.uploaded = true;
+                if let Err(e) = write(&dir, &report) {
+                    tracing::warn!("{}", e);
+                }
+            }
+            Ok(response) => {
+                tracing::warn!("Crash report upload rejected with {}", response.status());
+                break;
+            }
+            Err(e) => {
+                tracing::warn!("Failed to upload crash report: {}", e);
+                break;
+            }
+        }
+    }
+}
+
+// Resolve where reports go and upload any left over from previous runs
+pub fn init(app: &AppHandle) -> Result<(), String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to resolve app data dir: {}", e))?
+        .join(CRASH_DIR);
+    prune(&dir);
+    let _ = CRASH_DIR_PATH.set(dir.clone());
+    tauri::async_runtime::spawn(upload_pending(dir));
+    Ok(())
+}
+
+// Locally stored reports, newest first
+#[tauri::command]
+pub fn get_crash_reports() -> Vec<CrashReport> {
+    CRASH_DIR_PATH
+        .get()
+        .map(|dir| read_all(dir))
+        .unwrap_or_default()
+}
+
+#[tauri::command]
+pub fn clear_crash_reports() -> Result<(), AppError> {
+    let Some(dir) = CRASH_DIR_PATH.get() else {
+        return Ok(());
+    };
+    match fs::remove_dir_all(dir) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(AppError::Io(format!(
+            "Failed to remove {}: {}",
+            dir.display(),
+            e
+        ))),
+    }
+}
+
+// Opt in or out of sending crash reports; pending reports are sent on the next launch
+#[tauri::command]
+pub fn set_crash_upload_consent(
+    app: AppHandle,
+    enabled: bool,
+    url: Option<String>,
+) -> Result<(), AppError> {
+    settings::update(&app, |settings| {
+        settings.crash_upload_consent = enabled;
+        if url.is_some() {
+            settings.crash_report_url = url;
+        }
+    })?;
+    Ok(())
+}
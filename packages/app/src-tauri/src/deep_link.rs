@@ -0,0 +1,275 @@
+use std::sync::Mutex;
+
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Url};
+use tauri_plugin_deep_link::DeepLinkExt;
+
+use crate::auth::oauth;
+
+// OAuth providers should redirect to elizaos://oauth/callback
+pub const SCHEME: &str = "elizaos";
+
+// Everything else is rejected before it reaches the UI
+const ROUTES: &[&str] = &["oauth", "chat", "settings", "import-character"];
+// Longest prefilled chat message accepted from a link
+const MAX_TEXT_LEN: usize = 4000;
+const MAX_ID_LEN: usize = 64;
+const MAX_SETTINGS_DEPTH: usize = 3;
+
+// Payload of the `deep-link` event
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "route", rename_all = "kebab-case")]
+pub enum DeepLinkRoute {
+    // elizaos://chat/<agent-id>?text=...
+    Chat {
+        agent_id: String,
+        text: Option<String>,
+    },
+    // elizaos://settings/<section>/...
+    Settings {
+        path: Vec<String>,
+    },
+    // elizaos://import-character?url=https://...
+    ImportCharacter {
+        url: String,
+    },
+}
+
+// Payload of the `deep-link-rejected` event
+#[derive(Debug, Clone, Serialize)]
+struct DeepLinkRejected {
+    route: String,
+    reason: String,
+}
+
+// A link that arrived before the frontend could listen, kept until it asks for it
+static PENDING: Lazy<Mutex<Option<DeepLinkRoute>>> = Lazy::new(|| Mutex::new(None));
+
+// Ids and settings sections are made of letters, digits, `-` and `_`
+fn identifier(value: &str, what: &str) -> Result<String, String> {
+    if value.is_empty() || value.len() > MAX_ID_LEN {
+        return Err(format!("Invalid {}", what));
+    }
+    if !value
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(format!("Invalid {}: {}", what, value));
+    }
+    Ok(value.to_string())
+}
+
+fn segments(url: &Url) -> Vec<&str> {
+    url.path_segments()
+        .map(|segments| segments.filter(|s| !s.is_empty()).collect())
+        .unwrap_or_default()
+}
+
+fn query(url: &Url, key: &str) -> Option<String> {
+    url.query_pairs()
+        .find(|(name, _)| name == key)
+        .map(|(_, value)| value.into_owned())
+}
+
+fn parse_route(route: &str, url: &Url) -> Result<DeepLinkRoute, String> {
+    match route {
+        "chat" => {
+            let [agent_id] = segments(url)[..] else {
+                return Err("Expected elizaos://chat/<agent-id>".to_string());
+            };
+            let text = query(url, "text").filter(|text| !text.trim().is_empty());
+            if text.as_ref().is_some_and(|text| text.len() > MAX_TEXT_LEN) {
+                return Err(format!("The text is longer than {} bytes", MAX_TEXT_LEN));
+            }
+            Ok(DeepLinkRoute::Chat {
+                agent_id: identifier(agent_id, "agent id")?,
+                text,
+            })
+        }
+        "settings" => {
+            let path = segments(url);
+            if path.len() > MAX_SETTINGS_DEPTH {
+                return Err("The settings path is too deep".to_string());
+            }
+            Ok(DeepLinkRoute::Settings {
+                path: path
+                    .into_iter()
+                    .map(|segment| identifier(segment, "settings section"))
+                    .collect::<Result<_, _>>()?,
+            })
+        }
+        "import-character" => {
+            let source = query(url, "url").ok_or("Missing the url parameter")?;
+            let source = Url::parse(&source).map_err(|e| format!("Invalid url: {}", e))?;
+            // Plain http would let anyone on the network swap the character
+            if source.scheme() != "https" {
+                return Err("Characters can only be imported over https".to_string());
+            }
+            Ok(DeepLinkRoute::ImportCharacter {
+                url: source.to_string(),
+            })
+        }
+        _ => Err("Unknown route".to_string()),
+    }
+}
+
+fn dispatch(app: &AppHandle, route: DeepLinkRoute) {
+    #[cfg(desktop)]
+    crate::tray::show_main_window(app);
+    *PENDING.lock().unwrap() = Some(route.clone());
+    if let Err(e) = app.emit("deep-link", route) {
+        tracing::warn!("Failed to emit deep link: {}", e);
+    }
+}
+
+pub fn handle_url(app: &AppHandle, url: &str) {
+    let parsed = match Url::parse(url) {
+        Ok(parsed) if parsed.scheme() == SCHEME => parsed,
+        Ok(_) => return,
+        Err(e) => {
+            tracing::warn!("Ignoring malformed deep link: {}", e);
+            return;
+        }
+    };
+
+    let route = parsed.host_str().unwrap_or_default().to_string();
+    if route == "oauth" {
+        let app = app.clone();
+        let url = url.to_string();
+        tauri::async_runtime::spawn(async move {
+            if let Err(e) = oauth::handle_oauth_callback(app, url).await {
+                tracing::warn!("OAuth callback failed: {}", e);
+            }
+        });
+        return;
+    }
+
+    let result = if ROUTES.contains(&route.as_str()) {
+        parse_route(&route, &parsed)
+    } else {
+        Err("Unknown route".to_string())
+    };
+    match result {
+        Ok(parsed) => dispatch(app, parsed),
+        Err(reason) => {
+            // Only the route is logged; links can carry message text
+            tracing::warn!("Rejected deep link to {:?}: {}", route, reason);
+            let _ = app.emit("deep-link-rejected", DeepLinkRejected { route, reason });
+        }
+    }
+}
+
+pub fn init(app: &AppHandle) -> Result<(), Box<dyn std::error::Error>> {
+    // Installed builds register the scheme via the bundle; dev builds need it at runtime
+    #[cfg(any(target_os = "linux", all(debug_assertions, windows)))]
+    app.deep_link().register_all()?;
+
+    let handle = app.clone();
+    app.deep_link().on_open_url(move |event| {
+        for url in event.urls() {
+            handle_url(&handle, url.as_str());
+        }
+    });
+
+    // A link that launched this instance
+    if let Some(urls) = app.deep_link().get_current()? {
+        for url in urls {
+            handle_url(app, url.as_str());
+        }
+    }
+    Ok(())
+}
+
+// The last routed link, cleared once read; lets the UI handle the link that launched the app
+#[tauri::command]
+pub fn take_pending_deep_link() -> Option<DeepLinkRoute> {
+    PENDING.lock().unwrap().take()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn route(link: &str) -> Result<serde_json::Value, String> {
+        let url = Url::parse(link).unwrap();
+        let route = url.host_str().unwrap_or_default().to_string();
+        parse_route(&route, &url).map(|parsed| serde_json::to_value(parsed).unwrap())
+    }
+
+    #[test]
+    fn identifiers() {
+        assert_eq!(identifier("agent-1_b", "agent id").unwrap(), "agent-1_b");
+        assert!(identifier("", "agent id").is_err());
+        assert!(identifier(&"a".repeat(MAX_ID_LEN + 1), "agent id").is_err());
+        assert!(identifier(&"a".repeat(MAX_ID_LEN), "agent id").is_ok());
+        for value in ["..", "a/b", "a b", "ag%65nt", "<script>", "é"] {
+            assert!(identifier(value, "agent id").is_err(), "{}", value);
+        }
+    }
+
+    #[test]
+    fn chat_links() {
+        let parsed = route("elizaos://chat/agent-1?text=Hello%20there").unwrap();
+        assert_eq!(parsed["route"], "chat");
+        assert_eq!(parsed["agent_id"], "agent-1");
+        assert_eq!(parsed["text"], "Hello there");
+
+        let parsed = route("elizaos://chat/agent-1/?text=%20%20").unwrap();
+        assert!(parsed["text"].is_null());
+
+        assert!(route("elizaos://chat").is_err());
+        assert!(route("elizaos://chat/a/b").is_err());
+        assert!(route("elizaos://chat/..%2Fsecret").is_err());
+        let long = format!("elizaos://chat/agent?text={}", "a".repeat(MAX_TEXT_LEN + 1));
+        assert!(route(&long).is_err());
+    }
+
+    #[test]
+    fn settings_links() {
+        let parsed = route("elizaos://settings").unwrap();
+        assert_eq!(parsed["route"], "settings");
+        assert_eq!(parsed["path"], serde_json::json!([]));
+
+        let parsed = route("elizaos://settings/providers/openai").unwrap();
+        assert_eq!(parsed["path"], serde_json::json!(["providers", "openai"]));
+
+        assert!(route("elizaos://settings/a/b/c/d").is_err());
+        assert!(route("elizaos://settings/providers/bad%20name").is_err());
+    }
+
+    #[test]
+    fn import_character_links() {
+        let parsed =
+            route("elizaos://import-character?url=https%3A%2F%2Fexample.com%2Fcharacter.json")
+                .unwrap();
+        assert_eq!(parsed["route"], "import-character");
+        assert_eq!(parsed["url"], "https://example.com/character.json");
+
+        for link in [
+            "elizaos://import-character",
+            "elizaos://import-character?url=not%20a%20url",
+            "elizaos://import-character?url=http%3A%2F%2Fexample.com%2Fc.json",
+            "elizaos://import-character?url=file%3A%2F%2F%2Fetc%2Fpasswd",
+            "elizaos://import-character?url=javascript%3Aalert(1)",
+        ] {
+            assert!(route(link).is_err(), "{}", link);
+        }
+    }
+
+    #[test]
+    fn unknown_routes() {
+        assert!(route("elizaos://shell/run").is_err());
+        assert!(!ROUTES.contains(&"shell"));
+        // Every listed route but the OAuth callback is parsed here
+        for known in ROUTES.iter().filter(|known| **known != "oauth") {
+            let url = Url::parse(&format!("{}://{}/", SCHEME, known)).unwrap();
+            assert_ne!(
+                parse_route(known, &url).err().as_deref(),
+                Some("Unknown route"),
+                "{}",
+                known
+            );
+        }
+    }
+}
@@ -0,0 +1,227 @@
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use serde_json::{json, Value};
+use sysinfo::System;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_dialog::DialogExt;
+use zip::write::SimpleFileOptions;
+use zip::ZipWriter;
+
+use crate::audit;
+use crate::capabilities::{self, Capability};
+use crate::error::AppError;
+use crate::redaction::{self, REDACTED};
+use crate::server::{logs, metrics};
+use crate::startup;
+use crate::{cli, config, crash, settings};
+
+// Key names whose values never leave the machine
+const SENSITIVE_MARKERS: &[&str] = &["KEY", "TOKEN", "SECRET", "PASSWORD", "CREDENTIAL", "AUTH"];
+
+fn is_sensitive(key: &str) -> bool {
+    let key = key.to_ascii_uppercase();
+    SENSITIVE_MARKERS.iter().any(|marker| key.contains(marker))
+}
+
+// Replace the values of sensitive-looking keys anywhere in a JSON document
+fn redact(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                if is_sensitive(key) && !value.is_null() {
+                    *value = json!(REDACTED);
+                } else {
+                    redact(value);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(redact),
+        _ => {}
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct SystemInfo {
+    app_version: String,
+    os: Option<String>,
+    os_version: Option<String>,
+    kernel_version: Option<String>,
+    arch: String,
+    cpu: Option<String>,
+    physical_cores: Option<usize>,
+    logical_cores: usize,
+    total_memory_bytes: u64,
+    cli_path: Option<PathBuf>,
+    cli_version: Option<String>,
+}
+
+fn system_info(app: &AppHandle) -> SystemInfo {
+    let mut system = System::new();
+    system.refresh_cpu_list(sysinfo::CpuRefreshKind::nothing());
+    system.refresh_memory();
+
+    let cli_path = cli::resolve(app);
+    let cli_version = cli_path
+        .as_deref()
+        .map(|cli| cli::version(app, cli).unwrap_or_else(|e| format!("unavailable ({})", e)));
+
+    SystemInfo {
+        app_version: app.package_info().version.to_string(),
+        os: System::name(),
+        os_version: System::long_os_version(),
+        kernel_version: System::kernel_version(),
+        arch: System::cpu_arch(),
+        cpu: system.cpus().first().map(|cpu| cpu.brand().to_string()),
+        physical_cores: System::physical_core_count(),
+        logical_cores: system.cpus().len(),
+        total_memory_bytes: system.total_memory(),
+        cli_path,
+        cli_version,
+    }
+}
+
+// `.env` entries with sensitive values masked; keychain-backed values are never read
+fn redacted_env() -> String {
+    match config::read_env() {
+        Ok(vars) => vars
+            .into_iter()
+            .map(|var| {
+                let value = match var.value {
+                    _ if var.secret => "[keychain]".to_string(),
+                    Some(_) if is_sensitive(&var.key) => REDACTED.to_string(),
+                    Some(value) => value,
+                    None => String::new(),
+                };
+                format!("{}={}\n", var.key, value)
+            })
+            .collect(),
+        Err(e) => format!("# {}\n", e),
+    }
+}
+
+struct Bundle {
+    zip: ZipWriter<File>,
+    options: SimpleFileOptions,
+}
+
+impl Bundle {
+    fn add(&mut self, name: &str, contents: &[u8]) -> Result<(), String> {
+        self.zip
+            .start_file(name, self.options)
+            .map_err(|e| format!("Failed to add {} to the bundle: {}", name, e))?;
+        self.zip
+            .write_all(contents)
+            .map_err(|e| format!("Failed to add {} to the bundle: {}", name, e))
+    }
+
+    fn add_json(&mut self, name: &str, value: &impl Serialize) -> Result<(), String> {
+        let contents = serde_json::to_vec_pretty(value).map_err(|e| e.to_string())?;
+        self.add(name, &contents)
+    }
+
+    // Copy every file in `dir` under `prefix/`, skipping the directory if it doesn't exist.
+    // Files may predate the current redaction patterns, so they are masked again.
+    fn add_dir(&mut self, prefix: &str, dir: &Path) -> Result<(), String> {
+        let Ok(entries) = fs::read_dir(dir) else {
+            return Ok(());
+        };
+        for path in entries.filter_map(Result::ok).map(|entry| entry.path()) {
+            let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+                continue;
+            };
+            if !path.is_file() {
+                continue;
+            }
+            match fs::read(&path) {
+                Ok(contents) => {
+                    let contents = String::from_utf8_lossy(&contents);
+                    let contents = redaction::redact(&contents);
+                    self.add(&format!("{}/{}", prefix, name), contents.as_bytes())?
+                }
+                Err(e) => tracing::warn!("Skipping {} in diagnostics: {}", path.display(), e),
+            }
+        }
+        Ok(())
+    }
+}
+
+fn write_bundle(app: &AppHandle, destination: &Path) -> Result<(), String> {
+    let file = File::create(destination)
+        .map_err(|e| format!("Failed to create {}: {}", destination.display(), e))?;
+    let mut bundle = Bundle {
+        zip: ZipWriter::new(file),
+        options: SimpleFileOptions::default(),
+    };
+
+    bundle.add_json("system.json", &system_info(app))?;
+
+    let mut settings = serde_json::to_value(settings::current()).map_err(|e| e.to_string())?;
+    redact(&mut settings);
+    bundle.add_json("settings.json", &settings)?;
+    bundle.add("env.txt", redacted_env().as_bytes())?;
+    if let Some(audit_log) = audit::redacted() {
+        bundle.add("audit.jsonl", audit_log.as_bytes())?;
+    }
+
+    let server_logs = logs::get_server_logs(None)
+        .iter()
+        .filter_map(|line| serde_json::to_string(line).ok())
+        .collect::<Vec<_>>()
+        .join("\n");
+    bundle.add("server-logs.jsonl", server_logs.as_bytes())?;
+    bundle.add_json(
+        "server-metrics.json",
+        &metrics::get_server_metrics_history(),
+    )?;
+    bundle.add_json("startup-timings.json", &startup::get_startup_timings())?;
+
+    if let Ok(dir) = app.path().app_log_dir() {
+        bundle.add_dir("app-logs", &dir)?;
+    }
+    if let Ok(dir) = app.path().app_data_dir() {
+        bundle.add_dir("crashes", &dir.join(crash::CRASH_DIR))?;
+    }
+
+    bundle
+        .zip
+        .finish()
+        .map_err(|e| format!("Failed to finish {}: {}", destination.display(), e))?;
+    Ok(())
+}
+
+// Write a zip for bug reports to `path`, or to a location the user picks. Returns None
+// if the user cancels the save dialog.
+#[tauri::command]
+pub async fn export_diagnostics(
+    app: AppHandle,
+    path: Option<PathBuf>,
+) -> Result<Option<PathBuf>, AppError> {
+    capabilities::require(Capability::ExportData)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let destination = match path {
+            Some(path) => path,
+            None => {
+                let picked = app
+                    .dialog()
+                    .file()
+                    .set_title("Export diagnostics")
+                    .set_file_name("eliza-diagnostics.zip")
+                    .add_filter("Zip archive", &["zip"])
+                    .blocking_save_file();
+                match picked {
+                    Some(picked) => picked.into_path().map_err(|e| e.to_string())?,
+                    None => return Ok(None),
+                }
+            }
+        };
+
+        write_bundle(&app, &destination)?;
+        tracing::info!(path = %destination.display(), "Exported diagnostics");
+        Ok(Some(destination))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
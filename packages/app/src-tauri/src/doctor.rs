@@ -0,0 +1,404 @@
+use std::process::Command;
+use std::time::Duration;
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::auth::secure_store::{self, SecretBackend};
+use crate::cli::path::find_tool;
+use crate::cli::{self, install::CLI_VERSION, version::parse_version};
+use crate::error::AppError;
+use crate::server::{self, config, port, ServerStatus};
+use crate::{hardware, local_models, secrets};
+
+// elizaOS 1.x needs a recent Node.js runtime
+const MIN_NODE_MAJOR: u64 = 23;
+// Below this the app data dir can't hold a local model, and the database may fill it
+const MIN_FREE_DISK: u64 = 5 * 1024 * 1024 * 1024;
+const REACH_TIMEOUT: Duration = Duration::from_secs(5);
+
+// Base URLs of the providers in `secrets::PROVIDERS`; any HTTP response means reachable
+const PROVIDER_URLS: &[(&str, &str)] = &[
+    ("openai", "https://api.openai.com/v1"),
+    ("anthropic", "https://api.anthropic.com/v1"),
+    ("google", "https://generativelanguage.googleapis.com"),
+    ("groq", "https://api.groq.com/openai/v1"),
+    ("openrouter", "https://openrouter.ai/api/v1"),
+    ("together", "https://api.together.xyz/v1"),
+    ("mistral", "https://api.mistral.ai/v1"),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Pass,
+    Warn,
+    Fail,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DoctorCheck {
+    pub id: String,
+    pub title: String,
+    pub status: CheckStatus,
+    pub detail: String,
+    // What to do about a warning or failure
+    pub hint: Option<String>,
+}
+
+// Payload of the `doctor-progress` event, sent as each check finishes
+#[derive(Debug, Clone, Serialize)]
+struct DoctorProgress<'a> {
+    check: &'a DoctorCheck,
+    completed: usize,
+    total: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DoctorReport {
+    pub checks: Vec<DoctorCheck>,
+    // Nothing failed; warnings don't stop the server from running
+    pub healthy: bool,
+}
+
+fn check(id: &str, title: &str, status: CheckStatus, detail: String) -> DoctorCheck {
+    DoctorCheck {
+        id: id.to_string(),
+        title: title.to_string(),
+        status,
+        detail,
+        hint: None,
+    }
+}
+
+impl DoctorCheck {
+    fn hint(mut self, hint: &str) -> Self {
+        self.hint = Some(hint.to_string());
+        self
+    }
+}
+
+fn tool_version(app: &AppHandle, name: &str) -> Option<Result<String, String>> {
+    let path = find_tool(app, name)?;
+    Some(
+        Command::new(&path)
+            .arg("--version")
+            .output()
+            .map_err(|e| format!("Failed to run {}: {}", path.display(), e))
+            .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string()),
+    )
+}
+
+fn check_node(app: &AppHandle) -> DoctorCheck {
+    let title = "Node.js";
+    match tool_version(app, "node") {
+        None => check(
+            "node",
+            title,
+            CheckStatus::Fail,
+            "node was not found".to_string(),
+        )
+        .hint("Install Node.js 23 or later from nodejs.org"),
+        Some(Err(e)) => check("node", title, CheckStatus::Fail, e),
+        Some(Ok(version)) => match parse_version(&version).and_then(|v| v.first().copied()) {
+            Some(major) if major >= MIN_NODE_MAJOR => {
+                check("node", title, CheckStatus::Pass, version)
+            }
+            _ => check(
+                "node",
+                title,
+                CheckStatus::Fail,
+                format!("{} is older than {}", version, MIN_NODE_MAJOR),
+            )
+            .hint("Upgrade Node.js to version 23 or later"),
+        },
+    }
+}
+
+fn check_bun(app: &AppHandle) -> DoctorCheck {
+    let title = "Bun";
+    match tool_version(app, "bun") {
+        Some(Ok(version)) => check("bun", title, CheckStatus::Pass, version),
+        Some(Err(e)) => check("bun", title, CheckStatus::Warn, e),
+        None => check(
+            "bun",
+            title,
+            CheckStatus::Warn,
+            "bun was not found".to_string(),
+        )
+        .hint("Projects created with bun need it to install plugins; see bun.sh"),
+    }
+}
+
+fn check_cli(app: &AppHandle) -> DoctorCheck {
+    let title = "elizaos CLI";
+    if server::sidecar::enabled() {
+        return match server::sidecar::sidecar_path(app) {
+            Some(path) => check(
+                "cli",
+                title,
+                CheckStatus::Pass,
+                format!("Using the bundled server at {}", path.display()),
+            ),
+            None => check(
+                "cli",
+                title,
+                CheckStatus::Fail,
+                "The bundled server is missing".to_string(),
+            )
+            .hint("Reinstall the app, or switch to a system CLI in the settings"),
+        };
+    }
+    if server::container::enabled() {
+        return match server::container::detect(app) {
+            Ok(engine) => check(
+                "cli",
+                title,
+                CheckStatus::Pass,
+                format!(
+                    "Running in a container with {} {}",
+                    engine.path.display(),
+                    engine.version
+                ),
+            ),
+            Err(e) => check("cli", title, CheckStatus::Fail, e)
+                .hint("Start Docker or Podman, or run the server as a process in the settings"),
+        };
+    }
+    if server::runtime::enabled() {
+        let runtime = server::runtime::installed(app);
+        let entry = cli::install::installed_entry(app);
+        return match (runtime, entry) {
+            (Some(bun), Some(entry)) => check(
+                "cli",
+                title,
+                CheckStatus::Pass,
+                format!("Running {} with {}", entry.display(), bun.display()),
+            ),
+            _ => check(
+                "cli",
+                title,
+                CheckStatus::Fail,
+                "The app's runtime or its CLI isn't installed".to_string(),
+            )
+            .hint("Run setup again, or reinstall the runtime from the settings"),
+        };
+    }
+    let Some(path) = cli::resolve(app) else {
+        return check(
+            "cli",
+            title,
+            CheckStatus::Fail,
+            "The CLI was not found".to_string(),
+        )
+        .hint("Install it from the setup screen or with `npm install -g @elizaos/cli`");
+    };
+    match cli::version(app, &path) {
+        Err(e) => check("cli", title, CheckStatus::Fail, e)
+            .hint("Reinstall the CLI; the current one doesn't run"),
+        Ok(version) if parse_version(&version) < parse_version(CLI_VERSION) => check(
+            "cli",
+            title,
+            CheckStatus::Warn,
+            format!(
+                "{} at {} is older than {}",
+                version,
+                path.display(),
+                CLI_VERSION
+            ),
+        )
+        .hint("Upgrade the CLI from the settings"),
+        Ok(version) => check(
+            "cli",
+            title,
+            CheckStatus::Pass,
+            format!("{} at {}", version, path.display()),
+        ),
+    }
+}
+
+fn check_port() -> DoctorCheck {
+    let title = "Server port";
+    let config = config::current();
+    if server::status() == ServerStatus::Running {
+        return check(
+            "port",
+            title,
+            CheckStatus::Pass,
+            format!("The server is running on {}", config.address()),
+        );
+    }
+    if port::is_port_free(&config.host, config.port) {
+        return check(
+            "port",
+            title,
+            CheckStatus::Pass,
+            format!("{} is free", config.address()),
+        );
+    }
+    let status = if config.auto_select_port {
+        CheckStatus::Warn
+    } else {
+        CheckStatus::Fail
+    };
+    check(
+        "port",
+        title,
+        status,
+        format!("{} is in use by another program", config.address()),
+    )
+    .hint("Close the other program or choose a different port in the settings")
+}
+
+fn check_keychain() -> DoctorCheck {
+    let title = "Secret storage";
+    match secure_store::backend_blocking() {
+        SecretBackend::Keychain => check(
+            "keychain",
+            title,
+            CheckStatus::Pass,
+            "Secrets are stored in the OS keychain".to_string(),
+        ),
+        SecretBackend::EncryptedFile => check(
+            "keychain",
+            title,
+            CheckStatus::Warn,
+            "The OS keychain is unavailable; secrets are kept in an encrypted file".to_string(),
+        )
+        .hint("On Linux, install and unlock a Secret Service provider such as GNOME Keyring"),
+    }
+}
+
+fn check_disk(app: &AppHandle) -> DoctorCheck {
+    let title = "Disk space";
+    let disk = app
+        .path()
+        .app_data_dir()
+        .ok()
+        .and_then(|dir| hardware::disk_for(&dir));
+    match disk {
+        None => check(
+            "disk",
+            title,
+            CheckStatus::Warn,
+            "Could not determine free disk space".to_string(),
+        ),
+        Some(disk) => {
+            let detail = format!(
+                "{} GB free on {}",
+                disk.available_bytes / (1024 * 1024 * 1024),
+                disk.mount_point.display()
+            );
+            if disk.available_bytes < MIN_FREE_DISK {
+                check("disk", title, CheckStatus::Warn, detail)
+                    .hint("Free up space before downloading local models")
+            } else {
+                check("disk", title, CheckStatus::Pass, detail)
+            }
+        }
+    }
+}
+
+async fn check_provider(client: &reqwest::Client, provider: &str, url: &str) -> DoctorCheck {
+    let id = format!("provider:{}", provider);
+    let title = format!("Reach {}", provider);
+    match client.get(url).send().await {
+        Ok(response) => check(
+            &id,
+            &title,
+            CheckStatus::Pass,
+            format!("{} answered with {}", url, response.status()),
+        ),
+        Err(e) => check(&id, &title, CheckStatus::Fail, format!("{}: {}", url, e))
+            .hint("Check the network connection and proxy settings"),
+    }
+}
+
+async fn check_ollama() -> DoctorCheck {
+    let status = local_models::detect_ollama().await;
+    let title = "Reach Ollama";
+    match status.version {
+        Some(version) => check(
+            "provider:ollama",
+            title,
+            CheckStatus::Pass,
+            format!("Ollama {} at {}", version, status.url),
+        ),
+        None => check(
+            "provider:ollama",
+            title,
+            CheckStatus::Fail,
+            format!("Nothing answered at {}", status.url),
+        )
+        .hint("Start Ollama, or choose another model provider"),
+    }
+}
+
+async fn run_blocking<T: Send + 'static>(
+    task: impl FnOnce() -> T + Send + 'static,
+) -> Result<T, String> {
+    tauri::async_runtime::spawn_blocking(task)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+// Check the runtime, CLI, port, keychain, disk and configured providers, emitting
+// `doctor-progress` as each check finishes
+#[tauri::command]
+pub async fn run_doctor(app: AppHandle) -> Result<DoctorReport, AppError> {
+    let (providers, uses_ollama) = run_blocking(|| {
+        let providers: Vec<(&str, &str)> = secrets::list_providers()
+            .into_iter()
+            .filter(|provider| provider.configured)
+            .filter_map(|provider| {
+                PROVIDER_URLS
+                    .iter()
+                    .find(|(name, _)| *name == provider.provider)
+            })
+            .copied()
+            .collect();
+        let uses_ollama = crate::config::read_env()
+            .is_ok_and(|vars| vars.iter().any(|var| var.key == "OLLAMA_API_ENDPOINT"));
+        (providers, uses_ollama)
+    })
+    .await?;
+    let total = 6 + providers.len() + usize::from(uses_ollama);
+
+    let mut checks = Vec::with_capacity(total);
+    let mut record = |check: DoctorCheck| {
+        let progress = DoctorProgress {
+            check: &check,
+            completed: checks.len() + 1,
+            total,
+        };
+        if let Err(e) = app.emit("doctor-progress", progress) {
+            tracing::warn!("Failed to emit doctor progress: {}", e);
+        }
+        checks.push(check);
+    };
+
+    let handle = app.clone();
+    record(run_blocking(move || check_node(&handle)).await?);
+    let handle = app.clone();
+    record(run_blocking(move || check_bun(&handle)).await?);
+    let handle = app.clone();
+    record(run_blocking(move || check_cli(&handle)).await?);
+    record(run_blocking(check_port).await?);
+    record(run_blocking(check_keychain).await?);
+    let handle = app.clone();
+    record(run_blocking(move || check_disk(&handle)).await?);
+
+    let client = crate::network::builder()
+        .timeout(REACH_TIMEOUT)
+        .build()
+        .map_err(|e| e.to_string())?;
+    for (provider, url) in providers {
+        record(check_provider(&client, provider, url).await);
+    }
+    if uses_ollama {
+        record(check_ollama().await);
+    }
+
+    let healthy = checks.iter().all(|check| check.status != CheckStatus::Fail);
+    Ok(DoctorReport { checks, healthy })
+}
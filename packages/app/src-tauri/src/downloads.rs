@@ -0,0 +1,668 @@
+use std::collections::BTreeMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use futures_util::future::try_join_all;
+use reqwest::header;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256, Sha512};
+use tauri::{AppHandle, Emitter, Url};
+use tokio::sync::watch;
+
+use crate::error::AppError;
+use crate::hardware;
+use crate::history::new_id;
+
+// Files at least this large are fetched in parallel ranges when the server allows it
+const MIN_PARALLEL_BYTES: u64 = 32 * 1024 * 1024;
+const PARALLEL_CHUNKS: u64 = 4;
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+// The resume state is written every this many progress ticks, and whenever a transfer stops
+const SAVE_EVERY_TICKS: u32 = 8;
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(15);
+// A connection that sends nothing for this long is dropped and resumed
+const STALL_TIMEOUT: Duration = Duration::from_secs(30);
+const MAX_ATTEMPTS: u32 = 4;
+const RETRY_DELAY: Duration = Duration::from_secs(2);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DownloadState {
+    Downloading,
+    Paused,
+    Verifying,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+// Payload of the `download-progress` event, sent a few times a second while a file
+// downloads and once when it finishes
+#[derive(Debug, Clone, Serialize)]
+pub struct DownloadInfo {
+    pub id: String,
+    // What the download is for, e.g. `stt-model` or `cli`
+    pub kind: &'static str,
+    pub label: String,
+    pub url: String,
+    pub path: PathBuf,
+    pub state: DownloadState,
+    pub downloaded: u64,
+    // `None` when the server doesn't say how large the file is
+    pub total: Option<u64>,
+    pub bytes_per_second: u64,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub enum Checksum {
+    Sha256(Vec<u8>),
+    Sha512(Vec<u8>),
+}
+
+impl Checksum {
+    // A Subresource Integrity string, as npm publishes: `sha512-<base64>`
+    pub fn from_integrity(integrity: &str) -> Result<Self, String> {
+        let (algorithm, digest) = integrity
+            .split_once('-')
+            .ok_or_else(|| format!("Unsupported integrity format: {}", integrity))?;
+        let digest = STANDARD
+            .decode(digest)
+            .map_err(|e| format!("Invalid integrity checksum: {}", e))?;
+        match algorithm {
+            "sha256" => Ok(Checksum::Sha256(digest)),
+            "sha512" => Ok(Checksum::Sha512(digest)),
+            _ => Err(format!("Unsupported integrity format: {}", integrity)),
+        }
+    }
+
+    // A hex SHA-256 digest, as in a `SHASUMS256.txt` listing
+    pub fn from_sha256_hex(hex: &str) -> Result<Self, String> {
+        let hex = hex.trim();
+        if hex.len() != 64 || !hex.is_ascii() {
+            return Err(format!("Invalid SHA-256 checksum: {}", hex));
+        }
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16))
+            .collect::<Result<Vec<_>, _>>()
+            .map(Checksum::Sha256)
+            .map_err(|_| format!("Invalid SHA-256 checksum: {}", hex))
+    }
+
+    fn matches(&self, path: &Path) -> Result<bool, String> {
+        fn digest<D: Digest>(path: &Path) -> Result<Vec<u8>, String> {
+            let mut file = File::open(path)
+                .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+            let mut hasher = D::new();
+            let mut buffer = vec![0u8; 1024 * 1024];
+            loop {
+                let read = file
+                    .read(&mut buffer)
+                    .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+                if read == 0 {
+                    return Ok(hasher.finalize().to_vec());
+                }
+                hasher.update(&buffer[..read]);
+            }
+        }
+        Ok(match self {
+            Checksum::Sha256(expected) => digest::<Sha256>(path)? == *expected,
+            Checksum::Sha512(expected) => digest::<Sha512>(path)? == *expected,
+        })
+    }
+}
+
+// A file to download to `path`
+#[derive(Debug, Clone)]
+pub struct Download {
+    pub kind: &'static str,
+    pub label: String,
+    pub url: Url,
+    pub path: PathBuf,
+    pub checksum: Option<Checksum>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Control {
+    Run,
+    Pause,
+    Cancel,
+}
+
+struct Job {
+    info: DownloadInfo,
+    control: watch::Sender<Control>,
+}
+
+// Kept next to the partial file so a download picks up where it stopped, even after a restart
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ResumeState {
+    url: String,
+    total: Option<u64>,
+    // ETag or Last-Modified; a different one means the file changed and must start over
+    validator: Option<String>,
+    ranged: bool,
+    chunks: Vec<Chunk>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct Chunk {
+    start: u64,
+    // Exclusive; `None` reads to the end of the response
+    end: Option<u64>,
+    done: u64,
+}
+
+impl Chunk {
+    fn finished(&self) -> bool {
+        self.end.is_some_and(|end| self.start + self.done >= end)
+    }
+}
+
+struct Probe {
+    total: Option<u64>,
+    ranged: bool,
+    validator: Option<String>,
+}
+
+static JOBS: Mutex<BTreeMap<String, Job>> = Mutex::new(BTreeMap::new());
+
+fn sibling(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(suffix);
+    path.with_file_name(name)
+}
+
+fn part_path(path: &Path) -> PathBuf {
+    sibling(path, ".part")
+}
+
+fn state_path(path: &Path) -> PathBuf {
+    sibling(path, ".part.json")
+}
+
+fn client() -> Result<reqwest::Client, String> {
+    crate::network::builder()
+        .connect_timeout(CONNECT_TIMEOUT)
+        .read_timeout(STALL_TIMEOUT)
+        .build()
+        .map_err(|e| e.to_string())
+}
+
+fn emit(app: &AppHandle, info: &DownloadInfo) {
+    if let Err(e) = app.emit("download-progress", info) {
+        tracing::warn!("Failed to emit download progress: {}", e);
+    }
+}
+
+fn update(app: &AppHandle, id: &str, f: impl FnOnce(&mut DownloadInfo)) {
+    let info = JOBS.lock().unwrap().get_mut(id).map(|job| {
+        f(&mut job.info);
+        job.info.clone()
+    });
+    if let Some(info) = info {
+        emit(app, &info);
+    }
+}
+
+fn finish(app: &AppHandle, id: &str, state: DownloadState, error: Option<String>) {
+    let Some(mut job) = JOBS.lock().unwrap().remove(id) else {
+        return;
+    };
+    job.info.state = state;
+    job.info.error = error;
+    job.info.bytes_per_second = 0;
+    emit(app, &job.info);
+}
+
+fn load_state(path: &Path, url: &str) -> Option<ResumeState> {
+    let state: ResumeState = serde_json::from_slice(&fs::read(state_path(path)).ok()?).ok()?;
+    // The partial file must still hold at least what the state says was written
+    let written = fs::metadata(part_path(path)).ok()?.len();
+    let complete = state
+        .chunks
+        .iter()
+        .all(|chunk| chunk.start + chunk.done <= written);
+    (state.url == url && complete).then_some(state)
+}
+
+fn save_state(path: &Path, state: &ResumeState) {
+    let target = state_path(path);
+    let written = serde_json::to_vec(state)
+        .map_err(|e| e.to_string())
+        .and_then(|json| fs::write(&target, json).map_err(|e| e.to_string()));
+    if let Err(e) = written {
+        tracing::warn!("Failed to save {}: {}", target.display(), e);
+    }
+}
+
+fn discard(path: &Path) {
+    let _ = fs::remove_file(part_path(path));
+    let _ = fs::remove_file(state_path(path));
+}
+
+async fn probe(client: &reqwest::Client, url: &Url) -> Probe {
+    let Ok(response) = client
+        .head(url.clone())
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+    else {
+        return Probe {
+            total: None,
+            ranged: false,
+            validator: None,
+        };
+    };
+    let headers = response.headers();
+    let text = |name: header::HeaderName| {
+        headers
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string)
+    };
+    Probe {
+        total: text(header::CONTENT_LENGTH).and_then(|length| length.parse().ok()),
+        ranged: text(header::ACCEPT_RANGES).is_some_and(|ranges| ranges == "bytes"),
+        validator: text(header::ETAG).or_else(|| text(header::LAST_MODIFIED)),
+    }
+}
+
+// Split the file into the ranges to fetch: several for a large file on a server that
+// accepts ranges, otherwise one
+fn plan(probe: &Probe) -> Vec<Chunk> {
+    match probe.total {
+        Some(total) if probe.ranged && total >= MIN_PARALLEL_BYTES => {
+            let size = total.div_ceil(PARALLEL_CHUNKS);
+            (0..PARALLEL_CHUNKS)
+                .map(|i| Chunk {
+                    start: i * size,
+                    end: Some(((i + 1) * size).min(total)),
+                    done: 0,
+                })
+                .collect()
+        }
+        total => vec![Chunk {
+            start: 0,
+            end: total,
+            done: 0,
+        }],
+    }
+}
+
+// Stream one range into its place in the partial file. Returns `false` if it stopped
+// because the download was paused or cancelled.
+async fn fetch_chunk(
+    client: &reqwest::Client,
+    url: &Url,
+    part: &Path,
+    mut chunk: Chunk,
+    ranged: bool,
+    done: &AtomicU64,
+    mut control: watch::Receiver<Control>,
+) -> Result<bool, String> {
+    if chunk.finished() {
+        return Ok(true);
+    }
+    let mut request = client.get(url.clone());
+    let offset = chunk.start + chunk.done;
+    if ranged && (offset > 0 || chunk.end.is_some()) {
+        let end = chunk
+            .end
+            .map(|end| (end - 1).to_string())
+            .unwrap_or_default();
+        request = request.header(header::RANGE, format!("bytes={}-{}", offset, end));
+    }
+    let mut response = request
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| format!("Failed to download {}: {}", url, e))?;
+    // The server sent the whole file; only a download fetched in one piece can use that
+    let restarted = response.status() != reqwest::StatusCode::PARTIAL_CONTENT && offset > 0;
+    if restarted && chunk.start > 0 {
+        return Err(format!("{} no longer supports resuming", url));
+    }
+
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(part)
+        .map_err(|e| format!("Failed to open {}: {}", part.display(), e))?;
+    if restarted {
+        chunk.done = 0;
+        done.store(0, Ordering::SeqCst);
+        file.set_len(chunk.end.unwrap_or(0))
+            .map_err(|e| format!("Failed to write {}: {}", part.display(), e))?;
+    }
+    file.seek(SeekFrom::Start(chunk.start + chunk.done))
+        .map_err(|e| format!("Failed to write {}: {}", part.display(), e))?;
+    loop {
+        tokio::select! {
+            next = response.chunk() => {
+                let Some(bytes) = next.map_err(|e| format!("Download interrupted: {}", e))? else {
+                    break;
+                };
+                // A server may send more than the range asked for; keep only what fits
+                let room = chunk
+                    .end
+                    .map_or(u64::MAX, |end| end - chunk.start - chunk.done)
+                    .min(bytes.len() as u64) as usize;
+                file.write_all(&bytes[..room])
+                    .map_err(|e| format!("Failed to write {}: {}", part.display(), e))?;
+                chunk.done += room as u64;
+                done.store(chunk.done, Ordering::SeqCst);
+                if chunk.finished() {
+                    break;
+                }
+            }
+            changed = control.changed() => {
+                if changed.is_err() || *control.borrow() != Control::Run {
+                    return Ok(false);
+                }
+            }
+        }
+    }
+    if chunk.end.is_some() && !chunk.finished() {
+        return Err(format!("The download of {} ended early", url));
+    }
+    Ok(true)
+}
+
+// One attempt at fetching the rest of the file. Returns `false` if it was paused or cancelled.
+async fn transfer(
+    app: &AppHandle,
+    id: &str,
+    download: &Download,
+    control: &watch::Receiver<Control>,
+) -> Result<bool, String> {
+    let client = client()?;
+    let url = download.url.to_string();
+    let probe = probe(&client, &download.url).await;
+    let mut state = match load_state(&download.path, &url) {
+        Some(state) if state.validator == probe.validator && state.total == probe.total => state,
+        _ => {
+            discard(&download.path);
+            ResumeState {
+                url,
+                total: probe.total,
+                validator: probe.validator.clone(),
+                ranged: probe.ranged,
+                chunks: plan(&probe),
+            }
+        }
+    };
+
+    let part = part_path(&download.path);
+    let written: u64 = state.chunks.iter().map(|chunk| chunk.done).sum();
+    if let Some(total) = state.total {
+        if let Some(dir) = download.path.parent() {
+            hardware::ensure_disk_space(dir, total.saturating_sub(written))?;
+        }
+        if !part.exists() {
+            // Reserve the whole file up front so every range can be written in place
+            File::create(&part)
+                .and_then(|file| file.set_len(total))
+                .map_err(|e| format!("Failed to create {}: {}", part.display(), e))?;
+        }
+    }
+    save_state(&download.path, &state);
+    update(app, id, |info| {
+        info.state = DownloadState::Downloading;
+        info.total = state.total;
+        info.downloaded = written;
+        info.error = None;
+    });
+
+    let progress: Arc<Vec<AtomicU64>> = Arc::new(
+        state
+            .chunks
+            .iter()
+            .map(|chunk| AtomicU64::new(chunk.done))
+            .collect(),
+    );
+    let work = try_join_all(
+        state
+            .chunks
+            .iter()
+            .zip(progress.iter())
+            .map(|(chunk, done)| {
+                fetch_chunk(
+                    &client,
+                    &download.url,
+                    &part,
+                    *chunk,
+                    state.ranged,
+                    done,
+                    control.clone(),
+                )
+            }),
+    );
+    tokio::pin!(work);
+    let mut ticker = tokio::time::interval(PROGRESS_INTERVAL);
+    let (mut ticks, mut last) = (0u32, written);
+    let result = loop {
+        tokio::select! {
+            result = &mut work => break result,
+            _ = ticker.tick() => {
+                let downloaded: u64 = progress.iter().map(|done| done.load(Ordering::SeqCst)).sum();
+                let speed = downloaded.saturating_sub(last) * 1000 / PROGRESS_INTERVAL.as_millis() as u64;
+                last = downloaded;
+                update(app, id, |info| {
+                    info.downloaded = downloaded;
+                    info.bytes_per_second = speed;
+                });
+                ticks += 1;
+                if ticks % SAVE_EVERY_TICKS == 0 {
+                    for (chunk, done) in state.chunks.iter_mut().zip(progress.iter()) {
+                        chunk.done = done.load(Ordering::SeqCst);
+                    }
+                    save_state(&download.path, &state);
+                }
+            }
+        }
+    };
+    for (chunk, done) in state.chunks.iter_mut().zip(progress.iter()) {
+        chunk.done = done.load(Ordering::SeqCst);
+    }
+    save_state(&download.path, &state);
+    let downloaded = state.chunks.iter().map(|chunk| chunk.done).sum();
+    update(app, id, |info| info.downloaded = downloaded);
+    Ok(result?.into_iter().all(|finished| finished))
+}
+
+async fn run(
+    app: &AppHandle,
+    id: &str,
+    download: &Download,
+    mut control: watch::Receiver<Control>,
+) -> Result<(), String> {
+    let mut attempt = 0;
+    loop {
+        let current = *control.borrow_and_update();
+        match current {
+            Control::Cancel => return Err("The download was cancelled".to_string()),
+            Control::Pause => {
+                update(app, id, |info| {
+                    info.state = DownloadState::Paused;
+                    info.bytes_per_second = 0;
+                });
+                if control.changed().await.is_err() {
+                    return Err("The download was cancelled".to_string());
+                }
+                continue;
+            }
+            Control::Run => {}
+        }
+        match transfer(app, id, download, &control).await {
+            Ok(true) => break,
+            // Paused or cancelled; the state is handled at the top of the loop
+            Ok(false) => continue,
+            Err(e) if attempt + 1 < MAX_ATTEMPTS => {
+                attempt += 1;
+                tracing::warn!(attempt, "Retrying download of {}: {}", download.url, e);
+                tokio::time::sleep(RETRY_DELAY * attempt).await;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+
+    let part = part_path(&download.path);
+    if let Some(checksum) = download.checksum.clone() {
+        update(app, id, |info| {
+            info.state = DownloadState::Verifying;
+            info.bytes_per_second = 0;
+        });
+        let check = part.clone();
+        let matches = tauri::async_runtime::spawn_blocking(move || checksum.matches(&check))
+            .await
+            .map_err(|e| e.to_string())??;
+        if !matches {
+            discard(&download.path);
+            return Err(format!(
+                "{} does not match its published checksum",
+                download.label
+            ));
+        }
+    }
+    fs::rename(&part, &download.path)
+        .map_err(|e| format!("Failed to save {}: {}", download.path.display(), e))?;
+    let _ = fs::remove_file(state_path(&download.path));
+    Ok(())
+}
+
+// Download a file, resuming any earlier partial download of the same URL to the same path.
+// Progress is reported as `download-progress` events; returns once the file is in place.
+pub async fn fetch(app: &AppHandle, download: Download) -> Result<PathBuf, String> {
+    let id = new_id();
+    let (control, receiver) = watch::channel(Control::Run);
+    {
+        let mut jobs = JOBS.lock().unwrap();
+        if jobs.values().any(|job| job.info.path == download.path) {
+            return Err(format!("{} is already downloading", download.label));
+        }
+        jobs.insert(
+            id.clone(),
+            Job {
+                info: DownloadInfo {
+                    id: id.clone(),
+                    kind: download.kind,
+                    label: download.label.clone(),
+                    url: download.url.to_string(),
+                    path: download.path.clone(),
+                    state: DownloadState::Downloading,
+                    downloaded: 0,
+                    total: None,
+                    bytes_per_second: 0,
+                    error: None,
+                },
+                control,
+            },
+        );
+    }
+    if let Some(dir) = download.path.parent() {
+        if let Err(e) = fs::create_dir_all(dir) {
+            let e = format!("Failed to create {}: {}", dir.display(), e);
+            finish(app, &id, DownloadState::Failed, Some(e.clone()));
+            return Err(e);
+        }
+    }
+
+    let result = run(app, &id, &download, receiver.clone()).await;
+    match result {
+        Ok(()) => {
+            tracing::info!(url = %download.url, path = %download.path.display(), "Downloaded");
+            finish(app, &id, DownloadState::Completed, None);
+            Ok(download.path)
+        }
+        Err(e) if *receiver.borrow() == Control::Cancel => {
+            discard(&download.path);
+            finish(app, &id, DownloadState::Cancelled, None);
+            Err(e)
+        }
+        Err(e) => {
+            // The partial file is kept so trying again resumes it
+            tracing::error!("Failed to download {}: {}", download.url, e);
+            finish(app, &id, DownloadState::Failed, Some(e.clone()));
+            Err(e)
+        }
+    }
+}
+
+// Download a small file into memory, failing instead of truncating past `limit` bytes
+pub(crate) async fn fetch_bytes(url: Url, limit: usize) -> Result<Vec<u8>, AppError> {
+    let client = crate::network::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .map_err(|e| e.to_string())?;
+    let mut response = client
+        .get(url.clone())
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| AppError::Network(format!("Failed to download {}: {}", url, e)))?;
+    let too_large = || AppError::Validation(format!("{} is larger than {} KB", url, limit / 1024));
+    if response
+        .content_length()
+        .is_some_and(|length| length > limit as u64)
+    {
+        return Err(too_large());
+    }
+    let mut body = Vec::new();
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .map_err(|e| AppError::Network(format!("Download of {} interrupted: {}", url, e)))?
+    {
+        if body.len() + chunk.len() > limit {
+            return Err(too_large());
+        }
+        body.extend_from_slice(&chunk);
+    }
+    Ok(body)
+}
+
+fn control(id: &str, control: Control) -> Result<DownloadInfo, AppError> {
+    let jobs = JOBS.lock().unwrap();
+    let job = jobs
+        .get(id)
+        .ok_or_else(|| AppError::NotFound(format!("Unknown download: {}", id)))?;
+    job.control.send_replace(control);
+    Ok(job.info.clone())
+}
+
+// Downloads in progress or paused
+#[tauri::command]
+pub fn list_downloads() -> Vec<DownloadInfo> {
+    JOBS.lock()
+        .unwrap()
+        .values()
+        .map(|job| job.info.clone())
+        .collect()
+}
+
+// Stop a download, keeping what was fetched so `resume_download` carries on from there
+#[tauri::command]
+pub fn pause_download(id: String) -> Result<DownloadInfo, AppError> {
+    control(&id, Control::Pause)
+}
+
+#[tauri::command]
+pub fn resume_download(id: String) -> Result<DownloadInfo, AppError> {
+    control(&id, Control::Run)
+}
+
+// Stop a download and delete what was fetched
+#[tauri::command]
+pub fn cancel_download(id: String) -> Result<DownloadInfo, AppError> {
+    control(&id, Control::Cancel)
+}
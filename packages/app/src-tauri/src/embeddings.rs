@@ -0,0 +1,393 @@
+use std::sync::Mutex;
+use std::time::Duration;
+
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tauri::AppHandle;
+use tokio::sync::Notify;
+
+use crate::error::AppError;
+use crate::history::{self, db_error, message_from_row, Message};
+use crate::{local_models, secrets, settings};
+
+const OPENAI_EMBEDDINGS_URL: &str = "https://api.openai.com/v1/embeddings";
+const BATCH_SIZE: usize = 32;
+// Long messages are cut to roughly what embedding models accept
+const MAX_INPUT_CHARS: usize = 8000;
+// Shorter messages (e.g. "ok") carry nothing worth finding
+const MIN_CONTENT_LEN: usize = 3;
+const IDLE_INTERVAL: Duration = Duration::from_secs(60);
+const RETRY_INTERVAL: Duration = Duration::from_secs(5 * 60);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+const DEFAULT_RESULTS: usize = 10;
+const MAX_RESULTS: usize = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EmbeddingProvider {
+    // A local Ollama model; nothing leaves the machine
+    Ollama,
+    // OpenAI's embeddings API with the stored OpenAI key
+    Openai,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EmbeddingSettings {
+    // Index messages in the background for `semantic_search`
+    pub enabled: bool,
+    pub provider: EmbeddingProvider,
+    pub model: String,
+}
+
+impl Default for EmbeddingSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            provider: EmbeddingProvider::Ollama,
+            model: "nomic-embed-text".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SemanticIndexStatus {
+    pub enabled: bool,
+    pub provider: EmbeddingProvider,
+    pub model: String,
+    pub indexed: u64,
+    // Messages still waiting to be embedded
+    pub pending: u64,
+    pub last_error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SemanticHit {
+    pub message: Message,
+    // Cosine similarity to the query, from -1 to 1
+    pub score: f32,
+}
+
+static WAKE: Notify = Notify::const_new();
+static LAST_ERROR: Mutex<Option<String>> = Mutex::new(None);
+
+// Vectors are stored under the provider and model that made them, since vectors from
+// different models can't be compared
+fn model_key(config: &EmbeddingSettings) -> String {
+    let provider = match config.provider {
+        EmbeddingProvider::Ollama => "ollama",
+        EmbeddingProvider::Openai => "openai",
+    };
+    format!("{}:{}", provider, config.model)
+}
+
+fn normalize(mut vector: Vec<f32>) -> Vec<f32> {
+    let norm = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        vector.iter_mut().for_each(|x| *x /= norm);
+    }
+    vector
+}
+
+fn to_blob(vector: &[f32]) -> Vec<u8> {
+    vector.iter().flat_map(|x| x.to_le_bytes()).collect()
+}
+
+fn from_blob(blob: &[u8]) -> Vec<f32> {
+    blob.chunks_exact(4)
+        .map(|bytes| f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+        .collect()
+}
+
+fn vectors(value: &Value, pointer: &str) -> Option<Vec<Vec<f32>>> {
+    value
+        .pointer(pointer)?
+        .as_array()?
+        .iter()
+        .map(|vector| {
+            vector
+                .as_array()?
+                .iter()
+                .map(|x| x.as_f64().map(|x| x as f32))
+                .collect()
+        })
+        .collect()
+}
+
+// One normalized vector per input, in order
+async fn embed(config: &EmbeddingSettings, inputs: &[String]) -> Result<Vec<Vec<f32>>, String> {
+    let client = crate::network::client();
+    let request = match config.provider {
+        EmbeddingProvider::Ollama => client
+            .post(format!("{}/api/embed", local_models::base_url()))
+            .json(&json!({ "model": config.model, "input": inputs })),
+        EmbeddingProvider::Openai => {
+            let key = tauri::async_runtime::spawn_blocking(|| secrets::load_key("openai"))
+                .await
+                .map_err(|e| e.to_string())??
+                .ok_or("Add an OpenAI API key to use OpenAI embeddings")?;
+            client
+                .post(OPENAI_EMBEDDINGS_URL)
+                .bearer_auth(key)
+                .json(&json!({ "model": config.model, "input": inputs }))
+        }
+    };
+    let response = request
+        .timeout(REQUEST_TIMEOUT)
+        .send()
+        .await
+        .map_err(|e| format!("The embedding request failed: {}", e))?;
+    let status = response.status();
+    let body: Value = response
+        .json()
+        .await
+        .map_err(|e| format!("Invalid embedding response: {}", e))?;
+    if !status.is_success() {
+        let message = body
+            .pointer("/error/message")
+            .or_else(|| body.get("error"))
+            .and_then(Value::as_str)
+            .unwrap_or("unknown error");
+        return Err(format!(
+            "The embedding request failed ({}): {}",
+            status, message
+        ));
+    }
+    let vectors = match config.provider {
+        EmbeddingProvider::Ollama => vectors(&body, "/embeddings"),
+        EmbeddingProvider::Openai => body["data"]
+            .as_array()
+            .map(|data| {
+                let mut data = data.clone();
+                data.sort_by_key(|item| item["index"].as_u64());
+                json!(data
+                    .iter()
+                    .map(|item| &item["embedding"])
+                    .collect::<Vec<_>>())
+            })
+            .and_then(|list| vectors(&list, "")),
+    }
+    .ok_or("The embedding response has no vectors")?;
+    if vectors.len() != inputs.len() {
+        return Err("The embedding response doesn't match the request".to_string());
+    }
+    Ok(vectors.into_iter().map(normalize).collect())
+}
+
+fn pending(conn: &Connection, model: &str, limit: usize) -> Result<Vec<(String, String)>, String> {
+    let mut statement = conn
+        .prepare(
+            "SELECT m.id, m.content FROM messages m
+                LEFT JOIN message_embeddings e ON e.message_id = m.id AND e.model = ?1
+                WHERE e.message_id IS NULL AND length(trim(m.content)) >= ?2
+                ORDER BY m.created_at DESC
+                LIMIT ?3",
+        )
+        .map_err(db_error)?;
+    let rows = statement
+        .query_map(
+            params![model, MIN_CONTENT_LEN as i64, limit as i64],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .map_err(db_error)?
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .map_err(db_error)?;
+    Ok(rows)
+}
+
+// Embed the next batch of unindexed messages, newest first; returns how many were indexed
+async fn index_batch(config: &EmbeddingSettings) -> Result<usize, String> {
+    let model = model_key(config);
+    let lookup = model.clone();
+    let batch = history::with_db(move |conn| pending(conn, &lookup, BATCH_SIZE)).await?;
+    if batch.is_empty() {
+        return Ok(0);
+    }
+    let inputs: Vec<String> = batch
+        .iter()
+        .map(|(_, content)| content.chars().take(MAX_INPUT_CHARS).collect())
+        .collect();
+    let vectors = embed(config, &inputs).await?;
+    let count = batch.len();
+    history::with_db(move |conn| {
+        let tx = conn.transaction().map_err(db_error)?;
+        for ((id, _), vector) in batch.iter().zip(&vectors) {
+            // The message may have been deleted while its vector was computed
+            tx.execute(
+                "INSERT OR REPLACE INTO message_embeddings (message_id, model, dims, vector)
+                    SELECT ?1, ?2, ?3, ?4 WHERE EXISTS (SELECT 1 FROM messages WHERE id = ?1)",
+                params![id, model, vector.len() as i64, to_blob(vector)],
+            )
+            .map_err(db_error)?;
+        }
+        tx.commit().map_err(db_error)
+    })
+    .await?;
+    Ok(count)
+}
+
+// Ask the indexer to look for new messages now rather than at its next check
+pub fn wake() {
+    WAKE.notify_one();
+}
+
+// Keep the index up to date for as long as the app runs; called once from the setup hook
+pub fn spawn() {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let config = settings::current().embeddings;
+            let wait = if !config.enabled {
+                IDLE_INTERVAL
+            } else {
+                match index_batch(&config).await {
+                    Ok(indexed) => {
+                        *LAST_ERROR.lock().unwrap() = None;
+                        if indexed == BATCH_SIZE {
+                            // More are waiting; carry straight on
+                            continue;
+                        }
+                        IDLE_INTERVAL
+                    }
+                    Err(e) => {
+                        tracing::warn!("Failed to index messages: {}", e);
+                        *LAST_ERROR.lock().unwrap() = Some(e);
+                        RETRY_INTERVAL
+                    }
+                }
+            };
+            let _ = tokio::time::timeout(wait, WAKE.notified()).await;
+        }
+    });
+}
+
+fn count_pending(conn: &Connection, model: &str) -> Result<u64, String> {
+    conn.query_row(
+        "SELECT COUNT(*) FROM messages m
+            LEFT JOIN message_embeddings e ON e.message_id = m.id AND e.model = ?1
+            WHERE e.message_id IS NULL AND length(trim(m.content)) >= ?2",
+        params![model, MIN_CONTENT_LEN as i64],
+        |row| row.get::<_, i64>(0),
+    )
+    .map(|count| count as u64)
+    .map_err(db_error)
+}
+
+#[tauri::command]
+pub async fn get_semantic_index_status() -> Result<SemanticIndexStatus, AppError> {
+    let config = settings::current().embeddings;
+    let model = model_key(&config);
+    let (indexed, pending) = history::with_db(move |conn| {
+        let indexed = conn
+            .query_row(
+                "SELECT COUNT(*) FROM message_embeddings WHERE model = ?1",
+                [&model],
+                |row| row.get::<_, i64>(0),
+            )
+            .map_err(db_error)? as u64;
+        let pending = count_pending(conn, &model)?;
+        Ok((indexed, pending))
+    })
+    .await
+    .map_err(AppError::Database)?;
+    Ok(SemanticIndexStatus {
+        enabled: config.enabled,
+        provider: config.provider,
+        model: config.model,
+        indexed,
+        pending,
+        last_error: LAST_ERROR.lock().unwrap().clone(),
+    })
+}
+
+// Turn indexing on or off, or switch models. Vectors from other models are dropped and the
+// history is indexed again with the new one.
+#[tauri::command]
+pub async fn configure_embeddings(
+    app: AppHandle,
+    embeddings: EmbeddingSettings,
+) -> Result<SemanticIndexStatus, AppError> {
+    if embeddings.model.trim().is_empty() {
+        return Err(AppError::Validation(
+            "Choose an embedding model".to_string(),
+        ));
+    }
+    let config = settings::update(&app, |settings| settings.embeddings = embeddings)?.embeddings;
+    let model = model_key(&config);
+    history::with_db(move |conn| {
+        conn.execute("DELETE FROM message_embeddings WHERE model <> ?1", [model])
+            .map_err(db_error)
+    })
+    .await
+    .map_err(AppError::Database)?;
+    *LAST_ERROR.lock().unwrap() = None;
+    wake();
+    get_semantic_index_status().await
+}
+
+// Messages closest in meaning to `query`, best first, optionally within one conversation
+#[tauri::command]
+pub async fn semantic_search(
+    query: String,
+    k: Option<usize>,
+    conversation_id: Option<String>,
+) -> Result<Vec<SemanticHit>, AppError> {
+    let config = settings::current().embeddings;
+    if !config.enabled {
+        return Err(AppError::Validation(
+            "Turn on semantic search in settings first".to_string(),
+        ));
+    }
+    let query = query.trim().to_string();
+    if query.is_empty() {
+        return Ok(Vec::new());
+    }
+    let k = k.unwrap_or(DEFAULT_RESULTS).clamp(1, MAX_RESULTS);
+    let target = embed(&config, &[query])
+        .await
+        .map_err(AppError::Network)?
+        .remove(0);
+    let model = model_key(&config);
+
+    history::with_db(move |conn| {
+        // Every vector is compared; fine for the tens of thousands of messages a local
+        // history holds
+        let mut statement = conn
+            .prepare(
+                "SELECT message_id, vector FROM message_embeddings e
+                    JOIN messages m ON m.id = e.message_id
+                    WHERE e.model = ?1 AND e.dims = ?2 AND (?3 IS NULL OR m.conversation_id = ?3)",
+            )
+            .map_err(db_error)?;
+        let mut rows = statement
+            .query(params![model, target.len() as i64, conversation_id])
+            .map_err(db_error)?;
+        let mut scored: Vec<(f32, String)> = Vec::new();
+        while let Some(row) = rows.next().map_err(db_error)? {
+            let blob: Vec<u8> = row.get(1).map_err(db_error)?;
+            let score = from_blob(&blob)
+                .iter()
+                .zip(&target)
+                .map(|(a, b)| a * b)
+                .sum::<f32>();
+            scored.push((score, row.get(0).map_err(db_error)?));
+        }
+        scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+        scored.truncate(k);
+
+        let mut hits = Vec::with_capacity(scored.len());
+        for (score, id) in scored {
+            let message = conn
+                .query_row(
+                    "SELECT * FROM messages WHERE id = ?1",
+                    [&id],
+                    message_from_row,
+                )
+                .map_err(db_error)?;
+            hits.push(SemanticHit { message, score });
+        }
+        Ok(hits)
+    })
+    .await
+    .map_err(AppError::Database)
+}
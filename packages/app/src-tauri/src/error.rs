@@ -0,0 +1,102 @@
+use std::fmt;
+
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
+
+// Error returned by every command. The frontend receives `{ code, message }`: `code` is
+// stable and meant for branching, `message` is shown to the user as is.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AppError {
+    // The OS keychain or the encrypted secrets file
+    Keychain(String),
+    // A request to the elizaOS server, a model provider or the internet failed
+    Network(String),
+    // Starting, stopping or talking to the server isn't possible in its current state
+    ServerLifecycle(String),
+    // The input was rejected before anything was done
+    Validation(String),
+    NotFound(String),
+    // Reading or writing files, or running a program
+    Io(String),
+    Database(String),
+    // Sign-in, tokens and biometric verification
+    Auth(String),
+    // A sensitive command was called without the capability grant it needs
+    PermissionDenied(String),
+    Internal(String),
+}
+
+impl AppError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::Keychain(_) => "keychain",
+            AppError::Network(_) => "network",
+            AppError::ServerLifecycle(_) => "server_lifecycle",
+            AppError::Validation(_) => "validation",
+            AppError::NotFound(_) => "not_found",
+            AppError::Io(_) => "io",
+            AppError::Database(_) => "database",
+            AppError::Auth(_) => "auth",
+            AppError::PermissionDenied(_) => "permission_denied",
+            AppError::Internal(_) => "internal",
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            AppError::Keychain(message)
+            | AppError::Network(message)
+            | AppError::ServerLifecycle(message)
+            | AppError::Validation(message)
+            | AppError::NotFound(message)
+            | AppError::Io(message)
+            | AppError::Database(message)
+            | AppError::Auth(message)
+            | AppError::PermissionDenied(message)
+            | AppError::Internal(message) => message,
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl Serialize for AppError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut error = serializer.serialize_struct("AppError", 2)?;
+        error.serialize_field("code", self.code())?;
+        error.serialize_field("message", self.message())?;
+        error.end()
+    }
+}
+
+// Helpers still report errors as strings; those without a more specific kind are internal
+impl From<String> for AppError {
+    fn from(message: String) -> Self {
+        AppError::Internal(message)
+    }
+}
+
+impl From<&str> for AppError {
+    fn from(message: &str) -> Self {
+        AppError::Internal(message.to_string())
+    }
+}
+
+impl From<tauri::Error> for AppError {
+    fn from(error: tauri::Error) -> Self {
+        AppError::Internal(error.to_string())
+    }
+}
+
+// For helpers that call commands and still report errors as strings
+impl From<AppError> for String {
+    fn from(error: AppError) -> Self {
+        error.message().to_string()
+    }
+}
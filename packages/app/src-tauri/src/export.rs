@@ -0,0 +1,186 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+
+use rusqlite::Connection;
+use serde::Deserialize;
+use zip::write::SimpleFileOptions;
+use zip::ZipWriter;
+
+use crate::capabilities::{self, Capability};
+use crate::error::AppError;
+use crate::history::{self, ConversationSummary, Message};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Markdown,
+    Json,
+    Html,
+}
+
+impl ExportFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Markdown => "md",
+            ExportFormat::Json => "json",
+            ExportFormat::Html => "html",
+        }
+    }
+}
+
+fn io_error(e: std::io::Error) -> String {
+    format!("Failed to write export: {}", e)
+}
+
+// `YYYY-MM-DD HH:MM UTC` for a millisecond Unix timestamp
+fn format_timestamp(millis: u64) -> String {
+    let secs = millis / 1000;
+    let days = (secs / 86_400) as i64;
+    let (hour, minute) = ((secs % 86_400) / 3600, (secs % 3600) / 60);
+
+    // Civil-from-days, after Howard Hinnant's date algorithms
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02} UTC",
+        year, month, day, hour, minute
+    )
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn author(message: &Message) -> String {
+    message
+        .sender
+        .clone()
+        .unwrap_or_else(|| format!("{:?}", message.role).to_lowercase())
+}
+
+fn title(summary: &ConversationSummary) -> String {
+    summary
+        .title
+        .clone()
+        .unwrap_or_else(|| format!("Conversation {}", summary.id))
+}
+
+// Write one conversation, reading its messages one at a time
+fn write_conversation(
+    conn: &Connection,
+    summary: &ConversationSummary,
+    format: ExportFormat,
+    out: &mut impl Write,
+) -> Result<(), String> {
+    match format {
+        ExportFormat::Markdown => {
+            writeln!(out, "# {}\n", title(summary)).map_err(io_error)?;
+            history::for_each_message(conn, &summary.id, |message| {
+                writeln!(
+                    out,
+                    "**{}** · {}\n\n{}\n",
+                    author(&message),
+                    format_timestamp(message.created_at),
+                    message.content
+                )
+                .map_err(io_error)
+            })
+        }
+        ExportFormat::Json => {
+            let header = serde_json::to_string(summary).map_err(|e| e.to_string())?;
+            // Reopen the summary object to append the messages array as it streams
+            write!(out, "{},\"messages\":[", header.trim_end_matches('}')).map_err(io_error)?;
+            let mut first = true;
+            history::for_each_message(conn, &summary.id, |message| {
+                if !first {
+                    out.write_all(b",").map_err(io_error)?;
+                }
+                first = false;
+                serde_json::to_writer(&mut *out, &message).map_err(|e| e.to_string())
+            })?;
+            out.write_all(b"]}\n").map_err(io_error)
+        }
+        ExportFormat::Html => {
+            let title = escape_html(&title(summary));
+            write!(
+                out,
+                "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>{0}</title>\
+                <style>body{{font-family:sans-serif;max-width:48rem;margin:2rem auto}}\
+                .meta{{color:#666;font-size:.85em}}.content{{white-space:pre-wrap}}</style>\
+                </head><body><h1>{0}</h1>\n",
+                title
+            )
+            .map_err(io_error)?;
+            history::for_each_message(conn, &summary.id, |message| {
+                writeln!(
+                    out,
+                    "<section><p class=\"meta\"><strong>{}</strong> · {}</p>\
+                    <p class=\"content\">{}</p></section>",
+                    escape_html(&author(&message)),
+                    format_timestamp(message.created_at),
+                    escape_html(&message.content)
+                )
+                .map_err(io_error)
+            })?;
+            out.write_all(b"</body></html>\n").map_err(io_error)
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn export_conversation(
+    conversation_id: String,
+    format: ExportFormat,
+    path: PathBuf,
+) -> Result<PathBuf, AppError> {
+    capabilities::require(Capability::ExportData)?;
+    history::with_db(move |conn| {
+        let summary = history::load_summary(conn, &conversation_id)?
+            .ok_or_else(|| format!("Unknown conversation: {}", conversation_id))?;
+        let file = File::create(&path)
+            .map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+        let mut out = BufWriter::new(file);
+        write_conversation(conn, &summary, format, &mut out)?;
+        out.flush().map_err(io_error)?;
+        Ok(path)
+    })
+    .await
+    .map_err(AppError::Io)
+}
+
+// Write every conversation into one zip archive, one file per conversation
+#[tauri::command]
+pub async fn export_all_conversations(
+    format: ExportFormat,
+    path: PathBuf,
+) -> Result<PathBuf, AppError> {
+    capabilities::require(Capability::ExportData)?;
+    history::with_db(move |conn| {
+        let file = File::create(&path)
+            .map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+        let mut zip = ZipWriter::new(BufWriter::new(file));
+        for summary in history::load_summaries(conn)? {
+            let name = format!("{}.{}", summary.id, format.extension());
+            zip.start_file(name, SimpleFileOptions::default())
+                .map_err(|e| format!("Failed to add {} to the archive: {}", summary.id, e))?;
+            write_conversation(conn, &summary, format, &mut zip)?;
+        }
+        zip.finish()
+            .map_err(|e| format!("Failed to finish {}: {}", path.display(), e))?;
+        Ok(path)
+    })
+    .await
+    .map_err(AppError::Io)
+}
@@ -0,0 +1,197 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::capabilities::{self, Capability};
+use crate::error::AppError;
+use crate::{knowledge, settings};
+
+const ATTACHMENTS_DIR: &str = "attachments";
+const IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "gif", "webp"];
+const MAX_IMPORT_BYTES: u64 = 25 * 1024 * 1024;
+
+// Where dropped files go
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportTarget {
+    // The app's attachment store, for files the user wants to send with a message
+    Attachments,
+    // The workspace knowledge dir, for documents the agent should learn from
+    Knowledge,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportedFile {
+    pub source: PathBuf,
+    pub path: PathBuf,
+    // Plain text extracted from a PDF, written next to the copy
+    pub text_path: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RejectedFile {
+    pub source: PathBuf,
+    pub reason: String,
+}
+
+// Payload of the `files-imported` event
+#[derive(Debug, Clone, Serialize)]
+pub struct FilesImported {
+    pub target: ImportTarget,
+    pub files: Vec<ImportedFile>,
+    pub rejected: Vec<RejectedFile>,
+}
+
+pub fn attachments_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_data_dir()
+        .map(|dir| dir.join(ATTACHMENTS_DIR))
+        .map_err(|e| format!("Failed to resolve app data dir: {}", e))
+}
+
+fn is_image(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| IMAGE_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()))
+}
+
+fn is_pdf(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("pdf"))
+}
+
+fn validate(target: ImportTarget, path: &Path) -> Result<(), String> {
+    let metadata = fs::metadata(path).map_err(|e| e.to_string())?;
+    if !metadata.is_file() {
+        return Err("Only files can be imported".to_string());
+    }
+    if metadata.len() > MAX_IMPORT_BYTES {
+        return Err(format!(
+            "File is larger than {} MB",
+            MAX_IMPORT_BYTES / 1024 / 1024
+        ));
+    }
+    let allowed = match target {
+        ImportTarget::Knowledge => knowledge::is_supported(path),
+        ImportTarget::Attachments => knowledge::is_supported(path) || is_image(path),
+    };
+    if !allowed {
+        return Err("Unsupported file type".to_string());
+    }
+    Ok(())
+}
+
+// `name.ext`, then `name (1).ext`, `name (2).ext`, ... until one is free
+fn unique_path(dir: &Path, file_name: &str) -> PathBuf {
+    let candidate = dir.join(file_name);
+    if !candidate.exists() {
+        return candidate;
+    }
+    let path = Path::new(file_name);
+    let stem = path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or(file_name);
+    let ext = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| format!(".{}", e))
+        .unwrap_or_default();
+    (1..)
+        .map(|n| dir.join(format!("{} ({}){}", stem, n, ext)))
+        .find(|candidate| !candidate.exists())
+        .unwrap_or(candidate)
+}
+
+fn extract_pdf_text(pdf: &Path) -> Result<PathBuf, String> {
+    let text = pdf_extract::extract_text(pdf)
+        .map_err(|e| format!("Failed to extract text from {}: {}", pdf.display(), e))?;
+    let text_path = pdf.with_extension("txt");
+    fs::write(&text_path, text)
+        .map_err(|e| format!("Failed to write {}: {}", text_path.display(), e))?;
+    Ok(text_path)
+}
+
+fn import_one(dir: &Path, target: ImportTarget, source: &Path) -> Result<ImportedFile, String> {
+    validate(target, source)?;
+    let file_name = source
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or("File name is not valid UTF-8")?;
+    fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    let path = unique_path(dir, file_name);
+    fs::copy(source, &path).map_err(|e| format!("Failed to copy: {}", e))?;
+
+    let text_path = if is_pdf(&path) && settings::current().extract_pdf_text {
+        // The copy is still useful without its text
+        extract_pdf_text(&path)
+            .inspect_err(|e| tracing::warn!("{}", e))
+            .ok()
+    } else {
+        None
+    };
+    Ok(ImportedFile {
+        source: source.to_path_buf(),
+        path,
+        text_path,
+    })
+}
+
+pub fn import(
+    app: &AppHandle,
+    target: ImportTarget,
+    paths: &[PathBuf],
+) -> Result<FilesImported, String> {
+    let dir = match target {
+        ImportTarget::Attachments => attachments_dir(app)?,
+        ImportTarget::Knowledge => knowledge::knowledge_dir()?.join("imported"),
+    };
+
+    let mut imported = FilesImported {
+        target,
+        files: Vec::new(),
+        rejected: Vec::new(),
+    };
+    for source in paths {
+        match import_one(&dir, target, source) {
+            Ok(file) => imported.files.push(file),
+            Err(reason) => imported.rejected.push(RejectedFile {
+                source: source.clone(),
+                reason,
+            }),
+        }
+    }
+
+    if let Err(e) = app.emit("files-imported", &imported) {
+        tracing::warn!("Failed to emit files-imported: {}", e);
+    }
+    Ok(imported)
+}
+
+// Import files dropped on the main window into the configured target, off the main thread
+pub fn handle_drop(app: &AppHandle, paths: Vec<PathBuf>) {
+    let app = app.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        if let Err(e) = import(&app, settings::current().drop_target, &paths) {
+            tracing::warn!("Failed to import dropped files: {}", e);
+        }
+    });
+}
+
+// Import files picked in the UI, e.g. from a file dialog
+#[tauri::command]
+pub async fn import_files(
+    app: AppHandle,
+    paths: Vec<PathBuf>,
+    target: Option<ImportTarget>,
+) -> Result<FilesImported, AppError> {
+    // Dropped files arrive through `handle_drop`; named paths could be any file on disk
+    capabilities::require(Capability::ExportData)?;
+    let target = target.unwrap_or(settings::current().drop_target);
+    tauri::async_runtime::spawn_blocking(move || import(&app, target, &paths))
+        .await?
+        .map_err(AppError::Io)
+}
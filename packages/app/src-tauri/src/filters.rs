@@ -0,0 +1,918 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+use once_cell::sync::Lazy;
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::{AppHandle, Emitter};
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+
+use crate::audit::{self, AuditAction};
+use crate::error::AppError;
+use crate::history::new_id;
+use crate::i18n::{t, t_args};
+use crate::redaction::REDACTED;
+use crate::workspace;
+
+// Content rules for what the server sends to model providers and what they send back.
+// They're checked in the provider gateway, so they only apply while provider calls go
+// through the proxy.
+
+// Kept with the workspace the rules belong to, next to the agent's own data
+const FILTERS_FILE: &str = ".eliza/filters.json";
+const MAX_PATTERN_LEN: usize = 1024;
+// Every provider call is matched against each rule, so a huge pattern would slow them all
+const REGEX_SIZE_LIMIT: usize = 1024 * 1024;
+// How often the rules file is checked for edits made outside the app
+const RECHECK_INTERVAL: Duration = Duration::from_secs(5);
+// How much of a streamed reply is kept to look for matches split between two events
+const STREAM_TAIL: usize = 512;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchKind {
+    Regex,
+    // Plain text, found anywhere and ignoring case
+    Keyword,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Direction {
+    // Requests to the provider
+    Prompt,
+    // The provider's replies
+    Response,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AppliesTo {
+    Prompt,
+    Response,
+    Both,
+}
+
+impl AppliesTo {
+    fn covers(self, direction: Direction) -> bool {
+        match self {
+            AppliesTo::Both => true,
+            AppliesTo::Prompt => direction == Direction::Prompt,
+            AppliesTo::Response => direction == Direction::Response,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FilterAction {
+    // Refuse the call, unless the user overrides it
+    Block,
+    // Replace the matched text
+    Redact,
+    // Let it through and report it with `content-filter-triggered`
+    Warn,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FilterRule {
+    // Assigned when the rule is first saved
+    #[serde(default)]
+    pub id: String,
+    pub name: String,
+    pub kind: MatchKind,
+    pub pattern: String,
+    pub applies_to: AppliesTo,
+    pub action: FilterAction,
+    pub enabled: bool,
+    // Only count matches whose digits pass the Luhn checksum, as card numbers do
+    #[serde(default)]
+    pub luhn: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FilterConfig {
+    pub enabled: bool,
+    // Ask whether to let blocked content through rather than refusing it outright
+    pub confirm_overrides: bool,
+    pub rules: Vec<FilterRule>,
+}
+
+impl Default for FilterConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            confirm_overrides: true,
+            rules: default_rules(),
+        }
+    }
+}
+
+// What a workspace starts with: keys are held back, personal details only reported
+fn default_rules() -> Vec<FilterRule> {
+    let rule = |id: &str, name: &str, pattern: &str, action| FilterRule {
+        id: id.to_string(),
+        name: name.to_string(),
+        kind: MatchKind::Regex,
+        pattern: pattern.to_string(),
+        applies_to: AppliesTo::Both,
+        action,
+        enabled: true,
+        luhn: false,
+    };
+    vec![
+        FilterRule {
+            applies_to: AppliesTo::Prompt,
+            ..rule(
+                "builtin-api-keys",
+                "API keys",
+                r"sk-[A-Za-z0-9_-]{20,}|AIza[0-9A-Za-z_-]{35}|gsk_[A-Za-z0-9]{20,}|gh[pousr]_[A-Za-z0-9]{36,}|xox[abprs]-[A-Za-z0-9-]{10,}",
+                FilterAction::Block,
+            )
+        },
+        FilterRule {
+            applies_to: AppliesTo::Prompt,
+            ..rule(
+                "builtin-private-keys",
+                "Private keys",
+                r"-----BEGIN (?:[A-Z]+ )?PRIVATE KEY-----",
+                FilterAction::Block,
+            )
+        },
+        rule(
+            "builtin-email",
+            "Email addresses",
+            r"\b[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}\b",
+            FilterAction::Warn,
+        ),
+        // Visa, Mastercard, American Express and Discover prefixes, so the long ids and
+        // timestamps in most payloads don't match
+        FilterRule {
+            luhn: true,
+            ..rule(
+                "builtin-card-numbers",
+                "Card numbers",
+                r"\b(?:4\d{3}|5[1-5]\d{2}|2[2-7]\d{2}|3[47]\d{2}|6011|65\d{2})(?:[ -]?\d){9,15}\b",
+                FilterAction::Warn,
+            )
+        },
+    ]
+}
+
+struct Compiled {
+    rule: FilterRule,
+    regex: Regex,
+}
+
+impl Compiled {
+    fn accepts(&self, found: &str) -> bool {
+        !self.rule.luhn || luhn(found)
+    }
+
+    fn count(&self, text: &str) -> usize {
+        self.regex
+            .find_iter(text)
+            .filter(|found| self.accepts(found.as_str()))
+            .count()
+    }
+}
+
+fn luhn(text: &str) -> bool {
+    let digits: Vec<u32> = text.chars().filter_map(|c| c.to_digit(10)).collect();
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(index, &digit)| match index % 2 {
+            0 => digit,
+            _ if digit * 2 > 9 => digit * 2 - 9,
+            _ => digit * 2,
+        })
+        .sum();
+    !digits.is_empty() && sum.is_multiple_of(10)
+}
+
+// A workspace's rules, ready to match
+struct RuleSet {
+    path: PathBuf,
+    // The file's modification time when it was read, so edits outside the app are picked up
+    modified: Option<SystemTime>,
+    config: FilterConfig,
+    rules: Vec<Compiled>,
+}
+
+impl RuleSet {
+    fn active(&self, direction: Direction) -> impl Iterator<Item = &Compiled> {
+        let enabled = self.config.enabled;
+        self.rules
+            .iter()
+            .filter(move |compiled| enabled && compiled.rule.applies_to.covers(direction))
+    }
+
+    // Match `text` against every rule for `direction`, redacting in place. `counts` holds
+    // the matches per rule so far.
+    fn apply(&self, direction: Direction, text: &mut String, counts: &mut [usize]) -> bool {
+        let mut changed = false;
+        for (index, compiled) in self.rules.iter().enumerate() {
+            if !self.config.enabled || !compiled.rule.applies_to.covers(direction) {
+                continue;
+            }
+            let found = compiled.count(text);
+            if found == 0 {
+                continue;
+            }
+            counts[index] += found;
+            if compiled.rule.action == FilterAction::Redact {
+                *text = compiled
+                    .regex
+                    .replace_all(text, |captures: &regex::Captures| {
+                        let found = &captures[0];
+                        match compiled.accepts(found) {
+                            true => REDACTED.to_string(),
+                            false => found.to_string(),
+                        }
+                    })
+                    .into_owned();
+                changed = true;
+            }
+        }
+        changed
+    }
+
+    fn matches(&self, counts: &[usize]) -> Vec<FilterMatch> {
+        self.rules
+            .iter()
+            .zip(counts)
+            .filter(|(_, count)| **count > 0)
+            .map(|(compiled, count)| FilterMatch {
+                rule_id: compiled.rule.id.clone(),
+                rule_name: compiled.rule.name.clone(),
+                action: compiled.rule.action,
+                count: *count,
+            })
+            .collect()
+    }
+}
+
+// The rules last used, and when their file was last checked
+type Loaded = Option<(Arc<RuleSet>, Instant)>;
+static LOADED: Lazy<Mutex<Loaded>> = Lazy::new(Default::default);
+// One override question at a time, however many calls are held up
+static CONFIRMING: Lazy<tokio::sync::Mutex<()>> = Lazy::new(Default::default);
+
+fn path() -> Result<PathBuf, String> {
+    Ok(workspace::dir()?.join(FILTERS_FILE))
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|meta| meta.modified()).ok()
+}
+
+fn read(path: &Path) -> Result<FilterConfig, String> {
+    match fs::read_to_string(path) {
+        Ok(contents) => serde_json::from_str(&contents)
+            .map_err(|e| format!("Failed to parse {}: {}", path.display(), e)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(FilterConfig::default()),
+        Err(e) => Err(format!("Failed to read {}: {}", path.display(), e)),
+    }
+}
+
+fn write(path: &Path, config: &FilterConfig) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    let contents = serde_json::to_string_pretty(config).map_err(|e| e.to_string())?;
+    fs::write(path, contents).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+fn compile(rule: &FilterRule) -> Result<Regex, String> {
+    let source = match rule.kind {
+        MatchKind::Regex => rule.pattern.clone(),
+        MatchKind::Keyword => format!("(?i){}", regex::escape(rule.pattern.trim())),
+    };
+    RegexBuilder::new(&source)
+        .size_limit(REGEX_SIZE_LIMIT)
+        .build()
+        .map_err(|e| format!("Invalid pattern for {}: {}", rule.name, e))
+}
+
+fn build(path: PathBuf, config: FilterConfig) -> RuleSet {
+    let rules = config
+        .rules
+        .iter()
+        .filter(|rule| rule.enabled)
+        .filter_map(|rule| match compile(rule) {
+            Ok(regex) => Some(Compiled {
+                rule: rule.clone(),
+                regex,
+            }),
+            Err(e) => {
+                tracing::warn!("Ignoring content filter rule: {}", e);
+                None
+            }
+        })
+        .collect();
+    RuleSet {
+        modified: modified(&path),
+        path,
+        config,
+        rules,
+    }
+}
+
+// The current workspace's rules, read again only when the workspace or its file changed.
+// Every provider call asks, so the file is looked at no more than every few seconds.
+fn rules() -> Option<Arc<RuleSet>> {
+    let path = match path() {
+        Ok(path) => path,
+        Err(e) => {
+            tracing::warn!("Content filters are unavailable: {}", e);
+            return None;
+        }
+    };
+    let loaded = LOADED.lock().unwrap().clone();
+    if let Some((set, checked)) = loaded.filter(|(set, _)| set.path == path) {
+        if checked.elapsed() < RECHECK_INTERVAL {
+            return Some(set);
+        }
+        if set.modified == modified(&path) {
+            *LOADED.lock().unwrap() = Some((set.clone(), Instant::now()));
+            return Some(set);
+        }
+    }
+    let config = read(&path).unwrap_or_else(|e| {
+        // Keep the built-in protection rather than none at all
+        tracing::warn!("Using the default content filters: {}", e);
+        FilterConfig::default()
+    });
+    let set = Arc::new(build(path, config));
+    *LOADED.lock().unwrap() = Some((set.clone(), Instant::now()));
+    Some(set)
+}
+
+fn save(config: FilterConfig) -> Result<FilterConfig, String> {
+    let path = path()?;
+    write(&path, &config)?;
+    *LOADED.lock().unwrap() = Some((Arc::new(build(path, config.clone())), Instant::now()));
+    Ok(config)
+}
+
+// Whether provider calls need to go through the proxy to be filtered
+pub fn active() -> bool {
+    rules().is_some_and(|set| set.config.enabled && !set.rules.is_empty())
+}
+
+// Whether any rule looks at `direction`, so bodies that nothing inspects can stream through
+pub fn inspects(direction: Direction) -> bool {
+    rules().is_some_and(|set| set.active(direction).next().is_some())
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FilterMatch {
+    pub rule_id: String,
+    pub rule_name: String,
+    pub action: FilterAction,
+    pub count: usize,
+}
+
+// Payload of the `content-filter-triggered` event
+#[derive(Debug, Clone, Serialize)]
+struct FilterTriggered<'a> {
+    direction: Direction,
+    provider: &'a str,
+    matches: &'a [FilterMatch],
+    blocked: bool,
+    // A blocking rule matched and the user let the content through anyway
+    overridden: bool,
+}
+
+fn report(app: &AppHandle, triggered: FilterTriggered) {
+    let names = rule_names(triggered.matches);
+    tracing::warn!(
+        provider = triggered.provider,
+        direction = ?triggered.direction,
+        blocked = triggered.blocked,
+        overridden = triggered.overridden,
+        "Content filter matched: {}",
+        names
+    );
+    if let Err(e) = app.emit("content-filter-triggered", triggered) {
+        tracing::warn!("Failed to emit content filter match: {}", e);
+    }
+}
+
+fn rule_names(matches: &[FilterMatch]) -> String {
+    matches
+        .iter()
+        .map(|found| found.rule_name.as_str())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+// Apply the rules to a JSON body's string values, or to the body as text when it isn't
+// JSON, leaving keys and structure alone. The body is `None` when nothing was redacted.
+fn scan(set: &RuleSet, direction: Direction, body: &[u8]) -> (Option<Vec<u8>>, Vec<FilterMatch>) {
+    fn walk(set: &RuleSet, direction: Direction, value: &mut Value, counts: &mut [usize]) -> bool {
+        match value {
+            Value::String(text) => set.apply(direction, text, counts),
+            Value::Array(items) => items.iter_mut().fold(false, |changed, item| {
+                walk(set, direction, item, counts) | changed
+            }),
+            Value::Object(fields) => fields.values_mut().fold(false, |changed, field| {
+                walk(set, direction, field, counts) | changed
+            }),
+            _ => false,
+        }
+    }
+
+    let mut counts = vec![0; set.rules.len()];
+    let body = if let Ok(mut value) = serde_json::from_slice::<Value>(body) {
+        walk(set, direction, &mut value, &mut counts)
+            .then(|| serde_json::to_vec(&value).ok())
+            .flatten()
+    } else if let Ok(text) = std::str::from_utf8(body) {
+        let mut text = text.to_string();
+        set.apply(direction, &mut text, &mut counts)
+            .then(|| text.into_bytes())
+    } else {
+        None
+    };
+    (body, set.matches(&counts))
+}
+
+// The matches that refuse the call rather than only redacting or reporting
+fn blocking(matches: &[FilterMatch]) -> Vec<FilterMatch> {
+    matches
+        .iter()
+        .filter(|found| found.action == FilterAction::Block)
+        .cloned()
+        .collect()
+}
+
+pub enum Verdict {
+    // Send it on, with the redacted body if anything was redacted
+    Allow(Option<Vec<u8>>),
+    // Refused, with the reason to give the caller
+    Block(String),
+}
+
+// Ask the user whether content a blocking rule matched may go through anyway
+async fn confirm(app: &AppHandle, direction: Direction, provider: &str, rules: &str) -> bool {
+    let _waiting = CONFIRMING.lock().await;
+    let (message, allow) = match direction {
+        Direction::Prompt => (
+            t_args(
+                "filter-prompt-outgoing",
+                &[("provider", provider), ("rules", rules)],
+            ),
+            t("filter-send-anyway"),
+        ),
+        Direction::Response => (
+            t_args(
+                "filter-prompt-incoming",
+                &[("provider", provider), ("rules", rules)],
+            ),
+            t("filter-show-anyway"),
+        ),
+    };
+    let handle = app.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        handle
+            .dialog()
+            .message(message)
+            .title(t("filter-prompt-title"))
+            .kind(MessageDialogKind::Warning)
+            .buttons(MessageDialogButtons::OkCancelCustom(
+                allow,
+                t("filter-block"),
+            ))
+            .blocking_show()
+    })
+    .await
+    .unwrap_or(false)
+}
+
+// Check a whole request or reply against the workspace's rules
+pub async fn check(app: &AppHandle, direction: Direction, provider: &str, body: &[u8]) -> Verdict {
+    let Some(set) = rules() else {
+        return Verdict::Allow(None);
+    };
+    if set.active(direction).next().is_none() {
+        return Verdict::Allow(None);
+    }
+    let (redacted, matches) = scan(&set, direction, body);
+    if matches.is_empty() {
+        return Verdict::Allow(None);
+    }
+
+    let blocking = blocking(&matches);
+    let mut overridden = false;
+    if !blocking.is_empty() {
+        let names = rule_names(&blocking);
+        overridden =
+            set.config.confirm_overrides && confirm(app, direction, provider, &names).await;
+        if overridden {
+            audit::success(AuditAction::ContentFilterOverride, &names);
+        }
+    }
+    let blocked = !blocking.is_empty() && !overridden;
+    report(
+        app,
+        FilterTriggered {
+            direction,
+            provider,
+            matches: &matches,
+            blocked,
+            overridden,
+        },
+    );
+    if blocked {
+        Verdict::Block(t_args(
+            "filter-blocked",
+            &[("rules", &rule_names(&blocking))],
+        ))
+    } else {
+        Verdict::Allow(redacted)
+    }
+}
+
+// Checks a streamed reply as it arrives. Its chunks are forwarded as soon as they've passed,
+// so nothing can be redacted or asked about: a blocking rule ends the stream before the
+// matching chunk, and other matches are reported once it's over. The end of each chunk is
+// kept with the next, so text split between two events is still seen as one.
+pub struct StreamFilter {
+    app: AppHandle,
+    provider: &'static str,
+    scan: StreamScan,
+}
+
+impl StreamFilter {
+    // `None` when no rule looks at replies, or there's no workspace and so no rules at all;
+    // `check` lets whole replies through in the same cases
+    pub fn new(app: &AppHandle, provider: &'static str) -> Option<Self> {
+        let set = rules()?;
+        set.active(Direction::Response).next()?;
+        Some(StreamFilter {
+            app: app.clone(),
+            provider,
+            scan: StreamScan::new(set),
+        })
+    }
+
+    // Whether `chunk` may be forwarded
+    pub fn feed(&mut self, chunk: &[u8]) -> bool {
+        self.scan.feed(chunk)
+    }
+}
+
+impl Drop for StreamFilter {
+    fn drop(&mut self) {
+        let matches = self.scan.set.matches(&self.scan.counts);
+        if matches.is_empty() {
+            return;
+        }
+        report(
+            &self.app,
+            FilterTriggered {
+                direction: Direction::Response,
+                provider: self.provider,
+                matches: &matches,
+                blocked: self.scan.blocked,
+                overridden: false,
+            },
+        );
+    }
+}
+
+// What a streamed reply has matched so far
+struct StreamScan {
+    set: Arc<RuleSet>,
+    counts: Vec<usize>,
+    // The last few hundred bytes already checked
+    tail: String,
+    blocked: bool,
+}
+
+impl StreamScan {
+    fn new(set: Arc<RuleSet>) -> Self {
+        StreamScan {
+            counts: vec![0; set.rules.len()],
+            set,
+            tail: String::new(),
+            blocked: false,
+        }
+    }
+
+    fn feed(&mut self, chunk: &[u8]) -> bool {
+        if self.blocked {
+            return false;
+        }
+        let seen = self.tail.len();
+        let text = format!("{}{}", self.tail, String::from_utf8_lossy(chunk));
+        for (index, compiled) in self.set.rules.iter().enumerate() {
+            if !compiled.rule.applies_to.covers(Direction::Response) {
+                continue;
+            }
+            // Matches that ended in the tail were counted with the chunk before
+            let found = compiled
+                .regex
+                .find_iter(&text)
+                .filter(|found| found.end() > seen && compiled.accepts(found.as_str()))
+                .count();
+            self.counts[index] += found;
+            if found > 0 && compiled.rule.action == FilterAction::Block {
+                self.blocked = true;
+            }
+        }
+        let mut start = text.len().saturating_sub(STREAM_TAIL);
+        while !text.is_char_boundary(start) {
+            start += 1;
+        }
+        self.tail = text[start..].to_string();
+        !self.blocked
+    }
+}
+
+fn validate(rule: &FilterRule) -> Result<(), String> {
+    if rule.name.trim().is_empty() {
+        return Err("A content filter rule needs a name".to_string());
+    }
+    if rule.pattern.trim().is_empty() {
+        return Err(format!("{} has no pattern", rule.name));
+    }
+    if rule.pattern.len() > MAX_PATTERN_LEN {
+        return Err(format!("The pattern for {} is too long", rule.name));
+    }
+    compile(rule).map(|_| ())
+}
+
+#[tauri::command]
+pub fn get_content_filters() -> Result<FilterConfig, AppError> {
+    Ok(read(&path().map_err(AppError::Io)?)?)
+}
+
+// The workspace-wide switches; rules are changed one at a time below
+#[tauri::command]
+pub fn set_content_filters_enabled(
+    enabled: bool,
+    confirm_overrides: bool,
+) -> Result<FilterConfig, AppError> {
+    let mut config = read(&path().map_err(AppError::Io)?)?;
+    config.enabled = enabled;
+    config.confirm_overrides = confirm_overrides;
+    Ok(save(config)?)
+}
+
+// Add a rule, or replace the one with the same id
+#[tauri::command]
+pub fn save_content_filter_rule(mut rule: FilterRule) -> Result<FilterRule, AppError> {
+    validate(&rule).map_err(AppError::Validation)?;
+    rule.name = rule.name.trim().to_string();
+    if rule.id.is_empty() {
+        rule.id = new_id();
+    }
+    let mut config = read(&path().map_err(AppError::Io)?)?;
+    match config
+        .rules
+        .iter_mut()
+        .find(|existing| existing.id == rule.id)
+    {
+        Some(existing) => *existing = rule.clone(),
+        None => config.rules.push(rule.clone()),
+    }
+    save(config)?;
+    Ok(rule)
+}
+
+#[tauri::command]
+pub fn delete_content_filter_rule(id: String) -> Result<FilterConfig, AppError> {
+    let mut config = read(&path().map_err(AppError::Io)?)?;
+    let before = config.rules.len();
+    config.rules.retain(|rule| rule.id != id);
+    if config.rules.len() == before {
+        return Err(AppError::NotFound(format!("No content filter rule {}", id)));
+    }
+    Ok(save(config)?)
+}
+
+// Put back the built-in rules, dropping the workspace's own
+#[tauri::command]
+pub fn reset_content_filters() -> Result<FilterConfig, AppError> {
+    Ok(save(FilterConfig::default())?)
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FilterPreview {
+    pub matches: Vec<FilterMatch>,
+    // `text` as it would be sent on, after redaction
+    pub text: String,
+}
+
+// Try the workspace's rules on some text without sending it anywhere
+#[tauri::command]
+pub fn test_content_filters(text: String, direction: Direction) -> Result<FilterPreview, AppError> {
+    let path = path().map_err(AppError::Io)?;
+    let set = build(path.clone(), read(&path)?);
+    let mut counts = vec![0; set.rules.len()];
+    let mut text = text;
+    set.apply(direction, &mut text, &mut counts);
+    Ok(FilterPreview {
+        matches: set.matches(&counts),
+        text,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(name: &str, pattern: &str, applies_to: AppliesTo, action: FilterAction) -> FilterRule {
+        FilterRule {
+            id: name.to_string(),
+            name: name.to_string(),
+            kind: MatchKind::Keyword,
+            pattern: pattern.to_string(),
+            applies_to,
+            action,
+            enabled: true,
+            luhn: false,
+        }
+    }
+
+    fn set(rules: Vec<FilterRule>) -> RuleSet {
+        build(
+            PathBuf::new(),
+            FilterConfig {
+                enabled: true,
+                confirm_overrides: false,
+                rules,
+            },
+        )
+    }
+
+    #[test]
+    fn luhn_checks_card_numbers() {
+        assert!(luhn("4111111111111111"));
+        assert!(luhn("4242 4242 4242 4242"));
+        assert!(luhn("5555-5555-5555-4444"));
+        assert!(!luhn("4111111111111112"));
+        assert!(!luhn("1234567812345678"));
+        assert!(!luhn(""));
+    }
+
+    #[test]
+    fn default_card_rule_ignores_numbers_failing_luhn() {
+        let set = set(default_rules());
+        let (_, matches) = scan(&set, Direction::Prompt, b"pay with 4111 1111 1111 1111");
+        assert!(matches
+            .iter()
+            .any(|found| found.rule_id == "builtin-card-numbers"));
+        let (_, matches) = scan(&set, Direction::Prompt, b"order 4111 1111 1111 1112");
+        assert!(matches.is_empty());
+    }
+
+    #[test]
+    fn redacts_inside_json_strings() {
+        let set = set(vec![rule(
+            "codename",
+            "bluebird",
+            AppliesTo::Prompt,
+            FilterAction::Redact,
+        )]);
+        let body =
+            br#"{"bluebird":1,"messages":[{"role":"user","content":"Say \"Bluebird\" twice"}]}"#;
+        let (redacted, matches) = scan(&set, Direction::Prompt, body);
+        let value: Value = serde_json::from_slice(&redacted.unwrap()).unwrap();
+        assert_eq!(value["messages"][0]["content"], "Say \"[redacted]\" twice");
+        // Keys are left alone
+        assert_eq!(value["bluebird"], 1);
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].count, 1);
+    }
+
+    #[test]
+    fn redacts_plain_text_bodies() {
+        let set = set(vec![rule(
+            "codename",
+            "bluebird",
+            AppliesTo::Both,
+            FilterAction::Redact,
+        )]);
+        let (redacted, _) = scan(&set, Direction::Response, b"not json: bluebird {");
+        assert_eq!(redacted.unwrap(), b"not json: [redacted] {");
+    }
+
+    #[test]
+    fn only_rules_for_the_direction_apply() {
+        let set = set(vec![rule(
+            "codename",
+            "bluebird",
+            AppliesTo::Response,
+            FilterAction::Redact,
+        )]);
+        let (redacted, matches) = scan(&set, Direction::Prompt, br#"{"text":"bluebird"}"#);
+        assert!(redacted.is_none());
+        assert!(matches.is_empty());
+    }
+
+    #[test]
+    fn block_and_warn_verdicts() {
+        let set = set(vec![
+            rule("secret", "topsecret", AppliesTo::Both, FilterAction::Block),
+            rule("name", "alice", AppliesTo::Both, FilterAction::Warn),
+        ]);
+        let (body, matches) = scan(&set, Direction::Prompt, br#"{"text":"hi alice"}"#);
+        // Warnings are reported without changing or refusing anything
+        assert!(body.is_none());
+        assert_eq!(matches.len(), 1);
+        assert!(blocking(&matches).is_empty());
+
+        let (_, matches) = scan(&set, Direction::Prompt, br#"{"text":"alice: TopSecret"}"#);
+        assert_eq!(matches.len(), 2);
+        let blocking = blocking(&matches);
+        assert_eq!(blocking.len(), 1);
+        assert_eq!(blocking[0].rule_id, "secret");
+    }
+
+    #[test]
+    fn disabled_filters_match_nothing() {
+        let mut set = set(vec![rule(
+            "secret",
+            "topsecret",
+            AppliesTo::Both,
+            FilterAction::Block,
+        )]);
+        set.config.enabled = false;
+        let (_, matches) = scan(&set, Direction::Prompt, b"topsecret");
+        assert!(matches.is_empty());
+        assert!(set.active(Direction::Prompt).next().is_none());
+    }
+
+    #[test]
+    fn stream_blocks_a_match_split_between_events() {
+        let set = set(vec![rule(
+            "secret",
+            "topsecret",
+            AppliesTo::Response,
+            FilterAction::Block,
+        )]);
+        let mut scan = StreamScan::new(Arc::new(set));
+        assert!(scan.feed(b"data: {\"delta\":\"the top"));
+        assert!(!scan.feed(b"secret plan\"}\n\n"));
+        // Nothing more is let through once it's blocked
+        assert!(!scan.feed(b"data: [DONE]\n\n"));
+        assert_eq!(scan.counts, vec![1]);
+    }
+
+    #[test]
+    fn stream_counts_each_match_once() {
+        let set = set(vec![rule(
+            "name",
+            "alice",
+            AppliesTo::Response,
+            FilterAction::Warn,
+        )]);
+        let mut scan = StreamScan::new(Arc::new(set));
+        assert!(scan.feed(b"data: alice and al"));
+        assert!(scan.feed(b"ice\n\n"));
+        assert!(scan.feed(b"data: bob\n\n"));
+        assert_eq!(scan.counts, vec![2]);
+        assert!(!scan.blocked);
+    }
+
+    #[test]
+    fn stream_tail_stays_bounded() {
+        let set = set(vec![rule(
+            "name",
+            "alice",
+            AppliesTo::Response,
+            FilterAction::Warn,
+        )]);
+        let mut scan = StreamScan::new(Arc::new(set));
+        for _ in 0..10 {
+            assert!(scan.feed("é".repeat(200).as_bytes()));
+        }
+        assert!(scan.tail.len() <= STREAM_TAIL);
+    }
+
+    #[test]
+    fn validate_rejects_bad_rules() {
+        let mut bad = rule("", "x", AppliesTo::Both, FilterAction::Warn);
+        assert!(validate(&bad).is_err());
+        bad.name = "empty".to_string();
+        bad.pattern = "  ".to_string();
+        assert!(validate(&bad).is_err());
+        bad.pattern = "a".repeat(MAX_PATTERN_LEN + 1);
+        assert!(validate(&bad).is_err());
+        bad.kind = MatchKind::Regex;
+        bad.pattern = "(unclosed".to_string();
+        assert!(validate(&bad).is_err());
+        bad.pattern = r"\d+".to_string();
+        assert!(validate(&bad).is_ok());
+    }
+}
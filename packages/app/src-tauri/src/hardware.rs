@@ -0,0 +1,361 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use once_cell::sync::OnceCell;
+use serde::Serialize;
+use sysinfo::{CpuRefreshKind, Disks, System};
+use tauri::{AppHandle, Manager};
+
+use crate::error::AppError;
+
+// Share of unified memory a GPU may use on Apple Silicon; the rest stays with the OS
+const UNIFIED_GPU_SHARE: f64 = 0.75;
+// Weights plus context and runtime buffers; a model needs roughly this much more than its size
+const MODEL_MEMORY_OVERHEAD: f64 = 1.2;
+// Enough for a 7-8B model at 4-bit quantization, the smallest that chats well
+const LOCAL_MODEL_MEMORY: u64 = 6 * 1024 * 1024 * 1024;
+// CPU-only inference of that model is usable, if slow, with this much RAM
+const CPU_MODEL_MEMORY: u64 = 16 * 1024 * 1024 * 1024;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CpuInfo {
+    pub model: Option<String>,
+    pub physical_cores: Option<usize>,
+    pub logical_cores: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DiskInfo {
+    pub path: PathBuf,
+    pub mount_point: PathBuf,
+    pub available_bytes: u64,
+    pub total_bytes: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ModelRecommendation {
+    // Local models will run well enough to be the default
+    pub prefer_local: bool,
+    // Largest model download that fits in GPU memory
+    pub max_local_model_bytes: Option<u64>,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HardwareInfo {
+    pub cpu: CpuInfo,
+    pub total_memory_bytes: u64,
+    pub gpus: Vec<GpuInfo>,
+    // The disk holding the app data dir, where models are downloaded
+    pub disk: Option<DiskInfo>,
+    pub recommendation: ModelRecommendation,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GpuInfo {
+    pub name: String,
+    pub vendor: String,
+    // Dedicated memory; unknown for integrated GPUs without their own
+    pub vram_bytes: Option<u64>,
+    // Shares system RAM with the CPU, as on Apple Silicon
+    pub unified_memory: bool,
+}
+
+// GPUs don't change while the app runs, and probing them spawns processes
+static GPUS: OnceCell<Vec<GpuInfo>> = OnceCell::new();
+
+// Stdout of a successful run, without flashing a console window on Windows
+pub(crate) fn output(program: &str, args: &[&str]) -> Option<String> {
+    let mut command = Command::new(program);
+    command.args(args);
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        command.creation_flags(windows_sys::Win32::System::Threading::CREATE_NO_WINDOW);
+    }
+    let output = command.output().ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+// NVIDIA cards on every platform, with exact memory sizes
+fn nvidia_gpus() -> Vec<GpuInfo> {
+    let Some(csv) = output(
+        "nvidia-smi",
+        &[
+            "--query-gpu=name,memory.total",
+            "--format=csv,noheader,nounits",
+        ],
+    ) else {
+        return Vec::new();
+    };
+    csv.lines()
+        .filter_map(|line| {
+            let (name, mib) = line.rsplit_once(',')?;
+            Some(GpuInfo {
+                name: name.trim().to_string(),
+                vendor: "NVIDIA".to_string(),
+                vram_bytes: mib.trim().parse::<u64>().ok().map(|mib| mib * 1024 * 1024),
+                unified_memory: false,
+            })
+        })
+        .collect()
+}
+
+#[cfg(target_os = "macos")]
+fn platform_gpus() -> Vec<GpuInfo> {
+    use serde_json::Value;
+
+    // e.g. "8 GB" or "1536 MB"
+    fn parse_size(text: &str) -> Option<u64> {
+        let (amount, unit) = text.trim().split_once(' ')?;
+        let amount: u64 = amount.parse().ok()?;
+        match unit {
+            "GB" => Some(amount * 1024 * 1024 * 1024),
+            "MB" => Some(amount * 1024 * 1024),
+            _ => None,
+        }
+    }
+
+    let Some(json) = output("system_profiler", &["SPDisplaysDataType", "-json"]) else {
+        return Vec::new();
+    };
+    let Ok(report) = serde_json::from_str::<Value>(&json) else {
+        return Vec::new();
+    };
+    let field = |display: &Value, key: &str| display.get(key)?.as_str().map(str::to_string);
+    report
+        .get("SPDisplaysDataType")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .map(|display| {
+            let vendor = field(display, "spdisplays_vendor").unwrap_or_default();
+            let vram = field(display, "spdisplays_vram")
+                .or_else(|| field(display, "spdisplays_vram_shared"))
+                .as_deref()
+                .and_then(parse_size);
+            let apple = vendor.contains("Apple");
+            GpuInfo {
+                name: field(display, "sppci_model").unwrap_or_else(|| "Unknown GPU".to_string()),
+                vendor: if apple {
+                    "Apple".to_string()
+                } else {
+                    vendor.trim_start_matches("sppci_vendor_").to_string()
+                },
+                vram_bytes: if apple { None } else { vram },
+                unified_memory: apple,
+            }
+        })
+        .collect()
+}
+
+// WMI reports AdapterRAM as 32 bits, so cards above 4 GB read low; nvidia-smi covers most
+// of those
+#[cfg(windows)]
+fn platform_gpus() -> Vec<GpuInfo> {
+    use serde_json::Value;
+
+    let Some(json) = output(
+        "powershell",
+        &[
+            "-NoProfile",
+            "-Command",
+            "Get-CimInstance Win32_VideoController | Select-Object Name,AdapterCompatibility,AdapterRAM | ConvertTo-Json",
+        ],
+    ) else {
+        return Vec::new();
+    };
+    let controllers = match serde_json::from_str::<Value>(&json) {
+        Ok(Value::Array(controllers)) => controllers,
+        Ok(controller @ Value::Object(_)) => vec![controller],
+        _ => return Vec::new(),
+    };
+    controllers
+        .iter()
+        .map(|controller| GpuInfo {
+            name: controller["Name"]
+                .as_str()
+                .unwrap_or("Unknown GPU")
+                .to_string(),
+            vendor: controller["AdapterCompatibility"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string(),
+            vram_bytes: controller["AdapterRAM"].as_u64().filter(|bytes| *bytes > 0),
+            unified_memory: false,
+        })
+        .collect()
+}
+
+// amdgpu exposes VRAM size in sysfs; other drivers are listed without it
+#[cfg(target_os = "linux")]
+fn platform_gpus() -> Vec<GpuInfo> {
+    let Ok(cards) = std::fs::read_dir("/sys/class/drm") else {
+        return Vec::new();
+    };
+    let mut gpus = Vec::new();
+    for card in cards.filter_map(Result::ok) {
+        let name = card.file_name().to_string_lossy().into_owned();
+        // Skip connectors such as card0-HDMI-A-1
+        if !name.starts_with("card") || name.contains('-') {
+            continue;
+        }
+        let device = card.path().join("device");
+        let read = |file: &str| std::fs::read_to_string(device.join(file)).ok();
+        let vendor = match read("vendor").as_deref().map(str::trim) {
+            Some("0x1002") => "AMD",
+            Some("0x8086") => "Intel",
+            // NVIDIA is reported by nvidia-smi
+            Some("0x10de") => continue,
+            Some(_) => "Unknown",
+            None => continue,
+        };
+        gpus.push(GpuInfo {
+            name: format!("{} GPU ({})", vendor, name),
+            vendor: vendor.to_string(),
+            vram_bytes: read("mem_info_vram_total").and_then(|bytes| bytes.trim().parse().ok()),
+            unified_memory: false,
+        });
+    }
+    gpus
+}
+
+#[cfg(not(any(target_os = "macos", windows, target_os = "linux")))]
+fn platform_gpus() -> Vec<GpuInfo> {
+    Vec::new()
+}
+
+pub fn gpus() -> &'static [GpuInfo] {
+    GPUS.get_or_init(|| {
+        let mut gpus = nvidia_gpus();
+        for gpu in platform_gpus() {
+            // Windows lists NVIDIA cards too, with a capped memory size
+            if !gpus.iter().any(|known| known.name == gpu.name) {
+                gpus.push(gpu);
+            }
+        }
+        gpus
+    })
+}
+
+pub fn total_memory() -> u64 {
+    let mut system = System::new();
+    system.refresh_memory();
+    system.total_memory()
+}
+
+// Memory available to load a model into: the largest GPU's VRAM, a share of RAM when the
+// GPU uses unified memory, and `None` when inference would run on the CPU
+pub fn gpu_memory_budget() -> Option<u64> {
+    gpus()
+        .iter()
+        .filter_map(|gpu| {
+            if gpu.unified_memory {
+                Some((total_memory() as f64 * UNIFIED_GPU_SHARE) as u64)
+            } else {
+                gpu.vram_bytes
+            }
+        })
+        .max()
+}
+
+// Whether a model of `size` bytes fits in `budget`; unknown without a GPU we can size
+pub fn model_fits(size: u64, budget: Option<u64>) -> Option<bool> {
+    budget.map(|budget| (size as f64 * MODEL_MEMORY_OVERHEAD) as u64 <= budget)
+}
+
+fn cpu_info() -> CpuInfo {
+    let mut system = System::new();
+    system.refresh_cpu_list(CpuRefreshKind::nothing());
+    CpuInfo {
+        model: system
+            .cpus()
+            .first()
+            .map(|cpu| cpu.brand().trim().to_string()),
+        physical_cores: System::physical_core_count(),
+        logical_cores: system.cpus().len(),
+    }
+}
+
+// The disk `path` is on: the mount point that is its longest prefix
+pub fn disk_for(path: &Path) -> Option<DiskInfo> {
+    let path = path
+        .ancestors()
+        .find_map(|dir| dir.canonicalize().ok())
+        .unwrap_or_else(|| path.to_path_buf());
+    let disks = Disks::new_with_refreshed_list();
+    disks
+        .list()
+        .iter()
+        .filter(|disk| path.starts_with(disk.mount_point()))
+        .max_by_key(|disk| disk.mount_point().as_os_str().len())
+        .map(|disk| DiskInfo {
+            path: path.clone(),
+            mount_point: disk.mount_point().to_path_buf(),
+            available_bytes: disk.available_space(),
+            total_bytes: disk.total_space(),
+        })
+}
+
+// Refuse a download of `size` bytes into `path` when the disk is too full to hold it
+pub fn ensure_disk_space(path: &Path, size: u64) -> Result<(), String> {
+    match disk_for(path) {
+        Some(disk) if disk.available_bytes < size => Err(format!(
+            "Not enough disk space: {} MB needed, {} MB free on {}",
+            size / (1024 * 1024),
+            disk.available_bytes / (1024 * 1024),
+            disk.mount_point.display()
+        )),
+        _ => Ok(()),
+    }
+}
+
+fn recommend(total_memory: u64) -> ModelRecommendation {
+    match gpu_memory_budget() {
+        Some(budget) if budget >= LOCAL_MODEL_MEMORY => ModelRecommendation {
+            prefer_local: true,
+            max_local_model_bytes: Some((budget as f64 / MODEL_MEMORY_OVERHEAD) as u64),
+            reason: "The GPU has enough memory to run local models".to_string(),
+        },
+        Some(budget) => ModelRecommendation {
+            prefer_local: false,
+            max_local_model_bytes: Some((budget as f64 / MODEL_MEMORY_OVERHEAD) as u64),
+            reason: "The GPU only fits small local models; a cloud provider will respond better"
+                .to_string(),
+        },
+        None if total_memory >= CPU_MODEL_MEMORY => ModelRecommendation {
+            prefer_local: false,
+            max_local_model_bytes: None,
+            reason: "Local models would run on the CPU, which works but is slow".to_string(),
+        },
+        None => ModelRecommendation {
+            prefer_local: false,
+            max_local_model_bytes: None,
+            reason: "This machine is too small for local models; use a cloud provider".to_string(),
+        },
+    }
+}
+
+// CPU, memory, GPUs and free disk space, with a recommendation for local or cloud models
+#[tauri::command]
+pub async fn get_hardware_info(app: AppHandle) -> Result<HardwareInfo, AppError> {
+    let data_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| AppError::Io(format!("Failed to resolve app data dir: {}", e)))?;
+    let info = tauri::async_runtime::spawn_blocking(move || {
+        let total_memory = total_memory();
+        HardwareInfo {
+            cpu: cpu_info(),
+            total_memory_bytes: total_memory,
+            gpus: gpus().to_vec(),
+            disk: disk_for(&data_dir),
+            recommendation: recommend(total_memory),
+        }
+    })
+    .await?;
+    Ok(info)
+}
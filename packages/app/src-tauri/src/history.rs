@@ -0,0 +1,700 @@
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use once_cell::sync::OnceCell;
+use rand::RngCore;
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::{AppHandle, Manager};
+
+use crate::error::AppError;
+
+const DATABASE_FILE: &str = "history.sqlite3";
+
+// Each entry upgrades the schema by one version, tracked in `PRAGMA user_version`
+const MIGRATIONS: &[&str] = &[
+    r#"
+    CREATE TABLE conversations (
+        id TEXT PRIMARY KEY,
+        title TEXT,
+        agent_id TEXT,
+        created_at INTEGER NOT NULL,
+        updated_at INTEGER NOT NULL
+    );
+    CREATE TABLE messages (
+        id TEXT PRIMARY KEY,
+        conversation_id TEXT NOT NULL REFERENCES conversations(id) ON DELETE CASCADE,
+        role TEXT NOT NULL,
+        sender TEXT,
+        content TEXT NOT NULL,
+        created_at INTEGER NOT NULL,
+        metadata TEXT
+    );
+    CREATE INDEX messages_by_conversation ON messages(conversation_id, created_at);
+
+    CREATE VIRTUAL TABLE messages_fts USING fts5(
+        content, content = 'messages', content_rowid = 'rowid'
+    );
+    CREATE TRIGGER messages_ai AFTER INSERT ON messages BEGIN
+        INSERT INTO messages_fts(rowid, content) VALUES (new.rowid, new.content);
+    END;
+    CREATE TRIGGER messages_ad AFTER DELETE ON messages BEGIN
+        INSERT INTO messages_fts(messages_fts, rowid, content)
+            VALUES ('delete', old.rowid, old.content);
+    END;
+    CREATE TRIGGER messages_au AFTER UPDATE OF content ON messages BEGIN
+        INSERT INTO messages_fts(messages_fts, rowid, content)
+            VALUES ('delete', old.rowid, old.content);
+        INSERT INTO messages_fts(rowid, content) VALUES (new.rowid, new.content);
+    END;
+"#,
+    r#"
+    CREATE TABLE outbox (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        conversation_id TEXT NOT NULL,
+        agent_id TEXT,
+        content TEXT NOT NULL,
+        created_at INTEGER NOT NULL
+    );
+"#,
+    r#"
+    CREATE TABLE scheduled_tasks (
+        id TEXT PRIMARY KEY,
+        name TEXT NOT NULL,
+        prompt TEXT NOT NULL,
+        schedule TEXT NOT NULL,
+        agent_id TEXT,
+        delivery TEXT NOT NULL,
+        paused INTEGER NOT NULL DEFAULT 0,
+        created_at INTEGER NOT NULL,
+        next_run_at INTEGER,
+        last_run_at INTEGER,
+        last_error TEXT
+    );
+"#,
+    r#"
+    CREATE TABLE provider_usage (
+        id INTEGER PRIMARY KEY,
+        provider TEXT NOT NULL,
+        model TEXT,
+        status INTEGER NOT NULL,
+        input_tokens INTEGER NOT NULL,
+        output_tokens INTEGER NOT NULL,
+        bytes_sent INTEGER NOT NULL,
+        bytes_received INTEGER NOT NULL,
+        cost_usd REAL,
+        created_at INTEGER NOT NULL
+    );
+    CREATE INDEX provider_usage_created_at ON provider_usage(created_at);
+"#,
+    r#"
+    CREATE TABLE snippets (
+        name TEXT PRIMARY KEY,
+        description TEXT,
+        body TEXT NOT NULL,
+        version INTEGER NOT NULL,
+        created_at INTEGER NOT NULL,
+        updated_at INTEGER NOT NULL
+    );
+    CREATE TABLE snippet_versions (
+        name TEXT NOT NULL,
+        version INTEGER NOT NULL,
+        body TEXT NOT NULL,
+        created_at INTEGER NOT NULL,
+        PRIMARY KEY (name, version)
+    );
+"#,
+    r#"
+    CREATE TABLE archived_conversations (
+        conversation_id TEXT PRIMARY KEY,
+        archive_name TEXT NOT NULL,
+        updated_at INTEGER NOT NULL,
+        archived_at INTEGER NOT NULL,
+        restored INTEGER NOT NULL DEFAULT 0
+    );
+"#,
+    r#"
+    CREATE TABLE message_embeddings (
+        message_id TEXT PRIMARY KEY REFERENCES messages(id) ON DELETE CASCADE,
+        model TEXT NOT NULL,
+        dims INTEGER NOT NULL,
+        vector BLOB NOT NULL
+    );
+    CREATE INDEX message_embeddings_by_model ON message_embeddings(model);
+    -- An edited message is embedded again
+    CREATE TRIGGER message_embeddings_au AFTER UPDATE OF content ON messages BEGIN
+        DELETE FROM message_embeddings WHERE message_id = old.id;
+    END;
+"#,
+    r#"
+    CREATE TABLE attachments (
+        hash TEXT PRIMARY KEY,
+        file_name TEXT NOT NULL,
+        mime_type TEXT NOT NULL,
+        size INTEGER NOT NULL,
+        width INTEGER,
+        height INTEGER,
+        -- Extension of the generated preview, if there is one
+        preview TEXT,
+        created_at INTEGER NOT NULL
+    );
+"#,
+    r#"
+    -- Conversations forked from another one; the parent may since have been deleted
+    CREATE TABLE conversation_branches (
+        conversation_id TEXT PRIMARY KEY REFERENCES conversations(id) ON DELETE CASCADE,
+        parent_id TEXT NOT NULL,
+        fork_message_id TEXT NOT NULL,
+        created_at INTEGER NOT NULL
+    );
+    CREATE INDEX conversation_branches_by_parent ON conversation_branches(parent_id);
+"#,
+];
+
+static DB: OnceCell<Mutex<Connection>> = OnceCell::new();
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MessageRole {
+    User,
+    Agent,
+    System,
+}
+
+impl MessageRole {
+    fn as_str(self) -> &'static str {
+        match self {
+            MessageRole::User => "user",
+            MessageRole::Agent => "agent",
+            MessageRole::System => "system",
+        }
+    }
+
+    fn parse(role: &str) -> Self {
+        match role {
+            "user" => MessageRole::User,
+            "system" => MessageRole::System,
+            _ => MessageRole::Agent,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Message {
+    pub id: String,
+    pub conversation_id: String,
+    pub role: MessageRole,
+    pub sender: Option<String>,
+    pub content: String,
+    // Milliseconds since the Unix epoch
+    pub created_at: u64,
+    pub metadata: Option<Value>,
+}
+
+// A message as sent by the frontend; missing ids and timestamps are filled in
+#[derive(Debug, Clone, Deserialize)]
+pub struct NewMessage {
+    pub id: Option<String>,
+    pub conversation_id: String,
+    pub role: MessageRole,
+    pub sender: Option<String>,
+    pub content: String,
+    pub created_at: Option<u64>,
+    pub metadata: Option<Value>,
+    // Applied to the conversation when given
+    pub title: Option<String>,
+    pub agent_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConversationSummary {
+    pub id: String,
+    pub title: Option<String>,
+    pub agent_id: Option<String>,
+    pub created_at: u64,
+    pub updated_at: u64,
+    pub message_count: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Conversation {
+    #[serde(flatten)]
+    pub summary: ConversationSummary,
+    pub messages: Vec<Message>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchHit {
+    pub message: Message,
+    // Matching excerpt with the hits wrapped in `[` and `]`
+    pub snippet: String,
+}
+
+pub fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+pub fn new_id() -> String {
+    let mut bytes = [0u8; 16];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+pub fn db_error(e: rusqlite::Error) -> String {
+    format!("History database error: {}", e)
+}
+
+fn migrate(conn: &mut Connection) -> Result<(), String> {
+    let version: usize = conn
+        .query_row("PRAGMA user_version", [], |row| row.get(0))
+        .map_err(db_error)?;
+    for (index, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+        let tx = conn.transaction().map_err(db_error)?;
+        tx.execute_batch(migration).map_err(db_error)?;
+        tx.pragma_update(None, "user_version", index + 1)
+            .map_err(db_error)?;
+        tx.commit().map_err(db_error)?;
+    }
+    Ok(())
+}
+
+// Open the database in the app data dir and bring its schema up to date
+pub fn init(app: &AppHandle) -> Result<(), String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to resolve app data dir: {}", e))?;
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+
+    let mut conn = Connection::open(dir.join(DATABASE_FILE)).map_err(db_error)?;
+    conn.pragma_update(None, "journal_mode", "WAL")
+        .map_err(db_error)?;
+    conn.pragma_update(None, "foreign_keys", true)
+        .map_err(db_error)?;
+    migrate(&mut conn)?;
+    let _ = DB.set(Mutex::new(conn));
+    Ok(())
+}
+
+// Flush the write-ahead log into the database file, e.g. before the machine sleeps
+pub fn checkpoint() -> Result<(), String> {
+    let db = DB.get().ok_or("The history database is not available")?;
+    let conn = db.lock().unwrap();
+    conn.execute_batch("PRAGMA wal_checkpoint(TRUNCATE)")
+        .map_err(db_error)
+}
+
+// Run a query against the database on the current thread, for code that is already blocking
+pub fn with_db_blocking<T>(
+    f: impl FnOnce(&mut Connection) -> Result<T, String>,
+) -> Result<T, String> {
+    let db = DB.get().ok_or("The history database is not available")?;
+    let mut conn = db.lock().unwrap();
+    f(&mut conn)
+}
+
+// Run a query against the database on a blocking thread
+pub async fn with_db<T: Send + 'static>(
+    f: impl FnOnce(&mut Connection) -> Result<T, String> + Send + 'static,
+) -> Result<T, String> {
+    tauri::async_runtime::spawn_blocking(move || with_db_blocking(f))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+pub fn message_from_row(row: &Row) -> rusqlite::Result<Message> {
+    let role: String = row.get("role")?;
+    let metadata: Option<String> = row.get("metadata")?;
+    Ok(Message {
+        id: row.get("id")?,
+        conversation_id: row.get("conversation_id")?,
+        role: MessageRole::parse(&role),
+        sender: row.get("sender")?,
+        content: row.get("content")?,
+        created_at: row.get::<_, i64>("created_at")? as u64,
+        metadata: metadata.and_then(|json| serde_json::from_str(&json).ok()),
+    })
+}
+
+fn summary_from_row(row: &Row) -> rusqlite::Result<ConversationSummary> {
+    Ok(ConversationSummary {
+        id: row.get("id")?,
+        title: row.get("title")?,
+        agent_id: row.get("agent_id")?,
+        created_at: row.get::<_, i64>("created_at")? as u64,
+        updated_at: row.get::<_, i64>("updated_at")? as u64,
+        message_count: row.get::<_, i64>("message_count")? as u64,
+    })
+}
+
+const SUMMARY_QUERY: &str = "SELECT c.id, c.title, c.agent_id, c.created_at, c.updated_at,
+        (SELECT COUNT(*) FROM messages m WHERE m.conversation_id = c.id) AS message_count
+    FROM conversations c";
+
+pub fn load_summary(conn: &Connection, id: &str) -> Result<Option<ConversationSummary>, String> {
+    conn.query_row(
+        &format!("{} WHERE c.id = ?1", SUMMARY_QUERY),
+        [id],
+        summary_from_row,
+    )
+    .optional()
+    .map_err(db_error)
+}
+
+pub fn load_summaries(conn: &Connection) -> Result<Vec<ConversationSummary>, String> {
+    let mut statement = conn
+        .prepare(&format!("{} ORDER BY c.updated_at DESC", SUMMARY_QUERY))
+        .map_err(db_error)?;
+    let summaries = statement
+        .query_map([], summary_from_row)
+        .map_err(db_error)?
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .map_err(db_error)?;
+    Ok(summaries)
+}
+
+// Visit a conversation's messages in order without loading them all at once
+pub fn for_each_message(
+    conn: &Connection,
+    conversation_id: &str,
+    mut f: impl FnMut(Message) -> Result<(), String>,
+) -> Result<(), String> {
+    let mut statement = conn
+        .prepare("SELECT * FROM messages WHERE conversation_id = ?1 ORDER BY created_at, rowid")
+        .map_err(db_error)?;
+    let mut rows = statement.query([conversation_id]).map_err(db_error)?;
+    while let Some(row) = rows.next().map_err(db_error)? {
+        f(message_from_row(row).map_err(db_error)?)?;
+    }
+    Ok(())
+}
+
+pub fn load_conversation(conn: &Connection, id: &str) -> Result<Option<Conversation>, String> {
+    let Some(summary) = load_summary(conn, id)? else {
+        return Ok(None);
+    };
+    let mut messages = Vec::new();
+    for_each_message(conn, id, |message| {
+        messages.push(message);
+        Ok(())
+    })?;
+    Ok(Some(Conversation { summary, messages }))
+}
+
+// Make the stored conversation match `conversation` exactly, e.g. one received from sync
+pub fn replace_conversation(
+    conn: &mut Connection,
+    conversation: &Conversation,
+) -> Result<(), String> {
+    let summary = &conversation.summary;
+    let tx = conn.transaction().map_err(db_error)?;
+    tx.execute(
+        "INSERT INTO conversations (id, title, agent_id, created_at, updated_at)
+            VALUES (?1, ?2, ?3, ?4, ?5)
+        ON CONFLICT(id) DO UPDATE SET
+            title = excluded.title,
+            agent_id = excluded.agent_id,
+            created_at = excluded.created_at,
+            updated_at = excluded.updated_at",
+        params![
+            summary.id,
+            summary.title,
+            summary.agent_id,
+            summary.created_at as i64,
+            summary.updated_at as i64
+        ],
+    )
+    .map_err(db_error)?;
+    tx.execute(
+        "DELETE FROM messages WHERE conversation_id = ?1",
+        [&summary.id],
+    )
+    .map_err(db_error)?;
+    for message in &conversation.messages {
+        tx.execute(
+            "INSERT INTO messages (id, conversation_id, role, sender, content, created_at, metadata)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                message.id,
+                summary.id,
+                message.role.as_str(),
+                message.sender,
+                message.content,
+                message.created_at as i64,
+                message.metadata.as_ref().map(|metadata| metadata.to_string())
+            ],
+        )
+        .map_err(db_error)?;
+    }
+    tx.commit().map_err(db_error)
+}
+
+pub fn remove_conversation(conn: &Connection, id: &str) -> Result<(), String> {
+    conn.execute("DELETE FROM conversations WHERE id = ?1", [id])
+        .map_err(db_error)?;
+    Ok(())
+}
+
+// Quote every term so user input can't be parsed as FTS5 syntax; each term matches as a prefix
+fn fts_query(query: &str) -> String {
+    query
+        .split_whitespace()
+        .map(|term| format!("\"{}\"*", term.replace('"', "\"\"")))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// Save a message, returning it as stored. An id already taken by another conversation's
+// message is refused rather than overwriting that message.
+pub fn insert_message(conn: &mut Connection, message: NewMessage) -> Result<Message, AppError> {
+    let message_id = message.id.unwrap_or_else(new_id);
+    let created_at = message.created_at.unwrap_or_else(now_millis);
+    let metadata = message
+        .metadata
+        .as_ref()
+        .map(|metadata| metadata.to_string());
+
+    let database = |e| AppError::Database(db_error(e));
+    let tx = conn.transaction().map_err(database)?;
+    tx.execute(
+        "INSERT INTO conversations (id, title, agent_id, created_at, updated_at)
+            VALUES (?1, ?2, ?3, ?4, ?4)
+        ON CONFLICT(id) DO UPDATE SET
+            title = COALESCE(excluded.title, title),
+            agent_id = COALESCE(excluded.agent_id, agent_id),
+            updated_at = MAX(updated_at, excluded.updated_at)",
+        params![
+            message.conversation_id,
+            message.title,
+            message.agent_id,
+            created_at as i64
+        ],
+    )
+    .map_err(database)?;
+    // Re-saving a message (e.g. once a streamed reply completes) replaces its content
+    let saved = tx
+        .query_row(
+            "INSERT INTO messages (id, conversation_id, role, sender, content, created_at, metadata)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+            ON CONFLICT(id) DO UPDATE SET content = excluded.content, metadata = excluded.metadata
+                WHERE messages.conversation_id = excluded.conversation_id
+            RETURNING *",
+            params![
+                message_id,
+                message.conversation_id,
+                message.role.as_str(),
+                message.sender,
+                message.content,
+                created_at as i64,
+                metadata
+            ],
+            message_from_row,
+        )
+        .optional()
+        .map_err(database)?
+        .ok_or_else(|| {
+            AppError::Validation(format!(
+                "Message {} belongs to another conversation",
+                message_id
+            ))
+        })?;
+    tx.commit().map_err(database)?;
+    Ok(saved)
+}
+
+// Full-text search across all conversations, best matches first
+pub fn search(conn: &Connection, query: &str, limit: u32) -> Result<Vec<SearchHit>, String> {
+    let query = fts_query(query);
+    if query.is_empty() {
+        return Ok(Vec::new());
+    }
+    let mut statement = conn
+        .prepare(
+            "SELECT m.*, snippet(messages_fts, 0, '[', ']', '…', 12) AS snippet
+            FROM messages_fts
+            JOIN messages m ON m.rowid = messages_fts.rowid
+            WHERE messages_fts MATCH ?1
+            ORDER BY rank
+            LIMIT ?2",
+        )
+        .map_err(db_error)?;
+    let hits = statement
+        .query_map(params![query, limit], |row| {
+            Ok(SearchHit {
+                message: message_from_row(row)?,
+                snippet: row.get("snippet")?,
+            })
+        })
+        .map_err(db_error)?
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .map_err(db_error)?;
+    Ok(hits)
+}
+
+#[tauri::command]
+pub async fn save_message(message: NewMessage) -> Result<Message, AppError> {
+    let saved = with_db(move |conn| Ok(insert_message(conn, message)))
+        .await
+        .map_err(AppError::Database)??;
+    crate::embeddings::wake();
+    Ok(saved)
+}
+
+#[tauri::command]
+pub async fn list_conversations() -> Result<Vec<ConversationSummary>, AppError> {
+    with_db(|conn| load_summaries(conn))
+        .await
+        .map_err(AppError::Database)
+}
+
+#[tauri::command]
+pub async fn get_conversation(conversation_id: String) -> Result<Conversation, AppError> {
+    with_db(move |conn| {
+        load_conversation(conn, &conversation_id)?
+            .ok_or_else(|| format!("Unknown conversation: {}", conversation_id))
+    })
+    .await
+    .map_err(AppError::Database)
+}
+
+#[tauri::command]
+pub async fn search_messages(
+    query: String,
+    limit: Option<u32>,
+) -> Result<Vec<SearchHit>, AppError> {
+    with_db(move |conn| search(conn, &query, limit.unwrap_or(50)))
+        .await
+        .map_err(AppError::Database)
+}
+
+#[tauri::command]
+pub async fn delete_conversation(conversation_id: String) -> Result<(), AppError> {
+    with_db(move |conn| remove_conversation(conn, &conversation_id))
+        .await
+        .map_err(AppError::Database)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn open() -> Connection {
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.pragma_update(None, "foreign_keys", true).unwrap();
+        migrate(&mut conn).unwrap();
+        conn
+    }
+
+    fn new_message(id: &str, conversation_id: &str, content: &str) -> NewMessage {
+        NewMessage {
+            id: Some(id.to_string()),
+            conversation_id: conversation_id.to_string(),
+            role: MessageRole::User,
+            sender: None,
+            content: content.to_string(),
+            created_at: Some(1_000),
+            metadata: None,
+            title: None,
+            agent_id: None,
+        }
+    }
+
+    #[test]
+    fn migrates_to_the_latest_version_once() {
+        let mut conn = open();
+        let version = |conn: &Connection| -> usize {
+            conn.query_row("PRAGMA user_version", [], |row| row.get(0))
+                .unwrap()
+        };
+        assert_eq!(version(&conn), MIGRATIONS.len());
+        // Running again is a no-op rather than failing on existing tables
+        migrate(&mut conn).unwrap();
+        assert_eq!(version(&conn), MIGRATIONS.len());
+    }
+
+    #[test]
+    fn inserts_and_creates_the_conversation() {
+        let mut conn = open();
+        let mut message = new_message("m1", "c1", "hello there");
+        message.title = Some("Greetings".to_string());
+        message.metadata = Some(serde_json::json!({ "tokens": 3 }));
+        let saved = insert_message(&mut conn, message).unwrap();
+        assert_eq!(saved.conversation_id, "c1");
+        assert_eq!(saved.metadata, Some(serde_json::json!({ "tokens": 3 })));
+
+        let conversation = load_conversation(&conn, "c1").unwrap().unwrap();
+        assert_eq!(conversation.summary.title.as_deref(), Some("Greetings"));
+        assert_eq!(conversation.summary.message_count, 1);
+        assert_eq!(conversation.messages[0].content, "hello there");
+    }
+
+    #[test]
+    fn resaving_a_message_replaces_its_content() {
+        let mut conn = open();
+        insert_message(&mut conn, new_message("m1", "c1", "partial")).unwrap();
+        let mut complete = new_message("m1", "c1", "partial reply, now complete");
+        complete.created_at = Some(2_000);
+        let saved = insert_message(&mut conn, complete).unwrap();
+        // The original timestamp is kept
+        assert_eq!(saved.created_at, 1_000);
+
+        let conversation = load_conversation(&conn, "c1").unwrap().unwrap();
+        assert_eq!(conversation.messages.len(), 1);
+        assert_eq!(
+            conversation.messages[0].content,
+            "partial reply, now complete"
+        );
+        assert_eq!(search(&conn, "partial", 10).unwrap().len(), 1);
+        assert_eq!(search(&conn, "complete", 10).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn refuses_a_message_id_from_another_conversation() {
+        let mut conn = open();
+        insert_message(&mut conn, new_message("m1", "c1", "mine")).unwrap();
+        let result = insert_message(&mut conn, new_message("m1", "c2", "stolen"));
+        assert!(matches!(result, Err(AppError::Validation(_))));
+
+        let conversation = load_conversation(&conn, "c1").unwrap().unwrap();
+        assert_eq!(conversation.messages[0].content, "mine");
+        // Rolled back along with the message
+        assert!(load_summary(&conn, "c2").unwrap().is_none());
+    }
+
+    #[test]
+    fn searches_by_prefix_and_marks_hits() {
+        let mut conn = open();
+        insert_message(&mut conn, new_message("m1", "c1", "the quick brown fox")).unwrap();
+        insert_message(&mut conn, new_message("m2", "c2", "a lazy dog")).unwrap();
+
+        let hits = search(&conn, "qui", 10).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].message.id, "m1");
+        assert!(hits[0].snippet.contains("[quick]"));
+        assert!(search(&conn, "cat", 10).unwrap().is_empty());
+        assert!(search(&conn, "   ", 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn search_input_is_not_fts_syntax() {
+        let mut conn = open();
+        insert_message(&mut conn, new_message("m1", "c1", "say \"hi\" OR NOT")).unwrap();
+        for query in ["\"hi", "OR", "NOT", "content:hi", "(hi", "hi*"] {
+            assert!(search(&conn, query, 10).is_ok(), "{}", query);
+        }
+        assert_eq!(search(&conn, "\"hi\"", 10).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn deleting_a_conversation_removes_its_messages_from_search() {
+        let mut conn = open();
+        insert_message(&mut conn, new_message("m1", "c1", "remember this")).unwrap();
+        remove_conversation(&conn, "c1").unwrap();
+        assert!(search(&conn, "remember", 10).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,250 @@
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+use fluent_bundle::concurrent::FluentBundle;
+use fluent_bundle::{FluentArgs, FluentResource};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+use unic_langid::LanguageIdentifier;
+
+use crate::error::AppError;
+use crate::settings;
+
+// Messages missing from a translation fall back to these
+const FALLBACK: &str = "en";
+
+const LOCALES: &[(&str, &str)] = &[
+    ("en", include_str!("../locales/en.ftl")),
+    ("de", include_str!("../locales/de.ftl")),
+    ("es", include_str!("../locales/es.ftl")),
+    ("fr", include_str!("../locales/fr.ftl")),
+];
+
+// Payload of the `locale-changed` event
+#[derive(Debug, Clone, Serialize)]
+pub struct LocaleInfo {
+    // The locale Rust-side strings are produced in
+    pub locale: String,
+    // What the OS is set to, before matching it against the available translations
+    pub detected: Option<String>,
+    // The user's choice; `None` follows the OS
+    pub preference: Option<String>,
+    pub available: Vec<String>,
+}
+
+static BUNDLES: Lazy<HashMap<&'static str, FluentBundle<FluentResource>>> = Lazy::new(|| {
+    LOCALES
+        .iter()
+        .map(|&(locale, source)| {
+            let language: LanguageIdentifier = locale.parse().expect("invalid locale id");
+            let resource =
+                FluentResource::try_new(source.to_string()).unwrap_or_else(|(resource, errors)| {
+                    tracing::error!("Invalid {} translations: {:?}", locale, errors);
+                    resource
+                });
+            let mut bundle = FluentBundle::new_concurrent(vec![language]);
+            // Isolation marks show up as boxes in native menus
+            bundle.set_use_isolating(false);
+            if let Err(errors) = bundle.add_resource(resource) {
+                tracing::error!("Duplicate {} translations: {:?}", locale, errors);
+            }
+            (locale, bundle)
+        })
+        .collect()
+});
+
+static CURRENT: RwLock<&'static str> = RwLock::new(FALLBACK);
+
+// The available translation for a tag such as `de-AT`, `pt_BR.UTF-8` or `fr`
+fn supported(tag: &str) -> Option<&'static str> {
+    let tag = tag.split(['.', '@']).next()?.replace('_', "-");
+    let language: LanguageIdentifier = tag.parse().ok()?;
+    LOCALES
+        .iter()
+        .map(|&(locale, _)| locale)
+        .find(|locale| *locale == language.language.as_str())
+}
+
+#[cfg(windows)]
+fn detect() -> Option<String> {
+    use windows_sys::Win32::Globalization::GetUserDefaultLocaleName;
+
+    // LOCALE_NAME_MAX_LENGTH
+    let mut buffer = [0u16; 85];
+    let len = unsafe { GetUserDefaultLocaleName(buffer.as_mut_ptr(), buffer.len() as i32) };
+    // The length includes the terminating null
+    (len > 1).then(|| String::from_utf16_lossy(&buffer[..len as usize - 1]))
+}
+
+// GUI apps on macOS don't inherit a shell's `LANG`, so ask the OS first
+#[cfg(not(windows))]
+fn detect() -> Option<String> {
+    #[cfg(target_os = "macos")]
+    if let Some(locale) = crate::hardware::output("defaults", &["read", "-g", "AppleLocale"]) {
+        return Some(locale.trim().to_string());
+    }
+    ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|value| !value.is_empty() && value != "C" && value != "POSIX")
+}
+
+fn resolve(preference: Option<&str>) -> &'static str {
+    preference
+        .and_then(supported)
+        .or_else(|| detect().as_deref().and_then(supported))
+        .unwrap_or(FALLBACK)
+}
+
+pub fn locale() -> &'static str {
+    *CURRENT.read().unwrap()
+}
+
+fn format(locale: &str, id: &str, args: Option<&FluentArgs>) -> Option<String> {
+    let bundle = BUNDLES.get(locale)?;
+    let pattern = bundle.get_message(id)?.value()?;
+    let mut errors = Vec::new();
+    let text = bundle.format_pattern(pattern, args, &mut errors);
+    if !errors.is_empty() {
+        tracing::warn!("Failed to format {} in {}: {:?}", id, locale, errors);
+    }
+    Some(text.into_owned())
+}
+
+// The message `id` with `args` filled in, in the current locale or else in English
+pub fn t_args(id: &str, args: &[(&str, &str)]) -> String {
+    let mut fluent_args = FluentArgs::new();
+    for &(name, value) in args {
+        fluent_args.set(name, value.to_string());
+    }
+    let args = (!args.is_empty()).then_some(&fluent_args);
+    format(locale(), id, args)
+        .or_else(|| format(FALLBACK, id, args))
+        .unwrap_or_else(|| {
+            tracing::warn!("Missing translation for {}", id);
+            id.to_string()
+        })
+}
+
+pub fn t(id: &str) -> String {
+    t_args(id, &[])
+}
+
+fn info() -> LocaleInfo {
+    LocaleInfo {
+        locale: locale().to_string(),
+        detected: detect(),
+        preference: settings::current().locale,
+        available: LOCALES
+            .iter()
+            .map(|&(locale, _)| locale.to_string())
+            .collect(),
+    }
+}
+
+// Pick the locale from the settings or the OS; called once from the setup hook, before any
+// menus are built
+pub fn init() {
+    *CURRENT.write().unwrap() = resolve(settings::current().locale.as_deref());
+    tracing::info!(locale = locale(), "Using locale");
+}
+
+#[tauri::command]
+pub fn get_locale() -> LocaleInfo {
+    info()
+}
+
+// Switch Rust-side strings to `locale`, or back to following the OS with `None`, and relabel
+// the native menus
+#[tauri::command]
+pub fn set_locale(app: AppHandle, locale: Option<String>) -> Result<LocaleInfo, AppError> {
+    if let Some(locale) = &locale {
+        supported(locale).ok_or_else(|| format!("Unsupported locale: {}", locale))?;
+    }
+    settings::update(&app, |settings| settings.locale = locale.clone())?;
+    *CURRENT.write().unwrap() = resolve(locale.as_deref());
+
+    #[cfg(desktop)]
+    {
+        crate::tray::relabel();
+        if let Err(e) = crate::menu::install(&app) {
+            tracing::warn!("Failed to rebuild the app menu: {}", e);
+        }
+    }
+
+    let info = info();
+    if let Err(e) = app.emit("locale-changed", &info) {
+        tracing::warn!("Failed to emit locale change: {}", e);
+    }
+    Ok(info)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(source: &str) -> Vec<&str> {
+        source
+            .lines()
+            .filter_map(|line| line.split_once(" = ").map(|(id, _)| id))
+            .filter(|id| !id.starts_with(['#', ' ']))
+            .collect()
+    }
+
+    #[test]
+    fn translations_parse() {
+        for &(locale, source) in LOCALES {
+            assert!(
+                FluentResource::try_new(source.to_string()).is_ok(),
+                "{} has syntax errors",
+                locale
+            );
+        }
+    }
+
+    #[test]
+    fn every_locale_has_every_message() {
+        let english = ids(LOCALES[0].1);
+        assert!(english.contains(&"lan-pair-first"));
+        for &(locale, source) in &LOCALES[1..] {
+            let mut missing: Vec<_> = english
+                .iter()
+                .filter(|id| BUNDLES[locale].get_message(id).is_none())
+                .collect();
+            missing.sort();
+            assert!(missing.is_empty(), "{} is missing {:?}", locale, missing);
+            assert_eq!(
+                ids(source).len(),
+                english.len(),
+                "{} has extra messages",
+                locale
+            );
+        }
+    }
+
+    #[test]
+    fn formats_arguments() {
+        let mut args = FluentArgs::new();
+        args.set("max", "1440");
+        assert_eq!(
+            format("en", "lan-ttl-invalid", Some(&args)).unwrap(),
+            "The pairing must last between 1 and 1440 minutes"
+        );
+        assert_eq!(
+            format("de", "lan-ttl-invalid", Some(&args)).unwrap(),
+            "Die Kopplung muss zwischen 1 und 1440 Minuten dauern"
+        );
+        assert!(format("en", "no-such-message", None).is_none());
+    }
+
+    #[test]
+    fn matches_locale_tags() {
+        assert_eq!(supported("de-AT"), Some("de"));
+        assert_eq!(supported("pt_BR.UTF-8"), None);
+        assert_eq!(supported("fr_FR.UTF-8@euro"), Some("fr"));
+        assert_eq!(supported("es"), Some("es"));
+        assert_eq!(supported("not a tag"), None);
+        assert_eq!(resolve(Some("fr-CA")), "fr");
+    }
+}
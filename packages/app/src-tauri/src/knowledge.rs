@@ -0,0 +1,244 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+
+use crate::capabilities::{self, Capability};
+use crate::error::AppError;
+use crate::{settings, workspace};
+
+// The knowledge plugin loads documents from this workspace dir (its `KNOWLEDGE_PATH`)
+pub const KNOWLEDGE_DIR: &str = "docs";
+
+const SUPPORTED_EXTENSIONS: &[&str] = &[
+    "md", "mdx", "txt", "pdf", "json", "csv", "html", "htm", "docx", "xml", "yaml", "yml",
+];
+const MAX_DOCUMENT_BYTES: u64 = 50 * 1024 * 1024;
+
+// Editors write files in several steps; wait for changes to settle before copying
+const DEBOUNCE: Duration = Duration::from_millis(750);
+
+static WATCHER: Lazy<Mutex<Option<RecommendedWatcher>>> = Lazy::new(|| Mutex::new(None));
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IndexStatus {
+    Indexed,
+    Skipped,
+    Failed,
+}
+
+// Payload of the `knowledge-indexed` event
+#[derive(Debug, Clone, Serialize)]
+pub struct KnowledgeIndexed {
+    pub source: PathBuf,
+    pub destination: Option<PathBuf>,
+    pub status: IndexStatus,
+    pub message: Option<String>,
+}
+
+pub fn knowledge_dir() -> Result<PathBuf, String> {
+    Ok(workspace::dir()?.join(KNOWLEDGE_DIR))
+}
+
+pub fn is_supported(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| SUPPORTED_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()))
+}
+
+// Mirror a file under `docs/<folder name>/`, keeping its path relative to the registered folder
+fn destination(root: &Path, file: &Path) -> Result<PathBuf, String> {
+    let relative = file
+        .strip_prefix(root)
+        .map_err(|_| format!("{} is outside {}", file.display(), root.display()))?;
+    let folder = root
+        .file_name()
+        .ok_or_else(|| format!("Cannot use {} as a knowledge folder", root.display()))?;
+    Ok(knowledge_dir()?.join(folder).join(relative))
+}
+
+fn copy_document(root: &Path, file: &Path) -> KnowledgeIndexed {
+    let result = (|| {
+        let metadata = fs::metadata(file).map_err(|e| e.to_string())?;
+        if !metadata.is_file() || !is_supported(file) {
+            return Ok(None);
+        }
+        if metadata.len() > MAX_DOCUMENT_BYTES {
+            return Err(format!(
+                "File is larger than {} MB",
+                MAX_DOCUMENT_BYTES / 1024 / 1024
+            ));
+        }
+        let target = destination(root, file)?;
+        if let Some(dir) = target.parent() {
+            fs::create_dir_all(dir)
+                .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        }
+        fs::copy(file, &target).map_err(|e| format!("Failed to copy: {}", e))?;
+        Ok(Some(target))
+    })();
+
+    let (destination, status, message) = match result {
+        Ok(Some(target)) => (Some(target), IndexStatus::Indexed, None),
+        Ok(None) => (None, IndexStatus::Skipped, None),
+        Err(e) => (None, IndexStatus::Failed, Some(e)),
+    };
+    KnowledgeIndexed {
+        source: file.to_path_buf(),
+        destination,
+        status,
+        message,
+    }
+}
+
+fn report(app: &AppHandle, result: &KnowledgeIndexed) {
+    if let Some(message) = &result.message {
+        tracing::warn!(source = %result.source.display(), "Failed to index document: {}", message);
+    }
+    if let Err(e) = app.emit("knowledge-indexed", result) {
+        tracing::warn!("Failed to emit knowledge-indexed: {}", e);
+    }
+}
+
+// The registered folder a changed file belongs to, if any
+fn owning_root(file: &Path) -> Option<PathBuf> {
+    settings::current()
+        .knowledge_paths
+        .into_iter()
+        .find(|root| file.starts_with(root))
+}
+
+fn index(app: &AppHandle, file: &Path) {
+    if let Some(root) = owning_root(file) {
+        let result = copy_document(&root, file);
+        if !matches!(result.status, IndexStatus::Skipped) {
+            report(app, &result);
+        }
+    }
+}
+
+// Copy every supported document already in a folder
+fn sync_folder(app: &AppHandle, root: &Path) {
+    let mut pending = vec![root.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        for path in entries.filter_map(Result::ok).map(|entry| entry.path()) {
+            if path.is_dir() {
+                pending.push(path);
+            } else if is_supported(&path) {
+                report(app, &copy_document(root, &path));
+            }
+        }
+    }
+}
+
+// Collect changed paths until they stop arriving, then copy them once
+fn process_events(app: AppHandle, events: Receiver<notify::Result<Event>>) {
+    let mut changed = HashSet::new();
+    loop {
+        match events.recv_timeout(DEBOUNCE) {
+            Ok(Ok(event)) => {
+                if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+                    changed.extend(event.paths);
+                }
+            }
+            Ok(Err(e)) => tracing::warn!("Knowledge watcher error: {}", e),
+            Err(RecvTimeoutError::Timeout) => {
+                for file in changed.drain() {
+                    index(&app, &file);
+                }
+            }
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+    }
+}
+
+fn watch(path: &Path) -> Result<(), String> {
+    let mut guard = WATCHER.lock().unwrap();
+    let watcher = guard
+        .as_mut()
+        .ok_or("The knowledge watcher is not running")?;
+    watcher
+        .watch(path, RecursiveMode::Recursive)
+        .map_err(|e| format!("Failed to watch {}: {}", path.display(), e))
+}
+
+// Start watching the registered folders; called once from the setup hook
+pub fn init(app: &AppHandle) -> Result<(), String> {
+    let (tx, rx) = mpsc::channel();
+    let watcher = notify::recommended_watcher(move |event| {
+        let _ = tx.send(event);
+    })
+    .map_err(|e| format!("Failed to start the knowledge watcher: {}", e))?;
+    *WATCHER.lock().unwrap() = Some(watcher);
+
+    let handle = app.clone();
+    thread::spawn(move || process_events(handle, rx));
+
+    for path in settings::current().knowledge_paths {
+        if let Err(e) = watch(&path) {
+            tracing::warn!("{}", e);
+        }
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn list_knowledge_paths() -> Vec<PathBuf> {
+    settings::current().knowledge_paths
+}
+
+// Register a folder, copy its current documents and keep it in sync from now on
+#[tauri::command]
+pub async fn add_knowledge_path(app: AppHandle, dir: PathBuf) -> Result<Vec<PathBuf>, AppError> {
+    capabilities::require(Capability::ExportData)?;
+    let dir = dir
+        .canonicalize()
+        .map_err(|e| format!("Failed to resolve {}: {}", dir.display(), e))?;
+    if !dir.is_dir() {
+        return Err(AppError::Validation(format!(
+            "{} is not a directory",
+            dir.display()
+        )));
+    }
+    if dir.starts_with(knowledge_dir()?) {
+        return Err(AppError::Validation(
+            "The workspace knowledge dir cannot be registered as a source".to_string(),
+        ));
+    }
+
+    let settings = settings::update(&app, |settings| {
+        if !settings.knowledge_paths.contains(&dir) {
+            settings.knowledge_paths.push(dir.clone());
+        }
+    })?;
+    watch(&dir)?;
+
+    tauri::async_runtime::spawn_blocking(move || sync_folder(&app, &dir))
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(settings.knowledge_paths)
+}
+
+// Stop watching a folder; documents already copied stay in the knowledge dir
+#[tauri::command]
+pub fn remove_knowledge_path(app: AppHandle, dir: PathBuf) -> Result<Vec<PathBuf>, AppError> {
+    if let Some(watcher) = WATCHER.lock().unwrap().as_mut() {
+        let _ = watcher.unwatch(&dir);
+    }
+    let settings = settings::update(&app, |settings| {
+        settings.knowledge_paths.retain(|path| path != &dir)
+    })?;
+    Ok(settings.knowledge_paths)
+}
@@ -1,92 +1,545 @@
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
-use std::net::TcpStream;
-use std::process::{Child, Command};
-use std::sync::{Arc, Mutex};
-use std::thread;
-use std::time::{Duration, Instant};
-use tauri::Manager;
+use std::time::Instant;
 
-// Store the server process so we can kill it when the app closes
-static SERVER_PROCESS: once_cell::sync::Lazy<Arc<Mutex<Option<Child>>>> = 
-    once_cell::sync::Lazy::new(|| Arc::new(Mutex::new(None)));
+use tauri::{AppHandle, Manager};
+
+use startup::Track;
+
+mod archive;
+mod attachments;
+mod audit;
+mod auth;
+#[cfg(desktop)]
+mod autostart;
+mod backup;
+mod branches;
+mod capabilities;
+mod characters;
+mod cli;
+#[cfg(desktop)]
+mod clipboard;
+mod config;
+mod connectivity;
+mod crash;
+mod deep_link;
+mod diagnostics;
+mod doctor;
+mod downloads;
+mod embeddings;
+mod error;
+mod export;
+mod file_drop;
+mod filters;
+mod hardware;
+mod history;
+mod i18n;
+mod knowledge;
+mod local_models;
+mod logging;
+mod mcp;
+mod media;
+#[cfg(desktop)]
+mod menu;
+mod network;
+mod onboarding;
+mod plugins;
+#[cfg(desktop)]
+mod power;
+mod qr;
+#[cfg(desktop)]
+mod quick_chat;
+mod redaction;
+mod scheduler;
+#[cfg(desktop)]
+mod screenshot;
+mod secrets;
+mod server;
+mod settings;
+#[cfg(desktop)]
+mod shortcuts;
+mod snippets;
+#[cfg(desktop)]
+mod speech;
+mod startup;
+mod stt;
+mod sync;
+mod templates;
+#[cfg(desktop)]
+mod theme;
+mod tls;
+#[cfg(desktop)]
+mod tray;
+#[cfg(desktop)]
+mod voice;
+mod webhooks;
+#[cfg(desktop)]
+mod windows;
+mod workspace;
 
 #[tauri::command]
 fn greet(name: &str) -> String {
     format!("Hello, {}! You've been greeted from Rust!", name)
 }
 
-// Check if the server is running by attempting to connect to the port
-fn is_server_running() -> bool {
-    match TcpStream::connect("127.0.0.1:3000") {
-        Ok(_) => true,
-        Err(_) => false,
-    }
-}
-
-// Shutdown server when app exits
-fn shutdown_server() {
-    println!("Shutting down Eliza server...");
-    let mut guard = SERVER_PROCESS.lock().unwrap();
-    if let Some(ref mut child) = *guard {
-        if let Err(e) = child.kill() {
-            eprintln!("Failed to kill Eliza server: {}", e);
-        } else {
-            println!("Eliza server shut down successfully");
-        }
-    }
-    *guard = None;
+// Stop the server off the main thread so the UI can show progress, then exit
+#[cfg(desktop)]
+pub(crate) fn exit_app(app: &AppHandle) {
+    let app = app.clone();
+    std::thread::spawn(move || {
+        mcp::shutdown_all(&app);
+        server::shutdown_server(&app);
+        app.exit(0);
+    });
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    startup::begin();
+    crash::install_hook(env!("CARGO_PKG_VERSION").to_string());
+
+    let mut builder = tauri::Builder::default();
+
+    // Must be registered first. Later launches (e.g. from an OAuth deep link) exit and
+    // focus this instance instead; their deep link reaches the deep-link plugin handler.
+    #[cfg(desktop)]
+    {
+        builder = builder
+            .plugin(tauri_plugin_single_instance::init(|app, _argv, _cwd| {
+                tray::show_main_window(app);
+            }))
+            .plugin(autostart::plugin())
+            .plugin(tauri_plugin_global_shortcut::Builder::new().build())
+            .plugin(tauri_plugin_clipboard_manager::init());
+    }
+
     // Register cleanup for when app exits
-    let app = tauri::Builder::default()
+    let app = builder
+        .plugin(tauri_plugin_deep_link::init())
+        .plugin(tauri_plugin_dialog::init())
+        .plugin(tauri_plugin_notification::init())
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_shell::init())
-        .invoke_handler(tauri::generate_handler![greet])
+        .register_asynchronous_uri_scheme_protocol(attachments::SCHEME, attachments::protocol)
+        .register_asynchronous_uri_scheme_protocol(media::SCHEME, media::protocol)
+        .invoke_handler(tauri::generate_handler![
+            greet,
+            server::config::get_server_config,
+            server::config::set_server_port,
+            server::config::set_shutdown_timeout,
+            server::config::set_minimize_to_tray,
+            server::config::set_auto_select_port,
+            server::config::set_server_launch_options,
+            server::get_server_lifecycle,
+            server::force_kill_server_tree,
+            server::idle::is_server_suspended,
+            i18n::get_locale,
+            i18n::set_locale,
+            server::external::get_server_ownership,
+            server::external::adopt_server,
+            server::external::release_server,
+            server::external::set_stop_external_on_exit,
+            server::logs::get_server_logs,
+            server::start_server,
+            server::stop_server,
+            server::restart_server,
+            #[cfg(desktop)]
+            autostart::enable_autostart,
+            #[cfg(desktop)]
+            autostart::disable_autostart,
+            #[cfg(desktop)]
+            autostart::is_autostart_enabled,
+            #[cfg(desktop)]
+            voice::get_push_to_talk_shortcut,
+            #[cfg(desktop)]
+            voice::set_push_to_talk_shortcut,
+            #[cfg(desktop)]
+            speech::speak,
+            #[cfg(desktop)]
+            speech::pause_speech,
+            #[cfg(desktop)]
+            speech::resume_speech,
+            #[cfg(desktop)]
+            speech::stop_speech,
+            #[cfg(desktop)]
+            speech::list_voices,
+            #[cfg(desktop)]
+            quick_chat::toggle_quick_chat,
+            #[cfg(desktop)]
+            quick_chat::set_quick_chat_shortcut,
+            #[cfg(desktop)]
+            quick_chat::set_quick_chat_enabled,
+            #[cfg(desktop)]
+            clipboard::set_clipboard_watcher_enabled,
+            #[cfg(desktop)]
+            clipboard::configure_clipboard_action,
+            #[cfg(desktop)]
+            clipboard::last_clipboard_response,
+            #[cfg(desktop)]
+            screenshot::capture_screenshot,
+            #[cfg(desktop)]
+            screenshot::list_capture_windows,
+            #[cfg(desktop)]
+            windows::open_window,
+            #[cfg(desktop)]
+            theme::get_theme,
+            #[cfg(desktop)]
+            theme::set_theme,
+            server::instances::create_instance,
+            server::instances::delete_instance,
+            server::instances::list_instances,
+            server::instances::start_instance,
+            server::instances::stop_instance,
+            server::health::server_health,
+            server::poller::get_server_health_state,
+            server::poller::set_health_poll_config,
+            server::health::get_server_url,
+            server::readiness::wait_for_server_ready,
+            server::metrics::get_server_metrics_history,
+            server::prometheus::get_metrics,
+            server::prometheus::configure_metrics_endpoint,
+            server::usage::get_usage_report,
+            server::usage::set_usage_budget,
+            server::gateway::get_provider_health,
+            server::gateway::set_provider_guard,
+            network::get_network_proxy,
+            network::set_network_proxy,
+            network::test_proxy,
+            tls::get_local_cert_info,
+            tls::set_local_tls,
+            tls::rotate_local_cert,
+            server::lan::get_lan_access_status,
+            server::lan::enable_lan_access,
+            server::lan::get_pairing_qr,
+            server::mdns::discover_agents,
+            server::tailnet::get_tailnet_status,
+            server::tailnet::enable_tailnet_access,
+            server::tailnet::list_remote_clients,
+            server::proxy::get_proxy_url,
+            server::ws::ws_connect,
+            server::ws::ws_send,
+            server::ws::ws_disconnect,
+            server::ws::ws_state,
+            server::chat::stream_chat,
+            server::chat::cancel_stream,
+            server::chat::broadcast_prompt,
+            deep_link::take_pending_deep_link,
+            auth::oauth::begin_oauth_flow,
+            auth::oauth::handle_oauth_callback,
+            auth::get_auth_status,
+            auth::get_session_info,
+            auth::secure_store::get_secret_backend,
+            auth::verification::require_user_verification,
+            auth::verification::set_verification_settings,
+            audit::get_audit_log,
+            sync::configure_sync,
+            sync::disable_sync,
+            sync::sync_now,
+            sync::get_sync_status,
+            capabilities::request_capability,
+            capabilities::list_capability_grants,
+            capabilities::revoke_capability,
+            auth::logout,
+            auth::refresh::get_access_token,
+            auth::accounts::list_accounts,
+            auth::accounts::store_auth_session,
+            auth::accounts::switch_account,
+            auth::accounts::remove_account,
+            crash::get_crash_reports,
+            crash::clear_crash_reports,
+            crash::set_crash_upload_consent,
+            diagnostics::export_diagnostics,
+            doctor::run_doctor,
+            logging::set_log_level,
+            logging::get_app_logs,
+            settings::get_all_settings,
+            settings::get_setting,
+            settings::set_setting,
+            secrets::set_api_key,
+            secrets::get_api_key,
+            secrets::list_providers,
+            secrets::delete_api_key,
+            config::read_env,
+            config::write_env_var,
+            config::delete_env_var,
+            workspace::get_workspace_dir,
+            workspace::set_workspace_dir,
+            workspace::create_workspace,
+            filters::get_content_filters,
+            filters::set_content_filters_enabled,
+            filters::save_content_filter_rule,
+            filters::delete_content_filter_rule,
+            filters::reset_content_filters,
+            filters::test_content_filters,
+            file_drop::import_files,
+            backup::create_backup,
+            backup::restore_backup,
+            backup::schedule::configure_backups,
+            backup::schedule::list_backups,
+            backup::schedule::delete_backup,
+            archive::configure_retention,
+            archive::archive_now,
+            archive::list_archives,
+            archive::restore_archive,
+            attachments::store_attachment,
+            attachments::get_attachment,
+            attachments::delete_attachment,
+            media::get_media_url,
+            downloads::list_downloads,
+            downloads::pause_download,
+            downloads::resume_download,
+            downloads::cancel_download,
+            history::save_message,
+            history::list_conversations,
+            history::get_conversation,
+            history::search_messages,
+            embeddings::semantic_search,
+            embeddings::get_semantic_index_status,
+            embeddings::configure_embeddings,
+            history::delete_conversation,
+            branches::fork_conversation,
+            branches::list_branches,
+            connectivity::get_connectivity,
+            connectivity::queue_chat_message,
+            connectivity::list_queued_messages,
+            connectivity::discard_queued_message,
+            stt::list_stt_models,
+            stt::download_stt_model,
+            stt::delete_stt_model,
+            stt::set_stt_model,
+            stt::transcribe_audio,
+            export::export_conversation,
+            export::export_all_conversations,
+            knowledge::list_knowledge_paths,
+            knowledge::add_knowledge_path,
+            knowledge::remove_knowledge_path,
+            characters::list_characters,
+            characters::read_character,
+            characters::validate_character,
+            characters::save_character,
+            characters::delete_character,
+            characters::import::import_character_from_url,
+            characters::import::browse_character_registry,
+            templates::list_templates,
+            templates::create_from_template,
+            scheduler::create_task,
+            scheduler::list_tasks,
+            scheduler::pause_task,
+            scheduler::resume_task,
+            scheduler::delete_task,
+            snippets::list_snippets,
+            snippets::create_snippet,
+            snippets::delete_snippet,
+            snippets::list_snippet_versions,
+            snippets::render_snippet,
+            snippets::export_snippets,
+            snippets::import_snippets,
+            webhooks::get_webhook_status,
+            webhooks::configure_webhooks,
+            webhooks::register_webhook,
+            webhooks::unregister_webhook,
+            webhooks::get_webhook_token,
+            webhooks::rotate_webhook_token,
+            mcp::list_mcp_servers,
+            mcp::add_mcp_server,
+            mcp::remove_mcp_server,
+            mcp::start_mcp_server,
+            mcp::stop_mcp_server,
+            mcp::get_mcp_logs,
+            cli::get_cli_status,
+            cli::set_cli_path,
+            server::container::get_container_status,
+            server::container::set_server_backend,
+            server::container::pull_container_image,
+            server::profiles::list_profiles,
+            server::profiles::create_profile,
+            server::profiles::delete_profile,
+            server::profiles::activate_profile,
+            server::remote::list_remote_servers,
+            server::remote::add_remote_server,
+            server::remote::remove_remote_server,
+            server::remote::set_remote_server_key,
+            server::remote::connect_remote_server,
+            server::remote::disconnect_remote_server,
+            server::remote::get_remote_server_status,
+            server::remote::get_remote_session,
+            server::remote::save_remote_session,
+            server::runtime::get_runtime_info,
+            startup::get_startup_timings,
+            server::runtime::reinstall_runtime,
+            server::sidecar::get_server_runtime,
+            server::sidecar::set_server_runtime,
+            cli::install::install_cli,
+            cli::version::get_cli_version,
+            cli::version::check_cli_update,
+            cli::version::upgrade_cli,
+            plugins::list_installed_plugins,
+            plugins::search_registry,
+            plugins::install_plugin,
+            plugins::remove_plugin,
+            plugins::enable_plugin,
+            plugins::config::get_plugin_config,
+            plugins::config::set_plugin_config,
+            hardware::get_hardware_info,
+            onboarding::get_onboarding_state,
+            onboarding::complete_step,
+            local_models::detect_ollama,
+            local_models::list_local_models,
+            local_models::pull_local_model,
+            local_models::delete_local_model,
+            local_models::use_local_model
+        ])
         .setup(|app| {
-            // Start the server if it's not already running
-            if !is_server_running() {
-                println!("Starting Eliza server...");
-                match Command::new("elizaos")
-                    .arg("start")
-                    .spawn() {
-                        Ok(child) => {
-                            // Store the process so we can kill it when the app closes
-                            let mut server_guard = SERVER_PROCESS.lock().unwrap();
-                            *server_guard = Some(child);
-                            println!("Eliza server process started");
-                        },
-                        Err(e) => {
-                            eprintln!("Failed to start Eliza server: {}", e);
-                        }
-                    };
-            } else {
-                println!("Eliza server is already running");
+            let setup_started = Instant::now();
+            startup::measure(Track::App, "logging", || {
+                if let Err(e) = logging::init(app.handle()) {
+                    eprintln!("{}", e);
+                }
+                if let Err(e) = audit::init(app.handle()) {
+                    tracing::warn!("{}", e);
+                }
+            });
+            startup::measure(Track::App, "settings", || {
+                settings::init(app.handle());
+                i18n::init();
+                redaction::init(app.handle());
+            });
+            startup::measure(Track::App, "keychain", || {
+                if let Err(e) = auth::secure_store::init(app.handle()) {
+                    tracing::warn!("{}", e);
+                }
+            });
+            logging::apply_level(settings::current().log_level);
+            if let Err(e) = crash::init(app.handle()) {
+                tracing::warn!("{}", e);
             }
-            
-            // Add event listener for app exit
-            let app_handle = app.handle();
-            
+            auth::refresh::spawn(app.handle().clone());
+            deep_link::init(app.handle())?;
+            server::metrics::spawn(app.handle().clone());
+            server::prometheus::init();
+            server::usage::init(app.handle());
+            network::init();
+            startup::measure(Track::App, "tls", || {
+                if let Err(e) = tls::init(app.handle()) {
+                    tracing::error!("{}", e);
+                }
+            });
+            backup::schedule::spawn(app.handle().clone());
+            server::proxy::spawn(app.handle().clone());
+            startup::measure(Track::App, "history", || {
+                if let Err(e) = history::init(app.handle()) {
+                    tracing::error!("{}", e);
+                }
+            });
+            sync::spawn(app.handle().clone());
+            archive::spawn(app.handle().clone());
+            embeddings::spawn();
+            scheduler::spawn(app.handle().clone());
+            webhooks::init(app.handle());
+            server::tailnet::init(app.handle());
+            mcp::spawn(app.handle().clone());
+            server::poller::spawn(app.handle().clone());
+            connectivity::spawn(app.handle().clone());
+            server::idle::spawn(app.handle().clone());
+            server::remote::spawn(app.handle().clone());
+            startup::measure(Track::App, "knowledge", || {
+                if let Err(e) = knowledge::init(app.handle()) {
+                    tracing::warn!("{}", e);
+                }
+            });
+
+            #[cfg(desktop)]
+            let launched_at_login = autostart::launched_at_login();
+            #[cfg(not(desktop))]
+            let launched_at_login = false;
+
+            // Start the server if it's not already running, without blocking the UI. During
+            // first-run setup the onboarding flow starts it once everything is configured.
+            let start_server = onboarding::is_complete()
+                && (!launched_at_login || settings::current().autostart_server);
+            let app_handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                if !start_server {
+                    tracing::info!("Leaving the Eliza server stopped");
+                } else if server::remote::is_remote() {
+                    tracing::info!("Connected to a remote Eliza server; not starting one");
+                } else if server::health::check().await.is_ok() {
+                    tracing::info!("Eliza server is already running");
+                    server::readiness::track(&app_handle);
+                } else {
+                    let started =
+                        tauri::async_runtime::spawn_blocking(move || server::start(&app_handle))
+                            .await
+                            .map_err(|e| e.to_string())
+                            .and_then(|result| result);
+                    if let Err(e) = started {
+                        tracing::error!("{}", e);
+                    }
+                }
+            });
+
             #[cfg(desktop)]
             {
+                windows::init(app.handle());
+                theme::init(app.handle());
+                tray::init(app.handle())?;
+                menu::init(app.handle())?;
+                autostart::init(app.handle());
+                if let Err(e) = voice::init(app.handle()) {
+                    tracing::warn!("{}", e);
+                }
+                if let Err(e) = quick_chat::init(app.handle()) {
+                    tracing::warn!("{}", e);
+                }
+                if let Err(e) = clipboard::init(app.handle()) {
+                    tracing::warn!("{}", e);
+                }
+                if let Err(e) = power::init(app.handle()) {
+                    tracing::warn!("{}", e);
+                }
+
                 if let Some(main_window) = app.get_webview_window("main") {
-                    main_window.on_window_event(move |event| {
-                        if let tauri::WindowEvent::CloseRequested { .. } = event {
-                            shutdown_server();
+                    if launched_at_login && settings::current().autostart_minimized {
+                        let _ = main_window.hide();
+                    }
+                    let app_handle = app.handle().clone();
+                    let window = main_window.clone();
+                    main_window.on_window_event(move |event| match event {
+                        tauri::WindowEvent::CloseRequested { api, .. } => {
+                            api.prevent_close();
+                            if server::config::current().minimize_to_tray {
+                                let _ = window.hide();
+                            } else {
+                                exit_app(&app_handle);
+                            }
+                        }
+                        tauri::WindowEvent::DragDrop(tauri::DragDropEvent::Drop {
+                            paths, ..
+                        }) => file_drop::handle_drop(&app_handle, paths.clone()),
+                        tauri::WindowEvent::Focused(focused) => server::idle::set_focused(*focused),
+                        tauri::WindowEvent::ThemeChanged(_) => {
+                            let app_handle = app_handle.clone();
+                            std::thread::spawn(move || theme::refresh(&app_handle));
                         }
+                        _ => {}
                     });
                 }
             }
-            
+
+            startup::record(Track::App, "setup", setup_started);
+            startup::app_ready();
             Ok(())
         })
         .build(tauri::generate_context!())
         .expect("error while building tauri application");
-        
-    app.run(|_app_handle, event| {
+
+    app.run(|app_handle, event| {
         if let tauri::RunEvent::Exit = event {
-            shutdown_server();
+            #[cfg(desktop)]
+            if let Err(e) = windows::save(app_handle) {
+                tracing::warn!("{}", e);
+            }
+            mcp::shutdown_all(app_handle);
+            server::shutdown_server(app_handle);
         }
     });
 }
@@ -0,0 +1,355 @@
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::error::AppError;
+use crate::{config, hardware};
+
+const DEFAULT_OLLAMA_URL: &str = "http://127.0.0.1:11434";
+const DETECT_TIMEOUT: Duration = Duration::from_secs(2);
+
+static PULLING: Lazy<Mutex<HashSet<String>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OllamaStatus {
+    pub running: bool,
+    pub url: String,
+    pub version: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LocalModel {
+    pub name: String,
+    pub size_bytes: u64,
+    pub parameter_size: Option<String>,
+    pub quantization: Option<String>,
+    pub family: Option<String>,
+    // Whether it fits in GPU memory; unknown without a GPU we can size
+    pub fits_in_gpu: Option<bool>,
+}
+
+// Payload of the `local-model-pull-progress` event
+#[derive(Debug, Clone, Serialize)]
+struct PullProgress<'a> {
+    model: &'a str,
+    status: String,
+    completed: Option<u64>,
+    total: Option<u64>,
+    // Set once when the download turns out to be larger than the free disk space or GPU memory
+    warning: Option<String>,
+    done: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct Version {
+    version: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Tags {
+    models: Vec<Tag>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Tag {
+    name: String,
+    size: u64,
+    #[serde(default)]
+    details: TagDetails,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct TagDetails {
+    parameter_size: Option<String>,
+    quantization_level: Option<String>,
+    family: Option<String>,
+}
+
+// One line of the streamed `/api/pull` response
+#[derive(Debug, Deserialize)]
+struct PullLine {
+    #[serde(default)]
+    status: String,
+    total: Option<u64>,
+    completed: Option<u64>,
+    error: Option<String>,
+}
+
+// `OLLAMA_HOST` as Ollama itself reads it, which may omit the scheme
+pub(crate) fn base_url() -> String {
+    match std::env::var("OLLAMA_HOST") {
+        Ok(host) if !host.trim().is_empty() => {
+            let host = host.trim().trim_end_matches('/');
+            if host.contains("://") {
+                host.to_string()
+            } else {
+                format!("http://{}", host)
+            }
+        }
+        _ => DEFAULT_OLLAMA_URL.to_string(),
+    }
+}
+
+// Ollama model names, e.g. `llama3.2:3b` or `hf.co/user/repo:Q4_K_M`
+fn validate_model(name: &str) -> Result<(), String> {
+    let valid = !name.is_empty()
+        && name.len() <= 200
+        && !name.starts_with(['-', '/', '.'])
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_.:/".contains(c));
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("Invalid model name: {}", name))
+    }
+}
+
+fn emit_progress(app: &AppHandle, progress: PullProgress) {
+    if let Err(e) = app.emit("local-model-pull-progress", progress) {
+        tracing::warn!("Failed to emit pull progress: {}", e);
+    }
+}
+
+async fn installed(client: &reqwest::Client) -> Result<Vec<LocalModel>, String> {
+    let tags: Tags = client
+        .get(format!("{}/api/tags", base_url()))
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| format!("Failed to reach Ollama: {}", e))?
+        .json()
+        .await
+        .map_err(|e| format!("Invalid Ollama response: {}", e))?;
+    let budget = tauri::async_runtime::spawn_blocking(hardware::gpu_memory_budget)
+        .await
+        .map_err(|e| e.to_string())?;
+    let mut models: Vec<LocalModel> = tags
+        .models
+        .into_iter()
+        .map(|tag| LocalModel {
+            fits_in_gpu: hardware::model_fits(tag.size, budget),
+            name: tag.name,
+            size_bytes: tag.size,
+            parameter_size: tag.details.parameter_size,
+            quantization: tag.details.quantization_level,
+            family: tag.details.family,
+        })
+        .collect();
+    models.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(models)
+}
+
+// Where Ollama keeps its blobs, which is where a pull needs the disk space
+fn models_dir(app: &AppHandle) -> Option<PathBuf> {
+    match std::env::var_os("OLLAMA_MODELS") {
+        Some(dir) if !dir.is_empty() => Some(PathBuf::from(dir)),
+        _ => app
+            .path()
+            .home_dir()
+            .ok()
+            .map(|home| home.join(".ollama").join("models")),
+    }
+}
+
+fn size_warning(name: &str, size: u64, budget: Option<u64>, free: Option<u64>) -> Option<String> {
+    if free.is_some_and(|free| free < size) {
+        Some(format!(
+            "{} is larger than the free disk space and may fail to download",
+            name
+        ))
+    } else if hardware::model_fits(size, budget) == Some(false) {
+        Some(format!(
+            "{} needs more memory than the GPU has and will run partly on the CPU",
+            name
+        ))
+    } else {
+        None
+    }
+}
+
+async fn pull(app: &AppHandle, name: &str) -> Result<(), String> {
+    let mut response = reqwest::Client::new()
+        .post(format!("{}/api/pull", base_url()))
+        .json(&json!({ "model": name, "stream": true }))
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| format!("Failed to reach Ollama: {}", e))?;
+    let models_dir = models_dir(app);
+    let (budget, free) = tauri::async_runtime::spawn_blocking(move || {
+        let free = models_dir
+            .as_deref()
+            .and_then(hardware::disk_for)
+            .map(|disk| disk.available_bytes);
+        (hardware::gpu_memory_budget(), free)
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+
+    // Ollama streams one JSON object per line
+    let mut buffer = Vec::new();
+    let mut warned = false;
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .map_err(|e| format!("Pull interrupted: {}", e))?
+    {
+        buffer.extend_from_slice(&chunk);
+        while let Some(end) = buffer.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = buffer.drain(..=end).collect();
+            let Ok(line) = serde_json::from_slice::<PullLine>(&line) else {
+                continue;
+            };
+            if let Some(error) = line.error {
+                return Err(format!("Ollama failed to pull {}: {}", name, error));
+            }
+            // The weights are by far the largest layer, so the first oversized one is them
+            let warning = match line.total {
+                Some(total) if !warned => size_warning(name, total, budget, free),
+                _ => None,
+            };
+            warned |= warning.is_some();
+            emit_progress(
+                app,
+                PullProgress {
+                    model: name,
+                    status: line.status,
+                    completed: line.completed,
+                    total: line.total,
+                    warning,
+                    done: false,
+                },
+            );
+        }
+    }
+    emit_progress(
+        app,
+        PullProgress {
+            model: name,
+            status: "success".to_string(),
+            completed: None,
+            total: None,
+            warning: None,
+            done: true,
+        },
+    );
+    Ok(())
+}
+
+// Whether Ollama is reachable, and which version it runs
+#[tauri::command]
+pub async fn detect_ollama() -> OllamaStatus {
+    let url = base_url();
+    let version = match reqwest::Client::builder().timeout(DETECT_TIMEOUT).build() {
+        Ok(client) => match client.get(format!("{}/api/version", url)).send().await {
+            Ok(response) => response.json::<Version>().await.ok().map(|v| v.version),
+            Err(_) => None,
+        },
+        Err(_) => None,
+    };
+    OllamaStatus {
+        running: version.is_some(),
+        url,
+        version,
+    }
+}
+
+#[tauri::command]
+pub async fn list_local_models() -> Result<Vec<LocalModel>, AppError> {
+    installed(&reqwest::Client::new())
+        .await
+        .map_err(AppError::Network)
+}
+
+// Download a model through Ollama, streaming `local-model-pull-progress`
+#[tauri::command]
+pub async fn pull_local_model(app: AppHandle, name: String) -> Result<(), AppError> {
+    validate_model(&name).map_err(AppError::Validation)?;
+    if !PULLING.lock().unwrap().insert(name.clone()) {
+        return Err(AppError::Validation(format!(
+            "{} is already being pulled",
+            name
+        )));
+    }
+    let result = pull(&app, &name).await;
+    PULLING.lock().unwrap().remove(&name);
+    if let Err(e) = &result {
+        tracing::error!("{}", e);
+    }
+    result.map_err(AppError::Network)
+}
+
+#[tauri::command]
+pub async fn delete_local_model(name: String) -> Result<(), AppError> {
+    validate_model(&name).map_err(AppError::Validation)?;
+    reqwest::Client::new()
+        .delete(format!("{}/api/delete", base_url()))
+        .json(&json!({ "model": name }))
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map(|_| ())
+        .map_err(|e| AppError::Network(format!("Failed to delete {}: {}", name, e)))
+}
+
+// Point the agent's Ollama plugin at `model`; takes effect the next time the server starts
+#[tauri::command]
+pub async fn use_local_model(
+    model: String,
+    embedding_model: Option<String>,
+) -> Result<LocalModel, AppError> {
+    validate_model(&model).map_err(AppError::Validation)?;
+    if let Some(embedding_model) = &embedding_model {
+        validate_model(embedding_model).map_err(AppError::Validation)?;
+    }
+    let client = reqwest::Client::new();
+    let models = installed(&client).await?;
+    let selected = models
+        .iter()
+        .find(|candidate| candidate.name == model)
+        .cloned()
+        .ok_or_else(|| format!("{} is not installed in Ollama", model))?;
+    if let Some(embedding_model) = &embedding_model {
+        if !models
+            .iter()
+            .any(|candidate| candidate.name == *embedding_model)
+        {
+            return Err(AppError::NotFound(format!(
+                "{} is not installed in Ollama",
+                embedding_model
+            )));
+        }
+    }
+    if selected.fits_in_gpu == Some(false) {
+        tracing::warn!(
+            "{} is larger than GPU memory and will run partly on the CPU",
+            model
+        );
+    }
+
+    let endpoint = format!("{}/api", base_url());
+    tauri::async_runtime::spawn_blocking(move || {
+        config::write_env_var("OLLAMA_API_ENDPOINT".to_string(), endpoint, None)?;
+        for key in [
+            "OLLAMA_SMALL_MODEL",
+            "OLLAMA_MEDIUM_MODEL",
+            "OLLAMA_LARGE_MODEL",
+        ] {
+            config::write_env_var(key.to_string(), model.clone(), None)?;
+        }
+        if let Some(embedding_model) = embedding_model {
+            config::write_env_var("OLLAMA_EMBEDDING_MODEL".to_string(), embedding_model, None)?;
+        }
+        Ok::<_, String>(())
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+    Ok(selected)
+}
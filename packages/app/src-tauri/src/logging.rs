@@ -0,0 +1,138 @@
+use std::fs;
+use std::path::PathBuf;
+
+use once_cell::sync::OnceCell;
+use serde_json::Value;
+use tauri::{AppHandle, Listener, Manager};
+use tracing::level_filters::LevelFilter;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{fmt, reload, Registry};
+
+use crate::error::AppError;
+use crate::redaction::Redacting;
+use crate::settings::{self, LogLevel, Settings};
+
+const LOG_FILE_PREFIX: &str = "eliza-desktop";
+const LOG_FILE_SUFFIX: &str = "log";
+// Daily files kept before the oldest is deleted
+const MAX_LOG_FILES: usize = 7;
+// Lines returned by `get_app_logs` when no limit is given
+const DEFAULT_LOG_LIMIT: usize = 500;
+
+static LEVEL_HANDLE: OnceCell<reload::Handle<LevelFilter, Registry>> = OnceCell::new();
+
+fn level_filter(level: LogLevel) -> LevelFilter {
+    match level {
+        LogLevel::Error => LevelFilter::ERROR,
+        LogLevel::Warn => LevelFilter::WARN,
+        LogLevel::Info => LevelFilter::INFO,
+        LogLevel::Debug => LevelFilter::DEBUG,
+        LogLevel::Trace => LevelFilter::TRACE,
+    }
+}
+
+fn log_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_log_dir()
+        .map_err(|e| format!("Failed to resolve app log dir: {}", e))
+}
+
+pub fn apply_level(level: LogLevel) {
+    if let Some(handle) = LEVEL_HANDLE.get() {
+        if let Err(e) = handle.reload(level_filter(level)) {
+            tracing::warn!("Failed to change log level: {}", e);
+        }
+    }
+}
+
+// Install the global subscriber: JSON lines to daily-rotated files in the app log dir,
+// plus human-readable output on the terminal. Called first thing in the setup hook,
+// before the settings are loaded, so it starts at the default level.
+pub fn init(app: &AppHandle) -> Result<(), String> {
+    let dir = log_dir(app)?;
+    let appender = RollingFileAppender::builder()
+        .rotation(Rotation::DAILY)
+        .filename_prefix(LOG_FILE_PREFIX)
+        .filename_suffix(LOG_FILE_SUFFIX)
+        .max_log_files(MAX_LOG_FILES)
+        .build(&dir)
+        .map_err(|e| format!("Failed to open log dir {}: {}", dir.display(), e))?;
+
+    let (level, handle) = reload::Layer::new(level_filter(settings::current().log_level));
+    tracing_subscriber::registry()
+        .with(level)
+        .with(
+            fmt::layer()
+                .json()
+                .with_current_span(true)
+                .with_span_list(false)
+                .with_writer(Redacting(appender)),
+        )
+        .with(
+            fmt::layer()
+                .with_target(false)
+                .with_writer(Redacting(std::io::stdout)),
+        )
+        .try_init()
+        .map_err(|e| format!("Failed to install logger: {}", e))?;
+    let _ = LEVEL_HANDLE.set(handle);
+
+    // Changes made through `set_setting` take effect without a restart
+    app.listen("settings-changed", |event| {
+        if let Ok(settings) = serde_json::from_str::<Settings>(event.payload()) {
+            apply_level(settings.log_level);
+        }
+    });
+
+    tracing::info!(dir = %dir.display(), "Logging to file");
+    Ok(())
+}
+
+#[tauri::command]
+pub fn set_log_level(app: AppHandle, level: LogLevel) -> Result<(), AppError> {
+    settings::update(&app, |settings| settings.log_level = level)?;
+    apply_level(level);
+    Ok(())
+}
+
+// The most recent entries across the rotated files, oldest first
+#[tauri::command]
+pub fn get_app_logs(app: AppHandle, limit: Option<usize>) -> Result<Vec<Value>, AppError> {
+    let limit = limit.unwrap_or(DEFAULT_LOG_LIMIT);
+    let dir = log_dir(&app)?;
+    let Ok(entries) = fs::read_dir(&dir) else {
+        return Ok(Vec::new());
+    };
+
+    // Rotated files are named `<prefix>.<date>.<suffix>`, so names sort chronologically
+    let mut files = entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with(LOG_FILE_PREFIX))
+        })
+        .collect::<Vec<_>>();
+    files.sort();
+
+    let mut lines = Vec::new();
+    for file in files.iter().rev() {
+        if lines.len() >= limit {
+            break;
+        }
+        let contents = fs::read_to_string(file)
+            .map_err(|e| format!("Failed to read {}: {}", file.display(), e))?;
+        let mut entries = contents
+            .lines()
+            .rev()
+            .filter_map(|line| serde_json::from_str::<Value>(line).ok())
+            .take(limit - lines.len())
+            .collect::<Vec<_>>();
+        lines.append(&mut entries);
+    }
+    lines.reverse();
+    Ok(lines)
+}
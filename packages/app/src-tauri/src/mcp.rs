@@ -0,0 +1,471 @@
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::io::{BufRead, BufReader, Read};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Url};
+
+use crate::capabilities::{self, Capability};
+use crate::cli::path::find_tool;
+use crate::error::AppError;
+use crate::history::now_millis;
+use crate::server::shutdown;
+use crate::{redaction, settings, workspace};
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(5);
+const HEALTH_TIMEOUT: Duration = Duration::from_secs(2);
+// Lines kept per server for `get_mcp_logs`
+const MAX_LOG_LINES: usize = 500;
+// A server that crashes this often within the window is left stopped
+const MAX_RESTARTS: usize = 5;
+const RESTART_WINDOW: Duration = Duration::from_secs(5 * 60);
+
+// A Model Context Protocol tool server the app runs for the agent
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct McpServerConfig {
+    pub name: String,
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+    // Where the agent reaches the server, for servers speaking HTTP or SSE; checked for health
+    #[serde(default)]
+    pub url: Option<String>,
+    // Start together with the app
+    #[serde(default)]
+    pub autostart: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum McpState {
+    Stopped,
+    Running,
+    // Exited without being asked to; restarted unless it keeps crashing
+    Crashed,
+}
+
+// Payload of the `mcp-status` event
+#[derive(Debug, Clone, Serialize)]
+pub struct McpServerStatus {
+    pub name: String,
+    pub state: McpState,
+    pub pid: Option<u32>,
+    // Whether `url` accepts connections; `None` without a url or while stopped
+    pub healthy: Option<bool>,
+    pub restarts: u32,
+    pub last_error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct McpServerInfo {
+    #[serde(flatten)]
+    pub config: McpServerConfig,
+    pub status: McpServerStatus,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum McpLogStream {
+    Stdout,
+    Stderr,
+}
+
+// Payload of the `mcp-log` event
+#[derive(Debug, Clone, Serialize)]
+pub struct McpLogLine {
+    pub server: String,
+    // Milliseconds since the Unix epoch
+    pub timestamp: u64,
+    pub stream: McpLogStream,
+    pub line: String,
+}
+
+struct Process {
+    child: Option<Child>,
+    status: McpServerStatus,
+    crashes: VecDeque<Instant>,
+}
+
+static PROCESSES: Lazy<Mutex<HashMap<String, Process>>> = Lazy::new(Default::default);
+static LOGS: Lazy<Mutex<HashMap<String, VecDeque<McpLogLine>>>> = Lazy::new(Default::default);
+
+fn stopped_status(name: &str) -> McpServerStatus {
+    McpServerStatus {
+        name: name.to_string(),
+        state: McpState::Stopped,
+        pid: None,
+        healthy: None,
+        restarts: 0,
+        last_error: None,
+    }
+}
+
+fn emit_status(app: &AppHandle, status: &McpServerStatus) {
+    if let Err(e) = app.emit("mcp-status", status) {
+        tracing::warn!("Failed to emit MCP server status: {}", e);
+    }
+}
+
+fn configs() -> Vec<McpServerConfig> {
+    settings::current().mcp_servers
+}
+
+fn config(name: &str) -> Result<McpServerConfig, AppError> {
+    configs()
+        .into_iter()
+        .find(|server| server.name == name)
+        .ok_or_else(|| AppError::NotFound(format!("Unknown MCP server: {}", name)))
+}
+
+fn validate(config: &McpServerConfig) -> Result<(), String> {
+    let name_ok = !config.name.is_empty()
+        && config.name.len() <= 64
+        && config
+            .name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !name_ok {
+        return Err(format!(
+            "Invalid MCP server name {}; use letters, digits, - and _",
+            config.name
+        ));
+    }
+    if config.command.trim().is_empty() {
+        return Err("An MCP server needs a command".to_string());
+    }
+    if let Some(url) = &config.url {
+        Url::parse(url).map_err(|e| format!("Invalid MCP server url {}: {}", url, e))?;
+    }
+    Ok(())
+}
+
+fn record(app: &AppHandle, server: &str, stream: McpLogStream, line: String) {
+    let entry = McpLogLine {
+        server: server.to_string(),
+        timestamp: now_millis(),
+        stream,
+        line,
+    };
+    {
+        let mut logs = LOGS.lock().unwrap();
+        let buffer = logs.entry(server.to_string()).or_default();
+        if buffer.len() == MAX_LOG_LINES {
+            buffer.pop_front();
+        }
+        buffer.push_back(entry.clone());
+    }
+    if let Err(e) = app.emit("mcp-log", &entry) {
+        tracing::warn!("Failed to emit MCP server log: {}", e);
+    }
+}
+
+fn spawn_reader(
+    app: AppHandle,
+    server: String,
+    stream: McpLogStream,
+    source: impl Read + Send + 'static,
+) {
+    thread::spawn(move || {
+        for line in BufReader::new(source).lines().map_while(Result::ok) {
+            let line = redaction::redact(line.trim_end_matches('\r')).into_owned();
+            tracing::debug!(target: "mcp", %server, "{}", line);
+            record(&app, &server, stream, line);
+        }
+    });
+}
+
+// Bare command names are looked up where Node.js, Bun and other tools are usually installed
+fn resolve_command(app: &AppHandle, command: &str) -> PathBuf {
+    if command.contains(['/', '\\']) {
+        return PathBuf::from(command);
+    }
+    find_tool(app, command).unwrap_or_else(|| PathBuf::from(command))
+}
+
+fn spawn_child(app: &AppHandle, config: &McpServerConfig) -> Result<Child, String> {
+    let program = resolve_command(app, &config.command);
+    let mut command = Command::new(&program);
+    command
+        .args(&config.args)
+        .envs(&config.env)
+        .env("PATH", crate::cli::path::spawn_path(app, &program))
+        // stdio servers are driven by the agent itself; keep stdin open so they don't exit
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    if let Ok(dir) = workspace::dir() {
+        command.current_dir(dir);
+    }
+    shutdown::prepare(&mut command);
+    let mut child = command
+        .spawn()
+        .map_err(|e| format!("Failed to run {}: {}", program.display(), e))?;
+    shutdown::contain(&child);
+    if let Some(stdout) = child.stdout.take() {
+        spawn_reader(
+            app.clone(),
+            config.name.clone(),
+            McpLogStream::Stdout,
+            stdout,
+        );
+    }
+    if let Some(stderr) = child.stderr.take() {
+        spawn_reader(
+            app.clone(),
+            config.name.clone(),
+            McpLogStream::Stderr,
+            stderr,
+        );
+    }
+    tracing::info!(server = %config.name, pid = child.id(), "Started MCP server");
+    Ok(child)
+}
+
+fn start(app: &AppHandle, config: &McpServerConfig) -> Result<McpServerStatus, String> {
+    let mut processes = PROCESSES.lock().unwrap();
+    let process = processes
+        .entry(config.name.clone())
+        .or_insert_with(|| Process {
+            child: None,
+            status: stopped_status(&config.name),
+            crashes: VecDeque::new(),
+        });
+    if process.child.is_some() {
+        return Ok(process.status.clone());
+    }
+    let result = spawn_child(app, config);
+    match result {
+        Ok(child) => {
+            process.status.state = McpState::Running;
+            process.status.pid = Some(child.id());
+            process.status.last_error = None;
+            process.child = Some(child);
+        }
+        Err(ref e) => {
+            process.status.state = McpState::Stopped;
+            process.status.last_error = Some(e.clone());
+        }
+    }
+    let status = process.status.clone();
+    drop(processes);
+    emit_status(app, &status);
+    match status.state {
+        McpState::Stopped => Err(status.last_error.unwrap_or_default()),
+        _ => Ok(status),
+    }
+}
+
+fn stop(app: &AppHandle, name: &str) -> McpServerStatus {
+    let mut child = None;
+    let status = {
+        let mut processes = PROCESSES.lock().unwrap();
+        match processes.get_mut(name) {
+            Some(process) => {
+                child = process.child.take();
+                process.status.state = McpState::Stopped;
+                process.status.pid = None;
+                process.status.healthy = None;
+                process.status.clone()
+            }
+            None => stopped_status(name),
+        }
+    };
+    if let Some(mut child) = child {
+        tracing::info!(server = name, "Stopping MCP server");
+        shutdown::kill_quietly(&mut child);
+    }
+    emit_status(app, &status);
+    status
+}
+
+fn is_reachable(url: &str) -> bool {
+    let Ok(url) = Url::parse(url) else {
+        return false;
+    };
+    let (Some(host), Some(port)) = (url.host_str(), url.port_or_known_default()) else {
+        return false;
+    };
+    (host, port)
+        .to_socket_addrs()
+        .ok()
+        .and_then(|mut addresses| addresses.next())
+        .is_some_and(|address| TcpStream::connect_timeout(&address, HEALTH_TIMEOUT).is_ok())
+}
+
+// Restart crashed servers and refresh health; runs every `CHECK_INTERVAL`
+fn check(app: &AppHandle) {
+    let configs = configs();
+    let mut changed = Vec::new();
+    let mut restart = Vec::new();
+    {
+        let mut processes = PROCESSES.lock().unwrap();
+        for (name, process) in processes.iter_mut() {
+            let Some(child) = process.child.as_mut() else {
+                continue;
+            };
+            let config = configs.iter().find(|config| &config.name == name);
+            match child.try_wait() {
+                Ok(Some(exit)) => {
+                    tracing::warn!(server = %name, "MCP server exited with {}", exit);
+                    process.child = None;
+                    process.status.state = McpState::Crashed;
+                    process.status.pid = None;
+                    process.status.healthy = None;
+                    process.status.last_error = Some(format!("Exited with {}", exit));
+                    let now = Instant::now();
+                    process.crashes.push_back(now);
+                    while process
+                        .crashes
+                        .front()
+                        .is_some_and(|crash| now.duration_since(*crash) > RESTART_WINDOW)
+                    {
+                        process.crashes.pop_front();
+                    }
+                    match config {
+                        Some(config) if process.crashes.len() <= MAX_RESTARTS => {
+                            process.status.restarts += 1;
+                            restart.push(config.clone());
+                        }
+                        _ => {
+                            process.status.last_error =
+                                Some("Crashed too often; start it again manually".to_string());
+                        }
+                    }
+                    changed.push(process.status.clone());
+                }
+                Ok(None) => {
+                    let healthy = config
+                        .and_then(|config| config.url.as_deref())
+                        .map(is_reachable);
+                    if healthy != process.status.healthy {
+                        process.status.healthy = healthy;
+                        changed.push(process.status.clone());
+                    }
+                }
+                Err(e) => tracing::warn!(server = %name, "Failed to check MCP server: {}", e),
+            }
+        }
+    }
+    for status in &changed {
+        emit_status(app, status);
+    }
+    for config in restart {
+        if let Err(e) = start(app, &config) {
+            tracing::error!(server = %config.name, "Failed to restart MCP server: {}", e);
+        }
+    }
+}
+
+// Start the autostart servers and supervise them; called once from the setup hook
+pub fn spawn(app: AppHandle) {
+    thread::spawn(move || {
+        for config in configs().into_iter().filter(|config| config.autostart) {
+            if let Err(e) = start(&app, &config) {
+                tracing::error!(server = %config.name, "Failed to start MCP server: {}", e);
+            }
+        }
+        loop {
+            thread::sleep(CHECK_INTERVAL);
+            check(&app);
+        }
+    });
+}
+
+// Stop every MCP server; called when the app exits
+pub fn shutdown_all(app: &AppHandle) {
+    let names: Vec<String> = PROCESSES.lock().unwrap().keys().cloned().collect();
+    for name in names {
+        stop(app, &name);
+    }
+}
+
+fn status(name: &str) -> McpServerStatus {
+    PROCESSES
+        .lock()
+        .unwrap()
+        .get(name)
+        .map(|process| process.status.clone())
+        .unwrap_or_else(|| stopped_status(name))
+}
+
+#[tauri::command]
+pub fn list_mcp_servers() -> Vec<McpServerInfo> {
+    configs()
+        .into_iter()
+        .map(|config| McpServerInfo {
+            status: status(&config.name),
+            config,
+        })
+        .collect()
+}
+
+// Add a server, or replace the configuration of the one with the same name. A running server
+// keeps its old configuration until it is restarted.
+#[tauri::command]
+pub fn add_mcp_server(app: AppHandle, server: McpServerConfig) -> Result<McpServerInfo, AppError> {
+    capabilities::require(Capability::RunPrograms)?;
+    validate(&server).map_err(AppError::Validation)?;
+    let saved = server.clone();
+    settings::update(&app, |settings| {
+        let servers = &mut settings.mcp_servers;
+        match servers
+            .iter_mut()
+            .find(|existing| existing.name == saved.name)
+        {
+            Some(existing) => *existing = saved,
+            None => servers.push(saved),
+        }
+    })?;
+    Ok(McpServerInfo {
+        status: status(&server.name),
+        config: server,
+    })
+}
+
+#[tauri::command]
+pub async fn remove_mcp_server(app: AppHandle, name: String) -> Result<(), AppError> {
+    config(&name)?;
+    let handle = app.clone();
+    let stopped = name.clone();
+    tauri::async_runtime::spawn_blocking(move || stop(&handle, &stopped)).await?;
+    PROCESSES.lock().unwrap().remove(&name);
+    LOGS.lock().unwrap().remove(&name);
+    settings::update(&app, |settings| {
+        settings.mcp_servers.retain(|server| server.name != name)
+    })?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn start_mcp_server(app: AppHandle, name: String) -> Result<McpServerStatus, AppError> {
+    capabilities::require(Capability::RunPrograms)?;
+    let config = config(&name)?;
+    // Starting by hand also clears the crash history that stopped automatic restarts
+    if let Some(process) = PROCESSES.lock().unwrap().get_mut(&name) {
+        process.crashes.clear();
+    }
+    tauri::async_runtime::spawn_blocking(move || start(&app, &config).map_err(AppError::Io)).await?
+}
+
+#[tauri::command]
+pub async fn stop_mcp_server(app: AppHandle, name: String) -> Result<McpServerStatus, AppError> {
+    config(&name)?;
+    Ok(tauri::async_runtime::spawn_blocking(move || stop(&app, &name)).await?)
+}
+
+#[tauri::command]
+pub fn get_mcp_logs(name: String) -> Vec<McpLogLine> {
+    LOGS.lock()
+        .unwrap()
+        .get(&name)
+        .map(|lines| lines.iter().cloned().collect())
+        .unwrap_or_default()
+}
@@ -0,0 +1,346 @@
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Component, Path, PathBuf};
+
+use tauri::http::{header, Method, Request, Response, StatusCode};
+use tauri::{UriSchemeContext, UriSchemeResponder, Wry};
+
+use crate::error::AppError;
+use crate::workspace;
+
+// Registered in `run`; the webview loads `eliza-media://localhost/<path>` (or
+// `http://eliza-media.localhost/<path>` on Windows), where the path is relative to the
+// server's data directory, e.g. `generated/<agent-id>/speech.mp3`
+pub const SCHEME: &str = "eliza-media";
+// Where the elizaOS server keeps uploads and generated files, inside the workspace
+const DATA_DIR: &str = ".eliza/data";
+// Open-ended ranges (`bytes=0-`, which media elements send first) are answered in chunks
+// of this size; the webview asks for the rest as playback goes on
+const MAX_CHUNK_BYTES: u64 = 4 * 1024 * 1024;
+// Requests without a range get the whole file only up to this size
+const MAX_WHOLE_BYTES: u64 = 64 * 1024 * 1024;
+
+// Only media is served, so nothing else in the data directory (like the database) is exposed
+fn mime_type(path: &Path) -> Option<&'static str> {
+    let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+    Some(match extension.as_str() {
+        "mp3" => "audio/mpeg",
+        "wav" => "audio/wav",
+        "ogg" | "oga" => "audio/ogg",
+        "opus" => "audio/opus",
+        "m4a" => "audio/mp4",
+        "aac" => "audio/aac",
+        "flac" => "audio/flac",
+        "mp4" | "m4v" => "video/mp4",
+        "webm" => "video/webm",
+        "mov" => "video/quicktime",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        _ => return None,
+    })
+}
+
+fn root() -> Result<PathBuf, String> {
+    Ok(workspace::dir()?.join(DATA_DIR))
+}
+
+fn percent_decode(segment: &str) -> Option<String> {
+    let bytes = segment.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = segment.get(i + 1..i + 3)?;
+            out.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            out.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(out).ok()
+}
+
+// The file a request path names, refusing anything that would leave the data directory,
+// including through symlinks
+fn resolve(root: &Path, path: &str) -> Option<PathBuf> {
+    let mut relative = PathBuf::new();
+    for segment in path.split('/').filter(|segment| !segment.is_empty()) {
+        let segment = percent_decode(segment)?;
+        let mut components = Path::new(&segment).components();
+        match (components.next(), components.next()) {
+            (Some(Component::Normal(name)), None) => relative.push(name),
+            _ => return None,
+        }
+    }
+    let root = root.canonicalize().ok()?;
+    let file = root.join(relative).canonicalize().ok()?;
+    (file.starts_with(&root) && file.is_file()).then_some(file)
+}
+
+// The byte span a `Range` header asks for, clamped to the file; `Err` when it can't be met
+fn parse_range(range: &str, len: u64) -> Result<(u64, u64), ()> {
+    // No byte range of an empty file can be met
+    if len == 0 {
+        return Err(());
+    }
+    let spec = range.trim().strip_prefix("bytes=").ok_or(())?;
+    // Only single ranges; media elements never ask for more
+    if spec.contains(',') {
+        return Err(());
+    }
+    let (start, end) = spec.split_once('-').ok_or(())?;
+    let (start, end) = match (start.trim(), end.trim()) {
+        ("", "") => return Err(()),
+        // The last `suffix` bytes
+        ("", suffix) => {
+            let suffix: u64 = suffix.parse().map_err(|_| ())?;
+            if suffix == 0 {
+                return Err(());
+            }
+            (len.saturating_sub(suffix), len.saturating_sub(1))
+        }
+        (start, "") => {
+            let start: u64 = start.parse().map_err(|_| ())?;
+            (
+                start,
+                len.saturating_sub(1)
+                    .min(start.saturating_add(MAX_CHUNK_BYTES - 1)),
+            )
+        }
+        (start, end) => {
+            let start: u64 = start.parse().map_err(|_| ())?;
+            let end: u64 = end.parse().map_err(|_| ())?;
+            (start, end.min(len.saturating_sub(1)))
+        }
+    };
+    if start >= len || end < start {
+        return Err(());
+    }
+    // A shorter answer than asked for is allowed; the client asks again for the rest
+    Ok((start, end.min(start.saturating_add(MAX_WHOLE_BYTES - 1))))
+}
+
+fn error(status: StatusCode, message: &str) -> Response<Vec<u8>> {
+    Response::builder()
+        .status(status)
+        .header(header::CONTENT_TYPE, "text/plain")
+        .body(message.as_bytes().to_vec())
+        .unwrap()
+}
+
+fn read_span(path: &Path, start: u64, len: u64) -> std::io::Result<Vec<u8>> {
+    let mut file = File::open(path)?;
+    file.seek(SeekFrom::Start(start))?;
+    let mut body = Vec::with_capacity(len as usize);
+    file.take(len).read_to_end(&mut body)?;
+    Ok(body)
+}
+
+fn serve(request: &Request<Vec<u8>>) -> Response<Vec<u8>> {
+    if request.method() != Method::GET && request.method() != Method::HEAD {
+        return error(
+            StatusCode::METHOD_NOT_ALLOWED,
+            "Only GET and HEAD are supported",
+        );
+    }
+    let root = match root() {
+        Ok(root) => root,
+        Err(e) => return error(StatusCode::INTERNAL_SERVER_ERROR, &e),
+    };
+    let Some(path) = resolve(&root, request.uri().path()) else {
+        return error(StatusCode::NOT_FOUND, "Not found");
+    };
+    let Some(mime_type) = mime_type(&path) else {
+        return error(StatusCode::FORBIDDEN, "Not a media file");
+    };
+    let len = match path.metadata() {
+        Ok(metadata) => metadata.len(),
+        Err(e) => return error(StatusCode::INTERNAL_SERVER_ERROR, &e.to_string()),
+    };
+
+    let range = request
+        .headers()
+        .get(header::RANGE)
+        .and_then(|range| range.to_str().ok());
+    let response = Response::builder()
+        .header(header::CONTENT_TYPE, mime_type)
+        .header(header::ACCEPT_RANGES, "bytes")
+        .header(header::CACHE_CONTROL, "no-cache")
+        .header(header::X_CONTENT_TYPE_OPTIONS, "nosniff");
+    let (response, start, span) = match range {
+        Some(range) => match parse_range(range, len) {
+            Ok((start, end)) => (
+                response.status(StatusCode::PARTIAL_CONTENT).header(
+                    header::CONTENT_RANGE,
+                    format!("bytes {}-{}/{}", start, end, len),
+                ),
+                start,
+                end - start + 1,
+            ),
+            Err(()) => {
+                return Response::builder()
+                    .status(StatusCode::RANGE_NOT_SATISFIABLE)
+                    .header(header::CONTENT_RANGE, format!("bytes */{}", len))
+                    .body(Vec::new())
+                    .unwrap();
+            }
+        },
+        None if len > MAX_WHOLE_BYTES => {
+            return error(
+                StatusCode::PAYLOAD_TOO_LARGE,
+                "This file is too large to load at once; request a range",
+            );
+        }
+        None => (response.status(StatusCode::OK), 0, len),
+    };
+    let response = response.header(header::CONTENT_LENGTH, span);
+    if request.method() == Method::HEAD {
+        return response.body(Vec::new()).unwrap();
+    }
+    match read_span(&path, start, span) {
+        Ok(body) => response.body(body).unwrap(),
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, &e.to_string()),
+    }
+}
+
+// Handler for the `eliza-media` protocol; files are read off the main thread
+pub fn protocol(
+    _ctx: UriSchemeContext<'_, Wry>,
+    request: Request<Vec<u8>>,
+    responder: UriSchemeResponder,
+) {
+    tauri::async_runtime::spawn_blocking(move || {
+        responder.respond(serve(&request));
+    });
+}
+
+// The `eliza-media` URL for a file the server wrote, given its absolute path
+#[tauri::command]
+pub fn get_media_url(path: PathBuf) -> Result<String, AppError> {
+    let root = root()?
+        .canonicalize()
+        .map_err(|e| AppError::NotFound(format!("The server has no data directory yet: {}", e)))?;
+    let file = path
+        .canonicalize()
+        .map_err(|e| AppError::NotFound(format!("{}: {}", path.display(), e)))?;
+    let relative = file.strip_prefix(&root).map_err(|_| {
+        AppError::Validation(format!(
+            "{} is outside the server's data directory",
+            path.display()
+        ))
+    })?;
+    if mime_type(&file).is_none() {
+        return Err(AppError::Validation(format!(
+            "{} is not a media file",
+            path.display()
+        )));
+    }
+    let base = if cfg!(windows) {
+        format!("http://{}.localhost/", SCHEME)
+    } else {
+        format!("{}://localhost/", SCHEME)
+    };
+    let mut url = tauri::Url::parse(&base).map_err(|e| AppError::Internal(e.to_string()))?;
+    url.path_segments_mut()
+        .map_err(|_| AppError::Internal("Invalid media URL".to_string()))?
+        .pop_if_empty()
+        .extend(
+            relative
+                .components()
+                .filter_map(|component| component.as_os_str().to_str()),
+        );
+    Ok(url.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_suffix_ranges() {
+        assert_eq!(parse_range("bytes=-500", 1000), Ok((500, 999)));
+        assert_eq!(parse_range("bytes=-5000", 1000), Ok((0, 999)));
+        assert_eq!(parse_range("bytes=-0", 1000), Err(()));
+    }
+
+    #[test]
+    fn open_ended_ranges_are_chunked() {
+        assert_eq!(parse_range("bytes=0-", 1000), Ok((0, 999)));
+        assert_eq!(parse_range("bytes=100-", 1000), Ok((100, 999)));
+        let big = MAX_CHUNK_BYTES * 3;
+        assert_eq!(parse_range("bytes=0-", big), Ok((0, MAX_CHUNK_BYTES - 1)));
+    }
+
+    #[test]
+    fn explicit_ranges_are_clamped_to_the_file() {
+        assert_eq!(parse_range("bytes=10-19", 1000), Ok((10, 19)));
+        assert_eq!(parse_range(" bytes=990-2000 ", 1000), Ok((990, 999)));
+        let big = MAX_WHOLE_BYTES * 2;
+        let range = format!("bytes=0-{}", big - 1);
+        assert_eq!(parse_range(&range, big), Ok((0, MAX_WHOLE_BYTES - 1)));
+    }
+
+    #[test]
+    fn rejects_unsatisfiable_ranges() {
+        assert_eq!(parse_range("bytes=1000-", 1000), Err(()));
+        assert_eq!(parse_range("bytes=20-10", 1000), Err(()));
+        assert_eq!(parse_range("bytes=-", 1000), Err(()));
+        assert_eq!(parse_range("bytes=a-b", 1000), Err(()));
+        assert_eq!(parse_range("items=0-10", 1000), Err(()));
+    }
+
+    #[test]
+    fn rejects_every_range_of_an_empty_file() {
+        for range in ["bytes=-500", "bytes=0-", "bytes=0-0"] {
+            assert_eq!(parse_range(range, 0), Err(()), "{}", range);
+        }
+    }
+
+    #[test]
+    fn rejects_multiple_ranges() {
+        assert_eq!(parse_range("bytes=0-10,20-30", 1000), Err(()));
+    }
+
+    #[test]
+    fn resolves_files_inside_the_root_only() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("data");
+        std::fs::create_dir_all(root.join("generated")).unwrap();
+        std::fs::write(root.join("generated/speech.mp3"), b"id3").unwrap();
+        std::fs::write(dir.path().join("secret.mp3"), b"id3").unwrap();
+
+        let file = resolve(&root, "/generated/speech.mp3").unwrap();
+        assert!(file.ends_with("generated/speech.mp3"));
+        assert_eq!(resolve(&root, "/generated/sp%65ech.mp3"), Some(file));
+        assert_eq!(resolve(&root, "/generated"), None);
+        assert_eq!(resolve(&root, "/missing.mp3"), None);
+        assert_eq!(resolve(&root, "/../secret.mp3"), None);
+        assert_eq!(resolve(&root, "/generated/%2e%2e/../secret.mp3"), None);
+        assert_eq!(resolve(&root, "/generated%2f..%2f..%2fsecret.mp3"), None);
+        assert_eq!(resolve(&root, "/bad%zz.mp3"), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn refuses_symlinks_out_of_the_root() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("data");
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(dir.path().join("secret.mp3"), b"id3").unwrap();
+        std::os::unix::fs::symlink(dir.path().join("secret.mp3"), root.join("link.mp3")).unwrap();
+        std::os::unix::fs::symlink(dir.path(), root.join("escape")).unwrap();
+
+        assert_eq!(resolve(&root, "/link.mp3"), None);
+        assert_eq!(resolve(&root, "/escape/secret.mp3"), None);
+    }
+
+    #[test]
+    fn serves_media_types_only() {
+        assert_eq!(mime_type(Path::new("a/b.MP3")), Some("audio/mpeg"));
+        assert_eq!(mime_type(Path::new("db.sqlite")), None);
+        assert_eq!(mime_type(Path::new("noextension")), None);
+    }
+}
@@ -0,0 +1,202 @@
+use serde::Serialize;
+use tauri::menu::{Menu, MenuEvent, MenuItem, PredefinedMenuItem, Submenu};
+use tauri::{AppHandle, Emitter, Manager, Wry};
+use tauri_plugin_opener::OpenerExt;
+
+use crate::i18n::t;
+use crate::windows::{self, WindowKind};
+use crate::{quick_chat, server, tray};
+
+// Tray and app menu events reach every handler, so app menu ids carry this prefix
+const ID_PREFIX: &str = "app-menu:";
+
+// Payload of the `menu-action` event, emitted for every item of the app menu
+#[derive(Debug, Clone, Serialize)]
+struct MenuAction<'a> {
+    id: &'a str,
+}
+
+fn item(
+    app: &AppHandle,
+    id: &str,
+    label: &str,
+    accelerator: Option<&str>,
+) -> tauri::Result<MenuItem<Wry>> {
+    MenuItem::with_id(
+        app,
+        format!("{}{}", ID_PREFIX, id),
+        t(label),
+        true,
+        accelerator,
+    )
+}
+
+fn open_logs_folder(app: &AppHandle) -> Result<(), String> {
+    let dir = app
+        .path()
+        .app_log_dir()
+        .map_err(|e| format!("Failed to resolve app log dir: {}", e))?;
+    app.opener()
+        .open_path(dir.to_string_lossy(), None::<&str>)
+        .map_err(|e| format!("Failed to open {}: {}", dir.display(), e))
+}
+
+fn on_menu_event(app: &AppHandle, event: MenuEvent) {
+    let Some(id) = event.id.as_ref().strip_prefix(ID_PREFIX) else {
+        return;
+    };
+    let result = match id {
+        "restart-server" => {
+            tray::run_lifecycle(app, server::restart);
+            Ok(())
+        }
+        "open-logs-folder" => open_logs_folder(app),
+        "quick-chat" => quick_chat::toggle(app),
+        "settings" => windows::open_window(app.clone(), WindowKind::Settings).map_err(String::from),
+        "edit-character" => {
+            windows::open_window(app.clone(), WindowKind::CharacterEditor).map_err(String::from)
+        }
+        "server-logs" => {
+            tray::show_main_window(app);
+            app.emit("open-server-logs", ()).map_err(|e| e.to_string())
+        }
+        "quit" => {
+            crate::exit_app(app);
+            Ok(())
+        }
+        // The rest is handled by the frontend
+        _ => {
+            tray::show_main_window(app);
+            Ok(())
+        }
+    };
+    if let Err(e) = result {
+        tracing::warn!("{}", e);
+    }
+    let _ = app.emit("menu-action", MenuAction { id });
+}
+
+fn build(app: &AppHandle) -> tauri::Result<Menu<Wry>> {
+    let separator = || PredefinedMenuItem::separator(app);
+    let settings = item(app, "settings", "menu-settings", Some("CmdOrCtrl+,"))?;
+    let quit = item(app, "quit", "quit", Some("CmdOrCtrl+Q"))?;
+
+    let file = Submenu::with_items(
+        app,
+        t("menu-file"),
+        true,
+        &[
+            &item(
+                app,
+                "new-conversation",
+                "menu-new-conversation",
+                Some("CmdOrCtrl+N"),
+            )?,
+            &separator()?,
+            &item(app, "open-logs-folder", "menu-open-logs-folder", None)?,
+        ],
+    )?;
+    // Settings and Quit live in the app menu on macOS
+    #[cfg(not(target_os = "macos"))]
+    file.append_items(&[&separator()?, &settings])?;
+    file.append_items(&[&separator()?, &PredefinedMenuItem::close_window(app, None)?])?;
+    #[cfg(not(target_os = "macos"))]
+    file.append(&quit)?;
+
+    let edit = Submenu::with_items(
+        app,
+        t("menu-edit"),
+        true,
+        &[
+            &PredefinedMenuItem::undo(app, None)?,
+            &PredefinedMenuItem::redo(app, None)?,
+            &separator()?,
+            &PredefinedMenuItem::cut(app, None)?,
+            &PredefinedMenuItem::copy(app, None)?,
+            &PredefinedMenuItem::paste(app, None)?,
+            &PredefinedMenuItem::select_all(app, None)?,
+        ],
+    )?;
+
+    let agent = Submenu::with_items(
+        app,
+        t("menu-agent"),
+        true,
+        &[
+            &item(app, "quick-chat", "menu-quick-chat", None)?,
+            &item(app, "edit-character", "menu-edit-character", None)?,
+            &separator()?,
+            &item(
+                app,
+                "restart-server",
+                "restart-server",
+                Some("CmdOrCtrl+Shift+R"),
+            )?,
+            &item(
+                app,
+                "server-logs",
+                "menu-server-logs",
+                Some("CmdOrCtrl+Shift+L"),
+            )?,
+        ],
+    )?;
+
+    let help = Submenu::with_items(
+        app,
+        t("menu-help"),
+        true,
+        &[&item(
+            app,
+            "check-for-updates",
+            "menu-check-for-updates",
+            None,
+        )?],
+    )?;
+    #[cfg(not(target_os = "macos"))]
+    help.append_items(&[&separator()?, &PredefinedMenuItem::about(app, None, None)?])?;
+
+    #[cfg(target_os = "macos")]
+    {
+        let name = app.package_info().name.clone();
+        let app_menu = Submenu::with_items(
+            app,
+            &name,
+            true,
+            &[
+                &PredefinedMenuItem::about(app, None, None)?,
+                &separator()?,
+                &settings,
+                &separator()?,
+                &PredefinedMenuItem::services(app, None)?,
+                &separator()?,
+                &PredefinedMenuItem::hide(app, None)?,
+                &PredefinedMenuItem::hide_others(app, None)?,
+                &PredefinedMenuItem::show_all(app, None)?,
+                &separator()?,
+                &quit,
+            ],
+        )?;
+        Menu::with_items(app, &[&app_menu, &file, &edit, &agent, &help])
+    }
+    #[cfg(not(target_os = "macos"))]
+    Menu::with_items(app, &[&file, &edit, &agent, &help])
+}
+
+// Install the application menu, replacing the current one. On Windows and Linux menus belong
+// to a window, so it's only attached to the main one rather than to popups like quick chat.
+pub fn install(app: &AppHandle) -> tauri::Result<()> {
+    let menu = build(app)?;
+    #[cfg(target_os = "macos")]
+    app.set_menu(menu)?;
+    #[cfg(not(target_os = "macos"))]
+    if let Some(window) = app.get_webview_window("main") {
+        window.set_menu(menu)?;
+    }
+    Ok(())
+}
+
+pub fn init(app: &AppHandle) -> tauri::Result<()> {
+    install(app)?;
+    app.on_menu_event(on_menu_event);
+    Ok(())
+}
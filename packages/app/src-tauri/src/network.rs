@@ -0,0 +1,397 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Url};
+
+use crate::auth::secure_store;
+use crate::error::AppError;
+use crate::{redaction, settings};
+
+const PASSWORD_ENTRY: &str = "network-proxy-password";
+const TEST_URL: &str = "https://api.openai.com/v1/models";
+const TEST_TIMEOUT: Duration = Duration::from_secs(10);
+const PAC_TIMEOUT: Duration = Duration::from_secs(10);
+const MAX_PAC_BYTES: usize = 1024 * 1024;
+// Never proxied, so the app can always reach its own server and proxy
+const LOOPBACK: &str = "localhost,127.0.0.1,::1";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProxyMode {
+    // Connect directly, ignoring proxy variables and OS settings
+    Direct,
+    // Proxy variables from the environment and, on macOS and Windows, the OS settings
+    System,
+    // The proxy at `url`
+    Manual,
+    // The proxy named by the auto-configuration script at `pac_url`
+    Pac,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NetworkProxySettings {
+    pub mode: ProxyMode,
+    // `http://`, `https://` or `socks5://` with a host and port
+    pub url: Option<String>,
+    pub pac_url: Option<String>,
+    // The password is kept in the keychain
+    pub username: Option<String>,
+    // Hosts reached directly, e.g. `*.corp.example.com` or `10.0.0.0/8`
+    pub no_proxy: Vec<String>,
+}
+
+impl Default for NetworkProxySettings {
+    fn default() -> Self {
+        Self {
+            mode: ProxyMode::System,
+            url: None,
+            pac_url: None,
+            username: None,
+            no_proxy: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct NetworkProxyStatus {
+    #[serde(flatten)]
+    pub settings: NetworkProxySettings,
+    pub has_password: bool,
+    // The proxy in use, without credentials; `None` when connecting directly or following
+    // the system settings
+    pub effective_url: Option<String>,
+    // Whether this app's own requests use the proxy too; SOCKS proxies only reach the server
+    pub clients_proxied: bool,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProxyTest {
+    pub reachable: bool,
+    pub status: Option<u16>,
+    pub elapsed_ms: u64,
+    pub effective_url: Option<String>,
+    pub error: Option<String>,
+}
+
+// What the settings resolved to, with the password read from the keychain
+#[derive(Debug, Clone)]
+enum Route {
+    Direct,
+    System,
+    Via {
+        url: Url,
+        password: Option<String>,
+        no_proxy: String,
+    },
+}
+
+static ROUTE: Mutex<Option<Route>> = Mutex::new(None);
+static LAST_ERROR: Mutex<Option<String>> = Mutex::new(None);
+// Rebuilt whenever the route changes, so connections are pooled in between
+static CLIENT: Mutex<Option<reqwest::Client>> = Mutex::new(None);
+
+fn is_socks(url: &Url) -> bool {
+    url.scheme().starts_with("socks")
+}
+
+fn parse_proxy_url(url: &str) -> Result<Url, String> {
+    let url = Url::parse(url.trim()).map_err(|e| format!("Invalid proxy URL {}: {}", url, e))?;
+    if !matches!(url.scheme(), "http" | "https" | "socks5" | "socks5h") {
+        return Err(format!(
+            "Unsupported proxy scheme {}; use http, https or socks5",
+            url.scheme()
+        ));
+    }
+    if url.host_str().is_none() {
+        return Err(format!("The proxy URL {} has no host", url));
+    }
+    Ok(url)
+}
+
+// The first proxy a PAC script names. The script isn't run, so proxies it only picks for some
+// hosts are used for all of them; `DIRECT` means no proxy.
+fn pac_proxy(script: &str) -> Option<Url> {
+    let words: Vec<&str> = script
+        .split(|c: char| c.is_whitespace() || matches!(c, '"' | '\'' | ';'))
+        .filter(|word| !word.is_empty())
+        .collect();
+    words.windows(2).find_map(|pair| {
+        let scheme = match pair[0] {
+            "PROXY" | "HTTP" => "http",
+            "HTTPS" => "https",
+            "SOCKS" | "SOCKS5" => "socks5",
+            _ => return None,
+        };
+        Url::parse(&format!("{}://{}", scheme, pair[1])).ok()
+    })
+}
+
+async fn fetch_pac(url: &str) -> Result<Option<Url>, String> {
+    let client = reqwest::Client::builder()
+        .no_proxy()
+        .timeout(PAC_TIMEOUT)
+        .build()
+        .map_err(|e| e.to_string())?;
+    let script = client
+        .get(url)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| format!("Failed to download the proxy script {}: {}", url, e))?
+        .text()
+        .await
+        .map_err(|e| format!("Failed to read the proxy script {}: {}", url, e))?;
+    if script.len() > MAX_PAC_BYTES {
+        return Err(format!("The proxy script {} is too large", url));
+    }
+    Ok(pac_proxy(&script))
+}
+
+async fn resolve(config: &NetworkProxySettings) -> Result<Route, String> {
+    let url = match config.mode {
+        ProxyMode::Direct => return Ok(Route::Direct),
+        ProxyMode::System => return Ok(Route::System),
+        ProxyMode::Manual => {
+            let url = config.url.as_deref().ok_or("A manual proxy needs a URL")?;
+            parse_proxy_url(url)?
+        }
+        ProxyMode::Pac => {
+            let pac_url = config
+                .pac_url
+                .as_deref()
+                .ok_or("Proxy auto-configuration needs a script URL")?;
+            match fetch_pac(pac_url).await? {
+                Some(url) => url,
+                None => return Ok(Route::Direct),
+            }
+        }
+    };
+    let password = match config.username {
+        Some(_) => secure_store::read(PASSWORD_ENTRY).await?,
+        None => None,
+    };
+    if let Some(password) = &password {
+        redaction::add_secrets([password.clone()]);
+    }
+    let no_proxy = std::iter::once(LOOPBACK.to_string())
+        .chain(config.no_proxy.iter().cloned())
+        .collect::<Vec<_>>()
+        .join(",");
+    Ok(Route::Via {
+        url,
+        password,
+        no_proxy,
+    })
+}
+
+fn install(route: Route, error: Option<String>) {
+    *ROUTE.lock().unwrap() = Some(route);
+    *LAST_ERROR.lock().unwrap() = error;
+    *CLIENT.lock().unwrap() = None;
+}
+
+// Resolve the settings and use the result for every request from now on. A proxy that can't
+// be resolved falls back to the system settings.
+async fn apply() -> Result<NetworkProxyStatus, AppError> {
+    let config = settings::current().network_proxy;
+    match resolve(&config).await {
+        Ok(route) => install(route, None),
+        Err(e) => {
+            tracing::error!("{}", e);
+            install(Route::System, Some(e));
+        }
+    }
+    // Checking for a stored password queries the keychain
+    Ok(tauri::async_runtime::spawn_blocking(status).await?)
+}
+
+fn route() -> Route {
+    ROUTE.lock().unwrap().clone().unwrap_or(Route::System)
+}
+
+// A client builder that goes through the configured proxy. For requests to the internet;
+// loopback addresses are always reached directly.
+pub fn builder() -> reqwest::ClientBuilder {
+    let builder = reqwest::Client::builder();
+    match route() {
+        Route::Direct => builder.no_proxy(),
+        Route::System => builder,
+        // This build's HTTP client has no SOCKS support, so only the server uses those
+        Route::Via { url, .. } if is_socks(&url) => builder,
+        Route::Via {
+            url,
+            password,
+            no_proxy,
+        } => {
+            let username = settings::current().network_proxy.username;
+            match reqwest::Proxy::all(url.as_str()) {
+                Ok(mut proxy) => {
+                    if let Some(username) = &username {
+                        proxy = proxy.basic_auth(username, password.as_deref().unwrap_or(""));
+                    }
+                    builder.proxy(proxy.no_proxy(reqwest::NoProxy::from_string(&no_proxy)))
+                }
+                Err(e) => {
+                    tracing::error!("Failed to use the proxy {}: {}", url, e);
+                    builder
+                }
+            }
+        }
+    }
+}
+
+// A shared client for requests to the internet that don't need their own timeouts
+pub fn client() -> reqwest::Client {
+    let mut cached = CLIENT.lock().unwrap();
+    if let Some(client) = cached.as_ref() {
+        return client.clone();
+    }
+    let client = builder().build().unwrap_or_else(|e| {
+        tracing::error!("Failed to configure the HTTP client: {}", e);
+        reqwest::Client::new()
+    });
+    *cached = Some(client.clone());
+    client
+}
+
+// Proxy variables for the spawned server, with the credentials in the URL
+pub fn server_env() -> Vec<(&'static str, String)> {
+    const VARS: [&str; 6] = [
+        "HTTP_PROXY",
+        "HTTPS_PROXY",
+        "ALL_PROXY",
+        "http_proxy",
+        "https_proxy",
+        "all_proxy",
+    ];
+    match route() {
+        Route::System => Vec::new(),
+        // Blank values override whatever the app inherited
+        Route::Direct => VARS.iter().map(|var| (*var, String::new())).collect(),
+        Route::Via {
+            mut url,
+            password,
+            no_proxy,
+        } => {
+            if let Some(username) = settings::current().network_proxy.username {
+                let _ = url.set_username(&username);
+                let _ = url.set_password(password.as_deref());
+            }
+            let url = url.as_str().trim_end_matches('/').to_string();
+            let mut env: Vec<_> = VARS
+                .iter()
+                .filter(|var| is_socks_var(var) == url.starts_with("socks"))
+                .map(|var| (*var, url.clone()))
+                .collect();
+            env.push(("NO_PROXY", no_proxy.clone()));
+            env.push(("no_proxy", no_proxy));
+            env
+        }
+    }
+}
+
+// SOCKS proxies are only understood through `ALL_PROXY`
+fn is_socks_var(var: &str) -> bool {
+    var.eq_ignore_ascii_case("ALL_PROXY")
+}
+
+fn effective_url() -> Option<String> {
+    match route() {
+        Route::Via { url, .. } => Some(url.as_str().trim_end_matches('/').to_string()),
+        _ => None,
+    }
+}
+
+fn status() -> NetworkProxyStatus {
+    let settings = settings::current().network_proxy;
+    let route = route();
+    NetworkProxyStatus {
+        has_password: matches!(secure_store::read_blocking(PASSWORD_ENTRY), Ok(Some(_))),
+        clients_proxied: !matches!(&route, Route::Via { url, .. } if is_socks(url)),
+        effective_url: effective_url(),
+        error: LAST_ERROR.lock().unwrap().clone(),
+        settings,
+    }
+}
+
+// Resolve the proxy settings; called once from the setup hook. Requests made before this
+// finishes follow the system settings.
+pub fn init() {
+    tauri::async_runtime::spawn(async {
+        let _ = apply().await;
+    });
+}
+
+#[tauri::command]
+pub async fn get_network_proxy() -> Result<NetworkProxyStatus, AppError> {
+    Ok(tauri::async_runtime::spawn_blocking(status).await?)
+}
+
+// Save the proxy settings and apply them to new requests. `password` replaces the stored one
+// when given; an empty one removes it. The server picks the change up when it next starts.
+#[tauri::command]
+pub async fn set_network_proxy(
+    app: AppHandle,
+    proxy: NetworkProxySettings,
+    password: Option<String>,
+) -> Result<NetworkProxyStatus, AppError> {
+    match proxy.mode {
+        ProxyMode::Manual => {
+            let url = proxy
+                .url
+                .as_deref()
+                .ok_or_else(|| AppError::Validation("A manual proxy needs a URL".to_string()))?;
+            parse_proxy_url(url).map_err(AppError::Validation)?;
+        }
+        ProxyMode::Pac => {
+            let url = proxy.pac_url.as_deref().unwrap_or_default();
+            Url::parse(url).map_err(|e| {
+                AppError::Validation(format!("Invalid proxy script URL {}: {}", url, e))
+            })?;
+        }
+        ProxyMode::Direct | ProxyMode::System => {}
+    }
+    if let Some(password) = password {
+        match password.as_str() {
+            "" => secure_store::delete(PASSWORD_ENTRY).await,
+            password => secure_store::write(PASSWORD_ENTRY, password).await,
+        }
+        .map_err(AppError::Keychain)?;
+    }
+    settings::update(&app, |settings| settings.network_proxy = proxy)?;
+    apply().await
+}
+
+// Reach `url` (a model provider by default) through the configured proxy. Any HTTP reply
+// counts as reachable, since the request carries no API key.
+#[tauri::command]
+pub async fn test_proxy(url: Option<String>) -> Result<ProxyTest, AppError> {
+    let url = url.unwrap_or_else(|| TEST_URL.to_string());
+    Url::parse(&url).map_err(|e| AppError::Validation(format!("Invalid URL {}: {}", url, e)))?;
+    let client = builder()
+        .timeout(TEST_TIMEOUT)
+        .build()
+        .map_err(|e| AppError::Network(e.to_string()))?;
+    let started = Instant::now();
+    let result = client.get(&url).send().await;
+    let elapsed_ms = started.elapsed().as_millis() as u64;
+    Ok(match result {
+        Ok(response) => ProxyTest {
+            reachable: true,
+            status: Some(response.status().as_u16()),
+            elapsed_ms,
+            effective_url: effective_url(),
+            error: None,
+        },
+        Err(e) => ProxyTest {
+            reachable: false,
+            status: None,
+            elapsed_ms,
+            effective_url: effective_url(),
+            error: Some(e.to_string()),
+        },
+    })
+}
@@ -0,0 +1,232 @@
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter};
+
+use crate::error::AppError;
+use crate::server::{self, config, readiness, ServerStatus};
+use crate::{characters, cli, local_models, secrets, settings, workspace};
+
+// First-run setup, in the order it has to happen
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Step {
+    Cli,
+    Workspace,
+    Provider,
+    Character,
+    StartServer,
+}
+
+const STEPS: [Step; 5] = [
+    Step::Cli,
+    Step::Workspace,
+    Step::Provider,
+    Step::Character,
+    Step::StartServer,
+];
+
+impl Step {
+    fn label(self) -> &'static str {
+        match self {
+            Step::Cli => "elizaos CLI",
+            Step::Workspace => "project folder",
+            Step::Provider => "model provider",
+            Step::Character => "character",
+            Step::StartServer => "server start",
+        }
+    }
+}
+
+// Persisted in the settings so setup resumes where it stopped if the app is closed
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OnboardingProgress {
+    pub completed_steps: Vec<Step>,
+    // Set once the first server start succeeds; onboarding is never shown again
+    pub completed: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StepState {
+    pub step: Step,
+    pub done: bool,
+}
+
+// Payload of the `onboarding-changed` event
+#[derive(Debug, Clone, Serialize)]
+pub struct OnboardingState {
+    // The first step that isn't done yet; `None` once onboarding is complete
+    pub current: Option<Step>,
+    pub steps: Vec<StepState>,
+    pub completed: bool,
+}
+
+// What the user chose for a step; steps without choices take none
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct StepInput {
+    // Workspace: the project directory, scaffolded if it is missing or empty
+    path: Option<PathBuf>,
+    // Provider: one of `secrets::list_providers`, or "ollama" for a local model
+    provider: Option<String>,
+    api_key: Option<String>,
+    model: Option<String>,
+    // Character: a file in the characters dir or a path inside the workspace
+    character: Option<String>,
+}
+
+pub fn is_complete() -> bool {
+    settings::current().onboarding.completed
+}
+
+// Whether the step is already satisfied, e.g. by a CLI installed before the app
+fn detected(app: &AppHandle, step: Step) -> bool {
+    // A remote server brings its own CLI, workspace, providers and characters
+    if server::remote::is_remote() {
+        return !matches!(step, Step::StartServer) || server::poller::is_healthy();
+    }
+    match step {
+        Step::Cli if server::sidecar::enabled() || server::container::enabled() => true,
+        Step::Cli if server::runtime::enabled() => {
+            server::runtime::installed(app).is_some()
+                && cli::install::installed_entry(app).is_some()
+        }
+        Step::Cli => cli::resolve(app).is_some(),
+        Step::Workspace => {
+            settings::current().workspace_dir.is_some()
+                && workspace::dir().is_ok_and(|dir| dir.join("package.json").is_file())
+        }
+        Step::Provider => secrets::list_providers()
+            .iter()
+            .any(|provider| provider.configured),
+        Step::Character => !config::current().characters.is_empty(),
+        Step::StartServer => server::status() == ServerStatus::Running,
+    }
+}
+
+fn state(app: &AppHandle) -> OnboardingState {
+    let progress = settings::current().onboarding;
+    let steps: Vec<StepState> = STEPS
+        .iter()
+        .map(|&step| StepState {
+            step,
+            done: progress.completed
+                || progress.completed_steps.contains(&step)
+                || detected(app, step),
+        })
+        .collect();
+    OnboardingState {
+        current: steps
+            .iter()
+            .find(|state| !state.done)
+            .map(|state| state.step),
+        completed: progress.completed,
+        steps,
+    }
+}
+
+fn input<T>(value: Option<T>, what: &str) -> Result<T, String> {
+    value.ok_or_else(|| format!("Choose {} to continue", what))
+}
+
+async fn run_blocking<T: Send + 'static>(
+    task: impl FnOnce() -> Result<T, String> + Send + 'static,
+) -> Result<T, AppError> {
+    Ok(tauri::async_runtime::spawn_blocking(task).await??)
+}
+
+async fn perform(app: &AppHandle, step: Step, input_value: StepInput) -> Result<(), String> {
+    match step {
+        Step::Cli => {
+            // Setup installs the pinned versions the user asked for, so it takes no grant
+            if server::container::enabled() {
+                let app = app.clone();
+                tauri::async_runtime::spawn_blocking(move || server::container::ensure_image(&app))
+                    .await
+                    .map_err(|e| e.to_string())??;
+            } else if server::runtime::enabled() {
+                server::runtime::ensure(app).await?;
+                if cli::install::installed_entry(app).is_none() {
+                    cli::install::install_version(app, cli::install::CLI_VERSION).await?;
+                }
+            } else if !server::sidecar::enabled() && cli::resolve(app).is_none() {
+                cli::install::install_version(app, cli::install::CLI_VERSION).await?;
+            }
+        }
+        Step::Workspace => {
+            let path = input(input_value.path, "a project folder")?;
+            workspace::create_workspace(app.clone(), path).await?;
+        }
+        Step::Provider => {
+            let provider = input(input_value.provider, "a model provider")?;
+            if provider == "ollama" {
+                let model = input(input_value.model, "a local model")?;
+                local_models::use_local_model(model, None).await?;
+            } else {
+                let key = input(input_value.api_key, "an API key")?;
+                secrets::set_api_key(provider, key).await?;
+            }
+        }
+        Step::Character => {
+            let character = input(input_value.character, "a character")?;
+            characters::resolve(&character)?;
+            config::update(app, |config| config.characters = vec![character])?;
+        }
+        Step::StartServer if server::remote::is_remote() => {
+            server::health::check().await?;
+        }
+        Step::StartServer => {
+            if server::status() != ServerStatus::Running {
+                let handle = app.clone();
+                run_blocking(move || server::start(&handle)).await?;
+                readiness::wait_for_server_ready(config::current().startup_timeout_ms).await?;
+            }
+        }
+    }
+    Ok(())
+}
+
+// Where setup stands, counting anything already configured outside of it
+#[tauri::command]
+pub async fn get_onboarding_state(app: AppHandle) -> Result<OnboardingState, AppError> {
+    run_blocking(move || Ok(state(&app))).await
+}
+
+// Carry out `step` with the user's choices and record it. Steps run in order; an earlier
+// step that isn't done yet must be completed first.
+#[tauri::command]
+pub async fn complete_step(
+    app: AppHandle,
+    step: Step,
+    input: Option<StepInput>,
+) -> Result<OnboardingState, AppError> {
+    let handle = app.clone();
+    let before = run_blocking(move || Ok(state(&handle))).await?;
+    if let Some(pending) = before
+        .steps
+        .iter()
+        .take_while(|state| state.step != step)
+        .find(|state| !state.done)
+    {
+        return Err(AppError::Validation(format!(
+            "Complete the {} step first",
+            pending.step.label()
+        )));
+    }
+
+    perform(&app, step, input.unwrap_or_default()).await?;
+    settings::update(&app, |settings| {
+        let progress = &mut settings.onboarding;
+        if !progress.completed_steps.contains(&step) {
+            progress.completed_steps.push(step);
+        }
+        progress.completed |= step == Step::StartServer;
+    })?;
+
+    let handle = app.clone();
+    let after = run_blocking(move || Ok(state(&handle))).await?;
+    if let Err(e) = app.emit("onboarding-changed", &after) {
+        tracing::warn!("Failed to emit onboarding state: {}", e);
+    }
+    Ok(after)
+}
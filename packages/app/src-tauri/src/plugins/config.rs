@@ -0,0 +1,182 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::AppHandle;
+
+use super::{changed, package_manifest, validate_name};
+use crate::config;
+use crate::error::AppError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FieldType {
+    String,
+    Number,
+    Boolean,
+}
+
+// One entry of `agentConfig.pluginParameters` in the plugin's package.json
+#[derive(Debug, Clone, Deserialize)]
+struct Parameter {
+    #[serde(rename = "type", default = "default_type")]
+    kind: FieldType,
+    description: Option<String>,
+    #[serde(default)]
+    required: bool,
+    #[serde(default)]
+    sensitive: bool,
+    default: Option<Value>,
+}
+
+fn default_type() -> FieldType {
+    FieldType::String
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigField {
+    // Environment variable the plugin reads
+    pub key: String,
+    pub kind: FieldType,
+    pub description: Option<String>,
+    pub required: bool,
+    // Stored in the keychain; its value is never sent back to the frontend
+    pub sensitive: bool,
+    pub default: Option<String>,
+    pub value: Option<String>,
+    pub configured: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PluginConfig {
+    pub plugin: String,
+    pub fields: Vec<ConfigField>,
+    // Required fields with neither a value nor a default
+    pub missing: Vec<String>,
+}
+
+fn parameters(name: &str) -> Result<Vec<(String, Parameter)>, String> {
+    let manifest = package_manifest(name).ok_or_else(|| format!("{} is not installed", name))?;
+    let Some(declared) = manifest
+        .pointer("/agentConfig/pluginParameters")
+        .and_then(Value::as_object)
+    else {
+        return Ok(Vec::new());
+    };
+    let mut parameters: Vec<(String, Parameter)> = declared
+        .iter()
+        .filter_map(
+            |(key, parameter)| match serde_json::from_value(parameter.clone()) {
+                Ok(parameter) => Some((key.clone(), parameter)),
+                Err(e) => {
+                    tracing::warn!("Ignoring config parameter {} of {}: {}", key, name, e);
+                    None
+                }
+            },
+        )
+        .collect();
+    parameters.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(parameters)
+}
+
+fn default_text(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn check_value(key: &str, kind: FieldType, value: &str) -> Result<(), String> {
+    match kind {
+        FieldType::Number if value.parse::<f64>().is_err() => {
+            Err(format!("{} must be a number", key))
+        }
+        FieldType::Boolean if !matches!(value, "true" | "false") => {
+            Err(format!("{} must be true or false", key))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn load(name: &str) -> Result<PluginConfig, String> {
+    let env: HashMap<String, config::EnvVar> = config::read_env()?
+        .into_iter()
+        .map(|var| (var.key.clone(), var))
+        .collect();
+    let fields: Vec<ConfigField> = parameters(name)?
+        .into_iter()
+        .map(|(key, parameter)| {
+            let current = env.get(&key);
+            ConfigField {
+                kind: parameter.kind,
+                description: parameter.description,
+                required: parameter.required,
+                sensitive: parameter.sensitive,
+                default: parameter.default.as_ref().map(default_text),
+                value: current
+                    .filter(|_| !parameter.sensitive)
+                    .and_then(|var| var.value.clone()),
+                configured: current.is_some(),
+                key,
+            }
+        })
+        .collect();
+    let missing = fields
+        .iter()
+        .filter(|field| field.required && !field.configured && field.default.is_none())
+        .map(|field| field.key.clone())
+        .collect();
+    Ok(PluginConfig {
+        plugin: name.to_string(),
+        fields,
+        missing,
+    })
+}
+
+// The plugin's declared settings with their values from `.env`
+#[tauri::command]
+pub async fn get_plugin_config(name: String) -> Result<PluginConfig, AppError> {
+    validate_name(&name).map_err(AppError::Validation)?;
+    tauri::async_runtime::spawn_blocking(move || load(&name))
+        .await?
+        .map_err(AppError::Io)
+}
+
+// Write the given settings to `.env`, keeping sensitive ones in the keychain. A `null` or
+// empty value removes the setting so the plugin falls back to its default.
+#[tauri::command]
+pub async fn set_plugin_config(
+    app: AppHandle,
+    name: String,
+    values: HashMap<String, Option<String>>,
+) -> Result<PluginConfig, AppError> {
+    validate_name(&name).map_err(AppError::Validation)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let parameters: HashMap<String, Parameter> = parameters(&name)
+            .map_err(AppError::Io)?
+            .into_iter()
+            .collect();
+        // Check everything first so a bad value doesn't leave the config half written
+        for (key, value) in &values {
+            let parameter = parameters.get(key).ok_or_else(|| {
+                AppError::Validation(format!("{} has no setting named {}", name, key))
+            })?;
+            if let Some(value) = value.as_deref().filter(|value| !value.is_empty()) {
+                check_value(key, parameter.kind, value).map_err(AppError::Validation)?;
+            }
+        }
+
+        for (key, value) in values {
+            match value.filter(|value| !value.is_empty()) {
+                Some(value) => {
+                    config::write_env_var(key.clone(), value, Some(parameters[&key].sensitive))?
+                }
+                None => config::delete_env_var(key)?,
+            }
+        }
+        // The server only reads `.env` at startup
+        changed(&app)?;
+        load(&name).map_err(AppError::Io)
+    })
+    .await?
+}
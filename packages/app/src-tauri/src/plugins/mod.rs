@@ -0,0 +1,489 @@
+use std::fs;
+use std::io::{BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+use std::thread;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::{AppHandle, Emitter, Url};
+
+use crate::capabilities::{self, Capability};
+use crate::cli::path::find_tool;
+use crate::error::AppError;
+use crate::server::{self, ServerStatus};
+use crate::{characters, workspace};
+
+pub mod config;
+
+const SEARCH_URL: &str = "https://registry.npmjs.org/-/v1/search";
+const SEARCH_LIMIT: usize = 50;
+// elizaOS plugins are published as `plugin-*`, usually under the @elizaos scope
+const NAME_PREFIX: &str = "plugin-";
+
+// Only one package manager run at a time, since they all rewrite package.json
+static BUSY: Mutex<()> = Mutex::new(());
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum PluginStage {
+    Installing,
+    Removing,
+    Completed,
+    Failed,
+}
+
+// Payload of the `plugin-progress` event
+#[derive(Debug, Clone, Serialize)]
+struct PluginProgress {
+    plugin: String,
+    stage: PluginStage,
+    message: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PluginInfo {
+    pub name: String,
+    // Version range from package.json
+    pub requested: String,
+    // Version found in node_modules, if it has been installed
+    pub installed: Option<String>,
+    pub description: Option<String>,
+    // Listed in the characters the server starts with; unknown when there are no character files
+    pub enabled: Option<bool>,
+}
+
+// Payload of the `plugins-changed` event
+#[derive(Debug, Clone, Serialize)]
+pub struct PluginsChanged {
+    pub plugins: Vec<PluginInfo>,
+    // The running server still has the old plugin set loaded
+    pub restart_required: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RegistryPlugin {
+    pub name: String,
+    pub version: String,
+    pub description: Option<String>,
+    pub installed: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct SearchResponse {
+    objects: Vec<SearchObject>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SearchObject {
+    package: SearchPackage,
+}
+
+#[derive(Debug, Deserialize)]
+struct SearchPackage {
+    name: String,
+    version: String,
+    description: Option<String>,
+}
+
+fn emit(app: &AppHandle, plugin: &str, stage: PluginStage, message: Option<String>) {
+    let progress = PluginProgress {
+        plugin: plugin.to_string(),
+        stage,
+        message,
+    };
+    if let Err(e) = app.emit("plugin-progress", progress) {
+        tracing::warn!("Failed to emit plugin progress: {}", e);
+    }
+}
+
+fn is_plugin(name: &str) -> bool {
+    name.rsplit('/')
+        .next()
+        .is_some_and(|base| base.starts_with(NAME_PREFIX))
+}
+
+// npm package names, optionally scoped; anything else could be read as a flag or a path
+pub(crate) fn validate_package_name(name: &str) -> Result<(), String> {
+    let valid_part = |part: &str| {
+        !part.is_empty()
+            && !part.starts_with(['.', '_', '-'])
+            && part
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || "-._~".contains(c))
+    };
+    let valid = match name.strip_prefix('@') {
+        Some(scoped) => scoped
+            .split_once('/')
+            .is_some_and(|(scope, base)| valid_part(scope) && valid_part(base)),
+        None => valid_part(name),
+    };
+    if !valid || name.len() > 214 {
+        return Err(format!("Invalid package name: {}", name));
+    }
+    Ok(())
+}
+
+pub(crate) fn validate_name(name: &str) -> Result<(), String> {
+    validate_package_name(name)?;
+    if !is_plugin(name) {
+        return Err(format!("{} is not an elizaOS plugin", name));
+    }
+    Ok(())
+}
+
+pub(crate) fn validate_version(version: &str) -> Result<(), String> {
+    if version.is_empty()
+        || version.starts_with('-')
+        || !version
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || ".-+^~<>=*|".contains(c))
+    {
+        return Err(format!("Invalid version: {}", version));
+    }
+    Ok(())
+}
+
+fn read_json(path: &Path) -> Result<Value, String> {
+    let contents = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    serde_json::from_str(&contents)
+        .map_err(|e| format!("Invalid JSON in {}: {}", path.display(), e))
+}
+
+fn manifest_path() -> Result<PathBuf, String> {
+    let path = workspace::dir()?.join("package.json");
+    if !path.is_file() {
+        return Err(format!(
+            "{} has no package.json; choose an elizaOS project as the workspace",
+            path.parent().unwrap_or(&path).display()
+        ));
+    }
+    Ok(path)
+}
+
+// The installed package's own package.json
+pub(crate) fn package_manifest(name: &str) -> Option<Value> {
+    let mut path = workspace::dir().ok()?.join("node_modules");
+    path.extend(name.split('/'));
+    read_json(&path.join("package.json")).ok()
+}
+
+// Character files whose `plugins` lists decide what the server loads
+fn character_files() -> Result<Vec<PathBuf>, String> {
+    let configured = server::config::current().characters;
+    if !configured.is_empty() {
+        return configured
+            .iter()
+            .map(|character| characters::resolve(character))
+            .collect();
+    }
+    Ok(characters::list_characters()?
+        .into_iter()
+        .map(|character| character.path)
+        .collect())
+}
+
+fn character_plugins(character: &Value) -> Vec<String> {
+    character
+        .get("plugins")
+        .and_then(Value::as_array)
+        .map(|plugins| {
+            plugins
+                .iter()
+                .filter_map(|plugin| plugin.as_str().map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn enabled_plugins() -> Result<Option<Vec<String>>, String> {
+    let files = character_files()?;
+    if files.is_empty() {
+        return Ok(None);
+    }
+    let mut enabled = Vec::new();
+    for file in files {
+        for plugin in character_plugins(&read_json(&file)?) {
+            if !enabled.contains(&plugin) {
+                enabled.push(plugin);
+            }
+        }
+    }
+    Ok(Some(enabled))
+}
+
+// Add or remove `name` from the `plugins` list of every character the server starts with
+fn set_enabled(name: &str, enabled: bool) -> Result<(), String> {
+    let files = character_files()?;
+    if files.is_empty() {
+        return Err("There are no character files to enable the plugin in".to_string());
+    }
+    for file in files {
+        let mut character = read_json(&file)?;
+        let mut plugins = character_plugins(&character);
+        if plugins.iter().any(|plugin| plugin == name) == enabled {
+            continue;
+        }
+        if enabled {
+            plugins.push(name.to_string());
+        } else {
+            plugins.retain(|plugin| plugin != name);
+        }
+        let Some(object) = character.as_object_mut() else {
+            return Err(format!("{} is not a character object", file.display()));
+        };
+        object.insert("plugins".to_string(), plugins.into());
+        let contents = serde_json::to_string_pretty(&character).map_err(|e| e.to_string())?;
+        fs::write(&file, contents)
+            .map_err(|e| format!("Failed to write {}: {}", file.display(), e))?;
+    }
+    Ok(())
+}
+
+fn installed_plugins() -> Result<Vec<PluginInfo>, String> {
+    let manifest = read_json(&manifest_path()?)?;
+    let enabled = enabled_plugins()?;
+    let mut plugins: Vec<PluginInfo> = manifest
+        .get("dependencies")
+        .and_then(Value::as_object)
+        .into_iter()
+        .flatten()
+        .filter(|(name, _)| is_plugin(name))
+        .map(|(name, requested)| {
+            let package = package_manifest(name);
+            let field = |key: &str| {
+                package
+                    .as_ref()
+                    .and_then(|package| package.get(key)?.as_str().map(str::to_string))
+            };
+            PluginInfo {
+                name: name.clone(),
+                requested: requested.as_str().unwrap_or_default().to_string(),
+                installed: field("version"),
+                description: field("description"),
+                enabled: enabled.as_ref().map(|enabled| enabled.contains(name)),
+            }
+        })
+        .collect();
+    plugins.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(plugins)
+}
+
+// The project's own package manager: bun when it has a bun lockfile, otherwise npm, then bun
+pub(crate) fn package_manager(app: &AppHandle, dir: &Path) -> Result<(String, PathBuf), String> {
+    let uses_bun = ["bun.lock", "bun.lockb"]
+        .iter()
+        .any(|lockfile| dir.join(lockfile).is_file());
+    let order: &[&str] = if uses_bun {
+        &["bun", "npm"]
+    } else {
+        &["npm", "bun"]
+    };
+    order
+        .iter()
+        .find_map(|tool| find_tool(app, tool).map(|path| (tool.to_string(), path)))
+        .ok_or_else(|| "Installing packages requires Node.js (npm) or Bun".to_string())
+}
+
+fn stream_lines(app: &AppHandle, plugin: &str, stage: PluginStage, source: impl Read) {
+    for line in BufReader::new(source).lines().map_while(Result::ok) {
+        emit(app, plugin, stage, Some(line));
+    }
+}
+
+// Run the package manager in the workspace, which records the change in package.json
+fn run_package_manager(
+    app: &AppHandle,
+    plugin: &str,
+    stage: PluginStage,
+    spec: &str,
+) -> Result<(), String> {
+    let dir = workspace::dir()?;
+    let (tool, path) = package_manager(app, &dir)?;
+    let args: &[&str] = match (tool.as_str(), stage) {
+        ("bun", PluginStage::Removing) => &["remove"],
+        ("bun", _) => &["add"],
+        (_, PluginStage::Removing) => &["uninstall", "--no-audit", "--no-fund"],
+        _ => &["install", "--save", "--no-audit", "--no-fund"],
+    };
+
+    let _busy = BUSY.lock().unwrap();
+    emit(app, plugin, stage, None);
+    tracing::info!(plugin, "Running {} {} {}", tool, args[0], spec);
+    let mut child = Command::new(&path)
+        .args(args)
+        .arg(spec)
+        .current_dir(&dir)
+        .env("PATH", crate::cli::path::spawn_path(app, &path))
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to run {}: {}", tool, e))?;
+    let stdout = child.stdout.take();
+    let stderr = child.stderr.take();
+    thread::scope(|scope| {
+        if let Some(stdout) = stdout {
+            scope.spawn(|| stream_lines(app, plugin, stage, stdout));
+        }
+        if let Some(stderr) = stderr {
+            scope.spawn(|| stream_lines(app, plugin, stage, stderr));
+        }
+    });
+
+    let status = child
+        .wait()
+        .map_err(|e| format!("Failed to wait for {}: {}", tool, e))?;
+    if !status.success() {
+        return Err(format!("{} exited with {}", tool, status));
+    }
+    Ok(())
+}
+
+// Tell the frontend the plugin set or its configuration changed so it can offer a restart
+fn changed(app: &AppHandle) -> Result<PluginsChanged, String> {
+    let payload = PluginsChanged {
+        plugins: installed_plugins()?,
+        restart_required: server::status() == ServerStatus::Running,
+    };
+    if let Err(e) = app.emit("plugins-changed", &payload) {
+        tracing::warn!("Failed to emit plugins-changed: {}", e);
+    }
+    Ok(payload)
+}
+
+fn report(app: &AppHandle, plugin: &str, result: Result<(), String>) -> Result<(), String> {
+    match result {
+        Ok(()) => {
+            emit(app, plugin, PluginStage::Completed, None);
+            Ok(())
+        }
+        Err(e) => {
+            tracing::error!(plugin, "Plugin operation failed: {}", e);
+            emit(app, plugin, PluginStage::Failed, Some(e.clone()));
+            Err(e)
+        }
+    }
+}
+
+// Plugins declared in the workspace's package.json
+#[tauri::command]
+pub async fn list_installed_plugins() -> Result<Vec<PluginInfo>, AppError> {
+    tauri::async_runtime::spawn_blocking(installed_plugins)
+        .await?
+        .map_err(AppError::Io)
+}
+
+// Search npm for elizaOS plugins matching `query`
+#[tauri::command]
+pub async fn search_registry(query: String) -> Result<Vec<RegistryPlugin>, AppError> {
+    let query = query.trim();
+    let text = if query.is_empty() {
+        NAME_PREFIX.to_string()
+    } else {
+        format!("{} {}", NAME_PREFIX, query)
+    };
+    let url = Url::parse_with_params(
+        SEARCH_URL,
+        [
+            ("text", text.as_str()),
+            ("size", SEARCH_LIMIT.to_string().as_str()),
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    let response: SearchResponse = crate::network::client()
+        .get(url)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| format!("Failed to query the npm registry: {}", e))?
+        .json()
+        .await
+        .map_err(|e| format!("Invalid registry response: {}", e))?;
+
+    let installed = tauri::async_runtime::spawn_blocking(installed_plugins)
+        .await
+        .map_err(|e| e.to_string())?
+        .unwrap_or_default();
+    Ok(response
+        .objects
+        .into_iter()
+        .map(|object| object.package)
+        .filter(|package| is_plugin(&package.name))
+        .map(|package| RegistryPlugin {
+            installed: installed.iter().any(|plugin| plugin.name == package.name),
+            name: package.name,
+            version: package.version,
+            description: package.description,
+        })
+        .collect())
+}
+
+// Add a plugin to the project and enable it in its characters, streaming `plugin-progress`
+#[tauri::command]
+pub async fn install_plugin(
+    app: AppHandle,
+    name: String,
+    version: Option<String>,
+) -> Result<PluginsChanged, AppError> {
+    capabilities::require(Capability::RunPrograms)?;
+    validate_name(&name).map_err(AppError::Validation)?;
+    if let Some(version) = &version {
+        validate_version(version).map_err(AppError::Validation)?;
+    }
+    tauri::async_runtime::spawn_blocking(move || {
+        let spec = match &version {
+            Some(version) => format!("{}@{}", name, version),
+            None => name.clone(),
+        };
+        let result = run_package_manager(&app, &name, PluginStage::Installing, &spec);
+        report(&app, &name, result)?;
+        if let Err(e) = set_enabled(&name, true) {
+            tracing::warn!("Installed {} but could not enable it: {}", name, e);
+        }
+        changed(&app)
+    })
+    .await?
+    .map_err(AppError::Io)
+}
+
+// Disable a plugin in the characters, then remove it from the project
+#[tauri::command]
+pub async fn remove_plugin(app: AppHandle, name: String) -> Result<PluginsChanged, AppError> {
+    capabilities::require(Capability::RunPrograms)?;
+    validate_name(&name).map_err(AppError::Validation)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        if let Err(e) = set_enabled(&name, false) {
+            tracing::warn!("Could not disable {} before removing it: {}", name, e);
+        }
+        let result = run_package_manager(&app, &name, PluginStage::Removing, &name);
+        report(&app, &name, result)?;
+        changed(&app)
+    })
+    .await?
+    .map_err(AppError::Io)
+}
+
+// Load or skip an installed plugin by editing the characters' `plugins` lists
+#[tauri::command]
+pub async fn enable_plugin(
+    app: AppHandle,
+    name: String,
+    enabled: bool,
+) -> Result<PluginsChanged, AppError> {
+    validate_name(&name).map_err(AppError::Validation)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        if enabled
+            && !installed_plugins()?
+                .iter()
+                .any(|plugin| plugin.name == name)
+        {
+            return Err(format!("{} is not installed", name));
+        }
+        set_enabled(&name, enabled)?;
+        changed(&app)
+    })
+    .await?
+    .map_err(AppError::Io)
+}
@@ -0,0 +1,209 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter};
+
+use crate::server::{self, health, LifecycleState, SUPERVISOR};
+use crate::{history, settings};
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PowerSettings {
+    // Stop the server before the machine sleeps and start it again on wake
+    pub stop_server_on_sleep: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PowerState {
+    Suspending,
+    Resumed,
+}
+
+// Payload of the `power-changed` event
+#[derive(Debug, Clone, Serialize)]
+struct PowerEvent {
+    state: PowerState,
+    // After a resume: whether the server answered its health check
+    server_healthy: Option<bool>,
+}
+
+static APP: OnceCell<AppHandle> = OnceCell::new();
+// Set when `stop_server_on_sleep` stopped the server, so only then is it started on wake
+static STOPPED_FOR_SLEEP: AtomicBool = AtomicBool::new(false);
+
+fn emit(app: &AppHandle, state: PowerState, server_healthy: Option<bool>) {
+    let payload = PowerEvent {
+        state,
+        server_healthy,
+    };
+    if let Err(e) = app.emit("power-changed", payload) {
+        tracing::warn!("Failed to emit power change: {}", e);
+    }
+}
+
+// Runs on the notifying thread, which the OS only waits on briefly; save what would be
+// lost if the machine never wakes up
+fn on_suspend() {
+    let Some(app) = APP.get() else {
+        return;
+    };
+    tracing::info!("System is going to sleep");
+    emit(app, PowerState::Suspending, None);
+    if let Err(e) = crate::windows::save(app) {
+        tracing::warn!("{}", e);
+    }
+    if let Err(e) = history::checkpoint() {
+        tracing::warn!("Failed to checkpoint the history database: {}", e);
+    }
+    if settings::current().power.stop_server_on_sleep
+        && SUPERVISOR.state() == LifecycleState::Running
+    {
+        match server::stop(app) {
+            Ok(()) => STOPPED_FOR_SLEEP.store(true, Ordering::SeqCst),
+            Err(e) => tracing::warn!("Failed to stop the Eliza server before sleep: {}", e),
+        }
+    }
+}
+
+// Connections made before the sleep are likely dead even though nothing reported it
+fn on_resume() {
+    let Some(app) = APP.get().cloned() else {
+        return;
+    };
+    tracing::info!("System woke up");
+    // The keychain may have been locked or edited while asleep
+    crate::auth::secure_store::invalidate_all();
+    tauri::async_runtime::spawn(async move {
+        if STOPPED_FOR_SLEEP.swap(false, Ordering::SeqCst) {
+            let handle = app.clone();
+            let started = tauri::async_runtime::spawn_blocking(move || server::start(&handle))
+                .await
+                .map_err(|e| e.to_string())
+                .and_then(|result| result);
+            if let Err(e) = started {
+                tracing::warn!("Failed to start the Eliza server after sleep: {}", e);
+            }
+        }
+        let healthy = health::check().await.is_ok();
+        server::ws::reconnect(&app);
+        crate::auth::refresh::refresh_now(&app).await;
+        emit(&app, PowerState::Resumed, Some(healthy));
+    });
+}
+
+#[cfg(windows)]
+fn subscribe() -> Result<(), String> {
+    use windows_sys::Win32::System::Power::{
+        PowerRegisterSuspendResumeNotification, DEVICE_NOTIFY_SUBSCRIBE_PARAMETERS,
+    };
+    use windows_sys::Win32::UI::WindowsAndMessaging::{
+        DEVICE_NOTIFY_CALLBACK, PBT_APMRESUMEAUTOMATIC, PBT_APMSUSPEND,
+    };
+
+    unsafe extern "system" fn callback(
+        _context: *const std::ffi::c_void,
+        kind: u32,
+        _setting: *const std::ffi::c_void,
+    ) -> u32 {
+        match kind {
+            PBT_APMSUSPEND => on_suspend(),
+            // Sent on every wake, unlike PBT_APMRESUMESUSPEND which needs user input
+            PBT_APMRESUMEAUTOMATIC => on_resume(),
+            _ => {}
+        }
+        0
+    }
+
+    // The registration lives as long as the app, so the parameters are never freed
+    let parameters = Box::leak(Box::new(DEVICE_NOTIFY_SUBSCRIBE_PARAMETERS {
+        Callback: Some(callback),
+        Context: std::ptr::null_mut(),
+    }));
+    let mut registration = std::ptr::null_mut();
+    let error = unsafe {
+        PowerRegisterSuspendResumeNotification(
+            DEVICE_NOTIFY_CALLBACK,
+            parameters as *mut DEVICE_NOTIFY_SUBSCRIBE_PARAMETERS as _,
+            &mut registration,
+        )
+    };
+    if error == 0 {
+        Ok(())
+    } else {
+        Err(format!(
+            "Failed to register for power notifications: {}",
+            std::io::Error::from_raw_os_error(error as i32)
+        ))
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn subscribe() -> Result<(), String> {
+    use std::ptr::NonNull;
+
+    use block2::RcBlock;
+    use objc2_app_kit::{
+        NSWorkspace, NSWorkspaceDidWakeNotification, NSWorkspaceWillSleepNotification,
+    };
+    use objc2_foundation::NSNotification;
+
+    let center = NSWorkspace::sharedWorkspace().notificationCenter();
+    let observe = |name, handler: fn()| {
+        let block = RcBlock::new(move |_: NonNull<NSNotification>| handler());
+        // Without a queue the block runs on the posting thread, before the system sleeps
+        let observer = unsafe {
+            center.addObserverForName_object_queue_usingBlock(Some(name), None, None, &block)
+        };
+        // Observers stay registered for the life of the app
+        std::mem::forget(observer);
+    };
+    unsafe {
+        observe(NSWorkspaceWillSleepNotification, on_suspend);
+        observe(NSWorkspaceDidWakeNotification, on_resume);
+    }
+    Ok(())
+}
+
+// logind announces sleep with a `PrepareForSleep` signal on the system bus: true before
+// sleeping, false after waking
+#[cfg(target_os = "linux")]
+fn subscribe() -> Result<(), String> {
+    use std::io::{BufRead, BufReader};
+    use std::process::{Command, Stdio};
+
+    let mut monitor = Command::new("dbus-monitor")
+        .args([
+            "--system",
+            "type='signal',interface='org.freedesktop.login1.Manager',member='PrepareForSleep'",
+        ])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("Failed to watch for sleep with dbus-monitor: {}", e))?;
+    let stdout = monitor.stdout.take().ok_or("dbus-monitor has no output")?;
+    std::thread::spawn(move || {
+        for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+            match line.trim() {
+                "boolean true" => on_suspend(),
+                "boolean false" => on_resume(),
+                _ => {}
+            }
+        }
+        let _ = monitor.wait();
+        tracing::warn!("Stopped receiving sleep notifications");
+    });
+    Ok(())
+}
+
+#[cfg(not(any(windows, target_os = "macos", target_os = "linux")))]
+fn subscribe() -> Result<(), String> {
+    Err("Sleep notifications are not supported on this platform".to_string())
+}
+
+// Start listening for the OS going to sleep and waking up; called once from the setup hook
+pub fn init(app: &AppHandle) -> Result<(), String> {
+    let _ = APP.set(app.clone());
+    subscribe()
+}
@@ -0,0 +1,667 @@
+// Minimal QR code encoder: byte mode at error correction level M, versions 1 to 10, which
+// holds up to 213 bytes; plenty for the links the app shows as codes
+
+// Per version, indexed from 1: error correction codewords per block and block count at level M
+const ECC_PER_BLOCK: [usize; 11] = [0, 10, 16, 26, 18, 24, 16, 18, 22, 22, 26];
+const BLOCKS: [usize; 11] = [0, 1, 1, 1, 2, 2, 4, 4, 4, 5, 5];
+const MAX_VERSION: usize = 10;
+// Quiet zone around the code, in modules
+const BORDER: usize = 4;
+
+pub struct QrCode {
+    size: usize,
+    modules: Vec<bool>,
+}
+
+impl QrCode {
+    pub fn encode(data: &[u8]) -> Result<Self, String> {
+        let version = (1..=MAX_VERSION)
+            .find(|&version| {
+                4 + count_bits(version) + data.len() * 8 <= data_codewords(version) * 8
+            })
+            .ok_or_else(|| format!("{} bytes are too many for a QR code", data.len()))?;
+
+        let mut bits = Bits::default();
+        bits.push(0b0100, 4);
+        bits.push(data.len() as u32, count_bits(version));
+        for byte in data {
+            bits.push(u32::from(*byte), 8);
+        }
+        let capacity = data_codewords(version) * 8;
+        bits.push(0, (capacity - bits.len).min(4));
+        bits.push(0, (8 - bits.len % 8) % 8);
+        for pad in [0xec, 0x11].into_iter().cycle() {
+            if bits.len >= capacity {
+                break;
+            }
+            bits.push(pad, 8);
+        }
+
+        let mut code = Builder::new(version);
+        code.draw_function_patterns();
+        code.draw_codewords(&add_error_correction(version, &bits.bytes));
+        let mask = (0..8)
+            .min_by_key(|&mask| {
+                code.apply_mask(mask);
+                code.draw_format_bits(mask);
+                let penalty = code.penalty();
+                code.apply_mask(mask);
+                penalty
+            })
+            .unwrap_or(0);
+        code.apply_mask(mask);
+        code.draw_format_bits(mask);
+        Ok(QrCode {
+            size: code.size,
+            modules: code.modules,
+        })
+    }
+
+    // A standalone SVG with a white quiet zone, scaled by whoever displays it
+    pub fn to_svg(&self) -> String {
+        let extent = self.size + BORDER * 2;
+        let mut path = String::new();
+        for y in 0..self.size {
+            for x in 0..self.size {
+                if self.modules[y * self.size + x] {
+                    path.push_str(&format!("M{},{}h1v1h-1z", x + BORDER, y + BORDER));
+                }
+            }
+        }
+        format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 0 {0} {0}\" \
+             shape-rendering=\"crispEdges\"><rect width=\"{0}\" height=\"{0}\" fill=\"#fff\"/>\
+             <path d=\"{1}\" fill=\"#000\"/></svg>",
+            extent, path
+        )
+    }
+}
+
+#[derive(Default)]
+struct Bits {
+    bytes: Vec<u8>,
+    len: usize,
+}
+
+impl Bits {
+    fn push(&mut self, value: u32, count: usize) {
+        for i in (0..count).rev() {
+            if self.len.is_multiple_of(8) {
+                self.bytes.push(0);
+            }
+            if (value >> i) & 1 == 1 {
+                *self.bytes.last_mut().unwrap() |= 0x80 >> (self.len % 8);
+            }
+            self.len += 1;
+        }
+    }
+}
+
+fn count_bits(version: usize) -> usize {
+    if version < 10 {
+        8
+    } else {
+        16
+    }
+}
+
+// Modules left for data once the function patterns are drawn
+fn raw_modules(version: usize) -> usize {
+    let mut modules = (16 * version + 128) * version + 64;
+    if version >= 2 {
+        let alignments = version / 7 + 2;
+        modules -= (25 * alignments - 10) * alignments - 55;
+        if version >= 7 {
+            modules -= 36;
+        }
+    }
+    modules
+}
+
+fn data_codewords(version: usize) -> usize {
+    raw_modules(version) / 8 - ECC_PER_BLOCK[version] * BLOCKS[version]
+}
+
+fn alignment_positions(version: usize) -> Vec<usize> {
+    if version == 1 {
+        return Vec::new();
+    }
+    let size = version * 4 + 17;
+    let count = version / 7 + 2;
+    let step = (version * 4 + count * 2 + 1) / (count * 2 - 2) * 2;
+    let mut positions: Vec<usize> = (0..count - 1).map(|i| size - 7 - i * step).collect();
+    positions.push(6);
+    positions.reverse();
+    positions
+}
+
+// Multiplication in GF(2^8) modulo x^8 + x^4 + x^3 + x^2 + 1
+fn gf_multiply(x: u8, y: u8) -> u8 {
+    let mut z: u8 = 0;
+    for i in (0..8).rev() {
+        z = (z << 1) ^ ((z >> 7) * 0x1d);
+        z ^= ((y >> i) & 1) * x;
+    }
+    z
+}
+
+fn reed_solomon_divisor(degree: usize) -> Vec<u8> {
+    let mut divisor = vec![0u8; degree];
+    divisor[degree - 1] = 1;
+    let mut root: u8 = 1;
+    for _ in 0..degree {
+        for j in 0..degree {
+            divisor[j] = gf_multiply(divisor[j], root);
+            if j + 1 < degree {
+                divisor[j] ^= divisor[j + 1];
+            }
+        }
+        root = gf_multiply(root, 0x02);
+    }
+    divisor
+}
+
+fn reed_solomon_remainder(data: &[u8], divisor: &[u8]) -> Vec<u8> {
+    let mut remainder = vec![0u8; divisor.len()];
+    for byte in data {
+        let factor = byte ^ remainder.remove(0);
+        remainder.push(0);
+        for (value, coefficient) in remainder.iter_mut().zip(divisor) {
+            *value ^= gf_multiply(*coefficient, factor);
+        }
+    }
+    remainder
+}
+
+// Split the data into blocks, append each block's error correction and interleave them
+fn add_error_correction(version: usize, data: &[u8]) -> Vec<u8> {
+    let blocks = BLOCKS[version];
+    let ecc_len = ECC_PER_BLOCK[version];
+    let raw = raw_modules(version) / 8;
+    let short_blocks = blocks - raw % blocks;
+    let short_len = raw / blocks;
+    let divisor = reed_solomon_divisor(ecc_len);
+
+    let mut split = Vec::with_capacity(blocks);
+    let mut offset = 0;
+    for i in 0..blocks {
+        let len = short_len - ecc_len + usize::from(i >= short_blocks);
+        let mut block = data[offset..offset + len].to_vec();
+        offset += len;
+        let ecc = reed_solomon_remainder(&block, &divisor);
+        // Placeholder so every block has the same length; skipped when interleaving
+        if i < short_blocks {
+            block.push(0);
+        }
+        block.extend(ecc);
+        split.push(block);
+    }
+
+    let mut interleaved = Vec::with_capacity(raw);
+    for i in 0..split[0].len() {
+        for (j, block) in split.iter().enumerate() {
+            if i != short_len - ecc_len || j >= short_blocks {
+                interleaved.push(block[i]);
+            }
+        }
+    }
+    interleaved
+}
+
+struct Builder {
+    version: usize,
+    size: usize,
+    modules: Vec<bool>,
+    function: Vec<bool>,
+}
+
+impl Builder {
+    fn new(version: usize) -> Self {
+        let size = version * 4 + 17;
+        Builder {
+            version,
+            size,
+            modules: vec![false; size * size],
+            function: vec![false; size * size],
+        }
+    }
+
+    fn set_function(&mut self, x: usize, y: usize, dark: bool) {
+        self.modules[y * self.size + x] = dark;
+        self.function[y * self.size + x] = true;
+    }
+
+    fn draw_function_patterns(&mut self) {
+        for i in 0..self.size {
+            self.set_function(6, i, i % 2 == 0);
+            self.set_function(i, 6, i % 2 == 0);
+        }
+        let far = self.size - 4;
+        self.draw_finder(3, 3);
+        self.draw_finder(far, 3);
+        self.draw_finder(3, far);
+
+        let positions = alignment_positions(self.version);
+        let last = positions.len().saturating_sub(1);
+        for (i, &x) in positions.iter().enumerate() {
+            for (j, &y) in positions.iter().enumerate() {
+                // Those corners are taken by finder patterns
+                let corner = (i == 0 && (j == 0 || j == last)) || (i == last && j == 0);
+                if !corner {
+                    self.draw_alignment(x, y);
+                }
+            }
+        }
+        // Reserve the format area; the real bits are drawn once the mask is chosen
+        self.draw_format_bits(0);
+        self.draw_version();
+    }
+
+    fn draw_finder(&mut self, x: usize, y: usize) {
+        for dy in -4i32..=4 {
+            for dx in -4i32..=4 {
+                let (xx, yy) = (x as i32 + dx, y as i32 + dy);
+                if (0..self.size as i32).contains(&xx) && (0..self.size as i32).contains(&yy) {
+                    let distance = dx.abs().max(dy.abs());
+                    self.set_function(xx as usize, yy as usize, distance != 2 && distance != 4);
+                }
+            }
+        }
+    }
+
+    fn draw_alignment(&mut self, x: usize, y: usize) {
+        for dy in -2i32..=2 {
+            for dx in -2i32..=2 {
+                let dark = dx.abs().max(dy.abs()) != 1;
+                self.set_function((x as i32 + dx) as usize, (y as i32 + dy) as usize, dark);
+            }
+        }
+    }
+
+    fn draw_format_bits(&mut self, mask: u32) {
+        // Level M is encoded as 00
+        let data = mask;
+        let mut remainder = data;
+        for _ in 0..10 {
+            remainder = (remainder << 1) ^ ((remainder >> 9) * 0x537);
+        }
+        let bits = ((data << 10) | remainder) ^ 0x5412;
+        let bit = |i: usize| (bits >> i) & 1 == 1;
+
+        for i in 0..=5 {
+            self.set_function(8, i, bit(i));
+        }
+        self.set_function(8, 7, bit(6));
+        self.set_function(8, 8, bit(7));
+        self.set_function(7, 8, bit(8));
+        for i in 9..15 {
+            self.set_function(14 - i, 8, bit(i));
+        }
+
+        let size = self.size;
+        for i in 0..8 {
+            self.set_function(size - 1 - i, 8, bit(i));
+        }
+        for i in 8..15 {
+            self.set_function(8, size - 15 + i, bit(i));
+        }
+        // Always dark
+        self.set_function(8, size - 8, true);
+    }
+
+    fn draw_version(&mut self) {
+        if self.version < 7 {
+            return;
+        }
+        let version = self.version as u32;
+        let mut remainder = version;
+        for _ in 0..12 {
+            remainder = (remainder << 1) ^ ((remainder >> 11) * 0x1f25);
+        }
+        let bits = (version << 12) | remainder;
+        for i in 0..18 {
+            let dark = (bits >> i) & 1 == 1;
+            let (a, b) = (self.size - 11 + i % 3, i / 3);
+            self.set_function(a, b, dark);
+            self.set_function(b, a, dark);
+        }
+    }
+
+    // Place codewords in the zigzag order, two columns at a time from the bottom right
+    fn draw_codewords(&mut self, data: &[u8]) {
+        let mut i = 0;
+        let mut right = self.size as i32 - 1;
+        while right >= 1 {
+            if right == 6 {
+                right = 5;
+            }
+            for vertical in 0..self.size {
+                for j in 0..2 {
+                    let x = (right - j) as usize;
+                    let upward = (right + 1) & 2 == 0;
+                    let y = if upward {
+                        self.size - 1 - vertical
+                    } else {
+                        vertical
+                    };
+                    if !self.function[y * self.size + x] && i < data.len() * 8 {
+                        self.modules[y * self.size + x] = (data[i >> 3] >> (7 - (i & 7))) & 1 == 1;
+                        i += 1;
+                    }
+                }
+            }
+            right -= 2;
+        }
+    }
+
+    // Applying the same mask twice undoes it
+    fn apply_mask(&mut self, mask: u32) {
+        for y in 0..self.size {
+            for x in 0..self.size {
+                let invert = match mask {
+                    0 => (x + y) % 2 == 0,
+                    1 => y % 2 == 0,
+                    2 => x % 3 == 0,
+                    3 => (x + y) % 3 == 0,
+                    4 => (x / 3 + y / 2) % 2 == 0,
+                    5 => x * y % 2 + x * y % 3 == 0,
+                    6 => (x * y % 2 + x * y % 3) % 2 == 0,
+                    _ => ((x + y) % 2 + x * y % 3) % 2 == 0,
+                };
+                let index = y * self.size + x;
+                if invert && !self.function[index] {
+                    self.modules[index] = !self.modules[index];
+                }
+            }
+        }
+    }
+
+    // Runs, 2x2 blocks and dark/light balance from the standard's mask scoring; any mask
+    // decodes, this only favours codes that scan easily
+    fn penalty(&self) -> usize {
+        let size = self.size;
+        let at = |x: usize, y: usize| self.modules[y * size + x];
+        let mut penalty = 0;
+        for line in 0..size {
+            for horizontal in [true, false] {
+                let mut run = 1;
+                for i in 1..size {
+                    let (current, previous) = if horizontal {
+                        (at(i, line), at(i - 1, line))
+                    } else {
+                        (at(line, i), at(line, i - 1))
+                    };
+                    if current == previous {
+                        run += 1;
+                        if run == 5 {
+                            penalty += 3;
+                        } else if run > 5 {
+                            penalty += 1;
+                        }
+                    } else {
+                        run = 1;
+                    }
+                }
+            }
+        }
+        for y in 0..size - 1 {
+            for x in 0..size - 1 {
+                let color = at(x, y);
+                if color == at(x + 1, y) && color == at(x, y + 1) && color == at(x + 1, y + 1) {
+                    penalty += 3;
+                }
+            }
+        }
+        let dark = self.modules.iter().filter(|dark| **dark).count();
+        let total = size * size;
+        let deviation = (dark * 20).abs_diff(total * 10);
+        penalty + deviation.div_ceil(total).saturating_sub(1) * 10
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // From the standard, per version at level M: byte mode capacity, error correction
+    // codewords per block and the data codewords of each block
+    const CAPACITY: [usize; 11] = [0, 14, 26, 42, 62, 84, 106, 122, 152, 180, 213];
+    const LAYOUT: [(usize, &[usize]); 11] = [
+        (0, &[]),
+        (10, &[16]),
+        (16, &[28]),
+        (26, &[44]),
+        (18, &[32, 32]),
+        (24, &[43, 43]),
+        (16, &[27, 27, 27, 27]),
+        (18, &[31, 31, 31, 31]),
+        (22, &[38, 38, 39, 39]),
+        (22, &[36, 36, 36, 37, 37]),
+        (26, &[43, 43, 43, 43, 44]),
+    ];
+    const ALIGNMENT: [&[usize]; 11] = [
+        &[],
+        &[],
+        &[6, 18],
+        &[6, 22],
+        &[6, 26],
+        &[6, 30],
+        &[6, 34],
+        &[6, 22, 38],
+        &[6, 24, 42],
+        &[6, 26, 46],
+        &[6, 28, 50],
+    ];
+    // Format information for level M, by mask
+    const FORMAT: [u32; 8] = [
+        0b101010000010010,
+        0b101000100100101,
+        0b101111001111100,
+        0b101101101001011,
+        0b100010111111001,
+        0b100000011001110,
+        0b100111110010111,
+        0b100101010100000,
+    ];
+    // Version information for versions 7 to 10
+    const VERSION_INFO: [u32; 4] = [0x07c94, 0x085bc, 0x09a99, 0x0a4d3];
+
+    fn is_function(version: usize, x: usize, y: usize) -> bool {
+        let size = version * 4 + 17;
+        let (near, far) = (|v: usize| v < 9, |v: usize| v >= size - 8);
+        let corner = (near(y) && (near(x) || far(x))) || (near(x) && far(y));
+        let info_block = |a: usize, b: usize| a >= size - 11 && b < 6;
+        let version_info = version >= 7 && (info_block(x, y) || info_block(y, x));
+        let positions = ALIGNMENT[version];
+        let alignment = positions.iter().any(|&ax| {
+            positions.iter().any(|&ay| {
+                let overlaps_finder = [(6, 6), (6, size - 7), (size - 7, 6)].contains(&(ax, ay));
+                !overlaps_finder && x.abs_diff(ax) <= 2 && y.abs_diff(ay) <= 2
+            })
+        });
+        corner || version_info || alignment || x == 6 || y == 6
+    }
+
+    fn masked(mask: u32, x: usize, y: usize) -> bool {
+        match mask {
+            0 => (y + x).is_multiple_of(2),
+            1 => y.is_multiple_of(2),
+            2 => x.is_multiple_of(3),
+            3 => (y + x).is_multiple_of(3),
+            4 => (y / 2 + x / 3).is_multiple_of(2),
+            5 => (y * x) % 2 + (y * x) % 3 == 0,
+            6 => ((y * x) % 2 + (y * x) % 3).is_multiple_of(2),
+            _ => ((y + x) % 2 + (y * x) % 3).is_multiple_of(2),
+        }
+    }
+
+    // Evaluate the block as a polynomial at the first `ecc` powers of the generator; a
+    // valid Reed-Solomon codeword is zero at all of them
+    fn syndromes_are_zero(block: &[u8], ecc: usize) -> bool {
+        let mut exp = [0u8; 255];
+        let mut value: u16 = 1;
+        for entry in exp.iter_mut() {
+            *entry = value as u8;
+            value <<= 1;
+            if value & 0x100 != 0 {
+                value ^= 0x11d;
+            }
+        }
+        let mut log = [0usize; 256];
+        for (power, &entry) in exp.iter().enumerate() {
+            log[entry as usize] = power;
+        }
+        let multiply = |a: u8, b: u8| match (a, b) {
+            (0, _) | (_, 0) => 0,
+            _ => exp[(log[a as usize] + log[b as usize]) % 255],
+        };
+        (0..ecc).all(|i| {
+            block
+                .iter()
+                .fold(0u8, |sum, &coefficient| multiply(sum, exp[i]) ^ coefficient)
+                == 0
+        })
+    }
+
+    // Read a code back the way a scanner would, checking everything the standard fixes
+    fn decode(code: &QrCode) -> (usize, Vec<u8>) {
+        let size = code.size;
+        let version = (size - 17) / 4;
+        assert_eq!(version * 4 + 17, size);
+        let at = |x: usize, y: usize| code.modules[y * size + x];
+        let read = |cells: &[(usize, usize)]| {
+            cells
+                .iter()
+                .enumerate()
+                .fold(0u32, |bits, (i, &(x, y))| bits | (u32::from(at(x, y)) << i))
+        };
+
+        let mut first = Vec::new();
+        first.extend((0..6).map(|i| (8, i)));
+        first.extend([(8, 7), (8, 8), (7, 8)]);
+        first.extend((0..6).rev().map(|x| (x, 8)));
+        let mut second = Vec::new();
+        second.extend((0..8).map(|i| (size - 1 - i, 8)));
+        second.extend((size - 7..size).map(|y| (8, y)));
+        let format = read(&first);
+        assert_eq!(format, read(&second), "format copies differ");
+        let mask = FORMAT
+            .iter()
+            .position(|&known| known == format)
+            .expect("format information isn't level M") as u32;
+        assert!(at(8, size - 8), "dark module is missing");
+
+        if version >= 7 {
+            let below: Vec<_> = (0..18).map(|i| (i / 3, size - 11 + i % 3)).collect();
+            let right: Vec<_> = (0..18).map(|i| (size - 11 + i % 3, i / 3)).collect();
+            assert_eq!(read(&below), VERSION_INFO[version - 7]);
+            assert_eq!(read(&right), VERSION_INFO[version - 7]);
+        }
+
+        let mut bits = Vec::new();
+        let mut column = size - 1;
+        let mut upward = true;
+        loop {
+            for step in 0..size {
+                let y = if upward { size - 1 - step } else { step };
+                for x in [column, column - 1] {
+                    if !is_function(version, x, y) {
+                        bits.push(at(x, y) ^ masked(mask, x, y));
+                    }
+                }
+            }
+            upward = !upward;
+            if column < 3 {
+                break;
+            }
+            column -= 2;
+            if column == 6 {
+                column = 5;
+            }
+        }
+        let codewords: Vec<u8> = bits
+            .chunks_exact(8)
+            .map(|byte| {
+                byte.iter()
+                    .fold(0, |value, &bit| (value << 1) | u8::from(bit))
+            })
+            .collect();
+
+        let (ecc, lengths) = LAYOUT[version];
+        let mut blocks: Vec<Vec<u8>> = vec![Vec::new(); lengths.len()];
+        let mut next = codewords.iter();
+        for i in 0..*lengths.iter().max().unwrap() {
+            for (block, &len) in blocks.iter_mut().zip(lengths) {
+                if i < len {
+                    block.push(*next.next().unwrap());
+                }
+            }
+        }
+        let data: Vec<u8> = blocks.iter().flatten().copied().collect();
+        for _ in 0..ecc {
+            for block in blocks.iter_mut() {
+                block.push(*next.next().unwrap());
+            }
+        }
+        for block in &blocks {
+            assert!(syndromes_are_zero(block, ecc), "error correction is wrong");
+        }
+
+        let bit = |i: usize| u32::from((data[i / 8] >> (7 - i % 8)) & 1);
+        let field = |from: usize, len: usize| (from..from + len).fold(0, |v, i| (v << 1) | bit(i));
+        assert_eq!(field(0, 4), 0b0100, "not byte mode");
+        let count_len = if version < 10 { 8 } else { 16 };
+        let len = field(4, count_len) as usize;
+        let start = 4 + count_len;
+        let decoded = (0..len).map(|i| field(start + i * 8, 8) as u8).collect();
+        let end = (start + len * 8).div_ceil(8);
+        for (i, &pad) in data[end..].iter().enumerate() {
+            assert_eq!(pad, [0xec, 0x11][i % 2], "bad padding");
+        }
+        (version, decoded)
+    }
+
+    #[test]
+    fn data_codewords_match_the_standard() {
+        for (version, (_, lengths)) in LAYOUT.iter().enumerate().skip(1) {
+            assert_eq!(data_codewords(version), lengths.iter().sum::<usize>());
+        }
+    }
+
+    #[test]
+    fn decodes_to_the_input_in_the_smallest_version() {
+        let mut inputs: Vec<Vec<u8>> = vec![
+            b"".to_vec(),
+            b"https://example.com/pair?code=8f3a".to_vec(),
+            (0..=255).cycle().take(200).collect(),
+        ];
+        for &capacity in &CAPACITY[1..] {
+            inputs.push(vec![b'a'; capacity]);
+            inputs.push(vec![b'z'; capacity + 1]);
+        }
+        for input in inputs
+            .into_iter()
+            .filter(|input| input.len() <= CAPACITY[10])
+        {
+            let code = QrCode::encode(&input).unwrap();
+            let (version, decoded) = decode(&code);
+            assert_eq!(decoded, input);
+            let smallest = (1..=MAX_VERSION).find(|&v| input.len() <= CAPACITY[v]);
+            assert_eq!(Some(version), smallest, "{} bytes", input.len());
+        }
+    }
+
+    #[test]
+    fn rejects_data_over_the_capacity() {
+        assert!(QrCode::encode(&[0; 214]).is_err());
+    }
+
+    #[test]
+    fn svg_has_a_module_per_dark_cell() {
+        let code = QrCode::encode(b"hello").unwrap();
+        let dark = code.modules.iter().filter(|dark| **dark).count();
+        let svg = code.to_svg();
+        assert!(svg.contains("viewBox=\"0 0 29 29\""));
+        assert_eq!(svg.matches("h1v1h-1z").count(), dark);
+    }
+}
@@ -0,0 +1,143 @@
+use tauri::{
+    AppHandle, Manager, PhysicalPosition, WebviewUrl, WebviewWindow, WebviewWindowBuilder,
+    WindowEvent,
+};
+use tauri_plugin_global_shortcut::ShortcutState;
+
+use crate::error::AppError;
+use crate::{settings, shortcuts};
+
+const WINDOW_LABEL: &str = "quick-chat";
+// The frontend renders the compact "quick ask" view for this route
+const WINDOW_URL: &str = "index.html#/quick-chat";
+const WINDOW_WIDTH: f64 = 600.0;
+const WINDOW_HEIGHT: f64 = 360.0;
+// Gap between the cursor and the top of the window, in physical pixels
+const CURSOR_OFFSET: i32 = 16;
+
+fn build_window(app: &AppHandle) -> Result<WebviewWindow, String> {
+    let window = WebviewWindowBuilder::new(app, WINDOW_LABEL, WebviewUrl::App(WINDOW_URL.into()))
+        .title("Quick Ask")
+        .inner_size(WINDOW_WIDTH, WINDOW_HEIGHT)
+        .resizable(false)
+        .decorations(false)
+        .always_on_top(true)
+        .skip_taskbar(true)
+        .visible(false)
+        .build()
+        .map_err(|e| format!("Failed to create the quick chat window: {}", e))?;
+
+    // Behave like a popover: clicking elsewhere dismisses it
+    let popover = window.clone();
+    window.on_window_event(move |event| {
+        if let WindowEvent::Focused(false) = event {
+            let _ = popover.hide();
+        }
+    });
+    Ok(window)
+}
+
+// Center the window horizontally under the cursor, kept inside the cursor's monitor
+fn place_near_cursor(app: &AppHandle, window: &WebviewWindow) -> Result<(), String> {
+    let cursor = app.cursor_position().map_err(|e| e.to_string())?;
+    let size = window.outer_size().map_err(|e| e.to_string())?;
+    let (width, height) = (size.width as i32, size.height as i32);
+    let mut x = cursor.x as i32 - width / 2;
+    let mut y = cursor.y as i32 + CURSOR_OFFSET;
+
+    if let Ok(Some(monitor)) = app.monitor_from_point(cursor.x, cursor.y) {
+        let (origin, area) = (monitor.position(), monitor.size());
+        let (right, bottom) = (origin.x + area.width as i32, origin.y + area.height as i32);
+        x = x.clamp(origin.x, (right - width).max(origin.x));
+        if y + height > bottom {
+            // No room below the cursor, so open above it instead
+            y = cursor.y as i32 - CURSOR_OFFSET - height;
+        }
+        y = y.clamp(origin.y, (bottom - height).max(origin.y));
+    }
+    window
+        .set_position(PhysicalPosition::new(x, y))
+        .map_err(|e| e.to_string())
+}
+
+fn window(app: &AppHandle) -> Result<WebviewWindow, String> {
+    match app.get_webview_window(WINDOW_LABEL) {
+        Some(window) => Ok(window),
+        None => build_window(app),
+    }
+}
+
+// Open the quick chat window next to the cursor, creating it on first use
+pub fn show(app: &AppHandle) -> Result<WebviewWindow, String> {
+    let window = window(app)?;
+    if !window.is_visible().unwrap_or(false) {
+        if let Err(e) = place_near_cursor(app, &window) {
+            tracing::warn!("Failed to position the quick chat window: {}", e);
+        }
+        window.show().map_err(|e| e.to_string())?;
+    }
+    window.set_focus().map_err(|e| e.to_string())?;
+    Ok(window)
+}
+
+pub fn toggle(app: &AppHandle) -> Result<(), String> {
+    let window = window(app)?;
+    if window.is_visible().unwrap_or(false) {
+        window.hide().map_err(|e| e.to_string())
+    } else {
+        show(app).map(|_| ())
+    }
+}
+
+fn on_shortcut(app: &AppHandle, state: ShortcutState) {
+    if state == ShortcutState::Pressed {
+        if let Err(e) = toggle(app) {
+            tracing::warn!("{}", e);
+        }
+    }
+}
+
+fn active_shortcut() -> String {
+    let settings = settings::current();
+    if settings.quick_chat_enabled {
+        settings.quick_chat_shortcut
+    } else {
+        String::new()
+    }
+}
+
+pub fn init(app: &AppHandle) -> Result<(), String> {
+    shortcuts::bind(app, &active_shortcut(), on_shortcut)
+}
+
+#[tauri::command]
+pub fn toggle_quick_chat(app: AppHandle) -> Result<(), AppError> {
+    Ok(toggle(&app)?)
+}
+
+// Change the hotkey that summons the quick chat window, e.g. "CommandOrControl+Shift+K"
+#[tauri::command]
+pub fn set_quick_chat_shortcut(app: AppHandle, shortcut: String) -> Result<(), AppError> {
+    if settings::current().quick_chat_enabled {
+        shortcuts::rebind(&app, &active_shortcut(), &shortcut, on_shortcut)?;
+    }
+    settings::update(&app, |settings| settings.quick_chat_shortcut = shortcut)?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn set_quick_chat_enabled(app: AppHandle, enabled: bool) -> Result<(), AppError> {
+    let next = if enabled {
+        settings::current().quick_chat_shortcut
+    } else {
+        String::new()
+    };
+    shortcuts::rebind(&app, &active_shortcut(), &next, on_shortcut)?;
+    if !enabled {
+        if let Some(window) = app.get_webview_window(WINDOW_LABEL) {
+            let _ = window.hide();
+        }
+    }
+    settings::update(&app, |settings| settings.quick_chat_enabled = enabled)?;
+    Ok(())
+}
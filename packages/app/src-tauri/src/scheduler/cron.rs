@@ -0,0 +1,287 @@
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, TimeZone, Timelike};
+
+// How far ahead to look for the next match; a schedule like `0 0 30 2 *` never matches
+const SEARCH_DAYS: i64 = 366 * 4;
+
+// A standard five-field cron expression (minute, hour, day of month, month, day of week) in
+// local time. Fields take `*`, numbers, `a-b` ranges, `/step` and comma lists; the `@hourly`,
+// `@daily`, `@weekly`, `@monthly` and `@yearly` shorthands are accepted too.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Schedule {
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    // Cron matches either day field when both are restricted, and only the restricted one
+    // otherwise
+    any_day: bool,
+    any_weekday: bool,
+}
+
+fn parse_field(field: &str, min: u32, max: u32, what: &str) -> Result<(u64, bool), String> {
+    let invalid = || format!("Invalid {} in the schedule: {}", what, field);
+    let mut bits = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, step.parse::<u32>().map_err(|_| invalid())?),
+            None => (part, 1),
+        };
+        let (start, end) = match range {
+            "*" => (min, max),
+            _ => match range.split_once('-') {
+                Some((start, end)) => (
+                    start.parse().map_err(|_| invalid())?,
+                    end.parse().map_err(|_| invalid())?,
+                ),
+                None => {
+                    let value = range.parse().map_err(|_| invalid())?;
+                    // `5/15` means from 5 to the end in steps of 15
+                    (value, if part.contains('/') { max } else { value })
+                }
+            },
+        };
+        if step == 0 || start < min || end > max || start > end {
+            return Err(invalid());
+        }
+        for value in (start..=end).step_by(step as usize) {
+            bits |= 1 << value;
+        }
+    }
+    Ok((bits, field == "*"))
+}
+
+impl Schedule {
+    pub fn parse(expression: &str) -> Result<Schedule, String> {
+        let expression = match expression.trim() {
+            "@hourly" => "0 * * * *",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            other => other,
+        };
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            return Err(format!(
+                "A schedule needs five fields (minute hour day month weekday): {}",
+                expression
+            ));
+        };
+        let (minutes, _) = parse_field(minute, 0, 59, "minute")?;
+        let (hours, _) = parse_field(hour, 0, 23, "hour")?;
+        let (days, any_day) = parse_field(day, 1, 31, "day of month")?;
+        let (months, _) = parse_field(month, 1, 12, "month")?;
+        let (mut weekdays, any_weekday) = parse_field(weekday, 0, 7, "day of week")?;
+        // Both 0 and 7 are Sunday
+        if weekdays & (1 << 7) != 0 {
+            weekdays |= 1;
+        }
+        Ok(Schedule {
+            minutes,
+            hours,
+            days,
+            months,
+            weekdays,
+            any_day,
+            any_weekday,
+        })
+    }
+
+    fn matches_date(&self, date: NaiveDate) -> bool {
+        let day = self.days & (1 << date.day()) != 0;
+        let weekday = self.weekdays & (1 << date.weekday().num_days_from_sunday()) != 0;
+        let day_matches = match (self.any_day, self.any_weekday) {
+            (true, true) => true,
+            (false, true) => day,
+            (true, false) => weekday,
+            (false, false) => day || weekday,
+        };
+        self.months & (1 << date.month()) != 0 && day_matches
+    }
+
+    // The first matching minute strictly after `after`
+    pub fn next_after(&self, after: DateTime<Local>) -> Option<DateTime<Local>> {
+        let start = after.naive_local() + Duration::minutes(1);
+        let start_date = start.date();
+        for offset in 0..SEARCH_DAYS {
+            let date = start_date + Duration::days(offset);
+            if !self.matches_date(date) {
+                continue;
+            }
+            let first_hour = if offset == 0 { start.hour() } else { 0 };
+            for hour in (first_hour..24).filter(|hour| self.hours & (1 << hour) != 0) {
+                let first_minute = if offset == 0 && hour == start.hour() {
+                    start.minute()
+                } else {
+                    0
+                };
+                for minute in (first_minute..60).filter(|minute| self.minutes & (1 << minute) != 0)
+                {
+                    let naive = date.and_hms_opt(hour, minute, 0)?;
+                    // Skip times that fall into a daylight saving gap
+                    if let Some(time) = Local.from_local_datetime(&naive).earliest() {
+                        return Some(time);
+                    }
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> DateTime<Local> {
+        Local
+            .with_ymd_and_hms(year, month, day, hour, minute, 0)
+            .single()
+            .unwrap()
+    }
+
+    fn next(expression: &str, after: DateTime<Local>) -> Option<DateTime<Local>> {
+        Schedule::parse(expression).unwrap().next_after(after)
+    }
+
+    fn bits(values: &[u32]) -> u64 {
+        values.iter().fold(0, |bits, value| bits | 1 << value)
+    }
+
+    #[test]
+    fn fields() {
+        assert_eq!(
+            parse_field("*", 0, 5, "minute").unwrap(),
+            (bits(&[0, 1, 2, 3, 4, 5]), true)
+        );
+        assert_eq!(
+            parse_field("3", 0, 59, "minute").unwrap(),
+            (bits(&[3]), false)
+        );
+        assert_eq!(
+            parse_field("1-4", 0, 59, "minute").unwrap(),
+            (bits(&[1, 2, 3, 4]), false)
+        );
+        assert_eq!(
+            parse_field("1,5,9", 0, 59, "minute").unwrap(),
+            (bits(&[1, 5, 9]), false)
+        );
+        assert_eq!(
+            parse_field("*/15", 0, 59, "minute").unwrap(),
+            (bits(&[0, 15, 30, 45]), false)
+        );
+        assert_eq!(
+            parse_field("5/20", 0, 59, "minute").unwrap(),
+            (bits(&[5, 25, 45]), false)
+        );
+        assert_eq!(
+            parse_field("10-20/5", 0, 59, "minute").unwrap(),
+            (bits(&[10, 15, 20]), false)
+        );
+        assert_eq!(
+            parse_field("1-2,*/6", 0, 23, "hour").unwrap(),
+            (bits(&[0, 1, 2, 6, 12, 18]), false)
+        );
+    }
+
+    #[test]
+    fn field_ranges() {
+        assert!(parse_field("59", 0, 59, "minute").is_ok());
+        assert!(parse_field("60", 0, 59, "minute").is_err());
+        assert!(parse_field("0", 1, 31, "day of month").is_err());
+        assert!(parse_field("32", 1, 31, "day of month").is_err());
+        assert!(parse_field("13", 1, 12, "month").is_err());
+        assert!(parse_field("5-50", 0, 23, "hour").is_err());
+    }
+
+    #[test]
+    fn invalid_fields() {
+        for field in [
+            "", "a", "-1", "5-", "-5", "9-3", "*/0", "*/x", "1,,2", "1-2-3", "**",
+        ] {
+            assert!(parse_field(field, 0, 59, "minute").is_err(), "{:?}", field);
+        }
+    }
+
+    #[test]
+    fn invalid_expressions() {
+        for expression in [
+            "",
+            "* * * *",
+            "* * * * * *",
+            "@often",
+            "0 24 * * *",
+            "0 0 * * 8",
+        ] {
+            assert!(Schedule::parse(expression).is_err(), "{:?}", expression);
+        }
+    }
+
+    #[test]
+    fn shorthands() {
+        assert_eq!(Schedule::parse("@daily"), Schedule::parse("0 0 * * *"));
+        assert_eq!(Schedule::parse(" @hourly "), Schedule::parse("0 * * * *"));
+        assert_eq!(Schedule::parse("@annually"), Schedule::parse("0 0 1 1 *"));
+    }
+
+    #[test]
+    fn sunday_is_0_and_7() {
+        assert_eq!(Schedule::parse("0 9 * * 7"), Schedule::parse("0 9 * * 0,7"));
+        // 2026-10-18 is a Sunday
+        assert_eq!(
+            next("0 9 * * 7", at(2026, 10, 14, 12, 0)),
+            Some(at(2026, 10, 18, 9, 0))
+        );
+    }
+
+    #[test]
+    fn next_is_strictly_after() {
+        let now = at(2026, 3, 10, 10, 30);
+        assert_eq!(next("30 10 * * *", now), Some(at(2026, 3, 11, 10, 30)));
+        assert_eq!(next("* * * * *", now), Some(at(2026, 3, 10, 10, 31)));
+        assert_eq!(next("*/20 * * * *", now), Some(at(2026, 3, 10, 10, 40)));
+        assert_eq!(next("0 9-17 * * *", now), Some(at(2026, 3, 10, 11, 0)));
+    }
+
+    #[test]
+    fn next_across_month_and_year() {
+        assert_eq!(
+            next("0 0 1 * *", at(2026, 1, 31, 23, 59)),
+            Some(at(2026, 2, 1, 0, 0))
+        );
+        assert_eq!(
+            next("0 12 31 * *", at(2026, 4, 1, 0, 0)),
+            Some(at(2026, 5, 31, 12, 0))
+        );
+        assert_eq!(
+            next("@yearly", at(2026, 12, 31, 23, 59)),
+            Some(at(2027, 1, 1, 0, 0))
+        );
+        assert_eq!(
+            next("15 8 * 2 *", at(2026, 12, 1, 0, 0)),
+            Some(at(2027, 2, 1, 8, 15))
+        );
+        // The next 29 February
+        assert_eq!(
+            next("0 0 29 2 *", at(2026, 3, 1, 0, 0)),
+            Some(at(2028, 2, 29, 0, 0))
+        );
+    }
+
+    #[test]
+    fn day_fields_combine() {
+        let now = at(2026, 10, 14, 12, 0);
+        // Either the 20th or a Friday (2026-10-16), whichever comes first
+        assert_eq!(next("0 0 20 * 5", now), Some(at(2026, 10, 16, 0, 0)));
+        // Only the restricted field counts when the other is `*`
+        assert_eq!(next("0 0 20 * *", now), Some(at(2026, 10, 20, 0, 0)));
+        assert_eq!(next("0 0 * * 5", now), Some(at(2026, 10, 16, 0, 0)));
+    }
+
+    #[test]
+    fn impossible_dates_never_run() {
+        assert_eq!(next("0 0 30 2 *", at(2026, 1, 1, 0, 0)), None);
+        assert_eq!(next("0 0 31 4 *", at(2026, 1, 1, 0, 0)), None);
+    }
+}
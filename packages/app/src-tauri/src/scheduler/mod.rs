@@ -0,0 +1,367 @@
+pub mod cron;
+
+use std::time::Duration;
+
+use chrono::Local;
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter};
+use tauri_plugin_notification::NotificationExt;
+
+use crate::error::AppError;
+use crate::history::{self, db_error, now_millis, MessageRole, NewMessage};
+use crate::server::chat;
+
+use self::cron::Schedule;
+
+// Schedules have minute resolution
+const TICK: Duration = Duration::from_secs(30);
+const NOTIFICATION_LIMIT: usize = 240;
+// Each task keeps its prompts and replies in a conversation of its own
+const CONVERSATION_PREFIX: &str = "task-";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TaskDelivery {
+    // Show the reply as a system notification
+    Notification,
+    // Save the prompt and reply to the task's conversation in the history
+    Note,
+}
+
+impl TaskDelivery {
+    fn as_str(self) -> &'static str {
+        match self {
+            TaskDelivery::Notification => "notification",
+            TaskDelivery::Note => "note",
+        }
+    }
+
+    fn parse(delivery: &str) -> Self {
+        match delivery {
+            "note" => TaskDelivery::Note,
+            _ => TaskDelivery::Notification,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ScheduledTask {
+    pub id: String,
+    pub name: String,
+    pub prompt: String,
+    // Cron expression in local time, e.g. `0 8 * * *` for every morning at 8
+    pub schedule: String,
+    pub agent_id: Option<String>,
+    pub delivery: TaskDelivery,
+    pub paused: bool,
+    // Milliseconds since the Unix epoch
+    pub created_at: u64,
+    pub next_run_at: Option<u64>,
+    pub last_run_at: Option<u64>,
+    pub last_error: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct NewTask {
+    pub name: String,
+    pub prompt: String,
+    pub schedule: String,
+    pub agent_id: Option<String>,
+    pub delivery: Option<TaskDelivery>,
+}
+
+// Payload of the `task-run` event
+#[derive(Debug, Clone, Serialize)]
+struct TaskRun {
+    task_id: String,
+    name: String,
+    ran_at: u64,
+    reply: Option<String>,
+    error: Option<String>,
+}
+
+fn task_from_row(row: &Row) -> rusqlite::Result<ScheduledTask> {
+    let delivery: String = row.get("delivery")?;
+    Ok(ScheduledTask {
+        id: row.get("id")?,
+        name: row.get("name")?,
+        prompt: row.get("prompt")?,
+        schedule: row.get("schedule")?,
+        agent_id: row.get("agent_id")?,
+        delivery: TaskDelivery::parse(&delivery),
+        paused: row.get("paused")?,
+        created_at: row.get::<_, i64>("created_at")? as u64,
+        next_run_at: row
+            .get::<_, Option<i64>>("next_run_at")?
+            .map(|at| at as u64),
+        last_run_at: row
+            .get::<_, Option<i64>>("last_run_at")?
+            .map(|at| at as u64),
+        last_error: row.get("last_error")?,
+    })
+}
+
+fn load_task(conn: &Connection, id: &str) -> Result<Option<ScheduledTask>, String> {
+    conn.query_row(
+        "SELECT * FROM scheduled_tasks WHERE id = ?1",
+        [id],
+        task_from_row,
+    )
+    .optional()
+    .map_err(db_error)
+}
+
+fn query_tasks(
+    conn: &Connection,
+    query: &str,
+    params: impl rusqlite::Params,
+) -> Result<Vec<ScheduledTask>, String> {
+    let mut statement = conn.prepare(query).map_err(db_error)?;
+    let tasks = statement
+        .query_map(params, task_from_row)
+        .map_err(db_error)?
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .map_err(db_error)?;
+    Ok(tasks)
+}
+
+fn load_tasks(conn: &Connection) -> Result<Vec<ScheduledTask>, String> {
+    query_tasks(
+        conn,
+        "SELECT * FROM scheduled_tasks ORDER BY created_at",
+        [],
+    )
+}
+
+fn due_tasks(conn: &Connection, now: u64) -> Result<Vec<ScheduledTask>, String> {
+    query_tasks(
+        conn,
+        "SELECT * FROM scheduled_tasks
+            WHERE paused = 0 AND next_run_at IS NOT NULL AND next_run_at <= ?1
+            ORDER BY next_run_at",
+        [now as i64],
+    )
+}
+
+// When `schedule` next fires after now
+fn next_run(schedule: &str) -> Result<u64, String> {
+    Schedule::parse(schedule)?
+        .next_after(Local::now())
+        .map(|time| time.timestamp_millis() as u64)
+        .ok_or_else(|| format!("The schedule {} never runs", schedule))
+}
+
+fn notify(app: &AppHandle, title: &str, text: &str) {
+    let mut body = text.to_string();
+    if body.chars().count() > NOTIFICATION_LIMIT {
+        body = body.chars().take(NOTIFICATION_LIMIT).collect::<String>() + "…";
+    }
+    if let Err(e) = app.notification().builder().title(title).body(body).show() {
+        tracing::warn!("Failed to show notification: {}", e);
+    }
+}
+
+async fn save_note(task: &ScheduledTask, asked_at: u64, reply: &str) -> Result<(), String> {
+    let conversation_id = format!("{}{}", CONVERSATION_PREFIX, task.id);
+    let message = |role, content: &str, created_at| NewMessage {
+        id: None,
+        conversation_id: conversation_id.clone(),
+        role,
+        sender: None,
+        content: content.to_string(),
+        created_at: Some(created_at),
+        metadata: None,
+        title: Some(task.name.clone()),
+        agent_id: task.agent_id.clone(),
+    };
+    let asked = message(MessageRole::User, &task.prompt, asked_at);
+    let answered = message(MessageRole::Agent, reply, now_millis());
+    history::with_db(move |conn| {
+        history::insert_message(conn, asked)?;
+        history::insert_message(conn, answered)?;
+        Ok(())
+    })
+    .await
+}
+
+async fn execute(app: &AppHandle, task: &ScheduledTask) -> Result<String, String> {
+    let asked_at = now_millis();
+    let conversation_id = format!("{}{}", CONVERSATION_PREFIX, task.id);
+    let response =
+        chat::send_message(task.agent_id.clone(), &conversation_id, &task.prompt).await?;
+    let reply = chat::reply_text(&response).ok_or("The agent did not reply")?;
+    match task.delivery {
+        TaskDelivery::Notification => notify(app, &task.name, &reply),
+        TaskDelivery::Note => save_note(task, asked_at, &reply).await?,
+    }
+    Ok(reply)
+}
+
+// Run every task that is due. A task missed while the app was closed or the machine asleep
+// runs once, then continues on its schedule.
+async fn run_due(app: &AppHandle) -> Result<(), String> {
+    let now = now_millis();
+    for task in history::with_db(move |conn| due_tasks(conn, now)).await? {
+        // Move the task on before running it so a slow reply can't make it fire twice
+        let next = next_run(&task.schedule).ok();
+        let id = task.id.clone();
+        history::with_db(move |conn| {
+            conn.execute(
+                "UPDATE scheduled_tasks SET next_run_at = ?2 WHERE id = ?1",
+                params![id, next.map(|at| at as i64)],
+            )
+            .map_err(db_error)
+        })
+        .await?;
+
+        tracing::info!(task = %task.id, "Running scheduled task");
+        let ran_at = now_millis();
+        let result = execute(app, &task).await;
+        if let Err(e) = &result {
+            tracing::warn!(task = %task.id, "Scheduled task failed: {}", e);
+        }
+        let (id, error) = (task.id.clone(), result.as_ref().err().cloned());
+        history::with_db(move |conn| {
+            conn.execute(
+                "UPDATE scheduled_tasks SET last_run_at = ?2, last_error = ?3 WHERE id = ?1",
+                params![id, ran_at as i64, error],
+            )
+            .map_err(db_error)
+        })
+        .await?;
+
+        let (reply, error) = match result {
+            Ok(reply) => (Some(reply), None),
+            Err(e) => (None, Some(e)),
+        };
+        let run = TaskRun {
+            task_id: task.id,
+            name: task.name,
+            ran_at,
+            reply,
+            error,
+        };
+        if let Err(e) = app.emit("task-run", run) {
+            tracing::warn!("Failed to emit task run: {}", e);
+        }
+    }
+    Ok(())
+}
+
+// Check for due tasks for as long as the app runs; called once from the setup hook
+pub fn spawn(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            if let Err(e) = run_due(&app).await {
+                tracing::warn!("Failed to run scheduled tasks: {}", e);
+            }
+            tokio::time::sleep(TICK).await;
+        }
+    });
+}
+
+async fn set_paused(id: String, paused: bool) -> Result<ScheduledTask, AppError> {
+    history::with_db(move |conn| {
+        let task = load_task(conn, &id)?.ok_or_else(|| format!("Unknown task: {}", id))?;
+        // A resumed task picks up from now rather than catching up on what it missed
+        let next = if paused {
+            task.next_run_at
+        } else {
+            next_run(&task.schedule).ok()
+        };
+        conn.execute(
+            "UPDATE scheduled_tasks SET paused = ?2, next_run_at = ?3 WHERE id = ?1",
+            params![id, paused, next.map(|at| at as i64)],
+        )
+        .map_err(db_error)?;
+        load_task(conn, &id)?.ok_or_else(|| format!("Unknown task: {}", id))
+    })
+    .await
+    .map_err(|e| {
+        if e.starts_with("Unknown task") {
+            AppError::NotFound(e)
+        } else {
+            AppError::Database(e)
+        }
+    })
+}
+
+#[tauri::command]
+pub async fn create_task(task: NewTask) -> Result<ScheduledTask, AppError> {
+    let name = task.name.trim().to_string();
+    let prompt = task.prompt.trim().to_string();
+    if name.is_empty() || prompt.is_empty() {
+        return Err(AppError::Validation(
+            "A task needs a name and a prompt".to_string(),
+        ));
+    }
+    let schedule = task.schedule.trim().to_string();
+    let next = next_run(&schedule).map_err(AppError::Validation)?;
+
+    let task = ScheduledTask {
+        id: history::new_id(),
+        name,
+        prompt,
+        schedule,
+        agent_id: task.agent_id.filter(|agent| !agent.is_empty()),
+        delivery: task.delivery.unwrap_or(TaskDelivery::Notification),
+        paused: false,
+        created_at: now_millis(),
+        next_run_at: Some(next),
+        last_run_at: None,
+        last_error: None,
+    };
+    let saved = task.clone();
+    history::with_db(move |conn| {
+        conn.execute(
+            "INSERT INTO scheduled_tasks
+                (id, name, prompt, schedule, agent_id, delivery, paused, created_at, next_run_at)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, 0, ?7, ?8)",
+            params![
+                saved.id,
+                saved.name,
+                saved.prompt,
+                saved.schedule,
+                saved.agent_id,
+                saved.delivery.as_str(),
+                saved.created_at as i64,
+                next as i64
+            ],
+        )
+        .map_err(db_error)
+    })
+    .await
+    .map_err(AppError::Database)?;
+    tracing::info!(task = %task.id, schedule = %task.schedule, "Created scheduled task");
+    Ok(task)
+}
+
+#[tauri::command]
+pub async fn list_tasks() -> Result<Vec<ScheduledTask>, AppError> {
+    history::with_db(|conn| load_tasks(conn))
+        .await
+        .map_err(AppError::Database)
+}
+
+#[tauri::command]
+pub async fn pause_task(task_id: String) -> Result<ScheduledTask, AppError> {
+    set_paused(task_id, true).await
+}
+
+#[tauri::command]
+pub async fn resume_task(task_id: String) -> Result<ScheduledTask, AppError> {
+    set_paused(task_id, false).await
+}
+
+// Remove a task; the notes it saved stay in the history
+#[tauri::command]
+pub async fn delete_task(task_id: String) -> Result<(), AppError> {
+    history::with_db(move |conn| {
+        conn.execute("DELETE FROM scheduled_tasks WHERE id = ?1", [task_id])
+            .map_err(db_error)?;
+        Ok(())
+    })
+    .await
+    .map_err(AppError::Database)
+}
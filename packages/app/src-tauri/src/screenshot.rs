@@ -0,0 +1,198 @@
+use std::path::PathBuf;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+use xcap::image::RgbaImage;
+use xcap::{Monitor, Window};
+
+use crate::error::AppError;
+
+const SCREENSHOTS_DIR: &str = "screenshots";
+// Time for the compositor to remove our windows before the capture
+const HIDE_DELAY: Duration = Duration::from_millis(250);
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "mode", rename_all = "lowercase")]
+pub enum CaptureMode {
+    // Defaults to the monitor under the cursor
+    Screen {
+        monitor: Option<u32>,
+    },
+    // Defaults to the frontmost window of another app
+    Window {
+        window: Option<u32>,
+    },
+    // In global screen coordinates
+    Region {
+        x: i32,
+        y: i32,
+        width: u32,
+        height: u32,
+    },
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Screenshot {
+    pub path: PathBuf,
+    pub width: u32,
+    pub height: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CaptureWindow {
+    pub id: u32,
+    pub title: String,
+    pub app_name: String,
+}
+
+fn capture_error(e: xcap::XCapError) -> String {
+    format!("Screen capture failed: {}", e)
+}
+
+// Windows of other apps that can be captured, frontmost first
+fn other_windows() -> Result<Vec<Window>, String> {
+    let own_pid = std::process::id();
+    let mut windows: Vec<Window> = Window::all()
+        .map_err(capture_error)?
+        .into_iter()
+        .filter(|window| {
+            window.pid().is_ok_and(|pid| pid != own_pid)
+                && !window.is_minimized().unwrap_or(true)
+                && window.width().unwrap_or(0) > 0
+        })
+        .collect();
+    windows.sort_by_key(|window| std::cmp::Reverse(window.z().unwrap_or(0)));
+    Ok(windows)
+}
+
+fn cursor_monitor(app: &AppHandle) -> Result<Monitor, String> {
+    if let Ok(cursor) = app.cursor_position() {
+        if let Ok(monitor) = Monitor::from_point(cursor.x as i32, cursor.y as i32) {
+            return Ok(monitor);
+        }
+    }
+    let monitors = Monitor::all().map_err(capture_error)?;
+    monitors
+        .iter()
+        .position(|monitor| monitor.is_primary().unwrap_or(false))
+        .or(if monitors.is_empty() { None } else { Some(0) })
+        .map(|index| monitors[index].clone())
+        .ok_or_else(|| "No monitor is available".to_string())
+}
+
+fn capture(app: &AppHandle, mode: &CaptureMode) -> Result<RgbaImage, String> {
+    match *mode {
+        CaptureMode::Screen { monitor: None } => {
+            cursor_monitor(app)?.capture_image().map_err(capture_error)
+        }
+        CaptureMode::Screen { monitor: Some(id) } => Monitor::all()
+            .map_err(capture_error)?
+            .into_iter()
+            .find(|monitor| monitor.id().is_ok_and(|m| m == id))
+            .ok_or_else(|| format!("Unknown monitor: {}", id))?
+            .capture_image()
+            .map_err(capture_error),
+        CaptureMode::Window { window } => other_windows()?
+            .into_iter()
+            .find(|candidate| window.is_none_or(|id| candidate.id().is_ok_and(|w| w == id)))
+            .ok_or("No window to capture")?
+            .capture_image()
+            .map_err(capture_error),
+        CaptureMode::Region {
+            x,
+            y,
+            width,
+            height,
+        } => {
+            let monitor = Monitor::from_point(x, y).map_err(capture_error)?;
+            let (left, top) = (
+                (x - monitor.x().map_err(capture_error)?).max(0) as u32,
+                (y - monitor.y().map_err(capture_error)?).max(0) as u32,
+            );
+            // Regions spanning monitors are cut off at the edge of the one they start on
+            let width = width.min(monitor.width().map_err(capture_error)?.saturating_sub(left));
+            let height = height.min(monitor.height().map_err(capture_error)?.saturating_sub(top));
+            if width == 0 || height == 0 {
+                return Err("The capture region is empty".to_string());
+            }
+            monitor
+                .capture_region(left, top, width, height)
+                .map_err(capture_error)
+        }
+    }
+}
+
+// Keep our own windows out of screen and region captures
+fn with_app_hidden<T>(app: &AppHandle, f: impl FnOnce() -> T) -> T {
+    let hidden: Vec<_> = app
+        .webview_windows()
+        .into_values()
+        .filter(|window| window.is_visible().unwrap_or(false))
+        .filter(|window| window.hide().is_ok())
+        .collect();
+    if !hidden.is_empty() {
+        thread::sleep(HIDE_DELAY);
+    }
+    let result = f();
+    for window in hidden {
+        let _ = window.show();
+    }
+    result
+}
+
+fn save(app: &AppHandle, image: &RgbaImage) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_cache_dir()
+        .map_err(|e| format!("Failed to resolve app cache dir: {}", e))?
+        .join(SCREENSHOTS_DIR);
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or_default();
+    let path = dir.join(format!("screenshot-{}.png", timestamp));
+    image
+        .save(&path)
+        .map_err(|e| format!("Failed to save {}: {}", path.display(), e))?;
+    Ok(path)
+}
+
+// Capture the screen, another app's window or a region to a PNG in the cache dir
+#[tauri::command]
+pub async fn capture_screenshot(app: AppHandle, mode: CaptureMode) -> Result<Screenshot, AppError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let image = match mode {
+            CaptureMode::Window { .. } => capture(&app, &mode)?,
+            _ => with_app_hidden(&app, || capture(&app, &mode))?,
+        };
+        Ok(Screenshot {
+            path: save(&app, &image)?,
+            width: image.width(),
+            height: image.height(),
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+pub async fn list_capture_windows() -> Result<Vec<CaptureWindow>, AppError> {
+    tauri::async_runtime::spawn_blocking(|| {
+        Ok(other_windows()?
+            .into_iter()
+            .filter_map(|window| {
+                Some(CaptureWindow {
+                    id: window.id().ok()?,
+                    title: window.title().unwrap_or_default(),
+                    app_name: window.app_name().unwrap_or_default(),
+                })
+            })
+            .collect())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
@@ -0,0 +1,104 @@
+use serde::Serialize;
+use tauri::AppHandle;
+
+use crate::auth::secure_store;
+use crate::auth::verification::{self, Action};
+use crate::capabilities::{self, Capability};
+use crate::error::AppError;
+
+const API_KEY_PREFIX: &str = "api-key:";
+
+// Model providers we can store keys for, and the env var elizaOS reads each key from
+const PROVIDERS: &[(&str, &str)] = &[
+    ("openai", "OPENAI_API_KEY"),
+    ("anthropic", "ANTHROPIC_API_KEY"),
+    ("google", "GOOGLE_GENERATIVE_AI_API_KEY"),
+    ("groq", "GROQ_API_KEY"),
+    ("openrouter", "OPENROUTER_API_KEY"),
+    ("together", "TOGETHER_API_KEY"),
+    ("mistral", "MISTRAL_API_KEY"),
+];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProviderInfo {
+    pub provider: &'static str,
+    pub env_var: &'static str,
+    pub configured: bool,
+}
+
+fn env_var(provider: &str) -> Result<&'static str, String> {
+    PROVIDERS
+        .iter()
+        .find(|(name, _)| *name == provider)
+        .map(|(_, var)| *var)
+        .ok_or_else(|| format!("Unknown provider: {}", provider))
+}
+
+fn account(provider: &str) -> String {
+    format!("{}{}", API_KEY_PREFIX, provider)
+}
+
+pub(crate) fn load_key(provider: &str) -> Result<Option<String>, String> {
+    secure_store::read_blocking(&account(provider))
+        .map_err(|e| format!("Failed to read {} API key: {}", provider, e))
+}
+
+// Environment for the spawned server, one variable per stored provider key
+pub fn provider_env() -> Vec<(&'static str, String)> {
+    PROVIDERS
+        .iter()
+        .filter_map(|(provider, var)| match load_key(provider) {
+            Ok(key) => key.map(|key| (*var, key)),
+            Err(e) => {
+                tracing::warn!("{}", e);
+                None
+            }
+        })
+        .collect()
+}
+
+#[tauri::command]
+pub async fn set_api_key(provider: String, key: String) -> Result<(), AppError> {
+    env_var(&provider).map_err(AppError::NotFound)?;
+    let key = key.trim();
+    if key.is_empty() {
+        return Err(AppError::Validation(
+            "API key must not be empty".to_string(),
+        ));
+    }
+    secure_store::write(&account(&provider), key)
+        .await
+        .map_err(|e| AppError::Keychain(format!("Failed to store {} API key: {}", provider, e)))
+}
+
+#[tauri::command]
+pub async fn get_api_key(app: AppHandle, provider: String) -> Result<Option<String>, AppError> {
+    capabilities::require(Capability::ReadSecrets)?;
+    env_var(&provider).map_err(AppError::NotFound)?;
+    verification::verify(&app, Action::RevealApiKey)
+        .await
+        .map_err(AppError::Auth)?;
+    secure_store::read(&account(&provider))
+        .await
+        .map_err(|e| AppError::Keychain(format!("Failed to read {} API key: {}", provider, e)))
+}
+
+#[tauri::command]
+pub fn list_providers() -> Vec<ProviderInfo> {
+    PROVIDERS
+        .iter()
+        .map(|(provider, env_var)| ProviderInfo {
+            provider,
+            env_var,
+            configured: matches!(load_key(provider), Ok(Some(_))),
+        })
+        .collect()
+}
+
+#[tauri::command]
+pub async fn delete_api_key(provider: String) -> Result<(), AppError> {
+    env_var(&provider).map_err(AppError::NotFound)?;
+    secure_store::delete(&account(&provider))
+        .await
+        .map_err(|e| AppError::Keychain(format!("Failed to delete {} API key: {}", provider, e)))
+}
@@ -0,0 +1,495 @@
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::sync::Mutex;
+
+use once_cell::sync::Lazy;
+use rand::RngCore;
+use serde::Serialize;
+use serde_json::{json, Value};
+use tauri::async_runtime::JoinHandle;
+use tauri::{AppHandle, Emitter};
+
+use super::health;
+use super::manager::DEFAULT_INSTANCE;
+use super::prometheus::StreamTimer;
+use super::proxy::AUTH_HEADER;
+use crate::error::AppError;
+
+// Instances one prompt can be broadcast to
+const MAX_BROADCAST: usize = 8;
+
+// Streams started by `stream_chat` and `broadcast_prompt`, keyed by request id, so they can
+// be cancelled
+static STREAMS: Lazy<Mutex<HashMap<String, ActiveStream>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+struct ActiveStream {
+    conversation_id: String,
+    broadcast: Option<BroadcastTag>,
+    handle: JoinHandle<()>,
+}
+
+// Which broadcast a stream belongs to, and the instance answering it
+#[derive(Debug, Clone)]
+struct BroadcastTag {
+    broadcast_id: String,
+    instance_id: String,
+}
+
+// Payload of the `chat-token` event
+#[derive(Debug, Clone, Serialize)]
+struct ChatToken<'a> {
+    request_id: &'a str,
+    conversation_id: &'a str,
+    token: &'a str,
+}
+
+// Payload of the `chat-done` event, sent once per stream however it ends
+#[derive(Debug, Clone, Serialize)]
+struct ChatDone<'a> {
+    request_id: &'a str,
+    conversation_id: &'a str,
+    cancelled: bool,
+    error: Option<String>,
+}
+
+// Payload of the `broadcast-token` event
+#[derive(Debug, Clone, Serialize)]
+struct BroadcastToken<'a> {
+    broadcast_id: &'a str,
+    instance_id: &'a str,
+    request_id: &'a str,
+    token: &'a str,
+}
+
+// Payload of the `broadcast-done` event, sent once per instance however its stream ends
+#[derive(Debug, Clone, Serialize)]
+struct BroadcastDone<'a> {
+    broadcast_id: &'a str,
+    instance_id: &'a str,
+    request_id: &'a str,
+    cancelled: bool,
+    error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BroadcastStream {
+    pub instance_id: String,
+    // For `cancel_stream`
+    pub request_id: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Broadcast {
+    pub broadcast_id: String,
+    pub conversation_id: String,
+    pub streams: Vec<BroadcastStream>,
+}
+
+fn new_id() -> String {
+    let mut bytes = [0u8; 16];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+// Add the `x-api-key` the server at `base` expects
+pub async fn authorized(base: &str, request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+    match super::remote::auth_token_for(base).await {
+        Ok(Some(token)) => request.header(AUTH_HEADER, token),
+        Ok(None) => request,
+        Err(e) => {
+            tracing::warn!("{}", e);
+            request
+        }
+    }
+}
+
+// The first agent the server reports, used when the caller doesn't pick one
+pub async fn default_agent(client: &reqwest::Client) -> Result<String, String> {
+    first_agent(client, &health::base_url()).await
+}
+
+async fn first_agent(client: &reqwest::Client, base: &str) -> Result<String, String> {
+    let url = format!("{}/api/agents", base);
+    let body: Value = authorized(base, client.get(&url))
+        .await
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| format!("Failed to list agents: {}", e))?
+        .json()
+        .await
+        .map_err(|e| format!("Invalid agent list: {}", e))?;
+    body.pointer("/data/agents/0/id")
+        .or_else(|| body.pointer("/agents/0/id"))
+        .and_then(Value::as_str)
+        .map(str::to_string)
+        .ok_or_else(|| "The server has no agents".to_string())
+}
+
+async fn message_url(
+    client: &reqwest::Client,
+    base: &str,
+    agent_id: Option<String>,
+) -> Result<String, String> {
+    let agent_id = match agent_id {
+        Some(id) => id,
+        None => first_agent(client, base).await?,
+    };
+    Ok(format!("{}/api/agents/{}/message", base, agent_id))
+}
+
+// Send `message` without streaming and return the server's reply
+pub async fn send_message(
+    agent_id: Option<String>,
+    conversation_id: &str,
+    message: &str,
+) -> Result<Value, String> {
+    super::idle::wake().await?;
+    let client = reqwest::Client::new();
+    let base = health::base_url();
+    let url = message_url(&client, &base, agent_id).await?;
+    let response = authorized(&base, client.post(&url))
+        .await
+        .json(&json!({ "text": message, "roomId": conversation_id }))
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| format!("Chat request failed: {}", e))?;
+    Ok(response.json().await.unwrap_or(Value::Null))
+}
+
+// Tell the server about a conversation the app created itself, e.g. a fork, so the room
+// exists before its first message
+pub async fn create_room(
+    agent_id: Option<String>,
+    room_id: &str,
+    name: &str,
+    metadata: Value,
+) -> Result<(), String> {
+    let client = reqwest::Client::new();
+    let base = health::base_url();
+    let agent_id = match agent_id {
+        Some(id) => id,
+        None => first_agent(&client, &base).await?,
+    };
+    authorized(
+        &base,
+        client.post(format!("{}/api/agents/{}/rooms", base, agent_id)),
+    )
+    .await
+    .json(&json!({ "id": room_id, "name": name, "metadata": metadata }))
+    .send()
+    .await
+    .and_then(|r| r.error_for_status())
+    .map_err(|e| format!("Failed to create the room on the server: {}", e))?;
+    Ok(())
+}
+
+// The agent's reply text, whichever of the shapes elizaOS servers answer with
+pub fn reply_text(response: &Value) -> Option<String> {
+    let reply = match response {
+        Value::Array(messages) => messages.first()?,
+        Value::Object(object) => object.get("data").unwrap_or(response),
+        _ => response,
+    };
+    ["/text", "/message/text", "/content/text", "/0/text"]
+        .iter()
+        .find_map(|pointer| reply.pointer(pointer).and_then(Value::as_str))
+        .map(str::to_string)
+}
+
+// Text carried by one SSE `data:` payload; plain strings are passed through
+fn token_text(data: &str) -> Option<String> {
+    match serde_json::from_str::<Value>(data) {
+        Ok(Value::Object(object)) => ["token", "text", "content", "delta"]
+            .iter()
+            .find_map(|key| object.get(*key).and_then(Value::as_str))
+            .map(str::to_string),
+        Ok(Value::String(text)) => Some(text),
+        _ => Some(data.to_string()),
+    }
+}
+
+// Stream the reply of the server at `base`, handing each token to `on_token`
+async fn stream_reply(
+    base: &str,
+    conversation_id: &str,
+    agent_id: Option<String>,
+    message: String,
+    mut on_token: impl FnMut(&str),
+) -> Result<(), String> {
+    let client = reqwest::Client::new();
+    let url = message_url(&client, base, agent_id).await?;
+    let mut response = authorized(base, client.post(&url))
+        .await
+        .header(reqwest::header::ACCEPT, "text/event-stream")
+        .json(&json!({ "text": message, "roomId": conversation_id, "stream": true }))
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| format!("Chat request failed: {}", e))?;
+
+    let mut timer = StreamTimer::start();
+    let mut emit_token = |token: &str| {
+        timer.token();
+        on_token(token);
+    };
+
+    let streaming = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("text/event-stream"));
+    if !streaming {
+        // Servers without streaming support answer with the whole reply at once
+        let body = response.text().await.map_err(|e| e.to_string())?;
+        if let Some(text) = token_text(&body) {
+            emit_token(&text);
+        }
+        return Ok(());
+    }
+
+    let mut buffer = Vec::new();
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .map_err(|e| format!("Chat stream interrupted: {}", e))?
+    {
+        buffer.extend_from_slice(&chunk);
+        while let Some(end) = buffer.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = buffer.drain(..=end).collect();
+            let line = String::from_utf8_lossy(&line);
+            let Some(data) = line.trim_end().strip_prefix("data:") else {
+                continue;
+            };
+            let data = data.strip_prefix(' ').unwrap_or(data);
+            if data == "[DONE]" {
+                return Ok(());
+            }
+            if let Some(text) = token_text(data) {
+                emit_token(&text);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn emit_done(
+    app: &AppHandle,
+    request_id: &str,
+    conversation_id: &str,
+    broadcast: Option<&BroadcastTag>,
+    cancelled: bool,
+    error: Option<String>,
+) {
+    let emitted = match broadcast {
+        Some(tag) => app.emit(
+            "broadcast-done",
+            BroadcastDone {
+                broadcast_id: &tag.broadcast_id,
+                instance_id: &tag.instance_id,
+                request_id,
+                cancelled,
+                error,
+            },
+        ),
+        None => app.emit(
+            "chat-done",
+            ChatDone {
+                request_id,
+                conversation_id,
+                cancelled,
+                error,
+            },
+        ),
+    };
+    if let Err(e) = emitted {
+        tracing::warn!("Failed to report the end of a chat stream: {}", e);
+    }
+}
+
+// Run `stream` as a stream `cancel_stream` can stop, reporting its end once it's over
+fn spawn_stream(
+    app: AppHandle,
+    request_id: String,
+    conversation_id: String,
+    broadcast: Option<BroadcastTag>,
+    stream: impl Future<Output = Result<(), String>> + Send + 'static,
+) {
+    let (id, conversation, tag) = (
+        request_id.clone(),
+        conversation_id.clone(),
+        broadcast.clone(),
+    );
+    let mut streams = STREAMS.lock().unwrap();
+    let handle = tauri::async_runtime::spawn(async move {
+        let result = stream.await;
+        if STREAMS.lock().unwrap().remove(&id).is_none() {
+            // Cancelled; `cancel_stream` has already reported it
+            return;
+        }
+        if let Err(e) = &result {
+            tracing::warn!(request_id = %id, "{}", e);
+        }
+        emit_done(
+            &app,
+            &id,
+            &conversation_id,
+            broadcast.as_ref(),
+            false,
+            result.err(),
+        );
+    });
+    // Held across the spawn so a fast stream can't finish before it is registered
+    streams.insert(
+        request_id,
+        ActiveStream {
+            conversation_id: conversation,
+            broadcast: tag,
+            handle,
+        },
+    );
+}
+
+// Send `message` to an agent and forward its reply as `chat-token` events; returns the request id
+#[tauri::command]
+pub fn stream_chat(
+    app: AppHandle,
+    message: String,
+    conversation_id: String,
+    agent_id: Option<String>,
+) -> String {
+    let request_id = new_id();
+    let (handle, id, conversation) = (app.clone(), request_id.clone(), conversation_id.clone());
+    let stream = async move {
+        super::idle::wake().await?;
+        stream_reply(
+            &health::base_url(),
+            &conversation,
+            agent_id,
+            message,
+            |token| {
+                let payload = ChatToken {
+                    request_id: &id,
+                    conversation_id: &conversation,
+                    token,
+                };
+                if let Err(e) = handle.emit("chat-token", payload) {
+                    tracing::warn!("Failed to emit chat-token: {}", e);
+                }
+            },
+        )
+        .await
+    };
+    spawn_stream(app, request_id.clone(), conversation_id, None, stream);
+    request_id
+}
+
+// Send the same `message` to several running instances at once, e.g. to compare their
+// characters or models side by side. `instance_ids` are as `list_instances`
+// reports them; each reply streams back as `broadcast-token` events tagged with its id,
+// and any one of them can be stopped with `cancel_stream`.
+#[tauri::command]
+pub async fn broadcast_prompt(
+    app: AppHandle,
+    instance_ids: Vec<String>,
+    message: String,
+    conversation_id: Option<String>,
+) -> Result<Broadcast, AppError> {
+    let mut instance_ids = instance_ids;
+    let mut seen = HashSet::new();
+    instance_ids.retain(|id| seen.insert(id.clone()));
+    if instance_ids.is_empty() {
+        return Err(AppError::Validation(
+            "Pick at least one agent to send the prompt to".to_string(),
+        ));
+    }
+    if instance_ids.len() > MAX_BROADCAST {
+        return Err(AppError::Validation(format!(
+            "A prompt can be sent to at most {} agents at once",
+            MAX_BROADCAST
+        )));
+    }
+    if message.trim().is_empty() {
+        return Err(AppError::Validation("The prompt is empty".to_string()));
+    }
+    // Refuse the whole broadcast up front rather than failing some of its streams
+    let mut targets = Vec::new();
+    for instance_id in instance_ids {
+        let base = super::instances::base_url(&app, &instance_id).map_err(AppError::NotFound)?;
+        targets.push((instance_id, base));
+    }
+    if targets.iter().any(|(id, _)| id == DEFAULT_INSTANCE) {
+        super::idle::wake().await?;
+    }
+
+    let broadcast_id = new_id();
+    // The instances are separate servers, so they can share a room id
+    let conversation_id = conversation_id.unwrap_or_else(|| broadcast_id.clone());
+    let mut streams = Vec::new();
+    for (instance_id, base) in targets {
+        let request_id = new_id();
+        let tag = BroadcastTag {
+            broadcast_id: broadcast_id.clone(),
+            instance_id: instance_id.clone(),
+        };
+        let (handle, id, conversation, message, token_tag) = (
+            app.clone(),
+            request_id.clone(),
+            conversation_id.clone(),
+            message.clone(),
+            tag.clone(),
+        );
+        let stream = async move {
+            stream_reply(&base, &conversation, None, message, |token| {
+                let payload = BroadcastToken {
+                    broadcast_id: &token_tag.broadcast_id,
+                    instance_id: &token_tag.instance_id,
+                    request_id: &id,
+                    token,
+                };
+                if let Err(e) = handle.emit("broadcast-token", payload) {
+                    tracing::warn!("Failed to emit broadcast-token: {}", e);
+                }
+            })
+            .await
+        };
+        spawn_stream(
+            app.clone(),
+            request_id.clone(),
+            conversation_id.clone(),
+            Some(tag),
+            stream,
+        );
+        streams.push(BroadcastStream {
+            instance_id,
+            request_id,
+        });
+    }
+    tracing::info!(broadcast_id = %broadcast_id, agents = streams.len(), "Broadcasting a prompt");
+    Ok(Broadcast {
+        broadcast_id,
+        conversation_id,
+        streams,
+    })
+}
+
+#[tauri::command]
+pub fn cancel_stream(app: AppHandle, request_id: String) -> Result<(), AppError> {
+    let stream = STREAMS
+        .lock()
+        .unwrap()
+        .remove(&request_id)
+        .ok_or_else(|| format!("No active stream {}", request_id))?;
+    stream.handle.abort();
+    emit_done(
+        &app,
+        &request_id,
+        &stream.conversation_id,
+        stream.broadcast.as_ref(),
+        true,
+        None,
+    );
+    Ok(())
+}
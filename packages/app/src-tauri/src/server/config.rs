@@ -0,0 +1,282 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+use super::container::{ContainerConfig, ServerBackend};
+use super::poller::HealthPollConfig;
+use super::profiles::ServerProfile;
+use super::remote::RemoteServer;
+use super::sidecar::ServerRuntime;
+use crate::capabilities::{self, Capability};
+use crate::error::AppError;
+use crate::settings;
+
+const LEGACY_CONFIG_FILE: &str = "server.json";
+// Arguments are passed to the process directly, but anything a shell would interpret is
+// refused so a copied command line can't smuggle in a second command. Backslashes are
+// allowed for Windows paths.
+const SHELL_METACHARACTERS: &[char] = &[
+    ';', '&', '|', '$', '`', '<', '>', '(', ')', '{', '}', '!', '*', '?', '"', '\'', '\n', '\r',
+];
+// Set by the app itself for every spawn
+const MANAGED_ARGS: &[&str] = &["start", "--port", "--character"];
+const MANAGED_ENV: &[&str] = &["PATH", "KNOWLEDGE_PATH", "PGLITE_DATA_DIR"];
+
+// Connection settings for the elizaOS server, stored in the `server` section of the settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ServerConfig {
+    pub host: String,
+    pub port: u16,
+    // How long to wait for a graceful exit before killing the server
+    pub shutdown_timeout_ms: u64,
+    // How long to wait for the health endpoint after spawning before giving up
+    pub startup_timeout_ms: u64,
+    // How often and how patiently the server's health is checked in the background
+    pub health_poll: HealthPollConfig,
+    // Character files passed to `elizaos start`, remembered across launches
+    pub characters: Vec<String>,
+    // Hide the window on close and keep the server running in the tray
+    pub minimize_to_tray: bool,
+    // Resolved elizaos binary, recorded after installation
+    pub cli_path: Option<PathBuf>,
+    // Run the bundled server or a CLI; builds without the sidecar always use a CLI
+    pub runtime: ServerRuntime,
+    // Run the server as a child process or in a Docker/Podman container
+    pub backend: ServerBackend,
+    pub container: ContainerConfig,
+    // Self-hosted servers the app can connect to instead of running its own
+    pub remote_servers: Vec<RemoteServer>,
+    // While set, the app spawns nothing and talks to this remote server
+    pub active_remote: Option<String>,
+    // Saved combinations of the above, switched between with `activate_profile`
+    pub profiles: Vec<ServerProfile>,
+    // The last profile activated; cleared when the settings are changed by hand
+    pub active_profile: Option<String>,
+    // Move to a free port when something other than elizaOS holds the configured one
+    pub auto_select_port: bool,
+    // Appended to `elizaos start`, e.g. `--dev`
+    pub extra_args: Vec<String>,
+    // Set on the server process, e.g. LOG_LEVEL or NODE_OPTIONS
+    pub extra_env: BTreeMap<String, String>,
+    // Stop an adopted external server when the app exits; we never kill one otherwise
+    pub stop_external_on_exit: bool,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            host: "127.0.0.1".to_string(),
+            port: 3000,
+            shutdown_timeout_ms: 10_000,
+            startup_timeout_ms: 60_000,
+            health_poll: HealthPollConfig::default(),
+            characters: Vec::new(),
+            minimize_to_tray: false,
+            cli_path: None,
+            runtime: ServerRuntime::default(),
+            backend: ServerBackend::default(),
+            container: ContainerConfig::default(),
+            remote_servers: Vec::new(),
+            active_remote: None,
+            profiles: Vec::new(),
+            active_profile: None,
+            auto_select_port: true,
+            extra_args: Vec::new(),
+            extra_env: BTreeMap::new(),
+            stop_external_on_exit: false,
+        }
+    }
+}
+
+impl ServerConfig {
+    pub fn address(&self) -> String {
+        format!("{}:{}", self.host, self.port)
+    }
+}
+
+// Where the server config lived before it moved into the settings store
+pub fn legacy_path(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_config_dir()
+        .map(|dir| dir.join(LEGACY_CONFIG_FILE))
+        .map_err(|e| format!("Failed to resolve app config dir: {}", e))
+}
+
+pub fn current() -> ServerConfig {
+    settings::current().server
+}
+
+// Apply a change to the server section of the settings and persist it
+pub fn update(app: &AppHandle, f: impl FnOnce(&mut ServerConfig)) -> Result<ServerConfig, String> {
+    settings::update(app, |settings| f(&mut settings.server)).map(|settings| settings.server)
+}
+
+#[tauri::command]
+pub fn get_server_config() -> ServerConfig {
+    current()
+}
+
+// The new port takes effect the next time the server is started
+#[tauri::command]
+pub fn set_server_port(app: AppHandle, port: u16) -> Result<ServerConfig, AppError> {
+    if port == 0 {
+        return Err(AppError::Validation(
+            "Port must be between 1 and 65535".to_string(),
+        ));
+    }
+    Ok(update(&app, |config| config.port = port)?)
+}
+
+#[tauri::command]
+pub fn set_shutdown_timeout(app: AppHandle, timeout_ms: u64) -> Result<ServerConfig, AppError> {
+    Ok(update(&app, |config| {
+        config.shutdown_timeout_ms = timeout_ms
+    })?)
+}
+
+#[tauri::command]
+pub fn set_minimize_to_tray(app: AppHandle, enabled: bool) -> Result<ServerConfig, AppError> {
+    Ok(update(&app, |config| config.minimize_to_tray = enabled)?)
+}
+
+#[tauri::command]
+pub fn set_auto_select_port(app: AppHandle, enabled: bool) -> Result<ServerConfig, AppError> {
+    Ok(update(&app, |config| config.auto_select_port = enabled)?)
+}
+
+fn validate_arg(arg: &str) -> Result<(), String> {
+    if arg.is_empty() || arg.contains(SHELL_METACHARACTERS) {
+        return Err(format!("Invalid argument: {:?}", arg));
+    }
+    let flag = arg.split('=').next().unwrap_or(arg);
+    if MANAGED_ARGS.contains(&flag) {
+        return Err(format!("{} is set by the app", flag));
+    }
+    Ok(())
+}
+
+fn validate_env(key: &str, value: &str) -> Result<(), String> {
+    let mut chars = key.chars();
+    let valid_key = chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !valid_key {
+        return Err(format!("Invalid environment variable name: {}", key));
+    }
+    // Windows names are case-insensitive, so `Path` is the same variable
+    if MANAGED_ENV
+        .iter()
+        .any(|name| name.eq_ignore_ascii_case(key))
+    {
+        return Err(format!("{} is set by the app", key));
+    }
+    if value.contains(SHELL_METACHARACTERS) {
+        return Err(format!("Invalid value for {}", key));
+    }
+    Ok(())
+}
+
+// Extra arguments and environment for the server; they take effect the next time it starts
+#[tauri::command]
+pub fn set_server_launch_options(
+    app: AppHandle,
+    extra_args: Vec<String>,
+    extra_env: BTreeMap<String, String>,
+) -> Result<ServerConfig, AppError> {
+    capabilities::require(Capability::RunPrograms)?;
+    for arg in &extra_args {
+        validate_arg(arg).map_err(AppError::Validation)?;
+    }
+    for (key, value) in &extra_env {
+        validate_env(key, value).map_err(AppError::Validation)?;
+    }
+    Ok(update(&app, |config| {
+        config.extra_args = extra_args;
+        config.extra_env = extra_env;
+    })?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_ordinary_arguments() {
+        for arg in [
+            "--log-level=debug",
+            "--verbose",
+            "C:\\Users\\me\\agent.json",
+            "/home/me/agent.json",
+            "--name=my-agent_2",
+        ] {
+            assert!(validate_arg(arg).is_ok(), "{}", arg);
+        }
+    }
+
+    #[test]
+    fn rejects_shell_metacharacters() {
+        for arg in [
+            "",
+            "--x;rm -rf ~",
+            "a&&b",
+            "a|b",
+            "$(whoami)",
+            "`id`",
+            "--out>file",
+            "\"quoted\"",
+            "line\nbreak",
+        ] {
+            assert!(validate_arg(arg).is_err(), "{:?}", arg);
+        }
+    }
+
+    #[test]
+    fn rejects_managed_flags() {
+        for arg in [
+            "start",
+            "--port",
+            "--port=4000",
+            "--character",
+            "--character=x.json",
+        ] {
+            let error = validate_arg(arg).unwrap_err();
+            assert!(error.ends_with("is set by the app"), "{}", error);
+        }
+        // Only the exact flag is managed
+        assert!(validate_arg("--portal").is_ok());
+    }
+
+    #[test]
+    fn accepts_ordinary_environment() {
+        assert!(validate_env("LOG_LEVEL", "debug").is_ok());
+        assert!(validate_env("_PRIVATE", "").is_ok());
+        assert!(validate_env("OPENAI_BASE_URL", "https://example.com/v1").is_ok());
+        assert!(validate_env("DATA", "C:\\data\\agent").is_ok());
+    }
+
+    #[test]
+    fn rejects_invalid_environment_names() {
+        for key in ["", "1ABC", "WITH SPACE", "A-B", "A=B", "É"] {
+            assert!(validate_env(key, "x").is_err(), "{:?}", key);
+        }
+    }
+
+    #[test]
+    fn rejects_managed_environment() {
+        for key in ["PATH", "Path", "KNOWLEDGE_PATH", "PGLITE_DATA_DIR"] {
+            let error = validate_env(key, "x").unwrap_err();
+            assert!(error.ends_with("is set by the app"), "{}", error);
+        }
+    }
+
+    #[test]
+    fn rejects_metacharacters_in_values() {
+        for value in ["a;b", "$(id)", "x|y", "a\nb"] {
+            assert!(validate_env("LOG_LEVEL", value).is_err(), "{:?}", value);
+        }
+    }
+}
@@ -0,0 +1,552 @@
+use std::collections::{HashMap, HashSet};
+use std::io::{BufRead, BufReader, Read};
+use std::path::{Component, Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::{mpsc, Mutex};
+use std::time::Duration;
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter};
+
+use super::{config, shutdown};
+use crate::capabilities::{self, Capability};
+use crate::cli::path::{find_tool, spawn_path};
+use crate::error::AppError;
+use crate::workspace;
+
+// Containers are named after their instance, so one left behind by a crash can be replaced
+const NAME_PREFIX: &str = "eliza-desktop-";
+const LABEL: &str = "ai.elizaos.desktop=1";
+const WORKSPACE_MOUNT: &str = "/workspace";
+const DATA_MOUNT: &str = "/data";
+// Host files outside the workspace, like character files, are mounted read-only under here
+const FILES_MOUNT: &str = "/mnt/host";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ServerBackend {
+    // A child process of the app
+    #[default]
+    Process,
+    // A Docker or Podman container
+    Container,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ContainerEngine {
+    Docker,
+    Podman,
+}
+
+impl ContainerEngine {
+    fn program(self) -> &'static str {
+        match self {
+            ContainerEngine::Docker => "docker",
+            ContainerEngine::Podman => "podman",
+        }
+    }
+
+    // `info` fails when the daemon or machine isn't running, unlike `version`
+    fn version_format(self) -> &'static str {
+        match self {
+            ContainerEngine::Docker => "{{.ServerVersion}}",
+            ContainerEngine::Podman => "{{.Version.Version}}",
+        }
+    }
+
+    // How the container reaches services the app runs on the host, like the provider proxy
+    fn host_alias(self) -> &'static str {
+        match self {
+            ContainerEngine::Docker => "host.docker.internal",
+            ContainerEngine::Podman => "host.containers.internal",
+        }
+    }
+}
+
+// The `container` part of the server config
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ContainerConfig {
+    // `None` uses Docker when it's available, then Podman
+    pub engine: Option<ContainerEngine>,
+    // An image whose entrypoint is the `elizaos` CLI
+    pub image: String,
+}
+
+impl Default for ContainerConfig {
+    fn default() -> Self {
+        Self {
+            engine: None,
+            image: "ghcr.io/elizaos/eliza:latest".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EngineInfo {
+    pub engine: ContainerEngine,
+    pub path: PathBuf,
+    pub version: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ContainerStatus {
+    pub backend: ServerBackend,
+    pub image: String,
+    pub engine: Option<EngineInfo>,
+    // Why no engine could be used
+    pub error: Option<String>,
+    pub image_present: bool,
+}
+
+// Payload of the `container-pull-progress` event, sent for each line the engine prints
+#[derive(Debug, Clone, Serialize)]
+struct PullProgress<'a> {
+    image: &'a str,
+    layers: usize,
+    completed: usize,
+    message: &'a str,
+}
+
+// A container we started, keyed by instance id
+struct Running {
+    engine: EngineInfo,
+    name: String,
+}
+
+static CONTAINERS: Lazy<Mutex<HashMap<String, Running>>> = Lazy::new(Default::default);
+
+// Everything a container needs to run one instance
+pub struct Launch<'a> {
+    pub id: &'a str,
+    pub port: u16,
+    pub characters: &'a [PathBuf],
+    pub data_dir: Option<&'a Path>,
+    pub knowledge_dir: &'a Path,
+    pub env: &'a [(String, String)],
+}
+
+pub fn enabled() -> bool {
+    config::current().backend == ServerBackend::Container
+}
+
+fn engine_command(app: &AppHandle, engine: &EngineInfo) -> Command {
+    let mut command = Command::new(&engine.path);
+    // Credential helpers like `docker-credential-desktop` are found through PATH
+    command.env("PATH", spawn_path(app, &engine.path));
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        command.creation_flags(windows_sys::Win32::System::Threading::CREATE_NO_WINDOW);
+    }
+    command
+}
+
+fn run(app: &AppHandle, engine: &EngineInfo, args: &[&str]) -> Result<String, String> {
+    let output = engine_command(app, engine)
+        .args(args)
+        .output()
+        .map_err(|e| format!("Failed to run {}: {}", engine.engine.program(), e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "{} {} failed: {}",
+            engine.engine.program(),
+            args.first().unwrap_or(&""),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+// The configured engine, or the first one installed with a reachable daemon
+pub fn detect(app: &AppHandle) -> Result<EngineInfo, String> {
+    let engines = match config::current().container.engine {
+        Some(engine) => vec![engine],
+        None => vec![ContainerEngine::Docker, ContainerEngine::Podman],
+    };
+    let mut errors = Vec::new();
+    for engine in engines {
+        let Some(path) = find_tool(app, engine.program()) else {
+            continue;
+        };
+        let mut info = EngineInfo {
+            engine,
+            path,
+            version: String::new(),
+        };
+        match run(app, &info, &["info", "--format", engine.version_format()]) {
+            Ok(version) => {
+                info.version = version;
+                return Ok(info);
+            }
+            Err(e) => errors.push(e),
+        }
+    }
+    Err(if errors.is_empty() {
+        "Neither Docker nor Podman is installed".to_string()
+    } else {
+        format!("The container engine isn't running: {}", errors.join("; "))
+    })
+}
+
+fn image_present(app: &AppHandle, engine: &EngineInfo, image: &str) -> bool {
+    run(app, engine, &["image", "inspect", image]).is_ok()
+}
+
+// The layer a pull progress line is about, and whether it's done. Docker prints
+// `<id>: Pull complete`, Podman `Copying blob <id> done`.
+fn parse_layer(line: &str) -> Option<(&str, bool)> {
+    if let Some(rest) = line.strip_prefix("Copying blob ") {
+        let id = rest.split_whitespace().next()?;
+        return Some((id, rest.contains("done") || rest.contains("skipped")));
+    }
+    let (id, status) = line.split_once(": ")?;
+    if id.len() != 12 || !id.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    Some((
+        id,
+        status.starts_with("Pull complete") || status.starts_with("Already exists"),
+    ))
+}
+
+fn pull(app: &AppHandle, engine: &EngineInfo, image: &str) -> Result<(), String> {
+    tracing::info!("Pulling {} with {}", image, engine.engine.program());
+    let mut child = engine_command(app, engine)
+        .args(["pull", image])
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to run {}: {}", engine.engine.program(), e))?;
+
+    // Docker reports progress on stdout and Podman on stderr, where errors also go
+    let (sender, lines) = mpsc::channel();
+    for (is_stderr, source) in [
+        (
+            false,
+            child
+                .stdout
+                .take()
+                .map(|out| Box::new(out) as Box<dyn Read + Send>),
+        ),
+        (
+            true,
+            child
+                .stderr
+                .take()
+                .map(|err| Box::new(err) as Box<dyn Read + Send>),
+        ),
+    ] {
+        let (Some(source), sender) = (source, sender.clone()) else {
+            continue;
+        };
+        std::thread::spawn(move || {
+            for line in BufReader::new(source).lines().map_while(Result::ok) {
+                if sender.send((is_stderr, line)).is_err() {
+                    break;
+                }
+            }
+        });
+    }
+    drop(sender);
+
+    let (mut layers, mut completed) = (HashSet::new(), HashSet::new());
+    let mut last_error = String::new();
+    for (is_stderr, line) in lines {
+        if let Some((id, done)) = parse_layer(&line) {
+            layers.insert(id.to_string());
+            if done {
+                completed.insert(id.to_string());
+            }
+        } else if is_stderr {
+            last_error = line.clone();
+        }
+        emit_pull(app, image, layers.len(), completed.len(), &line);
+    }
+    let status = child
+        .wait()
+        .map_err(|e| format!("Failed to wait for the pull: {}", e))?;
+    if !status.success() {
+        return Err(format!("Failed to pull {}: {}", image, last_error));
+    }
+    emit_pull(app, image, layers.len(), layers.len(), "Pull complete");
+    Ok(())
+}
+
+fn emit_pull(app: &AppHandle, image: &str, layers: usize, completed: usize, message: &str) {
+    let progress = PullProgress {
+        image,
+        layers,
+        completed,
+        message,
+    };
+    if let Err(e) = app.emit("container-pull-progress", progress) {
+        tracing::warn!("Failed to emit pull progress: {}", e);
+    }
+}
+
+// Pull the configured image unless it's already there
+pub fn ensure_image(app: &AppHandle) -> Result<(), String> {
+    let engine = detect(app)?;
+    let image = config::current().container.image;
+    if image_present(app, &engine, &image) {
+        return Ok(());
+    }
+    pull(app, &engine, &image)
+}
+
+fn container_name(id: &str) -> String {
+    format!("{}{}", NAME_PREFIX, id)
+}
+
+fn join(base: &str, relative: &Path) -> String {
+    relative
+        .components()
+        .filter_map(|component| match component {
+            Component::Normal(name) => Some(name.to_string_lossy()),
+            _ => None,
+        })
+        .fold(base.to_string(), |path, name| format!("{}/{}", path, name))
+}
+
+// Bind mounts for a launch, and where host paths end up inside the container
+struct Mounts {
+    workspace: PathBuf,
+    binds: Vec<String>,
+}
+
+impl Mounts {
+    fn bind(&mut self, source: &Path, target: &str, read_only: bool) {
+        let mut bind = format!("type=bind,source={},target={}", source.display(), target);
+        if read_only {
+            bind.push_str(",readonly");
+        }
+        self.binds.push(bind);
+    }
+
+    // The container path of a host file, mounting it when it's outside the workspace
+    fn map(&mut self, path: &Path) -> String {
+        if let Ok(relative) = path.strip_prefix(&self.workspace) {
+            return join(WORKSPACE_MOUNT, relative);
+        }
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let target = format!("{}/{}/{}", FILES_MOUNT, self.binds.len(), name);
+        self.bind(path, &target, true);
+        target
+    }
+}
+
+// Start the instance's container and return a process following its logs, which exits
+// when the container does
+pub fn spawn(app: &AppHandle, launch: &Launch) -> Result<Child, String> {
+    ensure_image(app)?;
+    let engine = detect(app)?;
+    let image = config::current().container.image;
+    let name = container_name(launch.id);
+    // One left behind by a crash would hold the name and the port
+    let _ = run(app, &engine, &["rm", "--force", &name]);
+
+    let mut mounts = Mounts {
+        workspace: workspace::dir()?,
+        binds: Vec::new(),
+    };
+    let workspace = mounts.workspace.clone();
+    mounts.bind(&workspace, WORKSPACE_MOUNT, false);
+
+    let mut env: Vec<(String, String)> = Vec::new();
+    if let Some(data_dir) = launch.data_dir {
+        mounts.bind(data_dir, DATA_MOUNT, false);
+        env.push((
+            "PGLITE_DATA_DIR".to_string(),
+            format!("{}/.elizadb", DATA_MOUNT),
+        ));
+    }
+    env.push((
+        "KNOWLEDGE_PATH".to_string(),
+        mounts.map(launch.knowledge_dir),
+    ));
+    let alias = engine.engine.host_alias();
+    for (key, value) in launch.env {
+        let value = if key == "NODE_EXTRA_CA_CERTS" {
+            mounts.map(Path::new(value))
+        } else {
+            // Inside the container, loopback is the container itself
+            value
+                .replace("//127.0.0.1", &format!("//{}", alias))
+                .replace("//localhost", &format!("//{}", alias))
+        };
+        env.push((key.clone(), value));
+    }
+
+    let mut args = vec![
+        "start".to_string(),
+        "--port".to_string(),
+        launch.port.to_string(),
+    ];
+    if !launch.characters.is_empty() {
+        args.push("--character".to_string());
+        for character in launch.characters {
+            args.push(mounts.map(character));
+        }
+    }
+    args.extend(config::current().extra_args);
+
+    let mut command = engine_command(app, &engine);
+    command
+        .args(["run", "--detach", "--rm"])
+        .arg(format!("--name={}", name))
+        .arg(format!("--label={}", LABEL))
+        .arg(format!("--publish=127.0.0.1:{0}:{0}", launch.port))
+        .arg(format!("--workdir={}", WORKSPACE_MOUNT));
+    if engine.engine == ContainerEngine::Docker {
+        // Docker Desktop provides the alias itself; Docker on Linux needs it added
+        command.arg(format!("--add-host={}:host-gateway", alias));
+    }
+    for bind in &mounts.binds {
+        command.arg(format!("--mount={}", bind));
+    }
+    // Only names go on the command line; the values come from the engine's environment,
+    // so secrets don't show up in process listings
+    for (key, value) in &env {
+        command.arg(format!("--env={}", key)).env(key, value);
+    }
+    command.arg(&image).args(&args);
+
+    tracing::info!(
+        "Starting Eliza server '{}' in {} container {} on port {}...",
+        launch.id,
+        engine.engine.program(),
+        name,
+        launch.port
+    );
+    let output = command
+        .output()
+        .map_err(|e| format!("Failed to run {}: {}", engine.engine.program(), e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "Failed to start the container: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    let mut logs = engine_command(app, &engine);
+    shutdown::prepare(&mut logs);
+    let child = logs
+        .args(["logs", "--follow", &name])
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn();
+    let child = match child {
+        Ok(child) => child,
+        Err(e) => {
+            let _ = run(app, &engine, &["rm", "--force", &name]);
+            return Err(format!("Failed to follow the container's logs: {}", e));
+        }
+    };
+    CONTAINERS
+        .lock()
+        .unwrap()
+        .insert(launch.id.to_string(), Running { engine, name });
+    Ok(child)
+}
+
+// Stop the instance's container if it runs in one, giving it `timeout` to exit
+pub fn stop(app: &AppHandle, id: &str, timeout: Duration) {
+    let Some(running) = CONTAINERS.lock().unwrap().remove(id) else {
+        return;
+    };
+    let seconds = timeout.as_secs().max(1).to_string();
+    if let Err(e) = run(
+        app,
+        &running.engine,
+        &["stop", "--time", &seconds, &running.name],
+    ) {
+        tracing::warn!("Failed to stop container {}: {}", running.name, e);
+    }
+}
+
+// Kill the instance's container if it runs in one
+pub fn kill(app: &AppHandle, id: &str) {
+    let Some(running) = CONTAINERS.lock().unwrap().remove(id) else {
+        return;
+    };
+    if let Err(e) = run(app, &running.engine, &["rm", "--force", &running.name]) {
+        tracing::warn!("Failed to remove container {}: {}", running.name, e);
+    }
+}
+
+pub(super) fn validate_image(image: &str) -> Result<(), String> {
+    let valid = !image.is_empty()
+        && !image.starts_with('-')
+        && image
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "._-/:@".contains(c));
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("Invalid image name: {}", image))
+    }
+}
+
+fn status(app: &AppHandle) -> ContainerStatus {
+    let config = config::current();
+    let (engine, error) = match detect(app) {
+        Ok(engine) => (Some(engine), None),
+        Err(e) => (None, Some(e)),
+    };
+    ContainerStatus {
+        backend: config.backend,
+        image_present: engine
+            .as_ref()
+            .is_some_and(|engine| image_present(app, engine, &config.container.image)),
+        image: config.container.image,
+        engine,
+        error,
+    }
+}
+
+#[tauri::command]
+pub async fn get_container_status(app: AppHandle) -> Result<ContainerStatus, AppError> {
+    Ok(tauri::async_runtime::spawn_blocking(move || status(&app)).await?)
+}
+
+// Run the server as a process or in a container; takes effect on the next start
+#[tauri::command]
+pub async fn set_server_backend(
+    app: AppHandle,
+    backend: ServerBackend,
+    container: Option<ContainerConfig>,
+) -> Result<ContainerStatus, AppError> {
+    capabilities::require(Capability::RunPrograms)?;
+    if let Some(container) = &container {
+        validate_image(&container.image).map_err(AppError::Validation)?;
+    }
+    config::update(&app, |config| {
+        config.backend = backend;
+        config.active_profile = None;
+        if let Some(container) = container {
+            config.container = container;
+        }
+    })?;
+    get_container_status(app).await
+}
+
+// Pull the configured image ahead of the first start, reporting `container-pull-progress`
+#[tauri::command]
+pub async fn pull_container_image(app: AppHandle) -> Result<ContainerStatus, AppError> {
+    capabilities::require(Capability::RunPrograms)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let engine = detect(&app)?;
+        pull(&app, &engine, &config::current().container.image)?;
+        Ok::<_, String>(status(&app))
+    })
+    .await?
+    .map_err(AppError::ServerLifecycle)
+}
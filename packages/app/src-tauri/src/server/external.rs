@@ -0,0 +1,171 @@
+use std::sync::Mutex;
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+
+use super::{config, status, ServerStatus};
+use crate::error::AppError;
+
+// A server started outside the app, e.g. `elizaos start` in a terminal
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Adopted {
+    pid: u32,
+    port: u16,
+}
+
+static ADOPTED: Mutex<Option<Adopted>> = Mutex::new(None);
+
+// Payload of the `server-ownership` event
+#[derive(Debug, Clone, Serialize)]
+pub struct ServerOwnership {
+    pub status: ServerStatus,
+    // Process listening on the configured port
+    pub pid: Option<u32>,
+    pub process_name: Option<String>,
+    // We spawned it and will stop it on exit
+    pub managed: bool,
+    // An external server the user chose to adopt
+    pub adopted: bool,
+}
+
+fn port_owner(port: u16) -> Option<(u32, String)> {
+    listeners::get_process_by_port(port, listeners::Protocol::TCP)
+        .ok()
+        .map(|process| (process.pid, process.name))
+}
+
+pub fn ownership() -> ServerOwnership {
+    let status = status();
+    let port = config::current().port;
+    let owner = match status {
+        ServerStatus::Stopped => None,
+        _ => port_owner(port),
+    };
+    let owner_pid = owner.as_ref().map(|owner| owner.0);
+    let mut adopted = ADOPTED.lock().unwrap();
+    // Forget an adopted server once it has exited or something else holds the port
+    let still_running = |server: &Adopted| {
+        status == ServerStatus::External && server.port == port && owner_pid == Some(server.pid)
+    };
+    if !adopted.as_ref().is_some_and(still_running) {
+        *adopted = None;
+    }
+    ServerOwnership {
+        status,
+        pid: owner_pid,
+        process_name: owner.map(|owner| owner.1),
+        managed: status == ServerStatus::Running,
+        adopted: adopted.is_some(),
+    }
+}
+
+#[cfg(unix)]
+fn request_exit(pid: u32) -> Result<(), String> {
+    if unsafe { libc::kill(pid as libc::pid_t, libc::SIGTERM) } == 0 {
+        Ok(())
+    } else {
+        Err(std::io::Error::last_os_error().to_string())
+    }
+}
+
+// A console process in another session can't be sent CTRL_BREAK, so this is a hard stop
+#[cfg(windows)]
+fn request_exit(pid: u32) -> Result<(), String> {
+    use windows_sys::Win32::Foundation::CloseHandle;
+    use windows_sys::Win32::System::Threading::{OpenProcess, TerminateProcess, PROCESS_TERMINATE};
+
+    unsafe {
+        let handle = OpenProcess(PROCESS_TERMINATE, 0, pid);
+        if handle.is_null() {
+            return Err(std::io::Error::last_os_error().to_string());
+        }
+        let terminated = TerminateProcess(handle, 1) != 0;
+        let error = std::io::Error::last_os_error();
+        CloseHandle(handle);
+        if terminated {
+            Ok(())
+        } else {
+            Err(error.to_string())
+        }
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+fn request_exit(_pid: u32) -> Result<(), String> {
+    Err("Stopping external servers is not supported on this platform".to_string())
+}
+
+// Stop the adopted server when the app exits, if the user asked for that. A process we
+// didn't spawn is never touched otherwise.
+pub fn shutdown() {
+    if !config::current().stop_external_on_exit {
+        return;
+    }
+    let Some(server) = ADOPTED.lock().unwrap().take() else {
+        return;
+    };
+    // The pid must still own the port, so a reused pid is never signalled
+    if port_owner(server.port).map(|owner| owner.0) != Some(server.pid) {
+        return;
+    }
+    tracing::info!("Stopping adopted Eliza server (pid {})", server.pid);
+    if let Err(e) = request_exit(server.pid) {
+        tracing::warn!("Failed to stop adopted Eliza server: {}", e);
+    }
+}
+
+// Who runs the server on the configured port
+#[tauri::command]
+pub async fn get_server_ownership() -> Result<ServerOwnership, AppError> {
+    Ok(tauri::async_runtime::spawn_blocking(ownership).await?)
+}
+
+// Treat the external server on the configured port as this app's server
+#[tauri::command]
+pub async fn adopt_server(app: AppHandle) -> Result<ServerOwnership, AppError> {
+    let ownership = tauri::async_runtime::spawn_blocking(|| {
+        let current = ownership();
+        if current.status != ServerStatus::External {
+            return Err("There is no external elizaOS server to adopt".to_string());
+        }
+        let pid = current
+            .pid
+            .ok_or("Could not find the process that runs the server")?;
+        *ADOPTED.lock().unwrap() = Some(Adopted {
+            pid,
+            port: config::current().port,
+        });
+        tracing::info!(pid, "Adopted external Eliza server");
+        Ok(ownership())
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+    if let Err(e) = app.emit("server-ownership", &ownership) {
+        tracing::warn!("Failed to emit server ownership: {}", e);
+    }
+    Ok(ownership)
+}
+
+// Stop tracking the adopted server; it keeps running
+#[tauri::command]
+pub async fn release_server(app: AppHandle) -> Result<ServerOwnership, AppError> {
+    *ADOPTED.lock().unwrap() = None;
+    let ownership = tauri::async_runtime::spawn_blocking(ownership)
+        .await
+        .map_err(|e| e.to_string())?;
+    if let Err(e) = app.emit("server-ownership", &ownership) {
+        tracing::warn!("Failed to emit server ownership: {}", e);
+    }
+    Ok(ownership)
+}
+
+// Whether closing the app stops an adopted server; off by default
+#[tauri::command]
+pub fn set_stop_external_on_exit(
+    app: AppHandle,
+    enabled: bool,
+) -> Result<config::ServerConfig, AppError> {
+    Ok(config::update(&app, |config| {
+        config.stop_external_on_exit = enabled
+    })?)
+}
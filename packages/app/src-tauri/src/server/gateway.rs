@@ -0,0 +1,769 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use axum::body::{to_bytes, Body, Bytes};
+use axum::extract::{Path, Request, State};
+use axum::http::{header, HeaderMap, HeaderValue, Method, StatusCode};
+use axum::response::{IntoResponse, Response};
+use futures_util::StreamExt;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::{AppHandle, Emitter};
+
+use super::proxy::{self, forward_headers, RateLimiter};
+use super::usage::Meter;
+use crate::error::AppError;
+use crate::filters::{self, Direction, StreamFilter, Verdict};
+use crate::settings;
+
+// Provider calls are served under this path of the proxy. The server's plugins can't add
+// headers, so the proxy key is part of the address they're given.
+pub(super) const ROUTE: &str = "/providers/{key}/{provider}/{*path}";
+const MAX_BODY_BYTES: usize = 50 * 1024 * 1024;
+
+struct Upstream {
+    provider: &'static str,
+    // Env var the server's plugin reads the API address from
+    env_var: &'static str,
+    base: &'static str,
+    // Speaks the OpenAI API with a bearer token, so requests can fail over between these
+    openai_compatible: bool,
+}
+
+// Model providers whose plugins let the server override the API address
+const UPSTREAMS: &[Upstream] = &[
+    Upstream {
+        provider: "openai",
+        env_var: "OPENAI_BASE_URL",
+        base: "https://api.openai.com/v1",
+        openai_compatible: true,
+    },
+    Upstream {
+        provider: "anthropic",
+        env_var: "ANTHROPIC_BASE_URL",
+        base: "https://api.anthropic.com/v1",
+        openai_compatible: false,
+    },
+    Upstream {
+        provider: "groq",
+        env_var: "GROQ_BASE_URL",
+        base: "https://api.groq.com/openai/v1",
+        openai_compatible: true,
+    },
+    Upstream {
+        provider: "openrouter",
+        env_var: "OPENROUTER_BASE_URL",
+        base: "https://openrouter.ai/api/v1",
+        openai_compatible: true,
+    },
+];
+
+// Where requests go while a provider's circuit is open
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Failover {
+    pub provider: String,
+    // Replaces the requested model, since model names differ between providers
+    pub model: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProviderGuardSettings {
+    // Sustained calls per second to each provider; 0 disables rate limiting
+    pub rate_limit: u32,
+    pub burst: u32,
+    // Consecutive 429 or 5xx replies that open the circuit
+    pub failure_threshold: u32,
+    // How long an open circuit rejects calls before letting one through to probe
+    pub cooldown_secs: u64,
+    // Secondary provider by primary, e.g. `openai` -> `openrouter`
+    pub failover: BTreeMap<String, Failover>,
+}
+
+impl Default for ProviderGuardSettings {
+    fn default() -> Self {
+        Self {
+            rate_limit: 10,
+            burst: 20,
+            failure_threshold: 5,
+            cooldown_secs: 30,
+            failover: BTreeMap::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitState {
+    Closed,
+    // Calls are rejected or sent to the failover provider
+    Open,
+    // The cooldown has passed and one call is probing whether the provider recovered
+    HalfOpen,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProviderHealth {
+    pub provider: String,
+    pub state: CircuitState,
+    pub consecutive_failures: u32,
+    pub last_error: Option<String>,
+    // Until the next probe is let through; only while open
+    pub retry_in_ms: Option<u64>,
+}
+
+// Payload of the `provider-degraded` event, sent when a circuit opens
+#[derive(Debug, Clone, Serialize)]
+struct ProviderDegraded<'a> {
+    provider: &'a str,
+    consecutive_failures: u32,
+    error: &'a str,
+    retry_in_ms: u64,
+    // The provider taking over, if one is configured
+    failover: Option<&'a str>,
+}
+
+// Payload of the `provider-recovered` event
+#[derive(Debug, Clone, Serialize)]
+struct ProviderRecovered<'a> {
+    provider: &'a str,
+}
+
+struct Guard {
+    limiter: RateLimiter,
+    failures: u32,
+    opened_at: Option<Instant>,
+    probing: bool,
+    last_error: Option<String>,
+}
+
+impl Guard {
+    fn new(burst: u32) -> Self {
+        Guard {
+            limiter: RateLimiter::new(burst),
+            failures: 0,
+            opened_at: None,
+            probing: false,
+            last_error: None,
+        }
+    }
+
+    fn admit(&mut self, config: &ProviderGuardSettings) -> Admission {
+        let cooldown = Duration::from_secs(config.cooldown_secs);
+        if let Some(opened_at) = self.opened_at {
+            let elapsed = opened_at.elapsed();
+            if elapsed < cooldown || self.probing {
+                return Admission::Open(cooldown.saturating_sub(elapsed));
+            }
+            self.probing = true;
+        }
+        if self.limiter.try_acquire(config.rate_limit, config.burst) {
+            Admission::Allowed
+        } else {
+            self.probing = false;
+            Admission::Limited
+        }
+    }
+
+    // Returns whether the circuit just opened, whether it just closed again, and the
+    // failures in a row
+    fn record(&mut self, result: &Result<(), String>, threshold: u32) -> (bool, bool, u32) {
+        let was_open = self.opened_at.is_some();
+        self.probing = false;
+        match result {
+            Ok(()) => {
+                self.failures = 0;
+                self.opened_at = None;
+                self.last_error = None;
+                (false, was_open, 0)
+            }
+            Err(e) => {
+                self.failures += 1;
+                self.last_error = Some(e.clone());
+                let open = self.failures >= threshold.max(1);
+                if open {
+                    // A failed probe starts another cooldown
+                    self.opened_at = Some(Instant::now());
+                }
+                (open && !was_open, false, self.failures)
+            }
+        }
+    }
+}
+
+enum Admission {
+    Allowed,
+    Limited,
+    Open(Duration),
+}
+
+static GUARDS: Lazy<Mutex<HashMap<&'static str, Guard>>> = Lazy::new(Default::default);
+
+fn upstream(provider: &str) -> Option<&'static Upstream> {
+    UPSTREAMS
+        .iter()
+        .find(|upstream| upstream.provider == provider)
+}
+
+// Environment pointing the server's provider plugins at the proxy. Empty when neither usage
+// tracking nor content filters need it or the proxy isn't up yet, in which case the server
+// calls providers directly.
+pub fn server_env() -> Vec<(&'static str, String)> {
+    if !settings::current().usage.track_providers && !filters::active() {
+        return Vec::new();
+    }
+    let Ok(proxy) = proxy::url() else {
+        tracing::debug!("The proxy is not running; provider calls won't be counted or filtered");
+        return Vec::new();
+    };
+    UPSTREAMS
+        .iter()
+        .map(|upstream| {
+            (
+                upstream.env_var,
+                format!("{}/providers/{}/{}", proxy, proxy::key(), upstream.provider),
+            )
+        })
+        .collect()
+}
+
+fn with_guard<T>(
+    provider: &'static str,
+    config: &ProviderGuardSettings,
+    f: impl FnOnce(&mut Guard) -> T,
+) -> T {
+    let mut guards = GUARDS.lock().unwrap();
+    let guard = guards
+        .entry(provider)
+        .or_insert_with(|| Guard::new(config.burst));
+    f(guard)
+}
+
+fn admit(provider: &'static str, config: &ProviderGuardSettings) -> Admission {
+    with_guard(provider, config, |guard| guard.admit(config))
+}
+
+fn circuit_state(guard: &Guard, cooldown: Duration) -> CircuitState {
+    match guard.opened_at {
+        None => CircuitState::Closed,
+        Some(opened_at) if guard.probing || opened_at.elapsed() >= cooldown => {
+            CircuitState::HalfOpen
+        }
+        Some(_) => CircuitState::Open,
+    }
+}
+
+// Record how a call went, opening or closing the provider's circuit
+fn settle(app: &AppHandle, provider: &'static str, result: Result<(), String>) {
+    let config = settings::current().provider_guard;
+    let cooldown = Duration::from_secs(config.cooldown_secs);
+    let (opened, recovered, failures) = with_guard(provider, &config, |guard| {
+        guard.record(&result, config.failure_threshold)
+    });
+    if opened {
+        let error = result
+            .as_ref()
+            .err()
+            .map(String::as_str)
+            .unwrap_or_default();
+        tracing::warn!(provider, failures, "Provider circuit opened: {}", error);
+        let degraded = ProviderDegraded {
+            provider,
+            consecutive_failures: failures,
+            error,
+            retry_in_ms: cooldown.as_millis() as u64,
+            failover: config
+                .failover
+                .get(provider)
+                .map(|failover| failover.provider.as_str()),
+        };
+        if let Err(e) = app.emit("provider-degraded", degraded) {
+            tracing::warn!("Failed to emit provider-degraded: {}", e);
+        }
+    }
+    if recovered {
+        tracing::info!(provider, "Provider circuit closed");
+        if let Err(e) = app.emit("provider-recovered", ProviderRecovered { provider }) {
+            tracing::warn!("Failed to emit provider-recovered: {}", e);
+        }
+    }
+}
+
+fn error_response(status: StatusCode, message: impl Into<String>) -> Response {
+    (status, message.into()).into_response()
+}
+
+fn retry_after(status: StatusCode, wait: Duration, message: String) -> Response {
+    let mut response = error_response(status, message);
+    let seconds = wait.as_secs().max(1).to_string();
+    if let Ok(value) = HeaderValue::from_str(&seconds) {
+        response.headers_mut().insert(header::RETRY_AFTER, value);
+    }
+    response
+}
+
+// Where `primary`'s calls go while its circuit is open, if anywhere
+fn failover_for<'a>(
+    primary: &Upstream,
+    config: &'a ProviderGuardSettings,
+) -> Option<(&'a Failover, &'static Upstream)> {
+    let failover = config.failover.get(primary.provider)?;
+    let upstream = upstream(&failover.provider)?;
+    (primary.openai_compatible && upstream.openai_compatible).then_some((failover, upstream))
+}
+
+// The request as the failover provider expects it: its own key and, if set, its model
+async fn rewrite_for(
+    failover: &Failover,
+    headers: &mut HeaderMap,
+    body: &Bytes,
+) -> Result<Bytes, String> {
+    let provider = failover.provider.clone();
+    let key = tauri::async_runtime::spawn_blocking(move || crate::secrets::load_key(&provider))
+        .await
+        .map_err(|e| e.to_string())??
+        .ok_or_else(|| format!("No API key is stored for {}", failover.provider))?;
+    let authorization = HeaderValue::from_str(&format!("Bearer {}", key))
+        .map_err(|_| format!("The {} API key is not a valid header", failover.provider))?;
+    headers.insert(header::AUTHORIZATION, authorization);
+    match &failover.model {
+        Some(model) => with_model(body, model),
+        None => Ok(body.clone()),
+    }
+}
+
+// `body` asking for `model` instead; bodies that aren't JSON objects are sent as they are
+fn with_model(body: &Bytes, model: &str) -> Result<Bytes, String> {
+    let mut request: Value = match serde_json::from_slice(body) {
+        Ok(request) => request,
+        Err(_) => return Ok(body.clone()),
+    };
+    let Some(object) = request.as_object_mut() else {
+        return Ok(body.clone());
+    };
+    object.insert("model".to_string(), Value::String(model.to_string()));
+    serde_json::to_vec(&request)
+        .map(Bytes::from)
+        .map_err(|e| e.to_string())
+}
+
+// Forward a call from the server to its model provider, limiting its rate, failing over while
+// the provider keeps failing, applying the workspace's content filters and recording what it
+// used
+pub(super) async fn forward(
+    State(app): State<AppHandle>,
+    Path((key, provider, path)): Path<(String, String, String)>,
+    request: Request,
+) -> Response {
+    // Checked here rather than by the localhost guard, so LAN and tailnet clients can't
+    // spend the user's provider keys either
+    if !proxy::is_key(&key) {
+        return error_response(StatusCode::UNAUTHORIZED, "Invalid proxy key");
+    }
+    let Some(primary) = upstream(&provider) else {
+        return error_response(
+            StatusCode::NOT_FOUND,
+            format!("Unknown provider: {}", provider),
+        );
+    };
+    let config = settings::current().provider_guard;
+    let (parts, body) = request.into_parts();
+    let body = match to_bytes(body, MAX_BODY_BYTES).await {
+        Ok(body) => body,
+        Err(e) => return error_response(StatusCode::PAYLOAD_TOO_LARGE, e.to_string()),
+    };
+
+    let mut target = primary;
+    let mut headers = parts.headers.clone();
+    // Checked before the call is admitted, so it doesn't hold a slot while the user decides
+    let mut body = match filters::check(&app, Direction::Prompt, &provider, &body).await {
+        Verdict::Allow(Some(redacted)) => {
+            headers.remove(header::CONTENT_LENGTH);
+            Bytes::from(redacted)
+        }
+        Verdict::Allow(None) => body,
+        Verdict::Block(reason) => return error_response(StatusCode::FORBIDDEN, reason),
+    };
+    match admit(primary.provider, &config) {
+        Admission::Allowed => {}
+        Admission::Limited => {
+            return retry_after(
+                StatusCode::TOO_MANY_REQUESTS,
+                Duration::from_secs(1),
+                format!("Too many calls to {}", provider),
+            );
+        }
+        Admission::Open(wait) => {
+            let Some((failover, secondary)) = failover_for(primary, &config) else {
+                return retry_after(
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    wait,
+                    format!("{} is failing; calls are paused", provider),
+                );
+            };
+            if !matches!(admit(secondary.provider, &config), Admission::Allowed) {
+                return retry_after(
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    wait,
+                    format!(
+                        "{} is failing and {} is unavailable",
+                        provider, secondary.provider
+                    ),
+                );
+            }
+            body = match rewrite_for(failover, &mut headers, &body).await {
+                Ok(body) => body,
+                Err(e) => return error_response(StatusCode::SERVICE_UNAVAILABLE, e),
+            };
+            tracing::info!(from = %provider, to = secondary.provider, "Failing over provider call");
+            target = secondary;
+        }
+    }
+
+    let mut url = format!("{}/{}", target.base, path);
+    if let Some(query) = parts.uri.query() {
+        url = format!("{}?{}", url, query);
+    }
+    let response = send(&parts.method, &url, &headers, &body).await;
+    let result = match &response {
+        Ok(upstream) if upstream.status() == StatusCode::TOO_MANY_REQUESTS => {
+            Err("Rate limited by the provider".to_string())
+        }
+        Ok(upstream) if upstream.status().is_server_error() => {
+            Err(format!("The provider answered {}", upstream.status()))
+        }
+        Ok(_) => Ok(()),
+        Err(e) => Err(e.clone()),
+    };
+    settle(&app, target.provider, result);
+
+    let upstream = match response {
+        Ok(upstream) => upstream,
+        Err(e) => {
+            return error_response(
+                StatusCode::BAD_GATEWAY,
+                format!("{} is unreachable: {}", target.provider, e),
+            )
+        }
+    };
+    let streaming = upstream
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("text/event-stream"));
+    let mut meter = settings::current().usage.track_providers.then(|| {
+        Meter::new(
+            target.provider,
+            &body,
+            upstream.status().as_u16(),
+            streaming,
+        )
+    });
+
+    let mut response = Response::builder().status(upstream.status());
+    if let Some(headers) = response.headers_mut() {
+        forward_headers(upstream.headers(), headers);
+    }
+    if !streaming && filters::inspects(Direction::Response) {
+        let reply = match upstream.bytes().await {
+            Ok(reply) => reply,
+            Err(e) => return error_response(StatusCode::BAD_GATEWAY, e.to_string()),
+        };
+        if let Some(meter) = &mut meter {
+            meter.feed(&reply);
+        }
+        let reply = match filters::check(&app, Direction::Response, target.provider, &reply).await {
+            Verdict::Allow(Some(redacted)) => {
+                if let Some(headers) = response.headers_mut() {
+                    headers.remove(header::CONTENT_LENGTH);
+                }
+                Bytes::from(redacted)
+            }
+            Verdict::Allow(None) => reply,
+            Verdict::Block(reason) => return error_response(StatusCode::FORBIDDEN, reason),
+        };
+        return response
+            .body(Body::from(reply))
+            .unwrap_or_else(|e| error_response(StatusCode::BAD_GATEWAY, e.to_string()));
+    }
+
+    // No filter when no rule looks at replies, the same replies `check` would let through
+    let mut filter = streaming
+        .then(|| StreamFilter::new(&app, target.provider))
+        .flatten();
+    let stream = upstream
+        .bytes_stream()
+        .map(move |chunk| {
+            if let (Some(meter), Ok(bytes)) = (&mut meter, &chunk) {
+                meter.feed(bytes);
+            }
+            chunk
+        })
+        .take_while(move |chunk| {
+            let pass = match (&mut filter, chunk) {
+                (Some(filter), Ok(bytes)) => filter.feed(bytes),
+                _ => true,
+            };
+            std::future::ready(pass)
+        });
+    response
+        .body(Body::from_stream(stream))
+        .unwrap_or_else(|e| error_response(StatusCode::BAD_GATEWAY, e.to_string()))
+}
+
+async fn send(
+    method: &Method,
+    url: &str,
+    headers: &HeaderMap,
+    body: &Bytes,
+) -> Result<reqwest::Response, String> {
+    let client = crate::network::client();
+    let mut outgoing = client
+        .request(method.clone(), url)
+        .body(body.clone())
+        .build()
+        .map_err(|e| e.to_string())?;
+    forward_headers(headers, outgoing.headers_mut());
+    // Ask for an uncompressed reply so its token counts can be read
+    outgoing.headers_mut().remove(header::ACCEPT_ENCODING);
+    client.execute(outgoing).await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn get_provider_health() -> Vec<ProviderHealth> {
+    let cooldown = Duration::from_secs(settings::current().provider_guard.cooldown_secs);
+    let guards = GUARDS.lock().unwrap();
+    UPSTREAMS
+        .iter()
+        .map(|upstream| match guards.get(upstream.provider) {
+            Some(guard) => ProviderHealth {
+                provider: upstream.provider.to_string(),
+                state: circuit_state(guard, cooldown),
+                consecutive_failures: guard.failures,
+                last_error: guard.last_error.clone(),
+                retry_in_ms: guard
+                    .opened_at
+                    .map(|opened_at| cooldown.saturating_sub(opened_at.elapsed()))
+                    .filter(|wait| !wait.is_zero())
+                    .map(|wait| wait.as_millis() as u64),
+            },
+            None => ProviderHealth {
+                provider: upstream.provider.to_string(),
+                state: CircuitState::Closed,
+                consecutive_failures: 0,
+                last_error: None,
+                retry_in_ms: None,
+            },
+        })
+        .collect()
+}
+
+#[tauri::command]
+pub fn set_provider_guard(
+    app: AppHandle,
+    guard: ProviderGuardSettings,
+) -> Result<ProviderGuardSettings, AppError> {
+    for (primary, failover) in &guard.failover {
+        let compatible = |provider: &str| upstream(provider).is_some_and(|u| u.openai_compatible);
+        if primary == &failover.provider || !compatible(primary) || !compatible(&failover.provider)
+        {
+            return Err(AppError::Validation(format!(
+                "{} can't fail over to {}; both must be different OpenAI-compatible providers",
+                primary, failover.provider
+            )));
+        }
+    }
+    let saved = guard.clone();
+    settings::update(&app, |settings| settings.provider_guard = saved)?;
+    // Every limiter starts again with the new burst size; circuits keep their state
+    for state in GUARDS.lock().unwrap().values_mut() {
+        state.limiter = RateLimiter::new(guard.burst);
+    }
+    Ok(guard)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(failure_threshold: u32, cooldown_secs: u64) -> ProviderGuardSettings {
+        ProviderGuardSettings {
+            rate_limit: 0,
+            failure_threshold,
+            cooldown_secs,
+            ..Default::default()
+        }
+    }
+
+    fn fail(guard: &mut Guard, config: &ProviderGuardSettings) -> (bool, bool, u32) {
+        guard.record(&Err("503".to_string()), config.failure_threshold)
+    }
+
+    // As if the circuit had opened `secs` ago
+    fn opened(guard: &mut Guard, secs: u64) {
+        guard.opened_at = Some(Instant::now() - Duration::from_secs(secs));
+    }
+
+    #[test]
+    fn opens_at_the_threshold() {
+        let config = config(3, 30);
+        let mut guard = Guard::new(config.burst);
+        assert_eq!(fail(&mut guard, &config), (false, false, 1));
+        assert_eq!(fail(&mut guard, &config), (false, false, 2));
+        assert!(matches!(guard.admit(&config), Admission::Allowed));
+        assert_eq!(
+            circuit_state(&guard, Duration::from_secs(30)),
+            CircuitState::Closed
+        );
+
+        // Reported as opening only once
+        assert_eq!(fail(&mut guard, &config), (true, false, 3));
+        assert_eq!(fail(&mut guard, &config), (false, false, 4));
+        assert_eq!(
+            circuit_state(&guard, Duration::from_secs(30)),
+            CircuitState::Open
+        );
+        assert_eq!(guard.last_error.as_deref(), Some("503"));
+        match guard.admit(&config) {
+            Admission::Open(wait) => assert!(wait <= Duration::from_secs(30)),
+            _ => panic!("expected the circuit to be open"),
+        }
+    }
+
+    #[test]
+    fn success_resets_the_count() {
+        let config = config(2, 30);
+        let mut guard = Guard::new(config.burst);
+        fail(&mut guard, &config);
+        assert_eq!(
+            guard.record(&Ok(()), config.failure_threshold),
+            (false, false, 0)
+        );
+        assert_eq!(fail(&mut guard, &config), (false, false, 1));
+        assert!(guard.opened_at.is_none());
+    }
+
+    #[test]
+    fn zero_threshold_opens_on_the_first_failure() {
+        let config = config(0, 30);
+        let mut guard = Guard::new(config.burst);
+        assert_eq!(fail(&mut guard, &config), (true, false, 1));
+    }
+
+    #[test]
+    fn half_open_lets_one_probe_through() {
+        let config = config(1, 30);
+        let mut guard = Guard::new(config.burst);
+        fail(&mut guard, &config);
+        opened(&mut guard, 31);
+        assert_eq!(
+            circuit_state(&guard, Duration::from_secs(30)),
+            CircuitState::HalfOpen
+        );
+
+        assert!(matches!(guard.admit(&config), Admission::Allowed));
+        assert!(guard.probing);
+        // Others wait while the probe is out
+        assert!(matches!(guard.admit(&config), Admission::Open(_)));
+        assert_eq!(
+            circuit_state(&guard, Duration::from_secs(30)),
+            CircuitState::HalfOpen
+        );
+
+        // A good probe closes the circuit
+        assert_eq!(
+            guard.record(&Ok(()), config.failure_threshold),
+            (false, true, 0)
+        );
+        assert_eq!(
+            circuit_state(&guard, Duration::from_secs(30)),
+            CircuitState::Closed
+        );
+        assert!(matches!(guard.admit(&config), Admission::Allowed));
+    }
+
+    #[test]
+    fn failed_probe_restarts_the_cooldown() {
+        let config = config(1, 30);
+        let mut guard = Guard::new(config.burst);
+        fail(&mut guard, &config);
+        opened(&mut guard, 31);
+        assert!(matches!(guard.admit(&config), Admission::Allowed));
+
+        // Not reported as opening again
+        assert_eq!(fail(&mut guard, &config), (false, false, 2));
+        assert!(!guard.probing);
+        assert_eq!(
+            circuit_state(&guard, Duration::from_secs(30)),
+            CircuitState::Open
+        );
+        match guard.admit(&config) {
+            Admission::Open(wait) => assert!(wait > Duration::from_secs(29)),
+            _ => panic!("expected another cooldown"),
+        }
+    }
+
+    #[test]
+    fn rate_limited_probe_is_released() {
+        let mut config = config(1, 30);
+        config.rate_limit = 1;
+        config.burst = 1;
+        let mut guard = Guard::new(config.burst);
+        assert!(matches!(guard.admit(&config), Admission::Allowed));
+        assert!(matches!(guard.admit(&config), Admission::Limited));
+
+        fail(&mut guard, &config);
+        opened(&mut guard, 31);
+        assert!(matches!(guard.admit(&config), Admission::Limited));
+        // The next call may probe instead
+        assert!(!guard.probing);
+    }
+
+    #[test]
+    fn failover_between_compatible_providers() {
+        let openai = upstream("openai").unwrap();
+        let anthropic = upstream("anthropic").unwrap();
+        let mut config = config(1, 30);
+        assert!(failover_for(openai, &config).is_none());
+
+        let to = |provider: &str| Failover {
+            provider: provider.to_string(),
+            model: Some("llama-3.1-70b".to_string()),
+        };
+        config.failover.insert("openai".to_string(), to("groq"));
+        let (failover, secondary) = failover_for(openai, &config).unwrap();
+        assert_eq!(secondary.provider, "groq");
+        assert_eq!(failover.model.as_deref(), Some("llama-3.1-70b"));
+
+        config
+            .failover
+            .insert("openai".to_string(), to("anthropic"));
+        assert!(failover_for(openai, &config).is_none());
+        config
+            .failover
+            .insert("anthropic".to_string(), to("openai"));
+        assert!(failover_for(anthropic, &config).is_none());
+        config.failover.insert("openai".to_string(), to("nowhere"));
+        assert!(failover_for(openai, &config).is_none());
+    }
+
+    #[test]
+    fn failover_rewrites_the_model() {
+        let body = Bytes::from(r#"{"model":"gpt-4o","messages":[{"role":"user","content":"hi"}]}"#);
+        let rewritten: Value =
+            serde_json::from_slice(&with_model(&body, "llama-3.1-70b").unwrap()).unwrap();
+        assert_eq!(rewritten["model"], "llama-3.1-70b");
+        assert_eq!(rewritten["messages"][0]["content"], "hi");
+
+        let added: Value =
+            serde_json::from_slice(&with_model(&Bytes::from("{}"), "m").unwrap()).unwrap();
+        assert_eq!(added["model"], "m");
+
+        for body in ["not json", "[1,2]", ""] {
+            let body = Bytes::from(body);
+            assert_eq!(with_model(&body, "m").unwrap(), body);
+        }
+    }
+}
@@ -0,0 +1,128 @@
+use std::time::Instant;
+
+use serde::Serialize;
+use serde_json::Value;
+
+use super::config;
+use crate::error::AppError;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ServerHealth {
+    pub url: String,
+    pub status: String,
+    pub version: Option<String>,
+    pub uptime_secs: Option<f64>,
+    pub agent_count: Option<u64>,
+    pub latency_ms: u64,
+}
+
+// The remote server in remote mode, otherwise the local one
+pub fn base_url() -> String {
+    match super::remote::active() {
+        Some(server) => server.url,
+        None => format!("http://{}", config::current().address()),
+    }
+}
+
+async fn get_json(
+    client: &reqwest::Client,
+    url: &str,
+    token: Option<&str>,
+) -> Result<Value, String> {
+    let mut request = client.get(url);
+    if let Some(token) = token {
+        request = request.header(super::proxy::AUTH_HEADER, token);
+    }
+    let response = request
+        .send()
+        .await
+        .map_err(|e| format!("Server is not reachable: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!("{} returned {}", url, response.status()));
+    }
+    response
+        .json()
+        .await
+        .map_err(|_| format!("{} did not return JSON", url))
+}
+
+fn agent_count(value: &Value) -> Option<u64> {
+    value.get("agentCount").and_then(Value::as_u64).or_else(|| {
+        value
+            .get("agents")
+            .and_then(Value::as_array)
+            .map(|agents| agents.len() as u64)
+    })
+}
+
+// Query the elizaOS health endpoint, failing if whatever answers isn't an elizaOS server
+pub async fn check() -> Result<ServerHealth, String> {
+    let token = super::remote::auth_token().await.unwrap_or_else(|e| {
+        tracing::warn!("{}", e);
+        None
+    });
+    check_url(&base_url(), token.as_deref()).await
+}
+
+// The same check against any server, like a remote one before it's added
+pub async fn check_url(base: &str, token: Option<&str>) -> Result<ServerHealth, String> {
+    let base = base.to_string();
+    let client = reqwest::Client::builder()
+        .timeout(config::current().health_poll.timeout())
+        .build()
+        .map_err(|e| e.to_string())?;
+
+    let started = Instant::now();
+    let health = get_json(&client, &format!("{}/api/server/health", base), token).await?;
+    let latency_ms = started.elapsed().as_millis() as u64;
+
+    let status = health
+        .get("status")
+        .and_then(Value::as_str)
+        .ok_or_else(|| format!("The server on {} is not an elizaOS server", base))?
+        .to_string();
+
+    // Older servers only report the agent count from the status endpoint
+    let agent_count = match agent_count(&health) {
+        Some(count) => Some(count),
+        None => get_json(&client, &format!("{}/api/server/status", base), token)
+            .await
+            .ok()
+            .and_then(|status| agent_count(&status)),
+    };
+
+    Ok(ServerHealth {
+        url: base,
+        status,
+        version: health
+            .get("version")
+            .and_then(Value::as_str)
+            .map(str::to_string),
+        uptime_secs: health.get("uptime").and_then(Value::as_f64),
+        agent_count,
+        latency_ms,
+    })
+}
+
+// Blocking variant for the synchronous lifecycle code; must not be called from async code
+pub fn is_healthy() -> bool {
+    tauri::async_runtime::block_on(check()).is_ok()
+}
+
+#[tauri::command]
+pub async fn server_health() -> Result<ServerHealth, AppError> {
+    check().await.map_err(AppError::Network)
+}
+
+// Base URL the frontend should load, which changes if the server moved to another port
+#[tauri::command]
+pub fn get_server_url() -> String {
+    // The webview's CSP only allows local origins, so a remote server is reached through the
+    // proxy, which also adds its API key
+    if super::remote::is_remote() {
+        if let Ok(url) = super::proxy::url() {
+            return url;
+        }
+    }
+    base_url()
+}
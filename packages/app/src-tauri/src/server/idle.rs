@@ -0,0 +1,176 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use futures_util::future::{BoxFuture, Shared};
+use futures_util::FutureExt;
+use once_cell::sync::{Lazy, OnceCell};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter};
+
+use super::{config, readiness, LifecycleState, SUPERVISOR};
+use crate::settings;
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct IdleSettings {
+    // Stop the server after `minutes` without chat activity while the window is unfocused
+    pub enabled: bool,
+    pub minutes: u64,
+}
+
+impl Default for IdleSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            minutes: 30,
+        }
+    }
+}
+
+// Payload of the `server-suspended` and `server-resumed` events
+#[derive(Debug, Clone, Serialize)]
+struct IdleEvent {
+    idle_ms: u64,
+}
+
+static APP: OnceCell<AppHandle> = OnceCell::new();
+static LAST_ACTIVITY: Lazy<Mutex<Instant>> = Lazy::new(|| Mutex::new(Instant::now()));
+static FOCUSED: AtomicBool = AtomicBool::new(true);
+// Set while the server is stopped because of the idle policy rather than by the user
+static SUSPENDED: AtomicBool = AtomicBool::new(false);
+// The resume in progress, until the server answers again; every caller joins the same one
+type Resuming = Shared<BoxFuture<'static, Result<(), String>>>;
+static RESUMING: Lazy<Mutex<Option<Resuming>>> = Lazy::new(Default::default);
+
+fn emit(app: &AppHandle, event: &str, idle: Duration) {
+    let payload = IdleEvent {
+        idle_ms: idle.as_millis() as u64,
+    };
+    if let Err(e) = app.emit(event, payload) {
+        tracing::warn!("Failed to emit {}: {}", event, e);
+    }
+}
+
+fn idle_for() -> Duration {
+    LAST_ACTIVITY.lock().unwrap().elapsed()
+}
+
+fn suspend(app: &AppHandle, idle: Duration) {
+    if SUSPENDED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    tracing::info!("Suspending the idle Eliza server after {:?}", idle);
+    match super::stop(app) {
+        Ok(()) => emit(app, "server-suspended", idle),
+        Err(e) => {
+            SUSPENDED.store(false, Ordering::SeqCst);
+            tracing::warn!("Failed to suspend the Eliza server: {}", e);
+        }
+    }
+}
+
+// Start the server again if the idle policy stopped it; returns whether it did
+fn resume(app: &AppHandle, idle: Duration) -> bool {
+    if !SUSPENDED.swap(false, Ordering::SeqCst) {
+        return false;
+    }
+    // Started or stopped by hand since, which takes precedence over the suspension
+    if SUPERVISOR.state() != LifecycleState::Stopped {
+        return false;
+    }
+    tracing::info!("Resuming the suspended Eliza server");
+    match super::start(app) {
+        Ok(()) => {
+            emit(app, "server-resumed", idle);
+            true
+        }
+        Err(e) => {
+            tracing::warn!("Failed to resume the Eliza server: {}", e);
+            false
+        }
+    }
+}
+
+async fn resume_until_ready(app: AppHandle, idle: Duration) -> Result<(), String> {
+    let resumed = tauri::async_runtime::spawn_blocking(move || resume(&app, idle))
+        .await
+        .map_err(|e| e.to_string())?;
+    if resumed {
+        readiness::wait_for_server_ready(config::current().startup_timeout_ms).await?;
+    }
+    Ok(())
+}
+
+// Record user interaction; returns the resume to wait on if the server is suspended or
+// already coming back
+fn activity() -> Option<Resuming> {
+    let idle = std::mem::replace(&mut *LAST_ACTIVITY.lock().unwrap(), Instant::now()).elapsed();
+    let app = APP.get().cloned()?;
+    let mut resuming = RESUMING.lock().unwrap();
+    if let Some(current) = resuming.as_ref() {
+        return Some(current.clone());
+    }
+    if !SUSPENDED.load(Ordering::SeqCst) {
+        return None;
+    }
+    let task = async move {
+        let result = resume_until_ready(app, idle).await;
+        *RESUMING.lock().unwrap() = None;
+        result
+    }
+    .boxed()
+    .shared();
+    *resuming = Some(task.clone());
+    // Runs to the end even if nobody waits on it
+    tauri::async_runtime::spawn(task.clone());
+    Some(task)
+}
+
+// Record user interaction, restarting a suspended server in the background
+pub fn touch() {
+    activity();
+}
+
+// Record user interaction and, if the server was suspended, wait until it answers again
+pub async fn wake() -> Result<(), String> {
+    match activity() {
+        Some(resuming) => resuming.await,
+        None => Ok(()),
+    }
+}
+
+// Focusing the main window counts as interaction; the server is only suspended while unfocused
+pub fn set_focused(focused: bool) {
+    FOCUSED.store(focused, Ordering::SeqCst);
+    if focused {
+        touch();
+    }
+}
+
+// Watch for inactivity and stop the server once the configured threshold passes
+pub fn spawn(app: AppHandle) {
+    let _ = APP.set(app.clone());
+    thread::spawn(move || loop {
+        thread::sleep(CHECK_INTERVAL);
+        let policy = settings::current().idle_suspend;
+        if !policy.enabled || policy.minutes == 0 || FOCUSED.load(Ordering::SeqCst) {
+            continue;
+        }
+        let idle = idle_for();
+        if idle >= Duration::from_secs(policy.minutes * 60)
+            && SUPERVISOR.state() == LifecycleState::Running
+        {
+            suspend(&app, idle);
+        }
+    });
+}
+
+// Whether the server is currently stopped by the idle policy
+#[tauri::command]
+pub fn is_server_suspended() -> bool {
+    SUSPENDED.load(Ordering::SeqCst) && SUPERVISOR.state() == LifecycleState::Stopped
+}
@@ -0,0 +1,265 @@
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use once_cell::sync::Lazy;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+use super::manager::{InstanceStatus, AGENTS, DEFAULT_INSTANCE};
+use super::{config, port, run_blocking, Launch, SUPERVISOR};
+use crate::audit::{self, AuditAction};
+use crate::error::AppError;
+
+const INSTANCES_FILE: &str = "instances.json";
+const INSTANCES_DIR: &str = "instances";
+
+// Additional agent instances, persisted in the app config dir. The default instance
+// is described by `ServerConfig` and never appears in this file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InstanceConfig {
+    pub id: String,
+    pub name: String,
+    pub port: u16,
+    pub characters: Vec<String>,
+    pub data_dir: PathBuf,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct InstanceInfo {
+    #[serde(flatten)]
+    pub config: InstanceConfig,
+    pub status: InstanceStatus,
+    pub pid: Option<u32>,
+}
+
+impl InstanceInfo {
+    fn new(config: InstanceConfig) -> Self {
+        let status = if AGENTS.is_running(&config.id) {
+            InstanceStatus::Running
+        } else {
+            InstanceStatus::Stopped
+        };
+        Self {
+            pid: AGENTS.pid(&config.id),
+            config,
+            status,
+        }
+    }
+}
+
+// Guards read-modify-write cycles of the instances file
+static INSTANCES_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+
+fn instances_path(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_config_dir()
+        .map(|dir| dir.join(INSTANCES_FILE))
+        .map_err(|e| format!("Failed to resolve app config dir: {}", e))
+}
+
+fn load(app: &AppHandle) -> Result<Vec<InstanceConfig>, String> {
+    let path = instances_path(app)?;
+    match fs::read_to_string(&path) {
+        Ok(contents) => serde_json::from_str(&contents)
+            .map_err(|e| format!("Invalid instances file {}: {}", path.display(), e)),
+        Err(_) => Ok(Vec::new()),
+    }
+}
+
+fn save(app: &AppHandle, instances: &[InstanceConfig]) -> Result<(), String> {
+    let path = instances_path(app)?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    }
+    let contents = serde_json::to_string_pretty(instances).map_err(|e| e.to_string())?;
+    fs::write(&path, contents).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+fn find(app: &AppHandle, id: &str) -> Result<InstanceConfig, String> {
+    load(app)?
+        .into_iter()
+        .find(|instance| instance.id == id)
+        .ok_or_else(|| format!("Unknown instance: {}", id))
+}
+
+fn new_id() -> String {
+    let mut bytes = [0u8; 4];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+// First port above the default server's that no other instance claims and nothing is bound to
+fn allocate_port(instances: &[InstanceConfig]) -> Result<u16, String> {
+    let server = config::current();
+    (server.port.saturating_add(1)..=u16::MAX)
+        .find(|candidate| {
+            instances.iter().all(|instance| instance.port != *candidate)
+                && port::is_port_free(&server.host, *candidate)
+        })
+        .ok_or_else(|| "No free port is available for a new instance".to_string())
+}
+
+fn default_instance() -> Result<InstanceConfig, String> {
+    let server = config::current();
+    Ok(InstanceConfig {
+        id: DEFAULT_INSTANCE.to_string(),
+        name: "Default".to_string(),
+        port: server.port,
+        characters: server.characters,
+        data_dir: crate::workspace::dir()?,
+    })
+}
+
+fn start_locked(app: &AppHandle, instance: &InstanceConfig) -> Result<(), String> {
+    if AGENTS.is_running(&instance.id) {
+        return Err(format!("Instance '{}' is already running", instance.name));
+    }
+    let host = config::current().host;
+    if !port::is_port_free(&host, instance.port) {
+        return Err(format!(
+            "Port {} is already in use; cannot start instance '{}'",
+            instance.port, instance.name
+        ));
+    }
+    super::spawn_agent(
+        app,
+        &Launch {
+            id: &instance.id,
+            port: instance.port,
+            characters: &instance.characters,
+            data_dir: Some(&instance.data_dir),
+        },
+    )
+}
+
+// Where a running instance's server answers; the default one may be an external or remote
+// server, or asleep until the next request
+pub(crate) fn base_url(app: &AppHandle, id: &str) -> Result<String, String> {
+    if id == DEFAULT_INSTANCE {
+        return Ok(super::health::base_url());
+    }
+    let instance = find(app, id)?;
+    if !AGENTS.is_running(&instance.id) {
+        return Err(format!("Instance '{}' is not running", instance.name));
+    }
+    Ok(format!(
+        "http://{}:{}",
+        config::current().host,
+        instance.port
+    ))
+}
+
+// Register a new instance with its own port, characters and data dir; it is not started
+#[tauri::command]
+pub fn create_instance(
+    app: AppHandle,
+    name: String,
+    characters: Option<Vec<String>>,
+    port: Option<u16>,
+) -> Result<InstanceInfo, AppError> {
+    let _lock = INSTANCES_LOCK.lock().unwrap();
+    let mut instances = load(&app)?;
+
+    let port = match port {
+        Some(0) => {
+            return Err(AppError::Validation(
+                "Port must be between 1 and 65535".to_string(),
+            ))
+        }
+        Some(port) => {
+            if port == config::current().port || instances.iter().any(|i| i.port == port) {
+                return Err(AppError::Validation(format!(
+                    "Port {} is already assigned to an instance",
+                    port
+                )));
+            }
+            port
+        }
+        None => allocate_port(&instances)?,
+    };
+
+    let id = new_id();
+    let data_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to resolve app data dir: {}", e))?
+        .join(INSTANCES_DIR)
+        .join(&id);
+    fs::create_dir_all(&data_dir)
+        .map_err(|e| format!("Failed to create {}: {}", data_dir.display(), e))?;
+
+    let instance = InstanceConfig {
+        id,
+        name,
+        port,
+        characters: characters.unwrap_or_default(),
+        data_dir,
+    };
+    instances.push(instance.clone());
+    save(&app, &instances)?;
+    Ok(InstanceInfo::new(instance))
+}
+
+// Forget an instance, stopping it first. Its data dir is left on disk.
+#[tauri::command]
+pub async fn delete_instance(app: AppHandle, id: String) -> Result<(), AppError> {
+    if id == DEFAULT_INSTANCE {
+        return Err(AppError::Validation(
+            "The default instance cannot be deleted".to_string(),
+        ));
+    }
+    run_blocking(move || {
+        {
+            let _work = SUPERVISOR.work();
+            super::halt(&app, &id);
+        }
+        let _lock = INSTANCES_LOCK.lock().unwrap();
+        let mut instances = load(&app)?;
+        let before = instances.len();
+        instances.retain(|instance| instance.id != id);
+        if instances.len() == before {
+            return Err(format!("Unknown instance: {}", id));
+        }
+        save(&app, &instances)
+    })
+    .await
+}
+
+#[tauri::command]
+pub fn list_instances(app: AppHandle) -> Result<Vec<InstanceInfo>, AppError> {
+    let mut instances = vec![InstanceInfo::new(default_instance()?)];
+    instances.extend(load(&app)?.into_iter().map(InstanceInfo::new));
+    Ok(instances)
+}
+
+#[tauri::command]
+pub async fn start_instance(app: AppHandle, id: String) -> Result<(), AppError> {
+    if id == DEFAULT_INSTANCE {
+        return run_blocking(move || super::start(&app)).await;
+    }
+    let instance = find(&app, &id).map_err(AppError::NotFound)?;
+    run_blocking(move || {
+        let _work = SUPERVISOR.work();
+        let result = start_locked(&app, &instance);
+        audit::record(AuditAction::ServerStart, &instance.id, &result);
+        result
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn stop_instance(app: AppHandle, id: String) -> Result<(), AppError> {
+    run_blocking(move || {
+        if id == DEFAULT_INSTANCE {
+            super::stop(&app)?;
+        } else {
+            let _work = SUPERVISOR.work();
+            super::halt(&app, &id);
+        }
+        Ok(())
+    })
+    .await
+}
@@ -0,0 +1,318 @@
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, UdpSocket};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use axum::extract::Request;
+use axum::http::{header, HeaderMap, HeaderValue, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Redirect, Response};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter};
+use tokio::sync::oneshot;
+
+use super::{mdns, proxy};
+use crate::capabilities::{self, Capability};
+use crate::error::AppError;
+use crate::history::now_millis;
+use crate::i18n::{t, t_args};
+use crate::qr::QrCode;
+use crate::webhooks::same_secret;
+use crate::{settings, tls};
+
+// Query parameter the pairing link carries; swapped for a cookie on first use
+const PAIR_PARAM: &str = "pair";
+const COOKIE: &str = "eliza_pairing";
+const MAX_TTL_MINUTES: u32 = 24 * 60;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LanAccessSettings {
+    pub port: u16,
+    // Announce the endpoint over mDNS so the app on the user's other machines can find it
+    pub advertise: bool,
+    // How long a pairing token is accepted; the listener shuts down when it runs out
+    pub token_ttl_minutes: u32,
+}
+
+impl Default for LanAccessSettings {
+    fn default() -> Self {
+        Self {
+            port: 7788,
+            advertise: true,
+            token_ttl_minutes: 60,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LanAccessStatus {
+    pub enabled: bool,
+    // e.g. `http://192.168.1.20:7788/`, without the token; `None` while off
+    pub url: Option<String>,
+    // Milliseconds since the Unix epoch
+    pub expires_at: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PairingQr {
+    // The link the code encodes, token included
+    pub url: String,
+    pub svg: String,
+    pub expires_at: u64,
+}
+
+struct Listener {
+    url: String,
+    token: String,
+    expires_at: u64,
+    shutdown: oneshot::Sender<()>,
+}
+
+// Only kept in memory, so LAN access is always off after a restart
+static LISTENER: Mutex<Option<Listener>> = Mutex::new(None);
+
+fn random_token() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+// The address other devices reach this machine on. Connecting a UDP socket sends nothing;
+// it only asks the OS which interface routes outside.
+fn lan_address() -> Option<IpAddr> {
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).ok()?;
+    socket.connect((Ipv4Addr::new(192, 0, 2, 1), 80)).ok()?;
+    let ip = socket.local_addr().ok()?.ip();
+    (!ip.is_loopback() && !ip.is_unspecified()).then_some(ip)
+}
+
+fn status() -> LanAccessStatus {
+    let listener = LISTENER.lock().unwrap();
+    LanAccessStatus {
+        enabled: listener.is_some(),
+        url: listener.as_ref().map(|listener| listener.url.clone()),
+        expires_at: listener.as_ref().map(|listener| listener.expires_at),
+    }
+}
+
+fn emit_status(app: &AppHandle) {
+    if let Err(e) = app.emit("lan-access-changed", status()) {
+        tracing::warn!("Failed to emit LAN access status: {}", e);
+    }
+}
+
+fn stop() -> bool {
+    match LISTENER.lock().unwrap().take() {
+        Some(listener) => {
+            let _ = listener.shutdown.send(());
+            mdns::withdraw();
+            tracing::info!("LAN access stopped");
+            true
+        }
+        None => false,
+    }
+}
+
+fn query_token(request: &Request) -> Option<&str> {
+    request.uri().query()?.split('&').find_map(|pair| {
+        pair.strip_prefix(PAIR_PARAM)
+            .and_then(|rest| rest.strip_prefix('='))
+    })
+}
+
+fn cookie_token(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(';'))
+        .find_map(|cookie| {
+            cookie
+                .trim()
+                .strip_prefix(COOKIE)
+                .and_then(|rest| rest.strip_prefix('='))
+        })
+}
+
+fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(header::AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bearer ")
+}
+
+// The request's path and query without the pairing parameter
+fn without_pair_param(request: &Request) -> String {
+    let path = request.uri().path();
+    let rest: Vec<&str> = request
+        .uri()
+        .query()
+        .unwrap_or("")
+        .split('&')
+        .filter(|pair| !pair.is_empty() && pair.split('=').next() != Some(PAIR_PARAM))
+        .collect();
+    match rest.is_empty() {
+        true => path.to_string(),
+        false => format!("{}?{}", path, rest.join("&")),
+    }
+}
+
+// Every request must carry the current token, either from the pairing link, a cookie set by
+// it, or an `Authorization: Bearer` header
+async fn authorize(request: Request, next: Next) -> Response {
+    let (token, expires_at) = match LISTENER.lock().unwrap().as_ref() {
+        Some(listener) => (listener.token.clone(), listener.expires_at),
+        None => return (StatusCode::SERVICE_UNAVAILABLE, t("lan-off")).into_response(),
+    };
+    if now_millis() >= expires_at {
+        return (StatusCode::UNAUTHORIZED, t("lan-pairing-expired")).into_response();
+    }
+
+    if let Some(presented) = query_token(&request) {
+        if !same_secret(&token, presented) {
+            return (StatusCode::UNAUTHORIZED, t("lan-invalid-token")).into_response();
+        }
+        // Move the token into a cookie so it doesn't linger in the address bar or history
+        let max_age = expires_at.saturating_sub(now_millis()) / 1000;
+        let cookie = format!(
+            "{}={}; Max-Age={}; Path=/; HttpOnly; SameSite=Strict",
+            COOKIE, token, max_age
+        );
+        let mut response = Redirect::to(&without_pair_param(&request)).into_response();
+        if let Ok(cookie) = HeaderValue::from_str(&cookie) {
+            response.headers_mut().insert(header::SET_COOKIE, cookie);
+        }
+        return response;
+    }
+
+    let presented = cookie_token(request.headers()).or_else(|| bearer_token(request.headers()));
+    match presented {
+        Some(presented) if same_secret(&token, presented) => next.run(request).await,
+        _ => (StatusCode::UNAUTHORIZED, t("lan-pair-first")).into_response(),
+    }
+}
+
+async fn start(app: &AppHandle, ttl_minutes: u32) -> Result<(), String> {
+    let config = settings::current().lan_access;
+    let ip = lan_address().ok_or_else(|| t("lan-no-network"))?;
+    // Only the LAN interface, not every interface the machine has
+    let listener = tokio::net::TcpListener::bind(SocketAddr::new(ip, config.port))
+        .await
+        .map_err(|e| {
+            t_args(
+                "lan-bind-failed",
+                &[
+                    ("port", &config.port.to_string()),
+                    ("error", &e.to_string()),
+                ],
+            )
+        })?;
+    let port = listener
+        .local_addr()
+        .map_err(|e| t_args("lan-address-failed", &[("error", &e.to_string())]))?
+        .port();
+    let acceptor = tls::acceptor();
+    let url = format!(
+        "{}://{}/",
+        tls::scheme(&acceptor),
+        SocketAddr::new(ip, port)
+    );
+
+    let router = proxy::router(app).layer(middleware::from_fn(authorize));
+    let (shutdown, stopped) = oneshot::channel();
+    let ttl = Duration::from_secs(u64::from(ttl_minutes) * 60);
+    let expires_at = now_millis() + ttl.as_millis() as u64;
+    let token = random_token();
+    *LISTENER.lock().unwrap() = Some(Listener {
+        url: url.clone(),
+        token: token.clone(),
+        expires_at,
+        shutdown,
+    });
+    tracing::info!(%url, ttl_minutes, "LAN access started");
+    if let (true, IpAddr::V4(ip)) = (config.advertise, ip) {
+        let version = app.package_info().version.to_string();
+        mdns::advertise(ip, port, acceptor.is_some(), &version);
+    }
+
+    tauri::async_runtime::spawn(async move {
+        let served = tls::serve(listener, router, acceptor, async {
+            let _ = stopped.await;
+        })
+        .await;
+        if let Err(e) = served {
+            tracing::error!("LAN access listener failed: {}", e);
+        }
+    });
+
+    // Close the listener once the token runs out, unless it was replaced in the meantime
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(ttl).await;
+        let current = LISTENER
+            .lock()
+            .unwrap()
+            .as_ref()
+            .is_some_and(|listener| listener.token == token);
+        if current && stop() {
+            tracing::info!("LAN access pairing expired");
+            emit_status(&app);
+        }
+    });
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_lan_access_status() -> LanAccessStatus {
+    status()
+}
+
+// Expose the proxy to the local network behind a new pairing token, or shut it off again.
+// Enabling always issues a fresh token, which signs out every device paired before.
+#[tauri::command]
+pub async fn enable_lan_access(
+    app: AppHandle,
+    enabled: bool,
+    ttl_minutes: Option<u32>,
+) -> Result<LanAccessStatus, AppError> {
+    stop();
+    if enabled {
+        capabilities::require(Capability::RemoteAccess)?;
+        let ttl = ttl_minutes.unwrap_or(settings::current().lan_access.token_ttl_minutes);
+        if ttl == 0 || ttl > MAX_TTL_MINUTES {
+            return Err(AppError::Validation(t_args(
+                "lan-ttl-invalid",
+                &[("max", &MAX_TTL_MINUTES.to_string())],
+            )));
+        }
+        settings::update(&app, |settings| {
+            settings.lan_access.token_ttl_minutes = ttl;
+        })?;
+        start(&app, ttl).await.map_err(AppError::Network)?;
+    }
+    emit_status(&app);
+    Ok(status())
+}
+
+// The pairing link as a QR code for a phone camera
+#[tauri::command]
+pub fn get_pairing_qr() -> Result<PairingQr, AppError> {
+    capabilities::require(Capability::RemoteAccess)?;
+    let listener = LISTENER.lock().unwrap();
+    let listener = listener
+        .as_ref()
+        .ok_or_else(|| AppError::NotFound(t("lan-off")))?;
+    let url = format!("{}?{}={}", listener.url, PAIR_PARAM, listener.token);
+    let svg = QrCode::encode(url.as_bytes())
+        .map_err(AppError::Internal)?
+        .to_svg();
+    Ok(PairingQr {
+        url,
+        svg,
+        expires_at: listener.expires_at,
+    })
+}
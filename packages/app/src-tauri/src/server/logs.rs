@@ -0,0 +1,109 @@
+use std::collections::VecDeque;
+use std::io::{BufRead, BufReader, Read};
+use std::process::Child;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+
+use crate::redaction;
+
+// Number of lines kept in memory for `get_server_logs`
+const MAX_LOG_LINES: usize = 2000;
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogStream {
+    Stdout,
+    Stderr,
+}
+
+// Payload of the `server-log` event
+#[derive(Debug, Clone, Serialize)]
+pub struct LogLine {
+    // Id of the agent instance that wrote the line
+    pub instance: String,
+    // Milliseconds since the Unix epoch
+    pub timestamp: u64,
+    pub stream: LogStream,
+    pub line: String,
+}
+
+static LOG_BUFFER: Lazy<Mutex<VecDeque<LogLine>>> =
+    Lazy::new(|| Mutex::new(VecDeque::with_capacity(MAX_LOG_LINES)));
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+fn record(app: &AppHandle, instance: &str, stream: LogStream, line: String) {
+    let entry = LogLine {
+        instance: instance.to_string(),
+        timestamp: now_millis(),
+        stream,
+        line,
+    };
+
+    {
+        let mut buffer = LOG_BUFFER.lock().unwrap();
+        if buffer.len() == MAX_LOG_LINES {
+            buffer.pop_front();
+        }
+        buffer.push_back(entry.clone());
+    }
+
+    if let Err(e) = app.emit("server-log", &entry) {
+        tracing::warn!("Failed to emit server log: {}", e);
+    }
+}
+
+fn spawn_reader(
+    app: AppHandle,
+    instance: String,
+    stream: LogStream,
+    source: impl Read + Send + 'static,
+) {
+    thread::spawn(move || {
+        for chunk in BufReader::new(source).split(b'\n') {
+            let Ok(bytes) = chunk else { break };
+            let line = String::from_utf8_lossy(&bytes);
+            let line = redaction::redact(line.trim_end_matches('\r')).into_owned();
+
+            // Keep the server output in the app log alongside our own
+            match stream {
+                LogStream::Stdout => tracing::info!(target: "elizaos", %instance, "{}", line),
+                LogStream::Stderr => tracing::warn!(target: "elizaos", %instance, "{}", line),
+            }
+            super::migrations::observe(&app, &instance, &line);
+            record(&app, &instance, stream, line);
+        }
+    });
+}
+
+// Forward the child's piped stdout/stderr to the log buffer and the frontend
+pub fn capture(app: &AppHandle, instance: &str, child: &mut Child) {
+    if let Some(stdout) = child.stdout.take() {
+        spawn_reader(app.clone(), instance.to_string(), LogStream::Stdout, stdout);
+    }
+    if let Some(stderr) = child.stderr.take() {
+        spawn_reader(app.clone(), instance.to_string(), LogStream::Stderr, stderr);
+    }
+}
+
+// Buffered lines, optionally limited to one instance
+#[tauri::command]
+pub fn get_server_logs(instance: Option<String>) -> Vec<LogLine> {
+    LOG_BUFFER
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|line| instance.as_ref().is_none_or(|id| &line.instance == id))
+        .cloned()
+        .collect()
+}
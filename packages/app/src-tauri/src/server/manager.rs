@@ -0,0 +1,73 @@
+use std::collections::HashMap;
+use std::process::Child;
+use std::sync::Mutex;
+
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+
+// The instance driven by `start_server`/`stop_server`, the tray and the setup hook
+pub const DEFAULT_INSTANCE: &str = "default";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InstanceStatus {
+    Running,
+    Stopped,
+}
+
+// Payload of the `instance-status` event
+#[derive(Debug, Clone, Serialize)]
+struct InstanceStatusEvent<'a> {
+    id: &'a str,
+    status: InstanceStatus,
+}
+
+pub fn emit_status(app: &AppHandle, id: &str, status: InstanceStatus) {
+    if let Err(e) = app.emit("instance-status", InstanceStatusEvent { id, status }) {
+        tracing::warn!("Failed to emit instance status: {}", e);
+    }
+}
+
+// The elizaos processes we spawned, keyed by instance id
+#[derive(Default)]
+pub struct AgentManager {
+    processes: Mutex<HashMap<String, Child>>,
+}
+
+impl AgentManager {
+    pub fn insert(&self, id: &str, child: Child) {
+        self.processes.lock().unwrap().insert(id.to_string(), child);
+    }
+
+    pub fn take(&self, id: &str) -> Option<Child> {
+        self.processes.lock().unwrap().remove(id)
+    }
+
+    // Whether the instance's process is still alive, forgetting it if it has exited
+    pub fn is_running(&self, id: &str) -> bool {
+        let mut processes = self.processes.lock().unwrap();
+        match processes.get_mut(id).map(|child| child.try_wait()) {
+            Some(Ok(None)) => true,
+            Some(_) => {
+                processes.remove(id);
+                false
+            }
+            None => false,
+        }
+    }
+
+    pub fn pid(&self, id: &str) -> Option<u32> {
+        if self.is_running(id) {
+            self.processes.lock().unwrap().get(id).map(Child::id)
+        } else {
+            None
+        }
+    }
+
+    pub fn ids(&self) -> Vec<String> {
+        self.processes.lock().unwrap().keys().cloned().collect()
+    }
+}
+
+pub static AGENTS: Lazy<AgentManager> = Lazy::new(AgentManager::default);
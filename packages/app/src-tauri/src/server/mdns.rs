@@ -0,0 +1,448 @@
+use std::collections::BTreeMap;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, SocketAddrV4};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use rand::RngCore;
+use serde::Serialize;
+use socket2::{Domain, Protocol, Socket, Type};
+use tokio::net::UdpSocket;
+use tokio::sync::oneshot;
+
+use crate::error::AppError;
+use crate::hardware::output;
+
+// DNS-SD service type other copies of the app browse for
+const SERVICE: &str = "_elizaos._tcp.local";
+const GROUP: Ipv4Addr = Ipv4Addr::new(224, 0, 0, 251);
+const PORT: u16 = 5353;
+const TTL: u32 = 120;
+// Answers to one-shot queries from ordinary resolvers must not be cached for long
+const LEGACY_TTL: u32 = 10;
+const DEFAULT_DISCOVERY_MS: u64 = 1500;
+const MAX_DISCOVERY_MS: u64 = 10_000;
+
+const TYPE_A: u16 = 1;
+const TYPE_PTR: u16 = 12;
+const TYPE_TXT: u16 = 16;
+const TYPE_SRV: u16 = 33;
+const TYPE_ANY: u16 = 255;
+const CLASS_IN: u16 = 1;
+// Set on records only this host answers for, and on questions asking for a unicast reply
+const TOP_BIT: u16 = 0x8000;
+const FLAG_RESPONSE: u16 = 0x8400;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DiscoveredAgent {
+    // e.g. "Eliza on studio-mac"
+    pub name: String,
+    pub host: String,
+    pub address: IpAddr,
+    pub port: u16,
+    // Where to open it; the pairing token still has to be presented
+    pub url: String,
+    pub tls: bool,
+    pub version: Option<String>,
+}
+
+// What this copy of the app announces while LAN access is on
+struct Advertisement {
+    instance: String,
+    host: String,
+    address: Ipv4Addr,
+    port: u16,
+    txt: Vec<String>,
+}
+
+struct Responder {
+    shutdown: oneshot::Sender<()>,
+}
+
+static RESPONDER: Mutex<Option<Responder>> = Mutex::new(None);
+// Identifies our own announcements, so discovery doesn't list this machine
+static INSTANCE_ID: Mutex<Option<String>> = Mutex::new(None);
+
+fn instance_id() -> String {
+    INSTANCE_ID
+        .lock()
+        .unwrap()
+        .get_or_insert_with(|| {
+            let mut bytes = [0u8; 4];
+            rand::thread_rng().fill_bytes(&mut bytes);
+            bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+        })
+        .clone()
+}
+
+fn machine_name() -> String {
+    // Dots would split the instance name into several DNS labels
+    output("hostname", &[])
+        .and_then(|name| name.trim().split('.').next().map(str::to_string))
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "desktop".to_string())
+}
+
+fn push_name(packet: &mut Vec<u8>, name: &str) {
+    for label in name.trim_end_matches('.').split('.') {
+        let label = &label.as_bytes()[..label.len().min(63)];
+        packet.push(label.len() as u8);
+        packet.extend_from_slice(label);
+    }
+    packet.push(0);
+}
+
+fn push_record(packet: &mut Vec<u8>, name: &str, kind: u16, class: u16, ttl: u32, data: &[u8]) {
+    push_name(packet, name);
+    packet.extend_from_slice(&kind.to_be_bytes());
+    packet.extend_from_slice(&class.to_be_bytes());
+    packet.extend_from_slice(&ttl.to_be_bytes());
+    packet.extend_from_slice(&(data.len() as u16).to_be_bytes());
+    packet.extend_from_slice(data);
+}
+
+fn header(id: u16, flags: u16, questions: u16, answers: u16) -> Vec<u8> {
+    let mut packet = Vec::with_capacity(512);
+    for field in [id, flags, questions, answers, 0, 0] {
+        packet.extend_from_slice(&field.to_be_bytes());
+    }
+    packet
+}
+
+// PTR, SRV, TXT and A records for the advertisement; `ttl` 0 withdraws them
+fn response(ad: &Advertisement, id: u16, ttl: u32) -> Vec<u8> {
+    let mut packet = header(id, FLAG_RESPONSE, 0, 4);
+    let mut target = Vec::new();
+    push_name(&mut target, &ad.instance);
+    push_record(&mut packet, SERVICE, TYPE_PTR, CLASS_IN, ttl, &target);
+
+    let mut srv = Vec::new();
+    srv.extend_from_slice(&[0, 0, 0, 0]);
+    srv.extend_from_slice(&ad.port.to_be_bytes());
+    push_name(&mut srv, &ad.host);
+    let unique = CLASS_IN | TOP_BIT;
+    push_record(&mut packet, &ad.instance, TYPE_SRV, unique, ttl, &srv);
+
+    let mut txt = Vec::new();
+    for entry in &ad.txt {
+        let entry = &entry.as_bytes()[..entry.len().min(255)];
+        txt.push(entry.len() as u8);
+        txt.extend_from_slice(entry);
+    }
+    push_record(&mut packet, &ad.instance, TYPE_TXT, unique, ttl, &txt);
+    push_record(
+        &mut packet,
+        &ad.host,
+        TYPE_A,
+        unique,
+        ttl,
+        &ad.address.octets(),
+    );
+    packet
+}
+
+fn query(id: u16) -> Vec<u8> {
+    let mut packet = header(id, 0, 1, 0);
+    push_name(&mut packet, SERVICE);
+    packet.extend_from_slice(&TYPE_PTR.to_be_bytes());
+    packet.extend_from_slice(&(CLASS_IN | TOP_BIT).to_be_bytes());
+    packet
+}
+
+// Read a possibly compressed name starting at `offset`; returns it and the offset after it
+fn read_name(packet: &[u8], mut offset: usize) -> Option<(String, usize)> {
+    let mut labels = Vec::new();
+    let mut end = None;
+    // Bounds the number of compression pointers followed, so a loop can't hang us
+    for _ in 0..64 {
+        let len = *packet.get(offset)? as usize;
+        if len == 0 {
+            return Some((labels.join("."), end.unwrap_or(offset + 1)));
+        }
+        if len & 0xc0 == 0xc0 {
+            let pointer = ((len & 0x3f) << 8) | *packet.get(offset + 1)? as usize;
+            end.get_or_insert(offset + 2);
+            offset = pointer;
+            continue;
+        }
+        let label = packet.get(offset + 1..offset + 1 + len)?;
+        labels.push(String::from_utf8_lossy(label).into_owned());
+        offset += 1 + len;
+    }
+    None
+}
+
+fn read_u16(packet: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_be_bytes(
+        packet.get(offset..offset + 2)?.try_into().ok()?,
+    ))
+}
+
+struct Question {
+    name: String,
+    kind: u16,
+}
+
+struct Record {
+    name: String,
+    kind: u16,
+    // Offset of the record data in the packet, for names compressed against earlier ones
+    data: usize,
+    len: usize,
+}
+
+struct Message {
+    id: u16,
+    response: bool,
+    questions: Vec<Question>,
+    records: Vec<Record>,
+}
+
+fn parse(packet: &[u8]) -> Option<Message> {
+    let id = read_u16(packet, 0)?;
+    let flags = read_u16(packet, 2)?;
+    let counts: Vec<u16> = (0..4)
+        .map(|i| read_u16(packet, 4 + i * 2))
+        .collect::<Option<_>>()?;
+    let mut offset = 12;
+    let mut questions = Vec::new();
+    for _ in 0..counts[0] {
+        let (name, next) = read_name(packet, offset)?;
+        questions.push(Question {
+            name,
+            kind: read_u16(packet, next)?,
+        });
+        offset = next + 4;
+    }
+    let mut records = Vec::new();
+    for _ in 0..counts[1] as usize + counts[2] as usize + counts[3] as usize {
+        let (name, next) = read_name(packet, offset)?;
+        let kind = read_u16(packet, next)?;
+        let len = read_u16(packet, next + 8)? as usize;
+        let data = next + 10;
+        packet.get(data..data + len)?;
+        records.push(Record {
+            name,
+            kind,
+            data,
+            len,
+        });
+        offset = data + len;
+    }
+    Some(Message {
+        id,
+        response: flags & 0x8000 != 0,
+        questions,
+        records,
+    })
+}
+
+fn multicast_socket() -> std::io::Result<UdpSocket> {
+    let socket = Socket::new(Domain::IPV4, Type::DGRAM, Some(Protocol::UDP))?;
+    // Share the port with the system's own responder
+    socket.set_reuse_address(true)?;
+    #[cfg(unix)]
+    socket.set_reuse_port(true)?;
+    socket.set_nonblocking(true)?;
+    socket.bind(&SocketAddr::from((Ipv4Addr::UNSPECIFIED, PORT)).into())?;
+    socket.join_multicast_v4(&GROUP, &Ipv4Addr::UNSPECIFIED)?;
+    socket.set_multicast_loop_v4(true)?;
+    socket.set_multicast_ttl_v4(255)?;
+    UdpSocket::from_std(socket.into())
+}
+
+fn asks_for(ad: &Advertisement, question: &Question) -> bool {
+    let matches = |name: &str| question.name.eq_ignore_ascii_case(name);
+    match question.kind {
+        TYPE_PTR => matches(SERVICE),
+        TYPE_SRV | TYPE_TXT => matches(&ad.instance),
+        TYPE_A => matches(&ad.host),
+        TYPE_ANY => matches(SERVICE) || matches(&ad.instance) || matches(&ad.host),
+        _ => false,
+    }
+}
+
+async fn respond(ad: Advertisement, mut shutdown: oneshot::Receiver<()>) {
+    let socket = match multicast_socket() {
+        Ok(socket) => socket,
+        Err(e) => {
+            tracing::warn!("mDNS advertising unavailable: {}", e);
+            return;
+        }
+    };
+    let group = SocketAddr::V4(SocketAddrV4::new(GROUP, PORT));
+    // Announce twice a second apart, as RFC 6762 asks, so listeners that missed one packet
+    // still see it
+    let mut announcements = 0;
+    let announce = tokio::time::sleep(Duration::ZERO);
+    tokio::pin!(announce);
+
+    let mut buffer = vec![0u8; 9000];
+    loop {
+        tokio::select! {
+            _ = &mut shutdown => break,
+            _ = &mut announce, if announcements < 2 => {
+                let _ = socket.send_to(&response(&ad, 0, TTL), group).await;
+                announcements += 1;
+                announce
+                    .as_mut()
+                    .reset(tokio::time::Instant::now() + Duration::from_secs(1));
+            }
+            received = socket.recv_from(&mut buffer) => {
+                let Ok((len, from)) = received else { continue };
+                let Some(message) = parse(&buffer[..len]) else { continue };
+                if message.response || !message.questions.iter().any(|q| asks_for(&ad, q)) {
+                    continue;
+                }
+                // Queries from a port other than 5353 come from one-shot resolvers, which
+                // only listen for a direct reply
+                let sent = if from.port() == PORT {
+                    socket.send_to(&response(&ad, 0, TTL), group).await
+                } else {
+                    socket.send_to(&response(&ad, message.id, LEGACY_TTL), from).await
+                };
+                if let Err(e) = sent {
+                    tracing::debug!("Failed to answer an mDNS query: {}", e);
+                }
+            }
+        }
+    }
+    let _ = socket.send_to(&response(&ad, 0, 0), group).await;
+    tracing::info!("Stopped advertising over mDNS");
+}
+
+// Announce the LAN endpoint on the local network until `withdraw` is called
+pub(super) fn advertise(address: Ipv4Addr, port: u16, tls: bool, version: &str) {
+    withdraw();
+    let id = instance_id();
+    let ad = Advertisement {
+        instance: format!("Eliza on {}.{}", machine_name(), SERVICE),
+        host: format!("eliza-{}.local", id),
+        address,
+        port,
+        txt: vec![
+            format!("id={}", id),
+            format!("version={}", version),
+            format!("tls={}", u8::from(tls)),
+            "path=/".to_string(),
+        ],
+    };
+    tracing::info!(instance = %ad.instance, "Advertising over mDNS");
+    let (shutdown, stopped) = oneshot::channel();
+    *RESPONDER.lock().unwrap() = Some(Responder { shutdown });
+    tauri::async_runtime::spawn(respond(ad, stopped));
+}
+
+pub(super) fn withdraw() {
+    if let Some(responder) = RESPONDER.lock().unwrap().take() {
+        let _ = responder.shutdown.send(());
+    }
+}
+
+#[derive(Default)]
+struct Found {
+    host: Option<String>,
+    port: u16,
+    txt: BTreeMap<String, String>,
+}
+
+async fn browse(timeout: Duration) -> Result<Vec<DiscoveredAgent>, String> {
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))
+        .await
+        .map_err(|e| format!("Failed to open a discovery socket: {}", e))?;
+    let id = (rand::thread_rng().next_u32() & 0xffff) as u16;
+    socket
+        .send_to(&query(id), SocketAddrV4::new(GROUP, PORT))
+        .await
+        .map_err(|e| format!("Failed to send the discovery query: {}", e))?;
+
+    let mut instances: BTreeMap<String, Found> = BTreeMap::new();
+    let mut hosts: BTreeMap<String, Ipv4Addr> = BTreeMap::new();
+    let mut buffer = vec![0u8; 9000];
+    let deadline = tokio::time::Instant::now() + timeout;
+    while let Ok(Ok((len, _))) =
+        tokio::time::timeout_at(deadline, socket.recv_from(&mut buffer)).await
+    {
+        let packet = &buffer[..len];
+        let Some(message) = parse(packet) else {
+            continue;
+        };
+        if !message.response {
+            continue;
+        }
+        for record in &message.records {
+            let data = &packet[record.data..record.data + record.len];
+            match record.kind {
+                TYPE_PTR if record.name.eq_ignore_ascii_case(SERVICE) => {
+                    if let Some((instance, _)) = read_name(packet, record.data) {
+                        instances.entry(instance).or_default();
+                    }
+                }
+                TYPE_SRV if data.len() > 6 => {
+                    let found = instances.entry(record.name.clone()).or_default();
+                    found.port = u16::from_be_bytes([data[4], data[5]]);
+                    found.host = read_name(packet, record.data + 6).map(|(host, _)| host);
+                }
+                TYPE_TXT => {
+                    let found = instances.entry(record.name.clone()).or_default();
+                    let mut rest = data;
+                    while let Some((&len, tail)) = rest.split_first() {
+                        let Some(entry) = tail.get(..len as usize) else {
+                            break;
+                        };
+                        if let Some((key, value)) = String::from_utf8_lossy(entry).split_once('=') {
+                            found.txt.insert(key.to_string(), value.to_string());
+                        }
+                        rest = &tail[len as usize..];
+                    }
+                }
+                TYPE_A if data.len() == 4 => {
+                    let address = Ipv4Addr::new(data[0], data[1], data[2], data[3]);
+                    hosts.insert(record.name.to_ascii_lowercase(), address);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    let own = instance_id();
+    Ok(instances
+        .into_iter()
+        .filter(|(_, found)| found.txt.get("id") != Some(&own))
+        .filter_map(|(instance, found)| {
+            let host = found.host?;
+            let address = IpAddr::V4(*hosts.get(&host.to_ascii_lowercase())?);
+            let tls = found.txt.get("tls").is_some_and(|tls| tls == "1");
+            let path = found.txt.get("path").cloned().unwrap_or_else(|| "/".into());
+            let scheme = if tls { "https" } else { "http" };
+            Some(DiscoveredAgent {
+                name: instance
+                    .strip_suffix(&format!(".{}", SERVICE))
+                    .unwrap_or(&instance)
+                    .to_string(),
+                url: format!(
+                    "{}://{}{}",
+                    scheme,
+                    SocketAddr::new(address, found.port),
+                    path
+                ),
+                host,
+                address,
+                port: found.port,
+                tls,
+                version: found.txt.get("version").cloned(),
+            })
+        })
+        .collect())
+}
+
+// Look for other copies of the app with LAN access on, asking over mDNS and collecting
+// answers for `timeout_ms`
+#[tauri::command]
+pub async fn discover_agents(timeout_ms: Option<u64>) -> Result<Vec<DiscoveredAgent>, AppError> {
+    let timeout = timeout_ms
+        .unwrap_or(DEFAULT_DISCOVERY_MS)
+        .clamp(100, MAX_DISCOVERY_MS);
+    browse(Duration::from_millis(timeout))
+        .await
+        .map_err(AppError::Network)
+}
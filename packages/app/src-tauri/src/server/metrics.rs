@@ -0,0 +1,120 @@
+use std::collections::{HashSet, VecDeque};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use sysinfo::{Pid, ProcessRefreshKind, ProcessesToUpdate, System};
+use tauri::{AppHandle, Emitter};
+
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(5);
+
+// One hour of samples at the default interval
+const MAX_SAMPLES: usize = 720;
+
+// Payload of the `server-metrics` event. Figures cover the elizaos process and every
+// process it spawned, since the CLI runs the server in a child node/bun process.
+#[derive(Debug, Clone, Serialize)]
+pub struct MetricsSample {
+    // Milliseconds since the Unix epoch
+    pub timestamp: u64,
+    pub pid: u32,
+    pub process_count: usize,
+    // Percent of one core, so it can exceed 100 on multi-core machines
+    pub cpu_percent: f32,
+    pub memory_bytes: u64,
+    // Not available on every platform
+    pub open_files: Option<usize>,
+}
+
+static HISTORY: Lazy<Mutex<VecDeque<MetricsSample>>> =
+    Lazy::new(|| Mutex::new(VecDeque::with_capacity(MAX_SAMPLES)));
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+// `root` and all of its descendants
+fn process_tree(system: &System, root: Pid) -> HashSet<Pid> {
+    let mut tree = HashSet::from([root]);
+    loop {
+        let before = tree.len();
+        for (pid, process) in system.processes() {
+            if process
+                .parent()
+                .is_some_and(|parent| tree.contains(&parent))
+            {
+                tree.insert(*pid);
+            }
+        }
+        if tree.len() == before {
+            return tree;
+        }
+    }
+}
+
+fn sample(system: &mut System, pid: u32) -> Option<MetricsSample> {
+    system.refresh_processes_specifics(
+        ProcessesToUpdate::All,
+        true,
+        ProcessRefreshKind::nothing().with_cpu().with_memory(),
+    );
+    let root = Pid::from_u32(pid);
+    system.process(root)?;
+
+    let tree = process_tree(system, root);
+    let processes: Vec<_> = tree.iter().filter_map(|pid| system.process(*pid)).collect();
+    let open_files = processes
+        .iter()
+        .map(|process| process.open_files())
+        .sum::<Option<usize>>();
+
+    Some(MetricsSample {
+        timestamp: now_millis(),
+        pid,
+        process_count: processes.len(),
+        cpu_percent: processes.iter().map(|process| process.cpu_usage()).sum(),
+        memory_bytes: processes.iter().map(|process| process.memory()).sum(),
+        open_files,
+    })
+}
+
+// Sample the managed server in the background for as long as the app runs
+pub fn spawn(app: AppHandle) {
+    thread::spawn(move || {
+        let mut system = System::new();
+        loop {
+            thread::sleep(SAMPLE_INTERVAL);
+            let Some(pid) = super::managed_pid() else {
+                continue;
+            };
+            let Some(sample) = sample(&mut system, pid) else {
+                continue;
+            };
+
+            {
+                let mut history = HISTORY.lock().unwrap();
+                if history.len() == MAX_SAMPLES {
+                    history.pop_front();
+                }
+                history.push_back(sample.clone());
+            }
+            if let Err(e) = app.emit("server-metrics", &sample) {
+                tracing::warn!("Failed to emit server metrics: {}", e);
+            }
+        }
+    });
+}
+
+pub fn latest() -> Option<MetricsSample> {
+    HISTORY.lock().unwrap().back().cloned()
+}
+
+#[tauri::command]
+pub fn get_server_metrics_history() -> Vec<MetricsSample> {
+    HISTORY.lock().unwrap().iter().cloned().collect()
+}
@@ -0,0 +1,148 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+
+use super::manager::DEFAULT_INSTANCE;
+
+// A migration counts as stalled once the server has said nothing about it for this long
+const QUIET_TIMEOUT: Duration = Duration::from_secs(3 * 60);
+// However busy it looks, a start is given up on after this long
+const MAX_DURATION: Duration = Duration::from_secs(60 * 60);
+
+static MIGRATING: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?i)\b(running|applying|executing|pending)\b.*\bmigrations?\b|\bmigrating\b")
+        .unwrap()
+});
+static FINISHED: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?i)\bmigrations?\b.*\b(complete|completed|finished|done|applied successfully)\b|\bno pending migrations\b")
+        .unwrap()
+});
+// `3/12`, `3 of 12`
+static STEP: Lazy<Regex> = Lazy::new(|| Regex::new(r"\b(\d+)\s*(?:/|of)\s*(\d+)\b").unwrap());
+
+struct Migration {
+    started: Instant,
+    last_activity: Instant,
+    // Done migrating, with the rest of the start still to come
+    finished: bool,
+    completed: Option<u64>,
+    total: Option<u64>,
+}
+
+static CURRENT: Mutex<Option<Migration>> = Mutex::new(None);
+
+// Payload of the `server-migrating` event, sent for each line the server logs about its
+// migration and once when it's over
+#[derive(Debug, Clone, Serialize)]
+struct MigrationProgress<'a> {
+    elapsed_ms: u64,
+    message: &'a str,
+    completed: Option<u64>,
+    total: Option<u64>,
+    finished: bool,
+}
+
+fn emit(app: &AppHandle, migration: &Migration, message: &str, finished: bool) {
+    let progress = MigrationProgress {
+        elapsed_ms: migration.started.elapsed().as_millis() as u64,
+        message,
+        completed: migration.completed,
+        total: migration.total,
+        finished,
+    };
+    if let Err(e) = app.emit("server-migrating", progress) {
+        tracing::warn!("Failed to emit migration progress: {}", e);
+    }
+}
+
+// Forget the last start's migration
+pub fn reset() {
+    *CURRENT.lock().unwrap() = None;
+}
+
+// Note a migration the server is running, whether it shows in its log or its health status
+fn record(app: &AppHandle, message: &str) {
+    let mut current = CURRENT.lock().unwrap();
+    let migration = current.get_or_insert_with(|| {
+        tracing::info!("The Eliza server is migrating its database");
+        Migration {
+            started: Instant::now(),
+            last_activity: Instant::now(),
+            finished: false,
+            completed: None,
+            total: None,
+        }
+    });
+    migration.last_activity = Instant::now();
+    migration.finished = false;
+    if let Some(step) = STEP.captures(message) {
+        let (completed, total) = (step[1].parse().ok(), step[2].parse().ok());
+        if completed <= total {
+            migration.completed = completed;
+            migration.total = total;
+        }
+    }
+    emit(app, migration, message, false);
+}
+
+// Watch the default instance's log for the start and end of a migration
+pub fn observe(app: &AppHandle, instance: &str, line: &str) {
+    if instance != DEFAULT_INSTANCE {
+        return;
+    }
+    if FINISHED.is_match(line) {
+        finish(app, line);
+    } else if MIGRATING.is_match(line) {
+        record(app, line);
+    }
+}
+
+// A health response saying the server is still migrating
+pub fn observe_health(app: &AppHandle, status: &str) {
+    record(app, &format!("The server reports status {}", status));
+}
+
+pub fn finish(app: &AppHandle, message: &str) {
+    let mut current = CURRENT.lock().unwrap();
+    if let Some(migration) = current.as_mut().filter(|migration| !migration.finished) {
+        // The server gets a quiet period from here to finish starting
+        migration.finished = true;
+        migration.last_activity = Instant::now();
+        tracing::info!(
+            "The Eliza server finished migrating after {:?}",
+            migration.started.elapsed()
+        );
+        emit(app, migration, message, true);
+    }
+}
+
+// Whether a start that's past its timeout should still be waited on
+pub fn in_progress() -> bool {
+    CURRENT.lock().unwrap().as_ref().is_some_and(|migration| {
+        migration.last_activity.elapsed() < QUIET_TIMEOUT
+            && migration.started.elapsed() < MAX_DURATION
+    })
+}
+
+// The error for a start that timed out during a migration
+pub fn stalled_error() -> Option<String> {
+    let current = CURRENT.lock().unwrap();
+    let migration = current.as_ref()?;
+    Some(if migration.finished {
+        "Eliza server did not become ready after migrating its database".to_string()
+    } else if migration.started.elapsed() >= MAX_DURATION {
+        format!(
+            "The database migration was still running after {} minutes",
+            MAX_DURATION.as_secs() / 60
+        )
+    } else {
+        format!(
+            "The database migration made no progress for {} minutes",
+            QUIET_TIMEOUT.as_secs() / 60
+        )
+    })
+}
@@ -0,0 +1,400 @@
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::time::Duration;
+
+use serde::Serialize;
+use tauri::AppHandle;
+
+use crate::audit::{self, AuditAction};
+use crate::error::AppError;
+use crate::startup::{self, Track};
+
+pub mod chat;
+pub mod config;
+pub mod container;
+pub mod external;
+pub mod gateway;
+pub mod health;
+pub mod idle;
+pub mod instances;
+pub mod lan;
+pub mod logs;
+pub mod manager;
+pub mod mdns;
+pub mod metrics;
+mod migrations;
+pub mod poller;
+pub mod port;
+pub mod profiles;
+pub mod prometheus;
+pub mod proxy;
+pub mod readiness;
+pub mod remote;
+pub mod runtime;
+pub(crate) mod shutdown;
+pub mod sidecar;
+mod supervisor;
+pub mod tailnet;
+pub mod usage;
+pub mod ws;
+
+use manager::{InstanceStatus, AGENTS, DEFAULT_INSTANCE};
+pub use supervisor::{LifecycleState, SUPERVISOR};
+
+// Check if an elizaOS server is answering on the configured address; this blocks on a fresh
+// health request, so code that only needs the latest state should ask the poller
+pub fn is_server_running() -> bool {
+    health::is_healthy()
+}
+
+// Whether the default instance we spawned is still alive
+fn is_managed_running() -> bool {
+    AGENTS.is_running(DEFAULT_INSTANCE)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ServerStatus {
+    // Spawned and supervised by the app
+    Running,
+    // An elizaOS server we didn't spawn is answering on the configured address
+    External,
+    Stopped,
+}
+
+// PID of the default instance we spawned, if it is still running
+pub fn managed_pid() -> Option<u32> {
+    AGENTS.pid(DEFAULT_INSTANCE)
+}
+
+pub fn status() -> ServerStatus {
+    if is_managed_running() {
+        ServerStatus::Running
+    } else if poller::is_healthy() {
+        ServerStatus::External
+    } else {
+        ServerStatus::Stopped
+    }
+}
+
+// What to run for one agent instance
+struct Launch<'a> {
+    id: &'a str,
+    port: u16,
+    characters: &'a [String],
+    // Where the instance keeps its database; the default instance uses the project's own
+    data_dir: Option<&'a Path>,
+}
+
+// Variables the server is given, in the order they override each other
+fn launch_env() -> Vec<(String, String)> {
+    // Provider calls go through the proxy to be counted, unless the user's additions point
+    // them elsewhere. Those go next so the variables the app manages below win.
+    let mut env: Vec<(String, String)> = gateway::server_env()
+        .into_iter()
+        .chain(crate::network::server_env())
+        .chain(crate::tls::server_env())
+        .map(|(key, value)| (key.to_string(), value))
+        .chain(config::current().extra_env)
+        .collect();
+    let (provider_env, secret_env) = (
+        crate::secrets::provider_env(),
+        crate::config::resolve_secret_env(),
+    );
+    // Keep the secrets the server is given out of its own log output
+    crate::redaction::add_secrets(
+        provider_env
+            .iter()
+            .map(|(_, value)| value.clone())
+            .chain(secret_env.iter().map(|(_, value)| value.clone())),
+    );
+    env.extend(
+        provider_env
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), value)),
+    );
+    env.extend(secret_env);
+    // Documents from registered knowledge folders are only picked up at startup
+    if !crate::settings::current().knowledge_paths.is_empty() {
+        env.push(("LOAD_DOCS_ON_STARTUP".to_string(), "true".to_string()));
+    }
+    env
+}
+
+// Spawn `elizaos start` as a child process
+fn spawn_process(
+    app: &AppHandle,
+    launch: &Launch,
+    characters: &[PathBuf],
+    env: Vec<(String, String)>,
+) -> Result<Child, String> {
+    let mut command = startup::measure(Track::Server, "resolve_cli", || {
+        if sidecar::enabled() {
+            sidecar::command(app)
+        } else if runtime::enabled() {
+            runtime::command(app)
+        } else {
+            let Some(cli) = crate::cli::resolve(app) else {
+                crate::cli::report_missing(app);
+                return Err(crate::i18n::t("server-cli-missing"));
+            };
+            Ok(Command::new(cli))
+        }
+    })?;
+    let program = PathBuf::from(command.get_program());
+
+    tracing::info!(
+        program = %program.display(),
+        "Starting Eliza server '{}' on port {}...",
+        launch.id,
+        launch.port
+    );
+    command.env("PATH", crate::cli::path::spawn_path(app, &program));
+    shutdown::prepare(&mut command);
+    command
+        .arg("start")
+        .arg("--port")
+        .arg(launch.port.to_string());
+    if !characters.is_empty() {
+        command.arg("--character").args(characters);
+    }
+    command.args(config::current().extra_args).envs(env);
+    if let Some(data_dir) = launch.data_dir {
+        command.env("PGLITE_DATA_DIR", data_dir.join(".elizadb"));
+    }
+    command
+        .env("KNOWLEDGE_PATH", crate::knowledge::knowledge_dir()?)
+        .current_dir(crate::workspace::dir()?)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    let child = startup::measure(Track::Server, "spawn", || command.spawn())
+        .map_err(|e| crate::i18n::t_args("server-spawn-failed", &[("error", &e.to_string())]))?;
+    shutdown::contain(&child);
+    Ok(child)
+}
+
+// Start an instance as a process or in a container, streaming its output to the frontend
+#[tracing::instrument(skip_all, fields(instance = launch.id, port = launch.port))]
+fn spawn_agent(app: &AppHandle, launch: &Launch) -> Result<(), String> {
+    let characters = launch
+        .characters
+        .iter()
+        .map(|character| crate::characters::resolve(character))
+        .collect::<Result<Vec<_>, _>>()?;
+    // Reads the provider keys from the keychain, which can be slow
+    let env = startup::measure(Track::Server, "prepare_env", launch_env);
+
+    let mut child = if container::enabled() {
+        // What's tracked is the process following the container's logs
+        startup::measure(Track::Server, "spawn", || {
+            container::spawn(
+                app,
+                &container::Launch {
+                    id: launch.id,
+                    port: launch.port,
+                    characters: &characters,
+                    data_dir: launch.data_dir,
+                    knowledge_dir: &crate::knowledge::knowledge_dir()?,
+                    env: &env,
+                },
+            )
+        })?
+    } else {
+        spawn_process(app, launch, &characters, env)?
+    };
+    logs::capture(app, launch.id, &mut child);
+
+    // Store the process so we can kill it when the app closes
+    AGENTS.insert(launch.id, child);
+    manager::emit_status(app, launch.id, InstanceStatus::Running);
+    tracing::info!("Eliza server process started");
+    Ok(())
+}
+
+// Gracefully stop an instance, killing it if it doesn't exit within the configured timeout
+#[tracing::instrument(skip(app))]
+fn halt(app: &AppHandle, id: &str) {
+    if let Some(mut child) = AGENTS.take(id) {
+        tracing::info!("Shutting down Eliza server '{}'...", id);
+        let timeout = Duration::from_millis(config::current().shutdown_timeout_ms);
+        // A container is stopped through its engine, after which its log follower exits
+        container::stop(app, id, timeout);
+        shutdown::terminate(app, &mut child, timeout);
+        manager::emit_status(app, id, InstanceStatus::Stopped);
+        audit::success(AuditAction::ServerStop, id);
+    }
+}
+
+// Kill every instance we spawned along with its child processes, for when a server hangs
+// and won't stop. Waits for a start or stop in progress, which is bounded by its timeouts,
+// so a spawn can't land after the kill and leave a live server recorded as stopped.
+fn force_kill_tree(app: &AppHandle) -> usize {
+    let _work = SUPERVISOR.work();
+    SUPERVISOR.finish(app, LifecycleState::Stopping);
+    let ids = AGENTS.ids();
+    for id in &ids {
+        if let Some(mut child) = AGENTS.take(id) {
+            tracing::warn!("Force killing Eliza server '{}' and its children", id);
+            container::kill(app, id);
+            shutdown::kill_now(app, &mut child);
+            manager::emit_status(app, id, InstanceStatus::Stopped);
+            audit::success(AuditAction::ServerStop, id);
+        }
+    }
+    readiness::mark_stopped();
+    prometheus::server_stopped();
+    SUPERVISOR.finish(app, LifecycleState::Stopped);
+    ids.len()
+}
+
+fn spawn_server(app: &AppHandle) -> Result<(), String> {
+    let config = config::current();
+    spawn_agent(
+        app,
+        &Launch {
+            id: DEFAULT_INSTANCE,
+            port: config.port,
+            characters: &config.characters,
+            data_dir: None,
+        },
+    )
+}
+
+fn stop_locked(app: &AppHandle) {
+    readiness::mark_stopped();
+    prometheus::server_stopped();
+    halt(app, DEFAULT_INSTANCE);
+}
+
+fn start_locked(app: &AppHandle) -> Result<(), String> {
+    if let Some(server) = remote::active() {
+        return Err(crate::i18n::t_args(
+            "server-remote-connected",
+            &[("url", &server.url)],
+        ));
+    }
+    if is_server_running() {
+        return Err(crate::i18n::t_args(
+            "server-external-running",
+            &[("address", &config::current().address())],
+        ));
+    }
+    startup::begin_server();
+    if let Err(e) = startup::measure(Track::Server, "port_check", || port::ensure_available(app)) {
+        readiness::fail(app, &e);
+        return Err(e);
+    }
+    match spawn_server(app) {
+        Ok(()) => {
+            readiness::track(app);
+            Ok(())
+        }
+        Err(e) => {
+            readiness::fail(app, &e);
+            Err(e)
+        }
+    }
+}
+
+// Record the outcome of a start that `SUPERVISOR` has moved to `Starting`
+fn finish_start(app: &AppHandle, result: Result<(), String>) -> Result<(), String> {
+    audit::record(AuditAction::ServerStart, DEFAULT_INSTANCE, &result);
+    let state = match result {
+        Ok(()) => {
+            prometheus::server_started();
+            LifecycleState::Running
+        }
+        Err(_) => LifecycleState::Stopped,
+    };
+    SUPERVISOR.finish(app, state);
+    result
+}
+
+#[tracing::instrument(name = "server_start", skip_all)]
+pub fn start(app: &AppHandle) -> Result<(), String> {
+    SUPERVISOR.begin(app, &[LifecycleState::Stopped], LifecycleState::Starting)?;
+    let _work = SUPERVISOR.work();
+    finish_start(app, start_locked(app))
+}
+
+// Gracefully stop the default server, killing it if it doesn't exit within the configured timeout
+#[tracing::instrument(name = "server_stop", skip_all)]
+pub fn stop(app: &AppHandle) -> Result<(), String> {
+    SUPERVISOR.begin(
+        app,
+        &[LifecycleState::Running, LifecycleState::Stopped],
+        LifecycleState::Stopping,
+    )?;
+    let _work = SUPERVISOR.work();
+    stop_locked(app);
+    SUPERVISOR.finish(app, LifecycleState::Stopped);
+    Ok(())
+}
+
+// Stop every instance we spawned; called when the app exits, whatever state the server is in
+#[tracing::instrument(name = "server_shutdown", skip_all)]
+pub fn shutdown_server(app: &AppHandle) {
+    let _work = SUPERVISOR.work();
+    SUPERVISOR.finish(app, LifecycleState::Stopping);
+    stop_locked(app);
+    for id in AGENTS.ids() {
+        halt(app, &id);
+    }
+    external::shutdown();
+    SUPERVISOR.finish(app, LifecycleState::Stopped);
+}
+
+#[tracing::instrument(name = "server_restart", skip_all)]
+pub fn restart(app: &AppHandle) -> Result<(), String> {
+    SUPERVISOR.begin(
+        app,
+        &[LifecycleState::Running, LifecycleState::Stopped],
+        LifecycleState::Stopping,
+    )?;
+    let _work = SUPERVISOR.work();
+    prometheus::server_restarted();
+    stop_locked(app);
+    SUPERVISOR.finish(app, LifecycleState::Starting);
+    finish_start(app, start_locked(app))
+}
+
+// Lifecycle operations block while waiting on the process, so keep them off the main thread;
+// their failures are reported as lifecycle errors
+async fn run_blocking<T: Send + 'static>(
+    task: impl FnOnce() -> Result<T, String> + Send + 'static,
+) -> Result<T, AppError> {
+    tauri::async_runtime::spawn_blocking(task)
+        .await?
+        .map_err(AppError::ServerLifecycle)
+}
+
+// Start the server, optionally with a new character selection that is remembered for next time
+#[tauri::command]
+pub async fn start_server(app: AppHandle, characters: Option<Vec<String>>) -> Result<(), AppError> {
+    if let Some(characters) = characters {
+        config::update(&app, |config| config.characters = characters)?;
+    }
+    run_blocking(move || start(&app)).await
+}
+
+#[tauri::command]
+pub async fn stop_server(app: AppHandle) -> Result<(), AppError> {
+    run_blocking(move || stop(&app)).await
+}
+
+#[tauri::command]
+pub async fn restart_server(app: AppHandle) -> Result<(), AppError> {
+    run_blocking(move || restart(&app)).await
+}
+
+// Where the default server is in its start/stop lifecycle
+#[tauri::command]
+pub fn get_server_lifecycle() -> LifecycleState {
+    SUPERVISOR.state()
+}
+
+// Recovery for a server that ignores `stop_server`; returns how many instances were killed
+#[tauri::command]
+pub async fn force_kill_server_tree(app: AppHandle) -> Result<usize, AppError> {
+    run_blocking(move || Ok(force_kill_tree(&app))).await
+}
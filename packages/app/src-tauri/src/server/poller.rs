@@ -0,0 +1,199 @@
+use std::time::Duration;
+
+use once_cell::sync::Lazy;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter};
+use tokio::sync::{watch, Notify};
+
+use super::config;
+use super::health::{self, ServerHealth};
+use crate::error::AppError;
+use crate::history::now_millis;
+
+// The `health_poll` part of the server config
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HealthPollConfig {
+    pub interval_ms: u64,
+    // Up to this much is added to each interval at random
+    pub jitter_ms: u64,
+    // How long one health request may take, here and in every other health check
+    pub timeout_ms: u64,
+    // Failed checks in a row before a healthy server is reported down
+    pub failure_threshold: u32,
+}
+
+impl Default for HealthPollConfig {
+    fn default() -> Self {
+        Self {
+            interval_ms: 5_000,
+            jitter_ms: 1_000,
+            timeout_ms: 2_000,
+            failure_threshold: 2,
+        }
+    }
+}
+
+impl HealthPollConfig {
+    pub fn timeout(&self) -> Duration {
+        Duration::from_millis(self.timeout_ms)
+    }
+
+    // The wait before the next check
+    fn delay(&self) -> Duration {
+        let jitter = match self.jitter_ms {
+            0 => 0,
+            jitter => rand::thread_rng().gen_range(0..=jitter),
+        };
+        Duration::from_millis(self.interval_ms + jitter)
+    }
+}
+
+// Payload of the `server-health-changed` event
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct HealthState {
+    pub healthy: bool,
+    // The last successful check while healthy
+    pub health: Option<ServerHealth>,
+    // Why the last check failed
+    pub error: Option<String>,
+    // When the last check finished, in milliseconds since the epoch
+    pub checked_at: Option<u64>,
+}
+
+impl HealthState {
+    // Whether subscribers need to hear about the change; latency alone doesn't count
+    fn differs(&self, other: &HealthState) -> bool {
+        let key = |state: &HealthState| {
+            state.health.as_ref().map(|health| {
+                (
+                    health.url.clone(),
+                    health.status.clone(),
+                    health.version.clone(),
+                    health.agent_count,
+                )
+            })
+        };
+        self.healthy != other.healthy || key(self) != key(other)
+    }
+}
+
+static STATE: Lazy<watch::Sender<HealthState>> =
+    Lazy::new(|| watch::channel(HealthState::default()).0);
+static WAKE: Lazy<Notify> = Lazy::new(Notify::new);
+
+// The last state the poller saw, without checking again
+pub fn current() -> HealthState {
+    STATE.borrow().clone()
+}
+
+pub fn is_healthy() -> bool {
+    STATE.borrow().healthy
+}
+
+// Changes as the poller reports them, for code that follows the server inside the app
+pub fn subscribe() -> watch::Receiver<HealthState> {
+    STATE.subscribe()
+}
+
+// Check again without waiting out the interval, e.g. after a start or a stop
+pub fn poke() {
+    WAKE.notify_one();
+}
+
+fn publish(app: &AppHandle, state: HealthState) {
+    let changed = STATE.send_if_modified(|current| {
+        let changed = current.differs(&state);
+        *current = state.clone();
+        changed
+    });
+    if !changed {
+        return;
+    }
+    tracing::info!(healthy = state.healthy, "Eliza server health changed");
+    if let Err(e) = app.emit("server-health-changed", &state) {
+        tracing::warn!("Failed to emit server health: {}", e);
+    }
+    #[cfg(desktop)]
+    crate::tray::refresh_status();
+}
+
+// Check the local or remote server's health in the background, reporting changes with
+// `server-health-changed`
+pub fn spawn(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        // Failed checks in a row, so a single slow response doesn't flap the status
+        let mut failures = 0;
+        loop {
+            let poll = config::current().health_poll;
+            let checked = health::check().await;
+            let previous = current();
+            let state = match checked {
+                Ok(health) => {
+                    failures = 0;
+                    HealthState {
+                        healthy: true,
+                        health: Some(health),
+                        error: None,
+                        checked_at: Some(now_millis()),
+                    }
+                }
+                Err(e) => {
+                    failures += 1;
+                    if previous.healthy && failures < poll.failure_threshold {
+                        tracing::debug!("Eliza server health check failed: {}", e);
+                        HealthState {
+                            error: Some(e),
+                            checked_at: Some(now_millis()),
+                            ..previous
+                        }
+                    } else {
+                        HealthState {
+                            healthy: false,
+                            health: None,
+                            error: Some(e),
+                            checked_at: Some(now_millis()),
+                        }
+                    }
+                }
+            };
+            publish(&app, state);
+
+            tokio::select! {
+                _ = tokio::time::sleep(poll.delay()) => {}
+                _ = WAKE.notified() => {}
+            }
+        }
+    });
+}
+
+#[tauri::command]
+pub fn get_server_health_state() -> HealthState {
+    current()
+}
+
+#[tauri::command]
+pub fn set_health_poll_config(
+    app: AppHandle,
+    poll: HealthPollConfig,
+) -> Result<HealthPollConfig, AppError> {
+    if poll.interval_ms < 500 {
+        return Err(AppError::Validation(
+            "The health check interval must be at least 500ms".to_string(),
+        ));
+    }
+    if poll.timeout_ms == 0 || poll.timeout_ms > 60_000 {
+        return Err(AppError::Validation(
+            "The health check timeout must be between 1ms and 60s".to_string(),
+        ));
+    }
+    if poll.failure_threshold == 0 {
+        return Err(AppError::Validation(
+            "The failure threshold must be at least 1".to_string(),
+        ));
+    }
+    let config = config::update(&app, |config| config.health_poll = poll)?;
+    poke();
+    Ok(config.health_poll)
+}
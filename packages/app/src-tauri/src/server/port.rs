@@ -0,0 +1,73 @@
+use std::net::TcpListener;
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+
+use super::config;
+use super::health;
+
+// How far above the configured port to look for a free one
+const PORT_SEARCH_RANGE: u16 = 100;
+
+// Payload of the `port-conflict` event
+#[derive(Debug, Clone, Serialize)]
+pub struct PortConflict {
+    pub port: u16,
+    pub pid: Option<u32>,
+    pub process_name: Option<String>,
+    // The port we moved to, if automatic selection is enabled and one was found
+    pub selected_port: Option<u16>,
+}
+
+pub fn is_port_free(host: &str, port: u16) -> bool {
+    TcpListener::bind((host, port)).is_ok()
+}
+
+fn find_free_port(host: &str, start: u16) -> Option<u16> {
+    (start.saturating_add(1)..=start.saturating_add(PORT_SEARCH_RANGE))
+        .find(|port| is_port_free(host, *port))
+}
+
+// Make sure nothing else owns the configured port before spawning. When another process
+// holds it, report who, and either move to a free port or refuse to start.
+pub fn ensure_available(app: &AppHandle) -> Result<(), String> {
+    let current = config::current();
+    if is_port_free(&current.host, current.port) {
+        return Ok(());
+    }
+
+    let owner = listeners::get_process_by_port(current.port, listeners::Protocol::TCP).ok();
+    let selected_port = if current.auto_select_port {
+        find_free_port(&current.host, current.port)
+    } else {
+        None
+    };
+
+    let conflict = PortConflict {
+        port: current.port,
+        pid: owner.as_ref().map(|process| process.pid),
+        process_name: owner.as_ref().map(|process| process.name.clone()),
+        selected_port,
+    };
+    let owner_label = match &owner {
+        Some(process) => format!("{} (pid {})", process.name, process.pid),
+        None => "another process".to_string(),
+    };
+    tracing::warn!("Port {} is in use by {}", current.port, owner_label);
+    if let Err(e) = app.emit("port-conflict", &conflict) {
+        tracing::warn!("Failed to emit port conflict: {}", e);
+    }
+
+    match selected_port {
+        Some(port) => {
+            config::update(app, |config| config.port = port)?;
+            tracing::info!("Using port {} instead", port);
+            let _ = app.emit("server-url-changed", health::base_url());
+            Ok(())
+        }
+        None => Err(format!(
+            "Port {} is in use by {}",
+            current.port, owner_label
+        )),
+    }
+}
@@ -0,0 +1,211 @@
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter};
+
+use super::container::{self, ContainerConfig, ServerBackend};
+use super::sidecar::{self, ServerRuntime};
+use super::{config, remote, LifecycleState, SUPERVISOR};
+use crate::capabilities::{self, Capability};
+use crate::error::AppError;
+use crate::history::new_id;
+
+// Always offered, so there's a way back to a plain local server
+const LOCAL_PROFILE: &str = "local";
+
+// How a profile runs or reaches its server
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum ProfileKind {
+    // A child process, from a CLI, the app's runtime or the bundled sidecar
+    Local { runtime: ServerRuntime },
+    Container { container: ContainerConfig },
+    // One of the servers added with `add_remote_server`
+    Remote { server_id: String },
+}
+
+// A named way of running the server that can be switched to in one step
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServerProfile {
+    pub id: String,
+    pub name: String,
+    #[serde(flatten)]
+    pub kind: ProfileKind,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProfileList {
+    pub profiles: Vec<ServerProfile>,
+    pub active: Option<String>,
+}
+
+// Payload of the `server-profile-changed` event
+#[derive(Debug, Clone, Serialize)]
+struct ProfileChanged<'a> {
+    profile: &'a ServerProfile,
+    // Why the new server didn't start, when it didn't
+    error: Option<String>,
+}
+
+fn local_profile() -> ServerProfile {
+    ServerProfile {
+        id: LOCAL_PROFILE.to_string(),
+        name: "Local".to_string(),
+        kind: ProfileKind::Local {
+            runtime: ServerRuntime::default(),
+        },
+    }
+}
+
+fn profiles() -> Vec<ServerProfile> {
+    let mut profiles = config::current().profiles;
+    if !profiles.iter().any(|profile| profile.id == LOCAL_PROFILE) {
+        profiles.insert(0, local_profile());
+    }
+    profiles
+}
+
+fn validate(kind: &ProfileKind) -> Result<(), AppError> {
+    match kind {
+        ProfileKind::Local {
+            runtime: ServerRuntime::Sidecar,
+        } if !sidecar::BUNDLED => Err(AppError::Validation(
+            "This build of the app doesn't include a bundled server".to_string(),
+        )),
+        ProfileKind::Local { .. } => Ok(()),
+        ProfileKind::Container { container } => {
+            container::validate_image(&container.image).map_err(AppError::Validation)
+        }
+        ProfileKind::Remote { server_id } => {
+            if config::current()
+                .remote_servers
+                .iter()
+                .any(|server| &server.id == server_id)
+            {
+                Ok(())
+            } else {
+                Err(AppError::NotFound(format!(
+                    "No remote server with id {}",
+                    server_id
+                )))
+            }
+        }
+    }
+}
+
+// Point the server config at the profile's backend
+fn apply(config: &mut config::ServerConfig, profile: &ServerProfile) {
+    config.active_profile = Some(profile.id.clone());
+    match &profile.kind {
+        ProfileKind::Local { runtime } => {
+            config.runtime = *runtime;
+            config.backend = ServerBackend::Process;
+            config.active_remote = None;
+        }
+        ProfileKind::Container { container } => {
+            config.backend = ServerBackend::Container;
+            config.container = container.clone();
+            config.active_remote = None;
+        }
+        ProfileKind::Remote { server_id } => {
+            config.active_remote = Some(server_id.clone());
+        }
+    }
+}
+
+#[tauri::command]
+pub fn list_profiles() -> ProfileList {
+    ProfileList {
+        profiles: profiles(),
+        active: config::current().active_profile,
+    }
+}
+
+#[tauri::command]
+pub fn create_profile(
+    app: AppHandle,
+    name: String,
+    kind: ProfileKind,
+) -> Result<ServerProfile, AppError> {
+    capabilities::require(Capability::RunPrograms)?;
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err(AppError::Validation("Give the profile a name".to_string()));
+    }
+    validate(&kind)?;
+    let profile = ServerProfile {
+        id: new_id(),
+        name,
+        kind,
+    };
+    config::update(&app, |config| config.profiles.push(profile.clone()))?;
+    Ok(profile)
+}
+
+#[tauri::command]
+pub fn delete_profile(app: AppHandle, id: String) -> Result<(), AppError> {
+    if id == LOCAL_PROFILE {
+        return Err(AppError::Validation(
+            "The local profile can't be deleted".to_string(),
+        ));
+    }
+    if config::current().active_profile.as_deref() == Some(id.as_str()) {
+        return Err(AppError::Validation(
+            "Switch to another profile before deleting this one".to_string(),
+        ));
+    }
+    let before = config::current().profiles.len();
+    let after = config::update(&app, |config| {
+        config.profiles.retain(|profile| profile.id != id)
+    })?
+    .profiles
+    .len();
+    if before == after {
+        return Err(AppError::NotFound(format!("No profile with id {}", id)));
+    }
+    Ok(())
+}
+
+// Stop whatever the current profile runs, switch the config over, then start the new
+// profile's server or connect to it
+#[tauri::command]
+pub async fn activate_profile(app: AppHandle, id: String) -> Result<ServerProfile, AppError> {
+    capabilities::require(Capability::RunPrograms)?;
+    let profile = profiles()
+        .into_iter()
+        .find(|profile| profile.id == id)
+        .ok_or_else(|| AppError::NotFound(format!("No profile with id {}", id)))?;
+    validate(&profile.kind)?;
+    tracing::info!(profile = %profile.id, "Switching to server profile '{}'", profile.name);
+
+    let handle = app.clone();
+    let switching = profile.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        // Nothing to tear down for a remote server or a local one that's already stopped
+        if SUPERVISOR.state() != LifecycleState::Stopped {
+            super::stop(&handle)?;
+        }
+        config::update(&handle, |config| apply(config, &switching))?;
+        Ok::<_, String>(())
+    })
+    .await?
+    .map_err(AppError::ServerLifecycle)?;
+
+    let started = match profile.kind {
+        ProfileKind::Remote { .. } => Ok(()),
+        _ => {
+            let handle = app.clone();
+            tauri::async_runtime::spawn_blocking(move || super::start(&handle)).await?
+        }
+    };
+    // The webview, WebSocket bridge and health checks follow the new server
+    remote::switched(&app).await;
+
+    let event = ProfileChanged {
+        profile: &profile,
+        error: started.as_ref().err().cloned(),
+    };
+    if let Err(e) = app.emit("server-profile-changed", event) {
+        tracing::warn!("Failed to emit profile change: {}", e);
+    }
+    started.map_err(AppError::ServerLifecycle)?;
+    Ok(profile)
+}
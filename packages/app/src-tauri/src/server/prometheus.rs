@@ -0,0 +1,375 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::net::SocketAddr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use axum::http::header;
+use axum::response::IntoResponse;
+use axum::routing::get;
+use axum::Router;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+use tokio::sync::oneshot;
+
+use super::{metrics, LifecycleState, SUPERVISOR};
+use crate::error::AppError;
+use crate::settings;
+
+// Upper bounds of the proxy latency histogram, in seconds
+const LATENCY_BUCKETS: [f64; 12] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0,
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MetricsEndpointSettings {
+    // Serve `/metrics` on localhost for Prometheus and similar scrapers
+    pub enabled: bool,
+    pub port: u16,
+}
+
+impl Default for MetricsEndpointSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: 9464,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct LatencyHistogram {
+    // Cumulative counts per bucket of `LATENCY_BUCKETS`
+    pub buckets: Vec<u64>,
+    pub count: u64,
+    pub sum_seconds: f64,
+}
+
+impl LatencyHistogram {
+    fn observe(&mut self, seconds: f64) {
+        if self.buckets.is_empty() {
+            self.buckets = vec![0; LATENCY_BUCKETS.len()];
+        }
+        for (bucket, bound) in self.buckets.iter_mut().zip(LATENCY_BUCKETS) {
+            if seconds <= bound {
+                *bucket += 1;
+            }
+        }
+        self.count += 1;
+        self.sum_seconds += seconds;
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MetricsSnapshot {
+    pub server_running: bool,
+    pub server_uptime_seconds: Option<f64>,
+    // Restarts asked for since the app started
+    pub server_restarts: u64,
+    // Proxied requests by status class, e.g. `2xx`
+    pub proxy_requests: BTreeMap<String, u64>,
+    // Time until the server sent response headers
+    pub proxy_latency: LatencyHistogram,
+    pub chat_streams: u64,
+    // Streamed chunks; most servers send one token or word per chunk
+    pub chat_tokens: u64,
+    pub chat_stream_seconds: f64,
+    // Throughput of the most recent stream
+    pub last_tokens_per_second: Option<f64>,
+    pub cpu_percent: Option<f32>,
+    pub memory_bytes: Option<u64>,
+    // Where `/metrics` is served; `None` while the endpoint is off
+    pub endpoint_url: Option<String>,
+}
+
+#[derive(Default)]
+struct Counters {
+    server_started: Option<Instant>,
+    server_restarts: u64,
+    proxy_requests: BTreeMap<String, u64>,
+    proxy_latency: LatencyHistogram,
+    chat_streams: u64,
+    chat_tokens: u64,
+    chat_stream_seconds: f64,
+    last_tokens_per_second: Option<f64>,
+}
+
+static COUNTERS: Lazy<Mutex<Counters>> = Lazy::new(Default::default);
+
+struct Listener {
+    url: String,
+    shutdown: oneshot::Sender<()>,
+}
+
+static LISTENER: Mutex<Option<Listener>> = Mutex::new(None);
+
+pub(super) fn server_started() {
+    COUNTERS.lock().unwrap().server_started = Some(Instant::now());
+}
+
+pub(super) fn server_stopped() {
+    COUNTERS.lock().unwrap().server_started = None;
+}
+
+pub(super) fn server_restarted() {
+    COUNTERS.lock().unwrap().server_restarts += 1;
+}
+
+pub(super) fn record_request(status: u16, elapsed: Duration) {
+    let mut counters = COUNTERS.lock().unwrap();
+    *counters
+        .proxy_requests
+        .entry(format!("{}xx", status / 100))
+        .or_default() += 1;
+    counters.proxy_latency.observe(elapsed.as_secs_f64());
+}
+
+// Counts the chunks of one chat stream and records them when dropped, so cancelled and
+// failed streams are counted too
+pub(super) struct StreamTimer {
+    started: Instant,
+    tokens: u64,
+}
+
+impl StreamTimer {
+    pub(super) fn start() -> Self {
+        StreamTimer {
+            started: Instant::now(),
+            tokens: 0,
+        }
+    }
+
+    pub(super) fn token(&mut self) {
+        self.tokens += 1;
+    }
+}
+
+impl Drop for StreamTimer {
+    fn drop(&mut self) {
+        let seconds = self.started.elapsed().as_secs_f64();
+        let mut counters = COUNTERS.lock().unwrap();
+        counters.chat_streams += 1;
+        counters.chat_tokens += self.tokens;
+        counters.chat_stream_seconds += seconds;
+        if self.tokens > 0 && seconds > 0.0 {
+            counters.last_tokens_per_second = Some(self.tokens as f64 / seconds);
+        }
+    }
+}
+
+fn snapshot() -> MetricsSnapshot {
+    let counters = COUNTERS.lock().unwrap();
+    let server_running = SUPERVISOR.state() == LifecycleState::Running;
+    let sample = metrics::latest();
+    MetricsSnapshot {
+        server_running,
+        server_uptime_seconds: counters
+            .server_started
+            .filter(|_| server_running)
+            .map(|started| started.elapsed().as_secs_f64()),
+        server_restarts: counters.server_restarts,
+        proxy_requests: counters.proxy_requests.clone(),
+        proxy_latency: counters.proxy_latency.clone(),
+        chat_streams: counters.chat_streams,
+        chat_tokens: counters.chat_tokens,
+        chat_stream_seconds: counters.chat_stream_seconds,
+        last_tokens_per_second: counters.last_tokens_per_second,
+        cpu_percent: sample.as_ref().map(|sample| sample.cpu_percent),
+        memory_bytes: sample.as_ref().map(|sample| sample.memory_bytes),
+        endpoint_url: LISTENER
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|listener| listener.url.clone()),
+    }
+}
+
+fn metric(out: &mut String, name: &str, kind: &str, help: &str, value: impl std::fmt::Display) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+    let _ = writeln!(out, "{} {}", name, value);
+}
+
+// The snapshot in the Prometheus text exposition format
+fn render(snapshot: &MetricsSnapshot) -> String {
+    let mut out = String::new();
+    metric(
+        &mut out,
+        "eliza_server_up",
+        "gauge",
+        "Whether the managed elizaOS server is running.",
+        u8::from(snapshot.server_running),
+    );
+    metric(
+        &mut out,
+        "eliza_server_uptime_seconds",
+        "gauge",
+        "Seconds since the managed server last started.",
+        snapshot.server_uptime_seconds.unwrap_or(0.0),
+    );
+    metric(
+        &mut out,
+        "eliza_server_restarts_total",
+        "counter",
+        "Server restarts since the app started.",
+        snapshot.server_restarts,
+    );
+    if let Some(cpu) = snapshot.cpu_percent {
+        metric(
+            &mut out,
+            "eliza_server_cpu_percent",
+            "gauge",
+            "CPU use of the server process tree, in percent of one core.",
+            cpu,
+        );
+    }
+    if let Some(memory) = snapshot.memory_bytes {
+        metric(
+            &mut out,
+            "eliza_server_memory_bytes",
+            "gauge",
+            "Memory used by the server process tree.",
+            memory,
+        );
+    }
+
+    out.push_str("# HELP eliza_proxy_requests_total Requests forwarded by the proxy.\n");
+    out.push_str("# TYPE eliza_proxy_requests_total counter\n");
+    for (class, count) in &snapshot.proxy_requests {
+        let _ = writeln!(
+            out,
+            "eliza_proxy_requests_total{{status=\"{}\"}} {}",
+            class, count
+        );
+    }
+    let latency = &snapshot.proxy_latency;
+    out.push_str(
+        "# HELP eliza_proxy_request_duration_seconds Time until the server answered a proxied request.\n",
+    );
+    out.push_str("# TYPE eliza_proxy_request_duration_seconds histogram\n");
+    for (i, bound) in LATENCY_BUCKETS.iter().enumerate() {
+        let _ = writeln!(
+            out,
+            "eliza_proxy_request_duration_seconds_bucket{{le=\"{}\"}} {}",
+            bound,
+            latency.buckets.get(i).copied().unwrap_or(0)
+        );
+    }
+    let _ = writeln!(
+        out,
+        "eliza_proxy_request_duration_seconds_bucket{{le=\"+Inf\"}} {}",
+        latency.count
+    );
+    let _ = writeln!(
+        out,
+        "eliza_proxy_request_duration_seconds_sum {}",
+        latency.sum_seconds
+    );
+    let _ = writeln!(
+        out,
+        "eliza_proxy_request_duration_seconds_count {}",
+        latency.count
+    );
+
+    metric(
+        &mut out,
+        "eliza_chat_streams_total",
+        "counter",
+        "Streamed chat replies.",
+        snapshot.chat_streams,
+    );
+    metric(
+        &mut out,
+        "eliza_chat_tokens_total",
+        "counter",
+        "Chunks received from streamed chat replies.",
+        snapshot.chat_tokens,
+    );
+    metric(
+        &mut out,
+        "eliza_chat_stream_seconds_total",
+        "counter",
+        "Time spent streaming chat replies.",
+        snapshot.chat_stream_seconds,
+    );
+    out
+}
+
+async fn serve_metrics() -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        render(&snapshot()),
+    )
+}
+
+fn stop() {
+    if let Some(listener) = LISTENER.lock().unwrap().take() {
+        let _ = listener.shutdown.send(());
+        tracing::info!("Metrics endpoint stopped");
+    }
+}
+
+async fn start(port: u16) -> Result<(), String> {
+    let address = SocketAddr::from(([127, 0, 0, 1], port));
+    let listener = tokio::net::TcpListener::bind(address)
+        .await
+        .map_err(|e| format!("Failed to serve metrics on {}: {}", address, e))?;
+    let url = format!("http://{}/metrics", address);
+    tracing::info!(%url, "Metrics endpoint started");
+
+    let router = Router::new().route("/metrics", get(serve_metrics));
+    let (shutdown, stopped) = oneshot::channel();
+    *LISTENER.lock().unwrap() = Some(Listener { url, shutdown });
+    tauri::async_runtime::spawn(async move {
+        let served = axum::serve(listener, router)
+            .with_graceful_shutdown(async {
+                let _ = stopped.await;
+            })
+            .await;
+        if let Err(e) = served {
+            tracing::error!("Metrics endpoint failed: {}", e);
+        }
+    });
+    Ok(())
+}
+
+// Serve `/metrics` if it is enabled; called once from the setup hook
+pub fn init() {
+    let config = settings::current().metrics_endpoint;
+    if !config.enabled {
+        return;
+    }
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = start(config.port).await {
+            tracing::error!("{}", e);
+        }
+    });
+}
+
+#[tauri::command]
+pub fn get_metrics() -> MetricsSnapshot {
+    snapshot()
+}
+
+// Turn the `/metrics` endpoint on or off, or move it to another port
+#[tauri::command]
+pub async fn configure_metrics_endpoint(
+    app: AppHandle,
+    enabled: bool,
+    port: Option<u16>,
+) -> Result<MetricsSnapshot, AppError> {
+    let config = settings::update(&app, |settings| {
+        settings.metrics_endpoint.enabled = enabled;
+        if let Some(port) = port {
+            settings.metrics_endpoint.port = port;
+        }
+    })?
+    .metrics_endpoint;
+    stop();
+    if config.enabled {
+        start(config.port).await.map_err(AppError::Network)?;
+    }
+    Ok(snapshot())
+}
@@ -0,0 +1,504 @@
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use axum::body::{to_bytes, Body};
+use axum::extract::{Request, State};
+use axum::http::{header, HeaderMap, HeaderName, HeaderValue, Method, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::any;
+use axum::Router;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use once_cell::sync::{Lazy, OnceCell};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter};
+
+use super::{gateway, health, prometheus, readiness};
+use crate::error::AppError;
+use crate::webhooks::same_secret;
+use crate::{redaction, settings, tls};
+
+// Header the elizaOS server checks against `ELIZA_SERVER_AUTH_TOKEN`
+pub(super) const AUTH_HEADER: &str = "x-api-key";
+pub(super) const AUTH_TOKEN_VAR: &str = "ELIZA_SERVER_AUTH_TOKEN";
+const MAX_BODY_BYTES: usize = 50 * 1024 * 1024;
+// How long a request is held while the server restarts before giving up
+const RESTART_GRACE: Duration = Duration::from_secs(30);
+const RETRY_DELAY: Duration = Duration::from_millis(250);
+// The keychain is slow to query, so the token is looked up at most this often
+const TOKEN_TTL: Duration = Duration::from_secs(30);
+// Header the webview sends the per-launch key in
+const KEY_HEADER: &str = "x-eliza-proxy-key";
+// Where the app's own webview is loaded from
+const APP_ORIGINS: &[&str] = &[
+    "tauri://localhost",
+    "http://tauri.localhost",
+    "https://tauri.localhost",
+];
+// `devUrl` in tauri.conf.json
+const DEV_ORIGIN: &str = "http://localhost:1420";
+
+// Connection-level headers that must not be forwarded
+const HOP_BY_HOP: &[HeaderName] = &[
+    header::CONNECTION,
+    header::HOST,
+    header::TRANSFER_ENCODING,
+    header::UPGRADE,
+    header::TE,
+    header::TRAILER,
+    header::PROXY_AUTHORIZATION,
+];
+
+static PROXY_URL: OnceCell<String> = OnceCell::new();
+// Created fresh each launch and only handed to the webview and the server the app starts, so
+// other programs on this machine can't use the proxy's credentials
+static PROXY_KEY: Lazy<String> = Lazy::new(|| {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    let key = URL_SAFE_NO_PAD.encode(bytes);
+    // It's in the provider addresses the server may log
+    redaction::add_secrets([key.clone()]);
+    key
+});
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProxySettings {
+    // 0 picks a free port at startup
+    pub port: u16,
+    // Sustained requests per second; 0 disables rate limiting
+    pub rate_limit: u32,
+    pub burst: u32,
+    pub log_requests: bool,
+}
+
+impl Default for ProxySettings {
+    fn default() -> Self {
+        Self {
+            port: 0,
+            rate_limit: 50,
+            burst: 100,
+            log_requests: false,
+        }
+    }
+}
+
+// Token bucket shared by all requests
+pub(super) struct RateLimiter {
+    tokens: f64,
+    refilled: Instant,
+}
+
+impl RateLimiter {
+    pub(super) fn new(burst: u32) -> Self {
+        RateLimiter {
+            tokens: f64::from(burst),
+            refilled: Instant::now(),
+        }
+    }
+
+    pub(super) fn try_acquire(&mut self, rate: u32, burst: u32) -> bool {
+        if rate == 0 {
+            return true;
+        }
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.refilled).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * f64::from(rate)).min(f64::from(burst.max(1)));
+        self.refilled = now;
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+struct ProxyState {
+    client: reqwest::Client,
+    limiter: Mutex<RateLimiter>,
+    token: Mutex<Option<(Instant, Option<String>)>>,
+}
+
+impl ProxyState {
+    async fn auth_token(&self) -> Option<String> {
+        // Remote keys are cached by their own module; a cached local token must never be
+        // sent to a remote server
+        if super::remote::is_remote() {
+            return super::remote::auth_token().await.unwrap_or_default();
+        }
+        let cached = self.token.lock().unwrap().clone();
+        if let Some((fetched, token)) = cached {
+            if fetched.elapsed() < TOKEN_TTL {
+                return token;
+            }
+        }
+        let token = tauri::async_runtime::spawn_blocking(|| crate::config::lookup(AUTH_TOKEN_VAR))
+            .await
+            .map_err(|e| e.to_string())
+            .and_then(|token| token)
+            .unwrap_or_else(|e| {
+                tracing::warn!("{}", e);
+                None
+            });
+        *self.token.lock().unwrap() = Some((Instant::now(), token.clone()));
+        token
+    }
+}
+
+fn error_response(status: StatusCode, message: impl Into<String>) -> Response {
+    (status, message.into()).into_response()
+}
+
+pub(super) fn forward_headers(source: &HeaderMap, target: &mut HeaderMap) {
+    for (name, value) in source {
+        if !HOP_BY_HOP.contains(name) {
+            target.append(name.clone(), value.clone());
+        }
+    }
+}
+
+pub(super) fn key() -> &'static str {
+    &PROXY_KEY
+}
+
+pub(super) fn is_key(presented: &str) -> bool {
+    same_secret(&PROXY_KEY, presented)
+}
+
+fn allowed_origin(origin: &str, port: u16) -> bool {
+    if APP_ORIGINS.contains(&origin) || (cfg!(debug_assertions) && origin == DEV_ORIGIN) {
+        return true;
+    }
+    // Pages served through the proxy itself
+    ["http", "https"].iter().any(|scheme| {
+        ["127.0.0.1", "localhost"]
+            .iter()
+            .any(|host| origin == format!("{}://{}:{}", scheme, host, port))
+    })
+}
+
+// Answer a CORS preflight from the app, which can't carry the key itself
+fn preflight(origin: &HeaderValue, headers: &HeaderMap) -> Response {
+    let mut response = StatusCode::NO_CONTENT.into_response();
+    let cors = response.headers_mut();
+    cors.insert(header::ACCESS_CONTROL_ALLOW_ORIGIN, origin.clone());
+    for (request, allow) in [
+        (
+            header::ACCESS_CONTROL_REQUEST_METHOD,
+            header::ACCESS_CONTROL_ALLOW_METHODS,
+        ),
+        (
+            header::ACCESS_CONTROL_REQUEST_HEADERS,
+            header::ACCESS_CONTROL_ALLOW_HEADERS,
+        ),
+    ] {
+        if let Some(value) = headers.get(request) {
+            cors.insert(allow, value.clone());
+        }
+    }
+    cors.insert(
+        header::ACCESS_CONTROL_MAX_AGE,
+        HeaderValue::from_static("600"),
+    );
+    response
+}
+
+// The localhost listener only serves the app. Other programs on the machine lack the key,
+// and a web page reaching 127.0.0.1 through DNS rebinding sends its own Host and Origin.
+async fn guard_local(State(port): State<u16>, request: Request, next: Next) -> Response {
+    let host = request
+        .headers()
+        .get(header::HOST)
+        .and_then(|value| value.to_str().ok())
+        .or_else(|| {
+            request
+                .uri()
+                .authority()
+                .map(|authority| authority.as_str())
+        });
+    let expected =
+        |host: &str| host == format!("127.0.0.1:{}", port) || host == format!("localhost:{}", port);
+    if !host.is_some_and(expected) {
+        return error_response(StatusCode::MISDIRECTED_REQUEST, "Unexpected Host header");
+    }
+    let origin = request.headers().get(header::ORIGIN).cloned();
+    if let Some(origin) = &origin {
+        if !origin
+            .to_str()
+            .is_ok_and(|origin| allowed_origin(origin, port))
+        {
+            return error_response(StatusCode::FORBIDDEN, "Origin not allowed");
+        }
+        if request.method() == Method::OPTIONS
+            && request
+                .headers()
+                .contains_key(header::ACCESS_CONTROL_REQUEST_METHOD)
+        {
+            return preflight(origin, request.headers());
+        }
+    }
+    // Provider calls carry the key in their path, which the gateway checks
+    if request.uri().path().starts_with("/providers/") {
+        return next.run(request).await;
+    }
+    let presented = request
+        .headers()
+        .get(KEY_HEADER)
+        .and_then(|value| value.to_str().ok());
+    if !presented.is_some_and(is_key) {
+        return error_response(StatusCode::UNAUTHORIZED, "Missing or invalid proxy key");
+    }
+    next.run(request).await
+}
+
+// Forward a webview request to the server, holding it while the server restarts
+async fn forward(State(state): State<Arc<ProxyState>>, request: Request) -> Response {
+    let proxy = settings::current().proxy;
+    if !state
+        .limiter
+        .lock()
+        .unwrap()
+        .try_acquire(proxy.rate_limit, proxy.burst)
+    {
+        return error_response(StatusCode::TOO_MANY_REQUESTS, "Rate limit exceeded");
+    }
+
+    let started = Instant::now();
+    let (parts, body) = request.into_parts();
+    let body = match to_bytes(body, MAX_BODY_BYTES).await {
+        Ok(body) => body,
+        Err(e) => return error_response(StatusCode::PAYLOAD_TOO_LARGE, e.to_string()),
+    };
+    let path = parts
+        .uri
+        .path_and_query()
+        .map(|pq| pq.as_str())
+        .unwrap_or("/");
+
+    let deadline = started + RESTART_GRACE;
+    let response = loop {
+        // Built per attempt, since a restart may have moved the server to another port
+        let mut outgoing = state
+            .client
+            .request(
+                parts.method.clone(),
+                format!("{}{}", health::base_url(), path),
+            )
+            .body(body.clone());
+        if let Some(token) = state.auth_token().await {
+            outgoing = outgoing.header(AUTH_HEADER, token);
+        }
+        let mut outgoing = match outgoing.build() {
+            Ok(outgoing) => outgoing,
+            Err(e) => break Err(e),
+        };
+        forward_headers(&parts.headers, outgoing.headers_mut());
+        outgoing.headers_mut().remove(KEY_HEADER);
+
+        match state.client.execute(outgoing).await {
+            Err(e) if e.is_connect() && Instant::now() < deadline => {
+                let remaining = deadline.saturating_duration_since(Instant::now());
+                let _ = readiness::wait_for_server_ready(remaining.as_millis() as u64).await;
+                tokio::time::sleep(RETRY_DELAY).await;
+            }
+            result => break result,
+        }
+    };
+
+    let response = match response {
+        Ok(upstream) => {
+            let mut response = Response::builder().status(upstream.status());
+            if let Some(headers) = response.headers_mut() {
+                forward_headers(upstream.headers(), headers);
+            }
+            response
+                .body(Body::from_stream(upstream.bytes_stream()))
+                .unwrap_or_else(|e| error_response(StatusCode::BAD_GATEWAY, e.to_string()))
+        }
+        Err(e) => error_response(
+            StatusCode::BAD_GATEWAY,
+            format!("The elizaOS server is unavailable: {}", e),
+        ),
+    };
+
+    prometheus::record_request(response.status().as_u16(), started.elapsed());
+    if proxy.log_requests {
+        tracing::info!(
+            target: "proxy",
+            method = %parts.method,
+            path,
+            status = response.status().as_u16(),
+            elapsed_ms = started.elapsed().as_millis() as u64,
+            "Proxied request"
+        );
+    }
+    response
+}
+
+// Routes forwarding to the server and to model providers, each router with its own rate limit
+pub(super) fn router(app: &AppHandle) -> Router {
+    let state = Arc::new(ProxyState {
+        client: reqwest::Client::new(),
+        limiter: Mutex::new(RateLimiter::new(settings::current().proxy.burst)),
+        token: Mutex::new(None),
+    });
+    Router::new()
+        .route(
+            gateway::ROUTE,
+            any(gateway::forward).with_state(app.clone()),
+        )
+        .fallback(forward)
+        .with_state(state)
+}
+
+// Start the proxy on localhost; the webview uses `get_proxy_url` instead of the server address
+// and sends the key it returns with every request
+pub fn spawn(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let address = SocketAddr::from(([127, 0, 0, 1], settings::current().proxy.port));
+        let listener = match tokio::net::TcpListener::bind(address).await {
+            Ok(listener) => listener,
+            Err(e) => {
+                tracing::error!("Failed to start the proxy on {}: {}", address, e);
+                return;
+            }
+        };
+        let acceptor = tls::acceptor();
+        let local = match listener.local_addr() {
+            Ok(local) => local,
+            Err(e) => {
+                tracing::error!("Failed to read the proxy address: {}", e);
+                return;
+            }
+        };
+        let url = format!("{}://{}", tls::scheme(&acceptor), local);
+        let router = router(&app).layer(middleware::from_fn_with_state(local.port(), guard_local));
+        tracing::info!(%url, "Proxy listening");
+        let _ = PROXY_URL.set(url.clone());
+        let _ = app.emit("proxy-ready", &url);
+
+        if let Err(e) = tls::serve(listener, router, acceptor, std::future::pending()).await {
+            tracing::error!("Proxy stopped: {}", e);
+        }
+    });
+}
+
+pub fn url() -> Result<String, String> {
+    PROXY_URL
+        .get()
+        .cloned()
+        .ok_or_else(|| "The proxy is not running".to_string())
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProxyEndpoint {
+    pub url: String,
+    // Sent in the `header` header with every request
+    pub key: String,
+    pub header: &'static str,
+}
+
+#[tauri::command]
+pub fn get_proxy_url() -> Result<ProxyEndpoint, AppError> {
+    Ok(ProxyEndpoint {
+        url: url().map_err(AppError::ServerLifecycle)?,
+        key: key().to_string(),
+        header: KEY_HEADER,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn limiter_allows_a_burst_then_refuses() {
+        let mut limiter = RateLimiter::new(3);
+        for _ in 0..3 {
+            assert!(limiter.try_acquire(1, 3));
+        }
+        assert!(!limiter.try_acquire(1, 3));
+    }
+
+    #[test]
+    fn limiter_refills_at_the_rate() {
+        let mut limiter = RateLimiter::new(2);
+        assert!(limiter.try_acquire(10, 2));
+        assert!(limiter.try_acquire(10, 2));
+        assert!(!limiter.try_acquire(10, 2));
+        // 150ms at 10 per second is one more call
+        limiter.refilled -= Duration::from_millis(150);
+        assert!(limiter.try_acquire(10, 2));
+        assert!(!limiter.try_acquire(10, 2));
+    }
+
+    #[test]
+    fn limiter_never_saves_up_more_than_the_burst() {
+        let mut limiter = RateLimiter::new(2);
+        limiter.refilled -= Duration::from_secs(60);
+        assert!(limiter.try_acquire(100, 2));
+        assert!(limiter.try_acquire(100, 2));
+        assert!(!limiter.try_acquire(100, 2));
+        // A smaller burst applies straight away
+        let mut limiter = RateLimiter::new(10);
+        assert!(limiter.try_acquire(1, 1));
+        assert!(!limiter.try_acquire(1, 1));
+    }
+
+    #[test]
+    fn limiter_with_no_rate_is_off() {
+        let mut limiter = RateLimiter::new(0);
+        for _ in 0..1000 {
+            assert!(limiter.try_acquire(0, 0));
+        }
+        // A zero burst still lets calls through one at a time
+        let mut limiter = RateLimiter::new(0);
+        limiter.refilled -= Duration::from_secs(1);
+        assert!(limiter.try_acquire(5, 0));
+        assert!(!limiter.try_acquire(5, 0));
+    }
+
+    #[test]
+    fn drops_hop_by_hop_headers() {
+        let mut source = HeaderMap::new();
+        source.insert(header::HOST, HeaderValue::from_static("127.0.0.1:3000"));
+        source.insert(header::CONNECTION, HeaderValue::from_static("keep-alive"));
+        source.insert(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("application/json"),
+        );
+        source.append(header::ACCEPT, HeaderValue::from_static("text/html"));
+        source.append(header::ACCEPT, HeaderValue::from_static("application/json"));
+        let mut target = HeaderMap::new();
+        forward_headers(&source, &mut target);
+        assert!(target.get(header::HOST).is_none());
+        assert!(target.get(header::CONNECTION).is_none());
+        assert_eq!(target[header::CONTENT_TYPE], "application/json");
+        assert_eq!(target.get_all(header::ACCEPT).iter().count(), 2);
+    }
+
+    #[test]
+    fn origins() {
+        for origin in [
+            "tauri://localhost",
+            "http://tauri.localhost",
+            "http://127.0.0.1:4123",
+            "https://localhost:4123",
+        ] {
+            assert!(allowed_origin(origin, 4123), "{}", origin);
+        }
+        for origin in [
+            "http://127.0.0.1:4124",
+            "http://localhost",
+            "https://evil.example",
+            "http://localhost:4123.evil.example",
+            "null",
+        ] {
+            assert!(!allowed_origin(origin, 4123), "{}", origin);
+        }
+    }
+}
@@ -0,0 +1,199 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+use tokio::sync::watch;
+
+use super::config;
+use super::health::{self, ServerHealth};
+use super::migrations;
+use crate::error::AppError;
+use crate::startup::{self, Track};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "state", rename_all = "lowercase")]
+pub enum Readiness {
+    Idle,
+    Starting,
+    Ready,
+    Failed { error: String },
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct StartupEvent {
+    elapsed_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    health: Option<ServerHealth>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+static READINESS: Lazy<watch::Sender<Readiness>> = Lazy::new(|| watch::channel(Readiness::Idle).0);
+
+// Bumped on every start/stop so a stale poller can tell it has been superseded
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+fn emit(app: &AppHandle, event: &str, payload: StartupEvent) {
+    if let Err(e) = app.emit(event, payload) {
+        tracing::warn!("Failed to emit {}: {}", event, e);
+    }
+}
+
+// Record the outcome unless a newer start/stop has happened in the meantime
+fn finish(generation: u64, state: Readiness) -> bool {
+    if GENERATION.load(Ordering::SeqCst) != generation {
+        return false;
+    }
+    READINESS.send_replace(state);
+    true
+}
+
+pub fn mark_stopped() {
+    GENERATION.fetch_add(1, Ordering::SeqCst);
+    migrations::reset();
+    READINESS.send_replace(Readiness::Idle);
+    super::poller::poke();
+}
+
+pub fn fail(app: &AppHandle, error: &str) {
+    let generation = GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+    finish(
+        generation,
+        Readiness::Failed {
+            error: error.to_string(),
+        },
+    );
+    emit(
+        app,
+        "server-start-failed",
+        StartupEvent {
+            elapsed_ms: 0,
+            health: None,
+            error: Some(error.to_string()),
+        },
+    );
+}
+
+// Poll the health endpoint until the server answers, its process exits, or we time out
+pub fn track(app: &AppHandle) {
+    let generation = GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+    READINESS.send_replace(Readiness::Starting);
+    migrations::reset();
+    emit(
+        app,
+        "server-starting",
+        StartupEvent {
+            elapsed_ms: 0,
+            health: None,
+            error: None,
+        },
+    );
+
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let started = Instant::now();
+        let timeout = Duration::from_millis(config::current().startup_timeout_ms);
+
+        let error = loop {
+            if GENERATION.load(Ordering::SeqCst) != generation {
+                return;
+            }
+            match health::check().await {
+                // Some servers answer while they migrate, saying so in their status
+                Ok(health) if health.status.eq_ignore_ascii_case("migrating") => {
+                    migrations::observe_health(&app, &health.status);
+                }
+                Ok(health) => {
+                    migrations::finish(&app, "The server is ready");
+                    startup::record(Track::Server, "first_healthy", started);
+                    startup::server_ready();
+                    if finish(generation, Readiness::Ready) {
+                        super::poller::poke();
+                        tracing::info!("Eliza server ready after {:?}", started.elapsed());
+                        emit(
+                            &app,
+                            "server-ready",
+                            StartupEvent {
+                                elapsed_ms: started.elapsed().as_millis() as u64,
+                                health: Some(health),
+                                error: None,
+                            },
+                        );
+                    }
+                    return;
+                }
+                Err(_) => {}
+            }
+            if !super::is_managed_running() {
+                break "Eliza server exited during startup".to_string();
+            }
+            // A first start after an upgrade can spend minutes migrating the database, which
+            // is waited out for as long as the server keeps reporting progress
+            if started.elapsed() >= timeout && !migrations::in_progress() {
+                break migrations::stalled_error().unwrap_or_else(|| {
+                    format!(
+                        "Eliza server did not become ready within {}ms",
+                        timeout.as_millis()
+                    )
+                });
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        };
+
+        if finish(
+            generation,
+            Readiness::Failed {
+                error: error.clone(),
+            },
+        ) {
+            tracing::error!("{}", error);
+            emit(
+                &app,
+                "server-start-failed",
+                StartupEvent {
+                    elapsed_ms: started.elapsed().as_millis() as u64,
+                    health: None,
+                    error: Some(error),
+                },
+            );
+        }
+    });
+}
+
+// Resolve once the current startup finishes; fails if it fails or `timeout_ms` elapses
+#[tauri::command]
+pub async fn wait_for_server_ready(timeout_ms: u64) -> Result<(), AppError> {
+    let mut receiver = READINESS.subscribe();
+    let mut wait = Duration::from_millis(timeout_ms);
+    let outcome = loop {
+        let outcome = tokio::time::timeout(
+            wait,
+            receiver.wait_for(|state| matches!(state, Readiness::Ready | Readiness::Failed { .. })),
+        )
+        .await;
+        match outcome {
+            Ok(outcome) => {
+                break outcome
+                    .map_err(|e| AppError::Internal(e.to_string()))?
+                    .clone()
+            }
+            // Keep waiting while a migration holds up the start, as the poller does
+            Err(_) if migrations::in_progress() => wait = POLL_INTERVAL,
+            Err(_) => {
+                return Err(AppError::ServerLifecycle(format!(
+                    "Timed out after {}ms waiting for the server",
+                    timeout_ms
+                )));
+            }
+        }
+    };
+
+    match &outcome {
+        Readiness::Failed { error } => Err(AppError::ServerLifecycle(error.clone())),
+        _ => Ok(()),
+    }
+}
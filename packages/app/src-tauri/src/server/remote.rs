@@ -0,0 +1,386 @@
+use std::collections::HashMap;
+use std::fs;
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::{AppHandle, Emitter, Manager, Url};
+
+use super::health::{self, ServerHealth};
+use super::profiles::ProfileKind;
+use super::proxy::AUTH_TOKEN_VAR;
+use super::{config, ws};
+use crate::auth::secure_store;
+use crate::capabilities::{self, Capability};
+use crate::error::AppError;
+use crate::history::{new_id, now_millis};
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(15);
+// Keychain account holding a remote server's API key
+const KEY_PREFIX: &str = "remote-server:";
+const SESSIONS_DIR: &str = "remote-sessions";
+
+// A self-hosted elizaOS server the app can connect to instead of running its own
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RemoteServer {
+    pub id: String,
+    pub name: String,
+    // Base URL, e.g. `https://eliza.example.com`
+    pub url: String,
+    pub added_at: u64,
+}
+
+// Payload of the `remote-server-status` event, sent when the connection comes or goes
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RemoteStatus {
+    pub id: String,
+    pub url: String,
+    pub connected: bool,
+    pub latency_ms: Option<u64>,
+    pub version: Option<String>,
+    pub error: Option<String>,
+}
+
+// API keys by server id, so requests don't go to the keychain each time
+static KEYS: Lazy<Mutex<HashMap<String, Option<String>>>> = Lazy::new(Default::default);
+static STATUS: Mutex<Option<RemoteStatus>> = Mutex::new(None);
+
+fn key_account(id: &str) -> String {
+    format!("{}{}", KEY_PREFIX, id)
+}
+
+// The server the app is connected to, when it's in remote mode
+pub fn active() -> Option<RemoteServer> {
+    let config = config::current();
+    let id = config.active_remote?;
+    config
+        .remote_servers
+        .into_iter()
+        .find(|server| server.id == id)
+}
+
+pub fn is_remote() -> bool {
+    active().is_some()
+}
+
+fn api_key(id: &str) -> Option<String> {
+    if let Some(key) = KEYS.lock().unwrap().get(id) {
+        return key.clone();
+    }
+    let key = secure_store::read_blocking(&key_account(id)).unwrap_or_else(|e| {
+        tracing::warn!("Failed to read the remote server's API key: {}", e);
+        None
+    });
+    KEYS.lock().unwrap().insert(id.to_string(), key.clone());
+    key
+}
+
+// The `x-api-key` to send to the current server: the remote server's own key in remote
+// mode, otherwise the token the local server was configured with
+pub async fn auth_token() -> Result<Option<String>, String> {
+    auth_token_for(&super::health::base_url()).await
+}
+
+// The `x-api-key` for the server at `base`. The remote server's key goes only to its own
+// address; local instances share the workspace's token. Either may need the keychain.
+pub async fn auth_token_for(base: &str) -> Result<Option<String>, String> {
+    let base = base.to_string();
+    tauri::async_runtime::spawn_blocking(move || match active() {
+        Some(server) if server.url == base => Ok(api_key(&server.id)),
+        _ => crate::config::lookup(AUTH_TOKEN_VAR),
+    })
+    .await
+    .map_err(|e| format!("Failed to look up the server's API key: {}", e))?
+}
+
+// Loopback and private network addresses, where plain HTTP is accepted
+fn is_local(url: &Url) -> bool {
+    match url.host_str() {
+        Some("localhost") => true,
+        Some(host) if host.ends_with(".local") => true,
+        Some(host) => match host.trim_matches(['[', ']']).parse::<IpAddr>() {
+            Ok(IpAddr::V4(ip)) => ip.is_loopback() || ip.is_private() || ip.is_link_local(),
+            Ok(IpAddr::V6(ip)) => ip.is_loopback(),
+            Err(_) => false,
+        },
+        None => false,
+    }
+}
+
+// A remote server URL, without the trailing slash the API paths are appended to
+fn normalize(url: &str) -> Result<String, String> {
+    let parsed = Url::parse(url.trim()).map_err(|e| format!("Invalid URL {}: {}", url, e))?;
+    match parsed.scheme() {
+        "https" => {}
+        // The API key would be sent in the clear
+        "http" if is_local(&parsed) => {}
+        "http" => return Err("Remote servers must use https".to_string()),
+        scheme => return Err(format!("Unsupported URL scheme: {}", scheme)),
+    }
+    if parsed.host_str().is_none() || parsed.query().is_some() || parsed.fragment().is_some() {
+        return Err(format!("{} is not a server address", url));
+    }
+    if !parsed.username().is_empty() || parsed.password().is_some() {
+        return Err("Put credentials in the API key, not the URL".to_string());
+    }
+    Ok(parsed.as_str().trim_end_matches('/').to_string())
+}
+
+fn sessions_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_data_dir()
+        .map(|dir| dir.join(SESSIONS_DIR))
+        .map_err(|e| format!("Failed to resolve app data dir: {}", e))
+}
+
+fn session_path(app: &AppHandle, id: &str) -> Result<PathBuf, AppError> {
+    // Ids are generated by `new_id`, so anything else doesn't name a server
+    if id.is_empty() || !id.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return Err(AppError::Validation(format!("Invalid server id: {}", id)));
+    }
+    Ok(sessions_dir(app)?.join(format!("{}.json", id)))
+}
+
+fn find(id: &str) -> Result<RemoteServer, AppError> {
+    config::current()
+        .remote_servers
+        .into_iter()
+        .find(|server| server.id == id)
+        .ok_or_else(|| AppError::NotFound(format!("No remote server with id {}", id)))
+}
+
+fn to_status(server: &RemoteServer, health: Result<ServerHealth, String>) -> RemoteStatus {
+    let (latency_ms, version, error) = match health {
+        Ok(health) => (Some(health.latency_ms), health.version, None),
+        Err(e) => (None, None, Some(e)),
+    };
+    RemoteStatus {
+        id: server.id.clone(),
+        url: server.url.clone(),
+        connected: error.is_none(),
+        latency_ms,
+        version,
+        error,
+    }
+}
+
+async fn probe(server: &RemoteServer) -> RemoteStatus {
+    let key = api_key(&server.id);
+    to_status(server, health::check_url(&server.url, key.as_deref()).await)
+}
+
+// Record the latest check, telling the frontend when the connection came up or went down
+fn update_status(app: &AppHandle, status: Option<RemoteStatus>) {
+    let changed = {
+        let mut current = STATUS.lock().unwrap();
+        let changed = match (current.as_ref(), status.as_ref()) {
+            (Some(old), Some(new)) => old.id != new.id || old.connected != new.connected,
+            (None, None) => false,
+            _ => true,
+        };
+        *current = status.clone();
+        changed
+    };
+    if let (true, Some(status)) = (changed, status) {
+        if status.connected {
+            tracing::info!(url = %status.url, "Connected to the remote server");
+        } else {
+            tracing::warn!(url = %status.url, "Lost the remote server: {:?}", status.error);
+        }
+        if let Err(e) = app.emit("remote-server-status", &status) {
+            tracing::warn!("Failed to emit remote server status: {}", e);
+        }
+    }
+}
+
+// Check the active remote server's health in the background
+pub fn spawn(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let status = match active() {
+                Some(server) => Some(probe(&server).await),
+                None => None,
+            };
+            update_status(&app, status);
+            tokio::time::sleep(CHECK_INTERVAL).await;
+        }
+    });
+}
+
+// Point the webview, the WebSocket bridge and the health checks at the new server
+pub(super) async fn switched(app: &AppHandle) {
+    let status = match active() {
+        Some(server) => Some(probe(&server).await),
+        None => None,
+    };
+    update_status(app, status);
+    super::poller::poke();
+    ws::reconnect(app);
+    if let Err(e) = app.emit("server-url-changed", health::get_server_url()) {
+        tracing::warn!("Failed to emit server URL: {}", e);
+    }
+}
+
+#[tauri::command]
+pub fn list_remote_servers() -> Vec<RemoteServer> {
+    config::current().remote_servers
+}
+
+// Remember a remote server after checking it answers as elizaOS; the API key goes to the
+// keychain
+#[tauri::command]
+pub async fn add_remote_server(
+    app: AppHandle,
+    url: String,
+    name: Option<String>,
+    api_key: Option<String>,
+) -> Result<RemoteServer, AppError> {
+    capabilities::require(Capability::RemoteServers)?;
+    let url = normalize(&url).map_err(AppError::Validation)?;
+    if config::current()
+        .remote_servers
+        .iter()
+        .any(|server| server.url == url)
+    {
+        return Err(AppError::Validation(format!("{} is already added", url)));
+    }
+    let api_key = api_key.filter(|key| !key.trim().is_empty());
+    health::check_url(&url, api_key.as_deref())
+        .await
+        .map_err(AppError::Network)?;
+
+    let server = RemoteServer {
+        id: new_id(),
+        name: name
+            .filter(|name| !name.trim().is_empty())
+            .unwrap_or_else(|| {
+                Url::parse(&url)
+                    .ok()
+                    .and_then(|url| url.host_str().map(str::to_string))
+                    .unwrap_or_else(|| url.clone())
+            }),
+        url,
+        added_at: now_millis(),
+    };
+    if let Some(key) = &api_key {
+        secure_store::write(&key_account(&server.id), key)
+            .await
+            .map_err(AppError::Keychain)?;
+    }
+    KEYS.lock().unwrap().insert(server.id.clone(), api_key);
+    config::update(&app, |config| config.remote_servers.push(server.clone()))?;
+    Ok(server)
+}
+
+// Forget a server along with its key and session, disconnecting first if it's active
+#[tauri::command]
+pub async fn remove_remote_server(app: AppHandle, id: String) -> Result<(), AppError> {
+    capabilities::require(Capability::RemoteServers)?;
+    find(&id)?;
+    let was_active = config::current().active_remote.as_deref() == Some(id.as_str());
+    config::update(&app, |config| {
+        config.remote_servers.retain(|server| server.id != id);
+        // Profiles for the server have nothing left to connect to
+        config.profiles.retain(|profile| {
+            !matches!(&profile.kind, ProfileKind::Remote { server_id } if server_id == &id)
+        });
+        if was_active {
+            config.active_remote = None;
+            config.active_profile = None;
+        }
+    })?;
+    if let Err(e) = secure_store::delete(&key_account(&id)).await {
+        tracing::warn!("Failed to delete the remote server's API key: {}", e);
+    }
+    KEYS.lock().unwrap().remove(&id);
+    let _ = fs::remove_file(session_path(&app, &id)?);
+    if was_active {
+        switched(&app).await;
+    }
+    Ok(())
+}
+
+// Replace or clear a server's API key
+#[tauri::command]
+pub async fn set_remote_server_key(id: String, api_key: Option<String>) -> Result<(), AppError> {
+    capabilities::require(Capability::RemoteServers)?;
+    find(&id)?;
+    let account = key_account(&id);
+    let api_key = api_key.filter(|key| !key.trim().is_empty());
+    match &api_key {
+        Some(key) => secure_store::write(&account, key).await,
+        None => secure_store::delete(&account).await,
+    }
+    .map_err(AppError::Keychain)?;
+    KEYS.lock().unwrap().insert(id, api_key);
+    Ok(())
+}
+
+// Use a remote server instead of spawning one; the local server has to be stopped first
+#[tauri::command]
+pub async fn connect_remote_server(app: AppHandle, id: String) -> Result<RemoteStatus, AppError> {
+    capabilities::require(Capability::RemoteServers)?;
+    let server = find(&id)?;
+    if super::is_managed_running() {
+        return Err(AppError::ServerLifecycle(
+            "Stop the local server before connecting to a remote one".to_string(),
+        ));
+    }
+    config::update(&app, |config| {
+        config.active_remote = Some(server.id.clone());
+        config.active_profile = None;
+    })?;
+    switched(&app).await;
+    Ok(get_remote_server_status()
+        .unwrap_or_else(|| to_status(&server, Err("Not checked yet".to_string()))))
+}
+
+// Go back to the local server
+#[tauri::command]
+pub async fn disconnect_remote_server(app: AppHandle) -> Result<(), AppError> {
+    config::update(&app, |config| {
+        config.active_remote = None;
+        config.active_profile = None;
+    })?;
+    switched(&app).await;
+    Ok(())
+}
+
+// The last health check of the active remote server
+#[tauri::command]
+pub fn get_remote_server_status() -> Option<RemoteStatus> {
+    STATUS.lock().unwrap().clone()
+}
+
+// Frontend state kept per server, like the selected agent and rooms, since ids from one
+// server mean nothing on another
+#[tauri::command]
+pub fn get_remote_session(app: AppHandle, id: String) -> Result<Value, AppError> {
+    let path = session_path(&app, &id)?;
+    match fs::read_to_string(&path) {
+        Ok(json) => serde_json::from_str(&json)
+            .map_err(|e| AppError::Internal(format!("The session for {} is corrupt: {}", id, e))),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Value::Null),
+        Err(e) => Err(AppError::Io(format!(
+            "Failed to read {}: {}",
+            path.display(),
+            e
+        ))),
+    }
+}
+
+#[tauri::command]
+pub fn save_remote_session(app: AppHandle, id: String, session: Value) -> Result<(), AppError> {
+    find(&id)?;
+    let path = session_path(&app, &id)?;
+    let dir = sessions_dir(&app)?;
+    fs::create_dir_all(&dir)
+        .map_err(|e| AppError::Io(format!("Failed to create {}: {}", dir.display(), e)))?;
+    let json = serde_json::to_string(&session).map_err(|e| AppError::Internal(e.to_string()))?;
+    fs::write(&path, json)
+        .map_err(|e| AppError::Io(format!("Failed to write {}: {}", path.display(), e)))
+}
@@ -0,0 +1,296 @@
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use tauri::{AppHandle, Manager, Url};
+use tokio::sync::Mutex;
+use zip::ZipArchive;
+
+use super::config;
+use super::sidecar::ServerRuntime;
+use crate::capabilities::{self, Capability};
+use crate::downloads::{self, Checksum, Download};
+use crate::error::AppError;
+
+// The Bun release the server is run with, independent of any Node or Bun on the system
+pub const BUN_VERSION: &str = "1.2.15";
+const RELEASES_URL: &str = "https://github.com/oven-sh/bun/releases/download";
+const RUNTIME_DIR: &str = "runtime";
+
+// Held while the runtime is installed so setup and the settings don't race
+static INSTALL_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RuntimeInfo {
+    pub name: &'static str,
+    pub pinned_version: &'static str,
+    // The binary, once it's installed
+    pub path: Option<PathBuf>,
+    // What the installed binary reports, which should match the pinned version
+    pub version: Option<String>,
+    // Whether the server is run with this runtime
+    pub active: bool,
+}
+
+#[cfg(target_arch = "x86_64")]
+fn has_avx2() -> bool {
+    std::is_x86_feature_detected!("avx2")
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+fn has_avx2() -> bool {
+    false
+}
+
+// Bun's name for this platform; x64 CPUs without AVX2 need the baseline build
+fn target() -> Option<String> {
+    let (os, arch) = match (std::env::consts::OS, std::env::consts::ARCH) {
+        ("macos", "aarch64") => ("darwin", "aarch64"),
+        ("macos", "x86_64") => ("darwin", "x64"),
+        ("linux", "aarch64") => ("linux", "aarch64"),
+        ("linux", "x86_64") => ("linux", "x64"),
+        ("windows", "x86_64") => ("windows", "x64"),
+        _ => return None,
+    };
+    Some(if arch == "x64" && !has_avx2() {
+        format!("{}-{}-baseline", os, arch)
+    } else {
+        format!("{}-{}", os, arch)
+    })
+}
+
+fn binary_name() -> &'static str {
+    if cfg!(windows) {
+        "bun.exe"
+    } else {
+        "bun"
+    }
+}
+
+fn runtime_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_data_dir()
+        .map(|dir| dir.join(RUNTIME_DIR))
+        .map_err(|e| format!("Failed to resolve app data dir: {}", e))
+}
+
+// Each version gets its own directory, so a pin change never runs a half-replaced binary
+fn version_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(runtime_dir(app)?.join(format!("bun-v{}", BUN_VERSION)))
+}
+
+// The pinned Bun binary, if it's installed
+pub fn installed(app: &AppHandle) -> Option<PathBuf> {
+    let bun = version_dir(app).ok()?.join(binary_name());
+    bun.is_file().then_some(bun)
+}
+
+// Whether the server should be run with the app's own runtime
+pub fn enabled() -> bool {
+    config::current().runtime == ServerRuntime::Embedded
+}
+
+fn version(bun: &Path) -> Result<String, String> {
+    let output = Command::new(bun)
+        .arg("--version")
+        .output()
+        .map_err(|e| format!("Failed to run {}: {}", bun.display(), e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "{} --version exited with {}",
+            bun.display(),
+            output.status
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+// Copy the binary out of the release archive into `dir`, replacing what's there
+fn extract(archive: &Path, dir: &Path) -> Result<(), String> {
+    let file =
+        File::open(archive).map_err(|e| format!("Failed to open {}: {}", archive.display(), e))?;
+    let mut zip = ZipArchive::new(file).map_err(|e| format!("Not a valid archive: {}", e))?;
+    // Entries are `bun-<target>/bun`
+    let name = zip
+        .file_names()
+        .find(|name| name.rsplit('/').next() == Some(binary_name()))
+        .map(str::to_string)
+        .ok_or("The Bun archive doesn't contain a bun binary")?;
+    let mut entry = zip
+        .by_name(&name)
+        .map_err(|e| format!("Failed to read {}: {}", name, e))?;
+
+    // Not `with_extension`, which would replace the version's last component
+    let mut staging = dir.as_os_str().to_owned();
+    staging.push(".tmp");
+    let staging = PathBuf::from(staging);
+    let _ = fs::remove_dir_all(&staging);
+    fs::create_dir_all(&staging)
+        .map_err(|e| format!("Failed to create {}: {}", staging.display(), e))?;
+    let bun = staging.join(binary_name());
+    let mut out =
+        File::create(&bun).map_err(|e| format!("Failed to create {}: {}", bun.display(), e))?;
+    io::copy(&mut entry, &mut out).map_err(|e| format!("Failed to extract Bun: {}", e))?;
+    drop(out);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&bun, fs::Permissions::from_mode(0o755))
+            .map_err(|e| format!("Failed to make {} executable: {}", bun.display(), e))?;
+    }
+
+    let _ = fs::remove_dir_all(dir);
+    fs::rename(&staging, dir).map_err(|e| format!("Failed to install {}: {}", dir.display(), e))
+}
+
+// Drop runtimes left over from earlier pins, and archives from interrupted installs
+fn remove_stale(app: &AppHandle) {
+    let (Ok(root), Ok(current)) = (runtime_dir(app), version_dir(app)) else {
+        return;
+    };
+    let Ok(entries) = fs::read_dir(&root) else {
+        return;
+    };
+    for path in entries.filter_map(Result::ok).map(|entry| entry.path()) {
+        let name = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or("");
+        if path == current || !name.starts_with("bun-") || name.contains(".part") {
+            continue;
+        }
+        let removed = if path.is_dir() {
+            fs::remove_dir_all(&path)
+        } else {
+            fs::remove_file(&path)
+        };
+        if let Err(e) = removed {
+            tracing::warn!("Failed to remove {}: {}", path.display(), e);
+        }
+    }
+}
+
+async fn run(app: &AppHandle) -> Result<PathBuf, String> {
+    let target = target().ok_or("Bun isn't available for this platform")?;
+    let asset = format!("bun-{}.zip", target);
+    let release = format!("{}/bun-v{}", RELEASES_URL, BUN_VERSION);
+
+    // Verified against the checksums published with the release
+    let sums = crate::network::client()
+        .get(format!("{}/SHASUMS256.txt", release))
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| format!("Failed to fetch the Bun checksums: {}", e))?
+        .text()
+        .await
+        .map_err(|e| format!("Failed to fetch the Bun checksums: {}", e))?;
+    let digest = sums
+        .lines()
+        .find_map(|line| {
+            let (digest, name) = line.split_once(char::is_whitespace)?;
+            (name.trim() == asset).then_some(digest)
+        })
+        .ok_or_else(|| format!("No checksum is published for {}", asset))?;
+
+    let url = Url::parse(&format!("{}/{}", release, asset))
+        .map_err(|e| format!("Invalid Bun download URL: {}", e))?;
+    let archive = downloads::fetch(
+        app,
+        Download {
+            kind: "runtime",
+            label: format!("Bun {}", BUN_VERSION),
+            url,
+            path: runtime_dir(app)?.join(&asset),
+            checksum: Some(Checksum::from_sha256_hex(digest)?),
+        },
+    )
+    .await?;
+
+    let dir = version_dir(app)?;
+    let extract_to = dir.clone();
+    let extracted = tauri::async_runtime::spawn_blocking(move || {
+        let result = extract(&archive, &extract_to);
+        let _ = fs::remove_file(&archive);
+        result
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+    extracted?;
+
+    let bun = dir.join(binary_name());
+    let reported = version(&bun)?;
+    if reported != BUN_VERSION {
+        return Err(format!(
+            "The installed Bun reports version {} instead of {}",
+            reported, BUN_VERSION
+        ));
+    }
+    remove_stale(app);
+    tracing::info!("Installed Bun {} at {}", BUN_VERSION, bun.display());
+    Ok(bun)
+}
+
+// The pinned runtime, downloading it first if it isn't installed
+pub async fn ensure(app: &AppHandle) -> Result<PathBuf, String> {
+    let _guard = INSTALL_LOCK.lock().await;
+    if let Some(bun) = installed(app) {
+        return Ok(bun);
+    }
+    run(app).await.inspect_err(|e| {
+        tracing::error!("Failed to install Bun {}: {}", BUN_VERSION, e);
+    })
+}
+
+// A command running the app's managed CLI with the pinned runtime; takes the same
+// arguments as `elizaos`
+pub fn command(app: &AppHandle) -> Result<Command, String> {
+    let bun = installed(app)
+        .ok_or("The app's Bun runtime isn't installed; reinstall it from the settings")?;
+    let entry = crate::cli::install::installed_entry(app)
+        .ok_or("The elizaos CLI isn't installed for the app's runtime; run setup again")?;
+    let mut command = Command::new(bun);
+    command.arg(entry);
+    // Packages the server installs stay out of the user's own Bun cache
+    command.env("BUN_INSTALL_CACHE_DIR", runtime_dir(app)?.join("cache"));
+    Ok(command)
+}
+
+fn info(app: &AppHandle) -> RuntimeInfo {
+    let path = installed(app);
+    RuntimeInfo {
+        name: "bun",
+        pinned_version: BUN_VERSION,
+        version: path.as_deref().and_then(|bun| version(bun).ok()),
+        path,
+        active: enabled(),
+    }
+}
+
+#[tauri::command]
+pub async fn get_runtime_info(app: AppHandle) -> Result<RuntimeInfo, AppError> {
+    Ok(tauri::async_runtime::spawn_blocking(move || info(&app)).await?)
+}
+
+// Download the pinned runtime again, replacing the installed copy
+#[tauri::command]
+pub async fn reinstall_runtime(app: AppHandle) -> Result<RuntimeInfo, AppError> {
+    capabilities::require(Capability::RunPrograms)?;
+    // A running server holds the binary open, which Windows won't let us replace
+    if enabled() && super::is_managed_running() {
+        return Err(AppError::ServerLifecycle(
+            "Stop the server before reinstalling its runtime".to_string(),
+        ));
+    }
+    {
+        let _guard = INSTALL_LOCK.lock().await;
+        if let Ok(dir) = version_dir(&app) {
+            let _ = fs::remove_dir_all(dir);
+        }
+    }
+    ensure(&app).await.map_err(AppError::Io)?;
+    get_runtime_info(app).await
+}
@@ -0,0 +1,278 @@
+use std::io;
+use std::process::{Child, Command};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ShutdownPhase {
+    // The exit request was delivered and we are waiting for the process
+    Signalled,
+    // The process exited on its own
+    Exited,
+    // The process did not exit in time (or could not be signalled) and was killed
+    Killed,
+    Failed,
+}
+
+// Payload of the `server-shutdown` event
+#[derive(Debug, Clone, Serialize)]
+struct ShutdownProgress {
+    phase: ShutdownPhase,
+    elapsed_ms: u64,
+}
+
+fn emit(app: &AppHandle, phase: ShutdownPhase, started: Instant) {
+    let progress = ShutdownProgress {
+        phase,
+        elapsed_ms: started.elapsed().as_millis() as u64,
+    };
+    if let Err(e) = app.emit("server-shutdown", &progress) {
+        tracing::warn!("Failed to emit shutdown progress: {}", e);
+    }
+}
+
+// Job objects holding each spawned server and everything it starts, keyed by pid. Stored
+// as integers since raw handles aren't `Send`.
+#[cfg(windows)]
+static JOBS: once_cell::sync::Lazy<std::sync::Mutex<std::collections::HashMap<u32, isize>>> =
+    once_cell::sync::Lazy::new(Default::default);
+
+// Configure the command so the child can later be asked to exit, together with the
+// node processes the `elizaos` wrapper starts
+pub fn prepare(command: &mut Command) {
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        use windows_sys::Win32::System::Console::GetConsoleWindow;
+        use windows_sys::Win32::System::Threading::{CREATE_NEW_PROCESS_GROUP, CREATE_NO_WINDOW};
+
+        // CTRL_BREAK can only be delivered to the root of a process group
+        let mut flags = CREATE_NEW_PROCESS_GROUP;
+        // Release builds have no console to share, so the child gets a hidden one of its own
+        // that `request_exit` attaches to
+        if unsafe { GetConsoleWindow() }.is_null() {
+            flags |= CREATE_NO_WINDOW;
+        }
+        command.creation_flags(flags);
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        // Its own process group, so signals reach the whole tree
+        command.process_group(0);
+    }
+    #[cfg(not(any(unix, windows)))]
+    let _ = command;
+}
+
+// Put the spawned child in a job object that kills everything in it once the job closes,
+// including when the app itself dies
+#[cfg(windows)]
+pub fn contain(child: &Child) {
+    use std::os::windows::io::AsRawHandle;
+    use windows_sys::Win32::Foundation::CloseHandle;
+    use windows_sys::Win32::System::JobObjects::{
+        AssignProcessToJobObject, CreateJobObjectW, JobObjectExtendedLimitInformation,
+        SetInformationJobObject, JOBOBJECT_EXTENDED_LIMIT_INFORMATION,
+        JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE,
+    };
+
+    unsafe {
+        let job = CreateJobObjectW(std::ptr::null(), std::ptr::null());
+        if job.is_null() {
+            tracing::warn!(
+                "Failed to create a job object: {}",
+                io::Error::last_os_error()
+            );
+            return;
+        }
+        let mut info: JOBOBJECT_EXTENDED_LIMIT_INFORMATION = std::mem::zeroed();
+        info.BasicLimitInformation.LimitFlags = JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE;
+        let configured = SetInformationJobObject(
+            job,
+            JobObjectExtendedLimitInformation,
+            &info as *const _ as *const std::ffi::c_void,
+            std::mem::size_of::<JOBOBJECT_EXTENDED_LIMIT_INFORMATION>() as u32,
+        ) != 0;
+        if !configured || AssignProcessToJobObject(job, child.as_raw_handle()) == 0 {
+            tracing::warn!(
+                "Failed to put the Eliza server in a job object: {}",
+                io::Error::last_os_error()
+            );
+            CloseHandle(job);
+            return;
+        }
+        JOBS.lock().unwrap().insert(child.id(), job as isize);
+    }
+}
+
+// A process group is set up by `prepare`; there is nothing to do after spawning
+#[cfg(not(windows))]
+pub fn contain(_child: &Child) {}
+
+// Once the child has exited, kill any process it left behind: closing its job does that on
+// Windows, and on Unix the rest of its process group is killed
+fn release(child: &Child) {
+    #[cfg(windows)]
+    if let Some(job) = JOBS.lock().unwrap().remove(&child.id()) {
+        unsafe { windows_sys::Win32::Foundation::CloseHandle(job as _) };
+    }
+    // Node processes started by the leader may ignore SIGTERM or outlive it. Fails with
+    // ESRCH when nothing is left, which is the usual case.
+    #[cfg(unix)]
+    unsafe {
+        libc::killpg(child.id() as libc::pid_t, libc::SIGKILL);
+    }
+    #[cfg(not(any(unix, windows)))]
+    let _ = child;
+}
+
+// Kill the child and every process it started
+#[cfg(windows)]
+fn kill_tree(child: &mut Child) -> io::Result<()> {
+    use windows_sys::Win32::System::JobObjects::TerminateJobObject;
+
+    let job = JOBS.lock().unwrap().get(&child.id()).copied();
+    match job {
+        Some(job) if unsafe { TerminateJobObject(job as _, 1) } != 0 => Ok(()),
+        _ => child.kill(),
+    }
+}
+
+#[cfg(unix)]
+fn kill_tree(child: &mut Child) -> io::Result<()> {
+    let group = child.id() as libc::pid_t;
+    if unsafe { libc::killpg(group, libc::SIGKILL) } == 0 {
+        Ok(())
+    } else {
+        child.kill()
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+fn kill_tree(child: &mut Child) -> io::Result<()> {
+    child.kill()
+}
+
+#[cfg(unix)]
+fn request_exit(child: &Child) -> io::Result<()> {
+    let group = child.id() as libc::pid_t;
+    if unsafe { libc::killpg(group, libc::SIGTERM) } == 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+// A process is attached to at most one console, so stops of several servers take turns
+#[cfg(windows)]
+static CONSOLE: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+// Console events only reach processes on the sender's console. Without a console of our own
+// (release builds), attach to the child's for as long as it takes to send the event.
+#[cfg(windows)]
+fn request_exit(child: &Child) -> io::Result<()> {
+    use windows_sys::Win32::System::Console::{
+        AttachConsole, FreeConsole, GenerateConsoleCtrlEvent, GetConsoleWindow, CTRL_BREAK_EVENT,
+    };
+
+    let _console = CONSOLE.lock().unwrap();
+    unsafe {
+        let attached = GetConsoleWindow().is_null() && AttachConsole(child.id()) != 0;
+        let result = if GenerateConsoleCtrlEvent(CTRL_BREAK_EVENT, child.id()) != 0 {
+            Ok(())
+        } else {
+            Err(io::Error::last_os_error())
+        };
+        if attached {
+            FreeConsole();
+        }
+        result
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+fn request_exit(_child: &Child) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "graceful shutdown is not supported on this platform",
+    ))
+}
+
+fn force_kill(app: &AppHandle, child: &mut Child, started: Instant) {
+    match kill_tree(child).and_then(|_| child.wait()) {
+        Ok(_) => {
+            tracing::info!("Eliza server killed");
+            emit(app, ShutdownPhase::Killed, started);
+        }
+        Err(e) => {
+            tracing::error!("Failed to kill Eliza server: {}", e);
+            emit(app, ShutdownPhase::Failed, started);
+        }
+    }
+}
+
+// Kill a helper's whole process tree and reap it, without reporting shutdown progress
+pub fn kill_quietly(child: &mut Child) {
+    if let Err(e) = kill_tree(child) {
+        tracing::warn!("Failed to kill process {}: {}", child.id(), e);
+    }
+    let _ = child.wait();
+    release(child);
+}
+
+// Kill the server's whole process tree without asking it to exit first
+pub fn kill_now(app: &AppHandle, child: &mut Child) {
+    force_kill(app, child, Instant::now());
+    release(child);
+}
+
+// Ask the server to exit and wait up to `timeout` before killing its process tree
+pub fn terminate(app: &AppHandle, child: &mut Child, timeout: Duration) {
+    stop(app, child, timeout);
+    release(child);
+}
+
+fn stop(app: &AppHandle, child: &mut Child, timeout: Duration) {
+    let started = Instant::now();
+
+    if let Ok(Some(status)) = child.try_wait() {
+        tracing::info!("Eliza server already exited with {}", status);
+        emit(app, ShutdownPhase::Exited, started);
+        return;
+    }
+
+    if let Err(e) = request_exit(child) {
+        tracing::warn!("Failed to signal Eliza server, killing it: {}", e);
+        force_kill(app, child, started);
+        return;
+    }
+    emit(app, ShutdownPhase::Signalled, started);
+
+    while started.elapsed() < timeout {
+        match child.try_wait() {
+            Ok(Some(status)) => {
+                tracing::info!("Eliza server shut down successfully ({})", status);
+                emit(app, ShutdownPhase::Exited, started);
+                return;
+            }
+            Ok(None) => thread::sleep(POLL_INTERVAL),
+            Err(e) => {
+                tracing::warn!("Failed to wait for Eliza server: {}", e);
+                break;
+            }
+        }
+    }
+
+    tracing::info!(
+        "Eliza server did not exit within {}ms, killing it",
+        timeout.as_millis()
+    );
+    force_kill(app, child, started);
+}
@@ -0,0 +1,103 @@
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+use tauri_plugin_shell::ShellExt;
+
+use super::config;
+use crate::capabilities::{self, Capability};
+use crate::error::AppError;
+
+// The bundled server binary. Builds with the `sidecar` feature are made with
+// `tauri build --features sidecar --config src-tauri/tauri.sidecar.conf.json`, which takes
+// `binaries/elizaos-server-<target-triple>` and installs it next to the app executable.
+const SIDECAR_NAME: &str = "elizaos-server";
+pub const BUNDLED: bool = cfg!(feature = "sidecar");
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ServerRuntime {
+    // The server binary shipped inside the app
+    Sidecar,
+    // An `elizaos` CLI found on the system or installed by the app
+    Cli,
+    // The app's managed CLI, run with the Bun version the app pins
+    Embedded,
+}
+
+impl Default for ServerRuntime {
+    fn default() -> Self {
+        if BUNDLED {
+            ServerRuntime::Sidecar
+        } else {
+            ServerRuntime::Cli
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RuntimeStatus {
+    pub runtime: ServerRuntime,
+    // Whether this build ships the sidecar
+    pub bundled: bool,
+    pub sidecar_path: Option<PathBuf>,
+    pub cli_path: Option<PathBuf>,
+}
+
+fn sidecar_command(app: &AppHandle) -> Option<Command> {
+    if !BUNDLED {
+        return None;
+    }
+    let command: Command = app.shell().sidecar(SIDECAR_NAME).ok()?.into();
+    PathBuf::from(command.get_program())
+        .is_file()
+        .then_some(command)
+}
+
+pub fn sidecar_path(app: &AppHandle) -> Option<PathBuf> {
+    sidecar_command(app).map(|command| PathBuf::from(command.get_program()))
+}
+
+// Whether the server should be run from the bundled binary rather than a CLI
+pub fn enabled() -> bool {
+    BUNDLED && config::current().runtime == ServerRuntime::Sidecar
+}
+
+// A command for the bundled server, which takes the same arguments as `elizaos`
+pub fn command(app: &AppHandle) -> Result<Command, String> {
+    let mut command = sidecar_command(app)
+        .ok_or("The bundled server is missing from this installation; reinstall the app")?;
+    // The plugin pipes stdin, which the server never reads
+    command.stdin(Stdio::null());
+    Ok(command)
+}
+
+#[tauri::command]
+pub fn get_server_runtime(app: AppHandle) -> RuntimeStatus {
+    RuntimeStatus {
+        runtime: config::current().runtime,
+        bundled: BUNDLED,
+        sidecar_path: sidecar_path(&app),
+        cli_path: crate::cli::resolve(&app),
+    }
+}
+
+// Choose between the bundled server and a CLI; takes effect on the next start
+#[tauri::command]
+pub fn set_server_runtime(
+    app: AppHandle,
+    runtime: ServerRuntime,
+) -> Result<RuntimeStatus, AppError> {
+    capabilities::require(Capability::RunPrograms)?;
+    if runtime == ServerRuntime::Sidecar && !BUNDLED {
+        return Err(AppError::Validation(
+            "This build of the app doesn't include a bundled server".to_string(),
+        ));
+    }
+    config::update(&app, |config| {
+        config.runtime = runtime;
+        config.active_profile = None;
+    })?;
+    Ok(get_server_runtime(app))
+}
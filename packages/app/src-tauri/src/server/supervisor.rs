@@ -0,0 +1,206 @@
+use std::sync::{Mutex, MutexGuard};
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+
+use super::manager::{AGENTS, DEFAULT_INSTANCE};
+use crate::i18n::{t, t_args};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LifecycleState {
+    Stopped,
+    Starting,
+    Running,
+    Stopping,
+}
+
+impl LifecycleState {
+    fn label(self) -> String {
+        t(match self {
+            LifecycleState::Stopped => "lifecycle-stopped",
+            LifecycleState::Starting => "lifecycle-starting",
+            LifecycleState::Running => "lifecycle-running",
+            LifecycleState::Stopping => "lifecycle-stopping",
+        })
+    }
+}
+
+// Payload of the `server-lifecycle` event
+#[derive(Debug, Clone, Serialize)]
+struct LifecycleEvent {
+    state: LifecycleState,
+}
+
+// Payload of the `server-lifecycle-rejected` event, sent when a command arrives in a state
+// it can't run from
+#[derive(Debug, Clone, Serialize)]
+struct RejectedEvent<'a> {
+    state: LifecycleState,
+    requested: LifecycleState,
+    message: &'a str,
+}
+
+// Lifecycle of the default instance. Every start, stop and restart claims its transition
+// here before touching the process, so two commands can never both spawn a server.
+pub struct Supervisor {
+    state: Mutex<LifecycleState>,
+    // Held for the blocking part of a transition, so shutdown waits for a start in progress
+    work: Mutex<()>,
+}
+
+impl Supervisor {
+    const fn new() -> Self {
+        Supervisor {
+            state: Mutex::new(LifecycleState::Stopped),
+            work: Mutex::new(()),
+        }
+    }
+
+    // The recorded state, corrected for a server that exited by itself
+    pub fn state(&self) -> LifecycleState {
+        let mut state = self.state.lock().unwrap();
+        reconcile(&mut state);
+        *state
+    }
+
+    // Move from one of `from` to `to`, or fail without changing anything
+    pub fn begin(
+        &self,
+        app: &AppHandle,
+        from: &[LifecycleState],
+        to: LifecycleState,
+    ) -> Result<(), String> {
+        if let Err(state) = self.claim(from, to) {
+            let message = t_args("server-lifecycle-busy", &[("state", &state.label())]);
+            let rejected = RejectedEvent {
+                state,
+                requested: to,
+                message: &message,
+            };
+            if let Err(e) = app.emit("server-lifecycle-rejected", rejected) {
+                tracing::warn!("Failed to emit rejected transition: {}", e);
+            }
+            return Err(message);
+        }
+        emit(app, to);
+        Ok(())
+    }
+
+    // The state it was in when that isn't one of `from`
+    fn claim(&self, from: &[LifecycleState], to: LifecycleState) -> Result<(), LifecycleState> {
+        let mut state = self.state.lock().unwrap();
+        reconcile(&mut state);
+        *state = transition(*state, from, to)?;
+        Ok(())
+    }
+
+    // Record where a transition ended up, e.g. back to `Stopped` after a failed start
+    pub fn finish(&self, app: &AppHandle, to: LifecycleState) {
+        *self.state.lock().unwrap() = to;
+        emit(app, to);
+    }
+
+    // Exclusive access to the spawned processes for the duration of a transition
+    pub(super) fn work(&self) -> MutexGuard<'_, ()> {
+        self.work.lock().unwrap()
+    }
+}
+
+fn reconcile(state: &mut LifecycleState) {
+    *state = reconciled(*state, || AGENTS.is_running(DEFAULT_INSTANCE));
+}
+
+// A server recorded as running that has since exited is stopped
+fn reconciled(state: LifecycleState, running: impl FnOnce() -> bool) -> LifecycleState {
+    match state {
+        LifecycleState::Running if !running() => LifecycleState::Stopped,
+        state => state,
+    }
+}
+
+fn transition(
+    state: LifecycleState,
+    from: &[LifecycleState],
+    to: LifecycleState,
+) -> Result<LifecycleState, LifecycleState> {
+    match from.contains(&state) {
+        true => Ok(to),
+        false => Err(state),
+    }
+}
+
+fn emit(app: &AppHandle, state: LifecycleState) {
+    if let Err(e) = app.emit("server-lifecycle", LifecycleEvent { state }) {
+        tracing::warn!("Failed to emit server lifecycle: {}", e);
+    }
+}
+
+pub static SUPERVISOR: Supervisor = Supervisor::new();
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use LifecycleState::*;
+
+    // What `start`, `stop` and `restart` accept
+    const STARTABLE: &[LifecycleState] = &[Stopped];
+    const STOPPABLE: &[LifecycleState] = &[Running, Stopped];
+
+    #[test]
+    fn transitions() {
+        assert_eq!(transition(Stopped, STARTABLE, Starting), Ok(Starting));
+        for busy in [Starting, Running, Stopping] {
+            assert_eq!(transition(busy, STARTABLE, Starting), Err(busy));
+        }
+        assert_eq!(transition(Running, STOPPABLE, Stopping), Ok(Stopping));
+        assert_eq!(transition(Stopped, STOPPABLE, Stopping), Ok(Stopping));
+        for busy in [Starting, Stopping] {
+            assert_eq!(transition(busy, STOPPABLE, Stopping), Err(busy));
+        }
+    }
+
+    #[test]
+    fn exited_servers_are_stopped() {
+        assert_eq!(reconciled(Running, || false), Stopped);
+        assert_eq!(reconciled(Running, || true), Running);
+        // Only a running server is checked; a start in progress has no process yet
+        for state in [Stopped, Starting, Stopping] {
+            assert_eq!(reconciled(state, || panic!("checked the process")), state);
+        }
+    }
+
+    #[test]
+    fn a_rejected_claim_changes_nothing() {
+        let supervisor = Supervisor::new();
+        assert_eq!(supervisor.claim(STARTABLE, Starting), Ok(()));
+        assert_eq!(supervisor.claim(STARTABLE, Starting), Err(Starting));
+        assert_eq!(supervisor.claim(STOPPABLE, Stopping), Err(Starting));
+        assert_eq!(supervisor.state(), Starting);
+    }
+
+    #[test]
+    fn only_one_concurrent_start_wins() {
+        let supervisor = Supervisor::new();
+        let claimed = std::thread::scope(|scope| {
+            let claims: Vec<_> = (0..8)
+                .map(|_| scope.spawn(|| supervisor.claim(STARTABLE, Starting).is_ok()))
+                .collect();
+            claims
+                .into_iter()
+                .map(|claim| claim.join().unwrap())
+                .filter(|&won| won)
+                .count()
+        });
+        assert_eq!(claimed, 1);
+    }
+
+    #[test]
+    fn work_is_exclusive() {
+        let supervisor = Supervisor::new();
+        let work = supervisor.work();
+        assert!(supervisor.work.try_lock().is_err());
+        drop(work);
+        assert!(supervisor.work.try_lock().is_ok());
+    }
+}
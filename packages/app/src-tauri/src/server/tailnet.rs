@@ -0,0 +1,314 @@
+use std::collections::BTreeMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use axum::extract::{ConnectInfo, Request};
+use axum::http::{HeaderName, HeaderValue, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::{AppHandle, Emitter};
+use tokio::sync::oneshot;
+
+use super::proxy;
+use crate::capabilities::{self, Capability};
+use crate::error::AppError;
+use crate::hardware::output;
+use crate::history::now_millis;
+use crate::settings;
+use crate::tls::{self, Peer};
+use crate::webhooks::tailnet_address;
+
+// Forwarded to the server so it knows which tailnet user sent a request
+const USER_HEADER: HeaderName = HeaderName::from_static("x-eliza-remote-user");
+// Tailscale identities are looked up again after this long, so revoked devices drop off
+const WHOIS_TTL: Duration = Duration::from_secs(5 * 60);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TailnetAccessSettings {
+    pub enabled: bool,
+    pub port: u16,
+    // Tailscale login names allowed in; empty means only devices of the user signed in here
+    pub allowed_users: Vec<String>,
+}
+
+impl Default for TailnetAccessSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: 7789,
+            allowed_users: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TailnetStatus {
+    // Whether Tailscale is running with an address on this machine
+    pub available: bool,
+    pub enabled: bool,
+    // e.g. `http://100.101.102.103:7789/`; `None` while stopped
+    pub url: Option<String>,
+    pub error: Option<String>,
+}
+
+// A tailnet device that has made a request since access was turned on
+#[derive(Debug, Clone, Serialize)]
+pub struct RemoteClient {
+    pub address: IpAddr,
+    pub login: String,
+    pub display_name: Option<String>,
+    pub device: Option<String>,
+    pub allowed: bool,
+    pub requests: u64,
+    // Milliseconds since the Unix epoch
+    pub first_seen: u64,
+    pub last_seen: u64,
+}
+
+#[derive(Debug, Clone)]
+struct Identity {
+    login: String,
+    display_name: Option<String>,
+    device: Option<String>,
+}
+
+struct Listener {
+    url: String,
+    // This machine's tailnet address, whose owner is let in by default
+    address: IpAddr,
+    shutdown: oneshot::Sender<()>,
+}
+
+static LISTENER: Mutex<Option<Listener>> = Mutex::new(None);
+static LAST_ERROR: Mutex<Option<String>> = Mutex::new(None);
+static WHOIS: Mutex<BTreeMap<IpAddr, (Instant, Option<Identity>)>> = Mutex::new(BTreeMap::new());
+static CLIENTS: Mutex<BTreeMap<IpAddr, RemoteClient>> = Mutex::new(BTreeMap::new());
+
+// Who owns the device at `address`, per `tailscale whois`; `None` for addresses outside the tailnet
+fn whois_uncached(address: IpAddr) -> Option<Identity> {
+    let json = output("tailscale", &["whois", "--json", &address.to_string()])?;
+    let whois: Value = serde_json::from_str(&json).ok()?;
+    let profile = &whois["UserProfile"];
+    let text = |value: &Value| value.as_str().map(str::to_string);
+    Some(Identity {
+        login: text(&profile["LoginName"])?,
+        display_name: text(&profile["DisplayName"]),
+        device: text(&whois["Node"]["ComputedName"])
+            .or_else(|| text(&whois["Node"]["Name"]).map(|name| name.trim_end_matches('.').into())),
+    })
+}
+
+async fn whois(address: IpAddr) -> Option<Identity> {
+    if let Some((fetched, identity)) = WHOIS.lock().unwrap().get(&address) {
+        if fetched.elapsed() < WHOIS_TTL {
+            return identity.clone();
+        }
+    }
+    let identity = tauri::async_runtime::spawn_blocking(move || whois_uncached(address))
+        .await
+        .ok()
+        .flatten();
+    WHOIS
+        .lock()
+        .unwrap()
+        .insert(address, (Instant::now(), identity.clone()));
+    identity
+}
+
+fn allowed(identity: &Identity, own: Option<&Identity>) -> bool {
+    let users = settings::current().tailnet_access.allowed_users;
+    if users.is_empty() {
+        return own.is_some_and(|own| own.login.eq_ignore_ascii_case(&identity.login));
+    }
+    users
+        .iter()
+        .any(|user| user.eq_ignore_ascii_case(&identity.login))
+}
+
+fn record(address: IpAddr, identity: &Identity, allowed: bool) {
+    let now = now_millis();
+    let mut clients = CLIENTS.lock().unwrap();
+    let client = clients.entry(address).or_insert_with(|| RemoteClient {
+        address,
+        login: identity.login.clone(),
+        display_name: None,
+        device: None,
+        allowed,
+        requests: 0,
+        first_seen: now,
+        last_seen: now,
+    });
+    client.login = identity.login.clone();
+    client.display_name = identity.display_name.clone();
+    client.device = identity.device.clone();
+    client.allowed = allowed;
+    client.requests += 1;
+    client.last_seen = now;
+}
+
+// Let a request through only if the tailnet says it comes from an allowed user
+async fn authorize(
+    ConnectInfo(Peer(peer)): ConnectInfo<Peer>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let Some(identity) = whois(peer.ip()).await else {
+        return (StatusCode::FORBIDDEN, "Not a device on this tailnet").into_response();
+    };
+    let own_address = LISTENER
+        .lock()
+        .unwrap()
+        .as_ref()
+        .map(|listener| listener.address);
+    let own = match own_address {
+        Some(own) => whois(own).await,
+        None => None,
+    };
+    let allowed = allowed(&identity, own.as_ref());
+    record(peer.ip(), &identity, allowed);
+    if !allowed {
+        tracing::warn!(login = %identity.login, "Refused a tailnet request");
+        return (StatusCode::FORBIDDEN, "This tailnet user isn't allowed in").into_response();
+    }
+
+    let headers = request.headers_mut();
+    headers.remove(&USER_HEADER);
+    if let Ok(login) = HeaderValue::from_str(&identity.login) {
+        headers.insert(USER_HEADER, login);
+    }
+    next.run(request).await
+}
+
+fn stop() {
+    if let Some(listener) = LISTENER.lock().unwrap().take() {
+        let _ = listener.shutdown.send(());
+        tracing::info!("Tailnet access stopped");
+    }
+    CLIENTS.lock().unwrap().clear();
+    WHOIS.lock().unwrap().clear();
+}
+
+async fn start(app: &AppHandle) -> Result<(), String> {
+    let address = tailnet_address()
+        .ok_or("Tailscale isn't running or this machine has no tailnet address")?;
+    let port = settings::current().tailnet_access.port;
+    // Bound to the tailnet address only, so nothing outside the tailnet can connect
+    let listener = tokio::net::TcpListener::bind(SocketAddr::new(address, port))
+        .await
+        .map_err(|e| format!("Failed to listen on {}:{}: {}", address, port, e))?;
+    let local = listener
+        .local_addr()
+        .map_err(|e| format!("Failed to read the tailnet address: {}", e))?;
+    let acceptor = tls::acceptor();
+    let url = format!("{}://{}/", tls::scheme(&acceptor), local);
+    tracing::info!(%url, "Tailnet access started");
+
+    let router = proxy::router(app).layer(middleware::from_fn(authorize));
+    let (shutdown, stopped) = oneshot::channel();
+    *LISTENER.lock().unwrap() = Some(Listener {
+        url,
+        address,
+        shutdown,
+    });
+    tauri::async_runtime::spawn(async move {
+        let served = tls::serve(listener, router, acceptor, async {
+            let _ = stopped.await;
+        })
+        .await;
+        if let Err(e) = served {
+            tracing::error!("Tailnet listener failed: {}", e);
+        }
+    });
+    Ok(())
+}
+
+async fn restart(app: &AppHandle) -> TailnetStatus {
+    stop();
+    let error = if settings::current().tailnet_access.enabled {
+        start(app).await.err()
+    } else {
+        None
+    };
+    if let Some(e) = &error {
+        tracing::error!("{}", e);
+    }
+    *LAST_ERROR.lock().unwrap() = error;
+    let status = status();
+    if let Err(e) = app.emit("tailnet-access-changed", &status) {
+        tracing::warn!("Failed to emit tailnet access status: {}", e);
+    }
+    status
+}
+
+fn status() -> TailnetStatus {
+    TailnetStatus {
+        available: tailnet_address().is_some(),
+        enabled: settings::current().tailnet_access.enabled,
+        url: LISTENER
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|listener| listener.url.clone()),
+        error: LAST_ERROR.lock().unwrap().clone(),
+    }
+}
+
+// Start listening if tailnet access was left on; called once from the setup hook
+pub fn init(app: &AppHandle) {
+    if !settings::current().tailnet_access.enabled {
+        return;
+    }
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        restart(&app).await;
+    });
+}
+
+#[tauri::command]
+pub async fn get_tailnet_status() -> Result<TailnetStatus, AppError> {
+    Ok(tauri::async_runtime::spawn_blocking(status).await?)
+}
+
+// Serve the proxy on this machine's Tailscale address, for the user's other tailnet devices
+#[tauri::command]
+pub async fn enable_tailnet_access(
+    app: AppHandle,
+    enabled: bool,
+    port: Option<u16>,
+    allowed_users: Option<Vec<String>>,
+) -> Result<TailnetStatus, AppError> {
+    if enabled {
+        capabilities::require(Capability::RemoteAccess)?;
+    }
+    settings::update(&app, |settings| {
+        let access = &mut settings.tailnet_access;
+        access.enabled = enabled;
+        if let Some(port) = port {
+            access.port = port;
+        }
+        if let Some(users) = allowed_users {
+            access.allowed_users = users
+                .into_iter()
+                .map(|user| user.trim().to_string())
+                .filter(|user| !user.is_empty())
+                .collect();
+        }
+    })?;
+    let status = restart(&app).await;
+    match &status.error {
+        Some(e) => Err(AppError::Network(e.clone())),
+        None => Ok(status),
+    }
+}
+
+// Tailnet devices that have called in since access was turned on, most recent first
+#[tauri::command]
+pub fn list_remote_clients() -> Vec<RemoteClient> {
+    let mut clients: Vec<RemoteClient> = CLIENTS.lock().unwrap().values().cloned().collect();
+    clients.sort_by_key(|client| std::cmp::Reverse(client.last_seen));
+    clients
+}
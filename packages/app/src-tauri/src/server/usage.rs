@@ -0,0 +1,419 @@
+use std::collections::BTreeMap;
+
+use chrono::{Datelike, Duration, Local, TimeZone};
+use once_cell::sync::OnceCell;
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::AppHandle;
+use tauri_plugin_notification::NotificationExt;
+
+use crate::error::AppError;
+use crate::history::{self, db_error, now_millis};
+use crate::settings;
+
+// Non-streamed replies larger than this are forwarded without reading their token counts
+const MAX_JSON_BYTES: usize = 8 * 1024 * 1024;
+// Fractions of the monthly budget that trigger a notification when crossed
+const BUDGET_WARNINGS: [f64; 2] = [0.8, 1.0];
+
+// Built-in list prices in USD per million input and output tokens, matched by model prefix.
+// Estimates only; `UsageSettings::prices` overrides or extends them.
+const PRICES: &[(&str, f64, f64)] = &[
+    ("gpt-4o-mini", 0.15, 0.6),
+    ("gpt-4o", 2.5, 10.0),
+    ("gpt-4.1-nano", 0.1, 0.4),
+    ("gpt-4.1-mini", 0.4, 1.6),
+    ("gpt-4.1", 2.0, 8.0),
+    ("o3-mini", 1.1, 4.4),
+    ("o4-mini", 1.1, 4.4),
+    ("claude-3-5-haiku", 0.8, 4.0),
+    ("claude-3-haiku", 0.25, 1.25),
+    ("claude-3-5-sonnet", 3.0, 15.0),
+    ("claude-3-7-sonnet", 3.0, 15.0),
+    ("claude-sonnet-4", 3.0, 15.0),
+    ("claude-opus-4", 15.0, 75.0),
+    ("llama-3.1-8b-instant", 0.05, 0.08),
+    ("llama-3.3-70b-versatile", 0.59, 0.79),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ModelPrice {
+    pub input_per_million: f64,
+    pub output_per_million: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UsageSettings {
+    // Send the server's model provider calls through the proxy so they can be counted
+    pub track_providers: bool,
+    // Warn when the estimated spend of the current month nears this
+    pub monthly_budget_usd: Option<f64>,
+    // Prices by model prefix, e.g. `gpt-4o` or `openrouter/anthropic/`
+    pub prices: BTreeMap<String, ModelPrice>,
+}
+
+impl Default for UsageSettings {
+    fn default() -> Self {
+        Self {
+            track_providers: true,
+            monthly_budget_usd: None,
+            prices: BTreeMap::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UsagePeriod {
+    // Since midnight
+    Day,
+    // The last seven days
+    Week,
+    // Since the first of the month, the period budgets apply to
+    Month,
+    All,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ProviderUsage {
+    pub provider: String,
+    pub requests: u64,
+    // Requests the provider answered with an error status
+    pub errors: u64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+    pub cost_usd: f64,
+    // Requests for models without a known price, left out of `cost_usd`
+    pub unpriced_requests: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UsageReport {
+    pub period: UsagePeriod,
+    // Milliseconds since the Unix epoch
+    pub since: u64,
+    pub providers: Vec<ProviderUsage>,
+    pub total_cost_usd: f64,
+    pub month_cost_usd: f64,
+    pub monthly_budget_usd: Option<f64>,
+}
+
+static APP: OnceCell<AppHandle> = OnceCell::new();
+
+// Remembers the app for budget notifications; called once from the setup hook
+pub fn init(app: &AppHandle) {
+    let _ = APP.set(app.clone());
+}
+
+fn price(model: &str) -> Option<ModelPrice> {
+    let custom = settings::current().usage.prices;
+    let best = |prices: &mut dyn Iterator<Item = (&str, ModelPrice)>| {
+        prices
+            .filter(|(prefix, _)| model.starts_with(prefix))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(_, price)| price)
+    };
+    best(
+        &mut custom
+            .iter()
+            .map(|(prefix, price)| (prefix.as_str(), *price)),
+    )
+    .or_else(|| {
+        best(&mut PRICES.iter().map(|(prefix, input, output)| {
+            (
+                *prefix,
+                ModelPrice {
+                    input_per_million: *input,
+                    output_per_million: *output,
+                },
+            )
+        }))
+    })
+}
+
+// Reads token counts from a JSON reply or stream event of the OpenAI, Anthropic or Gemini APIs
+fn read_usage(value: &Value, meter: &mut Meter) {
+    if let Some(model) = value
+        .get("model")
+        .or_else(|| value.pointer("/message/model"))
+        .and_then(Value::as_str)
+    {
+        meter.model = Some(model.to_string());
+    }
+    let count = |usage: &Value, keys: &[&str]| {
+        keys.iter()
+            .find_map(|key| usage.get(*key).and_then(Value::as_u64))
+    };
+    // Streams repeat running totals, so keep the largest figure seen
+    for usage in [value.get("usage"), value.pointer("/message/usage")]
+        .into_iter()
+        .flatten()
+    {
+        if let Some(input) = count(usage, &["prompt_tokens", "input_tokens"]) {
+            meter.input_tokens = meter.input_tokens.max(input);
+        }
+        if let Some(output) = count(usage, &["completion_tokens", "output_tokens"]) {
+            meter.output_tokens = meter.output_tokens.max(output);
+        }
+    }
+    if let Some(usage) = value.get("usageMetadata") {
+        if let Some(input) = count(usage, &["promptTokenCount"]) {
+            meter.input_tokens = meter.input_tokens.max(input);
+        }
+        if let Some(output) = count(usage, &["candidatesTokenCount"]) {
+            meter.output_tokens = meter.output_tokens.max(output);
+        }
+    }
+}
+
+// Watches one provider reply as it is forwarded and records its usage when dropped, so
+// interrupted streams are counted too
+pub(super) struct Meter {
+    provider: String,
+    model: Option<String>,
+    status: u16,
+    streaming: bool,
+    bytes_sent: u64,
+    bytes_received: u64,
+    // Unparsed rest of a stream, or the whole body of a JSON reply
+    buffer: Vec<u8>,
+    input_tokens: u64,
+    output_tokens: u64,
+}
+
+impl Meter {
+    pub(super) fn new(provider: &str, request: &[u8], status: u16, streaming: bool) -> Self {
+        let model = serde_json::from_slice::<Value>(request)
+            .ok()
+            .and_then(|body| body.get("model")?.as_str().map(str::to_string));
+        Meter {
+            provider: provider.to_string(),
+            model,
+            status,
+            streaming,
+            bytes_sent: request.len() as u64,
+            bytes_received: 0,
+            buffer: Vec::new(),
+            input_tokens: 0,
+            output_tokens: 0,
+        }
+    }
+
+    pub(super) fn feed(&mut self, chunk: &[u8]) {
+        self.bytes_received += chunk.len() as u64;
+        if !self.streaming {
+            if self.buffer.len() + chunk.len() <= MAX_JSON_BYTES {
+                self.buffer.extend_from_slice(chunk);
+            }
+            return;
+        }
+        self.buffer.extend_from_slice(chunk);
+        while let Some(end) = self.buffer.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.buffer.drain(..=end).collect();
+            let line = String::from_utf8_lossy(&line);
+            let Some(data) = line.trim_end().strip_prefix("data:") else {
+                continue;
+            };
+            if let Ok(event) = serde_json::from_str::<Value>(data.trim_start()) {
+                read_usage(&event, self);
+            }
+        }
+    }
+}
+
+impl Drop for Meter {
+    fn drop(&mut self) {
+        if !self.streaming {
+            if let Ok(reply) = serde_json::from_slice::<Value>(&self.buffer) {
+                read_usage(&reply, self);
+            }
+        }
+        let record = Record {
+            provider: std::mem::take(&mut self.provider),
+            cost_usd: self.model.as_deref().and_then(price).map(|price| {
+                (self.input_tokens as f64 * price.input_per_million
+                    + self.output_tokens as f64 * price.output_per_million)
+                    / 1_000_000.0
+            }),
+            model: self.model.take(),
+            status: self.status,
+            input_tokens: self.input_tokens,
+            output_tokens: self.output_tokens,
+            bytes_sent: self.bytes_sent,
+            bytes_received: self.bytes_received,
+        };
+        tauri::async_runtime::spawn(async move {
+            if let Err(e) = save(record).await {
+                tracing::warn!("Failed to record provider usage: {}", e);
+            }
+        });
+    }
+}
+
+struct Record {
+    provider: String,
+    model: Option<String>,
+    status: u16,
+    input_tokens: u64,
+    output_tokens: u64,
+    bytes_sent: u64,
+    bytes_received: u64,
+    cost_usd: Option<f64>,
+}
+
+fn period_start(period: UsagePeriod) -> u64 {
+    let now = Local::now();
+    let start = match period {
+        UsagePeriod::Day => now.date_naive(),
+        UsagePeriod::Week => return now_millis().saturating_sub(7 * 24 * 60 * 60 * 1000),
+        UsagePeriod::Month => now.date_naive().with_day(1).unwrap_or(now.date_naive()),
+        UsagePeriod::All => return 0,
+    };
+    start
+        .and_hms_opt(0, 0, 0)
+        .and_then(|midnight| Local.from_local_datetime(&midnight).earliest())
+        // Midnight can fall into a daylight saving gap
+        .unwrap_or_else(|| now - Duration::hours(24))
+        .timestamp_millis() as u64
+}
+
+fn cost_since(conn: &Connection, since: u64) -> Result<f64, String> {
+    conn.query_row(
+        "SELECT COALESCE(SUM(cost_usd), 0) FROM provider_usage WHERE created_at >= ?1",
+        [since as i64],
+        |row| row.get(0),
+    )
+    .map_err(db_error)
+}
+
+fn warn_budget(before: f64, after: f64) {
+    let Some(budget) = settings::current()
+        .usage
+        .monthly_budget_usd
+        .filter(|budget| *budget > 0.0)
+    else {
+        return;
+    };
+    let Some(crossed) = BUDGET_WARNINGS
+        .iter()
+        .rev()
+        .find(|fraction| before < budget * **fraction && after >= budget * **fraction)
+    else {
+        return;
+    };
+    let Some(app) = APP.get() else {
+        return;
+    };
+    let body = format!(
+        "Model providers have cost an estimated ${:.2} this month, {:.0}% of your ${:.2} budget.",
+        after,
+        crossed * 100.0,
+        budget
+    );
+    tracing::warn!("{}", body);
+    if let Err(e) = app
+        .notification()
+        .builder()
+        .title("Usage budget")
+        .body(body)
+        .show()
+    {
+        tracing::warn!("Failed to show notification: {}", e);
+    }
+}
+
+async fn save(record: Record) -> Result<(), String> {
+    let month = period_start(UsagePeriod::Month);
+    let cost = record.cost_usd;
+    let before = history::with_db(move |conn| {
+        let before = cost_since(conn, month)?;
+        conn.execute(
+            "INSERT INTO provider_usage
+                (provider, model, status, input_tokens, output_tokens, bytes_sent,
+                 bytes_received, cost_usd, created_at)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                record.provider,
+                record.model,
+                record.status,
+                record.input_tokens as i64,
+                record.output_tokens as i64,
+                record.bytes_sent as i64,
+                record.bytes_received as i64,
+                record.cost_usd,
+                now_millis() as i64
+            ],
+        )
+        .map_err(db_error)?;
+        Ok(before)
+    })
+    .await?;
+    if let Some(cost) = cost.filter(|cost| *cost > 0.0) {
+        warn_budget(before, before + cost);
+    }
+    Ok(())
+}
+
+fn report(conn: &Connection, period: UsagePeriod) -> Result<UsageReport, String> {
+    let since = period_start(period);
+    let mut statement = conn
+        .prepare(
+            "SELECT provider, COUNT(*), SUM(status >= 400), SUM(input_tokens),
+                SUM(output_tokens), SUM(bytes_sent), SUM(bytes_received),
+                COALESCE(SUM(cost_usd), 0), SUM(cost_usd IS NULL)
+                FROM provider_usage WHERE created_at >= ?1
+                GROUP BY provider ORDER BY provider",
+        )
+        .map_err(db_error)?;
+    let providers = statement
+        .query_map([since as i64], |row| {
+            Ok(ProviderUsage {
+                provider: row.get(0)?,
+                requests: row.get::<_, i64>(1)? as u64,
+                errors: row.get::<_, i64>(2)? as u64,
+                input_tokens: row.get::<_, i64>(3)? as u64,
+                output_tokens: row.get::<_, i64>(4)? as u64,
+                bytes_sent: row.get::<_, i64>(5)? as u64,
+                bytes_received: row.get::<_, i64>(6)? as u64,
+                cost_usd: row.get(7)?,
+                unpriced_requests: row.get::<_, i64>(8)? as u64,
+            })
+        })
+        .map_err(db_error)?
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .map_err(db_error)?;
+    Ok(UsageReport {
+        period,
+        since,
+        total_cost_usd: providers.iter().map(|usage| usage.cost_usd).sum(),
+        providers,
+        month_cost_usd: cost_since(conn, period_start(UsagePeriod::Month))?,
+        monthly_budget_usd: settings::current().usage.monthly_budget_usd,
+    })
+}
+
+// Requests, tokens, traffic and estimated cost per model provider over `period`
+#[tauri::command]
+pub async fn get_usage_report(period: UsagePeriod) -> Result<UsageReport, AppError> {
+    history::with_db(move |conn| report(conn, period))
+        .await
+        .map_err(AppError::Database)
+}
+
+// Set or clear the monthly budget; `None` turns budget warnings off
+#[tauri::command]
+pub fn set_usage_budget(app: AppHandle, monthly_budget_usd: Option<f64>) -> Result<(), AppError> {
+    if monthly_budget_usd.is_some_and(|budget| !budget.is_finite() || budget < 0.0) {
+        return Err(AppError::Validation(
+            "The budget must be a positive amount".to_string(),
+        ));
+    }
+    settings::update(&app, |settings| {
+        settings.usage.monthly_budget_usd = monthly_budget_usd
+    })?;
+    Ok(())
+}
@@ -0,0 +1,224 @@
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use futures_util::{SinkExt, StreamExt};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+use tokio::sync::Notify;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::Message;
+
+use super::health;
+
+// elizaOS serves Socket.IO; this is its raw WebSocket transport
+const DEFAULT_PATH: &str = "/socket.io/?EIO=4&transport=websocket";
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+// Messages sent while disconnected are kept up to this many, oldest dropped first
+const MAX_QUEUED: usize = 100;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "state", rename_all = "lowercase")]
+pub enum WsState {
+    Disconnected,
+    Connecting,
+    Connected,
+    Reconnecting { attempt: u32, delay_ms: u64 },
+}
+
+#[derive(Clone)]
+struct Target {
+    path: String,
+    // Sent after every (re)connect, e.g. the Socket.IO handshake and room joins
+    on_open: Vec<String>,
+}
+
+static OUTBOX: Lazy<Mutex<VecDeque<String>>> = Lazy::new(|| Mutex::new(VecDeque::new()));
+static OUTBOX_READY: Lazy<Notify> = Lazy::new(Notify::new);
+static STATE: Lazy<Mutex<WsState>> = Lazy::new(|| Mutex::new(WsState::Disconnected));
+// What the bridge was last asked to connect to, so it can be reopened after a system sleep
+static TARGET: Lazy<Mutex<Option<Target>>> = Lazy::new(|| Mutex::new(None));
+
+// Bumped on every connect/disconnect so a superseded bridge task stops itself
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+fn set_state(app: &AppHandle, generation: u64, state: WsState) {
+    if GENERATION.load(Ordering::SeqCst) != generation {
+        return;
+    }
+    *STATE.lock().unwrap() = state.clone();
+    if let Err(e) = app.emit("ws-state-changed", &state) {
+        tracing::warn!("Failed to emit ws-state-changed: {}", e);
+    }
+}
+
+fn is_current(generation: u64) -> bool {
+    GENERATION.load(Ordering::SeqCst) == generation
+}
+
+fn pop_queued() -> Option<String> {
+    OUTBOX.lock().unwrap().pop_front()
+}
+
+fn ws_url(path: &str) -> String {
+    let base = health::base_url();
+    let base = base.replacen("http", "ws", 1);
+    format!("{}{}", base, path)
+}
+
+// Run one connection until it drops; Ok means the socket was open at some point
+async fn run_connection(app: &AppHandle, generation: u64, target: &Target) -> Result<(), String> {
+    let url = ws_url(&target.path);
+    let mut request = url
+        .as_str()
+        .into_client_request()
+        .map_err(|e| format!("Invalid WebSocket URL {}: {}", url, e))?;
+    if let Ok(Some(token)) = super::remote::auth_token().await {
+        let token = token
+            .parse()
+            .map_err(|_| "The server's API key isn't a valid header value".to_string())?;
+        request
+            .headers_mut()
+            .insert(super::proxy::AUTH_HEADER, token);
+    }
+    let (socket, _) = tokio_tungstenite::connect_async(request)
+        .await
+        .map_err(|e| format!("Failed to connect to {}: {}", url, e))?;
+    let (mut sink, mut stream) = socket.split();
+    set_state(app, generation, WsState::Connected);
+    tracing::info!(%url, "WebSocket bridge connected");
+
+    for message in &target.on_open {
+        sink.send(Message::text(message.clone()))
+            .await
+            .map_err(|e| format!("WebSocket send failed: {}", e))?;
+    }
+
+    loop {
+        if !is_current(generation) {
+            let _ = sink.close().await;
+            return Ok(());
+        }
+
+        // Replay anything queued while we were down, putting it back if the socket fails
+        while let Some(message) = pop_queued() {
+            if let Err(e) = sink.send(Message::text(message.clone())).await {
+                OUTBOX.lock().unwrap().push_front(message);
+                return Err(format!("WebSocket send failed: {}", e));
+            }
+        }
+
+        tokio::select! {
+            _ = OUTBOX_READY.notified() => {}
+            frame = stream.next() => match frame {
+                Some(Ok(Message::Text(text))) => {
+                    // Answer Engine.IO pings here so the session survives without the webview
+                    if text.as_str() == "2" {
+                        sink.send(Message::text("3"))
+                            .await
+                            .map_err(|e| format!("WebSocket send failed: {}", e))?;
+                    } else if let Err(e) = app.emit("ws-message", text.as_str()) {
+                        tracing::warn!("Failed to emit ws-message: {}", e);
+                    }
+                }
+                Some(Ok(Message::Close(_))) | None => return Ok(()),
+                Some(Ok(_)) => {}
+                Some(Err(e)) => return Err(format!("WebSocket error: {}", e)),
+            },
+        }
+    }
+}
+
+// Keep a connection open, reconnecting with exponential backoff until superseded
+async fn bridge(app: AppHandle, generation: u64, target: Target) {
+    let mut backoff = INITIAL_BACKOFF;
+    let mut attempt = 0;
+    while is_current(generation) {
+        set_state(&app, generation, WsState::Connecting);
+        match run_connection(&app, generation, &target).await {
+            Ok(()) => {
+                backoff = INITIAL_BACKOFF;
+                attempt = 0;
+            }
+            Err(e) => tracing::debug!("{}", e),
+        }
+        if !is_current(generation) {
+            break;
+        }
+
+        attempt += 1;
+        set_state(
+            &app,
+            generation,
+            WsState::Reconnecting {
+                attempt,
+                delay_ms: backoff.as_millis() as u64,
+            },
+        );
+        tokio::time::sleep(backoff).await;
+        backoff = (backoff * 2).min(MAX_BACKOFF);
+    }
+}
+
+// Open (or replace) the bridged connection; `path` defaults to the Socket.IO endpoint
+#[tauri::command]
+pub fn ws_connect(app: AppHandle, path: Option<String>, on_open: Option<Vec<String>>) {
+    let target = Target {
+        path: path.unwrap_or_else(|| DEFAULT_PATH.to_string()),
+        on_open: on_open.unwrap_or_default(),
+    };
+    *TARGET.lock().unwrap() = Some(target.clone());
+    open(app, target);
+}
+
+fn open(app: AppHandle, target: Target) {
+    let generation = GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+    // Wake the previous connection so it notices it has been superseded
+    OUTBOX_READY.notify_waiters();
+    tauri::async_runtime::spawn(bridge(app, generation, target));
+}
+
+// Replace the current connection with a fresh one, e.g. when a socket silently died while the
+// machine slept. Does nothing if the bridge was disconnected on purpose.
+pub fn reconnect(app: &AppHandle) {
+    if matches!(*STATE.lock().unwrap(), WsState::Disconnected) {
+        return;
+    }
+    let target = TARGET.lock().unwrap().clone();
+    if let Some(target) = target {
+        tracing::info!("Reconnecting the WebSocket bridge");
+        open(app.clone(), target);
+    }
+}
+
+// Queue a text frame; it is delivered as soon as the bridge is connected
+#[tauri::command]
+pub fn ws_send(message: String) {
+    super::idle::touch();
+    {
+        let mut outbox = OUTBOX.lock().unwrap();
+        if outbox.len() >= MAX_QUEUED {
+            tracing::warn!("WebSocket outbox full, dropping the oldest message");
+            outbox.pop_front();
+        }
+        outbox.push_back(message);
+    }
+    OUTBOX_READY.notify_one();
+}
+
+#[tauri::command]
+pub fn ws_disconnect(app: AppHandle) {
+    let generation = GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+    *TARGET.lock().unwrap() = None;
+    OUTBOX_READY.notify_waiters();
+    OUTBOX.lock().unwrap().clear();
+    set_state(&app, generation, WsState::Disconnected);
+}
+
+#[tauri::command]
+pub fn ws_state() -> WsState {
+    STATE.lock().unwrap().clone()
+}
@@ -0,0 +1,408 @@
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::archive::RetentionSettings;
+use crate::auth::verification::VerificationSettings;
+use crate::backup::schedule::BackupSchedule;
+#[cfg(desktop)]
+use crate::clipboard::ClipboardSettings;
+use crate::embeddings::EmbeddingSettings;
+use crate::error::AppError;
+use crate::file_drop::ImportTarget;
+use crate::mcp::McpServerConfig;
+use crate::network::NetworkProxySettings;
+use crate::onboarding::OnboardingProgress;
+#[cfg(desktop)]
+use crate::power::PowerSettings;
+use crate::redaction;
+use crate::server::config::{self as server_config, ServerConfig};
+use crate::server::gateway::ProviderGuardSettings;
+use crate::server::idle::IdleSettings;
+use crate::server::lan::LanAccessSettings;
+use crate::server::prometheus::MetricsEndpointSettings;
+use crate::server::proxy::ProxySettings;
+use crate::server::tailnet::TailnetAccessSettings;
+use crate::server::usage::UsageSettings;
+use crate::sync::SyncSettings;
+use crate::tls::LocalTlsSettings;
+use crate::webhooks::WebhookSettings;
+
+const SETTINGS_FILE: &str = "settings.json";
+
+// Bump when the layout changes and add a step to `migrate`
+const SETTINGS_VERSION: u64 = 2;
+
+// Plain preferences `set_setting` may change, with everything below them. The rest have
+// commands of their own that validate or apply them or ask the user first: launch options
+// and MCP servers run programs, access settings open the agent to the network, and
+// verification and redaction are safeguards.
+const WRITABLE: &[&str] = &[
+    "autostart_minimized",
+    "autostart_server",
+    "log_level",
+    "theme",
+    "server.port",
+    "server.shutdown_timeout_ms",
+    "server.startup_timeout_ms",
+    "server.minimize_to_tray",
+    "server.auto_select_port",
+    "server.stop_external_on_exit",
+    "drop_target",
+    "extract_pdf_text",
+    "proxy",
+    "transcribe_voice",
+    "idle_suspend",
+    "power",
+];
+
+// The command to use instead, for settings `set_setting` refuses
+const DEDICATED: &[(&str, &str)] = &[
+    ("autostart", "enable_autostart"),
+    ("server.health_poll", "set_health_poll_config"),
+    ("server.characters", "start_server"),
+    ("server.cli_path", "set_cli_path"),
+    ("server.runtime", "set_server_runtime"),
+    ("server.backend", "set_server_backend"),
+    ("server.container", "set_server_backend"),
+    ("server.remote_servers", "add_remote_server"),
+    ("server.active_remote", "connect_remote_server"),
+    ("server.profiles", "create_profile"),
+    ("server.active_profile", "activate_profile"),
+    ("server.extra_args", "set_server_launch_options"),
+    ("server.extra_env", "set_server_launch_options"),
+    ("crash_upload_consent", "set_crash_upload_consent"),
+    ("crash_report_url", "set_crash_upload_consent"),
+    ("workspace_dir", "set_workspace_dir"),
+    ("knowledge_paths", "add_knowledge_path"),
+    ("backups", "configure_backups"),
+    ("retention", "configure_retention"),
+    ("embeddings", "configure_embeddings"),
+    ("network_proxy", "set_network_proxy"),
+    ("local_tls", "set_local_tls"),
+    ("lan_access", "enable_lan_access"),
+    ("tailnet_access", "enable_tailnet_access"),
+    ("metrics_endpoint", "configure_metrics_endpoint"),
+    ("usage", "set_usage_budget"),
+    ("verification", "set_verification_settings"),
+    ("provider_guard", "set_provider_guard"),
+    ("sync", "configure_sync"),
+    ("webhooks", "configure_webhooks"),
+    ("mcp_servers", "add_mcp_server"),
+    ("push_to_talk_shortcut", "set_push_to_talk_shortcut"),
+    ("stt_model", "set_stt_model"),
+    ("quick_chat_enabled", "set_quick_chat_enabled"),
+    ("quick_chat_shortcut", "set_quick_chat_shortcut"),
+    ("clipboard", "configure_clipboard_action"),
+    ("onboarding", "complete_step"),
+    ("locale", "set_locale"),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Theme {
+    System,
+    Light,
+    Dark,
+}
+
+// Everything the app persists about itself, shared by the Rust side and the frontend
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub version: u64,
+    pub server: ServerConfig,
+    // Launch the app when the user logs in
+    pub autostart: bool,
+    // When launched at login, stay hidden in the tray
+    pub autostart_minimized: bool,
+    // When launched at login, start the elizaOS server in the background
+    pub autostart_server: bool,
+    pub log_level: LogLevel,
+    pub theme: Theme,
+    // The user agreed to send crash reports to `crash_report_url`
+    pub crash_upload_consent: bool,
+    pub crash_report_url: Option<String>,
+    // Project directory the server runs in; the app's working directory when unset
+    pub workspace_dir: Option<PathBuf>,
+    // Folders mirrored into the workspace knowledge dir
+    pub knowledge_paths: Vec<PathBuf>,
+    // Where files dropped on the window are imported
+    pub drop_target: ImportTarget,
+    // Write a `.txt` next to imported PDFs
+    pub extract_pdf_text: bool,
+    pub backups: BackupSchedule,
+    // Archiving old conversations out of the live history
+    pub retention: RetentionSettings,
+    // Background embedding of messages for semantic search
+    pub embeddings: EmbeddingSettings,
+    pub proxy: ProxySettings,
+    // How requests to the internet, from the app and the server, reach it
+    pub network_proxy: NetworkProxySettings,
+    // HTTPS for the localhost proxy and the webhook listener
+    pub local_tls: LocalTlsSettings,
+    // Pairing lifetime and port for reaching the agent from a phone on the LAN
+    pub lan_access: LanAccessSettings,
+    // Serving the proxy to the user's Tailscale devices
+    pub tailnet_access: TailnetAccessSettings,
+    // Opt-in Prometheus scrape endpoint on localhost
+    pub metrics_endpoint: MetricsEndpointSettings,
+    // Model provider accounting and the monthly spending budget
+    pub usage: UsageSettings,
+    // Rate limits, circuit breaking and failover for the server's provider calls
+    pub provider_guard: ProviderGuardSettings,
+    // Sharing conversations and preferences with other devices through a synced folder
+    pub sync: SyncSettings,
+    // Authenticated HTTP routes external tools call to trigger prompts or app actions
+    pub webhooks: WebhookSettings,
+    // Local MCP tool servers the app runs and supervises for the agent
+    pub mcp_servers: Vec<McpServerConfig>,
+    // Global push-to-talk shortcut; empty disables it
+    pub push_to_talk_shortcut: String,
+    // Send recordings to the agent's transcription endpoint instead of handing them to the UI
+    pub transcribe_voice: bool,
+    // whisper.cpp model used by `transcribe_audio`
+    pub stt_model: String,
+    pub quick_chat_enabled: bool,
+    pub quick_chat_shortcut: String,
+    #[cfg(desktop)]
+    pub clipboard: ClipboardSettings,
+    pub verification: VerificationSettings,
+    // Regexes masked in logs and diagnostics; a `secret` group masks only that part
+    pub redaction_patterns: Vec<String>,
+    pub onboarding: OnboardingProgress,
+    pub idle_suspend: IdleSettings,
+    #[cfg(desktop)]
+    pub power: PowerSettings,
+    // Language of messages produced on this side; `None` follows the OS
+    pub locale: Option<String>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            version: SETTINGS_VERSION,
+            server: ServerConfig::default(),
+            autostart: false,
+            autostart_minimized: true,
+            autostart_server: true,
+            log_level: LogLevel::Info,
+            theme: Theme::System,
+            crash_upload_consent: false,
+            crash_report_url: None,
+            workspace_dir: None,
+            knowledge_paths: Vec::new(),
+            drop_target: ImportTarget::Attachments,
+            extract_pdf_text: true,
+            backups: BackupSchedule::default(),
+            retention: RetentionSettings::default(),
+            embeddings: EmbeddingSettings::default(),
+            proxy: ProxySettings::default(),
+            network_proxy: NetworkProxySettings::default(),
+            local_tls: LocalTlsSettings::default(),
+            lan_access: LanAccessSettings::default(),
+            tailnet_access: TailnetAccessSettings::default(),
+            metrics_endpoint: MetricsEndpointSettings::default(),
+            usage: UsageSettings::default(),
+            provider_guard: ProviderGuardSettings::default(),
+            sync: SyncSettings::default(),
+            webhooks: WebhookSettings::default(),
+            mcp_servers: Vec::new(),
+            push_to_talk_shortcut: "CommandOrControl+Shift+Space".to_string(),
+            transcribe_voice: true,
+            stt_model: "base".to_string(),
+            quick_chat_enabled: true,
+            quick_chat_shortcut: "CommandOrControl+Shift+K".to_string(),
+            #[cfg(desktop)]
+            clipboard: ClipboardSettings::default(),
+            verification: VerificationSettings::default(),
+            redaction_patterns: redaction::default_patterns(),
+            onboarding: OnboardingProgress::default(),
+            idle_suspend: IdleSettings::default(),
+            #[cfg(desktop)]
+            power: PowerSettings::default(),
+            locale: None,
+        }
+    }
+}
+
+static SETTINGS: Lazy<Mutex<Settings>> = Lazy::new(|| Mutex::new(Settings::default()));
+
+pub fn settings_path(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_config_dir()
+        .map(|dir| dir.join(SETTINGS_FILE))
+        .map_err(|e| format!("Failed to resolve app config dir: {}", e))
+}
+
+// Bring a settings document written by an older version up to `SETTINGS_VERSION`
+fn migrate(app: &AppHandle, mut doc: Value) -> Value {
+    let version = doc.get("version").and_then(Value::as_u64).unwrap_or(0);
+
+    // v0 -> v1: the server config moved from server.json into the `server` section
+    if version < 1 && doc.get("server").is_none() {
+        let legacy = server_config::legacy_path(app)
+            .ok()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|contents| serde_json::from_str::<Value>(&contents).ok());
+        if let Some(legacy) = legacy {
+            doc["server"] = legacy;
+        }
+    }
+
+    // v1 -> v2: onboarding was added; installs that already have settings are set up
+    if version == 1 && doc.get("onboarding").is_none() {
+        doc["onboarding"] = json!({ "completed": true });
+    }
+
+    doc["version"] = json!(SETTINGS_VERSION);
+    doc
+}
+
+// Read the persisted settings, migrating them if needed and falling back to defaults
+fn load(app: &AppHandle) -> Result<Settings, String> {
+    let path = settings_path(app)?;
+    let doc = match fs::read_to_string(&path) {
+        Ok(contents) => serde_json::from_str::<Value>(&contents)
+            .ok()
+            .filter(Value::is_object)
+            .unwrap_or_else(|| {
+                tracing::warn!("Ignoring invalid settings file {}", path.display());
+                json!({})
+            }),
+        Err(_) => json!({}),
+    };
+
+    let needs_migration = doc.get("version").and_then(Value::as_u64) != Some(SETTINGS_VERSION);
+    let settings = serde_json::from_value(migrate(app, doc)).unwrap_or_else(|e| {
+        tracing::warn!("Ignoring invalid settings {}: {}", path.display(), e);
+        Settings::default()
+    });
+    if needs_migration {
+        save(app, &settings)?;
+    }
+    Ok(settings)
+}
+
+fn save(app: &AppHandle, settings: &Settings) -> Result<(), String> {
+    let path = settings_path(app)?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    }
+    let contents = serde_json::to_string_pretty(settings).map_err(|e| e.to_string())?;
+    fs::write(&path, contents).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+// Load the persisted settings into memory; called once from the setup hook
+pub fn init(app: &AppHandle) {
+    match load(app) {
+        Ok(settings) => *SETTINGS.lock().unwrap() = settings,
+        Err(e) => tracing::warn!("{}", e),
+    }
+}
+
+pub fn current() -> Settings {
+    SETTINGS.lock().unwrap().clone()
+}
+
+// Apply a change to the in-memory settings, persist it and notify the frontend
+pub fn update(app: &AppHandle, f: impl FnOnce(&mut Settings)) -> Result<Settings, String> {
+    try_update(app, |settings| {
+        f(settings);
+        Ok(())
+    })
+}
+
+// Like `update`, but `f` may refuse the change, leaving the settings as they were
+pub fn try_update<E: From<String>>(
+    app: &AppHandle,
+    f: impl FnOnce(&mut Settings) -> Result<(), E>,
+) -> Result<Settings, E> {
+    let mut guard = SETTINGS.lock().unwrap();
+    let mut settings = guard.clone();
+    f(&mut settings)?;
+    settings.version = SETTINGS_VERSION;
+    save(app, &settings)?;
+    *guard = settings.clone();
+    drop(guard);
+
+    if let Err(e) = app.emit("settings-changed", &settings) {
+        tracing::warn!("Failed to emit settings change: {}", e);
+    }
+    Ok(settings)
+}
+
+// Settings are addressed by dotted keys such as `theme` or `server.port`
+fn pointer(key: &str) -> Result<String, String> {
+    if key.is_empty() || key == "version" {
+        return Err(format!("Unknown setting: {}", key));
+    }
+    Ok(format!("/{}", key.replace('.', "/")))
+}
+
+// `key` is `prefix` or lies below it
+fn within(key: &str, prefix: &str) -> bool {
+    key.strip_prefix(prefix)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with('.'))
+}
+
+fn check_writable(key: &str) -> Result<(), AppError> {
+    if WRITABLE.iter().any(|prefix| within(key, prefix)) {
+        return Ok(());
+    }
+    let command = DEDICATED
+        .iter()
+        .find(|(prefix, _)| within(key, prefix))
+        .map(|(_, command)| command);
+    Err(AppError::PermissionDenied(match command {
+        Some(command) => format!("{} can't be changed with set_setting; use {}", key, command),
+        None => format!("{} can't be changed with set_setting", key),
+    }))
+}
+
+#[tauri::command]
+pub fn get_all_settings() -> Settings {
+    current()
+}
+
+#[tauri::command]
+pub fn get_setting(key: String) -> Result<Value, AppError> {
+    let doc = serde_json::to_value(current()).map_err(|e| e.to_string())?;
+    doc.pointer(&pointer(&key).map_err(AppError::Validation)?)
+        .cloned()
+        .ok_or_else(|| AppError::NotFound(format!("Unknown setting: {}", key)))
+}
+
+// Replace one of the plain preferences in `WRITABLE`, rejecting values that don't fit its
+// type
+#[tauri::command]
+pub fn set_setting(app: AppHandle, key: String, value: Value) -> Result<Settings, AppError> {
+    let pointer = pointer(&key).map_err(AppError::Validation)?;
+    check_writable(&key)?;
+    try_update(&app, |settings| {
+        let mut doc = serde_json::to_value(&*settings).map_err(|e| e.to_string())?;
+        let slot = doc
+            .pointer_mut(&pointer)
+            .ok_or_else(|| AppError::NotFound(format!("Unknown setting: {}", key)))?;
+        *slot = value;
+        *settings = serde_json::from_value(doc)
+            .map_err(|e| AppError::Validation(format!("Invalid value for {}: {}", key, e)))?;
+        Ok(())
+    })
+}
@@ -0,0 +1,33 @@
+use tauri::AppHandle;
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, ShortcutState};
+
+pub type Handler = fn(&AppHandle, ShortcutState);
+
+// Register a global shortcut such as "CommandOrControl+Shift+Space"; empty means unbound
+pub fn bind(app: &AppHandle, shortcut: &str, handler: Handler) -> Result<(), String> {
+    if shortcut.is_empty() {
+        return Ok(());
+    }
+    app.global_shortcut()
+        .on_shortcut(shortcut, move |app, _, event| handler(app, event.state))
+        .map_err(|e| format!("Failed to register shortcut {}: {}", shortcut, e))
+}
+
+pub fn unbind(app: &AppHandle, shortcut: &str) -> Result<(), String> {
+    if shortcut.is_empty() {
+        return Ok(());
+    }
+    app.global_shortcut()
+        .unregister(shortcut)
+        .map_err(|e| format!("Failed to unregister shortcut {}: {}", shortcut, e))
+}
+
+// Move a handler from `previous` to `next`, keeping `previous` if `next` can't be registered
+pub fn rebind(app: &AppHandle, previous: &str, next: &str, handler: Handler) -> Result<(), String> {
+    unbind(app, previous)?;
+    if let Err(e) = bind(app, next, handler) {
+        let _ = bind(app, previous, handler);
+        return Err(e);
+    }
+    Ok(())
+}
@@ -0,0 +1,380 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::PathBuf;
+
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+
+use crate::capabilities::{self, Capability};
+use crate::error::AppError;
+use crate::history::{self, db_error, now_millis};
+
+// Bumped when the export layout changes
+const EXPORT_VERSION: u32 = 1;
+const MAX_NAME_LEN: usize = 48;
+
+// A reusable prompt, invoked from the slash-command palette as `/<name>`. The body may hold
+// `{{variable}}` placeholders, or `{{variable|default}}` to make one optional.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snippet {
+    pub name: String,
+    pub description: Option<String>,
+    pub body: String,
+    // Placeholders in the order they first appear
+    pub variables: Vec<String>,
+    // Starts at 1 and goes up on every edit; earlier bodies stay in `snippet_versions`
+    pub version: u32,
+    // Milliseconds since the Unix epoch
+    pub created_at: u64,
+    pub updated_at: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NewSnippet {
+    pub name: String,
+    pub description: Option<String>,
+    pub body: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SnippetVersion {
+    pub version: u32,
+    pub body: String,
+    pub created_at: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SnippetExport {
+    version: u32,
+    snippets: Vec<NewSnippet>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportSummary {
+    pub created: usize,
+    pub updated: usize,
+    // Identical to the stored version, so left alone
+    pub unchanged: usize,
+}
+
+// A placeholder's name and default, from the text between `{{` and `}}`
+fn placeholder(inner: &str) -> (&str, Option<&str>) {
+    match inner.split_once('|') {
+        Some((name, default)) => (name.trim(), Some(default)),
+        None => (inner.trim(), None),
+    }
+}
+
+// Split `body` into literal text and placeholders
+fn tokens(body: &str) -> Vec<(bool, &str)> {
+    let mut tokens = Vec::new();
+    let mut rest = body;
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        tokens.push((false, &rest[..start]));
+        tokens.push((true, &rest[start + 2..start + 2 + len]));
+        rest = &rest[start + 2 + len + 2..];
+    }
+    tokens.push((false, rest));
+    tokens
+}
+
+fn variables(body: &str) -> Vec<String> {
+    let mut seen = BTreeSet::new();
+    tokens(body)
+        .into_iter()
+        .filter(|(is_placeholder, _)| *is_placeholder)
+        .map(|(_, inner)| placeholder(inner).0.to_string())
+        .filter(|name| seen.insert(name.clone()))
+        .collect()
+}
+
+fn render(body: &str, vars: &BTreeMap<String, String>) -> Result<String, String> {
+    let mut out = String::with_capacity(body.len());
+    let mut missing = Vec::new();
+    for (is_placeholder, text) in tokens(body) {
+        if !is_placeholder {
+            out.push_str(text);
+            continue;
+        }
+        let (name, default) = placeholder(text);
+        match vars.get(name).map(String::as_str).or(default) {
+            Some(value) => out.push_str(value),
+            None if !missing.contains(&name) => missing.push(name),
+            None => {}
+        }
+    }
+    if !missing.is_empty() {
+        return Err(format!("Missing values for {}", missing.join(", ")));
+    }
+    Ok(out)
+}
+
+fn validate(snippet: &NewSnippet) -> Result<NewSnippet, String> {
+    let name = snippet.name.trim().trim_start_matches('/').to_lowercase();
+    if name.is_empty()
+        || name.len() > MAX_NAME_LEN
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_')
+    {
+        return Err(format!(
+            "Invalid snippet name {}; use lowercase letters, digits, - and _",
+            snippet.name
+        ));
+    }
+    if snippet.body.trim().is_empty() {
+        return Err(format!("The snippet {} is empty", name));
+    }
+    if let Some(bad) = variables(&snippet.body)
+        .into_iter()
+        .find(|var| var.is_empty() || var.contains(char::is_whitespace))
+    {
+        return Err(format!("Invalid placeholder {{{{{}}}}} in {}", bad, name));
+    }
+    Ok(NewSnippet {
+        name,
+        description: snippet
+            .description
+            .as_ref()
+            .map(|description| description.trim().to_string())
+            .filter(|description| !description.is_empty()),
+        body: snippet.body.clone(),
+    })
+}
+
+fn snippet_from_row(row: &Row) -> rusqlite::Result<Snippet> {
+    let body: String = row.get("body")?;
+    Ok(Snippet {
+        name: row.get("name")?,
+        description: row.get("description")?,
+        variables: variables(&body),
+        body,
+        version: row.get("version")?,
+        created_at: row.get::<_, i64>("created_at")? as u64,
+        updated_at: row.get::<_, i64>("updated_at")? as u64,
+    })
+}
+
+fn load(conn: &Connection, name: &str) -> Result<Option<Snippet>, String> {
+    conn.query_row(
+        "SELECT * FROM snippets WHERE name = ?1",
+        [name],
+        snippet_from_row,
+    )
+    .optional()
+    .map_err(db_error)
+}
+
+fn load_all(conn: &Connection) -> Result<Vec<Snippet>, String> {
+    let mut statement = conn
+        .prepare("SELECT * FROM snippets ORDER BY name")
+        .map_err(db_error)?;
+    let snippets = statement
+        .query_map([], snippet_from_row)
+        .map_err(db_error)?
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .map_err(db_error)?;
+    Ok(snippets)
+}
+
+enum Saved {
+    Created,
+    Updated,
+    Unchanged,
+}
+
+// Insert a snippet, or store a new version of an existing one
+fn save(conn: &Connection, snippet: &NewSnippet) -> Result<Saved, String> {
+    let now = now_millis() as i64;
+    let existing = load(conn, &snippet.name)?;
+    let Some(existing) = existing else {
+        conn.execute(
+            "INSERT INTO snippets (name, description, body, version, created_at, updated_at)
+                VALUES (?1, ?2, ?3, 1, ?4, ?4)",
+            params![snippet.name, snippet.description, snippet.body, now],
+        )
+        .map_err(db_error)?;
+        return Ok(Saved::Created);
+    };
+    if existing.body == snippet.body && existing.description == snippet.description {
+        return Ok(Saved::Unchanged);
+    }
+    conn.execute(
+        "INSERT OR REPLACE INTO snippet_versions (name, version, body, created_at)
+            VALUES (?1, ?2, ?3, ?4)",
+        params![
+            existing.name,
+            existing.version,
+            existing.body,
+            existing.updated_at as i64
+        ],
+    )
+    .map_err(db_error)?;
+    conn.execute(
+        "UPDATE snippets SET description = ?2, body = ?3, version = version + 1, updated_at = ?4
+            WHERE name = ?1",
+        params![snippet.name, snippet.description, snippet.body, now],
+    )
+    .map_err(db_error)?;
+    Ok(Saved::Updated)
+}
+
+fn not_found(e: String) -> AppError {
+    if e.starts_with("Unknown snippet") {
+        AppError::NotFound(e)
+    } else {
+        AppError::Database(e)
+    }
+}
+
+#[tauri::command]
+pub async fn list_snippets() -> Result<Vec<Snippet>, AppError> {
+    history::with_db(|conn| load_all(conn))
+        .await
+        .map_err(AppError::Database)
+}
+
+// Save a snippet; saving over an existing name keeps the previous body as an older version
+#[tauri::command]
+pub async fn create_snippet(snippet: NewSnippet) -> Result<Snippet, AppError> {
+    let snippet = validate(&snippet).map_err(AppError::Validation)?;
+    history::with_db(move |conn| {
+        save(conn, &snippet)?;
+        load(conn, &snippet.name)?.ok_or_else(|| format!("Unknown snippet: {}", snippet.name))
+    })
+    .await
+    .map_err(not_found)
+}
+
+#[tauri::command]
+pub async fn delete_snippet(name: String) -> Result<(), AppError> {
+    history::with_db(move |conn| {
+        let deleted = conn
+            .execute("DELETE FROM snippets WHERE name = ?1", [&name])
+            .map_err(db_error)?;
+        if deleted == 0 {
+            return Err(format!("Unknown snippet: {}", name));
+        }
+        conn.execute("DELETE FROM snippet_versions WHERE name = ?1", [&name])
+            .map_err(db_error)?;
+        Ok(())
+    })
+    .await
+    .map_err(not_found)
+}
+
+// Earlier bodies of a snippet, newest first
+#[tauri::command]
+pub async fn list_snippet_versions(name: String) -> Result<Vec<SnippetVersion>, AppError> {
+    history::with_db(move |conn| {
+        if load(conn, &name)?.is_none() {
+            return Err(format!("Unknown snippet: {}", name));
+        }
+        let mut statement = conn
+            .prepare(
+                "SELECT version, body, created_at FROM snippet_versions
+                    WHERE name = ?1 ORDER BY version DESC",
+            )
+            .map_err(db_error)?;
+        let versions = statement
+            .query_map([&name], |row| {
+                Ok(SnippetVersion {
+                    version: row.get(0)?,
+                    body: row.get(1)?,
+                    created_at: row.get::<_, i64>(2)? as u64,
+                })
+            })
+            .map_err(db_error)?
+            .collect::<rusqlite::Result<Vec<_>>>()
+            .map_err(db_error)?;
+        Ok(versions)
+    })
+    .await
+    .map_err(not_found)
+}
+
+// The snippet's body with its placeholders filled in from `vars`
+#[tauri::command]
+pub async fn render_snippet(
+    name: String,
+    vars: Option<BTreeMap<String, String>>,
+) -> Result<String, AppError> {
+    let lookup = name.clone();
+    let snippet = history::with_db(move |conn| load(conn, &lookup))
+        .await
+        .map_err(AppError::Database)?
+        .ok_or_else(|| AppError::NotFound(format!("Unknown snippet: {}", name)))?;
+    render(&snippet.body, &vars.unwrap_or_default()).map_err(AppError::Validation)
+}
+
+#[tauri::command]
+pub async fn export_snippets(path: PathBuf) -> Result<PathBuf, AppError> {
+    capabilities::require(Capability::ExportData)?;
+    history::with_db(move |conn| {
+        let export = SnippetExport {
+            version: EXPORT_VERSION,
+            snippets: load_all(conn)?
+                .into_iter()
+                .map(|snippet| NewSnippet {
+                    name: snippet.name,
+                    description: snippet.description,
+                    body: snippet.body,
+                })
+                .collect(),
+        };
+        let json = serde_json::to_string_pretty(&export).map_err(|e| e.to_string())?;
+        fs::write(&path, json).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        Ok(path)
+    })
+    .await
+    .map_err(AppError::Io)
+}
+
+// Add the snippets from an export, storing a new version of any that changed
+#[tauri::command]
+pub async fn import_snippets(path: PathBuf) -> Result<ImportSummary, AppError> {
+    capabilities::require(Capability::ExportData)?;
+    let json = tauri::async_runtime::spawn_blocking(move || fs::read_to_string(&path))
+        .await?
+        .map_err(|e| AppError::Io(e.to_string()))?;
+    let export: SnippetExport = serde_json::from_str(&json)
+        .map_err(|e| AppError::Validation(format!("Not a snippet export: {}", e)))?;
+    if export.version > EXPORT_VERSION {
+        return Err(AppError::Validation(
+            "The snippets were exported by a newer version of the app".to_string(),
+        ));
+    }
+    let snippets = export
+        .snippets
+        .iter()
+        .map(validate)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(AppError::Validation)?;
+    history::with_db(move |conn| {
+        let tx = conn.transaction().map_err(db_error)?;
+        let mut summary = ImportSummary {
+            created: 0,
+            updated: 0,
+            unchanged: 0,
+        };
+        for snippet in &snippets {
+            match save(&tx, snippet)? {
+                Saved::Created => summary.created += 1,
+                Saved::Updated => summary.updated += 1,
+                Saved::Unchanged => summary.unchanged += 1,
+            }
+        }
+        tx.commit().map_err(db_error)?;
+        tracing::info!(
+            created = summary.created,
+            updated = summary.updated,
+            "Imported snippets"
+        );
+        Ok(summary)
+    })
+    .await
+    .map_err(AppError::Database)
+}
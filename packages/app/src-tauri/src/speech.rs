@@ -0,0 +1,332 @@
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use once_cell::sync::OnceCell;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+use tts::Tts;
+
+use crate::error::AppError;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+// Engines without end-of-utterance callbacks report "not speaking" briefly after `speak`
+const START_GRACE: Duration = Duration::from_millis(300);
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum SpeechState {
+    Queued,
+    Started,
+    Paused,
+    Resumed,
+    Finished,
+    Stopped,
+}
+
+// Payload of the `speech-progress` event
+#[derive(Debug, Clone, Serialize)]
+struct SpeechProgress {
+    id: u64,
+    state: SpeechState,
+    // Sentences spoken so far out of `total`
+    sentence: usize,
+    total: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct VoiceInfo {
+    pub id: String,
+    pub name: String,
+    pub language: String,
+}
+
+struct Utterance {
+    id: u64,
+    voice: Option<String>,
+    // Spoken one at a time so pausing only repeats the current sentence
+    sentences: Vec<String>,
+    next: usize,
+}
+
+enum Command {
+    Speak(Utterance),
+    Pause,
+    Resume,
+    Stop,
+    Voices(Sender<Result<Vec<VoiceInfo>, String>>),
+    // Sent by the engine when it finishes a sentence
+    Ended,
+}
+
+// Tts isn't Send on every platform, so it lives on a dedicated thread driven by this channel
+static ENGINE: OnceCell<Mutex<Sender<Command>>> = OnceCell::new();
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+fn split_sentences(text: &str) -> Vec<String> {
+    let mut sentences = Vec::new();
+    let mut current = String::new();
+    for c in text.chars() {
+        current.push(c);
+        if matches!(c, '.' | '!' | '?' | '\n') && current.trim().len() > 1 {
+            sentences.push(current.trim().to_string());
+            current.clear();
+        }
+    }
+    if !current.trim().is_empty() {
+        sentences.push(current.trim().to_string());
+    }
+    sentences
+}
+
+struct Player {
+    app: AppHandle,
+    tts: Tts,
+    queue: VecDeque<Utterance>,
+    paused: bool,
+    // When the current sentence was handed to the engine, if one is playing
+    speaking_since: Option<Instant>,
+    callbacks: bool,
+}
+
+impl Player {
+    fn emit(&self, utterance: &Utterance, state: SpeechState) {
+        let progress = SpeechProgress {
+            id: utterance.id,
+            state,
+            sentence: utterance.next,
+            total: utterance.sentences.len(),
+        };
+        if let Err(e) = self.app.emit("speech-progress", progress) {
+            tracing::warn!("Failed to emit speech-progress: {}", e);
+        }
+    }
+
+    fn select_voice(&mut self, wanted: &str) {
+        let voices = match self.tts.voices() {
+            Ok(voices) => voices,
+            Err(e) => return tracing::warn!("Failed to list voices: {}", e),
+        };
+        match voices
+            .iter()
+            .find(|voice| voice.id() == wanted || voice.name() == wanted)
+        {
+            Some(voice) => {
+                if let Err(e) = self.tts.set_voice(voice) {
+                    tracing::warn!("Failed to select voice {}: {}", wanted, e);
+                }
+            }
+            None => tracing::warn!("Unknown voice {}, using the default", wanted),
+        }
+    }
+
+    fn voices(&self) -> Result<Vec<VoiceInfo>, String> {
+        self.tts
+            .voices()
+            .map(|voices| {
+                voices
+                    .into_iter()
+                    .map(|voice| VoiceInfo {
+                        id: voice.id(),
+                        name: voice.name(),
+                        language: voice.language().to_string(),
+                    })
+                    .collect()
+            })
+            .map_err(|e| format!("Failed to list voices: {}", e))
+    }
+
+    // The current sentence is done; move on, finishing the utterance after its last one
+    fn sentence_ended(&mut self) {
+        self.speaking_since = None;
+        let Some(mut utterance) = self.queue.pop_front() else {
+            return;
+        };
+        utterance.next += 1;
+        if utterance.next >= utterance.sentences.len() {
+            self.emit(&utterance, SpeechState::Finished);
+        } else {
+            self.queue.push_front(utterance);
+        }
+    }
+
+    fn halt(&mut self) {
+        if self.speaking_since.take().is_some() {
+            if let Err(e) = self.tts.stop() {
+                tracing::warn!("Failed to stop speech: {}", e);
+            }
+        }
+    }
+
+    fn handle(&mut self, command: Command) {
+        match command {
+            Command::Speak(utterance) => {
+                self.emit(&utterance, SpeechState::Queued);
+                self.queue.push_back(utterance);
+            }
+            Command::Pause if !self.paused => {
+                self.paused = true;
+                self.halt();
+                if let Some(utterance) = self.queue.front() {
+                    self.emit(utterance, SpeechState::Paused);
+                }
+            }
+            Command::Resume if self.paused => {
+                self.paused = false;
+                if let Some(utterance) = self.queue.front() {
+                    self.emit(utterance, SpeechState::Resumed);
+                }
+            }
+            Command::Pause | Command::Resume => {}
+            Command::Stop => {
+                self.halt();
+                self.paused = false;
+                for utterance in std::mem::take(&mut self.queue) {
+                    self.emit(&utterance, SpeechState::Stopped);
+                }
+            }
+            Command::Voices(reply) => {
+                let _ = reply.send(self.voices());
+            }
+            Command::Ended => {
+                if self.speaking_since.is_some() {
+                    self.sentence_ended();
+                }
+            }
+        }
+    }
+
+    fn tick(&mut self) {
+        if let Some(since) = self.speaking_since {
+            // Without callbacks we find out the sentence ended by polling
+            if !self.callbacks
+                && since.elapsed() > START_GRACE
+                && !self.tts.is_speaking().unwrap_or(false)
+            {
+                self.sentence_ended();
+            }
+            return;
+        }
+        if self.paused {
+            return;
+        }
+        let Some(utterance) = self.queue.front() else {
+            return;
+        };
+        let (sentence, voice, first) = (
+            utterance.sentences[utterance.next].clone(),
+            utterance.voice.clone(),
+            utterance.next == 0,
+        );
+        if first {
+            if let Some(voice) = voice {
+                self.select_voice(&voice);
+            }
+            self.emit(self.queue.front().unwrap(), SpeechState::Started);
+        }
+        match self.tts.speak(sentence, false) {
+            Ok(_) => self.speaking_since = Some(Instant::now()),
+            Err(e) => {
+                tracing::error!("Text-to-speech failed: {}", e);
+                let utterance = self.queue.pop_front().unwrap();
+                self.emit(&utterance, SpeechState::Stopped);
+            }
+        }
+    }
+}
+
+fn run(app: AppHandle, sender: Sender<Command>, commands: mpsc::Receiver<Command>) {
+    let tts = match Tts::default() {
+        Ok(tts) => tts,
+        Err(e) => return tracing::error!("Text-to-speech is unavailable: {}", e),
+    };
+    let callbacks = tts.supported_features().utterance_callbacks
+        && tts
+            .on_utterance_end(Some(Box::new(move |_| {
+                let _ = sender.send(Command::Ended);
+            })))
+            .is_ok();
+
+    let mut player = Player {
+        app,
+        tts,
+        queue: VecDeque::new(),
+        paused: false,
+        speaking_since: None,
+        callbacks,
+    };
+    loop {
+        match commands.recv_timeout(POLL_INTERVAL) {
+            Ok(command) => player.handle(command),
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => return,
+        }
+        player.tick();
+    }
+}
+
+fn send(app: &AppHandle, command: Command) -> Result<(), String> {
+    let engine = ENGINE.get_or_init(|| {
+        let (sender, commands) = mpsc::channel();
+        let (app, callback_sender) = (app.clone(), sender.clone());
+        thread::spawn(move || run(app, callback_sender, commands));
+        Mutex::new(sender)
+    });
+    engine
+        .lock()
+        .unwrap()
+        .send(command)
+        .map_err(|_| "Text-to-speech is unavailable".to_string())
+}
+
+// Queue `text` to be read aloud and return its id, which `speech-progress` events refer to
+#[tauri::command]
+pub fn speak(app: AppHandle, text: String, voice: Option<String>) -> Result<u64, AppError> {
+    let sentences = split_sentences(&text);
+    if sentences.is_empty() {
+        return Err(AppError::Validation("Nothing to speak".to_string()));
+    }
+    let id = NEXT_ID.fetch_add(1, Ordering::SeqCst);
+    send(
+        &app,
+        Command::Speak(Utterance {
+            id,
+            voice,
+            sentences,
+            next: 0,
+        }),
+    )?;
+    Ok(id)
+}
+
+#[tauri::command]
+pub fn pause_speech(app: AppHandle) -> Result<(), AppError> {
+    Ok(send(&app, Command::Pause)?)
+}
+
+#[tauri::command]
+pub fn resume_speech(app: AppHandle) -> Result<(), AppError> {
+    Ok(send(&app, Command::Resume)?)
+}
+
+// Stop speaking and drop everything that is queued
+#[tauri::command]
+pub fn stop_speech(app: AppHandle) -> Result<(), AppError> {
+    Ok(send(&app, Command::Stop)?)
+}
+
+#[tauri::command]
+pub async fn list_voices(app: AppHandle) -> Result<Vec<VoiceInfo>, AppError> {
+    let (reply, response) = mpsc::channel();
+    send(&app, Command::Voices(reply))?;
+    tauri::async_runtime::spawn_blocking(move || {
+        response
+            .recv()
+            .map_err(|_| "Text-to-speech is unavailable".to_string())?
+    })
+    .await?
+    .map_err(AppError::from)
+}
@@ -0,0 +1,144 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use once_cell::sync::Lazy;
+use serde::Serialize;
+
+// When `run` was entered; app phases are reported relative to it
+static PROCESS_START: Lazy<Instant> = Lazy::new(Instant::now);
+static TIMINGS: Lazy<Mutex<StartupTimings>> = Lazy::new(Default::default);
+// When the current server start began
+static SERVER_START: Mutex<Option<Instant>> = Mutex::new(None);
+
+// Phases slower than this are logged as warnings
+const DEFAULT_THRESHOLD: Duration = Duration::from_secs(2);
+const THRESHOLDS: &[(&str, Duration)] = &[
+    ("setup", Duration::from_secs(5)),
+    // Searching for the CLI runs `npm prefix -g`
+    ("resolve_cli", Duration::from_secs(3)),
+    ("spawn", Duration::from_secs(5)),
+    ("first_healthy", Duration::from_secs(30)),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Track {
+    // Setup of the app itself, once per launch
+    App,
+    // The latest server start
+    Server,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PhaseTiming {
+    pub name: &'static str,
+    // Since the start of the track
+    pub started_ms: u64,
+    pub duration_ms: u64,
+    pub threshold_ms: u64,
+    pub slow: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct StartupTimings {
+    pub app: Vec<PhaseTiming>,
+    pub server: Vec<PhaseTiming>,
+    // From launch until setup finished
+    pub app_ready_ms: Option<u64>,
+    // From the server start until it first answered its health check
+    pub server_ready_ms: Option<u64>,
+}
+
+fn threshold(name: &str) -> Duration {
+    THRESHOLDS
+        .iter()
+        .find(|(phase, _)| *phase == name)
+        .map_or(DEFAULT_THRESHOLD, |(_, threshold)| *threshold)
+}
+
+fn as_millis(duration: Duration) -> u64 {
+    duration.as_millis() as u64
+}
+
+// Start the app clock; called first thing in `run`
+pub fn begin() {
+    Lazy::force(&PROCESS_START);
+}
+
+// Start a new server track, forgetting the previous start's phases
+pub fn begin_server() {
+    *SERVER_START.lock().unwrap() = Some(Instant::now());
+    let mut timings = TIMINGS.lock().unwrap();
+    timings.server.clear();
+    timings.server_ready_ms = None;
+}
+
+// Record a phase that began at `started` and has just ended
+pub fn record(track: Track, name: &'static str, started: Instant) {
+    let duration = started.elapsed();
+    let origin = match track {
+        Track::App => Some(*PROCESS_START),
+        Track::Server => *SERVER_START.lock().unwrap(),
+    };
+    let Some(origin) = origin else {
+        return;
+    };
+    let threshold = threshold(name);
+    let slow = duration > threshold;
+    if slow {
+        tracing::warn!(
+            phase = name,
+            duration_ms = as_millis(duration),
+            threshold_ms = as_millis(threshold),
+            "Startup phase '{}' took {:?}",
+            name,
+            duration
+        );
+    } else {
+        tracing::debug!(
+            phase = name,
+            duration_ms = as_millis(duration),
+            "Startup phase done"
+        );
+    }
+
+    let timing = PhaseTiming {
+        name,
+        started_ms: as_millis(started.saturating_duration_since(origin)),
+        duration_ms: as_millis(duration),
+        threshold_ms: as_millis(threshold),
+        slow,
+    };
+    let mut timings = TIMINGS.lock().unwrap();
+    match track {
+        Track::App => timings.app.push(timing),
+        Track::Server => timings.server.push(timing),
+    }
+}
+
+// Run `f` as a timed phase, inside a span so its own logging is attributed to it
+pub fn measure<T>(track: Track, name: &'static str, f: impl FnOnce() -> T) -> T {
+    let started = Instant::now();
+    let result = tracing::info_span!("startup_phase", phase = name).in_scope(f);
+    record(track, name, started);
+    result
+}
+
+// The app finished setting up and its window can be used
+pub fn app_ready() {
+    let elapsed = PROCESS_START.elapsed();
+    tracing::info!("App setup finished {:?} after launch", elapsed);
+    TIMINGS.lock().unwrap().app_ready_ms = Some(as_millis(elapsed));
+}
+
+// The server answered its first health check
+pub fn server_ready() {
+    let Some(started) = *SERVER_START.lock().unwrap() else {
+        return;
+    };
+    TIMINGS.lock().unwrap().server_ready_ms = Some(as_millis(started.elapsed()));
+}
+
+#[tauri::command]
+pub fn get_startup_timings() -> StartupTimings {
+    TIMINGS.lock().unwrap().clone()
+}
@@ -0,0 +1,268 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager, Url};
+
+use crate::capabilities::{self, Capability};
+use crate::downloads::{self, Download};
+use crate::error::AppError;
+use crate::{hardware, settings};
+
+const MODELS_DIR: &str = "stt-models";
+const MODEL_BASE_URL: &str = "https://huggingface.co/ggerganov/whisper.cpp/resolve/main";
+// whisper.cpp expects 16 kHz mono input
+#[cfg(feature = "local-stt")]
+const WHISPER_SAMPLE_RATE: u32 = 16_000;
+
+// ggml models published by whisper.cpp, with their approximate download size in MB
+const MODELS: &[(&str, u64)] = &[
+    ("tiny", 75),
+    ("tiny.en", 75),
+    ("base", 142),
+    ("base.en", 142),
+    ("small", 466),
+    ("small.en", 466),
+    ("medium", 1500),
+    ("large-v3-turbo", 1600),
+];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SttModel {
+    pub name: &'static str,
+    pub size_mb: u64,
+    pub downloaded: bool,
+    pub active: bool,
+}
+
+// Payload of the `stt-model-download-progress` event, sent once the model is in place
+#[derive(Debug, Clone, Serialize)]
+struct DownloadProgress<'a> {
+    model: &'a str,
+    downloaded: u64,
+    total: Option<u64>,
+    done: bool,
+}
+
+fn model_name(name: &str) -> Result<&'static str, String> {
+    MODELS
+        .iter()
+        .map(|(model, _)| *model)
+        .find(|model| *model == name)
+        .ok_or_else(|| format!("Unknown speech-to-text model: {}", name))
+}
+
+fn model_size(name: &str) -> u64 {
+    MODELS
+        .iter()
+        .find(|(model, _)| *model == name)
+        .map_or(0, |(_, size_mb)| *size_mb)
+}
+
+fn models_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_data_dir()
+        .map(|dir| dir.join(MODELS_DIR))
+        .map_err(|e| format!("Failed to resolve app data dir: {}", e))
+}
+
+fn model_path(app: &AppHandle, name: &str) -> Result<PathBuf, String> {
+    Ok(models_dir(app)?.join(format!("ggml-{}.bin", name)))
+}
+
+fn emit_progress(app: &AppHandle, progress: DownloadProgress) {
+    if let Err(e) = app.emit("stt-model-download-progress", progress) {
+        tracing::warn!("Failed to emit download progress: {}", e);
+    }
+}
+
+// Fetched by the download manager, which reports progress as `download-progress` and keeps
+// the partial file so an interrupted download resumes
+async fn download(app: &AppHandle, name: &str, path: &Path) -> Result<(), String> {
+    let url =
+        Url::parse(&format!("{}/ggml-{}.bin", MODEL_BASE_URL, name)).map_err(|e| e.to_string())?;
+    downloads::fetch(
+        app,
+        Download {
+            kind: "stt-model",
+            label: format!("Speech-to-text model {}", name),
+            url,
+            path: path.to_path_buf(),
+            checksum: None,
+        },
+    )
+    .await?;
+    let size = fs::metadata(path).map(|metadata| metadata.len()).ok();
+    emit_progress(
+        app,
+        DownloadProgress {
+            model: name,
+            downloaded: size.unwrap_or_default(),
+            total: size,
+            done: true,
+        },
+    );
+    Ok(())
+}
+
+// Decode a WAV file to 16 kHz mono samples in [-1, 1]
+#[cfg(feature = "local-stt")]
+fn load_audio(path: &Path) -> Result<Vec<f32>, String> {
+    let mut reader = hound::WavReader::open(path)
+        .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let spec = reader.spec();
+    let samples: Vec<f32> = match spec.sample_format {
+        hound::SampleFormat::Float => reader.samples::<f32>().collect::<Result<_, _>>(),
+        hound::SampleFormat::Int => {
+            let scale = (1i64 << (spec.bits_per_sample - 1)) as f32;
+            reader
+                .samples::<i32>()
+                .map(|sample| sample.map(|s| s as f32 / scale))
+                .collect::<Result<_, _>>()
+        }
+    }
+    .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+
+    let channels = spec.channels.max(1) as usize;
+    let mono: Vec<f32> = samples
+        .chunks(channels)
+        .map(|frame| frame.iter().sum::<f32>() / frame.len() as f32)
+        .collect();
+    if spec.sample_rate == WHISPER_SAMPLE_RATE || mono.is_empty() {
+        return Ok(mono);
+    }
+
+    // Linear interpolation is plenty for speech
+    let ratio = spec.sample_rate as f64 / WHISPER_SAMPLE_RATE as f64;
+    let length = (mono.len() as f64 / ratio) as usize;
+    Ok((0..length)
+        .map(|i| {
+            let position = i as f64 * ratio;
+            let index = position as usize;
+            let next = mono[(index + 1).min(mono.len() - 1)];
+            let fraction = (position - index as f64) as f32;
+            mono[index] + (next - mono[index]) * fraction
+        })
+        .collect())
+}
+
+#[cfg(feature = "local-stt")]
+fn run_whisper(model: &Path, audio: &Path, language: Option<&str>) -> Result<String, String> {
+    use whisper_rs::{FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters};
+
+    let samples = load_audio(audio)?;
+    let model = model.to_str().ok_or("The model path is not valid UTF-8")?;
+    let context = WhisperContext::new_with_params(model, WhisperContextParameters::default())
+        .map_err(|e| format!("Failed to load the speech-to-text model: {}", e))?;
+    let mut state = context
+        .create_state()
+        .map_err(|e| format!("Failed to initialise speech-to-text: {}", e))?;
+
+    let mut params = FullParams::new(SamplingStrategy::Greedy { best_of: 1 });
+    // "auto" lets whisper detect the language
+    params.set_language(Some(language.unwrap_or("auto")));
+    params.set_print_progress(false);
+    params.set_print_realtime(false);
+    params.set_print_special(false);
+    params.set_print_timestamps(false);
+    let threads = std::thread::available_parallelism().map_or(4, |n| n.get().min(8));
+    params.set_n_threads(threads as i32);
+
+    state
+        .full(params, &samples)
+        .map_err(|e| format!("Speech-to-text failed: {}", e))?;
+    let mut text = String::new();
+    for segment in state.as_iter() {
+        let segment = segment
+            .to_str_lossy()
+            .map_err(|e| format!("Speech-to-text failed: {}", e))?;
+        text.push_str(&segment);
+    }
+    Ok(text.trim().to_string())
+}
+
+#[cfg(not(feature = "local-stt"))]
+fn run_whisper(_model: &Path, _audio: &Path, _language: Option<&str>) -> Result<String, String> {
+    Err("This build does not include local speech-to-text".to_string())
+}
+
+#[tauri::command]
+pub fn list_stt_models(app: AppHandle) -> Result<Vec<SttModel>, AppError> {
+    let active = settings::current().stt_model;
+    MODELS
+        .iter()
+        .map(|(name, size_mb)| {
+            Ok(SttModel {
+                name,
+                size_mb: *size_mb,
+                downloaded: model_path(&app, name)?.is_file(),
+                active: *name == active,
+            })
+        })
+        .collect()
+}
+
+// Download a whisper.cpp model; `download-progress` events track it and
+// `stt-model-download-progress` says when it's ready
+#[tauri::command]
+pub async fn download_stt_model(app: AppHandle, model: String) -> Result<PathBuf, AppError> {
+    let name = model_name(&model).map_err(AppError::Validation)?;
+    let path = model_path(&app, name)?;
+    if path.is_file() {
+        return Ok(path);
+    }
+    let dir = models_dir(&app)?;
+    let result = match fs::create_dir_all(&dir) {
+        Ok(()) => match hardware::ensure_disk_space(&dir, model_size(name) * 1024 * 1024) {
+            Ok(()) => download(&app, name, &path).await,
+            Err(e) => Err(e),
+        },
+        Err(e) => Err(format!("Failed to create {}: {}", dir.display(), e)),
+    };
+    if let Err(e) = &result {
+        tracing::error!("Failed to download speech-to-text model {}: {}", name, e);
+    }
+    result.map(|_| path).map_err(AppError::Network)
+}
+
+#[tauri::command]
+pub fn delete_stt_model(app: AppHandle, model: String) -> Result<(), AppError> {
+    let path = model_path(&app, model_name(&model).map_err(AppError::Validation)?)?;
+    match fs::remove_file(&path) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(AppError::Io(format!(
+            "Failed to delete {}: {}",
+            path.display(),
+            e
+        ))),
+    }
+}
+
+#[tauri::command]
+pub fn set_stt_model(app: AppHandle, model: String) -> Result<(), AppError> {
+    let name = model_name(&model).map_err(AppError::Validation)?;
+    settings::update(&app, |settings| settings.stt_model = name.to_string())?;
+    Ok(())
+}
+
+// Transcribe a WAV file on this machine with the active model; `language` is an ISO 639-1 code
+#[tauri::command]
+pub async fn transcribe_audio(
+    app: AppHandle,
+    path: PathBuf,
+    language: Option<String>,
+) -> Result<String, AppError> {
+    capabilities::require(Capability::ExportData)?;
+    let name = settings::current().stt_model;
+    let model = model_path(&app, &name)?;
+    if !model.is_file() {
+        return Err(AppError::NotFound(format!(
+            "The {} speech-to-text model has not been downloaded",
+            name
+        )));
+    }
+    tauri::async_runtime::spawn_blocking(move || run_whisper(&model, &path, language.as_deref()))
+        .await?
+        .map_err(AppError::Io)
+}
@@ -0,0 +1,130 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+// How many changes each device has made to an item. Two clocks where neither has seen
+// everything the other has describe concurrent edits, which need to be merged.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct VectorClock(BTreeMap<String, u64>);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Causality {
+    Equal,
+    // The other clock has seen every change this one has, and more
+    Before,
+    After,
+    Concurrent,
+}
+
+impl VectorClock {
+    fn get(&self, device: &str) -> u64 {
+        self.0.get(device).copied().unwrap_or_default()
+    }
+
+    // Count a change made on `device`
+    pub fn tick(&mut self, device: &str) {
+        *self.0.entry(device.to_string()).or_default() += 1;
+    }
+
+    // Take in every change the other clock has seen
+    pub fn merge(&mut self, other: &VectorClock) {
+        for (device, &count) in &other.0 {
+            let entry = self.0.entry(device.clone()).or_default();
+            *entry = (*entry).max(count);
+        }
+    }
+
+    pub fn compare(&self, other: &VectorClock) -> Causality {
+        let (mut behind, mut ahead) = (false, false);
+        for device in self.0.keys().chain(other.0.keys()) {
+            let (mine, theirs) = (self.get(device), other.get(device));
+            behind |= mine < theirs;
+            ahead |= mine > theirs;
+        }
+        match (behind, ahead) {
+            (false, false) => Causality::Equal,
+            (true, false) => Causality::Before,
+            (false, true) => Causality::After,
+            (true, true) => Causality::Concurrent,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clock(counts: &[(&str, u64)]) -> VectorClock {
+        VectorClock(
+            counts
+                .iter()
+                .map(|&(device, count)| (device.to_string(), count))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn tick_counts_changes_per_device() {
+        let mut mine = VectorClock::default();
+        mine.tick("laptop");
+        mine.tick("laptop");
+        mine.tick("phone");
+        assert_eq!(mine, clock(&[("laptop", 2), ("phone", 1)]));
+    }
+
+    #[test]
+    fn compare() {
+        let base = clock(&[("laptop", 2), ("phone", 1)]);
+        assert_eq!(base.compare(&base.clone()), Causality::Equal);
+        assert_eq!(
+            VectorClock::default().compare(&VectorClock::default()),
+            Causality::Equal
+        );
+        // A device that made no changes counts as zero
+        assert_eq!(
+            clock(&[("phone", 0)]).compare(&VectorClock::default()),
+            Causality::Equal
+        );
+
+        let mut later = base.clone();
+        later.tick("phone");
+        assert_eq!(base.compare(&later), Causality::Before);
+        assert_eq!(later.compare(&base), Causality::After);
+        assert_eq!(VectorClock::default().compare(&base), Causality::Before);
+
+        let mut other = base.clone();
+        other.tick("laptop");
+        assert_eq!(later.compare(&other), Causality::Concurrent);
+        assert_eq!(other.compare(&later), Causality::Concurrent);
+        assert_eq!(
+            clock(&[("laptop", 1)]).compare(&clock(&[("phone", 1)])),
+            Causality::Concurrent
+        );
+    }
+
+    #[test]
+    fn merge_takes_the_latest_of_each_device() {
+        let mut mine = clock(&[("laptop", 3), ("phone", 1)]);
+        let theirs = clock(&[("phone", 4), ("tablet", 2)]);
+        mine.merge(&theirs);
+        assert_eq!(mine, clock(&[("laptop", 3), ("phone", 4), ("tablet", 2)]));
+        // The merged clock has seen both sides
+        assert_eq!(theirs.compare(&mine), Causality::Before);
+
+        // Resolving concurrent edits: merge, then count the change that merged them
+        let mut a = clock(&[("laptop", 1)]);
+        let b = clock(&[("phone", 1)]);
+        a.merge(&b);
+        a.tick("laptop");
+        assert_eq!(a.compare(&b), Causality::After);
+    }
+
+    #[test]
+    fn serializes_as_a_plain_map() {
+        let json = serde_json::to_string(&clock(&[("laptop", 2), ("phone", 1)])).unwrap();
+        assert_eq!(json, r#"{"laptop":2,"phone":1}"#);
+        let parsed: VectorClock = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, clock(&[("laptop", 2), ("phone", 1)]));
+    }
+}
@@ -0,0 +1,540 @@
+pub mod clock;
+pub mod store;
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::auth::secure_store;
+use crate::capabilities::{self, Capability};
+use crate::error::AppError;
+use crate::history::{self, now_millis, Conversation, Message};
+use crate::settings::{self, Settings};
+
+use self::clock::{Causality, VectorClock};
+use self::store::{Store, SyncRecord};
+
+const STATE_FILE: &str = "sync-state.json";
+const PASSPHRASE_ENTRY: &str = "sync-passphrase";
+const MIN_PASSPHRASE_LEN: usize = 8;
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+const SETTINGS_ITEM: &str = "settings";
+const CONVERSATION_PREFIX: &str = "conversation:";
+// Preferences that make sense on every device; paths, shortcuts and server setup stay local
+const SYNCED_SETTINGS: &[&str] = &[
+    "theme",
+    "verification",
+    "redaction_patterns",
+    "transcribe_voice",
+    "stt_model",
+    "extract_pdf_text",
+    "drop_target",
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SyncSettings {
+    pub enabled: bool,
+    // Usually inside a Dropbox, iCloud Drive or Syncthing folder; the sync set lives in
+    // `eliza-sync` below it
+    pub folder: Option<PathBuf>,
+    pub interval_minutes: u64,
+}
+
+impl Default for SyncSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            folder: None,
+            interval_minutes: 5,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SyncState {
+    Disabled,
+    Idle,
+    Syncing,
+    Failed,
+}
+
+// Payload of the `sync-status` event
+#[derive(Debug, Clone, Serialize)]
+pub struct SyncStatus {
+    pub state: SyncState,
+    pub folder: Option<PathBuf>,
+    // Milliseconds since the Unix epoch
+    pub last_synced_at: Option<u64>,
+    // Counts from the last run
+    pub uploaded: usize,
+    pub downloaded: usize,
+    // Items edited on two devices since they last synced, merged on this one
+    pub conflicts: usize,
+    pub error: Option<String>,
+}
+
+// What this device knows about an item as of its last sync
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct ItemState {
+    clock: VectorClock,
+    // Hash of the local data when it was last synced; `None` once deleted
+    hash: Option<String>,
+    modified_at: u64,
+}
+
+// Kept in the app data dir, never in the shared folder
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct LocalState {
+    device_id: String,
+    items: BTreeMap<String, ItemState>,
+}
+
+#[derive(Default)]
+struct Counts {
+    uploaded: usize,
+    downloaded: usize,
+    conflicts: usize,
+}
+
+static STATUS: Mutex<SyncStatus> = Mutex::new(SyncStatus {
+    state: SyncState::Disabled,
+    folder: None,
+    last_synced_at: None,
+    uploaded: 0,
+    downloaded: 0,
+    conflicts: 0,
+    error: None,
+});
+// Held for the duration of a run so the scheduler and `sync_now` don't overlap
+static SYNC_LOCK: Mutex<()> = Mutex::new(());
+
+fn set_status(app: &AppHandle, f: impl FnOnce(&mut SyncStatus)) {
+    let status = {
+        let mut status = STATUS.lock().unwrap();
+        f(&mut status);
+        status.clone()
+    };
+    if let Err(e) = app.emit("sync-status", &status) {
+        tracing::warn!("Failed to emit sync status: {}", e);
+    }
+}
+
+fn state_path(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_data_dir()
+        .map(|dir| dir.join(STATE_FILE))
+        .map_err(|e| format!("Failed to resolve app data dir: {}", e))
+}
+
+fn load_state(path: &Path) -> LocalState {
+    let mut state = fs::read_to_string(path)
+        .ok()
+        .and_then(|contents| serde_json::from_str::<LocalState>(&contents).ok())
+        .unwrap_or_default();
+    if state.device_id.is_empty() {
+        let mut bytes = [0u8; 8];
+        rand::thread_rng().fill_bytes(&mut bytes);
+        state.device_id = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    }
+    state
+}
+
+fn save_state(path: &Path, state: &LocalState) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    }
+    let contents = serde_json::to_string(state).map_err(|e| e.to_string())?;
+    let tmp = path.with_extension("tmp");
+    fs::write(&tmp, contents).map_err(|e| format!("Failed to write {}: {}", tmp.display(), e))?;
+    fs::rename(&tmp, path).map_err(|e| format!("Failed to replace {}: {}", path.display(), e))
+}
+
+fn hash(data: &Value) -> String {
+    let digest = Sha256::digest(data.to_string().as_bytes());
+    digest.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn synced_settings(settings: &Settings) -> Result<Value, String> {
+    let doc = serde_json::to_value(settings).map_err(|e| e.to_string())?;
+    Ok(Value::Object(
+        SYNCED_SETTINGS
+            .iter()
+            .filter_map(|key| Some((key.to_string(), doc.get(*key)?.clone())))
+            .collect(),
+    ))
+}
+
+// The current local data of every item, by sync id
+fn local_items() -> Result<HashMap<String, Value>, String> {
+    let mut items = HashMap::new();
+    items.insert(
+        SETTINGS_ITEM.to_string(),
+        synced_settings(&settings::current())?,
+    );
+    history::with_db_blocking(|conn| {
+        for summary in history::load_summaries(conn)? {
+            if let Some(conversation) = history::load_conversation(conn, &summary.id)? {
+                let data = serde_json::to_value(&conversation).map_err(|e| e.to_string())?;
+                items.insert(format!("{}{}", CONVERSATION_PREFIX, summary.id), data);
+            }
+        }
+        Ok(())
+    })?;
+    Ok(items)
+}
+
+fn load_local(id: &str) -> Result<Option<Value>, String> {
+    if id == SETTINGS_ITEM {
+        return synced_settings(&settings::current()).map(Some);
+    }
+    let Some(conversation_id) = id.strip_prefix(CONVERSATION_PREFIX) else {
+        return Ok(None);
+    };
+    history::with_db_blocking(|conn| history::load_conversation(conn, conversation_id))?
+        .map(|conversation| serde_json::to_value(&conversation).map_err(|e| e.to_string()))
+        .transpose()
+}
+
+// Make the local copy of an item match `data`, deleting it for a tombstone
+fn apply_local(app: &AppHandle, id: &str, data: Option<&Value>) -> Result<(), String> {
+    if id == SETTINGS_ITEM {
+        let Some(Value::Object(remote)) = data else {
+            return Ok(());
+        };
+        let mut doc = serde_json::to_value(settings::current()).map_err(|e| e.to_string())?;
+        for key in SYNCED_SETTINGS {
+            if let Some(value) = remote.get(*key) {
+                doc[*key] = value.clone();
+            }
+        }
+        let mut updated: Settings =
+            serde_json::from_value(doc).map_err(|e| format!("Ignoring synced settings: {}", e))?;
+        // Another device can add verification checks here but not take them away
+        let local = settings::current().verification;
+        if local.weakened_by(&updated.verification) {
+            tracing::warn!("Ignoring synced verification settings that turn checks off");
+            updated.verification = local;
+        }
+        settings::update(app, |settings| *settings = updated)?;
+        return Ok(());
+    }
+    let Some(conversation_id) = id.strip_prefix(CONVERSATION_PREFIX) else {
+        return Ok(());
+    };
+    match data {
+        Some(data) => {
+            let conversation: Conversation = serde_json::from_value(data.clone())
+                .map_err(|e| format!("Invalid synced conversation {}: {}", conversation_id, e))?;
+            if conversation.summary.id != conversation_id {
+                return Err(format!(
+                    "Mismatched synced conversation {}",
+                    conversation_id
+                ));
+            }
+            history::with_db_blocking(|conn| history::replace_conversation(conn, &conversation))
+        }
+        None => {
+            history::with_db_blocking(|conn| history::remove_conversation(conn, conversation_id))
+        }
+    }
+}
+
+// Combine two conversations edited concurrently: every message either side has, with the
+// newer side's title and timestamps
+fn merge_conversations(local: &Value, remote: &Value) -> Result<Value, String> {
+    let parse = |data: &Value| {
+        serde_json::from_value::<Conversation>(data.clone())
+            .map_err(|e| format!("Invalid synced conversation: {}", e))
+    };
+    let (local, remote) = (parse(local)?, parse(remote)?);
+    let (mut newer, older) = if remote.summary.updated_at > local.summary.updated_at {
+        (remote, local)
+    } else {
+        (local, remote)
+    };
+    let mut messages: BTreeMap<String, Message> = older
+        .messages
+        .into_iter()
+        .map(|message| (message.id.clone(), message))
+        .collect();
+    messages.extend(
+        newer
+            .messages
+            .drain(..)
+            .map(|message| (message.id.clone(), message)),
+    );
+    let mut messages: Vec<Message> = messages.into_values().collect();
+    messages.sort_by(|a, b| (a.created_at, &a.id).cmp(&(b.created_at, &b.id)));
+    newer.summary.created_at = newer.summary.created_at.min(older.summary.created_at);
+    newer.summary.message_count = messages.len() as u64;
+    newer.messages = messages;
+    serde_json::to_value(&newer).map_err(|e| e.to_string())
+}
+
+// Resolve concurrent edits. An edit always beats a delete, conversations are merged and the
+// settings written last win.
+fn merge(
+    id: &str,
+    local: Option<&Value>,
+    local_modified: u64,
+    remote: &SyncRecord,
+) -> Result<Option<Value>, String> {
+    Ok(match (local, remote.data.as_ref()) {
+        (None, None) => None,
+        (Some(data), None) | (None, Some(data)) => Some(data.clone()),
+        (Some(local), Some(remote_data)) if id == SETTINGS_ITEM => {
+            if remote.modified_at >= local_modified {
+                Some(remote_data.clone())
+            } else {
+                Some(local.clone())
+            }
+        }
+        (Some(local), Some(remote_data)) => Some(merge_conversations(local, remote_data)?),
+    })
+}
+
+fn run(app: &AppHandle, folder: &Path, passphrase: &str) -> Result<Counts, String> {
+    let store = Store::open(folder, passphrase)?;
+    let state_path = state_path(app)?;
+    let mut state = load_state(&state_path);
+    let device = state.device_id.clone();
+    let mut local = local_items()?;
+    let remote = store.read_all()?;
+
+    let ids: BTreeSet<String> = local
+        .keys()
+        .chain(remote.keys())
+        .chain(state.items.keys())
+        .cloned()
+        .collect();
+    let mut counts = Counts::default();
+    for id in ids {
+        let data = local.remove(&id);
+        let current_hash = data.as_ref().map(hash);
+        let mut item = state.items.get(&id).cloned();
+
+        // Count local edits made since the last sync; only items this device has synced
+        // before can have been deleted here
+        let changed = match &item {
+            Some(item) => item.hash != current_hash,
+            None => data.is_some(),
+        };
+        if changed {
+            let previous = item.take();
+            let first_sync = previous.is_none();
+            let mut next = previous.unwrap_or_default();
+            next.clock.tick(&device);
+            next.hash = current_hash.clone();
+            // Settings a device has never synced shouldn't override ones already shared
+            next.modified_at = match (&data, first_sync) {
+                (Some(_), true) if id == SETTINGS_ITEM => 0,
+                (Some(data), true) => data
+                    .pointer("/updated_at")
+                    .and_then(Value::as_u64)
+                    .unwrap_or_else(now_millis),
+                _ => now_millis(),
+            };
+            item = Some(next);
+        }
+        let Some(mut item) = item.or_else(|| remote.get(&id).map(|_| ItemState::default())) else {
+            continue;
+        };
+
+        let remote_record = remote.get(&id);
+        let empty = VectorClock::default();
+        let remote_clock = remote_record.map_or(&empty, |record| &record.clock);
+        let upload = |item: &ItemState, data: Option<Value>| {
+            store.write(&SyncRecord {
+                id: id.clone(),
+                clock: item.clock.clone(),
+                modified_at: item.modified_at,
+                device: device.clone(),
+                data,
+            })
+        };
+        match (item.clock.compare(remote_clock), remote_record) {
+            (Causality::Equal, _) => {}
+            (Causality::After, _) | (_, None) => {
+                upload(&item, data)?;
+                counts.uploaded += 1;
+            }
+            (Causality::Before, Some(record)) => {
+                apply_local(app, &id, record.data.as_ref())?;
+                item.clock = record.clock.clone();
+                item.modified_at = record.modified_at;
+                item.hash = load_local(&id)?.as_ref().map(hash);
+                counts.downloaded += 1;
+            }
+            (Causality::Concurrent, Some(record)) => {
+                tracing::info!(item = %id, "Merging concurrent edits");
+                let merged = merge(&id, data.as_ref(), item.modified_at, record)?;
+                item.clock.merge(&record.clock);
+                item.clock.tick(&device);
+                item.modified_at = now_millis();
+                apply_local(app, &id, merged.as_ref())?;
+                item.hash = load_local(&id)?.as_ref().map(hash);
+                upload(&item, merged)?;
+                counts.conflicts += 1;
+            }
+        }
+        state.items.insert(id, item);
+    }
+    save_state(&state_path, &state)?;
+    Ok(counts)
+}
+
+fn load_passphrase() -> Result<Option<String>, String> {
+    secure_store::read_blocking(PASSPHRASE_ENTRY)
+        .map_err(|e| format!("Failed to read the sync passphrase: {}", e))
+}
+
+// Run once now unless a run is already in progress, reporting progress through `sync-status`
+fn sync(app: &AppHandle) -> Result<SyncStatus, String> {
+    let Ok(_lock) = SYNC_LOCK.try_lock() else {
+        return Ok(STATUS.lock().unwrap().clone());
+    };
+    let config = settings::current().sync;
+    let (true, Some(folder)) = (config.enabled, config.folder.clone()) else {
+        set_status(app, |status| {
+            status.state = SyncState::Disabled;
+            status.folder = None;
+        });
+        return Err("Sync isn't set up".to_string());
+    };
+    set_status(app, |status| {
+        status.state = SyncState::Syncing;
+        status.folder = Some(folder.clone());
+        status.error = None;
+    });
+
+    let result = load_passphrase()
+        .and_then(|passphrase| passphrase.ok_or("The sync passphrase is missing".to_string()))
+        .and_then(|passphrase| run(app, &folder, &passphrase));
+    match result {
+        Ok(counts) => {
+            tracing::info!(
+                uploaded = counts.uploaded,
+                downloaded = counts.downloaded,
+                conflicts = counts.conflicts,
+                "Sync finished"
+            );
+            set_status(app, |status| {
+                status.state = SyncState::Idle;
+                status.last_synced_at = Some(now_millis());
+                status.uploaded = counts.uploaded;
+                status.downloaded = counts.downloaded;
+                status.conflicts = counts.conflicts;
+            });
+            Ok(STATUS.lock().unwrap().clone())
+        }
+        Err(e) => {
+            tracing::error!("Sync failed: {}", e);
+            set_status(app, |status| {
+                status.state = SyncState::Failed;
+                status.error = Some(e.clone());
+            });
+            Err(e)
+        }
+    }
+}
+
+fn is_due(config: &SyncSettings) -> bool {
+    let status = STATUS.lock().unwrap();
+    let interval = config.interval_minutes.max(1) * 60_000;
+    status
+        .last_synced_at
+        .is_none_or(|last| now_millis().saturating_sub(last) >= interval)
+}
+
+// Sync in the background whenever the interval has passed; called once from the setup hook
+pub fn spawn(app: AppHandle) {
+    thread::spawn(move || loop {
+        let config = settings::current().sync;
+        if config.enabled && is_due(&config) {
+            let _ = sync(&app);
+        }
+        thread::sleep(CHECK_INTERVAL);
+    });
+}
+
+// Start syncing with the sync set in `folder`, creating it on first use. Every device sharing
+// the folder has to use the same passphrase.
+#[tauri::command]
+pub async fn configure_sync(
+    app: AppHandle,
+    folder: PathBuf,
+    passphrase: String,
+) -> Result<SyncStatus, AppError> {
+    capabilities::require(Capability::ExportData)?;
+    if passphrase.chars().count() < MIN_PASSPHRASE_LEN {
+        return Err(AppError::Validation(format!(
+            "The passphrase must be at least {} characters",
+            MIN_PASSPHRASE_LEN
+        )));
+    }
+    if !folder.is_dir() {
+        return Err(AppError::Validation(format!(
+            "{} is not a folder",
+            folder.display()
+        )));
+    }
+    tauri::async_runtime::spawn_blocking(move || {
+        Store::open(&folder, &passphrase).map_err(AppError::Validation)?;
+        secure_store::write_blocking(PASSPHRASE_ENTRY, &passphrase).map_err(|e| {
+            AppError::Keychain(format!("Failed to store the sync passphrase: {}", e))
+        })?;
+        settings::update(&app, |settings| {
+            settings.sync.enabled = true;
+            settings.sync.folder = Some(folder);
+        })?;
+        sync(&app).map_err(AppError::Io)
+    })
+    .await?
+}
+
+// Stop syncing; what's already in the folder and on this device is left alone
+#[tauri::command]
+pub async fn disable_sync(app: AppHandle) -> Result<SyncStatus, AppError> {
+    settings::update(&app, |settings| settings.sync.enabled = false)?;
+    secure_store::delete(PASSPHRASE_ENTRY)
+        .await
+        .map_err(|e| AppError::Keychain(format!("Failed to delete the sync passphrase: {}", e)))?;
+    set_status(&app, |status| {
+        status.state = SyncState::Disabled;
+        status.folder = None;
+        status.error = None;
+    });
+    Ok(STATUS.lock().unwrap().clone())
+}
+
+#[tauri::command]
+pub async fn sync_now(app: AppHandle) -> Result<SyncStatus, AppError> {
+    let config = settings::current().sync;
+    if !config.enabled || config.folder.is_none() {
+        return Err(AppError::Validation("Sync isn't set up".to_string()));
+    }
+    tauri::async_runtime::spawn_blocking(move || sync(&app).map_err(AppError::Io)).await?
+}
+
+#[tauri::command]
+pub fn get_sync_status() -> SyncStatus {
+    let mut status = STATUS.lock().unwrap().clone();
+    let config = settings::current().sync;
+    if config.enabled && status.state == SyncState::Disabled {
+        status.state = SyncState::Idle;
+        status.folder = config.folder;
+    }
+    status
+}
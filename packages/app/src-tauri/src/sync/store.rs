@@ -0,0 +1,199 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use super::clock::VectorClock;
+
+// Layout inside the folder the user picked:
+//   eliza-sync/key.json          salt and a check value for the shared passphrase
+//   eliza-sync/items/<hash>.json one encrypted record per conversation, plus the settings
+const SYNC_DIR: &str = "eliza-sync";
+const ITEMS_DIR: &str = "items";
+const KEY_FILE: &str = "key.json";
+const FORMAT_VERSION: u32 = 1;
+const KDF_ROUNDS: u32 = 210_000;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+// Encrypted into `key.json` so a wrong passphrase is reported instead of every item failing
+const VERIFIER: &[u8] = b"eliza-sync";
+
+#[derive(Serialize, Deserialize)]
+struct KeyFile {
+    version: u32,
+    salt: String,
+    nonce: String,
+    verifier: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Envelope {
+    version: u32,
+    nonce: String,
+    ciphertext: String,
+}
+
+// What one device last wrote for an item; `data` is `None` once the item was deleted
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncRecord {
+    pub id: String,
+    pub clock: VectorClock,
+    // Milliseconds since the Unix epoch, on the writing device's clock
+    pub modified_at: u64,
+    pub device: String,
+    pub data: Option<Value>,
+}
+
+pub struct Store {
+    items: PathBuf,
+    cipher: Aes256Gcm,
+}
+
+fn decode(field: &str) -> Result<Vec<u8>, String> {
+    BASE64
+        .decode(field)
+        .map_err(|e| format!("The sync folder is corrupt: {}", e))
+}
+
+fn random<const N: usize>() -> [u8; N] {
+    let mut bytes = [0u8; N];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    bytes
+}
+
+fn cipher(passphrase: &str, salt: &[u8]) -> Aes256Gcm {
+    let mut key = [0u8; 32];
+    pbkdf2::pbkdf2_hmac::<Sha256>(passphrase.as_bytes(), salt, KDF_ROUNDS, &mut key);
+    Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key))
+}
+
+// Cloud clients upload whatever is on disk, so never let them see a half-written file
+fn write_atomic(path: &Path, contents: &[u8]) -> Result<(), String> {
+    let tmp = path.with_extension("tmp");
+    fs::write(&tmp, contents).map_err(|e| format!("Failed to write {}: {}", tmp.display(), e))?;
+    fs::rename(&tmp, path).map_err(|e| format!("Failed to replace {}: {}", path.display(), e))
+}
+
+impl Store {
+    // Open the sync set in `folder`, creating it on first use. Fails if the folder was set up
+    // with a different passphrase.
+    pub fn open(folder: &Path, passphrase: &str) -> Result<Store, String> {
+        let root = folder.join(SYNC_DIR);
+        let items = root.join(ITEMS_DIR);
+        fs::create_dir_all(&items)
+            .map_err(|e| format!("Failed to create {}: {}", items.display(), e))?;
+
+        let key_path = root.join(KEY_FILE);
+        let cipher = match fs::read_to_string(&key_path) {
+            Ok(contents) => {
+                let file: KeyFile = serde_json::from_str(&contents)
+                    .map_err(|e| format!("The sync folder is corrupt: {}", e))?;
+                if file.version != FORMAT_VERSION {
+                    return Err(format!("Unsupported sync format version {}", file.version));
+                }
+                let cipher = cipher(passphrase, &decode(&file.salt)?);
+                let verifier = cipher
+                    .decrypt(
+                        Nonce::from_slice(&decode(&file.nonce)?),
+                        decode(&file.verifier)?.as_slice(),
+                    )
+                    .map_err(|_| {
+                        "The passphrase doesn't match the one this sync folder was set up with"
+                            .to_string()
+                    })?;
+                if verifier != VERIFIER {
+                    return Err("The sync folder is corrupt".to_string());
+                }
+                cipher
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                let (salt, nonce) = (random::<SALT_LEN>(), random::<NONCE_LEN>());
+                let cipher = cipher(passphrase, &salt);
+                let verifier = cipher
+                    .encrypt(Nonce::from_slice(&nonce), VERIFIER)
+                    .map_err(|_| "Failed to set up the sync folder".to_string())?;
+                let file = KeyFile {
+                    version: FORMAT_VERSION,
+                    salt: BASE64.encode(salt),
+                    nonce: BASE64.encode(nonce),
+                    verifier: BASE64.encode(verifier),
+                };
+                let contents = serde_json::to_vec_pretty(&file).map_err(|e| e.to_string())?;
+                write_atomic(&key_path, &contents)?;
+                cipher
+            }
+            Err(e) => return Err(format!("Failed to read {}: {}", key_path.display(), e)),
+        };
+        Ok(Store { items, cipher })
+    }
+
+    // File names are derived from the id so they don't reveal which conversation they hold
+    fn item_path(&self, id: &str) -> PathBuf {
+        let digest = Sha256::digest(id.as_bytes());
+        let name: String = digest[..16].iter().map(|b| format!("{:02x}", b)).collect();
+        self.items.join(format!("{}.json", name))
+    }
+
+    fn decrypt(&self, contents: &str) -> Result<SyncRecord, String> {
+        let envelope: Envelope = serde_json::from_str(contents).map_err(|e| e.to_string())?;
+        if envelope.version != FORMAT_VERSION {
+            return Err(format!("unsupported version {}", envelope.version));
+        }
+        let plaintext = self
+            .cipher
+            .decrypt(
+                Nonce::from_slice(&decode(&envelope.nonce)?),
+                decode(&envelope.ciphertext)?.as_slice(),
+            )
+            .map_err(|_| "decryption failed".to_string())?;
+        serde_json::from_slice(&plaintext).map_err(|e| e.to_string())
+    }
+
+    // Every record in the folder by id. Unreadable files, such as a cloud client's conflict
+    // copies or records from a newer version, are skipped.
+    pub fn read_all(&self) -> Result<HashMap<String, SyncRecord>, String> {
+        let entries = fs::read_dir(&self.items)
+            .map_err(|e| format!("Failed to read {}: {}", self.items.display(), e))?;
+        let mut records = HashMap::new();
+        for path in entries.filter_map(Result::ok).map(|entry| entry.path()) {
+            if path.extension().is_none_or(|ext| ext != "json") {
+                continue;
+            }
+            let record = fs::read_to_string(&path)
+                .map_err(|e| e.to_string())
+                .and_then(|contents| self.decrypt(&contents));
+            match record {
+                Ok(record) if self.item_path(&record.id) == path => {
+                    records.insert(record.id.clone(), record);
+                }
+                Ok(_) => tracing::debug!("Ignoring misplaced sync record {}", path.display()),
+                Err(e) => tracing::warn!("Skipping sync record {}: {}", path.display(), e),
+            }
+        }
+        Ok(records)
+    }
+
+    pub fn write(&self, record: &SyncRecord) -> Result<(), String> {
+        let nonce = random::<NONCE_LEN>();
+        let plaintext = serde_json::to_vec(record).map_err(|e| e.to_string())?;
+        let ciphertext = self
+            .cipher
+            .encrypt(Nonce::from_slice(&nonce), plaintext.as_slice())
+            .map_err(|_| format!("Failed to encrypt {}", record.id))?;
+        let envelope = Envelope {
+            version: FORMAT_VERSION,
+            nonce: BASE64.encode(nonce),
+            ciphertext: BASE64.encode(ciphertext),
+        };
+        let contents = serde_json::to_vec(&envelope).map_err(|e| e.to_string())?;
+        write_atomic(&self.item_path(&record.id), &contents)
+    }
+}
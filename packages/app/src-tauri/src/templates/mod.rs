@@ -0,0 +1,389 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::thread;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tauri::{AppHandle, Emitter, Url};
+
+use crate::capabilities::{self, Capability};
+use crate::characters::{self, import};
+use crate::downloads;
+use crate::error::AppError;
+use crate::plugins;
+use crate::workspace::{self, Workspace};
+
+// Index of community templates: `{ "templates": [<template>, ...] }`, each in the same format
+// as the bundled ones
+const REGISTRY_URL: &str =
+    "https://raw.githubusercontent.com/elizaOS/templates/main/templates.json";
+const MAX_REGISTRY_BYTES: usize = 2 * 1024 * 1024;
+const BUNDLED: &[&str] = &[include_str!("starter.json"), include_str!("local.json")];
+const GITIGNORE: &str = "node_modules\ndist\n.env\n.eliza\n.elizadb\n";
+
+#[derive(Debug, Clone, Deserialize)]
+struct TemplateSpec {
+    id: String,
+    name: String,
+    #[serde(default)]
+    description: Option<String>,
+    // package.json dependencies, name to version range
+    dependencies: BTreeMap<String, String>,
+    character: Value,
+    // Written to `.env` in this order
+    #[serde(default)]
+    env: Vec<EnvEntry>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct EnvEntry {
+    key: String,
+    #[serde(default)]
+    value: String,
+    #[serde(default)]
+    comment: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Registry {
+    templates: Vec<TemplateSpec>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TemplateSource {
+    Bundled,
+    Remote,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TemplateInfo {
+    pub id: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub source: TemplateSource,
+    // Plugins the template's character loads
+    pub plugins: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct CreateOptions {
+    // Replaces the template character's name; the project and file names are derived from it
+    pub agent_name: Option<String>,
+    // Install the dependencies once the files are written
+    pub install: bool,
+    // Make the new project the workspace the server runs in
+    pub use_as_workspace: bool,
+}
+
+impl Default for CreateOptions {
+    fn default() -> Self {
+        Self {
+            agent_name: None,
+            install: true,
+            use_as_workspace: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum TemplateStage {
+    Writing,
+    Installing,
+    Completed,
+    Failed,
+}
+
+// Payload of the `template-progress` event
+#[derive(Debug, Clone, Serialize)]
+struct TemplateProgress {
+    dir: PathBuf,
+    stage: TemplateStage,
+    message: Option<String>,
+}
+
+fn emit(app: &AppHandle, dir: &Path, stage: TemplateStage, message: Option<String>) {
+    let progress = TemplateProgress {
+        dir: dir.to_path_buf(),
+        stage,
+        message,
+    };
+    if let Err(e) = app.emit("template-progress", progress) {
+        tracing::warn!("Failed to emit template progress: {}", e);
+    }
+}
+
+impl TemplateSpec {
+    fn info(&self, source: TemplateSource) -> TemplateInfo {
+        let plugins = self
+            .character
+            .get("plugins")
+            .and_then(Value::as_array)
+            .map(|plugins| {
+                plugins
+                    .iter()
+                    .filter_map(|plugin| plugin.as_str().map(str::to_string))
+                    .collect()
+            })
+            .unwrap_or_default();
+        TemplateInfo {
+            id: self.id.clone(),
+            name: self.name.clone(),
+            description: self.description.clone(),
+            source,
+            plugins,
+        }
+    }
+
+    // Remote templates end up in package.json and `.env` and get installed, so they are
+    // held to the same rules as imported characters and plugins
+    fn validate(&self) -> Result<(), String> {
+        if self.id.is_empty() || import::slug(&self.id) != self.id {
+            return Err(format!("Invalid template id: {}", self.id));
+        }
+        for (name, version) in &self.dependencies {
+            plugins::validate_package_name(name)?;
+            plugins::validate_version(version)?;
+        }
+        if let Some(issue) = characters::validate(&self.character).first() {
+            return Err(format!(
+                "The template character is invalid: {}",
+                issue.message
+            ));
+        }
+        import::check_untrusted(&self.character)?;
+        for entry in &self.env {
+            let valid_key = !entry.key.is_empty()
+                && !entry.key.starts_with(|c: char| c.is_ascii_digit())
+                && entry
+                    .key
+                    .chars()
+                    .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_');
+            if !valid_key {
+                return Err(format!("Invalid environment variable name: {}", entry.key));
+            }
+            let multiline = |text: &str| text.contains(['\n', '\r']);
+            if multiline(&entry.value) || entry.comment.as_deref().is_some_and(multiline) {
+                return Err(format!("Invalid default for {}", entry.key));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn bundled() -> Vec<TemplateSpec> {
+    BUNDLED
+        .iter()
+        .map(|contents| serde_json::from_str(contents).expect("bundled templates are valid"))
+        .collect()
+}
+
+// Community templates that pass validation; the rest are skipped
+async fn remote() -> Result<Vec<TemplateSpec>, AppError> {
+    let url = Url::parse(REGISTRY_URL).map_err(|e| e.to_string())?;
+    let body = downloads::fetch_bytes(url, MAX_REGISTRY_BYTES).await?;
+    let registry: Registry = serde_json::from_slice(&body)
+        .map_err(|e| AppError::Network(format!("Invalid template registry: {}", e)))?;
+    Ok(registry
+        .templates
+        .into_iter()
+        .filter(|template| match template.validate() {
+            Ok(()) => true,
+            Err(e) => {
+                tracing::warn!(template = %template.id, "Skipping remote template: {}", e);
+                false
+            }
+        })
+        .collect())
+}
+
+fn write_file(path: &Path, contents: &str) -> Result<(), String> {
+    fs::write(path, contents).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+fn env_file(entries: &[EnvEntry]) -> String {
+    let mut contents = String::new();
+    for entry in entries {
+        if let Some(comment) = &entry.comment {
+            contents.push_str(&format!("# {}\n", comment));
+        }
+        contents.push_str(&format!("{}={}\n", entry.key, entry.value));
+    }
+    contents
+}
+
+// Write package.json, the character, `.env` and `.gitignore` into the empty `dir`
+fn write_project(template: &TemplateSpec, dir: &Path, agent_name: &str) -> Result<(), String> {
+    let mut stem = import::slug(agent_name);
+    if stem.is_empty() {
+        stem = template.id.clone();
+    }
+    let character_file = format!("characters/{}.json", stem);
+    let package = json!({
+        "name": stem,
+        "version": "0.1.0",
+        "private": true,
+        "type": "module",
+        "scripts": {
+            "start": format!("elizaos start --character {}", character_file),
+            "dev": format!("elizaos dev --character {}", character_file),
+        },
+        "dependencies": template.dependencies,
+    });
+    let mut character = template.character.clone();
+    character["name"] = json!(agent_name);
+
+    let characters_dir = dir.join("characters");
+    fs::create_dir_all(&characters_dir)
+        .map_err(|e| format!("Failed to create {}: {}", characters_dir.display(), e))?;
+    let pretty = |value: &Value| serde_json::to_string_pretty(value).map_err(|e| e.to_string());
+    write_file(&dir.join("package.json"), &pretty(&package)?)?;
+    write_file(&dir.join(&character_file), &pretty(&character)?)?;
+    write_file(&dir.join(".env"), &env_file(&template.env))?;
+    write_file(&dir.join(".gitignore"), GITIGNORE)
+}
+
+fn stream_lines(app: &AppHandle, dir: &Path, source: impl Read) {
+    for line in BufReader::new(source).lines().map_while(Result::ok) {
+        emit(app, dir, TemplateStage::Installing, Some(line));
+    }
+}
+
+fn install(app: &AppHandle, dir: &Path) -> Result<(), String> {
+    let (tool, path) = plugins::package_manager(app, dir)?;
+    let args: &[&str] = match tool.as_str() {
+        "bun" => &["install"],
+        _ => &["install", "--no-audit", "--no-fund"],
+    };
+    emit(app, dir, TemplateStage::Installing, None);
+    tracing::info!(dir = %dir.display(), "Running {} install", tool);
+    let mut child = Command::new(&path)
+        .args(args)
+        .current_dir(dir)
+        .env("PATH", crate::cli::path::spawn_path(app, &path))
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to run {}: {}", tool, e))?;
+    let stdout = child.stdout.take();
+    let stderr = child.stderr.take();
+    thread::scope(|scope| {
+        if let Some(stdout) = stdout {
+            scope.spawn(|| stream_lines(app, dir, stdout));
+        }
+        if let Some(stderr) = stderr {
+            scope.spawn(|| stream_lines(app, dir, stderr));
+        }
+    });
+
+    let status = child
+        .wait()
+        .map_err(|e| format!("Failed to wait for {}: {}", tool, e))?;
+    if !status.success() {
+        return Err(format!("{} install exited with {}", tool, status));
+    }
+    Ok(())
+}
+
+fn create(
+    app: &AppHandle,
+    template: &TemplateSpec,
+    dir: &Path,
+    options: &CreateOptions,
+) -> Result<Workspace, String> {
+    emit(app, dir, TemplateStage::Writing, None);
+    fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    let agent_name = options
+        .agent_name
+        .as_deref()
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .or_else(|| template.character.get("name")?.as_str())
+        .unwrap_or(&template.name)
+        .to_string();
+    write_project(template, dir, &agent_name)?;
+    if options.install {
+        install(app, dir)?;
+    }
+    let path = if options.use_as_workspace {
+        workspace::set(app, dir)?
+    } else {
+        dir.canonicalize()
+            .map_err(|e| format!("Failed to resolve {}: {}", dir.display(), e))?
+    };
+    Ok(Workspace {
+        path,
+        created: true,
+    })
+}
+
+// Bundled templates first, then the community ones when the registry is reachable
+#[tauri::command]
+pub async fn list_templates() -> Result<Vec<TemplateInfo>, AppError> {
+    let mut templates: Vec<TemplateInfo> = bundled()
+        .iter()
+        .map(|template| template.info(TemplateSource::Bundled))
+        .collect();
+    match remote().await {
+        Ok(remote) => templates.extend(
+            remote
+                .iter()
+                .filter(|template| !templates.iter().any(|bundled| bundled.id == template.id))
+                .map(|template| template.info(TemplateSource::Remote))
+                .collect::<Vec<_>>(),
+        ),
+        Err(e) => tracing::warn!("Failed to load remote templates: {}", e),
+    }
+    Ok(templates)
+}
+
+// Scaffold a new elizaOS project in `dir`, which must be missing or empty, streaming
+// `template-progress` while the dependencies install
+#[tauri::command]
+pub async fn create_from_template(
+    app: AppHandle,
+    template: String,
+    dir: PathBuf,
+    options: Option<CreateOptions>,
+) -> Result<Workspace, AppError> {
+    let options = options.unwrap_or_default();
+    // The server runs a workspace's plugins, like the packages an install brings in
+    if options.install || options.use_as_workspace {
+        capabilities::require(Capability::RunPrograms)?;
+    }
+    if dir.exists() && !workspace::is_empty_dir(&dir) {
+        return Err(AppError::Validation(format!(
+            "{} already exists and is not empty",
+            dir.display()
+        )));
+    }
+    let spec = match bundled().into_iter().find(|spec| spec.id == template) {
+        Some(spec) => spec,
+        None => remote()
+            .await?
+            .into_iter()
+            .find(|spec| spec.id == template)
+            .ok_or_else(|| AppError::NotFound(format!("Unknown template: {}", template)))?,
+    };
+
+    tauri::async_runtime::spawn_blocking(move || {
+        tracing::info!(template = %spec.id, dir = %dir.display(), "Creating project from template");
+        match create(&app, &spec, &dir, &options) {
+            Ok(workspace) => {
+                emit(&app, &dir, TemplateStage::Completed, None);
+                Ok(workspace)
+            }
+            Err(e) => {
+                tracing::error!(template = %spec.id, "Creating project failed: {}", e);
+                emit(&app, &dir, TemplateStage::Failed, Some(e.clone()));
+                Err(AppError::Io(e))
+            }
+        }
+    })
+    .await?
+}
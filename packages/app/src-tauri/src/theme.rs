@@ -0,0 +1,151 @@
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+
+use crate::error::AppError;
+use crate::hardware::output;
+use crate::settings::{self, Theme};
+
+// The window events only report changes of the window's own theme, which an override pins,
+// so the OS setting is also polled
+const CHECK_INTERVAL: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorScheme {
+    Light,
+    Dark,
+}
+
+// Payload of the `theme-changed` event
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Appearance {
+    // What the user chose in the settings
+    pub preference: Theme,
+    // What the OS is set to
+    pub system: ColorScheme,
+    // What the UI should render
+    pub effective: ColorScheme,
+}
+
+static CURRENT: Mutex<Option<Appearance>> = Mutex::new(None);
+
+// `AppleInterfaceStyle` is only set while dark mode is on
+#[cfg(target_os = "macos")]
+fn system_scheme() -> ColorScheme {
+    match output("defaults", &["read", "-g", "AppleInterfaceStyle"]) {
+        Some(style) if style.trim().eq_ignore_ascii_case("dark") => ColorScheme::Dark,
+        _ => ColorScheme::Light,
+    }
+}
+
+#[cfg(windows)]
+fn system_scheme() -> ColorScheme {
+    let light = output(
+        "reg",
+        &[
+            "query",
+            r"HKCU\Software\Microsoft\Windows\CurrentVersion\Themes\Personalize",
+            "/v",
+            "AppsUseLightTheme",
+        ],
+    );
+    // The value is printed as e.g. `AppsUseLightTheme    REG_DWORD    0x0`
+    match light {
+        Some(text) if text.trim_end().ends_with("0x0") => ColorScheme::Dark,
+        _ => ColorScheme::Light,
+    }
+}
+
+// GNOME 42+ and most desktops following it expose `color-scheme`; older ones only pick a
+// dark GTK theme
+#[cfg(all(unix, not(target_os = "macos")))]
+fn system_scheme() -> ColorScheme {
+    let interface = "org.gnome.desktop.interface";
+    if let Some(scheme) = output("gsettings", &["get", interface, "color-scheme"]) {
+        if scheme.contains("dark") {
+            return ColorScheme::Dark;
+        }
+        if scheme.contains("light") {
+            return ColorScheme::Light;
+        }
+    }
+    match output("gsettings", &["get", interface, "gtk-theme"]) {
+        Some(theme) if theme.to_lowercase().contains("dark") => ColorScheme::Dark,
+        _ => ColorScheme::Light,
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+fn system_scheme() -> ColorScheme {
+    ColorScheme::Light
+}
+
+fn resolve(preference: Theme) -> Appearance {
+    let system = system_scheme();
+    let effective = match preference {
+        Theme::System => system,
+        Theme::Light => ColorScheme::Light,
+        Theme::Dark => ColorScheme::Dark,
+    };
+    Appearance {
+        preference,
+        system,
+        effective,
+    }
+}
+
+// Match the native window chrome such as the title bar to the chosen theme
+fn apply_chrome(app: &AppHandle, preference: Theme) {
+    app.set_theme(match preference {
+        Theme::System => None,
+        Theme::Light => Some(tauri::Theme::Light),
+        Theme::Dark => Some(tauri::Theme::Dark),
+    });
+}
+
+// Re-read the OS and user preference, applying and announcing any change
+pub fn refresh(app: &AppHandle) -> Appearance {
+    let appearance = resolve(settings::current().theme);
+    let previous = CURRENT.lock().unwrap().replace(appearance);
+    if previous == Some(appearance) {
+        return appearance;
+    }
+    if previous.map(|previous| previous.preference) != Some(appearance.preference) {
+        apply_chrome(app, appearance.preference);
+    }
+    tracing::debug!(?appearance, "Theme changed");
+    if let Err(e) = app.emit("theme-changed", appearance) {
+        tracing::warn!("Failed to emit theme change: {}", e);
+    }
+    appearance
+}
+
+// Apply the saved theme and follow OS changes; called once from the setup hook
+pub fn init(app: &AppHandle) {
+    refresh(app);
+    let app = app.clone();
+    thread::spawn(move || loop {
+        thread::sleep(CHECK_INTERVAL);
+        refresh(&app);
+    });
+}
+
+#[tauri::command]
+pub async fn get_theme(app: AppHandle) -> Result<Appearance, AppError> {
+    let current = *CURRENT.lock().unwrap();
+    match current {
+        Some(appearance) => Ok(appearance),
+        None => Ok(tauri::async_runtime::spawn_blocking(move || refresh(&app)).await?),
+    }
+}
+
+// Persist the user's override and apply it right away
+#[tauri::command]
+pub async fn set_theme(app: AppHandle, theme: Theme) -> Result<Appearance, AppError> {
+    settings::update(&app, |settings| settings.theme = theme)?;
+    Ok(tauri::async_runtime::spawn_blocking(move || refresh(&app)).await?)
+}
@@ -0,0 +1,351 @@
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use chrono::{DateTime, Datelike, NaiveDateTime, Utc};
+use ring::rand::{SecureRandom, SystemRandom};
+use ring::signature::{EcdsaKeyPair, KeyPair, ECDSA_P256_SHA256_ASN1_SIGNING};
+
+// Object identifiers used in the certificate
+const ECDSA_WITH_SHA256: &[u64] = &[1, 2, 840, 10045, 4, 3, 2];
+const EC_PUBLIC_KEY: &[u64] = &[1, 2, 840, 10045, 2, 1];
+const PRIME256V1: &[u64] = &[1, 2, 840, 10045, 3, 1, 7];
+const COMMON_NAME: &[u64] = &[2, 5, 4, 3];
+const SUBJECT_ALT_NAME: &[u64] = &[2, 5, 29, 17];
+const EXT_KEY_USAGE: &[u64] = &[2, 5, 29, 37];
+const SERVER_AUTH: &[u64] = &[1, 3, 6, 1, 5, 5, 7, 3, 1];
+
+const SEQUENCE: u8 = 0x30;
+const SET: u8 = 0x31;
+const INTEGER: u8 = 0x02;
+const BIT_STRING: u8 = 0x03;
+const OCTET_STRING: u8 = 0x04;
+const OID: u8 = 0x06;
+const UTF8_STRING: u8 = 0x0c;
+const UTC_TIME: u8 = 0x17;
+const GENERALIZED_TIME: u8 = 0x18;
+
+pub const SUBJECT: &str = "elizaOS Desktop localhost";
+
+// A self-signed certificate and its private key, both DER
+pub struct Generated {
+    pub cert: Vec<u8>,
+    pub key: Vec<u8>,
+}
+
+fn tlv(tag: u8, content: &[u8]) -> Vec<u8> {
+    let mut out = vec![tag];
+    let len = content.len();
+    if len < 0x80 {
+        out.push(len as u8);
+    } else {
+        let bytes: Vec<u8> = len
+            .to_be_bytes()
+            .into_iter()
+            .skip_while(|byte| *byte == 0)
+            .collect();
+        out.push(0x80 | bytes.len() as u8);
+        out.extend(bytes);
+    }
+    out.extend_from_slice(content);
+    out
+}
+
+fn constructed(tag: u8, parts: &[Vec<u8>]) -> Vec<u8> {
+    tlv(tag, &parts.concat())
+}
+
+fn oid(arcs: &[u64]) -> Vec<u8> {
+    let mut content = vec![(arcs[0] * 40 + arcs[1]) as u8];
+    for arc in &arcs[2..] {
+        let mut groups = vec![(arc & 0x7f) as u8];
+        let mut rest = arc >> 7;
+        while rest > 0 {
+            groups.push((rest & 0x7f) as u8 | 0x80);
+            rest >>= 7;
+        }
+        content.extend(groups.into_iter().rev());
+    }
+    tlv(OID, &content)
+}
+
+fn time(at: DateTime<Utc>) -> Vec<u8> {
+    // UTCTime only covers 1950 to 2049
+    if at.year() < 2050 {
+        tlv(UTC_TIME, at.format("%y%m%d%H%M%SZ").to_string().as_bytes())
+    } else {
+        tlv(
+            GENERALIZED_TIME,
+            at.format("%Y%m%d%H%M%SZ").to_string().as_bytes(),
+        )
+    }
+}
+
+fn name() -> Vec<u8> {
+    constructed(
+        SEQUENCE,
+        &[constructed(
+            SET,
+            &[constructed(
+                SEQUENCE,
+                &[oid(COMMON_NAME), tlv(UTF8_STRING, SUBJECT.as_bytes())],
+            )],
+        )],
+    )
+}
+
+fn extension(id: &[u64], value: Vec<u8>) -> Vec<u8> {
+    constructed(SEQUENCE, &[oid(id), tlv(OCTET_STRING, &value)])
+}
+
+// A certificate for `localhost`, `127.0.0.1` and `::1`, signed with a new P-256 key
+pub fn self_signed(
+    not_before: DateTime<Utc>,
+    not_after: DateTime<Utc>,
+) -> Result<Generated, String> {
+    let rng = SystemRandom::new();
+    let key = EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_ASN1_SIGNING, &rng)
+        .map_err(|_| "Failed to generate a key".to_string())?;
+    let pair = EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_ASN1_SIGNING, key.as_ref(), &rng)
+        .map_err(|e| format!("Failed to load the generated key: {}", e))?;
+
+    let mut serial = [0u8; 16];
+    rng.fill(&mut serial)
+        .map_err(|_| "Failed to generate a serial number".to_string())?;
+    // Serial numbers must be positive
+    serial[0] &= 0x7f;
+    serial[0] |= 0x01;
+
+    let algorithm = constructed(SEQUENCE, &[oid(ECDSA_WITH_SHA256)]);
+    let mut public_key = vec![0];
+    public_key.extend_from_slice(pair.public_key().as_ref());
+    let alt_names = constructed(
+        SEQUENCE,
+        &[
+            tlv(0x82, b"localhost"),
+            tlv(0x87, &[127, 0, 0, 1]),
+            tlv(0x87, &std::net::Ipv6Addr::LOCALHOST.octets()),
+        ],
+    );
+    let tbs = constructed(
+        SEQUENCE,
+        &[
+            // Version 3
+            constructed(0xa0, &[tlv(INTEGER, &[2])]),
+            tlv(INTEGER, &serial),
+            algorithm.clone(),
+            name(),
+            constructed(SEQUENCE, &[time(not_before), time(not_after)]),
+            name(),
+            constructed(
+                SEQUENCE,
+                &[
+                    constructed(SEQUENCE, &[oid(EC_PUBLIC_KEY), oid(PRIME256V1)]),
+                    tlv(BIT_STRING, &public_key),
+                ],
+            ),
+            constructed(
+                0xa3,
+                &[constructed(
+                    SEQUENCE,
+                    &[
+                        extension(SUBJECT_ALT_NAME, alt_names),
+                        extension(EXT_KEY_USAGE, constructed(SEQUENCE, &[oid(SERVER_AUTH)])),
+                    ],
+                )],
+            ),
+        ],
+    );
+    let signature = pair
+        .sign(&rng, &tbs)
+        .map_err(|_| "Failed to sign the certificate".to_string())?;
+    let mut signature_bits = vec![0];
+    signature_bits.extend_from_slice(signature.as_ref());
+    let cert = constructed(
+        SEQUENCE,
+        &[tbs, algorithm, tlv(BIT_STRING, &signature_bits)],
+    );
+    Ok(Generated {
+        cert,
+        key: key.as_ref().to_vec(),
+    })
+}
+
+// Split off the first element: its tag, its content and what follows it
+fn read_tlv(input: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    let (&tag, rest) = input.split_first()?;
+    let (&first, rest) = rest.split_first()?;
+    let (len, rest) = if first < 0x80 {
+        (first as usize, rest)
+    } else {
+        let count = (first & 0x7f) as usize;
+        if count == 0 || count > 4 || rest.len() < count {
+            return None;
+        }
+        let len = rest[..count]
+            .iter()
+            .fold(0usize, |len, byte| (len << 8) | *byte as usize);
+        (len, &rest[count..])
+    };
+    (rest.len() >= len).then(|| (tag, &rest[..len], &rest[len..]))
+}
+
+fn parse_time(tag: u8, content: &[u8]) -> Option<DateTime<Utc>> {
+    let text = std::str::from_utf8(content).ok()?;
+    let format = match tag {
+        UTC_TIME => "%y%m%d%H%M%SZ",
+        GENERALIZED_TIME => "%Y%m%d%H%M%SZ",
+        _ => return None,
+    };
+    NaiveDateTime::parse_from_str(text, format)
+        .ok()
+        .map(|time| time.and_utc())
+}
+
+// When a DER certificate becomes valid and when it expires
+pub fn validity(cert: &[u8]) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+    let (_, certificate, _) = read_tlv(cert)?;
+    let (_, tbs, _) = read_tlv(certificate)?;
+    let mut rest = tbs;
+    // The version is optional and tagged [0]
+    if rest.first() == Some(&0xa0) {
+        rest = read_tlv(rest)?.2;
+    }
+    // Serial number, signature algorithm and issuer
+    for _ in 0..3 {
+        rest = read_tlv(rest)?.2;
+    }
+    let (_, validity, _) = read_tlv(rest)?;
+    let (tag, start, rest) = read_tlv(validity)?;
+    let not_before = parse_time(tag, start)?;
+    let (tag, end, _) = read_tlv(rest)?;
+    Some((not_before, parse_time(tag, end)?))
+}
+
+pub fn pem_encode(label: &str, der: &[u8]) -> String {
+    let encoded = STANDARD.encode(der);
+    let mut pem = format!("-----BEGIN {}-----\n", label);
+    for line in encoded.as_bytes().chunks(64) {
+        pem.push_str(&String::from_utf8_lossy(line));
+        pem.push('\n');
+    }
+    pem.push_str(&format!("-----END {}-----\n", label));
+    pem
+}
+
+// The first `label` block in a PEM file
+pub fn pem_decode(label: &str, pem: &str) -> Option<Vec<u8>> {
+    let begin = format!("-----BEGIN {}-----", label);
+    let end = format!("-----END {}-----", label);
+    let start = pem.find(&begin)? + begin.len();
+    let stop = start + pem[start..].find(&end)?;
+    let body: String = pem[start..stop]
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect();
+    STANDARD.decode(body).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone};
+    use webpki::types::{CertificateDer, ServerName, UnixTime};
+    use webpki::{EndEntityCert, KeyUsage};
+
+    fn at(time: DateTime<Utc>) -> UnixTime {
+        UnixTime::since_unix_epoch(std::time::Duration::from_secs(time.timestamp() as u64))
+    }
+
+    fn generate() -> (Generated, DateTime<Utc>, DateTime<Utc>) {
+        let now = Utc::now();
+        let (not_before, not_after) = (now - Duration::days(1), now + Duration::days(30));
+        (
+            self_signed(not_before, not_after).unwrap(),
+            not_before,
+            not_after,
+        )
+    }
+
+    // Checked as its own trust anchor, which covers the signature, validity and key usage
+    fn verify(cert: &[u8], time: DateTime<Utc>) -> Result<(), webpki::Error> {
+        let der = CertificateDer::from(cert);
+        let anchor = webpki::anchor_from_trusted_cert(&der)?.to_owned();
+        EndEntityCert::try_from(&der)?
+            .verify_for_usage(
+                &[webpki::ring::ECDSA_P256_SHA256],
+                &[anchor],
+                &[],
+                at(time),
+                KeyUsage::server_auth(),
+                None,
+                None,
+            )
+            .map(|_| ())
+    }
+
+    #[test]
+    fn is_valid_for_its_dates_only() {
+        let (generated, not_before, not_after) = generate();
+        verify(&generated.cert, Utc::now()).unwrap();
+        assert_eq!(
+            verify(&generated.cert, not_before - Duration::hours(1)),
+            Err(webpki::Error::CertNotValidYet)
+        );
+        assert_eq!(
+            verify(&generated.cert, not_after + Duration::hours(1)),
+            Err(webpki::Error::CertExpired)
+        );
+    }
+
+    #[test]
+    fn names_localhost_and_loopback_addresses() {
+        let (generated, _, _) = generate();
+        let der = CertificateDer::from(generated.cert.as_slice());
+        let cert = EndEntityCert::try_from(&der).unwrap();
+        let names = [
+            ServerName::try_from("localhost").unwrap(),
+            ServerName::from(std::net::IpAddr::from([127, 0, 0, 1])),
+            ServerName::from(std::net::IpAddr::from(std::net::Ipv6Addr::LOCALHOST)),
+        ];
+        for name in names {
+            cert.verify_is_valid_for_subject_name(&name).unwrap();
+        }
+        let other = ServerName::try_from("example.com").unwrap();
+        assert!(cert.verify_is_valid_for_subject_name(&other).is_err());
+    }
+
+    #[test]
+    fn key_matches_the_certificate() {
+        let (generated, _, _) = generate();
+        let rng = SystemRandom::new();
+        let pair = EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_ASN1_SIGNING, &generated.key, &rng)
+            .unwrap();
+        let signature = pair.sign(&rng, b"handshake").unwrap();
+        let der = CertificateDer::from(generated.cert.as_slice());
+        EndEntityCert::try_from(&der)
+            .unwrap()
+            .verify_signature(
+                webpki::ring::ECDSA_P256_SHA256,
+                b"handshake",
+                signature.as_ref(),
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn reads_back_both_time_encodings() {
+        let not_before = Utc.with_ymd_and_hms(2049, 12, 31, 23, 59, 59).unwrap();
+        let not_after = Utc.with_ymd_and_hms(2051, 6, 1, 12, 0, 0).unwrap();
+        let generated = self_signed(not_before, not_after).unwrap();
+        assert_eq!(validity(&generated.cert), Some((not_before, not_after)));
+        verify(&generated.cert, not_before + Duration::days(1)).unwrap();
+    }
+
+    #[test]
+    fn pem_round_trips() {
+        let (generated, _, _) = generate();
+        let pem = pem_encode("CERTIFICATE", &generated.cert);
+        assert!(pem.lines().all(|line| line.len() <= 64));
+        assert_eq!(pem_decode("CERTIFICATE", &pem), Some(generated.cert));
+        assert_eq!(pem_decode("PRIVATE KEY", &pem), None);
+    }
+}
@@ -0,0 +1,397 @@
+pub mod cert;
+
+use std::fs;
+use std::future::Future;
+use std::io;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use axum::extract::connect_info::Connected;
+use axum::serve::IncomingStream;
+use axum::Router;
+use chrono::{DateTime, Utc};
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tauri::{AppHandle, Manager};
+use tokio::net::{TcpListener, TcpStream};
+use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer, PrivatePkcs8KeyDer};
+use tokio_rustls::rustls::{self, ServerConfig};
+use tokio_rustls::server::TlsStream;
+use tokio_rustls::TlsAcceptor;
+
+use crate::capabilities::{self, Capability};
+use crate::cli::path::{find_tool, spawn_path};
+use crate::error::AppError;
+use crate::settings;
+
+const CERT_FILE: &str = "localhost.pem";
+const KEY_FILE: &str = "localhost-key.pem";
+// Browsers reject server certificates valid for longer than 398 days
+const LIFETIME_DAYS: i64 = 397;
+// Certificates are replaced this long before they expire
+const RENEW_BEFORE_DAYS: i64 = 30;
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CertSource {
+    // Generated here; browsers and the webview warn until the certificate is trusted
+    SelfSigned,
+    // Issued by mkcert's local authority, which `mkcert -install` adds to the OS trust store
+    Mkcert,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LocalTlsSettings {
+    // Serve the localhost proxy and the webhook listener over HTTPS
+    pub enabled: bool,
+    pub source: CertSource,
+}
+
+impl Default for LocalTlsSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            source: CertSource::SelfSigned,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LocalCertInfo {
+    pub enabled: bool,
+    pub source: CertSource,
+    pub cert_path: Option<PathBuf>,
+    // Colon-separated hex, the form certificate viewers show
+    pub fingerprint_sha256: Option<String>,
+    // Milliseconds since the Unix epoch
+    pub not_before: Option<u64>,
+    pub not_after: Option<u64>,
+    // Whether listeners started before the last change still serve the old setup
+    pub restart_required: bool,
+}
+
+static DIR: OnceCell<PathBuf> = OnceCell::new();
+// Fingerprint of the certificate the listeners were started with; `None` when serving plain HTTP
+static SERVING: Mutex<Option<String>> = Mutex::new(None);
+
+fn dir() -> Result<&'static PathBuf, String> {
+    DIR.get()
+        .ok_or_else(|| "The certificate folder is not available".to_string())
+}
+
+fn cert_path() -> Result<PathBuf, String> {
+    Ok(dir()?.join(CERT_FILE))
+}
+
+fn key_path() -> Result<PathBuf, String> {
+    Ok(dir()?.join(KEY_FILE))
+}
+
+fn fingerprint(der: &[u8]) -> String {
+    Sha256::digest(der)
+        .iter()
+        .map(|byte| format!("{:02X}", byte))
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+fn write_private(path: &Path, contents: &str) -> Result<(), String> {
+    let tmp = path.with_extension("pem.tmp");
+    fs::write(&tmp, contents).map_err(|e| format!("Failed to write {}: {}", tmp.display(), e))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let _ = fs::set_permissions(&tmp, fs::Permissions::from_mode(0o600));
+    }
+    fs::rename(&tmp, path).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+fn read_cert() -> Option<Vec<u8>> {
+    let pem = fs::read_to_string(cert_path().ok()?).ok()?;
+    cert::pem_decode("CERTIFICATE", &pem)
+}
+
+fn read_key() -> Option<Vec<u8>> {
+    let pem = fs::read_to_string(key_path().ok()?).ok()?;
+    cert::pem_decode("PRIVATE KEY", &pem)
+}
+
+fn generate_self_signed() -> Result<(), String> {
+    let now = Utc::now();
+    let generated = cert::self_signed(
+        now - chrono::Duration::days(1),
+        now + chrono::Duration::days(LIFETIME_DAYS),
+    )?;
+    write_private(
+        &key_path()?,
+        &cert::pem_encode("PRIVATE KEY", &generated.key),
+    )?;
+    write_private(
+        &cert_path()?,
+        &cert::pem_encode("CERTIFICATE", &generated.cert),
+    )?;
+    tracing::info!(
+        fingerprint = %fingerprint(&generated.cert),
+        "Generated a self-signed localhost certificate"
+    );
+    Ok(())
+}
+
+fn generate_mkcert(app: &AppHandle) -> Result<(), String> {
+    let mkcert = find_tool(app, "mkcert")
+        .ok_or("mkcert is not installed; install it and run `mkcert -install` first")?;
+    let output = Command::new(&mkcert)
+        .env("PATH", spawn_path(app, &mkcert))
+        .arg("-cert-file")
+        .arg(cert_path()?)
+        .arg("-key-file")
+        .arg(key_path()?)
+        .args(["localhost", "127.0.0.1", "::1"])
+        .output()
+        .map_err(|e| format!("Failed to run mkcert: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "mkcert failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    tracing::info!("Issued a localhost certificate with mkcert");
+    Ok(())
+}
+
+// Create or replace the certificate from the configured source
+fn generate(app: &AppHandle, source: CertSource) -> Result<(), String> {
+    fs::create_dir_all(dir()?)
+        .map_err(|e| format!("Failed to create the certificate folder: {}", e))?;
+    match source {
+        CertSource::SelfSigned => generate_self_signed(),
+        CertSource::Mkcert => generate_mkcert(app),
+    }
+}
+
+fn needs_renewal(cert: Option<&[u8]>) -> bool {
+    let Some((_, not_after)) = cert.and_then(cert::validity) else {
+        return true;
+    };
+    not_after - Utc::now() < chrono::Duration::days(RENEW_BEFORE_DAYS)
+}
+
+// Make sure a usable certificate exists if TLS is on; called once from the setup hook,
+// before any listener starts
+pub fn init(app: &AppHandle) -> Result<(), String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to resolve the app data dir: {}", e))?
+        .join("tls");
+    let _ = DIR.set(dir);
+    let config = settings::current().local_tls;
+    if config.enabled && (needs_renewal(read_cert().as_deref()) || read_key().is_none()) {
+        generate(app, config.source)?;
+    }
+    Ok(())
+}
+
+fn server_config() -> Result<ServerConfig, String> {
+    let cert = read_cert().ok_or("The localhost certificate is missing")?;
+    let key = read_key().ok_or("The localhost certificate key is missing")?;
+    let mut config =
+        ServerConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+            .with_safe_default_protocol_versions()
+            .map_err(|e| e.to_string())?
+            .with_no_client_auth()
+            .with_single_cert(
+                vec![CertificateDer::from(cert)],
+                PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(key)),
+            )
+            .map_err(|e| format!("The localhost certificate is unusable: {}", e))?;
+    config.alpn_protocols = vec![b"http/1.1".to_vec()];
+    Ok(config)
+}
+
+// The acceptor listeners should use, or `None` to serve plain HTTP. A certificate that can't
+// be loaded is logged and falls back to plain HTTP so the app stays usable.
+pub fn acceptor() -> Option<TlsAcceptor> {
+    if !settings::current().local_tls.enabled {
+        return None;
+    }
+    match server_config() {
+        Ok(config) => {
+            *SERVING.lock().unwrap() = read_cert().map(|cert| fingerprint(&cert));
+            Some(TlsAcceptor::from(Arc::new(config)))
+        }
+        Err(e) => {
+            tracing::error!("Serving plain HTTP: {}", e);
+            None
+        }
+    }
+}
+
+pub fn scheme(acceptor: &Option<TlsAcceptor>) -> &'static str {
+    match acceptor {
+        Some(_) => "https",
+        None => "http",
+    }
+}
+
+// Environment that makes the spawned server trust the certificate when calling back into the
+// proxy over HTTPS
+pub fn server_env() -> Vec<(&'static str, String)> {
+    match (settings::current().local_tls.enabled, cert_path()) {
+        (true, Ok(path)) if path.exists() => {
+            vec![("NODE_EXTRA_CA_CERTS", path.to_string_lossy().into_owned())]
+        }
+        _ => Vec::new(),
+    }
+}
+
+struct TlsListener {
+    inner: TcpListener,
+    acceptor: TlsAcceptor,
+}
+
+impl axum::serve::Listener for TlsListener {
+    type Io = TlsStream<TcpStream>;
+    type Addr = SocketAddr;
+
+    async fn accept(&mut self) -> (Self::Io, Self::Addr) {
+        loop {
+            let (stream, address) = match self.inner.accept().await {
+                Ok(accepted) => accepted,
+                Err(e) => {
+                    tracing::debug!("Failed to accept a connection: {}", e);
+                    tokio::time::sleep(Duration::from_millis(50)).await;
+                    continue;
+                }
+            };
+            match tokio::time::timeout(HANDSHAKE_TIMEOUT, self.acceptor.accept(stream)).await {
+                Ok(Ok(stream)) => return (stream, address),
+                Ok(Err(e)) => tracing::debug!(%address, "TLS handshake failed: {}", e),
+                Err(_) => tracing::debug!(%address, "TLS handshake timed out"),
+            }
+        }
+    }
+
+    fn local_addr(&self) -> io::Result<Self::Addr> {
+        self.inner.local_addr()
+    }
+}
+
+// The remote address of a connection, for handlers extracting `ConnectInfo<Peer>` from either
+// kind of listener
+#[derive(Debug, Clone, Copy)]
+pub struct Peer(pub SocketAddr);
+
+impl Connected<IncomingStream<'_, TcpListener>> for Peer {
+    fn connect_info(stream: IncomingStream<'_, TcpListener>) -> Self {
+        Peer(*stream.remote_addr())
+    }
+}
+
+impl Connected<IncomingStream<'_, TlsListener>> for Peer {
+    fn connect_info(stream: IncomingStream<'_, TlsListener>) -> Self {
+        Peer(*stream.remote_addr())
+    }
+}
+
+// Serve `router` until `shutdown` completes, over TLS when `acceptor` is given
+pub async fn serve(
+    listener: TcpListener,
+    router: Router,
+    acceptor: Option<TlsAcceptor>,
+    shutdown: impl Future<Output = ()> + Send + 'static,
+) -> io::Result<()> {
+    match acceptor {
+        Some(acceptor) => {
+            let listener = TlsListener {
+                inner: listener,
+                acceptor,
+            };
+            axum::serve(
+                listener,
+                router.into_make_service_with_connect_info::<Peer>(),
+            )
+            .with_graceful_shutdown(shutdown)
+            .await
+        }
+        None => {
+            axum::serve(
+                listener,
+                router.into_make_service_with_connect_info::<Peer>(),
+            )
+            .with_graceful_shutdown(shutdown)
+            .await
+        }
+    }
+}
+
+fn info() -> LocalCertInfo {
+    let config = settings::current().local_tls;
+    let cert = read_cert();
+    let validity = cert.as_deref().and_then(cert::validity);
+    let millis = |time: DateTime<Utc>| time.timestamp_millis().max(0) as u64;
+    let current = cert.as_deref().map(fingerprint);
+    let serving = SERVING.lock().unwrap().clone();
+    LocalCertInfo {
+        enabled: config.enabled,
+        source: config.source,
+        cert_path: cert.as_ref().and_then(|_| cert_path().ok()),
+        restart_required: match config.enabled {
+            true => serving.is_none() || serving != current,
+            false => serving.is_some(),
+        },
+        fingerprint_sha256: current,
+        not_before: validity.map(|(start, _)| millis(start)),
+        not_after: validity.map(|(_, end)| millis(end)),
+    }
+}
+
+#[tauri::command]
+pub fn get_local_cert_info() -> LocalCertInfo {
+    info()
+}
+
+// Turn HTTPS for local listeners on or off. Listeners pick the change up when the app restarts.
+#[tauri::command]
+pub async fn set_local_tls(
+    app: AppHandle,
+    enabled: bool,
+    source: Option<CertSource>,
+) -> Result<LocalCertInfo, AppError> {
+    let source = source.unwrap_or(settings::current().local_tls.source);
+    if enabled && source == CertSource::Mkcert {
+        capabilities::require(Capability::RunPrograms)?;
+    }
+    let changed = source != settings::current().local_tls.source;
+    if enabled && (changed || needs_renewal(read_cert().as_deref())) {
+        let handle = app.clone();
+        tauri::async_runtime::spawn_blocking(move || generate(&handle, source))
+            .await?
+            .map_err(AppError::Io)?;
+    }
+    settings::update(&app, |settings| {
+        settings.local_tls.enabled = enabled;
+        settings.local_tls.source = source;
+    })?;
+    Ok(info())
+}
+
+// Replace the certificate with a new one, e.g. after its key may have leaked
+#[tauri::command]
+pub async fn rotate_local_cert(app: AppHandle) -> Result<LocalCertInfo, AppError> {
+    let source = settings::current().local_tls.source;
+    if source == CertSource::Mkcert {
+        capabilities::require(Capability::RunPrograms)?;
+    }
+    let handle = app.clone();
+    tauri::async_runtime::spawn_blocking(move || generate(&handle, source))
+        .await?
+        .map_err(AppError::Io)?;
+    Ok(info())
+}
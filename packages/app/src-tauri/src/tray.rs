@@ -0,0 +1,143 @@
+use std::thread;
+use std::time::Duration;
+
+use once_cell::sync::OnceCell;
+use tauri::menu::{Menu, MenuEvent, MenuItem, PredefinedMenuItem};
+use tauri::tray::TrayIconBuilder;
+use tauri::{AppHandle, Emitter, Manager, Wry};
+
+use crate::i18n::t;
+use crate::server::{self, ServerStatus};
+
+const STATUS_REFRESH_INTERVAL: Duration = Duration::from_secs(5);
+
+// Disabled menu item whose label reflects the current server status
+static STATUS_ITEM: OnceCell<MenuItem<Wry>> = OnceCell::new();
+// The other items with the message id of their label, relabelled when the locale changes
+static ITEMS: OnceCell<Vec<(MenuItem<Wry>, &'static str)>> = OnceCell::new();
+
+fn status_label(status: ServerStatus) -> String {
+    t(match status {
+        ServerStatus::Running => "tray-status-running",
+        ServerStatus::External => "tray-status-external",
+        ServerStatus::Stopped => "tray-status-stopped",
+    })
+}
+
+pub fn refresh_status() {
+    if let Some(item) = STATUS_ITEM.get() {
+        if let Err(e) = item.set_text(status_label(server::status())) {
+            tracing::warn!("Failed to update tray status: {}", e);
+        }
+    }
+}
+
+// Apply the current locale to every item
+pub fn relabel() {
+    for (item, label) in ITEMS.get().into_iter().flatten() {
+        if let Err(e) = item.set_text(t(label)) {
+            tracing::warn!("Failed to relabel tray item: {}", e);
+        }
+    }
+    refresh_status();
+}
+
+pub fn show_main_window(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.show();
+        let _ = window.unminimize();
+        let _ = window.set_focus();
+    }
+}
+
+fn toggle_main_window(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window("main") {
+        if window.is_visible().unwrap_or(false) {
+            let _ = window.hide();
+        } else {
+            show_main_window(app);
+        }
+    }
+}
+
+// Lifecycle actions block, so run them off the main thread
+pub(crate) fn run_lifecycle(app: &AppHandle, action: fn(&AppHandle) -> Result<(), String>) {
+    let app = app.clone();
+    thread::spawn(move || {
+        if let Err(e) = action(&app) {
+            tracing::warn!("{}", e);
+        }
+        refresh_status();
+    });
+}
+
+fn on_menu_event(app: &AppHandle, event: MenuEvent) {
+    match event.id.as_ref() {
+        "toggle-window" => toggle_main_window(app),
+        "start-server" => run_lifecycle(app, server::start),
+        "stop-server" => run_lifecycle(app, server::stop),
+        "restart-server" => run_lifecycle(app, server::restart),
+        "open-logs" => {
+            show_main_window(app);
+            let _ = app.emit("open-server-logs", ());
+        }
+        "quit" => crate::exit_app(app),
+        _ => {}
+    }
+}
+
+pub fn init(app: &AppHandle) -> tauri::Result<()> {
+    let mut items = Vec::new();
+    let mut item = |id: &str, label: &'static str| {
+        let item = MenuItem::with_id(app, id, t(label), true, None::<&str>)?;
+        items.push((item.clone(), label));
+        tauri::Result::Ok(item)
+    };
+    let toggle = item("toggle-window", "tray-toggle-window")?;
+    let start = item("start-server", "start-server")?;
+    let stop = item("stop-server", "stop-server")?;
+    let restart = item("restart-server", "restart-server")?;
+    let logs = item("open-logs", "tray-open-logs")?;
+    let quit = item("quit", "quit")?;
+    let status = MenuItem::with_id(
+        app,
+        "server-status",
+        status_label(server::status()),
+        false,
+        None::<&str>,
+    )?;
+
+    let menu = Menu::with_items(
+        app,
+        &[
+            &toggle,
+            &PredefinedMenuItem::separator(app)?,
+            &status,
+            &start,
+            &stop,
+            &restart,
+            &logs,
+            &PredefinedMenuItem::separator(app)?,
+            &quit,
+        ],
+    )?;
+
+    let mut tray = TrayIconBuilder::with_id("main")
+        .tooltip("Eliza Desktop")
+        .menu(&menu)
+        .on_menu_event(on_menu_event);
+    if let Some(icon) = app.default_window_icon() {
+        tray = tray.icon(icon.clone());
+    }
+    tray.build(app)?;
+
+    let _ = STATUS_ITEM.set(status);
+    let _ = ITEMS.set(items);
+
+    // Pick up servers that exit or appear without going through the tray
+    thread::spawn(|| loop {
+        thread::sleep(STATUS_REFRESH_INTERVAL);
+        refresh_status();
+    });
+    Ok(())
+}
@@ -0,0 +1,254 @@
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{FromSample, SampleFormat, SizedSample};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use serde_json::Value;
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_global_shortcut::ShortcutState;
+
+use crate::error::AppError;
+use crate::server::{chat, health};
+use crate::{settings, shortcuts};
+
+const RECORDINGS_DIR: &str = "recordings";
+
+// Raw 16-bit PCM as captured from the input device
+struct Captured {
+    samples: Vec<i16>,
+    channels: u16,
+    sample_rate: u32,
+}
+
+struct Recording {
+    stop: mpsc::Sender<()>,
+    thread: JoinHandle<Result<Captured, String>>,
+    started: Instant,
+}
+
+static RECORDING: Lazy<Mutex<Option<Recording>>> = Lazy::new(|| Mutex::new(None));
+
+// Payload of the `voice-recording-finished` event
+#[derive(Debug, Clone, Serialize)]
+struct RecordingFinished {
+    path: PathBuf,
+    duration_ms: u64,
+}
+
+// Payload of the `voice-transcribed` event
+#[derive(Debug, Clone, Serialize)]
+struct Transcribed {
+    path: PathBuf,
+    text: String,
+}
+
+fn input_stream<T>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    samples: Arc<Mutex<Vec<i16>>>,
+) -> Result<cpal::Stream, cpal::BuildStreamError>
+where
+    T: SizedSample,
+    i16: FromSample<T>,
+{
+    device.build_input_stream(
+        config,
+        move |data: &[T], _: &cpal::InputCallbackInfo| {
+            samples
+                .lock()
+                .unwrap()
+                .extend(data.iter().map(|&sample| sample.to_sample::<i16>()));
+        },
+        |e| tracing::warn!("Microphone stream error: {}", e),
+        None,
+    )
+}
+
+// Capture from the default microphone until `stop` fires. cpal streams can't move between
+// threads, so the stream lives and dies on this one.
+fn capture(stop: mpsc::Receiver<()>) -> Result<Captured, String> {
+    let device = cpal::default_host()
+        .default_input_device()
+        .ok_or("No microphone is available")?;
+    let supported = device
+        .default_input_config()
+        .map_err(|e| format!("Failed to query the microphone: {}", e))?;
+    let config = supported.config();
+    let samples = Arc::new(Mutex::new(Vec::new()));
+
+    let stream = match supported.sample_format() {
+        SampleFormat::F32 => input_stream::<f32>(&device, &config, samples.clone()),
+        SampleFormat::I16 => input_stream::<i16>(&device, &config, samples.clone()),
+        SampleFormat::U16 => input_stream::<u16>(&device, &config, samples.clone()),
+        SampleFormat::I32 => input_stream::<i32>(&device, &config, samples.clone()),
+        format => return Err(format!("Unsupported microphone sample format: {}", format)),
+    }
+    .map_err(|e| format!("Failed to open the microphone: {}", e))?;
+    stream
+        .play()
+        .map_err(|e| format!("Failed to start recording: {}", e))?;
+
+    let _ = stop.recv();
+    drop(stream);
+
+    let samples = std::mem::take(&mut *samples.lock().unwrap());
+    Ok(Captured {
+        samples,
+        channels: config.channels,
+        sample_rate: config.sample_rate.0,
+    })
+}
+
+fn write_wav(path: &Path, captured: &Captured) -> Result<(), String> {
+    let spec = hound::WavSpec {
+        channels: captured.channels,
+        sample_rate: captured.sample_rate,
+        bits_per_sample: 16,
+        sample_format: hound::SampleFormat::Int,
+    };
+    let mut writer = hound::WavWriter::create(path, spec)
+        .map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+    for &sample in &captured.samples {
+        writer
+            .write_sample(sample)
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    }
+    writer
+        .finalize()
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+fn recording_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_cache_dir()
+        .map_err(|e| format!("Failed to resolve app cache dir: {}", e))?
+        .join(RECORDINGS_DIR);
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or_default();
+    Ok(dir.join(format!("recording-{}.wav", timestamp)))
+}
+
+// Upload a recording to the default agent's speech-to-text endpoint
+async fn transcribe(path: &Path) -> Result<String, String> {
+    let client = reqwest::Client::new();
+    let agent_id = chat::default_agent(&client).await?;
+    let bytes =
+        std::fs::read(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let part = reqwest::multipart::Part::bytes(bytes)
+        .file_name("recording.wav")
+        .mime_str("audio/wav")
+        .map_err(|e| e.to_string())?;
+    let form = reqwest::multipart::Form::new().part("file", part);
+
+    let base = health::base_url();
+    let url = format!("{}/api/audio/{}/transcriptions", base, agent_id);
+    let body: Value = chat::authorized(&base, client.post(&url))
+        .await
+        .multipart(form)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| format!("Transcription request failed: {}", e))?
+        .json()
+        .await
+        .map_err(|e| format!("Invalid transcription response: {}", e))?;
+    body.pointer("/data/text")
+        .or_else(|| body.get("text"))
+        .and_then(Value::as_str)
+        .map(str::to_string)
+        .ok_or_else(|| "The transcription response has no text".to_string())
+}
+
+fn start_recording(app: &AppHandle) {
+    let mut recording = RECORDING.lock().unwrap();
+    if recording.is_some() {
+        return;
+    }
+    let (stop, stopped) = mpsc::channel();
+    *recording = Some(Recording {
+        stop,
+        thread: thread::spawn(move || capture(stopped)),
+        started: Instant::now(),
+    });
+    let _ = app.emit("voice-recording-started", ());
+}
+
+async fn finish(app: &AppHandle, recording: Recording) -> Result<(), String> {
+    let duration_ms = recording.started.elapsed().as_millis() as u64;
+    let _ = recording.stop.send(());
+    let captured = tauri::async_runtime::spawn_blocking(move || recording.thread.join())
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|_| "The recording thread panicked".to_string())??;
+
+    let path = recording_path(app)?;
+    let (wav, data) = (path.clone(), captured);
+    tauri::async_runtime::spawn_blocking(move || write_wav(&wav, &data))
+        .await
+        .map_err(|e| e.to_string())??;
+
+    if settings::current().transcribe_voice {
+        match transcribe(&path).await {
+            Ok(text) => {
+                let _ = app.emit("voice-transcribed", Transcribed { path, text });
+                return Ok(());
+            }
+            // The UI can still use the recording, e.g. with local speech-to-text
+            Err(e) => tracing::warn!("{}", e),
+        }
+    }
+    app.emit(
+        "voice-recording-finished",
+        RecordingFinished { path, duration_ms },
+    )
+    .map_err(|e| e.to_string())
+}
+
+fn stop_recording(app: &AppHandle) {
+    let Some(recording) = RECORDING.lock().unwrap().take() else {
+        return;
+    };
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = finish(&app, recording).await {
+            tracing::error!("Push-to-talk failed: {}", e);
+            let _ = app.emit("voice-recording-failed", &e);
+        }
+    });
+}
+
+fn on_shortcut(app: &AppHandle, state: ShortcutState) {
+    match state {
+        ShortcutState::Pressed => start_recording(app),
+        ShortcutState::Released => stop_recording(app),
+    }
+}
+
+// Register the configured push-to-talk shortcut
+pub fn init(app: &AppHandle) -> Result<(), String> {
+    shortcuts::bind(app, &settings::current().push_to_talk_shortcut, on_shortcut)
+}
+
+#[tauri::command]
+pub fn get_push_to_talk_shortcut() -> String {
+    settings::current().push_to_talk_shortcut
+}
+
+// Replace the push-to-talk shortcut, e.g. "CommandOrControl+Shift+Space"; empty disables it
+#[tauri::command]
+pub fn set_push_to_talk_shortcut(app: AppHandle, shortcut: String) -> Result<(), AppError> {
+    let previous = settings::current().push_to_talk_shortcut;
+    shortcuts::rebind(&app, &previous, &shortcut, on_shortcut)?;
+    settings::update(&app, |settings| settings.push_to_talk_shortcut = shortcut)?;
+    Ok(())
+}
@@ -0,0 +1,464 @@
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::sync::{Arc, Mutex};
+
+use axum::body::Bytes;
+use axum::extract::{Path, State};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::post;
+use axum::{Json, Router};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tauri::{AppHandle, Emitter};
+use tokio::sync::oneshot;
+
+use crate::auth::secure_store;
+use crate::capabilities::{self, Capability};
+use crate::error::AppError;
+use crate::hardware::output;
+use crate::history::now_millis;
+use crate::server::chat;
+use crate::{settings, tls};
+
+const TOKEN_ENTRY: &str = "webhook-token";
+const TOKEN_HEADER: &str = "x-webhook-token";
+const MAX_BODY_BYTES: usize = 1024 * 1024;
+// Replaced with the request body in prompt templates
+const PAYLOAD_PLACEHOLDER: &str = "{{payload}}";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WebhookBind {
+    // Only programs on this machine
+    Localhost,
+    // Every network interface, so other machines on the LAN can call in
+    Lan,
+    // The Tailscale address, reachable from the user's other tailnet devices
+    Tailnet,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum WebhookAction {
+    // Send the payload, optionally wrapped in `template`, to the agent and answer with its reply
+    Prompt {
+        agent_id: Option<String>,
+        template: Option<String>,
+    },
+    // Hand the payload to the frontend as a `webhook-received` event to act on
+    Event,
+}
+
+// Calls to `POST /hooks/<name>` run `action`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WebhookRoute {
+    pub name: String,
+    pub action: WebhookAction,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WebhookSettings {
+    pub enabled: bool,
+    pub port: u16,
+    pub bind: WebhookBind,
+    pub routes: Vec<WebhookRoute>,
+}
+
+impl Default for WebhookSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: 7787,
+            bind: WebhookBind::Localhost,
+            routes: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WebhookStatus {
+    pub enabled: bool,
+    // Where routes are served, e.g. `http://127.0.0.1:7787/hooks/`; `None` while stopped
+    pub base_url: Option<String>,
+    pub routes: Vec<WebhookRoute>,
+    pub error: Option<String>,
+}
+
+// Payload of the `webhook-received` event
+#[derive(Debug, Clone, Serialize)]
+struct WebhookReceived {
+    route: String,
+    received_at: u64,
+    payload: Value,
+}
+
+// Payload of the `webhook-result` event, sent after a prompt route ran
+#[derive(Debug, Clone, Serialize)]
+struct WebhookResult {
+    route: String,
+    received_at: u64,
+    reply: Option<String>,
+    error: Option<String>,
+}
+
+struct Listener {
+    base_url: String,
+    shutdown: oneshot::Sender<()>,
+}
+
+static LISTENER: Mutex<Option<Listener>> = Mutex::new(None);
+static LAST_ERROR: Mutex<Option<String>> = Mutex::new(None);
+// Read from the keychain when the listener starts rather than on every request
+static TOKEN: Mutex<Option<String>> = Mutex::new(None);
+
+fn random_token() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+// The shared secret callers present, created the first time it's needed
+fn load_token() -> Result<String, String> {
+    if let Some(token) = TOKEN.lock().unwrap().clone() {
+        return Ok(token);
+    }
+    let token = match secure_store::read_blocking(TOKEN_ENTRY)? {
+        Some(token) => token,
+        None => {
+            let token = random_token();
+            secure_store::write_blocking(TOKEN_ENTRY, &token)?;
+            token
+        }
+    };
+    *TOKEN.lock().unwrap() = Some(token.clone());
+    Ok(token)
+}
+
+// Compare without returning early, so response times don't reveal how much of a guess matched
+pub(crate) fn same_secret(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0u8, |diff, (x, y)| diff | (x ^ y))
+            == 0
+}
+
+fn presented_token(headers: &HeaderMap) -> Option<&str> {
+    if let Some(token) = headers.get(TOKEN_HEADER) {
+        return token.to_str().ok();
+    }
+    headers
+        .get(header::AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bearer ")
+}
+
+pub(crate) fn tailnet_address() -> Option<IpAddr> {
+    output("tailscale", &["ip", "-4"])?
+        .lines()
+        .find_map(|line| line.trim().parse().ok())
+}
+
+fn bind_address(config: &WebhookSettings) -> Result<SocketAddr, String> {
+    let ip = match config.bind {
+        WebhookBind::Localhost => IpAddr::V4(Ipv4Addr::LOCALHOST),
+        WebhookBind::Lan => IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+        WebhookBind::Tailnet => tailnet_address()
+            .ok_or("Tailscale isn't running or this machine has no tailnet address")?,
+    };
+    Ok(SocketAddr::new(ip, config.port))
+}
+
+fn validate_name(name: &str) -> Result<(), String> {
+    if name.is_empty()
+        || name.len() > 64
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_')
+    {
+        return Err(format!(
+            "Invalid webhook name {}; use lowercase letters, digits, - and _",
+            name
+        ));
+    }
+    Ok(())
+}
+
+// The payload as text for a prompt: a JSON body's `text` field, or the whole body
+fn payload_text(payload: &Value) -> String {
+    match payload {
+        Value::String(text) => text.clone(),
+        Value::Object(fields) => match fields.get("text") {
+            Some(Value::String(text)) => text.clone(),
+            _ => payload.to_string(),
+        },
+        _ => payload.to_string(),
+    }
+}
+
+async fn run_prompt(
+    route: &str,
+    agent_id: Option<String>,
+    template: Option<&str>,
+    payload: &Value,
+) -> Result<String, String> {
+    let text = payload_text(payload);
+    let prompt = match template {
+        Some(template) if template.contains(PAYLOAD_PLACEHOLDER) => {
+            template.replace(PAYLOAD_PLACEHOLDER, &text)
+        }
+        Some(template) => format!("{}\n\n{}", template, text),
+        None => text,
+    };
+    let conversation_id = format!("webhook-{}", route);
+    let response = chat::send_message(agent_id, &conversation_id, &prompt).await?;
+    chat::reply_text(&response).ok_or_else(|| "The agent did not reply".to_string())
+}
+
+fn error_response(status: StatusCode, message: impl Into<String>) -> Response {
+    (status, Json(json!({ "error": message.into() }))).into_response()
+}
+
+async fn handle(
+    State(app): State<Arc<AppHandle>>,
+    Path(name): Path<String>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    let token = TOKEN.lock().unwrap().clone();
+    let authorized = match (token, presented_token(&headers)) {
+        (Some(expected), Some(presented)) => same_secret(&expected, presented),
+        _ => false,
+    };
+    if !authorized {
+        return error_response(StatusCode::UNAUTHORIZED, "Missing or invalid webhook token");
+    }
+    let Some(route) = settings::current()
+        .webhooks
+        .routes
+        .into_iter()
+        .find(|route| route.name == name)
+    else {
+        return error_response(StatusCode::NOT_FOUND, format!("Unknown webhook: {}", name));
+    };
+
+    let payload = serde_json::from_slice::<Value>(&body)
+        .unwrap_or_else(|_| Value::String(String::from_utf8_lossy(&body).into_owned()));
+    let received_at = now_millis();
+    tracing::info!(route = %name, "Webhook called");
+    match route.action {
+        WebhookAction::Event => {
+            let received = WebhookReceived {
+                route: name,
+                received_at,
+                payload,
+            };
+            if let Err(e) = app.emit("webhook-received", received) {
+                tracing::warn!("Failed to emit webhook: {}", e);
+            }
+            StatusCode::ACCEPTED.into_response()
+        }
+        WebhookAction::Prompt { agent_id, template } => {
+            let result = run_prompt(&name, agent_id, template.as_deref(), &payload).await;
+            let (reply, error) = match &result {
+                Ok(reply) => (Some(reply.clone()), None),
+                Err(e) => {
+                    tracing::warn!(route = %name, "Webhook prompt failed: {}", e);
+                    (None, Some(e.clone()))
+                }
+            };
+            let outcome = WebhookResult {
+                route: name,
+                received_at,
+                reply,
+                error,
+            };
+            if let Err(e) = app.emit("webhook-result", outcome) {
+                tracing::warn!("Failed to emit webhook result: {}", e);
+            }
+            match result {
+                Ok(reply) => Json(json!({ "reply": reply })).into_response(),
+                Err(e) => error_response(StatusCode::BAD_GATEWAY, e),
+            }
+        }
+    }
+}
+
+fn stop() {
+    if let Some(listener) = LISTENER.lock().unwrap().take() {
+        let _ = listener.shutdown.send(());
+        tracing::info!("Webhook listener stopped");
+    }
+}
+
+async fn start(app: AppHandle, config: WebhookSettings) -> Result<(), String> {
+    let token = tauri::async_runtime::spawn_blocking(load_token)
+        .await
+        .map_err(|e| e.to_string())??;
+    *TOKEN.lock().unwrap() = Some(token);
+
+    let address = bind_address(&config)?;
+    let listener = tokio::net::TcpListener::bind(address)
+        .await
+        .map_err(|e| format!("Failed to listen on {}: {}", address, e))?;
+    let local = listener
+        .local_addr()
+        .map_err(|e| format!("Failed to read the webhook address: {}", e))?;
+    let acceptor = tls::acceptor();
+    let base_url = format!("{}://{}/hooks/", tls::scheme(&acceptor), local);
+    tracing::info!(%base_url, "Webhook listener started");
+
+    let router = Router::new()
+        .route("/hooks/{name}", post(handle))
+        .layer(axum::extract::DefaultBodyLimit::max(MAX_BODY_BYTES))
+        .with_state(Arc::new(app));
+    let (shutdown, stopped) = oneshot::channel();
+    *LISTENER.lock().unwrap() = Some(Listener { base_url, shutdown });
+    tauri::async_runtime::spawn(async move {
+        let served = tls::serve(listener, router, acceptor, async {
+            let _ = stopped.await;
+        })
+        .await;
+        if let Err(e) = served {
+            tracing::error!("Webhook listener failed: {}", e);
+        }
+    });
+    Ok(())
+}
+
+// Stop the listener and start it again with the current settings
+async fn restart(app: &AppHandle) -> WebhookStatus {
+    stop();
+    let config = settings::current().webhooks;
+    let error = if config.enabled {
+        start(app.clone(), config).await.err()
+    } else {
+        None
+    };
+    if let Some(e) = &error {
+        tracing::error!("{}", e);
+    }
+    *LAST_ERROR.lock().unwrap() = error;
+    status()
+}
+
+fn status() -> WebhookStatus {
+    let config = settings::current().webhooks;
+    WebhookStatus {
+        enabled: config.enabled,
+        base_url: LISTENER
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|listener| listener.base_url.clone()),
+        routes: config.routes,
+        error: LAST_ERROR.lock().unwrap().clone(),
+    }
+}
+
+// Start listening if webhooks are enabled; called once from the setup hook
+pub fn init(app: &AppHandle) {
+    if !settings::current().webhooks.enabled {
+        return;
+    }
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        restart(&app).await;
+    });
+}
+
+#[tauri::command]
+pub fn get_webhook_status() -> WebhookStatus {
+    status()
+}
+
+// Turn the listener on or off, or move it to another port or interface
+#[tauri::command]
+pub async fn configure_webhooks(
+    app: AppHandle,
+    enabled: bool,
+    port: Option<u16>,
+    bind: Option<WebhookBind>,
+) -> Result<WebhookStatus, AppError> {
+    let current = settings::current();
+    let exposed = bind.unwrap_or(current.webhooks.bind);
+    if enabled && exposed != WebhookBind::Localhost {
+        capabilities::require(Capability::RemoteAccess)?;
+    }
+    // The webhook token would cross the network in plain text
+    if enabled && exposed == WebhookBind::Lan && !current.local_tls.enabled {
+        return Err(AppError::Validation(
+            "Turn on HTTPS for local connections before opening webhooks to the LAN".to_string(),
+        ));
+    }
+    settings::update(&app, |settings| {
+        settings.webhooks.enabled = enabled;
+        if let Some(port) = port {
+            settings.webhooks.port = port;
+        }
+        if let Some(bind) = bind {
+            settings.webhooks.bind = bind;
+        }
+    })?;
+    let status = restart(&app).await;
+    match &status.error {
+        Some(e) => Err(AppError::Network(e.clone())),
+        None => Ok(status),
+    }
+}
+
+// Add a route, or replace the one with the same name
+#[tauri::command]
+pub fn register_webhook(app: AppHandle, route: WebhookRoute) -> Result<WebhookStatus, AppError> {
+    validate_name(&route.name).map_err(AppError::Validation)?;
+    settings::update(&app, |settings| {
+        let routes = &mut settings.webhooks.routes;
+        match routes
+            .iter_mut()
+            .find(|existing| existing.name == route.name)
+        {
+            Some(existing) => *existing = route,
+            None => routes.push(route),
+        }
+    })?;
+    Ok(status())
+}
+
+#[tauri::command]
+pub fn unregister_webhook(app: AppHandle, name: String) -> Result<WebhookStatus, AppError> {
+    let before = settings::current().webhooks.routes.len();
+    let settings = settings::update(&app, |settings| {
+        settings.webhooks.routes.retain(|route| route.name != name)
+    })?;
+    if settings.webhooks.routes.len() == before {
+        return Err(AppError::NotFound(format!("Unknown webhook: {}", name)));
+    }
+    Ok(status())
+}
+
+// The token callers send as `Authorization: Bearer <token>` or `X-Webhook-Token`
+#[tauri::command]
+pub async fn get_webhook_token() -> Result<String, AppError> {
+    capabilities::require(Capability::ReadSecrets)?;
+    tauri::async_runtime::spawn_blocking(load_token)
+        .await?
+        .map_err(AppError::Keychain)
+}
+
+// Replace the token, locking out every caller still using the old one
+#[tauri::command]
+pub async fn rotate_webhook_token() -> Result<String, AppError> {
+    capabilities::require(Capability::ReadSecrets)?;
+    let token = random_token();
+    secure_store::write(TOKEN_ENTRY, &token)
+        .await
+        .map_err(AppError::Keychain)?;
+    *TOKEN.lock().unwrap() = Some(token.clone());
+    tracing::info!("Webhook token rotated");
+    Ok(token)
+}
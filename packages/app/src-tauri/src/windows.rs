@@ -0,0 +1,190 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use tauri::{
+    AppHandle, Manager, PhysicalPosition, PhysicalSize, WebviewUrl, WebviewWindow,
+    WebviewWindowBuilder, WindowEvent,
+};
+
+use crate::error::AppError;
+
+const STATE_FILE: &str = "window-state.json";
+const MAIN_WINDOW: &str = "main";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WindowKind {
+    Settings,
+    Logs,
+    CharacterEditor,
+}
+
+impl WindowKind {
+    // Also the window label; each must be listed in capabilities/default.json
+    fn label(self) -> &'static str {
+        match self {
+            WindowKind::Settings => "settings",
+            WindowKind::Logs => "logs",
+            WindowKind::CharacterEditor => "character-editor",
+        }
+    }
+
+    fn title(self) -> &'static str {
+        match self {
+            WindowKind::Settings => "Settings",
+            WindowKind::Logs => "Logs",
+            WindowKind::CharacterEditor => "Character Editor",
+        }
+    }
+
+    // Logical size used until the user resizes the window
+    fn default_size(self) -> (f64, f64) {
+        match self {
+            WindowKind::Settings => (720.0, 560.0),
+            WindowKind::Logs => (900.0, 600.0),
+            WindowKind::CharacterEditor => (960.0, 720.0),
+        }
+    }
+}
+
+// Outer position and inner size in physical pixels, as last seen while not maximized
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct WindowGeometry {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    pub maximized: bool,
+}
+
+// Geometry by window label
+static STATE: Lazy<Mutex<HashMap<String, WindowGeometry>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+fn state_path(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_config_dir()
+        .map(|dir| dir.join(STATE_FILE))
+        .map_err(|e| format!("Failed to resolve app config dir: {}", e))
+}
+
+fn load(app: &AppHandle) -> Result<HashMap<String, WindowGeometry>, String> {
+    let path = state_path(app)?;
+    match fs::read_to_string(&path) {
+        Ok(contents) => serde_json::from_str(&contents)
+            .map_err(|e| format!("Ignoring invalid window state {}: {}", path.display(), e)),
+        Err(_) => Ok(HashMap::new()),
+    }
+}
+
+// Write the geometry of every window seen so far
+pub fn save(app: &AppHandle) -> Result<(), String> {
+    let path = state_path(app)?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    }
+    let contents =
+        serde_json::to_string_pretty(&*STATE.lock().unwrap()).map_err(|e| e.to_string())?;
+    fs::write(&path, contents).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+// Record the window's current geometry. Maximized and minimized windows keep the
+// last normal bounds so unmaximizing after a relaunch still lands somewhere sensible.
+fn record(window: &WebviewWindow) {
+    if window.is_minimized().unwrap_or(true) {
+        return;
+    }
+    let maximized = window.is_maximized().unwrap_or(false);
+    let mut state = STATE.lock().unwrap();
+    let geometry = state.entry(window.label().to_string()).or_default();
+    geometry.maximized = maximized;
+    if maximized {
+        return;
+    }
+    if let (Ok(position), Ok(size)) = (window.outer_position(), window.inner_size()) {
+        geometry.x = position.x;
+        geometry.y = position.y;
+        geometry.width = size.width;
+        geometry.height = size.height;
+    }
+}
+
+// A saved position is only used if it is still on a connected monitor
+fn on_screen(window: &WebviewWindow, geometry: &WindowGeometry) -> bool {
+    window.available_monitors().is_ok_and(|monitors| {
+        monitors.iter().any(|monitor| {
+            let (origin, size) = (monitor.position(), monitor.size());
+            (origin.x..origin.x + size.width as i32).contains(&geometry.x)
+                && (origin.y..origin.y + size.height as i32).contains(&geometry.y)
+        })
+    })
+}
+
+fn restore(window: &WebviewWindow) {
+    let Some(geometry) = STATE.lock().unwrap().get(window.label()).copied() else {
+        return;
+    };
+    if geometry.width > 0 && geometry.height > 0 {
+        let _ = window.set_size(PhysicalSize::new(geometry.width, geometry.height));
+    }
+    if on_screen(window, &geometry) {
+        let _ = window.set_position(PhysicalPosition::new(geometry.x, geometry.y));
+    }
+    if geometry.maximized {
+        let _ = window.maximize();
+    }
+}
+
+// Restore the window's saved geometry and keep it up to date from now on
+fn track(app: &AppHandle, window: &WebviewWindow) {
+    restore(window);
+    let (app, tracked) = (app.clone(), window.clone());
+    window.on_window_event(move |event| match event {
+        WindowEvent::Moved(_) | WindowEvent::Resized(_) => record(&tracked),
+        WindowEvent::CloseRequested { .. } => {
+            record(&tracked);
+            if let Err(e) = save(&app) {
+                tracing::warn!("{}", e);
+            }
+        }
+        _ => {}
+    });
+}
+
+// Load the saved geometry and apply it to the main window
+pub fn init(app: &AppHandle) {
+    match load(app) {
+        Ok(state) => *STATE.lock().unwrap() = state,
+        Err(e) => tracing::warn!("{}", e),
+    }
+    if let Some(window) = app.get_webview_window(MAIN_WINDOW) {
+        track(app, &window);
+    }
+}
+
+// Open a secondary window, or focus it if it is already open
+#[tauri::command]
+pub fn open_window(app: AppHandle, kind: WindowKind) -> Result<(), AppError> {
+    if let Some(window) = app.get_webview_window(kind.label()) {
+        let _ = window.unminimize();
+        return Ok(window.set_focus()?);
+    }
+
+    let (width, height) = kind.default_size();
+    let url = format!("index.html#/{}", kind.label());
+    let window = WebviewWindowBuilder::new(&app, kind.label(), WebviewUrl::App(url.into()))
+        .title(kind.title())
+        .inner_size(width, height)
+        .min_inner_size(480.0, 360.0)
+        .visible(false)
+        .build()
+        .map_err(|e| format!("Failed to open the {} window: {}", kind.title(), e))?;
+    track(&app, &window);
+    window.show()?;
+    Ok(window.set_focus()?)
+}
@@ -0,0 +1,106 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+
+use crate::capabilities::{self, Capability};
+use crate::error::AppError;
+use crate::{cli, settings};
+
+// Directory the elizaOS server runs in; it reads `.env` and `characters/` from here.
+// Falls back to the app's own working directory until the user picks one.
+pub fn dir() -> Result<PathBuf, String> {
+    match settings::current().workspace_dir {
+        Some(dir) => Ok(dir),
+        None => std::env::current_dir()
+            .map_err(|e| format!("Failed to resolve working directory: {}", e)),
+    }
+}
+
+pub(crate) fn is_empty_dir(path: &Path) -> bool {
+    fs::read_dir(path).is_ok_and(|mut entries| entries.next().is_none())
+}
+
+pub(crate) fn set(app: &AppHandle, path: &Path) -> Result<PathBuf, String> {
+    let path = path
+        .canonicalize()
+        .map_err(|e| format!("Failed to resolve {}: {}", path.display(), e))?;
+    if !path.is_dir() {
+        return Err(format!("{} is not a directory", path.display()));
+    }
+    settings::update(app, |settings| settings.workspace_dir = Some(path.clone()))?;
+    if let Err(e) = app.emit("workspace-changed", &path) {
+        tracing::warn!("Failed to emit workspace change: {}", e);
+    }
+    Ok(path)
+}
+
+// Run `elizaos create` in the parent so the project lands exactly at `path`
+fn scaffold(app: &AppHandle, path: &Path) -> Result<(), String> {
+    let (Some(parent), Some(name)) = (path.parent(), path.file_name()) else {
+        return Err(format!("Cannot create a project at {}", path.display()));
+    };
+    let Some(cli) = cli::resolve(app) else {
+        cli::report_missing(app);
+        return Err("The elizaos CLI is required to create a project".to_string());
+    };
+    fs::create_dir_all(parent)
+        .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+
+    tracing::info!(path = %path.display(), "Creating elizaOS project");
+    let output = Command::new(&cli)
+        .arg("create")
+        .arg(name)
+        .arg("--yes")
+        .current_dir(parent)
+        .env("PATH", cli::path::spawn_path(app, &cli))
+        .output()
+        .map_err(|e| format!("Failed to run {}: {}", cli.display(), e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "elizaos create failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Workspace {
+    pub path: PathBuf,
+    // A new project was scaffolded rather than an existing directory adopted
+    pub created: bool,
+}
+
+#[tauri::command]
+pub fn get_workspace_dir() -> Result<PathBuf, AppError> {
+    dir().map_err(AppError::Io)
+}
+
+// Use an existing directory as the workspace; takes effect the next time the server starts.
+// The server runs the workspace's `package.json` and plugins, so this needs RunPrograms.
+#[tauri::command]
+pub fn set_workspace_dir(app: AppHandle, path: PathBuf) -> Result<PathBuf, AppError> {
+    capabilities::require(Capability::RunPrograms)?;
+    set(&app, &path).map_err(AppError::Validation)
+}
+
+// Make `path` the workspace, scaffolding a new elizaOS project there if it is missing or empty
+#[tauri::command]
+pub async fn create_workspace(app: AppHandle, path: PathBuf) -> Result<Workspace, AppError> {
+    capabilities::require(Capability::RunPrograms)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let created = !path.exists() || is_empty_dir(&path);
+        if created {
+            scaffold(&app, &path)?;
+        }
+        Ok(Workspace {
+            path: set(&app, &path)?,
+            created,
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}